@@ -0,0 +1,272 @@
+//! Benchmarks for the core database operations.
+//!
+//! Run with `cargo bench`; pass a group name to run one (e.g. `cargo bench --
+//! tree_read`). Setup (building the task graphs) is not timed.
+//!
+//! - `claim_contention`: agents racing to claim the same ready tasks
+//! - `ready_list`: the ready listing over 10k tasks, half of them blocked
+//! - `tree_read`: reading a depth-10 subtree and a deep ancestor chain
+//! - `import`: a fresh import of a ~50k-row snapshot
+//! - `fts_search`: full-text search over 10k tasks
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use serde_json::json;
+use std::hint::black_box;
+use std::thread;
+use task_graph_mcp::config::{
+    DependenciesConfig, IdScheme, IdsConfig, PhasesConfig, StatesConfig, TagsConfig,
+};
+use task_graph_mcp::db::Database;
+use task_graph_mcp::db::export::ExportOptions;
+use task_graph_mcp::db::import::ImportOptions;
+use task_graph_mcp::db::tasks::CreateTreeOptions;
+use task_graph_mcp::export::Snapshot;
+use task_graph_mcp::types::TaskTreeInput;
+use tempfile::TempDir;
+
+const WORDS: &[&str] = &[
+    "deploy", "parser", "cache", "index", "review", "migrate", "schema", "worker", "render",
+    "upload", "search", "billing", "report", "config", "refactor", "audit",
+];
+
+/// Create `input` as a task tree and return all created IDs in creation order
+/// (pre-order). Sequential IDs keep large trees free of petname collisions.
+fn create_tree(db: &Database, input: serde_json::Value) -> Vec<String> {
+    let input: TaskTreeInput = serde_json::from_value(input).unwrap();
+    let ids_config = IdsConfig {
+        task_id_scheme: IdScheme::Sequential,
+        ..IdsConfig::default()
+    };
+    let (_, ids, _, _) = db
+        .create_task_tree(CreateTreeOptions {
+            input,
+            parent_id: None,
+            child_type: None,
+            sibling_type: None,
+            states_config: &StatesConfig::default(),
+            phases_config: &PhasesConfig::default(),
+            tags_config: &TagsConfig::default(),
+            ids_config: &ids_config,
+        })
+        .unwrap();
+    ids
+}
+
+/// A root with `groups` groups of `leaves` tagged leaves whose titles and
+/// descriptions are drawn from `WORDS`.
+fn wide_tree(groups: usize, leaves: usize) -> serde_json::Value {
+    let groups: Vec<_> = (0..groups)
+        .map(|g| {
+            let leaves: Vec<_> = (0..leaves)
+                .map(|l| {
+                    let n = g * leaves + l;
+                    json!({
+                        "title": format!("{} {} {}", WORDS[n % 16], WORDS[(n / 16) % 16], n),
+                        "description": format!("Task {} of the {} backlog", n, WORDS[(n / 7) % 16]),
+                        "tags": ["bench", WORDS[n % 16]]
+                    })
+                })
+                .collect();
+            json!({ "title": format!("Group {}", g), "children": leaves })
+        })
+        .collect();
+    json!({ "title": "Root", "children": groups })
+}
+
+/// A complete binary tree with `levels` levels (10 levels = 1023 tasks).
+fn binary_tree(levels: u32) -> serde_json::Value {
+    fn node(level: u32, levels: u32) -> serde_json::Value {
+        let children: Vec<_> = if level + 1 < levels {
+            vec![node(level + 1, levels), node(level + 1, levels)]
+        } else {
+            vec![]
+        };
+        json!({ "title": format!("Level {}", level), "children": children })
+    }
+    node(0, levels)
+}
+
+/// 10k tasks in a file database; every other leaf is blocked by its neighbour.
+fn ready_list_db(dir: &TempDir) -> Database {
+    let db = Database::open(dir.path().join("ready.db")).unwrap();
+    let ids = create_tree(&db, wide_tree(100, 100));
+    let deps = DependenciesConfig::default();
+    // IDs are in creation order: the root, then each group followed by its leaves
+    for group in ids[1..].chunks(101) {
+        for pair in group[1..].chunks(2) {
+            if let [blocker, blocked] = pair {
+                db.add_dependency(blocker, blocked, "blocks", &deps)
+                    .unwrap();
+            }
+        }
+    }
+    db
+}
+
+fn claim_contention(c: &mut Criterion) {
+    const AGENTS: usize = 8;
+    const TASKS: usize = 400;
+
+    let mut group = c.benchmark_group("claim_contention");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(TASKS as u64));
+    group.bench_function("8_agents_400_tasks", |b| {
+        b.iter_custom(|iters| {
+            let mut total = std::time::Duration::ZERO;
+            for _ in 0..iters {
+                let dir = TempDir::new().unwrap();
+                let path = dir.path().join("claim.db");
+                let db = Database::open(&path).unwrap();
+                create_tree(&db, wide_tree(4, TASKS / 4));
+
+                // One database handle per agent, as with one server per agent
+                let agents: Vec<(Database, String)> = (0..AGENTS)
+                    .map(|i| {
+                        let db = Database::open(&path).unwrap();
+                        let worker = db
+                            .register_worker(
+                                Some(format!("agent-{}", i)),
+                                vec![],
+                                false,
+                                &IdsConfig::default(),
+                                None,
+                                vec![],
+                            )
+                            .unwrap();
+                        (db, worker.id)
+                    })
+                    .collect();
+
+                let start = std::time::Instant::now();
+                thread::scope(|s| {
+                    for (i, (db, worker_id)) in agents.iter().enumerate() {
+                        s.spawn(move || claim_until_empty(db, worker_id, i));
+                    }
+                });
+                total += start.elapsed();
+            }
+            total
+        })
+    });
+    group.finish();
+}
+
+/// Claim ready leaves until none are left, retrying when another agent wins.
+fn claim_until_empty(db: &Database, worker_id: &str, agent: usize) {
+    let states = StatesConfig::default();
+    let deps = DependenciesConfig::default();
+    loop {
+        let ready = db
+            .get_ready_tasks(None, &states, &deps, None, None, None)
+            .unwrap();
+        let leaves: Vec<_> = ready
+            .iter()
+            .filter(|t| t.tags.iter().any(|tag| tag == "bench"))
+            .collect();
+        if leaves.is_empty() {
+            return;
+        }
+        // Agents start at different offsets so they do not all race for one task
+        let task = leaves[agent % leaves.len()];
+        let _ = db.claim_task(&task.id, worker_id, &states);
+    }
+}
+
+fn ready_list(c: &mut Criterion) {
+    let dir = TempDir::new().unwrap();
+    let db = ready_list_db(&dir);
+    let states = StatesConfig::default();
+    let deps = DependenciesConfig::default();
+
+    let mut group = c.benchmark_group("ready_list");
+    group.bench_function("10k_tasks", |b| {
+        b.iter(|| {
+            black_box(
+                db.get_ready_tasks(None, &states, &deps, None, None, None)
+                    .unwrap(),
+            )
+        })
+    });
+    group.finish();
+}
+
+fn tree_read(c: &mut Criterion) {
+    let db = Database::open_in_memory().unwrap();
+    let ids = create_tree(&db, binary_tree(10));
+    let root = ids[0].clone();
+    let leaf = ids.last().unwrap().clone();
+
+    let mut group = c.benchmark_group("tree_read");
+    group.bench_function("depth_10_tree", |b| {
+        b.iter(|| black_box(db.get_task_tree(&root).unwrap()))
+    });
+    group.bench_function("depth_10_descendants", |b| {
+        b.iter(|| black_box(db.get_descendants(&root, -1).unwrap()))
+    });
+    group.bench_function("depth_10_ancestors", |b| {
+        b.iter(|| black_box(db.get_ancestors(&leaf, -1).unwrap()))
+    });
+    group.finish();
+}
+
+fn import(c: &mut Criterion) {
+    // 10k tasks with two tags each, their containment edges, and history
+    let source = Database::open_in_memory().unwrap();
+    create_tree(&source, wide_tree(100, 100));
+    let tables = source.export_tables(&ExportOptions::default()).unwrap();
+    let snapshot = Snapshot::from_tables(tables).unwrap();
+    let rows: usize = snapshot.tables.values().map(Vec::len).sum();
+
+    let mut group = c.benchmark_group("import");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(rows as u64));
+    group.bench_function(format!("fresh_{}_rows", rows), |b| {
+        b.iter_batched(
+            || {
+                let dir = TempDir::new().unwrap();
+                let db = Database::open(dir.path().join("import.db")).unwrap();
+                (dir, db)
+            },
+            |(_dir, db)| {
+                db.import_snapshot(&snapshot, &ImportOptions::fresh())
+                    .unwrap()
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+fn fts_search(c: &mut Criterion) {
+    let db = Database::open_in_memory().unwrap();
+    create_tree(&db, wide_tree(100, 100));
+
+    let mut group = c.benchmark_group("fts_search");
+    group.bench_function("single_term_10k", |b| {
+        b.iter(|| {
+            black_box(
+                db.search_tasks("deploy", Some(20), 0, false, None, None)
+                    .unwrap(),
+            )
+        })
+    });
+    group.bench_function("phrase_10k", |b| {
+        b.iter(|| {
+            black_box(
+                db.search_tasks("\"cache index\"", Some(20), 0, false, None, None)
+                    .unwrap(),
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    claim_contention,
+    ready_list,
+    tree_read,
+    import,
+    fts_search
+);
+criterion_main!(benches);
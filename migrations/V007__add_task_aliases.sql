@@ -0,0 +1,12 @@
+-- Task ID aliases
+-- When a task is renamed, its old ID is kept here so external references and
+-- in-flight agent context keep resolving to the task under its new ID.
+-- Aliases are only consulted when no task exists with the requested ID.
+
+CREATE TABLE task_aliases (
+    alias TEXT PRIMARY KEY,
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_task_aliases_task ON task_aliases(task_id);
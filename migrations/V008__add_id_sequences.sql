@@ -0,0 +1,8 @@
+-- Counters for sequential task IDs (ids.task_id_scheme: sequential)
+-- One row per ID prefix; last_value is the most recently issued number.
+-- Rows are seeded lazily from existing IDs the first time a prefix is used.
+
+CREATE TABLE id_sequences (
+    prefix TEXT PRIMARY KEY,
+    last_value INTEGER NOT NULL
+);
@@ -0,0 +1,12 @@
+-- User-defined custom fields on tasks
+-- Field definitions live in config (custom_fields.definitions); values are
+-- stored here JSON-encoded so strings, numbers, and booleans round-trip.
+
+CREATE TABLE task_fields (
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,                          -- JSON-encoded scalar
+    PRIMARY KEY (task_id, name)
+);
+
+CREATE INDEX idx_task_fields_name_value ON task_fields(name, value);
@@ -0,0 +1,5 @@
+-- Lag on dependency edges
+-- A start-blocking dependency with lag_ms stays unsatisfied until lag_ms
+-- milliseconds after the blocker's completed_at (NULL = no lag).
+
+ALTER TABLE dependencies ADD COLUMN lag_ms INTEGER;
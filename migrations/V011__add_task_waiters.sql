@@ -0,0 +1,13 @@
+-- Agents waiting for tasks to become ready
+-- Rows are added by wait_for; notified_at is set when the task's last start
+-- blocker completes, and the row is removed once the agent has been told.
+
+CREATE TABLE task_waiters (
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    worker_id TEXT NOT NULL REFERENCES workers(id) ON DELETE CASCADE,
+    registered_at INTEGER NOT NULL,
+    notified_at INTEGER,                          -- NULL while still waiting
+    PRIMARY KEY (task_id, worker_id)
+);
+
+CREATE INDEX idx_task_waiters_worker ON task_waiters(worker_id, notified_at);
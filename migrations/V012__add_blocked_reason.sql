@@ -0,0 +1,8 @@
+-- Structured blocked-reason tracking
+-- blocked_reason holds one of the known reason codes (see BLOCKED_REASONS);
+-- blocked_note is free text explaining what the task is waiting on.
+
+ALTER TABLE tasks ADD COLUMN blocked_reason TEXT;
+ALTER TABLE tasks ADD COLUMN blocked_note TEXT;
+
+CREATE INDEX idx_tasks_blocked_reason ON tasks(blocked_reason) WHERE blocked_reason IS NOT NULL;
@@ -0,0 +1,12 @@
+-- Agents waiting for conflicting file marks to be released
+-- Rows are added by mark_file(wait=true) and removed by mark_updates once no
+-- other agent holds an overlapping mark on the path.
+
+CREATE TABLE mark_waiters (
+    file_path TEXT NOT NULL,
+    worker_id TEXT NOT NULL REFERENCES workers(id) ON DELETE CASCADE,
+    registered_at INTEGER NOT NULL,
+    PRIMARY KEY (file_path, worker_id)
+);
+
+CREATE INDEX idx_mark_waiters_worker ON mark_waiters(worker_id);
@@ -0,0 +1,11 @@
+-- Files modified on disk without an advisory mark
+-- Rows are recorded by the optional mark watcher (server.mark_watch) and are
+-- informational only; nothing is blocked.
+
+CREATE TABLE mark_violations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    file_path TEXT NOT NULL,
+    detected_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_mark_violations_detected ON mark_violations(detected_at);
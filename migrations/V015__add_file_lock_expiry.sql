@@ -0,0 +1,7 @@
+-- Lease expiry for file marks
+-- mark_file(ttl_ms=...) sets expires_at; the reaper releases marks once it passes.
+-- NULL means the mark never expires.
+
+ALTER TABLE file_locks ADD COLUMN expires_at INTEGER;
+
+CREATE INDEX idx_file_locks_expires ON file_locks(expires_at) WHERE expires_at IS NOT NULL;
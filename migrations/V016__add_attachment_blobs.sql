@@ -0,0 +1,10 @@
+-- Content-addressed binary attachments
+-- Binary attachments (base64 content or copied files) are stored once per SHA-256
+-- digest under media_dir/blobs/; rows keep only metadata plus the digest so blobs
+-- can be deduplicated and garbage-collected when no attachment references them.
+-- NULL for inline and referenced-file attachments.
+
+ALTER TABLE attachments ADD COLUMN content_hash TEXT;
+ALTER TABLE attachments ADD COLUMN size_bytes INTEGER;
+
+CREATE INDEX idx_attachments_content_hash ON attachments(content_hash) WHERE content_hash IS NOT NULL;
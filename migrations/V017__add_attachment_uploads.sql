@@ -0,0 +1,19 @@
+-- Chunked attachment uploads
+-- attach_begin creates a row, attach_chunk appends base64-decoded chunks in order to
+-- media_dir/uploads/<id>.part, and attach_commit verifies size/digest, moves the data
+-- into the content-addressed blob store, and deletes the row.
+
+CREATE TABLE attachment_uploads (
+    id TEXT PRIMARY KEY,
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    attachment_type TEXT NOT NULL,
+    name TEXT NOT NULL DEFAULT '',
+    mime_type TEXT NOT NULL,
+    mode TEXT NOT NULL DEFAULT 'append',
+    expected_size INTEGER,                        -- total bytes, if declared up front
+    expected_hash TEXT,                           -- SHA-256 hex, if declared up front
+    received_bytes INTEGER NOT NULL DEFAULT 0,
+    next_chunk INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
@@ -0,0 +1,8 @@
+-- Attachment versioning
+-- attach(mode="version") adds a new version of the attachment with the same
+-- (task_id, attachment_type, name) instead of an unrelated entry. Each version is
+-- still its own row with its own sequence; existing rows are version 1.
+
+ALTER TABLE attachments ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+CREATE INDEX idx_attachments_task_type_name ON attachments(task_id, attachment_type, name);
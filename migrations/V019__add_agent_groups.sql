@@ -0,0 +1,16 @@
+-- Agent groups (teams)
+-- Workers join groups by config or register_group; a task with needed_group can
+-- only be claimed or assigned by a member of that group.
+
+CREATE TABLE worker_groups (
+    worker_id TEXT NOT NULL REFERENCES workers(id) ON DELETE CASCADE,
+    group_name TEXT NOT NULL,
+    joined_at INTEGER NOT NULL,
+    PRIMARY KEY (worker_id, group_name)
+);
+
+CREATE INDEX idx_worker_groups_group ON worker_groups(group_name);
+
+ALTER TABLE tasks ADD COLUMN needed_group TEXT;
+
+CREATE INDEX idx_tasks_needed_group ON tasks(needed_group) WHERE needed_group IS NOT NULL;
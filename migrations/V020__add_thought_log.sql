@@ -0,0 +1,14 @@
+-- Recent thoughts per worker
+-- set_thought appends a row per updated task (task_id NULL when the worker held
+-- no matching claims); only the latest rows per worker are kept. Rows outlive the
+-- worker so the dashboard can still show a disconnected agent's thought stream.
+
+CREATE TABLE thought_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    worker_id TEXT NOT NULL,
+    task_id TEXT REFERENCES tasks(id) ON DELETE CASCADE,
+    thought TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_thought_log_worker ON thought_log(worker_id, id);
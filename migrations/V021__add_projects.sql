@@ -0,0 +1,10 @@
+-- Multi-project namespaces
+-- Every task, worker, and file mark belongs to a project so one server can host
+-- several isolated task graphs. Existing rows join the 'default' project.
+
+ALTER TABLE tasks ADD COLUMN project TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE workers ADD COLUMN project TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE file_locks ADD COLUMN project TEXT NOT NULL DEFAULT 'default';
+
+CREATE INDEX idx_tasks_project ON tasks(project);
+CREATE INDEX idx_file_locks_project ON file_locks(project);
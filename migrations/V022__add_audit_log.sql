@@ -0,0 +1,19 @@
+-- Audit log of mutating tool calls
+-- One row per call of a tool that can change state, successful or not. Rows are
+-- never updated and outlive the tasks and workers they mention, so task_ids is a
+-- plain JSON array rather than a foreign key.
+
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    worker_id TEXT,
+    tool TEXT NOT NULL,
+    args_hash TEXT NOT NULL,
+    task_ids TEXT NOT NULL DEFAULT '[]',
+    outcome TEXT NOT NULL,
+    error TEXT,
+    timestamp INTEGER NOT NULL
+);
+
+CREATE INDEX idx_audit_log_timestamp ON audit_log(timestamp);
+CREATE INDEX idx_audit_log_worker ON audit_log(worker_id, timestamp);
+CREATE INDEX idx_audit_log_tool ON audit_log(tool, timestamp);
@@ -0,0 +1,15 @@
+-- Field-level change history
+-- update records one row per task field it changed, with the values before and
+-- after as JSON (null when unset). Custom fields are recorded as `fields.<name>`.
+
+CREATE TABLE task_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    worker_id TEXT,
+    field TEXT NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    timestamp INTEGER NOT NULL
+);
+
+CREATE INDEX idx_task_changes_task ON task_changes(task_id, id);
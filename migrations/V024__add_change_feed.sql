@@ -0,0 +1,129 @@
+-- Change feed
+-- Triggers append one row per mutation of project data, whatever made it (tool
+-- calls, the CLI, imports, background reclaim). The row ID is the feed cursor:
+-- strictly increasing and persistent, so clients resume where they left off.
+-- Live thoughts and other runtime columns are not tracked.
+
+CREATE TABLE change_feed (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity TEXT NOT NULL,
+    action TEXT NOT NULL,
+    task_id TEXT,
+    related_id TEXT,
+    detail TEXT,
+    timestamp INTEGER NOT NULL
+);
+
+CREATE INDEX idx_change_feed_task ON change_feed(task_id, id);
+
+-- Tasks
+
+CREATE TRIGGER change_feed_task_insert AFTER INSERT ON tasks BEGIN
+    INSERT INTO change_feed (entity, action, task_id, timestamp)
+    VALUES ('task', 'created', NEW.id,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+CREATE TRIGGER change_feed_task_update AFTER UPDATE ON tasks
+WHEN OLD.id IS NOT NEW.id
+  OR OLD.title IS NOT NEW.title
+  OR OLD.description IS NOT NEW.description
+  OR OLD.status IS NOT NEW.status
+  OR OLD.phase IS NOT NEW.phase
+  OR OLD.priority IS NOT NEW.priority
+  OR OLD.worker_id IS NOT NEW.worker_id
+  OR OLD.tags IS NOT NEW.tags
+  OR OLD.needed_tags IS NOT NEW.needed_tags
+  OR OLD.wanted_tags IS NOT NEW.wanted_tags
+  OR OLD.needed_group IS NOT NEW.needed_group
+  OR OLD.points IS NOT NEW.points
+  OR OLD.time_estimate_ms IS NOT NEW.time_estimate_ms
+  OR OLD.blocked_reason IS NOT NEW.blocked_reason
+  OR OLD.blocked_note IS NOT NEW.blocked_note
+  OR OLD.cost_usd IS NOT NEW.cost_usd
+  OR OLD.metric_0 IS NOT NEW.metric_0 OR OLD.metric_1 IS NOT NEW.metric_1
+  OR OLD.metric_2 IS NOT NEW.metric_2 OR OLD.metric_3 IS NOT NEW.metric_3
+  OR OLD.metric_4 IS NOT NEW.metric_4 OR OLD.metric_5 IS NOT NEW.metric_5
+  OR OLD.metric_6 IS NOT NEW.metric_6 OR OLD.metric_7 IS NOT NEW.metric_7
+  OR OLD.project IS NOT NEW.project
+  OR OLD.deleted_at IS NOT NEW.deleted_at
+BEGIN
+    INSERT INTO change_feed (entity, action, task_id, related_id, timestamp)
+    VALUES ('task',
+            CASE
+                WHEN OLD.id IS NOT NEW.id THEN 'renamed'
+                WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'deleted'
+                WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restored'
+                ELSE 'updated'
+            END,
+            NEW.id,
+            CASE WHEN OLD.id IS NOT NEW.id THEN OLD.id END,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+CREATE TRIGGER change_feed_task_delete AFTER DELETE ON tasks BEGIN
+    INSERT INTO change_feed (entity, action, task_id, timestamp)
+    VALUES ('task', 'purged', OLD.id,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+-- Custom fields (reported as task updates naming the field)
+
+CREATE TRIGGER change_feed_field_insert AFTER INSERT ON task_fields BEGIN
+    INSERT INTO change_feed (entity, action, task_id, detail, timestamp)
+    VALUES ('task', 'updated', NEW.task_id, 'fields.' || NEW.name,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+CREATE TRIGGER change_feed_field_update AFTER UPDATE ON task_fields
+WHEN OLD.value IS NOT NEW.value BEGIN
+    INSERT INTO change_feed (entity, action, task_id, detail, timestamp)
+    VALUES ('task', 'updated', NEW.task_id, 'fields.' || NEW.name,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+CREATE TRIGGER change_feed_field_delete AFTER DELETE ON task_fields BEGIN
+    INSERT INTO change_feed (entity, action, task_id, detail, timestamp)
+    VALUES ('task', 'updated', OLD.task_id, 'fields.' || OLD.name,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+-- Dependencies (task_id is the dependent task, related_id the other end)
+
+CREATE TRIGGER change_feed_dependency_insert AFTER INSERT ON dependencies BEGIN
+    INSERT INTO change_feed (entity, action, task_id, related_id, detail, timestamp)
+    VALUES ('dependency', 'added', NEW.to_task_id, NEW.from_task_id, NEW.dep_type,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+CREATE TRIGGER change_feed_dependency_update AFTER UPDATE ON dependencies BEGIN
+    INSERT INTO change_feed (entity, action, task_id, related_id, detail, timestamp)
+    VALUES ('dependency', 'updated', NEW.to_task_id, NEW.from_task_id, NEW.dep_type,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+CREATE TRIGGER change_feed_dependency_delete AFTER DELETE ON dependencies BEGIN
+    INSERT INTO change_feed (entity, action, task_id, related_id, detail, timestamp)
+    VALUES ('dependency', 'removed', OLD.to_task_id, OLD.from_task_id, OLD.dep_type,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+-- Attachments (detail is the attachment name)
+
+CREATE TRIGGER change_feed_attachment_insert AFTER INSERT ON attachments BEGIN
+    INSERT INTO change_feed (entity, action, task_id, detail, timestamp)
+    VALUES ('attachment', 'added', NEW.task_id, NEW.name,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+CREATE TRIGGER change_feed_attachment_update AFTER UPDATE ON attachments BEGIN
+    INSERT INTO change_feed (entity, action, task_id, detail, timestamp)
+    VALUES ('attachment', 'updated', NEW.task_id, NEW.name,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+CREATE TRIGGER change_feed_attachment_delete AFTER DELETE ON attachments BEGIN
+    INSERT INTO change_feed (entity, action, task_id, detail, timestamp)
+    VALUES ('attachment', 'removed', OLD.task_id, OLD.name,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
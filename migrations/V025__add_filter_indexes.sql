@@ -0,0 +1,18 @@
+-- Indexes for the filters tools actually use
+-- Each index matches a hot query shape; `task-graph doctor` checks them with
+-- EXPLAIN QUERY PLAN and flags full table scans.
+
+-- Ready listing: unclaimed, live tasks of one status in priority order
+CREATE INDEX idx_tasks_ready ON tasks(status, CAST(priority AS INTEGER) DESC, created_at DESC)
+    WHERE worker_id IS NULL AND deleted_at IS NULL;
+
+-- Claimed views: a worker's claims in claim order (supersedes idx_tasks_worker)
+DROP INDEX IF EXISTS idx_tasks_worker;
+CREATE INDEX idx_tasks_worker_claimed ON tasks(worker_id, claimed_at, id);
+
+-- Incremental export and recent-activity views
+CREATE INDEX idx_tasks_updated ON tasks(updated_at);
+
+-- Blocker lookups: incoming edges of a task by type (supersedes idx_deps_to)
+DROP INDEX IF EXISTS idx_deps_to;
+CREATE INDEX idx_deps_to_type ON dependencies(to_task_id, dep_type);
@@ -0,0 +1,73 @@
+-- Transitive closure of the `contains` hierarchy
+-- One row per (ancestor, descendant) pair at any depth (a task is not its own
+-- ancestor), so subtree and ancestor reads are a single indexed lookup instead
+-- of a recursive walk. Triggers on `dependencies` keep it current for every
+-- writer: link/unlink, relink, rename, import, and cascading deletes.
+
+CREATE TABLE task_closure (
+    ancestor_id TEXT NOT NULL,
+    descendant_id TEXT NOT NULL,
+    depth INTEGER NOT NULL,         -- 1 = direct child
+    PRIMARY KEY (ancestor_id, descendant_id)
+) WITHOUT ROWID;
+
+CREATE INDEX idx_task_closure_descendant ON task_closure(descendant_id, depth);
+
+-- Populate from existing edges
+INSERT OR IGNORE INTO task_closure (ancestor_id, descendant_id, depth)
+WITH RECURSIVE pairs(ancestor_id, descendant_id, depth) AS (
+    SELECT from_task_id, to_task_id, 1 FROM dependencies WHERE dep_type = 'contains'
+    UNION
+    SELECT p.ancestor_id, d.to_task_id, p.depth + 1
+    FROM pairs p
+    INNER JOIN dependencies d ON d.from_task_id = p.descendant_id
+    WHERE d.dep_type = 'contains'
+)
+SELECT ancestor_id, descendant_id, MIN(depth) FROM pairs GROUP BY ancestor_id, descendant_id;
+
+-- Adding P contains C connects P and its ancestors to C and its descendants
+CREATE TRIGGER task_closure_insert AFTER INSERT ON dependencies
+WHEN NEW.dep_type = 'contains' BEGIN
+    INSERT OR IGNORE INTO task_closure (ancestor_id, descendant_id, depth)
+    SELECT a.id, d.id, a.depth + d.depth + 1
+    FROM (SELECT NEW.from_task_id AS id, 0 AS depth
+          UNION ALL
+          SELECT ancestor_id, depth FROM task_closure WHERE descendant_id = NEW.from_task_id) a,
+         (SELECT NEW.to_task_id AS id, 0 AS depth
+          UNION ALL
+          SELECT descendant_id, depth FROM task_closure WHERE ancestor_id = NEW.to_task_id) d;
+END;
+
+-- Removing P contains C disconnects the same pairs (each task has one parent)
+CREATE TRIGGER task_closure_delete AFTER DELETE ON dependencies
+WHEN OLD.dep_type = 'contains' BEGIN
+    DELETE FROM task_closure
+    WHERE ancestor_id IN (SELECT OLD.from_task_id
+                          UNION
+                          SELECT ancestor_id FROM task_closure WHERE descendant_id = OLD.from_task_id)
+      AND descendant_id IN (SELECT OLD.to_task_id
+                            UNION
+                            SELECT descendant_id FROM task_closure WHERE ancestor_id = OLD.to_task_id);
+END;
+
+-- Rewriting an edge (task rename) is a removal followed by an addition
+CREATE TRIGGER task_closure_update AFTER UPDATE OF from_task_id, to_task_id, dep_type ON dependencies
+WHEN OLD.dep_type = 'contains' OR NEW.dep_type = 'contains' BEGIN
+    DELETE FROM task_closure
+    WHERE OLD.dep_type = 'contains'
+      AND ancestor_id IN (SELECT OLD.from_task_id
+                          UNION
+                          SELECT ancestor_id FROM task_closure WHERE descendant_id = OLD.from_task_id)
+      AND descendant_id IN (SELECT OLD.to_task_id
+                            UNION
+                            SELECT descendant_id FROM task_closure WHERE ancestor_id = OLD.to_task_id);
+    INSERT OR IGNORE INTO task_closure (ancestor_id, descendant_id, depth)
+    SELECT a.id, d.id, a.depth + d.depth + 1
+    FROM (SELECT NEW.from_task_id AS id, 0 AS depth
+          UNION ALL
+          SELECT ancestor_id, depth FROM task_closure WHERE descendant_id = NEW.from_task_id) a,
+         (SELECT NEW.to_task_id AS id, 0 AS depth
+          UNION ALL
+          SELECT descendant_id, depth FROM task_closure WHERE ancestor_id = NEW.to_task_id) d
+    WHERE NEW.dep_type = 'contains';
+END;
@@ -0,0 +1,10 @@
+-- Last delivery of each scheduled reminder to each worker
+-- Reminders (workflow `reminders`) piggyback on `thinking` responses; the row
+-- lets a reminder repeat once per interval rather than on every call.
+
+CREATE TABLE reminder_deliveries (
+    worker_id TEXT NOT NULL REFERENCES workers(id) ON DELETE CASCADE,
+    reminder TEXT NOT NULL,         -- reminder name from the workflow config
+    delivered_at INTEGER NOT NULL,
+    PRIMARY KEY (worker_id, reminder)
+) WITHOUT ROWID;
@@ -0,0 +1,5 @@
+-- Required-tag policies on subtrees
+-- required_child_tags holds a JSON array of tag rules that every task created
+-- or retagged under this task must meet (e.g. ["component/*"]).
+
+ALTER TABLE tasks ADD COLUMN required_child_tags TEXT;
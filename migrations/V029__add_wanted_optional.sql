@@ -0,0 +1,5 @@
+-- Optional wanted tags
+-- When wanted_optional is set, a task's wanted_tags only raise the match score
+-- of agents carrying them; agents without any of them may still claim it.
+
+ALTER TABLE tasks ADD COLUMN wanted_optional INTEGER NOT NULL DEFAULT 0;
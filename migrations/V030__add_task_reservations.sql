@@ -0,0 +1,12 @@
+-- Short-lived holds on tasks, placed by reserve before a claim
+-- While unexpired, other workers cannot claim the task and do not see it in
+-- their ready lists. Expired rows are ignored and pruned on the next reserve.
+
+CREATE TABLE task_reservations (
+    task_id TEXT PRIMARY KEY REFERENCES tasks(id) ON DELETE CASCADE,
+    worker_id TEXT NOT NULL REFERENCES workers(id) ON DELETE CASCADE,
+    reserved_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_task_reservations_worker ON task_reservations(worker_id);
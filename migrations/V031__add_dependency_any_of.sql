@@ -0,0 +1,6 @@
+-- Any-of groups on dependency edges
+-- Start-blocking edges into the same task with the same dep_type and any_of
+-- name form a group that is satisfied once any one of them is (alternative
+-- paths). NULL = the edge must be satisfied on its own.
+
+ALTER TABLE dependencies ADD COLUMN any_of TEXT;
@@ -0,0 +1,6 @@
+-- Dependency feed entries by the other end of the edge
+-- A task's history lists dependency changes on both ends; the dependent end
+-- uses idx_change_feed_task.
+
+CREATE INDEX idx_change_feed_related ON change_feed(related_id, id)
+    WHERE entity = 'dependency';
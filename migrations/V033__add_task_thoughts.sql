@@ -0,0 +1,14 @@
+-- Per-task thought journal
+-- thinking appends a row per claimed task it applies to, so an agent resuming a
+-- task can read back the reasoning behind it. Unlike thought_log (a short
+-- stream per worker for the dashboard), rows are kept per task and exported.
+
+CREATE TABLE task_thoughts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    worker_id TEXT NOT NULL,
+    thought TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_task_thoughts_task ON task_thoughts(task_id, id);
@@ -0,0 +1,12 @@
+-- Deadline reminders already sent
+-- The deadline reminder scheduler records each reminder it fires so a restart
+-- does not send it again. Rows keep the deadline they fired for: moving a
+-- task's due_at re-arms its reminders. Operational state, not exported.
+
+CREATE TABLE deadline_reminders (
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    offset_ms INTEGER NOT NULL,
+    due_at INTEGER NOT NULL,
+    sent_at INTEGER NOT NULL,
+    PRIMARY KEY (task_id, offset_ms)
+);
@@ -0,0 +1,31 @@
+//! Config subcommand for task-graph CLI
+//!
+//! Inspects the configuration the server would run with.
+
+use clap::{Args, Subcommand};
+
+/// Arguments for the config subcommand
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+/// Config inspection commands
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the merged configuration, annotating each value with the tier or
+    /// file it came from, and the files prompts and workflows are loaded from
+    Show(ConfigShowArgs),
+}
+
+/// Arguments for `config show`
+#[derive(Args, Debug)]
+pub struct ConfigShowArgs {
+    /// Only show values under this dotted key (e.g. `server.ui`)
+    pub key: Option<String>,
+
+    /// Print JSON instead of annotated text
+    #[arg(long)]
+    pub json: bool,
+}
@@ -0,0 +1,14 @@
+//! Doctor subcommand for task-graph CLI
+//!
+//! Checks the database for problems: currently, hot queries whose plans scan
+//! whole tables instead of using an index.
+
+use clap::Args;
+
+/// Arguments for the doctor subcommand
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    /// Print the full query plan of every check, not just failing ones
+    #[arg(long)]
+    pub plans: bool,
+}
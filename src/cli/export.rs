@@ -3,6 +3,7 @@
 //! Exports the task database to a structured JSON format that can be
 //! version-controlled, diffed, and re-imported.
 
+use anyhow::{Result, anyhow};
 use clap::Args;
 use std::path::PathBuf;
 
@@ -20,11 +21,12 @@ pub struct ExportArgs {
     /// Comma-separated list of tables to export
     ///
     /// Available tables: tasks, dependencies, attachments, task_tags,
-    /// task_needed_tags, task_wanted_tags, task_sequence
+    /// task_needed_tags, task_wanted_tags, task_fields, task_sequence, task_changes,
+    /// task_thoughts, audit_log
     #[arg(long, value_name = "LIST", value_delimiter = ',')]
     pub tables: Option<Vec<String>>,
 
-    /// Exclude task_sequence table (audit history)
+    /// Exclude history tables (task_sequence, task_changes, task_thoughts, and audit_log)
     #[arg(long)]
     pub no_history: bool,
 
@@ -39,6 +41,21 @@ pub struct ExportArgs {
     /// will be gzip compressed (and .gz appended to filename if needed).
     #[arg(long, value_name = "SIZE")]
     pub compress_threshold: Option<String>,
+
+    /// Export the project as it stood at this time
+    ///
+    /// Accepts an ISO 8601 time or epoch milliseconds. Tasks are rebuilt
+    /// from their status history and recorded field changes; tasks created
+    /// later are left out, and tasks deleted later are included.
+    #[arg(long, value_name = "TIME")]
+    pub snapshot_at: Option<String>,
+
+    /// Export the subtree under this task as a GitHub task list instead
+    ///
+    /// Completed tasks are checked and each item carries its task ID in an
+    /// HTML comment, so the list can be edited and imported back.
+    #[arg(long, value_name = "TASK", conflicts_with_all = ["tables", "no_history", "snapshot_at", "gzip"])]
+    pub markdown: Option<String>,
 }
 
 impl ExportArgs {
@@ -50,12 +67,17 @@ impl ExportArgs {
                 Some(
                     tables
                         .iter()
-                        .filter(|t| *t != "task_sequence")
+                        .filter(|t| {
+                            !matches!(
+                                t.as_str(),
+                                "task_sequence" | "task_changes" | "task_thoughts" | "audit_log"
+                            )
+                        })
                         .cloned()
                         .collect(),
                 )
             } else {
-                // Return all tables except task_sequence
+                // Return all tables except the history tables
                 Some(vec![
                     "tasks".to_string(),
                     "dependencies".to_string(),
@@ -63,6 +85,7 @@ impl ExportArgs {
                     "task_tags".to_string(),
                     "task_needed_tags".to_string(),
                     "task_wanted_tags".to_string(),
+                    "task_fields".to_string(),
                 ])
             }
         } else {
@@ -70,6 +93,21 @@ impl ExportArgs {
         }
     }
 
+    /// Parse the snapshot time into epoch milliseconds
+    pub fn snapshot_at_ms(&self) -> Result<Option<i64>> {
+        self.snapshot_at
+            .as_deref()
+            .map(|s| {
+                crate::tools::tracking::parse_timestamp(s).ok_or_else(|| {
+                    anyhow!(
+                        "Invalid --snapshot-at '{}': expected an ISO 8601 time or epoch milliseconds",
+                        s
+                    )
+                })
+            })
+            .transpose()
+    }
+
     /// Parse the compress threshold into bytes
     pub fn compress_threshold_bytes(&self) -> Option<u64> {
         self.compress_threshold.as_ref().and_then(|s| parse_size(s))
@@ -145,10 +183,15 @@ mod tests {
             no_history: true,
             exclude_deleted: false,
             compress_threshold: None,
+            snapshot_at: None,
+            markdown: None,
         };
 
         let tables = args.tables_to_export().unwrap();
         assert!(!tables.contains(&"task_sequence".to_string()));
+        assert!(!tables.contains(&"task_changes".to_string()));
+        assert!(!tables.contains(&"task_thoughts".to_string()));
+        assert!(!tables.contains(&"audit_log".to_string()));
         assert!(tables.contains(&"tasks".to_string()));
     }
 
@@ -162,6 +205,8 @@ mod tests {
             no_history: false,
             exclude_deleted: false,
             compress_threshold: None,
+            snapshot_at: None,
+            markdown: None,
         };
         assert!(args.should_compress(None));
 
@@ -173,6 +218,8 @@ mod tests {
             no_history: false,
             exclude_deleted: false,
             compress_threshold: None,
+            snapshot_at: None,
+            markdown: None,
         };
         assert!(args.should_compress(None));
 
@@ -184,6 +231,8 @@ mod tests {
             no_history: false,
             exclude_deleted: false,
             compress_threshold: Some("100KB".to_string()),
+            snapshot_at: None,
+            markdown: None,
         };
         assert!(!args.should_compress(Some(50 * 1024))); // Under threshold
         assert!(args.should_compress(Some(150 * 1024))); // Over threshold
@@ -1,11 +1,23 @@
 //! Import subcommand for task-graph CLI
 //!
 //! Imports task data from a structured JSON export file back into
-//! the database.
+//! the database, or tasks from a markdown checklist.
 
-use clap::Args;
+use clap::{Args, ValueEnum};
 use std::path::PathBuf;
 
+/// Format of the file being imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ImportFormat {
+    /// Markdown for .md and .markdown files, otherwise a snapshot (default)
+    #[default]
+    Auto,
+    /// JSON export snapshot (optionally gzipped)
+    Snapshot,
+    /// Markdown checklist (`- [ ]` / `- [x]` items under headings)
+    Markdown,
+}
+
 /// Arguments for the import subcommand
 #[derive(Args, Debug)]
 pub struct ImportArgs {
@@ -62,6 +74,14 @@ pub struct ImportArgs {
     /// importing a snapshot as a subtree of an existing task.
     #[arg(long, value_name = "TASK_ID")]
     pub parent: Option<String>,
+
+    /// Input format
+    ///
+    /// Markdown checklists become one task per checkbox item, with
+    /// headings as parents and checked items completed. They are always
+    /// merged into the existing data with fresh task IDs.
+    #[arg(long, value_enum, default_value_t = ImportFormat::Auto)]
+    pub format: ImportFormat,
 }
 
 impl ImportArgs {
@@ -70,6 +90,17 @@ impl ImportArgs {
         self.file.extension().is_some_and(|ext| ext == "gz")
     }
 
+    /// Check if the file is a markdown checklist (by --format or extension)
+    pub fn is_checklist(&self) -> bool {
+        match self.format {
+            ImportFormat::Markdown => true,
+            ImportFormat::Snapshot => false,
+            ImportFormat::Auto => self.file.extension().is_some_and(|ext| {
+                ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown")
+            }),
+        }
+    }
+
     /// Describe the import mode for logging
     pub fn import_mode(&self) -> &'static str {
         if self.dry_run {
@@ -102,6 +133,7 @@ mod tests {
             strict: false,
             remap_ids: false,
             parent: None,
+            format: ImportFormat::Auto,
         };
         assert!(!args.is_gzipped());
 
@@ -113,6 +145,7 @@ mod tests {
             strict: false,
             remap_ids: false,
             parent: None,
+            format: ImportFormat::Auto,
         };
         assert!(args.is_gzipped());
     }
@@ -128,6 +161,7 @@ mod tests {
             strict: false,
             remap_ids: false,
             parent: None,
+            format: ImportFormat::Auto,
         };
         assert_eq!(args.import_mode(), "dry-run");
 
@@ -140,6 +174,7 @@ mod tests {
             strict: false,
             remap_ids: false,
             parent: None,
+            format: ImportFormat::Auto,
         };
         assert_eq!(args.import_mode(), "replace");
 
@@ -152,6 +187,7 @@ mod tests {
             strict: false,
             remap_ids: false,
             parent: None,
+            format: ImportFormat::Auto,
         };
         assert_eq!(args.import_mode(), "merge-skip");
 
@@ -164,6 +200,7 @@ mod tests {
             strict: false,
             remap_ids: false,
             parent: None,
+            format: ImportFormat::Auto,
         };
         assert_eq!(args.import_mode(), "merge-overwrite");
 
@@ -176,7 +213,33 @@ mod tests {
             strict: false,
             remap_ids: true,
             parent: None,
+            format: ImportFormat::Auto,
         };
         assert_eq!(args.import_mode(), "replace-remap");
     }
+
+    #[test]
+    fn test_is_checklist() {
+        let mut args = ImportArgs {
+            file: PathBuf::from("PLAN.md"),
+            dry_run: false,
+            merge: false,
+            force: false,
+            strict: false,
+            remap_ids: false,
+            parent: None,
+            format: ImportFormat::Auto,
+        };
+        assert!(args.is_checklist());
+
+        args.format = ImportFormat::Snapshot;
+        assert!(!args.is_checklist());
+
+        args.file = PathBuf::from("snapshot.json");
+        args.format = ImportFormat::Auto;
+        assert!(!args.is_checklist());
+
+        args.format = ImportFormat::Markdown;
+        assert!(args.is_checklist());
+    }
 }
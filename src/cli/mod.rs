@@ -3,16 +3,24 @@
 //! This module defines the CLI structure using clap's derive macros.
 //! The main entry point is the `Cli` struct which contains subcommands.
 
+pub mod config;
 pub mod diff;
+pub mod doctor;
 pub mod export;
 pub mod import;
 pub mod migrate;
+pub mod purge;
+pub mod schema;
 
 use clap::{Parser, Subcommand, ValueEnum};
+use config::ConfigArgs;
 use diff::DiffArgs;
+use doctor::DoctorArgs;
 use export::ExportArgs;
 use import::ImportArgs;
 use migrate::MigrateArgs;
+use purge::PurgeArgs;
+use schema::SchemaArgs;
 
 /// UI mode for the server.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
@@ -35,6 +43,10 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub config: Option<String>,
 
+    /// Config profile: merges config.<PROFILE>.yaml over the project and user config
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
     /// Path to database file (overrides config)
     #[arg(short, long, global = true)]
     pub database: Option<String>,
@@ -82,6 +94,18 @@ pub enum Command {
     /// Compare snapshot files or snapshot against database
     Diff(DiffArgs),
 
+    /// Permanently remove soft-deleted tasks older than a given age
+    Purge(PurgeArgs),
+
     /// Migrate from deprecated .task-graph/ to task-graph/ directory
     Migrate(MigrateArgs),
+
+    /// Check that hot queries use indexes (EXPLAIN QUERY PLAN)
+    Doctor(DoctorArgs),
+
+    /// Print the JSON Schema for a configuration file
+    Schema(SchemaArgs),
+
+    /// Inspect the effective configuration
+    Config(ConfigArgs),
 }
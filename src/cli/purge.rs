@@ -0,0 +1,20 @@
+//! Purge subcommand for task-graph CLI
+//!
+//! Permanently removes soft-deleted tasks from the database.
+
+use crate::tools::trash::DEFAULT_PURGE_AGE_DAYS;
+use clap::Args;
+
+/// Arguments for the purge subcommand
+#[derive(Args, Debug)]
+pub struct PurgeArgs {
+    /// Only purge tasks deleted at least this many days ago
+    ///
+    /// Use 0 to purge every soft-deleted task.
+    #[arg(long, value_name = "DAYS", default_value_t = DEFAULT_PURGE_AGE_DAYS)]
+    pub older_than_days: i64,
+
+    /// Show what would be removed without modifying the database
+    #[arg(long)]
+    pub dry_run: bool,
+}
@@ -0,0 +1,37 @@
+//! Schema subcommand for task-graph CLI
+//!
+//! Prints the JSON Schema that configuration files are validated against, for
+//! editor completion and validation (e.g. a `# yaml-language-server: $schema=`
+//! comment pointing at the saved output).
+
+use crate::config::schema::ConfigFileKind;
+use clap::{Args, ValueEnum};
+
+/// Configuration file whose schema to print
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaKind {
+    /// config.yaml
+    Config,
+    /// prompts.yaml
+    Prompts,
+    /// workflows.yaml, workflow-*.yaml, and overlay-*.yaml
+    Workflow,
+}
+
+impl From<SchemaKind> for ConfigFileKind {
+    fn from(kind: SchemaKind) -> Self {
+        match kind {
+            SchemaKind::Config => ConfigFileKind::Config,
+            SchemaKind::Prompts => ConfigFileKind::Prompts,
+            SchemaKind::Workflow => ConfigFileKind::Workflow,
+        }
+    }
+}
+
+/// Arguments for the schema subcommand
+#[derive(Args, Debug)]
+pub struct SchemaArgs {
+    /// Which configuration file's schema to print
+    #[arg(value_enum, default_value = "config")]
+    pub kind: SchemaKind,
+}
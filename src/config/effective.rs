@@ -0,0 +1,245 @@
+//! Effective configuration with the source of every value.
+//!
+//! `ConfigLoader` records each layer it merges (defaults, config files,
+//! profile files, environment variables). The effective configuration is
+//! flattened to dotted keys, and each key is attributed to the highest layer
+//! that sets it. Values changed after loading (command-line flags) are
+//! attributed to the command line. Backs `task-graph config show` and the
+//! `config://effective` resource.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Source reported for values overridden after the layers were merged.
+pub const COMMAND_LINE: &str = "command line";
+
+/// One source merged into the configuration.
+#[derive(Debug, Clone)]
+pub struct ConfigLayer {
+    /// Where the values came from: `defaults`, a file path, or `env NAME`.
+    pub source: String,
+    /// The values this layer sets.
+    pub value: Value,
+}
+
+impl ConfigLayer {
+    pub fn new(source: impl Into<String>, value: Value) -> Self {
+        Self {
+            source: source.into(),
+            value,
+        }
+    }
+}
+
+/// A configuration value and the layer it came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveValue {
+    pub key: String,
+    pub value: Value,
+    pub source: String,
+}
+
+/// A named workflow or overlay and the file it resolves to.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedWorkflow {
+    pub name: String,
+    /// The file that is loaded.
+    pub path: String,
+    /// Files with the same name in lower-priority directories, which are ignored.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shadowed: Vec<String>,
+}
+
+/// The merged configuration with provenance, and the files behind prompts and workflows.
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    /// Selected profile, if any.
+    pub profile: Option<String>,
+    /// Layers merged into `config`, lowest precedence first.
+    pub sources: Vec<String>,
+    /// Every leaf value of the configuration, in key order.
+    pub values: Vec<EffectiveValue>,
+    /// `prompts.yaml` files merged, lowest precedence first.
+    pub prompts_files: Vec<String>,
+    /// `workflows.yaml` files merged, lowest precedence first.
+    pub workflows_files: Vec<String>,
+    /// `server.default_workflow`, if set.
+    pub default_workflow: Option<String>,
+    /// Named workflows (`workflow-*.yaml`) available for selection.
+    pub workflows: Vec<ResolvedWorkflow>,
+    /// Overlays (`overlay-*.yaml`) available for selection.
+    pub overlays: Vec<ResolvedWorkflow>,
+}
+
+impl EffectiveConfig {
+    /// Attribute every leaf of `effective` to the highest layer setting it.
+    pub fn attribute(layers: &[ConfigLayer], effective: &Value) -> Vec<EffectiveValue> {
+        let mut leaves = Vec::new();
+        flatten(effective, &mut Vec::new(), &mut leaves);
+        leaves
+            .into_iter()
+            .map(|(path, value)| {
+                // Null in a layer means "not specified" to the merge
+                let layer = layers.iter().rev().find_map(|layer| {
+                    lookup(&layer.value, &path)
+                        .filter(|v| !v.is_null())
+                        .map(|v| (layer, v))
+                });
+                let source = match layer {
+                    Some((layer, set)) if same_value(set, &value) => layer.source.clone(),
+                    Some(_) => COMMAND_LINE.to_string(),
+                    // Filled in by a serde default under an entry a layer added
+                    None => "defaults".to_string(),
+                };
+                EffectiveValue {
+                    key: path.join("."),
+                    value,
+                    source,
+                }
+            })
+            .collect()
+    }
+
+    /// Keep only values whose key is `prefix` or lies under it.
+    pub fn retain_prefix(&mut self, prefix: &str) {
+        let nested = format!("{}.", prefix);
+        self.values
+            .retain(|v| v.key == prefix || v.key.starts_with(&nested));
+    }
+
+    /// Render as annotated text: one `key = value  # source` line per value.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        if let Some(ref profile) = self.profile {
+            out.push_str(&format!("Profile: {}\n\n", profile));
+        }
+
+        out.push_str("Config sources (lowest to highest precedence):\n");
+        for source in &self.sources {
+            out.push_str(&format!("  {}\n", source));
+        }
+
+        out.push_str("\nValues:\n");
+        let width = self.values.iter().map(|v| v.key.len()).max().unwrap_or(0);
+        for v in &self.values {
+            let value = serde_json::to_string(&v.value).unwrap_or_default();
+            out.push_str(&format!(
+                "  {:<width$} = {}  # {}\n",
+                v.key,
+                value,
+                v.source,
+                width = width
+            ));
+        }
+
+        out.push_str("\nPrompts files:\n");
+        push_files(&mut out, &self.prompts_files);
+        out.push_str("\nWorkflows files:\n");
+        push_files(&mut out, &self.workflows_files);
+
+        let default_workflow = match self.default_workflow {
+            Some(ref name) if self.workflows.iter().any(|w| &w.name == name) => name.clone(),
+            Some(ref name) => format!("{} (not found; using built-in defaults)", name),
+            None => "(none)".to_string(),
+        };
+        out.push_str(&format!("\nDefault workflow: {}\n", default_workflow));
+        out.push_str("\nNamed workflows:\n");
+        push_resolved(&mut out, &self.workflows);
+        out.push_str("\nOverlays:\n");
+        push_resolved(&mut out, &self.overlays);
+        out
+    }
+}
+
+fn push_files(out: &mut String, files: &[String]) {
+    if files.is_empty() {
+        out.push_str("  (none)\n");
+    }
+    for file in files {
+        out.push_str(&format!("  {}\n", file));
+    }
+}
+
+fn push_resolved(out: &mut String, resolved: &[ResolvedWorkflow]) {
+    if resolved.is_empty() {
+        out.push_str("  (none)\n");
+    }
+    for r in resolved {
+        out.push_str(&format!("  {}: {}\n", r.name, r.path));
+        for path in &r.shadowed {
+            out.push_str(&format!("    shadows {}\n", path));
+        }
+    }
+}
+
+/// Collect `(path, value)` for every leaf. Arrays and empty objects are leaves,
+/// matching the merge, which replaces arrays whole.
+fn flatten(value: &Value, path: &mut Vec<String>, out: &mut Vec<(Vec<String>, Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            for key in keys {
+                path.push(key.clone());
+                flatten(&map[key], path, out);
+                path.pop();
+            }
+        }
+        _ => out.push((path.clone(), value.clone())),
+    }
+}
+
+/// Whether the effective value `b` is what the layer value `a` deserializes to.
+/// Numbers compare by value (a file may write `1` for a float) and objects only
+/// on the keys the layer sets, as the rest are filled in by defaults.
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| same_value(x, y))
+        }
+        (Value::Object(x), Value::Object(y)) => x
+            .iter()
+            .all(|(k, v)| v.is_null() || y.get(k).is_some_and(|w| same_value(v, w))),
+        _ => a == b,
+    }
+}
+
+fn lookup<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |v, key| v.get(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_attribute_picks_highest_layer() {
+        let layers = vec![
+            ConfigLayer::new("defaults", json!({"server": {"a": 1, "b": 2, "c": [1]}})),
+            ConfigLayer::new("project", json!({"server": {"b": 3, "c": null}})),
+            ConfigLayer::new("env X", json!({"server": {"c": [2]}})),
+        ];
+        let effective = json!({"server": {"a": 1, "b": 3, "c": [2], "d": 9}});
+        let values = EffectiveConfig::attribute(&layers, &effective);
+        let sources: Vec<(&str, &str)> = values
+            .iter()
+            .map(|v| (v.key.as_str(), v.source.as_str()))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                ("server.a", "defaults"),
+                ("server.b", "project"),
+                ("server.c", "env X"),
+                ("server.d", "defaults"),
+            ]
+        );
+
+        // A value changed after merging is attributed to the command line
+        let effective = json!({"server": {"a": 5, "b": 3, "c": [2]}});
+        let values = EffectiveConfig::attribute(&layers, &effective);
+        assert_eq!(values[0].source, COMMAND_LINE);
+    }
+}
@@ -174,6 +174,22 @@ impl ConfigLoader {
         None
     }
 
+    /// Directories named `relative_dir` in each tier, highest priority first.
+    ///
+    /// Directories are returned whether or not they exist yet, so callers
+    /// that resolve files lazily pick up directories created later.
+    pub fn tier_dirs(&self, relative_dir: &str) -> Vec<PathBuf> {
+        [
+            &self.paths.user_dir,
+            &self.paths.project_dir,
+            &self.paths.project_dir_deprecated,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(relative_dir))
+        .collect()
+    }
+
     /// List files in a directory across all tiers.
     ///
     /// Returns a deduplicated list where higher-tier files shadow lower-tier ones.
@@ -295,6 +311,16 @@ mod tests {
         assert!(loader.find_file("nonexistent.txt").is_none());
     }
 
+    #[test]
+    fn test_tier_dirs_highest_first() {
+        let temp = TempDir::new().unwrap();
+        let loader = create_test_loader(&temp);
+
+        let dirs = loader.tier_dirs("dashboard");
+        assert_eq!(dirs[0], temp.path().join("user").join("dashboard"));
+        assert_eq!(dirs[1], temp.path().join("task-graph").join("dashboard"));
+    }
+
     #[test]
     fn test_list_files_deduplication() {
         let temp = TempDir::new().unwrap();
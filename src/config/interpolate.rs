@@ -0,0 +1,188 @@
+//! Environment variable interpolation in configuration files.
+//!
+//! String values may reference environment variables, so secrets and
+//! machine-specific paths stay out of committed files:
+//!
+//! - `${VAR}` - the value of `VAR`; an error if it is not set
+//! - `${VAR:-fallback}` - `fallback` if `VAR` is unset or empty
+//! - `$${` - a literal `${`
+//!
+//! Names follow shell rules (`[A-Za-z_][A-Za-z0-9_]*`), so other `${...}`
+//! forms, such as the `${server.media_dir}` references in `paths.mappings`,
+//! pass through unchanged. Only values are interpolated, never keys. A value
+//! that is a single reference is read back as a YAML scalar, so
+//! `port: ${UI_PORT}` yields a number.
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+/// Interpolate every string value in `value`, looking variables up with `lookup`.
+pub fn interpolate_with(value: &mut Value, lookup: &dyn Fn(&str) -> Option<String>) -> Result<()> {
+    walk(value, &mut Vec::new(), lookup)
+}
+
+/// Interpolate every string value in `value` from the process environment.
+pub fn interpolate(value: &mut Value) -> Result<()> {
+    interpolate_with(value, &|name| std::env::var(name).ok())
+}
+
+fn walk(
+    value: &mut Value,
+    path: &mut Vec<String>,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<()> {
+    match value {
+        Value::String(s) if s.contains("${") => {
+            let expanded = expand(s, lookup).map_err(|e| {
+                let key = if path.is_empty() {
+                    "(root)".to_string()
+                } else {
+                    path.join(".")
+                };
+                anyhow!("{}: {}", key, e)
+            })?;
+            *value = if is_single_reference(s) {
+                scalar(expanded)
+            } else {
+                Value::String(expanded)
+            };
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                path.push(i.to_string());
+                walk(item, path, lookup)?;
+                path.pop();
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                path.push(key.clone());
+                walk(item, path, lookup)?;
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Expand the references in `s`.
+fn expand(s: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        if let Some(after) = tail.strip_prefix("$${") {
+            out.push_str("${");
+            rest = after;
+            continue;
+        }
+        match parse_reference(tail) {
+            Some((name, default, len)) => {
+                let value = lookup(name).filter(|v| !(default.is_some() && v.is_empty()));
+                match (value, default) {
+                    (Some(v), _) => out.push_str(&v),
+                    (None, Some(d)) => out.push_str(d),
+                    (None, None) => {
+                        return Err(anyhow!(
+                            "environment variable '{}' is not set (use ${{{}:-default}} for a fallback)",
+                            name,
+                            name
+                        ));
+                    }
+                }
+                rest = &tail[len..];
+            }
+            None => {
+                out.push('$');
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Parse `${NAME}` or `${NAME:-default}` at the start of `s`. Returns the
+/// name, the default, and the length of the reference.
+fn parse_reference(s: &str) -> Option<(&str, Option<&str>, usize)> {
+    let body = s.strip_prefix("${")?;
+    let close = body.find('}')?;
+    let inner = &body[..close];
+    let (name, default) = match inner.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (inner, None),
+    };
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then_some((name, default, close + 3))
+}
+
+/// Whether `s` is exactly one `${...}` reference.
+fn is_single_reference(s: &str) -> bool {
+    parse_reference(s).is_some_and(|(_, _, len)| len == s.len())
+}
+
+/// Read a substituted value as a YAML scalar: numbers and booleans keep their
+/// type, anything else stays a string.
+fn scalar(s: String) -> Value {
+    match serde_yaml::from_str::<Value>(&s) {
+        Ok(v @ (Value::Number(_) | Value::Bool(_))) => v,
+        _ => Value::String(s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "DB_DIR" => Some("/var/lib/tg".to_string()),
+            "PORT" => Some("9090".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_interpolates_values() {
+        let mut value = json!({
+            "server": {
+                "db_path": "${DB_DIR}/tasks.db",
+                "log_dir": "${LOG_DIR:-logs}",
+                "skills_dir": "${EMPTY:-skills}",
+                "ui": { "port": "${PORT}" }
+            },
+            "paths": { "mappings": { "media": "${server.media_dir}", "home": "$HOME" } },
+            "note": "cost: $${amount}"
+        });
+        interpolate_with(&mut value, &env).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "server": {
+                    "db_path": "/var/lib/tg/tasks.db",
+                    "log_dir": "logs",
+                    "skills_dir": "skills",
+                    "ui": { "port": 9090 }
+                },
+                "paths": { "mappings": { "media": "${server.media_dir}", "home": "$HOME" } },
+                "note": "cost: ${amount}"
+            })
+        );
+    }
+
+    #[test]
+    fn test_unset_variable_names_key() {
+        let mut value = json!({ "server": { "db_path": "${MISSING}/tasks.db" } });
+        let err = interpolate_with(&mut value, &env).unwrap_err().to_string();
+        assert!(err.starts_with("server.db_path: "), "{}", err);
+        assert!(err.contains("'MISSING' is not set"), "{}", err);
+    }
+}
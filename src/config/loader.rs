@@ -2,7 +2,10 @@
 //!
 //! Loads configuration from multiple tiers and merges them field-by-field.
 
+use super::effective::{ConfigLayer, EffectiveConfig, ResolvedWorkflow};
+use super::interpolate::interpolate;
 use super::merge::deep_merge_all;
+use super::schema::{self, ConfigFileKind};
 use super::types::{Config, Prompts};
 use anyhow::Result;
 use serde_json::Value;
@@ -46,6 +49,8 @@ pub struct ConfigPaths {
     pub project_dir_deprecated: Option<PathBuf>,
     /// User-level config directory
     pub user_dir: Option<PathBuf>,
+    /// Named profile whose `config.{profile}.yaml` files are merged last
+    pub profile: Option<String>,
 }
 
 impl Default for ConfigPaths {
@@ -78,12 +83,18 @@ impl ConfigPaths {
             .map(PathBuf::from)
             .or_else(|| Some(PathBuf::from("config")));
 
+        // Profile: TASK_GRAPH_PROFILE (set by --profile)
+        let profile = std::env::var("TASK_GRAPH_PROFILE")
+            .ok()
+            .filter(|p| !p.is_empty());
+
         Self {
             defaults_dir: None, // Defaults are embedded, not on disk
             install_dir,
             project_dir,
             project_dir_deprecated,
             user_dir,
+            profile,
         }
     }
 
@@ -96,6 +107,7 @@ impl ConfigPaths {
             project_dir,
             project_dir_deprecated: Some(PathBuf::from(".task-graph")),
             user_dir,
+            profile: None,
         }
     }
 
@@ -111,9 +123,16 @@ impl ConfigPaths {
             project_dir,
             project_dir_deprecated: Some(PathBuf::from(".task-graph")),
             user_dir,
+            profile: None,
         }
     }
 
+    /// Select a named profile (replaces any from the environment).
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
     /// Get the effective project directory (prefers new location, falls back to deprecated).
     pub fn effective_project_dir(&self) -> Option<&Path> {
         // Check new location first
@@ -161,6 +180,8 @@ pub struct ConfigLoader {
     config_path: Option<PathBuf>,
     /// Whether deprecated paths are in use
     using_deprecated: bool,
+    /// Layers merged into `config`, lowest precedence first
+    layers: Vec<ConfigLayer>,
 }
 
 impl ConfigLoader {
@@ -183,33 +204,44 @@ impl ConfigLoader {
         // Check for explicit config path override
         if let Ok(explicit_path) = std::env::var("TASK_GRAPH_CONFIG_PATH") {
             let path = PathBuf::from(&explicit_path);
+            if let Some(ref profile) = paths.profile {
+                warn!(
+                    "Ignoring profile '{}': TASK_GRAPH_CONFIG_PATH overrides all config files.",
+                    profile
+                );
+            }
             let config = Config::load(&path)?;
+            let layers = vec![
+                ConfigLayer::new("defaults", serde_json::to_value(Config::default())?),
+                ConfigLayer::new(path.display().to_string(), read_yaml(&path)?),
+            ];
             return Ok(Self {
                 paths,
                 config,
                 config_path: Some(path),
                 using_deprecated,
+                layers,
             });
         }
 
         // Collect configs from each tier
-        let mut configs: Vec<Value> = Vec::new();
+        let mut layers: Vec<ConfigLayer> = Vec::new();
 
         // Tier 1: Defaults (embedded)
-        let default_config = Config::default();
-        if let Ok(default_json) = serde_json::to_value(&default_config) {
-            configs.push(default_json);
-        }
+        layers.push(ConfigLayer::new(
+            "defaults",
+            serde_json::to_value(Config::default())?,
+        ));
 
         // Tier 2: Project config
         let mut project_config_path = None;
         if let Some(project_dir) = paths.effective_project_dir() {
             let config_file = project_dir.join("config.yaml");
-            if config_file.exists()
-                && let Ok(content) = std::fs::read_to_string(&config_file)
-                && let Ok(yaml_value) = serde_yaml::from_str::<Value>(&content)
-            {
-                configs.push(yaml_value);
+            if let Some(yaml_value) = read_config_file(&config_file, ConfigFileKind::Config)? {
+                layers.push(ConfigLayer::new(
+                    config_file.display().to_string(),
+                    yaml_value,
+                ));
                 project_config_path = Some(config_file);
             }
         }
@@ -217,50 +249,82 @@ impl ConfigLoader {
         // Tier 3: User config
         if let Some(ref user_dir) = paths.user_dir {
             let config_file = user_dir.join("config.yaml");
-            if config_file.exists()
-                && let Ok(content) = std::fs::read_to_string(&config_file)
-                && let Ok(yaml_value) = serde_yaml::from_str::<Value>(&content)
-            {
-                configs.push(yaml_value);
+            if let Some(yaml_value) = read_config_file(&config_file, ConfigFileKind::Config)? {
+                layers.push(ConfigLayer::new(
+                    config_file.display().to_string(),
+                    yaml_value,
+                ));
             }
         }
 
-        // Merge all configs
-        let merged = deep_merge_all(configs);
-        let mut config: Config = serde_json::from_value(merged)?;
+        // Tier 3b: Profile overlays (project, then user), on top of the base tiers
+        if let Some(ref profile) = paths.profile {
+            validate_profile_name(profile)?;
+            let filename = format!("config.{}.yaml", profile);
+            let profile_files: Vec<PathBuf> =
+                [paths.effective_project_dir(), paths.user_dir.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .map(|dir| dir.join(&filename))
+                    .collect();
+            let mut found = false;
+            for profile_file in &profile_files {
+                if let Some(yaml_value) = read_config_file(profile_file, ConfigFileKind::Config)? {
+                    layers.push(ConfigLayer::new(
+                        profile_file.display().to_string(),
+                        yaml_value,
+                    ));
+                    found = true;
+                }
+            }
+            if !found {
+                return Err(anyhow::anyhow!(
+                    "Profile '{}' not found. Searched for '{}' in project and user directories.",
+                    profile,
+                    filename
+                ));
+            }
+        }
 
         // Tier 4: Environment variable overrides
-        Self::apply_env_overrides(&mut config);
+        layers.extend(Self::env_override_layers());
+
+        // Merge all configs
+        let merged = deep_merge_all(layers.iter().map(|layer| layer.value.clone()));
+        let config: Config = serde_json::from_value(merged)?;
 
         Ok(Self {
             paths,
             config,
             config_path: project_config_path,
             using_deprecated,
+            layers,
         })
     }
 
-    /// Apply environment variable overrides to config.
-    fn apply_env_overrides(config: &mut Config) {
-        if let Ok(db_path) = std::env::var("TASK_GRAPH_DB_PATH") {
-            config.server.db_path = PathBuf::from(db_path);
-        }
-
-        if let Ok(media_dir) = std::env::var("TASK_GRAPH_MEDIA_DIR") {
-            config.server.media_dir = PathBuf::from(media_dir);
-        }
-
-        if let Ok(log_dir) = std::env::var("TASK_GRAPH_LOG_DIR") {
-            config.server.log_dir = PathBuf::from(log_dir);
-        }
-
-        if let Ok(skills_dir) = std::env::var("TASK_GRAPH_SKILLS_DIR") {
-            config.server.skills_dir = PathBuf::from(skills_dir);
-        }
+    /// One layer per set environment variable that overrides a config value.
+    fn env_override_layers() -> Vec<ConfigLayer> {
+        [
+            ("TASK_GRAPH_DB_PATH", "db_path"),
+            ("TASK_GRAPH_MEDIA_DIR", "media_dir"),
+            ("TASK_GRAPH_LOG_DIR", "log_dir"),
+            ("TASK_GRAPH_SKILLS_DIR", "skills_dir"),
+        ]
+        .into_iter()
+        .filter_map(|(var, key)| {
+            let value = std::env::var(var).ok()?;
+            Some(ConfigLayer::new(
+                format!("env {}", var),
+                serde_json::json!({ "server": { key: value } }),
+            ))
+        })
+        .collect()
     }
 
     /// Load prompts configuration with tier merging.
-    pub fn load_prompts(&self) -> Prompts {
+    ///
+    /// Fails if a prompts file is not valid YAML or does not match the schema.
+    pub fn load_prompts(&self) -> Result<Prompts> {
         let mut prompts_configs: Vec<Value> = Vec::new();
 
         // Tier 1: Defaults (empty)
@@ -271,10 +335,7 @@ impl ConfigLoader {
         // Tier 2: Project prompts
         if let Some(project_dir) = self.paths.effective_project_dir() {
             let prompts_file = project_dir.join("prompts.yaml");
-            if prompts_file.exists()
-                && let Ok(content) = std::fs::read_to_string(&prompts_file)
-                && let Ok(yaml_value) = serde_yaml::from_str::<Value>(&content)
-            {
+            if let Some(yaml_value) = read_config_file(&prompts_file, ConfigFileKind::Prompts)? {
                 prompts_configs.push(yaml_value);
             }
         }
@@ -282,24 +343,22 @@ impl ConfigLoader {
         // Tier 3: User prompts
         if let Some(ref user_dir) = self.paths.user_dir {
             let prompts_file = user_dir.join("prompts.yaml");
-            if prompts_file.exists()
-                && let Ok(content) = std::fs::read_to_string(&prompts_file)
-                && let Ok(yaml_value) = serde_yaml::from_str::<Value>(&content)
-            {
+            if let Some(yaml_value) = read_config_file(&prompts_file, ConfigFileKind::Prompts)? {
                 prompts_configs.push(yaml_value);
             }
         }
 
         // Merge and deserialize
         let merged = deep_merge_all(prompts_configs);
-        serde_json::from_value(merged).unwrap_or_default()
+        Ok(serde_json::from_value(merged)?)
     }
 
     /// Load workflows configuration with tier merging.
     ///
     /// Loads from embedded defaults, then project workflows.yaml, then user workflows.yaml.
     /// Later tiers override earlier ones (objects are deep-merged, prompts are replaced).
-    pub fn load_workflows(&self) -> super::workflows::WorkflowsConfig {
+    /// Fails if a workflows file is not valid YAML or does not match the schema.
+    pub fn load_workflows(&self) -> Result<super::workflows::WorkflowsConfig> {
         let mut workflows_configs: Vec<Value> = Vec::new();
 
         // Tier 1: Defaults (embedded)
@@ -311,10 +370,7 @@ impl ConfigLoader {
         // Tier 2: Project workflows
         if let Some(project_dir) = self.paths.effective_project_dir() {
             let workflows_file = project_dir.join("workflows.yaml");
-            if workflows_file.exists()
-                && let Ok(content) = std::fs::read_to_string(&workflows_file)
-                && let Ok(yaml_value) = serde_yaml::from_str::<Value>(&content)
-            {
+            if let Some(yaml_value) = read_config_file(&workflows_file, ConfigFileKind::Workflow)? {
                 workflows_configs.push(yaml_value);
             }
         }
@@ -322,17 +378,62 @@ impl ConfigLoader {
         // Tier 3: User workflows
         if let Some(ref user_dir) = self.paths.user_dir {
             let workflows_file = user_dir.join("workflows.yaml");
-            if workflows_file.exists()
-                && let Ok(content) = std::fs::read_to_string(&workflows_file)
-                && let Ok(yaml_value) = serde_yaml::from_str::<Value>(&content)
-            {
+            if let Some(yaml_value) = read_config_file(&workflows_file, ConfigFileKind::Workflow)? {
                 workflows_configs.push(yaml_value);
             }
         }
 
         // Merge and deserialize
         let merged = deep_merge_all(workflows_configs);
-        serde_json::from_value(merged).unwrap_or_default()
+        Ok(serde_json::from_value(merged)?)
+    }
+
+    /// Report the merged configuration with the source of every value, and
+    /// the files prompts, workflows, and overlays are loaded from.
+    ///
+    /// Values changed through `config_mut` after loading are attributed to
+    /// the command line.
+    pub fn effective_config(&self) -> Result<EffectiveConfig> {
+        let effective = serde_json::to_value(&self.config)?;
+        let base_files = |filename: &str| -> Vec<String> {
+            [
+                self.paths.effective_project_dir(),
+                self.paths.user_dir.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .map(|dir| dir.join(filename))
+            .filter(|path| path.exists())
+            .map(|path| path.display().to_string())
+            .collect()
+        };
+        let resolve = |names: Vec<String>, prefix: &str| -> Vec<ResolvedWorkflow> {
+            names
+                .into_iter()
+                .filter_map(|name| {
+                    let mut files = self
+                        .tier_files(&format!("{}-{}.yaml", prefix, name))
+                        .into_iter()
+                        .map(|path| path.display().to_string());
+                    Some(ResolvedWorkflow {
+                        path: files.next()?,
+                        shadowed: files.collect(),
+                        name,
+                    })
+                })
+                .collect()
+        };
+
+        Ok(EffectiveConfig {
+            profile: self.paths.profile.clone(),
+            sources: self.layers.iter().map(|l| l.source.clone()).collect(),
+            values: EffectiveConfig::attribute(&self.layers, &effective),
+            prompts_files: base_files("prompts.yaml"),
+            workflows_files: base_files("workflows.yaml"),
+            default_workflow: self.config.server.default_workflow.clone(),
+            workflows: resolve(self.list_workflows(), "workflow"),
+            overlays: resolve(self.list_overlays(), "overlay"),
+        })
     }
 
     /// Get the loaded configuration.
@@ -360,6 +461,11 @@ impl ConfigLoader {
         self.using_deprecated
     }
 
+    /// Get the selected profile, if any.
+    pub fn profile(&self) -> Option<&str> {
+        self.paths.profile.as_deref()
+    }
+
     /// Get the effective project directory.
     pub fn project_dir(&self) -> Option<&Path> {
         self.paths.effective_project_dir()
@@ -396,42 +502,35 @@ impl ConfigLoader {
     /// Returns the merged workflow config (defaults + named workflow).
     pub fn load_workflow_by_name(&self, name: &str) -> Result<super::workflows::WorkflowsConfig> {
         let filename = format!("workflow-{}.yaml", name);
-
-        // Check user directory first (highest priority)
-        if let Some(ref user_dir) = self.paths.user_dir {
-            let workflow_file = user_dir.join(&filename);
-            if workflow_file.exists() {
-                return self.load_workflow_from_path(&workflow_file);
-            }
-        }
-
-        // Check project directory second
-        if let Some(project_dir) = self.paths.effective_project_dir() {
-            let workflow_file = project_dir.join(&filename);
-            if workflow_file.exists() {
-                return self.load_workflow_from_path(&workflow_file);
-            }
-        }
-
-        // Fall back to install directory (built-in defaults)
-        if let Some(ref install_dir) = self.paths.install_dir {
-            let workflow_file = install_dir.join(&filename);
-            if workflow_file.exists() {
-                return self.load_workflow_from_path(&workflow_file);
-            }
-        }
-
-        Err(anyhow::anyhow!(
-            "Workflow '{}' not found. Searched for '{}' in user, project, and install directories.",
-            name,
-            filename
-        ))
+        match self.tier_files(&filename).first() {
+            Some(workflow_file) => self.load_workflow_from_path(workflow_file),
+            None => Err(anyhow::anyhow!(
+                "Workflow '{}' not found. Searched for '{}' in user, project, and install directories.",
+                name,
+                filename
+            )),
+        }
+    }
+
+    /// Paths of `filename` that exist in the user, project, and install
+    /// directories, highest priority first. The first one is the one loaded.
+    fn tier_files(&self, filename: &str) -> Vec<PathBuf> {
+        [
+            self.paths.user_dir.as_deref(),
+            self.paths.effective_project_dir(),
+            self.paths.install_dir.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(filename))
+        .filter(|path| path.exists())
+        .collect()
     }
 
     /// Load workflow from a specific path, merging with defaults.
     fn load_workflow_from_path(&self, path: &Path) -> Result<super::workflows::WorkflowsConfig> {
-        let content = std::fs::read_to_string(path)?;
-        let yaml_value: Value = serde_yaml::from_str(&content)?;
+        let yaml_value = read_yaml(path)?;
+        schema::validate_file(ConfigFileKind::Workflow, path, &yaml_value)?;
 
         // Start with defaults and merge the named workflow on top
         let mut configs: Vec<Value> = Vec::new();
@@ -571,43 +670,22 @@ impl ConfigLoader {
     /// the overlay is later applied via `apply_overlay()`.
     pub fn load_overlay_by_name(&self, name: &str) -> Result<super::workflows::WorkflowsConfig> {
         let filename = format!("overlay-{}.yaml", name);
-
-        // Check user directory first (highest priority)
-        if let Some(ref user_dir) = self.paths.user_dir {
-            let overlay_file = user_dir.join(&filename);
-            if overlay_file.exists() {
-                return self.load_overlay_from_path(&overlay_file);
-            }
-        }
-
-        // Check project directory second
-        if let Some(project_dir) = self.paths.effective_project_dir() {
-            let overlay_file = project_dir.join(&filename);
-            if overlay_file.exists() {
-                return self.load_overlay_from_path(&overlay_file);
-            }
-        }
-
-        // Fall back to install directory (built-in defaults)
-        if let Some(ref install_dir) = self.paths.install_dir {
-            let overlay_file = install_dir.join(&filename);
-            if overlay_file.exists() {
-                return self.load_overlay_from_path(&overlay_file);
-            }
+        match self.tier_files(&filename).first() {
+            Some(overlay_file) => self.load_overlay_from_path(overlay_file),
+            None => Err(anyhow::anyhow!(
+                "Overlay '{}' not found. Searched for '{}' in user, project, and install directories.",
+                name,
+                filename
+            )),
         }
-
-        Err(anyhow::anyhow!(
-            "Overlay '{}' not found. Searched for '{}' in user, project, and install directories.",
-            name,
-            filename
-        ))
     }
 
     /// Load an overlay from a specific path WITHOUT merging with defaults.
     /// This is the critical difference from `load_workflow_from_path`.
     fn load_overlay_from_path(&self, path: &Path) -> Result<super::workflows::WorkflowsConfig> {
-        let content = std::fs::read_to_string(path)?;
-        let mut overlay: super::workflows::WorkflowsConfig = serde_yaml::from_str(&content)?;
+        let yaml_value = read_yaml(path)?;
+        schema::validate_file(ConfigFileKind::Workflow, path, &yaml_value)?;
+        let mut overlay: super::workflows::WorkflowsConfig = serde_json::from_value(yaml_value)?;
         overlay.source_file = Some(path.to_path_buf());
         Ok(overlay)
     }
@@ -625,6 +703,39 @@ impl ConfigLoader {
     }
 }
 
+/// Profile names become part of a file name, so keep them to one plain segment.
+fn validate_profile_name(profile: &str) -> Result<()> {
+    let valid = profile
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if profile.is_empty() || !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid profile name '{}': use letters, digits, '-' and '_'",
+            profile
+        ));
+    }
+    Ok(())
+}
+
+/// Read a YAML configuration file and interpolate environment variables in it.
+pub(super) fn read_yaml(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)?;
+    let mut value: Value = serde_yaml::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Invalid {}: {}", path.display(), e))?;
+    interpolate(&mut value).map_err(|e| anyhow::anyhow!("Invalid {}:\n{}", path.display(), e))?;
+    Ok(value)
+}
+
+/// Read and validate a YAML configuration file. Returns `None` if it does not exist.
+fn read_config_file(path: &Path, kind: ConfigFileKind) -> Result<Option<Value>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let value = read_yaml(path)?;
+    schema::validate_file(kind, path, &value)?;
+    Ok(Some(value))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -711,4 +822,159 @@ server:
         // stale_timeout_seconds should be from project
         assert_eq!(config.server.stale_timeout_seconds, 600);
     }
+
+    #[test]
+    fn test_unknown_config_key_is_rejected() {
+        let temp = TempDir::new().unwrap();
+        let project_dir = temp.path().join("task-graph");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(
+            project_dir.join("config.yaml"),
+            "server:\n  claim_limt: 10\n",
+        )
+        .unwrap();
+
+        let paths = ConfigPaths::with_dirs(Some(project_dir), Some(temp.path().join("user")));
+
+        let err = ConfigLoader::load_with_paths(paths)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("config.yaml"), "{}", err);
+        assert!(err.contains("server.claim_limt: unknown key"), "{}", err);
+    }
+
+    #[test]
+    fn test_env_vars_are_interpolated() {
+        let temp = TempDir::new().unwrap();
+        let project_dir = temp.path().join("task-graph");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(
+            project_dir.join("config.yaml"),
+            "server:\n  claim_limit: ${TG_TEST_UNSET_CLAIM_LIMIT:-7}\n  db_path: ${TG_TEST_UNSET_DIR:-data}/tasks.db\n",
+        )
+        .unwrap();
+
+        let paths = ConfigPaths::with_dirs(Some(project_dir.clone()), None);
+        let loader = ConfigLoader::load_with_paths(paths).unwrap();
+        assert_eq!(loader.config().server.claim_limit, 7);
+        assert_eq!(
+            loader.config().server.db_path,
+            PathBuf::from("data/tasks.db")
+        );
+
+        // A variable without a fallback must be set
+        std::fs::write(
+            project_dir.join("config.yaml"),
+            "server:\n  db_path: ${TG_TEST_UNSET_DIR}/tasks.db\n",
+        )
+        .unwrap();
+        let paths = ConfigPaths::with_dirs(Some(project_dir), None);
+        let err = ConfigLoader::load_with_paths(paths)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("server.db_path: "), "{}", err);
+        assert!(err.contains("'TG_TEST_UNSET_DIR' is not set"), "{}", err);
+    }
+
+    #[test]
+    fn test_profile_overrides_user_and_project() {
+        let temp = TempDir::new().unwrap();
+        let project_dir = temp.path().join("task-graph");
+        let user_dir = temp.path().join("user");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::create_dir_all(&user_dir).unwrap();
+
+        std::fs::write(
+            project_dir.join("config.yaml"),
+            "server:\n  claim_limit: 10\n  stale_timeout_seconds: 600\n",
+        )
+        .unwrap();
+        std::fs::write(user_dir.join("config.yaml"), "server:\n  claim_limit: 20\n").unwrap();
+        std::fs::write(
+            project_dir.join("config.prod.yaml"),
+            "server:\n  claim_limit: 2\n",
+        )
+        .unwrap();
+
+        let paths = ConfigPaths::with_dirs(Some(project_dir.clone()), Some(user_dir.clone()));
+
+        // Without the profile its file is ignored
+        let loader = ConfigLoader::load_with_paths(paths.clone()).unwrap();
+        assert_eq!(loader.config().server.claim_limit, 20);
+
+        let loader =
+            ConfigLoader::load_with_paths(paths.clone().with_profile(Some("prod".into()))).unwrap();
+        assert_eq!(loader.profile(), Some("prod"));
+        // The project profile beats the user base config...
+        assert_eq!(loader.config().server.claim_limit, 2);
+        // ...and leaves unset keys to the base tiers
+        assert_eq!(loader.config().server.stale_timeout_seconds, 600);
+
+        // A user profile file beats the project one
+        std::fs::write(
+            user_dir.join("config.prod.yaml"),
+            "server:\n  claim_limit: 3\n",
+        )
+        .unwrap();
+        let loader =
+            ConfigLoader::load_with_paths(paths.clone().with_profile(Some("prod".into()))).unwrap();
+        assert_eq!(loader.config().server.claim_limit, 3);
+    }
+
+    #[test]
+    fn test_missing_or_invalid_profile_is_an_error() {
+        let temp = TempDir::new().unwrap();
+        let paths = ConfigPaths::with_dirs(
+            Some(temp.path().join("project")),
+            Some(temp.path().join("user")),
+        );
+
+        let err = ConfigLoader::load_with_paths(paths.clone().with_profile(Some("staging".into())))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("config.staging.yaml"), "{}", err);
+
+        let err = ConfigLoader::load_with_paths(paths.with_profile(Some("../prod".into())))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Invalid profile name"), "{}", err);
+    }
+
+    #[test]
+    fn test_effective_config_attributes_sources() {
+        let temp = TempDir::new().unwrap();
+        let project_dir = temp.path().join("task-graph");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(
+            project_dir.join("config.yaml"),
+            "server:\n  claim_limit: 10\n",
+        )
+        .unwrap();
+        std::fs::write(project_dir.join("workflow-solo.yaml"), "name: solo\n").unwrap();
+
+        let paths =
+            ConfigPaths::with_dirs(Some(project_dir.clone()), Some(temp.path().join("user")));
+        let mut loader = ConfigLoader::load_with_paths(paths).unwrap();
+        loader.config_mut().server.stale_timeout_seconds = 60;
+
+        let effective = loader.effective_config().unwrap();
+        let source = |key: &str| {
+            effective
+                .values
+                .iter()
+                .find(|v| v.key == key)
+                .map(|v| v.source.clone())
+                .unwrap()
+        };
+        let config_file = project_dir.join("config.yaml").display().to_string();
+        assert_eq!(
+            effective.sources,
+            vec!["defaults".to_string(), config_file.clone()]
+        );
+        assert_eq!(source("server.claim_limit"), config_file);
+        assert_eq!(source("server.stale_timeout_seconds"), "command line");
+        assert_eq!(source("server.ui.port"), "defaults");
+        assert_eq!(effective.workflows.len(), 1);
+        assert_eq!(effective.workflows[0].name, "solo");
+    }
 }
@@ -11,6 +11,7 @@
 //!
 //! ## Environment Variables
 //! - `TASK_GRAPH_CONFIG_PATH` - Explicit config file (overrides all)
+//! - `TASK_GRAPH_PROFILE` - Profile whose `config.{profile}.yaml` files are merged last
 //! - `TASK_GRAPH_DB_PATH` - Database path
 //! - `TASK_GRAPH_MEDIA_DIR` - Media directory
 //! - `TASK_GRAPH_LOG_DIR` - Log directory
@@ -18,9 +19,13 @@
 //! - `TASK_GRAPH_USER_DIR` - User config dir (default: `~/.task-graph`)
 //! - `TASK_GRAPH_PROJECT_DIR` - Project config dir (default: `./task-graph`)
 
+pub mod effective;
 mod files;
+pub mod interpolate;
 mod loader;
 mod merge;
+pub mod schema;
+pub mod status;
 mod types;
 pub mod watcher;
 pub mod workflows;
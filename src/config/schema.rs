@@ -0,0 +1,234 @@
+//! JSON Schema for the configuration files, and strict validation against it.
+//!
+//! Schemas are generated from the serde types, so they track the types
+//! automatically. Every object with named fields is closed
+//! (`additionalProperties: false`), which turns a misspelled key, silently
+//! dropped by serde's defaults, into an error naming the key's path. Keys
+//! starting with `x-` are extensions: accepted anywhere and never interpreted,
+//! for notes meant for the agents and people reading the file.
+
+use super::types::{Config, Prompts};
+use super::workflows::WorkflowsConfig;
+use anyhow::{Result, anyhow};
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::{ValidationError, Validator};
+use schemars::generate::SchemaSettings;
+use schemars::transform::RecursiveTransform;
+use schemars::{JsonSchema, Schema};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Keys matching this are extensions and skip validation.
+const EXTENSION_PATTERN: &str = "^x-";
+
+/// A kind of configuration file, each validated by its own schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFileKind {
+    /// `config.yaml`
+    Config,
+    /// `prompts.yaml`
+    Prompts,
+    /// `workflows.yaml`, `workflow-*.yaml`, and `overlay-*.yaml`
+    Workflow,
+}
+
+impl ConfigFileKind {
+    /// All kinds, in declaration order.
+    pub const ALL: [ConfigFileKind; 3] = [
+        ConfigFileKind::Config,
+        ConfigFileKind::Prompts,
+        ConfigFileKind::Workflow,
+    ];
+
+    /// Generate the JSON Schema for this kind of file.
+    pub fn schema(self) -> Value {
+        let schema = match self {
+            ConfigFileKind::Config => generate::<Config>(),
+            ConfigFileKind::Prompts => generate::<Prompts>(),
+            ConfigFileKind::Workflow => generate::<WorkflowsConfig>(),
+        };
+        schema.to_value()
+    }
+
+    fn validator(self) -> &'static Validator {
+        static VALIDATORS: OnceLock<Vec<Validator>> = OnceLock::new();
+        let validators = VALIDATORS.get_or_init(|| {
+            Self::ALL
+                .iter()
+                .map(|kind| {
+                    jsonschema::validator_for(&kind.schema())
+                        .expect("generated config schema is valid")
+                })
+                .collect()
+        });
+        &validators[self as usize]
+    }
+}
+
+fn generate<T: JsonSchema>() -> Schema {
+    SchemaSettings::draft2020_12()
+        .with_transform(RecursiveTransform(close_object))
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// Reject keys other than the declared properties and `x-` extensions.
+fn close_object(schema: &mut Schema) {
+    if schema.get("properties").is_some() && schema.get("additionalProperties").is_none() {
+        schema.insert(
+            "patternProperties".to_string(),
+            serde_json::json!({ EXTENSION_PATTERN: true }),
+        );
+        schema.insert("additionalProperties".to_string(), Value::Bool(false));
+    }
+}
+
+/// Validate the parsed contents of a configuration file.
+///
+/// An empty file (`null`) is valid. Every violation is reported on its own
+/// line as `<key.path>: <message>`.
+pub fn validate(kind: ConfigFileKind, value: &Value) -> Result<()> {
+    if value.is_null() {
+        return Ok(());
+    }
+    let mut problems = Vec::new();
+    for error in kind.validator().iter_errors(value) {
+        describe(&error, &mut problems);
+    }
+    if problems.is_empty() {
+        return Ok(());
+    }
+    problems.dedup();
+    Err(anyhow!("{}", problems.join("\n")))
+}
+
+/// Validate a configuration file's parsed contents, naming the file in the error.
+pub fn validate_file(kind: ConfigFileKind, path: &Path, value: &Value) -> Result<()> {
+    validate(kind, value).map_err(|e| anyhow!("Invalid {}:\n{}", path.display(), e))
+}
+
+/// Append one line per problem in `error`, as `<key.path>: <message>`.
+fn describe(error: &ValidationError<'_>, out: &mut Vec<String>) {
+    let path = key_path(error.instance_path().as_str());
+    match error.kind() {
+        ValidationErrorKind::AdditionalProperties { unexpected } => {
+            for key in unexpected {
+                out.push(format!("{}: unknown key", join_key(&path, key)));
+            }
+        }
+        ValidationErrorKind::AnyOf { context } | ValidationErrorKind::OneOfNotValid { context } => {
+            // `Option<T>` is `anyOf [T, null]`; when only one branch got past
+            // the type check, its errors say more than "not valid under any"
+            let specific: Vec<_> = context
+                .iter()
+                .filter(|branch| !branch.iter().all(|e| is_type_mismatch(e, &path)))
+                .collect();
+            match specific.as_slice() {
+                [branch] => branch.iter().for_each(|e| describe(e, out)),
+                _ => out.push(format!("{}: {}", display_path(&path), error)),
+            }
+        }
+        _ => out.push(format!("{}: {}", display_path(&path), error)),
+    }
+}
+
+fn is_type_mismatch(error: &ValidationError<'_>, path: &str) -> bool {
+    matches!(error.kind(), ValidationErrorKind::Type { .. })
+        && key_path(error.instance_path().as_str()) == path
+}
+
+/// Convert a JSON pointer (`/server/ui/port`) to a key path (`server.ui.port`).
+fn key_path(pointer: &str) -> String {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "(root)" } else { path }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_shipped_files_are_valid() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("config");
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let kind = match name.as_str() {
+                "config.yaml" => ConfigFileKind::Config,
+                "prompts.yaml" => ConfigFileKind::Prompts,
+                n if n.ends_with(".yaml")
+                    && (n.starts_with("workflow-") || n.starts_with("overlay-")) =>
+                {
+                    ConfigFileKind::Workflow
+                }
+                _ => continue,
+            };
+            let value = yaml(&std::fs::read_to_string(&path).unwrap());
+            if let Err(e) = validate_file(kind, &path, &value) {
+                panic!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_defaults_are_valid() {
+        let config = serde_json::to_value(Config::default()).unwrap();
+        validate(ConfigFileKind::Config, &config).unwrap();
+        let workflows = serde_json::to_value(WorkflowsConfig::default()).unwrap();
+        validate(ConfigFileKind::Workflow, &workflows).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_key_reports_path() {
+        let err = validate(
+            ConfigFileKind::Config,
+            &yaml("server:\n  claim_limt: 3\n  ui:\n    prot: 8080\n"),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("server.claim_limt: unknown key"), "{}", err);
+        assert!(err.contains("server.ui.prot: unknown key"), "{}", err);
+
+        let err = validate(ConfigFileKind::Config, &yaml("sever: {}\n"))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "sever: unknown key");
+    }
+
+    #[test]
+    fn test_wrong_type_reports_path() {
+        let err = validate(
+            ConfigFileKind::Config,
+            &yaml("server:\n  claim_limit: lots\n"),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.starts_with("server.claim_limit: "), "{}", err);
+    }
+
+    #[test]
+    fn test_empty_file_is_valid() {
+        validate(ConfigFileKind::Prompts, &Value::Null).unwrap();
+    }
+}
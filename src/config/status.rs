@@ -0,0 +1,206 @@
+//! Hot-reload status, served at the `config://status` resource.
+//!
+//! Most settings take effect when the config files change on disk. Settings
+//! fixed at startup do not: the database and directories are shared by every
+//! handler and background task, and the dashboard is already listening. A
+//! reload that changes one keeps the running value and records the edit as
+//! pending a restart, so it is reported rather than silently ignored.
+
+use super::effective::{COMMAND_LINE, EffectiveConfig};
+use super::types::Config;
+use crate::db::now_ms;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::RwLock;
+
+/// Settings read once at startup; changing them requires a restart.
+pub const RESTART_REQUIRED_KEYS: &[&str] = &[
+    "server.db_path",
+    "server.media_dir",
+    "server.skills_dir",
+    "server.log_dir",
+    "server.ui.mode",
+    "server.ui.port",
+];
+
+/// A setting whose configured value differs from the one in use.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PendingRestart {
+    pub key: String,
+    /// Value the server is running with.
+    pub running: Value,
+    /// Value in the config files, applied on the next start.
+    pub configured: Value,
+}
+
+/// Outcome of the most recent reload.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadAttempt {
+    /// When the reload ran (ms since epoch).
+    pub at: i64,
+    pub ok: bool,
+    /// Why the reload was rejected; the previous configuration stays in effect.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Snapshot of the hot-reload state.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigStatusReport {
+    /// When the server loaded its configuration (ms since epoch).
+    pub started_at: i64,
+    /// Successful reloads since startup.
+    pub reloads: u64,
+    pub last_reload: Option<ReloadAttempt>,
+    /// Edits to startup-only settings that wait for a restart.
+    pub pending_restart: Vec<PendingRestart>,
+}
+
+/// Tracks reloads against the startup-only settings the server is running with.
+#[derive(Debug)]
+pub struct ConfigStatus {
+    /// `(key, running value)` for each restart-required key not set on the
+    /// command line (command-line values win over any file edit).
+    running: Vec<(&'static str, Value)>,
+    report: RwLock<ConfigStatusReport>,
+}
+
+impl ConfigStatus {
+    /// Record the startup-only settings of the running configuration.
+    pub fn new(config: &Config, effective: &EffectiveConfig) -> Self {
+        let config = serde_json::to_value(config).unwrap_or_default();
+        let running = RESTART_REQUIRED_KEYS
+            .iter()
+            .filter(|key| {
+                !effective
+                    .values
+                    .iter()
+                    .any(|v| v.key == **key && v.source == COMMAND_LINE)
+            })
+            .map(|key| (*key, lookup(&config, key)))
+            .collect();
+        Self {
+            running,
+            report: RwLock::new(ConfigStatusReport {
+                started_at: now_ms(),
+                reloads: 0,
+                last_reload: None,
+                pending_restart: Vec::new(),
+            }),
+        }
+    }
+
+    /// Startup-only settings whose value in `reloaded` differs from the running one.
+    pub fn pending_restart(&self, reloaded: &Config) -> Vec<PendingRestart> {
+        let reloaded = serde_json::to_value(reloaded).unwrap_or_default();
+        self.running
+            .iter()
+            .filter_map(|(key, running)| {
+                let configured = lookup(&reloaded, key);
+                (configured != *running).then(|| PendingRestart {
+                    key: key.to_string(),
+                    running: running.clone(),
+                    configured,
+                })
+            })
+            .collect()
+    }
+
+    /// Record a successful reload of `reloaded`. Returns the settings pending a restart.
+    pub fn record_reload(&self, reloaded: &Config) -> Vec<PendingRestart> {
+        let pending = self.pending_restart(reloaded);
+        let mut report = self.report.write().unwrap_or_else(|e| e.into_inner());
+        report.reloads += 1;
+        report.last_reload = Some(ReloadAttempt {
+            at: now_ms(),
+            ok: true,
+            error: None,
+        });
+        report.pending_restart = pending.clone();
+        pending
+    }
+
+    /// Record a rejected reload. Pending restarts from the last good reload stand.
+    pub fn record_failure(&self, error: &str) {
+        let mut report = self.report.write().unwrap_or_else(|e| e.into_inner());
+        report.last_reload = Some(ReloadAttempt {
+            at: now_ms(),
+            ok: false,
+            error: Some(error.to_string()),
+        });
+    }
+
+    pub fn report(&self) -> ConfigStatusReport {
+        self.report
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+fn lookup(value: &Value, key: &str) -> Value {
+    key.split('.')
+        .try_fold(value, |v, k| v.get(k))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::effective::EffectiveValue;
+
+    fn effective(values: Vec<EffectiveValue>) -> EffectiveConfig {
+        EffectiveConfig {
+            profile: None,
+            sources: vec![],
+            values,
+            prompts_files: vec![],
+            workflows_files: vec![],
+            default_workflow: None,
+            workflows: vec![],
+            overlays: vec![],
+        }
+    }
+
+    #[test]
+    fn test_path_change_is_pending_restart() {
+        let running = Config::default();
+        let status = ConfigStatus::new(&running, &effective(vec![]));
+
+        // Settings that reload in place are not reported
+        let mut reloaded = running.clone();
+        reloaded.server.claim_limit += 1;
+        assert!(status.record_reload(&reloaded).is_empty());
+
+        reloaded.server.db_path = "elsewhere/tasks.db".into();
+        let pending = status.record_reload(&reloaded);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].key, "server.db_path");
+        assert_eq!(pending[0].configured, "elsewhere/tasks.db");
+
+        status.record_failure("bad yaml");
+        let report = status.report();
+        assert_eq!(report.reloads, 2);
+        assert_eq!(report.pending_restart, pending);
+        let last = report.last_reload.unwrap();
+        assert!(!last.ok);
+        assert_eq!(last.error.as_deref(), Some("bad yaml"));
+    }
+
+    #[test]
+    fn test_command_line_values_are_not_pending() {
+        let running = Config::default();
+        let status = ConfigStatus::new(
+            &running,
+            &effective(vec![EffectiveValue {
+                key: "server.db_path".into(),
+                value: Value::Null,
+                source: COMMAND_LINE.into(),
+            }]),
+        );
+        let mut reloaded = running.clone();
+        reloaded.server.db_path = "elsewhere/tasks.db".into();
+        assert!(status.pending_restart(&reloaded).is_empty());
+    }
+}
@@ -4,10 +4,13 @@
 
 use crate::config::workflows::WorkflowsConfig;
 use crate::format::OutputFormat;
+use crate::types::{PRIORITY_DEFAULT, Priority, TaskScope};
 use anyhow::{Result, anyhow};
 use heck::{ToKebabCase, ToLowerCamelCase, ToSnakeCase, ToTitleCase, ToUpperCamelCase};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -18,7 +21,7 @@ pub const DEFAULT_UI_PORT: u16 = 31994;
 pub const DEFAULT_ID_WORDS: u8 = 2;
 
 /// Case style for generated IDs.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum IdCase {
     /// kebab-case (default): happy-turtle-swift-fox
@@ -38,8 +41,27 @@ pub enum IdCase {
     TitleCase,
 }
 
+/// Scheme used to generate task IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IdScheme {
+    /// Random petnames (default): gentle-turtle
+    #[default]
+    Petname,
+    /// Monotonic numbered IDs: T-0142
+    Sequential,
+    /// Lexicographically sortable ULIDs: 01JA2Z8K3M4N5P6Q7R8S9T0V1W
+    Ulid,
+}
+
+/// Default prefix for sequential task IDs.
+pub const DEFAULT_SEQUENCE_PREFIX: &str = "T";
+
+/// Default zero-padded width of sequential task ID numbers.
+pub const DEFAULT_SEQUENCE_DIGITS: u8 = 4;
+
 /// ID generation configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IdsConfig {
     /// Number of words for generated task IDs (default: 2).
     #[serde(default = "default_id_words")]
@@ -56,12 +78,29 @@ pub struct IdsConfig {
     /// Case style for generated agent IDs (default: PascalCase).
     #[serde(default = "default_agent_id_case")]
     pub agent_id_case: IdCase,
+
+    /// Scheme for generated task IDs (default: petname).
+    #[serde(default)]
+    pub task_id_scheme: IdScheme,
+
+    /// Project prefix prepended to generated task IDs (e.g., "auth" gives
+    /// "auth-gentle-turtle"). Sequential IDs use "T" when unset.
+    #[serde(default)]
+    pub task_id_prefix: Option<String>,
+
+    /// Zero-padded width of sequential ID numbers (default: 4, giving T-0142).
+    #[serde(default = "default_sequence_digits")]
+    pub sequence_digits: u8,
 }
 
 fn default_id_words() -> u8 {
     DEFAULT_ID_WORDS
 }
 
+fn default_sequence_digits() -> u8 {
+    DEFAULT_SEQUENCE_DIGITS
+}
+
 fn default_agent_id_case() -> IdCase {
     IdCase::PascalCase
 }
@@ -73,10 +112,22 @@ impl Default for IdsConfig {
             agent_id_words: DEFAULT_ID_WORDS,
             id_case: IdCase::default(),
             agent_id_case: default_agent_id_case(),
+            task_id_scheme: IdScheme::default(),
+            task_id_prefix: None,
+            sequence_digits: DEFAULT_SEQUENCE_DIGITS,
         }
     }
 }
 
+impl IdsConfig {
+    /// Prefix used for sequential task IDs.
+    pub fn sequence_prefix(&self) -> &str {
+        self.task_id_prefix
+            .as_deref()
+            .unwrap_or(DEFAULT_SEQUENCE_PREFIX)
+    }
+}
+
 impl IdCase {
     /// Convert a kebab-case string to the target case style.
     /// Input is expected to be lowercase words separated by hyphens.
@@ -105,7 +156,7 @@ impl IdCase {
 }
 
 /// UI mode for the server.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum UiMode {
     /// No UI, MCP server only (default)
@@ -115,8 +166,104 @@ pub enum UiMode {
     Web,
 }
 
+/// Filesystem watcher that flags files modified without an advisory mark.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MarkWatchConfig {
+    /// Enable the watcher (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Workspace root to watch (default: the current directory).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
+
+    /// Path components to ignore anywhere under the root (e.g., `.git`, `target`).
+    #[serde(default = "default_mark_watch_ignore")]
+    pub ignore: Vec<String>,
+
+    /// Debounce duration in milliseconds for coalescing rapid changes (default: 500).
+    #[serde(default = "default_mark_watch_debounce_ms")]
+    pub debounce_ms: u64,
+}
+
+impl Default for MarkWatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            root: None,
+            ignore: default_mark_watch_ignore(),
+            debounce_ms: default_mark_watch_debounce_ms(),
+        }
+    }
+}
+
+/// Structured JSONL log of tool calls under `log_dir`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ToolLogConfig {
+    /// Write the call log (default: true).
+    #[serde(default = "default_tool_log_enabled")]
+    pub enabled: bool,
+
+    /// Rotate once the active file would exceed this size (default: 10 MiB).
+    #[serde(default = "default_tool_log_max_file_bytes")]
+    pub max_file_bytes: u64,
+
+    /// Rotate once the active file's first record is this old; 0 disables
+    /// age-based rotation (default: 24).
+    #[serde(default = "default_tool_log_max_age_hours")]
+    pub max_age_hours: u64,
+
+    /// Number of rotated files to keep (default: 5).
+    #[serde(default = "default_tool_log_max_files")]
+    pub max_files: usize,
+}
+
+impl Default for ToolLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_file_bytes: default_tool_log_max_file_bytes(),
+            max_age_hours: default_tool_log_max_age_hours(),
+            max_files: default_tool_log_max_files(),
+        }
+    }
+}
+
+fn default_tool_log_enabled() -> bool {
+    true
+}
+
+fn default_tool_log_max_file_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_tool_log_max_age_hours() -> u64 {
+    24
+}
+
+fn default_tool_log_max_files() -> usize {
+    5
+}
+
+fn default_mark_watch_ignore() -> Vec<String> {
+    [
+        ".git",
+        "target",
+        "node_modules",
+        "task-graph",
+        ".task-graph",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+fn default_mark_watch_debounce_ms() -> u64 {
+    500
+}
+
 /// UI configuration for the web dashboard.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UiConfig {
     /// UI mode: none (MCP only) or web (enable dashboard).
     #[serde(default)]
@@ -126,6 +273,27 @@ pub struct UiConfig {
     #[serde(default = "default_ui_port")]
     pub port: u16,
 
+    /// Address the dashboard listens on (default: 127.0.0.1). Requests from
+    /// other machines need a share link and are read-only.
+    #[serde(default = "default_ui_bind")]
+    pub bind: IpAddr,
+
+    /// Owner credential. Requests carrying it (as a bearer token, or the cookie
+    /// set by opening `/owner/<token>`) get full access. Without it, requests
+    /// get full access only when `bind` is a loopback address and
+    /// `share.base_url` is unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_token: Option<String>,
+
+    /// Admin credential for snapshot import, sent in the `X-Admin-Token`
+    /// header on top of owner access. Import is disabled while unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_token: Option<String>,
+
+    /// Read-only share links.
+    #[serde(default)]
+    pub share: ShareConfig,
+
     /// Initial retry delay in milliseconds when dashboard fails to start (default: 15000).
     #[serde(default = "default_retry_initial_ms")]
     pub retry_initial_ms: u64,
@@ -148,6 +316,10 @@ impl Default for UiConfig {
         Self {
             mode: UiMode::default(),
             port: default_ui_port(),
+            bind: default_ui_bind(),
+            owner_token: None,
+            admin_token: None,
+            share: ShareConfig::default(),
             retry_initial_ms: default_retry_initial_ms(),
             retry_jitter_ms: default_retry_jitter_ms(),
             retry_max_ms: default_retry_max_ms(),
@@ -160,6 +332,51 @@ fn default_ui_port() -> u16 {
     DEFAULT_UI_PORT
 }
 
+fn default_ui_bind() -> IpAddr {
+    IpAddr::from([127, 0, 0, 1])
+}
+
+/// Read-only dashboard share links (signed, time-limited tokens).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ShareConfig {
+    /// Key share tokens are signed with. When unset a random key is made at
+    /// startup, so links stop working when the server restarts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+
+    /// Public URL share links point to (e.g., behind a tunnel); defaults to
+    /// the host the link was generated from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+
+    /// Default link lifetime in hours (default: 24).
+    #[serde(default = "default_share_ttl_hours")]
+    pub ttl_hours: u64,
+
+    /// Longest lifetime a link can be generated with, in hours (default: 720).
+    #[serde(default = "default_share_max_ttl_hours")]
+    pub max_ttl_hours: u64,
+}
+
+impl Default for ShareConfig {
+    fn default() -> Self {
+        Self {
+            secret: None,
+            base_url: None,
+            ttl_hours: default_share_ttl_hours(),
+            max_ttl_hours: default_share_max_ttl_hours(),
+        }
+    }
+}
+
+fn default_share_ttl_hours() -> u64 {
+    24
+}
+
+fn default_share_max_ttl_hours() -> u64 {
+    720 // 30 days
+}
+
 fn default_retry_initial_ms() -> u64 {
     15_000 // 15 seconds
 }
@@ -177,28 +394,694 @@ fn default_retry_multiplier() -> f64 {
 }
 
 /// Auto-advance configuration for automatically transitioning tasks when dependencies are satisfied.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct AutoAdvanceConfig {
     /// Enable auto-advance when dependencies are satisfied (default: false).
     #[serde(default)]
     pub enabled: bool,
 
-    /// Target state for auto-advanced tasks (e.g., "ready").
-    /// If None, tasks remain in their current state even when unblocked.
-    #[serde(default)]
-    pub target_state: Option<String>,
+    /// Target state for auto-advanced tasks (e.g., "ready").
+    /// If None, tasks remain in their current state even when unblocked.
+    #[serde(default)]
+    pub target_state: Option<String>,
+}
+
+/// Agent feedback configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct FeedbackConfig {
+    /// Enable agent feedback tools (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Size limits on task titles and descriptions, and description previews.
+///
+/// Writes over a limit are rejected, so a pasted log or file cannot end up in
+/// every listing that includes the task. Lengths are counted in characters.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LimitsConfig {
+    /// Maximum title length (default: none).
+    #[serde(default)]
+    pub max_title_chars: Option<usize>,
+
+    /// Maximum description length (default: none).
+    #[serde(default)]
+    pub max_description_chars: Option<usize>,
+
+    /// Descriptions in `list_tasks` are cut to this many characters unless
+    /// `full=true` is passed (default: 1000; 0 returns them whole).
+    #[serde(default = "default_description_preview_chars")]
+    pub description_preview_chars: usize,
+}
+
+fn default_description_preview_chars() -> usize {
+    1000
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_title_chars: None,
+            max_description_chars: None,
+            description_preview_chars: default_description_preview_chars(),
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// Reject a title over `max_title_chars`.
+    pub fn check_title(&self, title: &str) -> Result<()> {
+        check_length("title", title, self.max_title_chars)
+    }
+
+    /// Reject a description over `max_description_chars`.
+    pub fn check_description(&self, description: &str) -> Result<()> {
+        check_length("description", description, self.max_description_chars)
+    }
+}
+
+fn check_length(field: &str, text: &str, max: Option<usize>) -> Result<()> {
+    let len = text.chars().count();
+    match max {
+        Some(max) if len > max => Err(anyhow!(
+            "{} is {} characters; the limit is {} (limits.max_{}_chars). Move long content into an attachment.",
+            field,
+            len,
+            max,
+            field
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Which tools are exposed to agents, and under what names.
+///
+/// With `enabled` set, only the listed tools are exposed; `disabled` then
+/// removes tools from what remains. Hidden tools are left out of the tool list
+/// and calls to them fail as unknown tools. Both lists use unprefixed names.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct ToolsConfig {
+    /// Tools to expose (default: empty, meaning all tools).
+    #[serde(default)]
+    pub enabled: Vec<String>,
+
+    /// Tools to hide, e.g. `query`, `delete`, or `scan`.
+    #[serde(default)]
+    pub disabled: Vec<String>,
+
+    /// Prefix added to every exposed tool name (e.g. `tg_` exposes `tg_claim`),
+    /// to avoid collisions with other MCP servers' tools (default: none).
+    #[serde(default)]
+    pub prefix: String,
+}
+
+impl ToolsConfig {
+    /// Whether the tool called `name` is exposed.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        (self.enabled.is_empty() || self.enabled.iter().any(|t| t == name))
+            && !self.disabled.iter().any(|t| t == name)
+    }
+
+    /// The name a tool is exposed under.
+    pub fn exposed_name(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// The tool an exposed name refers to, or `None` if it lacks the prefix.
+    pub fn internal_name<'a>(&self, exposed: &'a str) -> Option<&'a str> {
+        exposed.strip_prefix(self.prefix.as_str())
+    }
+
+    /// Check that the prefix is usable in tool names and that every listed
+    /// tool exists, so a misspelled name cannot leave a tool exposed that was
+    /// meant to be hidden.
+    pub fn validate(&self, known: &[String]) -> Result<()> {
+        if !self
+            .prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(anyhow!(
+                "tools.prefix: '{}' may only contain letters, digits, '_' and '-'",
+                self.prefix
+            ));
+        }
+        for (list, names) in [("enabled", &self.enabled), ("disabled", &self.disabled)] {
+            if let Some(unknown) = names.iter().find(|name| !known.contains(name)) {
+                return Err(anyhow!("tools.{}: unknown tool '{}'", list, unknown));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Skill delivery configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SkillsConfig {
+    /// Skills appended to the `connect` response, by role or tag.
+    #[serde(default)]
+    pub on_connect: Vec<SkillDelivery>,
+
+    /// Deliver skill content (default: true). When false, only the name,
+    /// description, and URI are sent, and agents fetch content with `get_skill`.
+    #[serde(default = "default_skills_include_content")]
+    pub include_content: bool,
+}
+
+fn default_skills_include_content() -> bool {
+    true
+}
+
+impl Default for SkillsConfig {
+    fn default() -> Self {
+        Self {
+            on_connect: Vec::new(),
+            include_content: default_skills_include_content(),
+        }
+    }
+}
+
+/// Skills delivered to the agents a rule matches. A rule with neither `role`
+/// nor `tags` matches every agent.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SkillDelivery {
+    /// Workflow role the agent must have.
+    #[serde(default)]
+    pub role: Option<String>,
+
+    /// Tags of which the agent must have at least one.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Skill names to deliver.
+    pub skills: Vec<String>,
+}
+
+impl SkillsConfig {
+    /// Skills to deliver on connect to an agent with `role` and `tags`, in
+    /// rule order without duplicates.
+    pub fn skills_for(&self, role: Option<&str>, tags: &[String]) -> Vec<String> {
+        let mut skills: Vec<String> = Vec::new();
+        for rule in &self.on_connect {
+            let role_matches = rule.role.as_deref().is_none_or(|r| Some(r) == role);
+            let tags_match = rule.tags.is_empty() || rule.tags.iter().any(|t| tags.contains(t));
+            if role_matches && tags_match {
+                for skill in &rule.skills {
+                    if !skills.contains(skill) {
+                        skills.push(skill.clone());
+                    }
+                }
+            }
+        }
+        skills
+    }
+}
+
+/// Agent group (team) configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct GroupsConfig {
+    /// Preconfigured groups; listed members join automatically on connect.
+    #[serde(default)]
+    pub definitions: HashMap<String, GroupDefinition>,
+}
+
+/// Definition of a preconfigured agent group.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct GroupDefinition {
+    /// Human-readable description of the group.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Worker IDs that join this group when they connect.
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+impl GroupsConfig {
+    /// Names of the configured groups that list this worker as a member, sorted.
+    pub fn groups_for(&self, worker_id: &str) -> Vec<String> {
+        let mut groups: Vec<String> = self
+            .definitions
+            .iter()
+            .filter(|(_, def)| def.members.iter().any(|m| m == worker_id))
+            .map(|(name, _)| name.clone())
+            .collect();
+        groups.sort();
+        groups
+    }
+
+    /// All configured group names, sorted.
+    pub fn group_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.definitions.keys().map(|s| s.as_str()).collect();
+        names.sort();
+        names
+    }
+}
+
+/// What happens to a stale worker's claimed tasks when it is evicted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StaleAction {
+    /// Release the tasks back to the queue (`status`, default `states.disconnect_state`).
+    #[default]
+    Requeue,
+    /// Release the tasks as failed (`status`, default `failed`).
+    Fail,
+    /// Requeue the tasks and attach a note to `coordinator_task` listing them.
+    Escalate,
+    /// Assign the tasks to the least-loaded live worker in `role`; tasks no such
+    /// worker can take are requeued.
+    Reassign,
+}
+
+impl StaleAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StaleAction::Requeue => "requeue",
+            StaleAction::Fail => "fail",
+            StaleAction::Escalate => "escalate",
+            StaleAction::Reassign => "reassign",
+        }
+    }
+}
+
+/// Policy applied to the claims of an evicted stale worker.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct StalePolicy {
+    #[serde(default)]
+    pub action: StaleAction,
+    /// Status for released tasks; must be an untimed state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Task that receives escalation notes (`escalate`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coordinator_task: Option<String>,
+    /// Workflow role whose workers take over the tasks (`reassign`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+}
+
+impl StalePolicy {
+    /// Status applied to tasks this policy releases rather than reassigns.
+    pub fn release_status<'a>(&'a self, states: &'a StatesConfig) -> &'a str {
+        match (&self.status, self.action) {
+            (Some(status), _) => status,
+            (None, StaleAction::Fail) => "failed",
+            (None, _) => &states.disconnect_state,
+        }
+    }
+}
+
+/// Stale-claim policies for `cleanup_stale` and automatic stale-worker eviction.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct StaleClaimsConfig {
+    /// Policy for workers whose workflow has no entry in `workflows`.
+    #[serde(default)]
+    pub default: StalePolicy,
+    /// Policies by workflow name (the workflow a worker chose on connect).
+    #[serde(default)]
+    pub workflows: HashMap<String, StalePolicy>,
+}
+
+impl StaleClaimsConfig {
+    /// Policy for a worker using the given workflow (`None` = default workflow).
+    pub fn policy_for(&self, workflow: Option<&str>) -> &StalePolicy {
+        workflow
+            .and_then(|w| self.workflows.get(w))
+            .unwrap_or(&self.default)
+    }
+
+    /// Check that every policy has what its action needs and releases to an untimed state.
+    pub fn validate(&self, states: &StatesConfig) -> Result<()> {
+        let policies = std::iter::once(("default", &self.default))
+            .chain(self.workflows.iter().map(|(n, p)| (n.as_str(), p)));
+        for (name, policy) in policies {
+            let status = policy.release_status(states);
+            if !states.is_valid_state(status) || states.is_timed_state(status) {
+                return Err(anyhow!(
+                    "stale_claims.{}: status '{}' must be an untimed state",
+                    name,
+                    status
+                ));
+            }
+            match policy.action {
+                StaleAction::Escalate if policy.coordinator_task.is_none() => {
+                    return Err(anyhow!(
+                        "stale_claims.{}: escalate requires coordinator_task",
+                        name
+                    ));
+                }
+                StaleAction::Reassign if policy.role.is_none() => {
+                    return Err(anyhow!("stale_claims.{}: reassign requires role", name));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Claim selection configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct ClaimingConfig {
+    #[serde(default)]
+    pub fairness: FairnessConfig,
+}
+
+/// Fair-share scheduling: agents that have recently claimed more than their share
+/// of high-priority tasks see those tasks ranked lower when choosing what to claim.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FairnessConfig {
+    /// Enable fair-share ranking (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+    /// How far back claims are counted (default: 3600 seconds).
+    #[serde(default = "default_fairness_window")]
+    pub window_seconds: i64,
+    /// Lowest priority that counts as high-priority work (default: above `priorities.default`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high_priority: Option<Priority>,
+    /// Largest fraction of a high-priority task's priority score a hogging agent loses (default: 0.5).
+    #[serde(default = "default_fairness_weight")]
+    pub weight: f64,
+}
+
+fn default_fairness_window() -> i64 {
+    3600
+}
+
+fn default_fairness_weight() -> f64 {
+    0.5
+}
+
+impl Default for FairnessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_seconds: default_fairness_window(),
+            high_priority: None,
+            weight: default_fairness_weight(),
+        }
+    }
+}
+
+impl FairnessConfig {
+    /// Lowest priority treated as high-priority work.
+    pub fn high_priority_threshold(&self, priorities: &PrioritiesConfig) -> Priority {
+        self.high_priority.unwrap_or(priorities.default + 1)
+    }
+}
+
+/// Per-agent task visibility, narrowing listings to the tasks an agent works on.
+///
+/// An agent matched by any rule sees only the tasks its matching rules grant in
+/// every read path: `list_tasks`, `search`, `get`, `scan`, the exports, and the
+/// `query://visible/...` resources. Raw `query` and the unscoped `tasks://`
+/// resources are refused while rules exist. Agents that no rule matches see
+/// everything; callers that do not identify themselves see nothing.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct VisibilityConfig {
+    #[serde(default)]
+    pub rules: HashMap<String, VisibilityRule>,
+}
+
+/// Which agents a visibility rule restricts, and the tasks it lets them see.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct VisibilityRule {
+    /// Human-readable description of the rule.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Worker IDs the rule applies to.
+    #[serde(default)]
+    pub agents: Vec<String>,
+    /// Agent groups whose members the rule applies to.
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// Agent tags (e.g. workflow role tags) the rule applies to; any one matches.
+    #[serde(default)]
+    pub agent_tags: Vec<String>,
+    /// Tasks carrying any of these tags are visible.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Tasks in these subtrees (the root and everything it contains) are visible.
+    #[serde(default)]
+    pub subtrees: Vec<String>,
+}
+
+impl VisibilityRule {
+    /// Whether the rule applies to an agent with these groups and tags.
+    pub fn applies_to(&self, agent_id: &str, groups: &[String], agent_tags: &[String]) -> bool {
+        self.agents.iter().any(|a| a == agent_id)
+            || self.groups.iter().any(|g| groups.contains(g))
+            || self.agent_tags.iter().any(|t| agent_tags.contains(t))
+    }
+}
+
+impl VisibilityConfig {
+    /// Combined scope of the rules that apply to an agent, or None when no rule
+    /// does and the agent is unrestricted.
+    pub fn scope_for(
+        &self,
+        agent_id: &str,
+        groups: &[String],
+        agent_tags: &[String],
+    ) -> Option<TaskScope> {
+        let mut names: Vec<&String> = self
+            .rules
+            .iter()
+            .filter(|(_, rule)| rule.applies_to(agent_id, groups, agent_tags))
+            .map(|(name, _)| name)
+            .collect();
+        if names.is_empty() {
+            return None;
+        }
+        names.sort();
+
+        let mut scope = TaskScope::default();
+        for name in names {
+            let rule = &self.rules[name];
+            scope.rules.push(name.clone());
+            scope.tags.extend(rule.tags.iter().cloned());
+            scope.subtrees.extend(rule.subtrees.iter().cloned());
+        }
+        scope.tags.sort();
+        scope.tags.dedup();
+        scope.subtrees.sort();
+        scope.subtrees.dedup();
+        Some(scope)
+    }
+
+    /// Check that every rule names the agents it applies to.
+    pub fn validate(&self) -> Result<()> {
+        for (name, rule) in &self.rules {
+            if rule.agents.is_empty() && rule.groups.is_empty() && rule.agent_tags.is_empty() {
+                return Err(anyhow!(
+                    "visibility.rules.{}: needs agents, groups, or agent_tags",
+                    name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scanning source files for TODO-style comments with `scan_source`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SourceScanConfig {
+    /// Directory that `dirs` and reported file paths are relative to
+    /// (default: the current directory).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
+
+    /// Directories to scan (default: the whole root).
+    #[serde(default = "default_source_scan_dirs")]
+    pub dirs: Vec<PathBuf>,
+
+    /// Comment markers that turn into tasks (default: TODO, FIXME, HACK).
+    #[serde(default = "default_source_scan_markers")]
+    pub markers: Vec<String>,
+
+    /// File extensions to scan, without the dot; empty scans every text file.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+
+    /// Path components to skip anywhere under the root.
+    #[serde(default = "default_mark_watch_ignore")]
+    pub ignore: Vec<String>,
+
+    /// Tag put on every task the scanner creates (default: `from-code`).
+    #[serde(default = "default_source_scan_tag")]
+    pub tag: String,
+
+    /// Task that new tasks are created under (default: none, top level).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    /// Files larger than this are skipped (default: 1 MiB).
+    #[serde(default = "default_source_scan_max_file_bytes")]
+    pub max_file_bytes: u64,
+
+    /// Rescan files as they change and resolve tasks whose TODO is removed
+    /// (default: false).
+    #[serde(default)]
+    pub watch: bool,
+
+    /// Debounce duration in milliseconds for the watcher (default: 500).
+    #[serde(default = "default_mark_watch_debounce_ms")]
+    pub debounce_ms: u64,
+}
+
+impl Default for SourceScanConfig {
+    fn default() -> Self {
+        Self {
+            root: None,
+            dirs: default_source_scan_dirs(),
+            markers: default_source_scan_markers(),
+            extensions: Vec::new(),
+            ignore: default_mark_watch_ignore(),
+            tag: default_source_scan_tag(),
+            parent: None,
+            max_file_bytes: default_source_scan_max_file_bytes(),
+            watch: false,
+            debounce_ms: default_mark_watch_debounce_ms(),
+        }
+    }
+}
+
+impl SourceScanConfig {
+    /// Check that there is something to look for.
+    pub fn validate(&self) -> Result<()> {
+        if self.markers.iter().all(|m| m.trim().is_empty()) {
+            return Err(anyhow!("source_scan.markers: needs at least one marker"));
+        }
+        if self.tag.trim().is_empty() {
+            return Err(anyhow!("source_scan.tag: must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+/// Reminders before the `due_at` deadlines of claimed tasks, and tagging of
+/// overdue tasks.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DeadlineRemindersConfig {
+    /// Run the reminder scheduler (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Seconds between deadline checks (default: 60).
+    #[serde(default = "default_deadline_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Minutes before `due_at` at which a reminder fires; 0 fires at the
+    /// deadline (default: 1440, 60, 0).
+    #[serde(default = "default_deadline_offsets_minutes")]
+    pub offsets_minutes: Vec<u64>,
+
+    /// Tag put on unfinished tasks past their deadline and taken off once they
+    /// finish or the deadline moves out; null disables tagging (default: `overdue`).
+    #[serde(default = "default_overdue_tag")]
+    pub overdue_tag: Option<String>,
+
+    /// URLs each reminder is POSTed to as JSON.
+    #[serde(default)]
+    pub webhooks: Vec<String>,
+
+    /// Timeout of a webhook request in milliseconds (default: 5000).
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub webhook_timeout_ms: u64,
+}
+
+impl Default for DeadlineRemindersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_deadline_interval_secs(),
+            offsets_minutes: default_deadline_offsets_minutes(),
+            overdue_tag: default_overdue_tag(),
+            webhooks: Vec::new(),
+            webhook_timeout_ms: default_webhook_timeout_ms(),
+        }
+    }
+}
+
+impl DeadlineRemindersConfig {
+    /// Check the interval, tag, and webhook URLs.
+    pub fn validate(&self) -> Result<()> {
+        if self.interval_secs == 0 {
+            return Err(anyhow!(
+                "deadline_reminders.interval_secs: must be positive"
+            ));
+        }
+        if self
+            .overdue_tag
+            .as_deref()
+            .is_some_and(|tag| tag.trim().is_empty())
+        {
+            return Err(anyhow!(
+                "deadline_reminders.overdue_tag: must not be empty (use null to disable)"
+            ));
+        }
+        for url in &self.webhooks {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(anyhow!(
+                    "deadline_reminders.webhooks: '{}' is not an http(s) URL",
+                    url
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reminder offsets in milliseconds, largest first, without duplicates.
+    pub fn offsets_ms(&self) -> Vec<i64> {
+        let mut offsets: Vec<i64> = self
+            .offsets_minutes
+            .iter()
+            .map(|minutes| *minutes as i64 * 60_000)
+            .collect();
+        offsets.sort_unstable_by(|a, b| b.cmp(a));
+        offsets.dedup();
+        offsets
+    }
+}
+
+fn default_deadline_interval_secs() -> u64 {
+    60
+}
+
+fn default_deadline_offsets_minutes() -> Vec<u64> {
+    vec![1440, 60, 0]
+}
+
+fn default_overdue_tag() -> Option<String> {
+    Some("overdue".to_string())
+}
+
+fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_source_scan_dirs() -> Vec<PathBuf> {
+    vec![PathBuf::from(".")]
+}
+
+fn default_source_scan_markers() -> Vec<String> {
+    ["TODO", "FIXME", "HACK"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
 }
 
-/// Agent feedback configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct FeedbackConfig {
-    /// Enable agent feedback tools (default: false).
-    #[serde(default)]
-    pub enabled: bool,
+fn default_source_scan_tag() -> String {
+    "from-code".to_string()
+}
+
+fn default_source_scan_max_file_bytes() -> u64 {
+    1024 * 1024
 }
 
 /// Behavior for unknown attachment keys.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum UnknownKeyBehavior {
     /// Silently use default mime/mode.
@@ -211,7 +1094,7 @@ pub enum UnknownKeyBehavior {
 }
 
 /// Enforcement level for workflow gates (checklists that must be satisfied before status transitions).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum GateEnforcement {
     /// Advisory only, never blocks transitions. Unsatisfied gates are reported but do not prevent status changes.
@@ -226,10 +1109,13 @@ pub enum GateEnforcement {
 /// Definition of a gate (checklist item) for status or phase exits.
 ///
 /// Gates are checked when transitioning out of a status or phase. A gate is satisfied
-/// when the task has an attachment with a matching type (e.g., "gate/tests", "gate/commit").
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// when the task has an attachment with a matching type (e.g., "gate/tests", "gate/commit"),
+/// or, for a gate with `sql`, when its predicate holds, or, for a gate with `command`,
+/// when the command exits 0.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GateDefinition {
     /// Attachment type that satisfies this gate (e.g., "gate/tests", "gate/commit").
+    /// Names the gate when it has `sql` or `command`.
     #[serde(rename = "type")]
     pub gate_type: String,
 
@@ -240,16 +1126,44 @@ pub struct GateDefinition {
     /// Human-readable description of what this gate requires.
     #[serde(default)]
     pub description: String,
+
+    /// Read-only SQL predicate evaluated instead of looking for an attachment.
+    /// `:task_id` is bound to the task; the gate is satisfied when the first
+    /// column of the first row is non-zero (no row or NULL fails).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sql: Option<String>,
+
+    /// Shell command run instead of looking for an attachment; the gate is
+    /// satisfied when it exits 0. It runs on transitions; its output is
+    /// attached to the task under the gate's `type`, replacing the previous
+    /// run's, within the attachment quotas.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    /// Seconds `command` may run before it is killed and the gate fails
+    /// (default: 300).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
 }
 
 /// Definition of a preconfigured attachment key.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AttachmentKeyDefinition {
     /// Default MIME type for this key.
     pub mime: String,
-    /// Default mode: "append" or "replace".
+    /// Default mode: "append", "replace", or "version".
     #[serde(default = "default_append_mode")]
     pub mode: String,
+    /// Versions kept per attachment name when adding with mode "version"
+    /// (overrides `attachments.max_versions`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_versions: Option<usize>,
+    /// Retention: keep only the newest N attachments of this type per task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_last: Option<usize>,
+    /// Retention: attachments of this type older than this many seconds are deleted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_seconds: Option<i64>,
 }
 
 fn default_append_mode() -> String {
@@ -257,7 +1171,7 @@ fn default_append_mode() -> String {
 }
 
 /// Attachments configuration with preconfigured key definitions.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AttachmentsConfig {
     /// Behavior for unknown attachment keys (allow, warn, reject).
     #[serde(default)]
@@ -265,6 +1179,19 @@ pub struct AttachmentsConfig {
     /// Preconfigured attachment key definitions.
     #[serde(default = "AttachmentsConfig::default_definitions")]
     pub definitions: HashMap<String, AttachmentKeyDefinition>,
+    /// Where content-addressed blobs are stored (default: the local media dir).
+    #[serde(default)]
+    pub storage: AttachmentStorageConfig,
+    /// Versions kept per attachment name when adding with mode "version";
+    /// older versions are deleted (default: unlimited).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_versions: Option<usize>,
+    /// Quota on the total attachment bytes of a single task (default: unlimited).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_task_bytes: Option<i64>,
+    /// Quota on the total attachment bytes of the whole project (default: unlimited).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_project_bytes: Option<i64>,
 }
 
 impl Default for AttachmentsConfig {
@@ -272,10 +1199,67 @@ impl Default for AttachmentsConfig {
         Self {
             unknown_key: UnknownKeyBehavior::default(),
             definitions: Self::default_definitions(),
+            storage: AttachmentStorageConfig::default(),
+            max_versions: None,
+            max_task_bytes: None,
+            max_project_bytes: None,
         }
     }
 }
 
+/// Backend for content-addressed attachment blobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// `blobs/` under the server's media directory (default).
+    #[default]
+    Local,
+    /// An S3-compatible object store (AWS S3, MinIO, R2, ...).
+    S3,
+}
+
+/// Attachment blob storage configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct AttachmentStorageConfig {
+    /// Storage backend (default: local).
+    #[serde(default)]
+    pub backend: StorageBackend,
+    /// Settings for the `s3` backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s3: Option<S3StorageConfig>,
+}
+
+/// Settings for an S3-compatible blob store.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct S3StorageConfig {
+    /// Bucket name.
+    pub bucket: String,
+    /// Region used for request signing (default: us-east-1).
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    /// Endpoint URL for non-AWS services, e.g. `http://localhost:9000` (default: AWS).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Key prefix for blobs, e.g. `task-graph/`. Required, and must not be
+    /// shared with another server.
+    #[serde(default)]
+    pub prefix: String,
+    /// Address the bucket in the path instead of the host name (needed by most
+    /// self-hosted services; default: false).
+    #[serde(default)]
+    pub path_style: bool,
+    /// Access key ID (default: `AWS_ACCESS_KEY_ID`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_key_id: Option<String>,
+    /// Secret access key (default: `AWS_SECRET_ACCESS_KEY`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_access_key: Option<String>,
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
 impl AttachmentsConfig {
     /// Default attachment key definitions.
     pub fn default_definitions() -> HashMap<String, AttachmentKeyDefinition> {
@@ -286,6 +1270,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/git.hash".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -294,6 +1281,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/p4.changelist".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -302,6 +1292,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "application/json".to_string(),
                 mode: "replace".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -310,6 +1303,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/plain".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -318,6 +1314,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/plain".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -326,6 +1325,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/plain".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -334,6 +1336,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/plain".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -342,6 +1347,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/x-diff".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -350,6 +1358,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/plain".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -358,6 +1369,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/markdown".to_string(),
                 mode: "replace".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -366,6 +1380,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "application/json".to_string(),
                 mode: "replace".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -374,6 +1391,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/plain".to_string(),
                 mode: "replace".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -383,6 +1403,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/plain".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -391,6 +1414,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/plain".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -399,6 +1425,9 @@ impl AttachmentsConfig {
             AttachmentKeyDefinition {
                 mime: "text/plain".to_string(),
                 mode: "append".to_string(),
+                max_versions: None,
+                keep_last: None,
+                max_age_seconds: None,
             },
         );
 
@@ -430,10 +1459,27 @@ impl AttachmentsConfig {
             .map(|d| d.mode.as_str())
             .unwrap_or("append")
     }
+
+    /// Get the retention policy for a key as (keep_last, max_age_seconds).
+    /// Returns `None` if the key has no retention policy.
+    pub fn get_retention(&self, key: &str) -> Option<(Option<usize>, Option<i64>)> {
+        self.definitions
+            .get(key)
+            .map(|d| (d.keep_last, d.max_age_seconds))
+            .filter(|(keep_last, max_age)| keep_last.is_some() || max_age.is_some())
+    }
+
+    /// Get the version retention limit for a key (per-key setting, then global).
+    pub fn get_max_versions(&self, key: &str) -> Option<usize> {
+        self.definitions
+            .get(key)
+            .and_then(|d| d.max_versions)
+            .or(self.max_versions)
+    }
 }
 
 /// Definition of a preconfigured tag.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct TagDefinition {
     /// Category for grouping (e.g., "language", "domain", "type").
     #[serde(default)]
@@ -441,23 +1487,112 @@ pub struct TagDefinition {
     /// Human-readable description.
     #[serde(default)]
     pub description: Option<String>,
+    /// Accept any tag under this one as known, e.g. `lang/go` when `lang`
+    /// is defined (default: false).
+    #[serde(default)]
+    pub children: bool,
+    /// Weight of this tag in claim match scoring when a task needs or wants
+    /// it (default: 1.0). Applies to child tags without their own weight.
+    #[serde(default)]
+    pub weight: Option<f64>,
 }
 
 /// Tags configuration with preconfigured tag definitions.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+///
+/// Tags form a hierarchy through `/`: `lang/rust` and `lang/python` are
+/// children of `lang`. Filtering by a parent tag matches its children, and an
+/// agent with a child tag meets a task's need for the parent.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct TagsConfig {
     /// Behavior for unknown tags (allow, warn, reject).
     #[serde(default)]
     pub unknown_tag: UnknownKeyBehavior,
+    /// Fold tags to lowercase, with `_` and spaces as `-` (default: false).
+    #[serde(default)]
+    pub normalize: bool,
+    /// Alternative spellings and their canonical tag (e.g. `js: javascript`).
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
     /// Preconfigured tag definitions.
     #[serde(default)]
     pub definitions: HashMap<String, TagDefinition>,
 }
 
+/// Lowercase a tag, with `_` and whitespace as `-`.
+fn normalize_tag(tag: &str) -> String {
+    tag.trim()
+        .chars()
+        .map(|c| {
+            if c == '_' || c.is_whitespace() {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect::<String>()
+        .to_lowercase()
+}
+
 impl TagsConfig {
-    /// Check if a tag is a known/defined tag.
+    /// The canonical form of a tag: normalized if `normalize` is set, then
+    /// with aliases resolved. An alias also stands for a parent tag, so with
+    /// `js: javascript`, `js/react` becomes `javascript/react`.
+    pub fn canonical_tag(&self, tag: &str) -> String {
+        let tag = if self.normalize {
+            normalize_tag(tag)
+        } else {
+            tag.to_string()
+        };
+        let prefixes = std::iter::once(tag.as_str()).chain(crate::types::tag_ancestors(&tag));
+        for prefix in prefixes {
+            let target = self.aliases.iter().find_map(|(alias, target)| {
+                let alias = if self.normalize {
+                    normalize_tag(alias)
+                } else {
+                    alias.clone()
+                };
+                (alias == prefix).then_some(target)
+            });
+            if let Some(target) = target {
+                let target = if self.normalize {
+                    normalize_tag(target)
+                } else {
+                    target.clone()
+                };
+                return format!("{}{}", target, &tag[prefix.len()..]);
+            }
+        }
+        tag
+    }
+
+    /// Canonical forms of `tags`, without duplicates.
+    pub fn canonical_tags(&self, tags: &[String]) -> Vec<String> {
+        let mut canonical: Vec<String> = Vec::with_capacity(tags.len());
+        for tag in tags {
+            let tag = self.canonical_tag(tag);
+            if !canonical.contains(&tag) {
+                canonical.push(tag);
+            }
+        }
+        canonical
+    }
+
+    /// Check if a tag is a known/defined tag, or lies under a defined tag
+    /// that accepts children.
     pub fn is_known_tag(&self, tag: &str) -> bool {
         self.definitions.contains_key(tag)
+            || crate::types::tag_ancestors(tag)
+                .any(|parent| self.definitions.get(parent).is_some_and(|d| d.children))
+    }
+
+    /// Match-score weight of a tag: its own definition's weight, else the
+    /// nearest defined ancestor's, else 1.0.
+    pub fn weight(&self, tag: &str) -> f64 {
+        std::iter::once(tag)
+            .chain(crate::types::tag_ancestors(tag))
+            .find_map(|t| self.definitions.get(t).and_then(|d| d.weight))
+            .unwrap_or(1.0)
+            .max(0.0)
     }
 
     /// Get all defined tag names.
@@ -527,13 +1662,350 @@ impl TagsConfig {
                 .or_insert_with(|| TagDefinition {
                     category: Some("workflow-role".to_string()),
                     description: Some("Auto-registered from workflow role definition".to_string()),
+                    children: false,
+                    weight: None,
                 });
         }
     }
 }
 
+/// Value type of a custom field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomFieldType {
+    /// Free text (default), optionally constrained by `pattern`.
+    #[default]
+    String,
+    /// Whole number, optionally bounded by `min`/`max`.
+    Integer,
+    /// Any number, optionally bounded by `min`/`max`.
+    Number,
+    /// true or false.
+    Boolean,
+    /// One of the configured `values`.
+    Enum,
+}
+
+/// Definition of a user-defined task field.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CustomFieldDefinition {
+    /// Value type (default: string).
+    #[serde(rename = "type", default)]
+    pub field_type: CustomFieldType,
+    /// Human-readable description.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Allowed values for enum fields.
+    #[serde(default)]
+    pub values: Vec<String>,
+    /// Regex that string values must match.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Inclusive lower bound for integer/number fields.
+    #[serde(default)]
+    pub min: Option<f64>,
+    /// Inclusive upper bound for integer/number fields.
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl CustomFieldDefinition {
+    /// Check a value against this definition, returning a reason if it is invalid.
+    fn check(&self, value: &serde_json::Value) -> Option<String> {
+        use serde_json::Value;
+
+        match self.field_type {
+            CustomFieldType::String => {
+                let Some(s) = value.as_str() else {
+                    return Some("expected a string".to_string());
+                };
+                if let Some(ref pattern) = self.pattern {
+                    match regex_lite::Regex::new(pattern) {
+                        Ok(re) if !re.is_match(s) => {
+                            return Some(format!("must match pattern '{}'", pattern));
+                        }
+                        Err(e) => return Some(format!("invalid pattern '{}': {}", pattern, e)),
+                        _ => {}
+                    }
+                }
+                None
+            }
+            CustomFieldType::Integer | CustomFieldType::Number => {
+                let n = match value {
+                    Value::Number(n)
+                        if self.field_type == CustomFieldType::Number || n.is_i64() =>
+                    {
+                        n.as_f64()
+                    }
+                    _ => None,
+                };
+                let Some(n) = n else {
+                    return Some(if self.field_type == CustomFieldType::Integer {
+                        "expected an integer".to_string()
+                    } else {
+                        "expected a number".to_string()
+                    });
+                };
+                if self.min.is_some_and(|min| n < min) || self.max.is_some_and(|max| n > max) {
+                    return Some(format!(
+                        "must be between {} and {}",
+                        self.min.map_or("-inf".to_string(), |v| v.to_string()),
+                        self.max.map_or("inf".to_string(), |v| v.to_string())
+                    ));
+                }
+                None
+            }
+            CustomFieldType::Boolean => {
+                (!value.is_boolean()).then(|| "expected a boolean".to_string())
+            }
+            CustomFieldType::Enum => match value.as_str() {
+                Some(s) if self.values.iter().any(|v| v == s) => None,
+                _ => Some(format!("must be one of {:?}", self.values)),
+            },
+        }
+    }
+}
+
+/// Custom fields configuration with field definitions.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+pub struct CustomFieldsConfig {
+    /// Behavior for fields without a definition (allow, warn, reject).
+    #[serde(default)]
+    pub unknown_field: UnknownKeyBehavior,
+    /// Field definitions keyed by field name.
+    #[serde(default)]
+    pub definitions: HashMap<String, CustomFieldDefinition>,
+}
+
+impl CustomFieldsConfig {
+    /// Get all defined field names.
+    pub fn field_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.definitions.keys().map(|s| s.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    /// Validate a single field value, returning Ok(None) if valid, Ok(Some(warning)) for
+    /// unknown fields in warn mode, or Err for invalid values and rejected unknown fields.
+    /// A null value (which clears the field) is always valid.
+    pub fn validate_field(&self, name: &str, value: &serde_json::Value) -> Result<Option<String>> {
+        if value.is_null() {
+            return Ok(None);
+        }
+
+        if let Some(def) = self.definitions.get(name) {
+            return match def.check(value) {
+                Some(reason) => Err(anyhow!("Invalid value for field '{}': {}", name, reason)),
+                None => Ok(None),
+            };
+        }
+
+        if value.is_object() || value.is_array() {
+            return Err(anyhow!(
+                "Invalid value for field '{}': custom fields must be strings, numbers, or booleans",
+                name
+            ));
+        }
+
+        match self.unknown_field {
+            UnknownKeyBehavior::Allow => Ok(None),
+            UnknownKeyBehavior::Warn => Ok(Some(format!(
+                "Unknown field '{}'. Known fields: {:?}",
+                name,
+                self.field_names()
+            ))),
+            UnknownKeyBehavior::Reject => Err(anyhow!(
+                "Unknown field '{}'. Configure in custom_fields.definitions or set unknown_field to 'allow' or 'warn'. Known fields: {:?}",
+                name,
+                self.field_names()
+            )),
+        }
+    }
+
+    /// Validate a map of field values, collecting warnings and stopping on the first error.
+    pub fn validate_fields(
+        &self,
+        fields: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Vec<String>> {
+        let mut warnings = Vec::new();
+        for (name, value) in fields {
+            if let Some(warning) = self.validate_field(name, value)? {
+                warnings.push(warning);
+            }
+        }
+        Ok(warnings)
+    }
+}
+
+/// Priority scale configuration.
+///
+/// Priorities are stored as integers where higher means more important.
+/// Labels let organizations use their own names (e.g., P0-P4) for values on the scale.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrioritiesConfig {
+    /// Lowest valid priority (default: 0).
+    #[serde(default = "default_priority_min")]
+    pub min: Priority,
+
+    /// Highest valid priority (default: 10).
+    #[serde(default = "default_priority_max")]
+    pub max: Priority,
+
+    /// Priority given to tasks created without one (default: 5).
+    #[serde(default = "default_priority_default")]
+    pub default: Priority,
+
+    /// Named priority values (e.g., P0: 4). Labels are shown in markdown and
+    /// dashboard output and accepted as input wherever a priority is given.
+    #[serde(default)]
+    pub labels: BTreeMap<String, Priority>,
+
+    /// How far an imminent deadline (the `due_at` custom field) lifts a task toward
+    /// the top of the scale in ready ordering, from 0.0 (ignore deadlines) to 1.0
+    /// (a task due now ranks with the highest priority). Default: 0.5.
+    #[serde(default = "default_deadline_weight")]
+    pub deadline_weight: f64,
+}
+
+fn default_deadline_weight() -> f64 {
+    0.5
+}
+
+fn default_priority_min() -> Priority {
+    0
+}
+
+fn default_priority_max() -> Priority {
+    10
+}
+
+fn default_priority_default() -> Priority {
+    PRIORITY_DEFAULT
+}
+
+impl Default for PrioritiesConfig {
+    fn default() -> Self {
+        Self {
+            min: default_priority_min(),
+            max: default_priority_max(),
+            default: PRIORITY_DEFAULT,
+            labels: BTreeMap::new(),
+            deadline_weight: default_deadline_weight(),
+        }
+    }
+}
+
+impl PrioritiesConfig {
+    /// Get the label for a priority value, if one is configured.
+    pub fn label(&self, priority: Priority) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(_, value)| **value == priority)
+            .map(|(label, _)| label.as_str())
+    }
+
+    /// Render a priority for display: its label, or the number when unlabeled.
+    pub fn display(&self, priority: Priority) -> String {
+        self.label(priority)
+            .map(String::from)
+            .unwrap_or_else(|| priority.to_string())
+    }
+
+    /// Describe the scale for tool schemas and error messages.
+    pub fn describe(&self) -> String {
+        let mut desc = format!(
+            "{}-{} (higher = more important, default {})",
+            self.min,
+            self.max,
+            self.display(self.default)
+        );
+        if !self.labels.is_empty() {
+            let mut labels: Vec<(&String, &Priority)> = self.labels.iter().collect();
+            labels.sort_by_key(|(_, value)| std::cmp::Reverse(**value));
+            let labels: Vec<String> = labels
+                .into_iter()
+                .map(|(label, value)| format!("{}={}", label, value))
+                .collect();
+            desc.push_str(&format!("; labels: {}", labels.join(", ")));
+        }
+        desc
+    }
+
+    /// Parse a priority from a tool argument: an integer, a numeric string, or a
+    /// configured label (case-insensitive). Values outside the scale are rejected.
+    pub fn parse(&self, value: &serde_json::Value) -> Result<Priority> {
+        let priority = match value {
+            serde_json::Value::Number(n) => n
+                .as_i64()
+                .and_then(|n| Priority::try_from(n).ok())
+                .ok_or_else(|| anyhow!("Priority must be an integer, got {}", n))?,
+            serde_json::Value::String(s) => {
+                let s = s.trim();
+                match self
+                    .labels
+                    .iter()
+                    .find(|(label, _)| label.eq_ignore_ascii_case(s))
+                {
+                    Some((_, value)) => *value,
+                    None => s.parse().map_err(|_| {
+                        anyhow!("Unknown priority '{}'. Expected {}", s, self.describe())
+                    })?,
+                }
+            }
+            other => {
+                return Err(anyhow!(
+                    "Priority must be an integer or label, got {}",
+                    other
+                ));
+            }
+        };
+
+        if priority < self.min || priority > self.max {
+            return Err(anyhow!(
+                "Priority {} is out of range. Expected {}",
+                priority,
+                self.describe()
+            ));
+        }
+        Ok(priority)
+    }
+
+    /// Position of a priority on the scale, from 0.0 (min) to 1.0 (max).
+    pub fn position(&self, priority: Priority) -> f64 {
+        if self.max <= self.min {
+            return 0.0;
+        }
+        (priority.clamp(self.min, self.max) - self.min) as f64 / (self.max - self.min) as f64
+    }
+
+    /// Blend a scale position (0.0-1.0) with deadline proximity (0.0-1.0), lifting
+    /// the position toward 1.0 by `deadline_weight`. Without a deadline the
+    /// position is unchanged.
+    pub fn urgency(&self, position: f64, deadline_score: f64) -> f64 {
+        let weight = self.deadline_weight.clamp(0.0, 1.0);
+        position + weight * deadline_score * (1.0 - position)
+    }
+
+    /// Urgency marker for list output. Only shows for above-default priorities,
+    /// scaled to the distance between the default and the top of the scale.
+    pub fn marker(&self, priority: Priority) -> &'static str {
+        if priority <= self.default || self.max <= self.default {
+            return "";
+        }
+        let above =
+            (priority.min(self.max) - self.default) as f64 / (self.max - self.default) as f64;
+        if above >= 1.0 {
+            "!!! "
+        } else if above >= 0.6 {
+            "!! "
+        } else {
+            "! "
+        }
+    }
+}
+
 /// Server configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct Config {
     #[serde(default)]
     pub server: ServerConfig,
@@ -562,12 +2034,45 @@ pub struct Config {
     #[serde(default)]
     pub ids: IdsConfig,
 
+    #[serde(default)]
+    pub custom_fields: CustomFieldsConfig,
+
+    #[serde(default)]
+    pub priorities: PrioritiesConfig,
+
     #[serde(default)]
     pub feedback: FeedbackConfig,
+
+    #[serde(default)]
+    pub groups: GroupsConfig,
+
+    #[serde(default)]
+    pub stale_claims: StaleClaimsConfig,
+
+    #[serde(default)]
+    pub claiming: ClaimingConfig,
+
+    #[serde(default)]
+    pub visibility: VisibilityConfig,
+
+    #[serde(default)]
+    pub source_scan: SourceScanConfig,
+
+    #[serde(default)]
+    pub deadline_reminders: DeadlineRemindersConfig,
+
+    #[serde(default)]
+    pub tools: ToolsConfig,
+
+    #[serde(default)]
+    pub skills: SkillsConfig,
+
+    #[serde(default)]
+    pub limits: LimitsConfig,
 }
 
 /// Paths configured for the server, returned by connect.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ServerPaths {
     /// Path to the SQLite database file.
     pub db_path: PathBuf,
@@ -580,7 +2085,7 @@ pub struct ServerPaths {
 }
 
 /// Server-specific configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ServerConfig {
     /// Path to the SQLite database file.
     #[serde(default = "default_db_path")]
@@ -624,6 +2129,14 @@ pub struct ServerConfig {
     /// Applies when no explicit limit is provided. Default: 50. Max: 1000.
     #[serde(default = "default_page_size")]
     pub default_page_size: i32,
+
+    /// Optional filesystem watcher that records unmarked file modifications.
+    #[serde(default)]
+    pub mark_watch: MarkWatchConfig,
+
+    /// Structured per-tool call log with rotation.
+    #[serde(default)]
+    pub tool_log: ToolLogConfig,
 }
 
 impl Default for ServerConfig {
@@ -639,6 +2152,8 @@ impl Default for ServerConfig {
             ui: UiConfig::default(),
             default_workflow: None,
             default_page_size: default_page_size(),
+            mark_watch: MarkWatchConfig::default(),
+            tool_log: ToolLogConfig::default(),
         }
     }
 }
@@ -676,7 +2191,7 @@ fn default_page_size() -> i32 {
 }
 
 /// Path handling configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PathsConfig {
     /// Root directory for sandboxing (default: ".")
     #[serde(default = "default_paths_root")]
@@ -708,7 +2223,7 @@ impl Default for PathsConfig {
 }
 
 /// Path style for file locks.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 #[derive(Default)]
 pub enum PathStyle {
@@ -720,7 +2235,7 @@ pub enum PathStyle {
 }
 
 /// Task state configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StatesConfig {
     /// Default state for new tasks.
     #[serde(default = "default_initial_state")]
@@ -847,7 +2362,7 @@ fn default_state_definitions() -> HashMap<String, StateDefinition> {
 }
 
 /// Definition of a single task state.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StateDefinition {
     /// Allowed states to transition to from this state.
     #[serde(default)]
@@ -859,7 +2374,7 @@ pub struct StateDefinition {
 }
 
 /// Dependency type configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DependenciesConfig {
     /// Dependency type definitions.
     #[serde(default = "default_dependency_definitions")]
@@ -875,17 +2390,28 @@ impl Default for DependenciesConfig {
 }
 
 /// Definition of a dependency type.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DependencyDefinition {
     /// Display orientation: "horizontal" (same level) or "vertical" (parent-child).
     pub display: DependencyDisplay,
 
     /// What this dependency blocks: "start" (blocks claiming) or "completion" (blocks completing).
     pub blocks: BlockTarget,
+
+    /// How the dependency is enforced: "hard" (default), "soft" (warn only),
+    /// or "exclusive" (linked tasks must not be worked concurrently).
+    #[serde(default)]
+    pub semantics: DependencySemantics,
+
+    /// When the target is ready: "all" (default) once every edge of this type
+    /// is satisfied, or "any" once one of them is. Applies to `blocks: start`
+    /// types; `any` puts new edges in an any-of group named after the type.
+    #[serde(default)]
+    pub readiness: DependencyReadiness,
 }
 
 /// Display orientation for dependency visualization.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum DependencyDisplay {
     /// Same level dependencies (blocks, follows).
@@ -895,7 +2421,7 @@ pub enum DependencyDisplay {
 }
 
 /// What a dependency blocks.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum BlockTarget {
     /// Does not block - informational link only.
@@ -906,6 +2432,30 @@ pub enum BlockTarget {
     Completion,
 }
 
+/// How a dependency is enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencySemantics {
+    /// Unsatisfied dependencies block the target (`blocks`).
+    #[default]
+    Hard,
+    /// Unsatisfied dependencies only produce warnings when claiming.
+    Soft,
+    /// Anti-dependency: the linked tasks must not be in a timed state at the same time.
+    Exclusive,
+}
+
+/// How many of a task's incoming edges of one type must be satisfied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyReadiness {
+    /// Every edge must be satisfied.
+    #[default]
+    All,
+    /// One satisfied edge is enough (alternative paths).
+    Any,
+}
+
 fn default_dependency_definitions() -> HashMap<String, DependencyDefinition> {
     let mut defs = HashMap::new();
 
@@ -915,6 +2465,8 @@ fn default_dependency_definitions() -> HashMap<String, DependencyDefinition> {
         DependencyDefinition {
             display: DependencyDisplay::Horizontal,
             blocks: BlockTarget::Start,
+            semantics: DependencySemantics::Hard,
+            readiness: DependencyReadiness::All,
         },
     );
 
@@ -923,6 +2475,8 @@ fn default_dependency_definitions() -> HashMap<String, DependencyDefinition> {
         DependencyDefinition {
             display: DependencyDisplay::Horizontal,
             blocks: BlockTarget::Start,
+            semantics: DependencySemantics::Hard,
+            readiness: DependencyReadiness::All,
         },
     );
 
@@ -931,6 +2485,8 @@ fn default_dependency_definitions() -> HashMap<String, DependencyDefinition> {
         DependencyDefinition {
             display: DependencyDisplay::Vertical,
             blocks: BlockTarget::Completion,
+            semantics: DependencySemantics::Hard,
+            readiness: DependencyReadiness::All,
         },
     );
 
@@ -940,6 +2496,8 @@ fn default_dependency_definitions() -> HashMap<String, DependencyDefinition> {
         DependencyDefinition {
             display: DependencyDisplay::Horizontal,
             blocks: BlockTarget::None,
+            semantics: DependencySemantics::Hard,
+            readiness: DependencyReadiness::All,
         },
     );
 
@@ -948,6 +2506,8 @@ fn default_dependency_definitions() -> HashMap<String, DependencyDefinition> {
         DependencyDefinition {
             display: DependencyDisplay::Horizontal,
             blocks: BlockTarget::None,
+            semantics: DependencySemantics::Hard,
+            readiness: DependencyReadiness::All,
         },
     );
 
@@ -956,6 +2516,8 @@ fn default_dependency_definitions() -> HashMap<String, DependencyDefinition> {
         DependencyDefinition {
             display: DependencyDisplay::Horizontal,
             blocks: BlockTarget::None,
+            semantics: DependencySemantics::Hard,
+            readiness: DependencyReadiness::All,
         },
     );
 
@@ -973,20 +2535,43 @@ impl DependenciesConfig {
         self.definitions.get(dep_type)
     }
 
-    /// Get all dependency types that block start.
+    /// Get all dependency types that block start (hard semantics only).
     pub fn start_blocking_types(&self) -> Vec<&str> {
+        self.types_matching(BlockTarget::Start, DependencySemantics::Hard)
+    }
+
+    /// Get all dependency types that block completion (hard semantics only).
+    pub fn completion_blocking_types(&self) -> Vec<&str> {
+        self.types_matching(BlockTarget::Completion, DependencySemantics::Hard)
+    }
+
+    /// Get soft dependency types with `blocks: start` (warn on claim instead of blocking).
+    pub fn soft_start_types(&self) -> Vec<&str> {
+        self.types_matching(BlockTarget::Start, DependencySemantics::Soft)
+    }
+
+    /// The any-of group new edges of `dep_type` join when the link names none:
+    /// the type name for `readiness: any` types.
+    pub fn default_any_of(&self, dep_type: &str) -> Option<&str> {
+        self.definitions
+            .get_key_value(dep_type)
+            .filter(|(_, def)| def.readiness == DependencyReadiness::Any)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Get all exclusive (anti-dependency) types.
+    pub fn exclusive_types(&self) -> Vec<&str> {
         self.definitions
             .iter()
-            .filter(|(_, def)| def.blocks == BlockTarget::Start)
+            .filter(|(_, def)| def.semantics == DependencySemantics::Exclusive)
             .map(|(name, _)| name.as_str())
             .collect()
     }
 
-    /// Get all dependency types that block completion.
-    pub fn completion_blocking_types(&self) -> Vec<&str> {
+    fn types_matching(&self, blocks: BlockTarget, semantics: DependencySemantics) -> Vec<&str> {
         self.definitions
             .iter()
-            .filter(|(_, def)| def.blocks == BlockTarget::Completion)
+            .filter(|(_, def)| def.blocks == blocks && def.semantics == semantics)
             .map(|(name, _)| name.as_str())
             .collect()
     }
@@ -1014,16 +2599,30 @@ impl DependenciesConfig {
         }
 
         // Check for at least one start-blocking type (for task sequencing)
-        let has_start_blocking = self
-            .definitions
-            .values()
-            .any(|d| d.blocks == BlockTarget::Start);
-        if !has_start_blocking {
+        if self.start_blocking_types().is_empty() {
             return Err(anyhow::anyhow!(
-                "At least one dependency type with blocks: start must be defined"
+                "At least one hard dependency type with blocks: start must be defined"
             ));
         }
 
+        // Exclusive types constrain concurrency, not ordering or hierarchy
+        for (name, def) in &self.definitions {
+            if def.readiness == DependencyReadiness::Any && def.blocks != BlockTarget::Start {
+                return Err(anyhow::anyhow!(
+                    "Dependency type '{}' uses readiness: any, which requires blocks: start",
+                    name
+                ));
+            }
+            if def.semantics == DependencySemantics::Exclusive
+                && (def.blocks != BlockTarget::None || def.display != DependencyDisplay::Horizontal)
+            {
+                return Err(anyhow::anyhow!(
+                    "Exclusive dependency type '{}' must use display: horizontal and blocks: none",
+                    name
+                ));
+            }
+        }
+
         Ok(())
     }
 }
@@ -1077,6 +2676,15 @@ impl StatesConfig {
             .unwrap_or_default()
     }
 
+    /// Get all timed state names (states in which a task is being worked).
+    pub fn timed_state_names(&self) -> Vec<&str> {
+        self.definitions
+            .iter()
+            .filter(|(_, def)| def.timed)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
     /// Get all untimed state names (valid for disconnect final_state).
     pub fn untimed_state_names(&self) -> Vec<&str> {
         self.definitions
@@ -1146,7 +2754,7 @@ impl StatesConfig {
 }
 
 /// Phase configuration for categorizing type of work.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PhasesConfig {
     /// Behavior for unknown phase values (allow, warn, reject).
     #[serde(default)]
@@ -1226,10 +2834,13 @@ impl PhasesConfig {
 }
 
 impl Config {
-    /// Load configuration from file.
+    /// Load configuration from file, interpolating environment variables and
+    /// validating it against the schema.
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = serde_yaml::from_str(&content)?;
+        let path = path.as_ref();
+        let value = super::loader::read_yaml(path)?;
+        super::schema::validate_file(super::schema::ConfigFileKind::Config, path, &value)?;
+        let config: Config = serde_json::from_value(value)?;
         Ok(config)
     }
 
@@ -1304,13 +2915,13 @@ impl Config {
 }
 
 /// Tool description override.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ToolPrompt {
     pub description: String,
 }
 
 /// LLM-facing prompts configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct Prompts {
     /// Server instructions shown to the LLM.
     pub instructions: Option<String>,
@@ -1367,8 +2978,18 @@ pub struct AppConfig {
     pub attachments: Arc<AttachmentsConfig>,
     pub tags: Arc<TagsConfig>,
     pub ids: Arc<IdsConfig>,
+    pub custom_fields: Arc<CustomFieldsConfig>,
+    pub priorities: Arc<PrioritiesConfig>,
     pub workflows: Arc<WorkflowsConfig>,
     pub feedback: Arc<FeedbackConfig>,
+    pub groups: Arc<GroupsConfig>,
+    pub stale_claims: Arc<StaleClaimsConfig>,
+    pub claiming: Arc<ClaimingConfig>,
+    pub visibility: Arc<VisibilityConfig>,
+    pub source_scan: Arc<SourceScanConfig>,
+    pub tools: Arc<ToolsConfig>,
+    pub skills: Arc<SkillsConfig>,
+    pub limits: Arc<LimitsConfig>,
 }
 
 impl AppConfig {
@@ -1382,8 +3003,18 @@ impl AppConfig {
         attachments: Arc<AttachmentsConfig>,
         tags: Arc<TagsConfig>,
         ids: Arc<IdsConfig>,
+        custom_fields: Arc<CustomFieldsConfig>,
+        priorities: Arc<PrioritiesConfig>,
         workflows: Arc<WorkflowsConfig>,
         feedback: Arc<FeedbackConfig>,
+        groups: Arc<GroupsConfig>,
+        stale_claims: Arc<StaleClaimsConfig>,
+        claiming: Arc<ClaimingConfig>,
+        visibility: Arc<VisibilityConfig>,
+        source_scan: Arc<SourceScanConfig>,
+        tools: Arc<ToolsConfig>,
+        skills: Arc<SkillsConfig>,
+        limits: Arc<LimitsConfig>,
     ) -> Self {
         Self {
             states,
@@ -1393,8 +3024,18 @@ impl AppConfig {
             attachments,
             tags,
             ids,
+            custom_fields,
+            priorities,
             workflows,
             feedback,
+            groups,
+            stale_claims,
+            claiming,
+            visibility,
+            source_scan,
+            tools,
+            skills,
+            limits,
         }
     }
 }
@@ -1427,6 +3068,8 @@ mod tests {
             TagDefinition {
                 category: Some("custom".to_string()),
                 description: Some("Manually defined".to_string()),
+                children: false,
+                weight: None,
             },
         );
 
@@ -1442,6 +3085,44 @@ mod tests {
         assert_eq!(lead_def.category, Some("workflow-role".to_string()));
     }
 
+    #[test]
+    fn canonical_tags_fold_case_and_resolve_aliases() {
+        let mut tags_config = TagsConfig::default();
+        tags_config
+            .aliases
+            .insert("js".to_string(), "javascript".to_string());
+        assert_eq!(tags_config.canonical_tag("js"), "javascript");
+        assert_eq!(tags_config.canonical_tag("js/react"), "javascript/react");
+        assert_eq!(tags_config.canonical_tag("JS"), "JS");
+        assert_eq!(tags_config.canonical_tag("jsx"), "jsx");
+
+        tags_config.normalize = true;
+        assert_eq!(tags_config.canonical_tag("JS"), "javascript");
+        assert_eq!(tags_config.canonical_tag(" Code_Review "), "code-review");
+        assert_eq!(
+            tags_config.canonical_tags(&["Rust".into(), "rust".into(), "js".into()]),
+            vec!["rust", "javascript"]
+        );
+    }
+
+    #[test]
+    fn children_of_defined_tags_are_known() {
+        let mut tags_config = TagsConfig::default();
+        for (name, children) in [("lang", true), ("team", false)] {
+            tags_config.definitions.insert(
+                name.to_string(),
+                TagDefinition {
+                    children,
+                    ..Default::default()
+                },
+            );
+        }
+        assert!(tags_config.is_known_tag("lang/rust"));
+        assert!(tags_config.is_known_tag("lang/rust/async"));
+        assert!(!tags_config.is_known_tag("language"));
+        assert!(!tags_config.is_known_tag("team/core"));
+    }
+
     #[test]
     fn registered_workflow_tags_suppress_warnings() {
         let mut tags_config = TagsConfig {
@@ -1459,4 +3140,46 @@ mod tests {
         let warnings = tags_config.validate_tags(&["worker".to_string()]).unwrap();
         assert!(warnings.is_empty());
     }
+
+    fn p_scale() -> PrioritiesConfig {
+        serde_yaml::from_str(
+            "min: 0\nmax: 4\ndefault: 2\nlabels:\n  P0: 4\n  P1: 3\n  P2: 2\n  P3: 1\n  P4: 0\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn priority_scale_parses_numbers_and_labels() {
+        let scale = p_scale();
+
+        assert_eq!(scale.parse(&serde_json::json!(3)).unwrap(), 3);
+        assert_eq!(scale.parse(&serde_json::json!("1")).unwrap(), 1);
+        assert_eq!(scale.parse(&serde_json::json!("P0")).unwrap(), 4);
+        assert_eq!(scale.parse(&serde_json::json!("p4")).unwrap(), 0);
+        assert!(scale.parse(&serde_json::json!(5)).is_err());
+        assert!(scale.parse(&serde_json::json!("P9")).is_err());
+        assert!(scale.parse(&serde_json::json!(true)).is_err());
+
+        assert_eq!(scale.display(4), "P0");
+        assert_eq!(
+            scale.describe(),
+            "0-4 (higher = more important, default P2); labels: P0=4, P1=3, P2=2, P3=1, P4=0"
+        );
+    }
+
+    #[test]
+    fn priority_markers_scale_above_default() {
+        // Default scale keeps the original 0-10 markers
+        let default_scale = PrioritiesConfig::default();
+        let markers: Vec<&str> = (0..=10).map(|p| default_scale.marker(p)).collect();
+        assert_eq!(
+            markers,
+            vec!["", "", "", "", "", "", "! ", "! ", "!! ", "!! ", "!!! "]
+        );
+
+        let scale = p_scale();
+        assert_eq!(scale.marker(2), "");
+        assert_eq!(scale.marker(3), "! ");
+        assert_eq!(scale.marker(4), "!!! ");
+    }
 }
@@ -5,6 +5,7 @@
 //! - Phase definitions
 //! - Transition prompts (enter/exit for states, phases, and combos)
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -14,7 +15,7 @@ use super::types::{
 };
 
 /// Settings for workflow behavior.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WorkflowSettings {
     /// Default state for new tasks.
     #[serde(default = "default_initial_state")]
@@ -61,7 +62,7 @@ impl Default for WorkflowSettings {
 }
 
 /// Prompts for state/phase transitions.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct TransitionPrompts {
     /// Prompt shown when entering this state/phase.
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -73,7 +74,7 @@ pub struct TransitionPrompts {
 }
 
 /// Definition of a single state in the workflow.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct StateWorkflow {
     /// Allowed states to transition to from this state.
     #[serde(default)]
@@ -89,7 +90,7 @@ pub struct StateWorkflow {
 }
 
 /// Definition of a phase in the workflow.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct PhaseWorkflow {
     /// Prompts for entering/exiting this phase.
     #[serde(default)]
@@ -97,7 +98,7 @@ pub struct PhaseWorkflow {
 }
 
 /// Prompts for state+phase combinations.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct ComboPrompts {
     /// Prompt shown when entering this state+phase combination.
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -109,7 +110,7 @@ pub struct ComboPrompts {
 }
 
 /// Definition of a role in a workflow (e.g., "lead", "worker").
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct RoleDefinition {
     /// Human-readable description of this role.
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -132,8 +133,28 @@ pub struct RoleDefinition {
     pub can_create_subtasks: Option<bool>,
 }
 
+/// A prompt repeated to agents while they hold claims.
+///
+/// Delivered in the `thinking` response once the agent has held a matching
+/// claim for `every_minutes`, then again every `every_minutes` after that.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Reminder {
+    /// Identifies the reminder; an overlay entry with the same name replaces it.
+    pub name: String,
+
+    /// Minutes between deliveries.
+    pub every_minutes: u32,
+
+    /// Claim statuses the reminder applies to (empty = any claimed task).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub states: Vec<String>,
+
+    /// Prompt text; supports the same `{{...}}` variables as other prompts.
+    pub prompt: String,
+}
+
 /// Unified workflow configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WorkflowsConfig {
     /// Short identifier for the workflow (e.g., "swarm", "relay", "solo").
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -179,6 +200,15 @@ pub struct WorkflowsConfig {
     #[serde(default)]
     pub role_prompts: HashMap<String, HashMap<String, String>>,
 
+    /// Scheduled prompts delivered while agents hold claims.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reminders: Vec<Reminder>,
+
+    /// Tag rules every task created or retagged under this workflow must meet
+    /// (`component` or `component/*`; see [`crate::types::meets_required_tag`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_tags: Vec<String>,
+
     /// Cache of named workflow configs (e.g., "swarm" -> workflow-swarm.yaml).
     /// Populated at server startup, not serialized.
     #[serde(skip)]
@@ -212,6 +242,8 @@ impl Default for WorkflowsConfig {
             gates: HashMap::new(),
             roles: HashMap::new(),
             role_prompts: HashMap::new(),
+            reminders: Vec::new(),
+            required_tags: Vec::new(),
             named_workflows: HashMap::new(),
             default_workflow_key: None,
             named_overlays: HashMap::new(),
@@ -399,6 +431,21 @@ impl WorkflowsConfig {
             }
         }
 
+        // --- reminders (same name replaces) ---
+        for reminder in &overlay.reminders {
+            match self.reminders.iter_mut().find(|r| r.name == reminder.name) {
+                Some(existing) => *existing = reminder.clone(),
+                None => self.reminders.push(reminder.clone()),
+            }
+        }
+
+        // --- required tags (union) ---
+        for rule in &overlay.required_tags {
+            if !self.required_tags.contains(rule) {
+                self.required_tags.push(rule.clone());
+            }
+        }
+
         // --- settings ---
         if overlay.settings.initial_state != default_initial_state() {
             self.settings.initial_state = overlay.settings.initial_state.clone();
@@ -1136,6 +1183,9 @@ mod tests {
                 gate_type: "gate/commit".to_string(),
                 enforcement: super::super::types::GateEnforcement::Warn,
                 description: "Changes should be committed.".to_string(),
+                sql: None,
+                command: None,
+                timeout_seconds: None,
             }],
         );
 
@@ -1182,6 +1232,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_apply_overlay_replaces_reminders_by_name() {
+        let reminder = |name: &str, every_minutes: u32| Reminder {
+            name: name.to_string(),
+            every_minutes,
+            states: vec![],
+            prompt: "Update your thinking".to_string(),
+        };
+        let mut base = WorkflowsConfig {
+            reminders: vec![reminder("update", 30), reminder("metrics", 60)],
+            ..Default::default()
+        };
+        let overlay = WorkflowsConfig {
+            states: HashMap::new(),
+            phases: HashMap::new(),
+            reminders: vec![reminder("update", 10), reminder("commit", 45)],
+            ..Default::default()
+        };
+
+        base.apply_overlay(&overlay);
+        let reminders: Vec<(&str, u32)> = base
+            .reminders
+            .iter()
+            .map(|r| (r.name.as_str(), r.every_minutes))
+            .collect();
+        assert_eq!(
+            reminders,
+            vec![("update", 10), ("metrics", 60), ("commit", 45)]
+        );
+    }
+
     #[test]
     fn test_compute_overlay_diff() {
         let base = WorkflowsConfig::default();
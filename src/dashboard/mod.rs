@@ -4,7 +4,9 @@
 //! It is enabled when the `--ui=web` CLI option is passed.
 
 mod server;
+pub mod share;
 pub mod templates;
+pub mod thumbnails;
 
 pub use server::{
     DashboardHandle, DashboardServer, DashboardStatus, start_server, start_server_with_retry,
@@ -5,25 +5,46 @@
 
 use axum::{
     Router,
-    extract::{Form, Path, Query, State},
-    response::{Html, IntoResponse, Json, Redirect},
+    extract::{DefaultBodyLimit, Form, Path, Query, State},
+    http::{Method, StatusCode, header},
+    response::{Html, IntoResponse, Json, Redirect, Response},
     routing::{get, post},
 };
+use std::borrow::Cow;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{oneshot, watch};
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::info;
 
-use super::templates;
-use crate::config::{StatesConfig, UiConfig};
+use super::share::{self, AccessPolicy, ShareSigner};
+use super::templates::{self, TemplateOverrides};
+use super::thumbnails;
+use crate::blob_store::open_blob_store;
+use crate::config::{AppConfig, PrioritiesConfig, ShareConfig, StatesConfig, UiConfig};
 use crate::db::Database;
-use crate::db::dashboard::{ActivityListQuery, TaskListQuery};
+use crate::db::dashboard::{ActivityListQuery, AgentPresence, TaskListQuery};
+use crate::db::export::ExportOptions;
+use crate::db::import::{ImportMode, ImportOptions, remap_snapshot_with};
 use crate::db::now_ms;
+use crate::db::search::{SearchFilters, SearchResult};
+use crate::export::{CURRENT_SCHEMA_VERSION, Snapshot};
+use crate::subscriptions::MutationKind;
+use crate::tools::tasks::{UpdateOptions, update};
+use crate::types::Task;
+use serde_json::{Map, Value, json};
 use tracing::warn;
 
+/// Agent ID dashboard edits are made as (recorded in task history and the audit trail).
+const DASHBOARD_AGENT: &str = "dashboard";
+
+/// Heartbeat age after which an agent counts as stale: the default
+/// `stale_timeout` at which `list_agents` evicts it.
+const STALE_HEARTBEAT_MS: i64 = 300_000;
+
 /// Dashboard server state shared across handlers.
 #[derive(Clone)]
 pub struct DashboardServer {
@@ -31,17 +52,40 @@ pub struct DashboardServer {
     db: Arc<Database>,
     /// Port the server is listening on.
     port: u16,
-    /// States configuration for determining timed/untimed states.
-    states_config: Arc<StatesConfig>,
+    /// Application configuration (states, priorities, tags, workflows, attachments)
+    /// used to render and validate edits the same way the MCP tools do.
+    config: AppConfig,
+    /// Media directory (file attachments, local blobs, and cached thumbnails).
+    media_dir: PathBuf,
+    /// Share link settings (base URL and lifetimes).
+    share_config: ShareConfig,
+    /// Signs and checks read-only share tokens.
+    share: Arc<ShareSigner>,
+    /// Decides owner and share-link access.
+    access: Arc<AccessPolicy>,
+    /// Template and static asset overrides from `dashboard/` config directories.
+    templates: TemplateOverrides,
 }
 
 impl DashboardServer {
     /// Create a new dashboard server instance.
-    pub fn new(db: Arc<Database>, port: u16, states_config: Arc<StatesConfig>) -> Self {
+    pub fn new(
+        db: Arc<Database>,
+        ui: &UiConfig,
+        config: AppConfig,
+        media_dir: PathBuf,
+        templates: TemplateOverrides,
+    ) -> Self {
+        let share = Arc::new(ShareSigner::new(ui.share.secret.as_deref()));
         Self {
             db,
-            port,
-            states_config,
+            port: ui.port,
+            config,
+            media_dir,
+            share_config: ui.share.clone(),
+            access: Arc::new(AccessPolicy::new(Arc::clone(&share), ui)),
+            share,
+            templates,
         }
     }
 
@@ -57,7 +101,27 @@ impl DashboardServer {
 
     /// Get the states configuration.
     pub fn states_config(&self) -> &StatesConfig {
-        &self.states_config
+        &self.config.states
+    }
+
+    /// Get the priority scale configuration.
+    pub fn priorities_config(&self) -> &PrioritiesConfig {
+        &self.config.priorities
+    }
+
+    /// Get the application configuration.
+    pub fn config(&self) -> &AppConfig {
+        &self.config
+    }
+
+    /// Get the media directory.
+    pub fn media_dir(&self) -> &std::path::Path {
+        &self.media_dir
+    }
+
+    /// Get a page template by file name, preferring an override on disk.
+    pub fn template(&self, name: &str) -> Cow<'static, str> {
+        self.templates.template(name)
     }
 }
 
@@ -69,23 +133,53 @@ struct HealthResponse {
 }
 
 /// Root endpoint - serves the dashboard index page with htmx.
-async fn root() -> Html<&'static str> {
-    Html(templates::INDEX_TEMPLATE)
+async fn root(State(state): State<DashboardServer>) -> Html<Cow<'static, str>> {
+    Html(state.template("index.html"))
 }
 
 /// Workers page - serves the workers list page.
-async fn workers_page() -> Html<&'static str> {
-    Html(templates::WORKERS_TEMPLATE)
+async fn workers_page(State(state): State<DashboardServer>) -> Html<Cow<'static, str>> {
+    Html(state.template("workers.html"))
+}
+
+/// Worker detail page - serves the per-agent view; the overview is loaded via htmx.
+async fn worker_detail_page(
+    State(state): State<DashboardServer>,
+    Path(worker_id): Path<String>,
+) -> Html<String> {
+    Html(
+        state
+            .template("worker_detail.html")
+            .replace("{{worker_id_path}}", &urlencoding::encode(&worker_id))
+            .replace("{{worker_id}}", &html_escape(&worker_id)),
+    )
+}
+
+/// Dashboard URL of a worker's detail page.
+fn worker_url(worker_id: &str) -> String {
+    format!("/workers/{}", urlencoding::encode(worker_id))
+}
+
+/// Link to a worker's detail page.
+fn worker_link(worker_id: &str) -> String {
+    format!(
+        r#"<a href="{}" class="worker-link">{}</a>"#,
+        worker_url(worker_id),
+        html_escape(worker_id)
+    )
 }
 
 /// Stats API endpoint for htmx - returns HTML fragment.
 async fn api_stats(State(state): State<DashboardServer>) -> Html<String> {
-    // Query task counts from database
-    let (total, working, completed): (i64, i64, i64) =
-        state.db().get_task_stats().unwrap_or_default();
-
-    // Query worker count from database
-    let worker_count: i64 = state.db().get_active_worker_count().unwrap_or_default();
+    // Task and worker counts, cached between polls
+    let db = state.db();
+    let ((total, working, completed), worker_count) = db
+        .cached(
+            "dashboard://stats",
+            &[MutationKind::TaskChanged, MutationKind::AgentChanged],
+            || Ok((db.get_task_stats()?, db.get_active_worker_count()?)),
+        )
+        .unwrap_or_default();
 
     Html(format!(
         r#"
@@ -144,7 +238,7 @@ async fn api_recent_tasks(State(state): State<DashboardServer>) -> Html<String>
             html_escape(title),
             badge_class,
             task.status,
-            task.priority
+            html_escape(&state.priorities_config().display(task.priority))
         ));
     }
 
@@ -152,24 +246,125 @@ async fn api_recent_tasks(State(state): State<DashboardServer>) -> Html<String>
     Html(html)
 }
 
-/// Active workers API endpoint for htmx - returns HTML fragment.
-async fn api_active_workers(State(state): State<DashboardServer>) -> Html<String> {
-    let workers = state.db().get_active_workers().unwrap_or_default();
+/// Agent presence API endpoint for htmx - returns HTML fragment listing
+/// connected workers, with silent ones highlighted.
+async fn api_agent_presence(State(state): State<DashboardServer>) -> Html<String> {
+    let workers = state.db().get_agent_presence().unwrap_or_default();
+    Html(render_presence(&workers, now_ms()))
+}
 
+/// Render the presence table; workers whose last heartbeat is at least
+/// `STALE_HEARTBEAT_MS` before `now` get the stale highlight.
+fn render_presence(workers: &[AgentPresence], now: i64) -> String {
     if workers.is_empty() {
-        return Html(r#"<div class="empty-state">No active workers</div>"#.to_string());
+        return r#"<div class="empty-state">No connected agents</div>"#.to_string();
     }
 
-    let mut html = String::from(
-        "<table><thead><tr><th>Worker</th><th>Status</th><th>Claims</th></tr></thead><tbody>",
+    let silent = workers
+        .iter()
+        .filter(|w| now - w.last_heartbeat >= STALE_HEARTBEAT_MS)
+        .count();
+    let mut html = if silent > 0 {
+        format!(
+            r#"<div class="presence-summary"><span class="badge badge-warning">{} of {} silent for {}+</span></div>"#,
+            silent,
+            workers.len(),
+            format_duration(STALE_HEARTBEAT_MS)
+        )
+    } else {
+        String::new()
+    };
+    html.push_str(
+        "<table><thead><tr><th>Agent</th><th>Heartbeat</th><th>Claims</th><th>Thought</th></tr></thead><tbody>",
     );
 
     for worker in workers {
+        let age = now - worker.last_heartbeat;
+        let (row_class, dot_class) = if age >= STALE_HEARTBEAT_MS {
+            (r#" class="presence-stale""#, "stale")
+        } else {
+            ("", "online")
+        };
+        let (heartbeat_text, _) = format_time_ago(age.max(0));
+        let claims = if worker.claims.is_empty() {
+            "none".to_string()
+        } else {
+            worker
+                .claims
+                .iter()
+                .map(|task| {
+                    format!(
+                        r#"<a href="/tasks/{}" title="{}">{}</a>"#,
+                        urlencoding::encode(&task.id),
+                        html_escape(task.title.as_deref().unwrap_or(&task.id)),
+                        html_escape(&task.id)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
         html.push_str(&format!(
-            r#"<tr><td><div class="worker-status"><span class="status-dot online"></span>{}</div></td><td>{}</td><td>{}</td></tr>"#,
-            html_escape(&worker.id),
-            worker.current_thought.as_deref().unwrap_or("idle"),
-            worker.claim_count
+            r#"<tr{}><td><div class="worker-status"><span class="status-dot {}"></span>{}</div></td><td>{}</td><td>{}</td><td>{}</td></tr>"#,
+            row_class,
+            dot_class,
+            worker_link(&worker.id),
+            heartbeat_text,
+            claims,
+            html_escape(worker.current_thought.as_deref().unwrap_or("idle"))
+        ));
+    }
+
+    html.push_str("</tbody></table>");
+    html
+}
+
+/// Blocked tasks API endpoint for htmx - returns HTML fragment grouped by blocked reason.
+async fn api_blocked_tasks(State(state): State<DashboardServer>) -> Html<String> {
+    let db = state.db();
+    let tasks = db
+        .cached(
+            "dashboard://tasks/blocked",
+            &[MutationKind::TaskChanged],
+            || db.get_tasks_with_blocked_reason(),
+        )
+        .unwrap_or_default();
+
+    if tasks.is_empty() {
+        return Html(r#"<div class="empty-state">No blocked tasks</div>"#.to_string());
+    }
+
+    let mut html = String::from(
+        "<table><thead><tr><th>Task</th><th>Status</th><th>Note</th></tr></thead><tbody>",
+    );
+
+    // Tasks arrive ordered by reason, so emit a header row whenever it changes
+    let mut current_reason: Option<&str> = None;
+    for task in &tasks {
+        let reason = task.blocked_reason.as_deref().unwrap_or("other");
+        if current_reason != Some(reason) {
+            let count = tasks
+                .iter()
+                .filter(|t| t.blocked_reason.as_deref() == Some(reason))
+                .count();
+            html.push_str(&format!(
+                r#"<tr><td colspan="3"><span class="badge badge-warning">{}</span> {}</td></tr>"#,
+                html_escape(reason),
+                count
+            ));
+            current_reason = Some(reason);
+        }
+
+        let title = if task.title.is_empty() {
+            &task.id
+        } else {
+            &task.title
+        };
+
+        html.push_str(&format!(
+            r#"<tr><td>{}</td><td>{}</td><td>{}</td></tr>"#,
+            html_escape(title),
+            html_escape(&task.status),
+            html_escape(task.blocked_note.as_deref().unwrap_or(""))
         ));
     }
 
@@ -225,7 +420,7 @@ async fn api_workers_list(State(state): State<DashboardServer>) -> Html<String>
         let heartbeat_age = now - worker.last_heartbeat;
         let status_class = if heartbeat_age < 60_000 {
             "online"
-        } else if heartbeat_age < 300_000 {
+        } else if heartbeat_age < STALE_HEARTBEAT_MS {
             "stale"
         } else {
             "offline"
@@ -245,14 +440,18 @@ async fn api_workers_list(State(state): State<DashboardServer>) -> Html<String>
             .map(|t| format!(r#"<span class="tag">{}</span>"#, html_escape(t)))
             .collect();
 
-        // Escape worker ID for use in HTML attribute
-        let worker_id_escaped = html_escape(&worker.id);
+        // Link to the detail page; clicks on it should not toggle the row
+        let worker_id_link = format!(
+            r#"<a href="{}" class="worker-link" onclick="event.stopPropagation()">{}</a>"#,
+            worker_url(&worker.id),
+            html_escape(&worker.id)
+        );
         let worker_id_attr = worker.id.replace('"', "&quot;").replace('\'', "&#39;");
 
         html.push_str(&format!(
             r#"<tr class="expandable-row" onclick="toggleWorkerDetail('{worker_id_attr}')">
                 <td><span id="expand-icon-{worker_id_attr}" class="expand-icon">&#9654;</span></td>
-                <td><div class="worker-status"><span class="status-dot {status_class}"></span>{worker_id_escaped}</div></td>
+                <td><div class="worker-status"><span class="status-dot {status_class}"></span>{worker_id_link}</div></td>
                 <td>{tags_html}</td>
                 <td><span class="time-ago">{registered_text}</span></td>
                 <td><span class="time-ago {heartbeat_class}">{heartbeat_text}</span></td>
@@ -269,7 +468,7 @@ async fn api_workers_list(State(state): State<DashboardServer>) -> Html<String>
                 </td>
             </tr>"#,
             worker_id_attr = worker_id_attr,
-            worker_id_escaped = worker_id_escaped,
+            worker_id_link = worker_id_link,
             status_class = status_class,
             tags_html = if tags_html.is_empty() { "<span class=\"tag\">none</span>".to_string() } else { tags_html },
             registered_text = registered_text,
@@ -373,6 +572,243 @@ async fn api_worker_details(
     Html(html)
 }
 
+/// Number of completions and thoughts shown on the worker detail page.
+const WORKER_HISTORY_LIMIT: i32 = 20;
+
+/// Worker overview API endpoint for htmx - returns the worker detail page body:
+/// heartbeat status, current claims, recent completions, thought stream, and metrics.
+/// Disconnected workers still show their history.
+async fn api_worker_overview(
+    State(state): State<DashboardServer>,
+    Path(worker_id): Path<String>,
+) -> Html<String> {
+    let db = state.db();
+    let worker = db.get_worker(&worker_id).ok().flatten();
+    let groups = db.get_worker_groups(&worker_id).unwrap_or_default();
+    let claims = db.get_worker_claimed_tasks(&worker_id).unwrap_or_default();
+    let completions = db
+        .get_worker_completions(&worker_id, WORKER_HISTORY_LIMIT)
+        .unwrap_or_default();
+    let thoughts = db
+        .get_worker_thoughts(&worker_id, WORKER_HISTORY_LIMIT)
+        .unwrap_or_default();
+    let metrics = db.get_worker_metrics(&worker_id).unwrap_or_default();
+    let now = now_ms();
+
+    // Heartbeat status, using the same thresholds as the workers list
+    let (status_class, status_text, heartbeat_text) = match &worker {
+        Some(w) => {
+            let age = now - w.last_heartbeat;
+            let class = if age < 60_000 {
+                "online"
+            } else if age < 300_000 {
+                "stale"
+            } else {
+                "offline"
+            };
+            (class, class.to_string(), format_time_ago(age).0)
+        }
+        None => ("offline", "disconnected".to_string(), "-".to_string()),
+    };
+
+    let mut html = format!(
+        r#"<div class="stats-row">
+            <div class="stat-card">
+                <div class="stat-value"><div class="worker-status"><span class="status-dot {status_class}"></span>{status_text}</div></div>
+                <div class="stat-label">Heartbeat {heartbeat_text}</div>
+            </div>
+            <div class="stat-card">
+                <div class="stat-value">{claims}</div>
+                <div class="stat-label">Claims</div>
+            </div>
+            <div class="stat-card">
+                <div class="stat-value">{completed}</div>
+                <div class="stat-label">Completed</div>
+            </div>
+            <div class="stat-card">
+                <div class="stat-value">${cost:.4}</div>
+                <div class="stat-label">Cost</div>
+            </div>
+            <div class="stat-card">
+                <div class="stat-value">{time}</div>
+                <div class="stat-label">Time</div>
+            </div>
+        </div>"#,
+        status_class = status_class,
+        status_text = status_text,
+        heartbeat_text = html_escape(&heartbeat_text),
+        claims = claims.len(),
+        completed = metrics.completed_count,
+        cost = metrics.total_cost_usd,
+        time = format_duration(metrics.total_time_ms),
+    );
+
+    html.push_str(r#"<div class="grid-2">"#);
+
+    // Worker info
+    html.push_str(
+        r#"<div class="card"><h2 class="card-title">Worker</h2><ul class="detail-list">"#,
+    );
+    match &worker {
+        Some(w) => {
+            let tags: String = w
+                .tags
+                .iter()
+                .map(|t| format!(r#"<span class="tag">{}</span>"#, html_escape(t)))
+                .collect();
+            let groups: String = groups
+                .iter()
+                .map(|g| format!(r#"<span class="tag">{}</span>"#, html_escape(g)))
+                .collect();
+            let rows = [
+                ("Registered", format_time_ago(now - w.registered_at).0),
+                ("Last heartbeat", heartbeat_text.clone()),
+                (
+                    "Status",
+                    html_escape(w.last_status.as_deref().unwrap_or("-")),
+                ),
+                ("Phase", html_escape(w.last_phase.as_deref().unwrap_or("-"))),
+                (
+                    "Workflow",
+                    html_escape(w.workflow.as_deref().unwrap_or("default")),
+                ),
+                ("Tags", if tags.is_empty() { "-".to_string() } else { tags }),
+                (
+                    "Groups",
+                    if groups.is_empty() {
+                        "-".to_string()
+                    } else {
+                        groups
+                    },
+                ),
+            ];
+            for (label, value) in rows {
+                html.push_str(&format!("<li><strong>{}</strong>: {}</li>", label, value));
+            }
+        }
+        None => html.push_str(
+            r#"<li class="empty-state">Not connected. History below is from earlier sessions.</li>"#,
+        ),
+    }
+    html.push_str("</ul></div>");
+
+    // Current claims with their thoughts
+    html.push_str(r#"<div class="card"><h2 class="card-title">Current Claims</h2>"#);
+    if claims.is_empty() {
+        html.push_str(r#"<div class="empty-state">No claimed tasks</div>"#);
+    } else {
+        html.push_str(r#"<ul class="detail-list">"#);
+        for task in &claims {
+            let title = task
+                .title
+                .as_deref()
+                .filter(|t| !t.is_empty())
+                .unwrap_or(&task.id);
+            let thought = task
+                .current_thought
+                .as_deref()
+                .map(|t| format!(r#" - <span class="thought">{}</span>"#, html_escape(t)))
+                .unwrap_or_default();
+            html.push_str(&format!(
+                r#"<li><a href="/tasks/{}">{}</a>{}</li>"#,
+                urlencoding::encode(&task.id),
+                html_escape(title),
+                thought
+            ));
+        }
+        html.push_str("</ul>");
+    }
+    html.push_str("</div>");
+
+    // Recent completions
+    html.push_str(r#"<div class="card"><h2 class="card-title">Recent Completions</h2>"#);
+    if completions.is_empty() {
+        html.push_str(r#"<div class="empty-state">No completed tasks</div>"#);
+    } else {
+        html.push_str(r#"<ul class="detail-list">"#);
+        for c in &completions {
+            let title = if c.title.is_empty() {
+                &c.task_id
+            } else {
+                &c.title
+            };
+            let duration = c
+                .time_actual_ms
+                .filter(|ms| *ms > 0)
+                .map(|ms| format!(" &middot; {}", format_duration(ms)))
+                .unwrap_or_default();
+            let cost = if c.cost_usd > 0.0 {
+                format!(" &middot; ${:.4}", c.cost_usd)
+            } else {
+                String::new()
+            };
+            html.push_str(&format!(
+                r#"<li><a href="/tasks/{}">{}</a><span class="meta">{}{}{}</span></li>"#,
+                urlencoding::encode(&c.task_id),
+                html_escape(title),
+                format_time_ago(now - c.completed_at).0,
+                duration,
+                cost
+            ));
+        }
+        html.push_str("</ul>");
+    }
+    html.push_str("</div>");
+
+    // Thought stream
+    html.push_str(r#"<div class="card"><h2 class="card-title">Thought Stream</h2>"#);
+    if thoughts.is_empty() {
+        html.push_str(r#"<div class="empty-state">No thoughts recorded</div>"#);
+    } else {
+        html.push_str(r#"<ul class="detail-list">"#);
+        for t in &thoughts {
+            let task = t
+                .task_id
+                .as_deref()
+                .map(|id| {
+                    format!(
+                        r#" &middot; <a href="/tasks/{}">{}</a>"#,
+                        urlencoding::encode(id),
+                        html_escape(id)
+                    )
+                })
+                .unwrap_or_default();
+            html.push_str(&format!(
+                r#"<li><span class="thought">{}</span><span class="meta">{}{}</span></li>"#,
+                html_escape(&t.thought),
+                format_time_ago(now - t.created_at).0,
+                task
+            ));
+        }
+        html.push_str("</ul>");
+    }
+    html.push_str("</div>");
+
+    html.push_str("</div>");
+
+    // Cost and metric totals
+    html.push_str(&format!(
+        r#"<div class="card"><h2 class="card-title">Metrics</h2>
+        <p class="meta">Totals over {} tasks this worker has held or transitioned.</p>"#,
+        metrics.task_count
+    ));
+    if metrics.metrics.iter().all(|&m| m == 0) {
+        html.push_str(r#"<div class="empty-state">No custom metrics recorded</div>"#);
+    } else {
+        html.push_str(r#"<div class="stats-row">"#);
+        for (i, value) in metrics.metrics.iter().enumerate() {
+            html.push_str(&format!(
+                r#"<div class="stat-card"><div class="stat-value">{}</div><div class="stat-label">Metric {}</div></div>"#,
+                value, i
+            ));
+        }
+        html.push_str("</div>");
+    }
+    html.push_str("</div>");
+
+    Html(html)
+}
+
 /// Form data for disconnect endpoint.
 #[derive(Debug, serde::Deserialize)]
 struct DisconnectForm {
@@ -412,8 +848,7 @@ async fn api_worker_disconnect(
 
 /// Cleanup stale workers endpoint.
 async fn api_workers_cleanup(State(state): State<DashboardServer>) -> Html<String> {
-    // Default timeout: 5 minutes (300 seconds)
-    let timeout_seconds = 300;
+    let timeout_seconds = STALE_HEARTBEAT_MS / 1000;
     let final_status = "pending";
 
     match state
@@ -438,13 +873,13 @@ async fn api_workers_cleanup(State(state): State<DashboardServer>) -> Html<Strin
 }
 
 /// Tasks page - serves the tasks list page.
-async fn tasks_page() -> Html<&'static str> {
-    Html(templates::TASKS_TEMPLATE)
+async fn tasks_page(State(state): State<DashboardServer>) -> Html<Cow<'static, str>> {
+    Html(state.template("tasks.html"))
 }
 
 /// Activity page - serves the activity feed page.
-async fn activity_page() -> Html<&'static str> {
-    Html(templates::ACTIVITY_TEMPLATE)
+async fn activity_page(State(state): State<DashboardServer>) -> Html<Cow<'static, str>> {
+    Html(state.template("activity.html"))
 }
 
 /// Query parameters for activity list API.
@@ -604,10 +1039,7 @@ async fn api_activity_list(
 
         // Worker info
         let worker_html = match &event.worker_id {
-            Some(worker) => format!(
-                r#"by <a href="/workers" class="worker-link">{}</a>"#,
-                html_escape(worker)
-            ),
+            Some(worker) => format!("by {}", worker_link(worker)),
             None => String::new(),
         };
 
@@ -793,6 +1225,8 @@ async fn task_detail_page(
             .join("\n")
     };
 
+    let attachments_html = render_attachments(&task_id, &state);
+
     // Status badge class
     let status_badge = match task.status.as_str() {
         "completed" => "badge-success",
@@ -832,37 +1266,8 @@ async fn task_detail_page(
     let description = task.description.as_deref().unwrap_or("");
     let description_escaped = html_escape(description);
 
-    // Status select options
-    let status_pending = if task.status == "pending" {
-        "selected"
-    } else {
-        ""
-    };
-    let status_assigned = if task.status == "assigned" {
-        "selected"
-    } else {
-        ""
-    };
-    let status_working = if task.status == "working" {
-        "selected"
-    } else {
-        ""
-    };
-    let status_completed = if task.status == "completed" {
-        "selected"
-    } else {
-        ""
-    };
-    let status_failed = if task.status == "failed" {
-        "selected"
-    } else {
-        ""
-    };
-    let status_cancelled = if task.status == "cancelled" {
-        "selected"
-    } else {
-        ""
-    };
+    // Status dropdown: the current status and its valid transitions
+    let status_options = status_options(state.states_config(), &task.status);
 
     // Check for message from form submission
     let message = params
@@ -884,13 +1289,26 @@ async fn task_detail_page(
         .unwrap_or_default();
 
     // Load and render template
-    let template = templates::TASK_DETAIL_TEMPLATE;
+    let template = state.template("task_detail.html");
     let html = template
         .replace("{{task_id}}", &html_escape(&task.id))
         .replace("{{task_title}}", &html_escape(title_display))
+        .replace("{{task_title_raw}}", &html_escape(title))
         .replace("{{task_status}}", &task.status)
         .replace("{{status_badge}}", status_badge)
-        .replace("{{task_priority}}", &task.priority.to_string())
+        .replace(
+            "{{task_priority}}",
+            &html_escape(&state.priorities_config().display(task.priority)),
+        )
+        .replace("{{task_priority_value}}", &task.priority.to_string())
+        .replace(
+            "{{priority_min}}",
+            &state.priorities_config().min.to_string(),
+        )
+        .replace(
+            "{{priority_max}}",
+            &state.priorities_config().max.to_string(),
+        )
         .replace("{{task_owner}}", &owner_html)
         .replace("{{task_parent}}", &parent_html)
         .replace("{{task_tags}}", &tags_html)
@@ -904,24 +1322,315 @@ async fn task_detail_page(
         .replace("{{completed_at}}", &format_timestamp(task.completed_at))
         .replace("{{blocked_by}}", &blocked_by_html)
         .replace("{{blocks}}", &blocks_html)
-        .replace("{{status_pending}}", status_pending)
-        .replace("{{status_assigned}}", status_assigned)
-        .replace("{{status_working}}", status_working)
-        .replace("{{status_completed}}", status_completed)
-        .replace("{{status_failed}}", status_failed)
-        .replace("{{status_cancelled}}", status_cancelled)
+        .replace("{{attachments}}", &attachments_html)
+        .replace("{{status_options}}", &status_options)
         .replace("{{message}}", &message);
 
     Html(html)
 }
 
+/// Render the attachments card: image thumbnails linking to the full image,
+/// followed by a list of the remaining attachments.
+fn render_attachments(task_id: &str, state: &DashboardServer) -> String {
+    let attachments = state.db().get_attachments(task_id).unwrap_or_default();
+    if attachments.is_empty() {
+        return r#"<div class="empty-state">No attachments</div>"#.to_string();
+    }
+
+    let (images, others): (Vec<_>, Vec<_>) = attachments
+        .iter()
+        .partition(|a| thumbnails::is_previewable(&a.mime_type));
+
+    let mut html = String::new();
+    if !images.is_empty() {
+        html.push_str(r#"<div class="attachment-grid">"#);
+        for a in &images {
+            let base = attachment_url(task_id, &a.attachment_type, a.sequence);
+            // SVGs scale in the browser, so they are shown as-is
+            let src = if thumbnails::is_thumbnailable(&a.mime_type) {
+                format!("{}/thumbnail", base)
+            } else {
+                format!("{}/content", base)
+            };
+            let label = if a.name.is_empty() {
+                format!("{} [{}]", a.attachment_type, a.sequence)
+            } else {
+                a.name.clone()
+            };
+            html.push_str(&format!(
+                r#"<a class="attachment-thumb" href="{base}/content" target="_blank"><img src="{src}" alt="{label}" loading="lazy"><span>{label}</span></a>"#,
+                base = base,
+                src = src,
+                label = html_escape(&label),
+            ));
+        }
+        html.push_str("</div>");
+    }
+    if !others.is_empty() {
+        html.push_str(r#"<ul class="dep-list">"#);
+        for a in &others {
+            let file = a
+                .file_path
+                .as_deref()
+                .and_then(|fp| std::path::Path::new(fp).file_name())
+                .map(|f| format!(" &middot; {}", html_escape(&f.to_string_lossy())))
+                .unwrap_or_default();
+            let name = if a.name.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", html_escape(&a.name))
+            };
+            html.push_str(&format!(
+                r#"<li>{} [{}]{} <span class="attachment-meta">{}{}</span></li>"#,
+                html_escape(&a.attachment_type),
+                a.sequence,
+                name,
+                html_escape(&a.mime_type),
+                file
+            ));
+        }
+        html.push_str("</ul>");
+    }
+    html
+}
+
+/// Dashboard URL of an attachment (append `/content` or `/thumbnail`).
+fn attachment_url(task_id: &str, attachment_type: &str, sequence: i32) -> String {
+    format!(
+        "/api/attachments/{}/{}/{}",
+        urlencoding::encode(task_id),
+        urlencoding::encode(attachment_type),
+        sequence
+    )
+}
+
+/// Load an image attachment's bytes. Inline image content is stored base64-encoded.
+fn load_image_attachment(
+    state: &DashboardServer,
+    task_id: &str,
+    attachment_type: &str,
+    sequence: i32,
+) -> Result<Option<(crate::types::Attachment, Vec<u8>)>, String> {
+    use base64::Engine;
+
+    let attachment = match state
+        .db()
+        .get_attachment(task_id, attachment_type, sequence)
+    {
+        Ok(Some(a)) if thumbnails::is_previewable(&a.mime_type) => a,
+        Ok(_) => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+    let store = open_blob_store(&state.config().attachments.storage, state.media_dir())
+        .map_err(|e| e.to_string())?;
+    let bytes = match crate::tools::attachments::read_stored_content(&attachment, store.as_ref()) {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => base64::engine::general_purpose::STANDARD
+            .decode(attachment.content.trim())
+            .unwrap_or_else(|_| attachment.content.clone().into_bytes()),
+        Err(_) => return Ok(None),
+    };
+    Ok(Some((attachment, bytes)))
+}
+
+/// Serve an image attachment for full-size preview.
+async fn api_attachment_content(
+    State(state): State<DashboardServer>,
+    Path((task_id, attachment_type, sequence)): Path<(String, String, i32)>,
+) -> Response {
+    let loaded = tokio::task::spawn_blocking(move || {
+        load_image_attachment(&state, &task_id, &attachment_type, sequence)
+    })
+    .await;
+    match loaded {
+        Ok(Ok(Some((attachment, bytes)))) => (
+            [
+                (header::CONTENT_TYPE, attachment.mime_type),
+                (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+                // SVGs may carry scripts; never let them run on the dashboard origin
+                (header::CONTENT_SECURITY_POLICY, "sandbox".to_string()),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Ok(Ok(None)) => (StatusCode::NOT_FOUND, "Image attachment not found").into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Serve a cached PNG thumbnail of an image attachment, generating it on first use.
+async fn api_attachment_thumbnail(
+    State(state): State<DashboardServer>,
+    Path((task_id, attachment_type, sequence)): Path<(String, String, i32)>,
+) -> Response {
+    let result = tokio::task::spawn_blocking(move || {
+        let Some((attachment, bytes)) =
+            load_image_attachment(&state, &task_id, &attachment_type, sequence)?
+        else {
+            return Ok(None);
+        };
+        if !thumbnails::is_thumbnailable(&attachment.mime_type) {
+            return Ok(None);
+        }
+        thumbnails::get_or_create_thumbnail(
+            state.media_dir(),
+            attachment.content_hash.as_deref(),
+            &bytes,
+        )
+        .map(Some)
+        .map_err(|e| e.to_string())
+    })
+    .await;
+    match result {
+        Ok(Ok(Some(png))) => (
+            [
+                (header::CONTENT_TYPE, "image/png"),
+                (header::CACHE_CONTROL, "max-age=3600"),
+            ],
+            png,
+        )
+            .into_response(),
+        Ok(Ok(None)) => (StatusCode::NOT_FOUND, "Image attachment not found").into_response(),
+        Ok(Err(e)) => (StatusCode::UNPROCESSABLE_ENTITY, e).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 /// Form data for task updates.
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Default, serde::Deserialize)]
 struct TaskUpdateForm {
+    title: Option<String>,
     status: Option<String>,
-    priority: Option<i32>,
+    priority: Option<String>,
     tags: Option<String>,
     description: Option<String>,
+    /// Checkbox: edit a task claimed by an agent and skip warn-level gates.
+    force: Option<String>,
+}
+
+/// `update` arguments for the fields the form changed.
+fn task_form_changes(task: &Task, form: &TaskUpdateForm) -> Map<String, Value> {
+    let mut changes = Map::new();
+    if let Some(title) = form.title.as_deref().map(str::trim)
+        && !title.is_empty()
+        && title != task.title
+    {
+        changes.insert("title".to_string(), json!(title));
+    }
+    if let Some(ref status) = form.status
+        && !status.is_empty()
+        && *status != task.status
+    {
+        changes.insert("status".to_string(), json!(status));
+    }
+    if let Some(priority) = form.priority.as_deref().map(str::trim)
+        && !priority.is_empty()
+        && priority != task.priority.to_string()
+    {
+        changes.insert("priority".to_string(), json!(priority));
+    }
+    if let Some(ref tags) = form.tags {
+        let tags: Vec<&str> = tags
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .collect();
+        if tags != task.tags {
+            changes.insert("tags".to_string(), json!(tags));
+        }
+    }
+    if let Some(ref description) = form.description {
+        // Browsers submit textarea line breaks as CRLF
+        let description = description.replace("\r\n", "\n");
+        if description != task.description.as_deref().unwrap_or("") {
+            let value = if description.trim().is_empty() {
+                Value::Null
+            } else {
+                json!(description)
+            };
+            changes.insert("description".to_string(), value);
+        }
+    }
+    changes
+}
+
+/// Apply changes through the `update` tool, so dashboard edits get the same
+/// transition, ownership, tag, priority, and gate checks as MCP clients.
+///
+/// Runs on the blocking pool: command gates can run shell commands for
+/// minutes, which must not hold a runtime worker.
+async fn apply_task_update(
+    state: &DashboardServer,
+    task_id: &str,
+    mut changes: Map<String, Value>,
+    force: bool,
+) -> anyhow::Result<Value> {
+    changes.insert("worker_id".to_string(), json!(DASHBOARD_AGENT));
+    changes.insert("task".to_string(), json!(task_id));
+    if force {
+        changes.insert("force".to_string(), json!(true));
+        changes.insert("reason".to_string(), json!("Forced from the dashboard"));
+    }
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let config = state.config();
+        update(
+            UpdateOptions {
+                db: state.db(),
+                config,
+                workflows: &config.workflows,
+            },
+            Value::Object(changes),
+        )
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Update task failed: {}", e))?
+}
+
+/// Warnings from an `update` response (`tag_warnings`, `gate_warnings`, ...).
+fn update_warnings(response: &Value) -> Vec<String> {
+    let Some(map) = response.as_object() else {
+        return Vec::new();
+    };
+    map.iter()
+        .filter(|(key, _)| key.ends_with("_warning") || key.ends_with("_warnings"))
+        .flat_map(|(_, value)| match value {
+            Value::Array(items) => items.iter().map(value_text).collect(),
+            other => vec![value_text(other)],
+        })
+        .collect()
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// `<option>`s for the status dropdown: the current status and the states the
+/// workflow lets it move to. Timed states are listed but disabled, since
+/// entering one claims the task for the agent doing the work.
+fn status_options(states: &StatesConfig, current: &str) -> String {
+    let mut html = format!(
+        r#"<option value="{0}" selected>{0} (current)</option>"#,
+        html_escape(current)
+    );
+    for exit in states.get_exits(current) {
+        if states.is_timed_state(exit) {
+            html.push_str(&format!(
+                r#"<option value="{0}" disabled title="Agents enter this state by claiming the task">{0} (claimed by agents)</option>"#,
+                html_escape(exit)
+            ));
+        } else {
+            html.push_str(&format!(
+                r#"<option value="{0}">{0}</option>"#,
+                html_escape(exit)
+            ));
+        }
+    }
+    html
 }
 
 /// Handle task update form submission.
@@ -930,25 +1639,48 @@ async fn task_update_handler(
     Path(task_id): Path<String>,
     Form(form): Form<TaskUpdateForm>,
 ) -> impl IntoResponse {
-    // Parse tags from comma-separated string
-    let new_tags: Option<Vec<String>> = form.tags.as_ref().map(|t| {
-        t.split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
-    });
+    let task = match state.db().get_task(&task_id) {
+        Ok(Some(task)) => task,
+        Ok(None) => {
+            return Html(
+                r#"<div class="message message-error">Failed to update task: Task not found</div>"#
+                    .to_string(),
+            );
+        }
+        Err(e) => {
+            return Html(format!(
+                r#"<div class="message message-error">Failed to update task: {}</div>"#,
+                html_escape(&e.to_string())
+            ));
+        }
+    };
 
-    // Use dashboard-specific update method
-    match state.db().dashboard_update_task(
-        &task_id,
-        form.status.as_deref(),
-        form.priority,
-        form.description.as_deref(),
-        new_tags,
-    ) {
-        Ok(()) => Html(
-            r#"<div class="message message-success">Task updated successfully</div>"#.to_string(),
-        ),
+    let changes = task_form_changes(&task, &form);
+    if changes.is_empty() {
+        return Html(
+            r#"<div class="message message-success">No changes to save</div>"#.to_string(),
+        );
+    }
+    let force = form.force.is_some();
+
+    match apply_task_update(&state, &task_id, changes, force).await {
+        Ok(response) => {
+            let warnings = update_warnings(&response);
+            let mut html =
+                r#"<div class="message message-success">Task updated successfully</div>"#
+                    .to_string();
+            if !warnings.is_empty() {
+                html.push_str(&format!(
+                    r#"<div class="message message-warning">{}</div>"#,
+                    warnings
+                        .iter()
+                        .map(|w| html_escape(w))
+                        .collect::<Vec<_>>()
+                        .join("<br>")
+                ));
+            }
+            Html(html)
+        }
         Err(e) => Html(format!(
             r#"<div class="message message-error">Failed to update task: {}</div>"#,
             html_escape(&e.to_string())
@@ -1094,9 +1826,10 @@ async fn api_tasks_list(
             _ => "badge-warning",
         };
 
-        let priority_class = if task.priority >= 8 {
+        let position = state.priorities_config().position(task.priority);
+        let priority_class = if position >= 0.8 {
             "priority-high"
-        } else if task.priority >= 4 {
+        } else if position >= 0.4 {
             "priority-normal"
         } else {
             "priority-low"
@@ -1154,7 +1887,7 @@ async fn api_tasks_list(
             badge_class = badge_class,
             status = task.status,
             priority_class = priority_class,
-            priority = task.priority,
+            priority = html_escape(&state.priorities_config().display(task.priority)),
             tags = tags_html,
             owner = owner_display,
         ));
@@ -1211,6 +1944,184 @@ struct TaskSearchParams {
     limit: Option<i32>,
 }
 
+/// Query parameters for the search page.
+#[derive(Debug, serde::Deserialize)]
+struct SearchPageParams {
+    q: Option<String>,
+    status: Option<String>,
+    tag: Option<String>,
+    agent: Option<String>,
+    limit: Option<i32>,
+}
+
+/// Escape an FTS5 snippet for HTML, keeping its `<mark>` highlights.
+fn highlight_snippet(snippet: &str) -> String {
+    html_escape(snippet)
+        .replace("&lt;mark&gt;", "<mark>")
+        .replace("&lt;/mark&gt;", "</mark>")
+}
+
+/// Static asset from a `dashboard/static/` override directory.
+async fn static_asset(State(state): State<DashboardServer>, Path(path): Path<String>) -> Response {
+    let Some(file) = state.templates.static_file(&path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match tokio::fs::read(&file).await {
+        Ok(bytes) => (
+            [(header::CONTENT_TYPE, templates::content_type(&file))],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => {
+            warn!("Failed to read dashboard asset {}: {}", file.display(), e);
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+}
+
+/// Search page - serves the full-text search page.
+async fn search_page(State(state): State<DashboardServer>) -> Html<Cow<'static, str>> {
+    Html(state.template("search.html"))
+}
+
+/// Search results API endpoint for htmx - returns HTML fragment with
+/// highlighted matches, narrowed by status, tag, and agent.
+async fn api_search_results(
+    State(state): State<DashboardServer>,
+    Query(params): Query<SearchPageParams>,
+) -> Html<String> {
+    let Some(query) = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) else {
+        return Html(
+            r#"<div class="empty-state">Enter a search query to find tasks</div>"#.to_string(),
+        );
+    };
+    let non_empty = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let (status, tag, agent) = (
+        non_empty(&params.status),
+        non_empty(&params.tag),
+        non_empty(&params.agent),
+    );
+    let filters = SearchFilters {
+        status: status.as_deref(),
+        tag: tag.as_deref(),
+        owner: agent.as_deref(),
+    };
+    let limit = params.limit.unwrap_or(50).clamp(10, 100);
+
+    match state
+        .db()
+        .search_tasks_filtered(query, Some(limit), 0, true, &filters, None)
+    {
+        Ok(results) => Html(render_search_results(query, &results)),
+        Err(e) => {
+            let error_msg = e.to_string();
+            if error_msg.contains("fts5")
+                || error_msg.contains("syntax")
+                || error_msg.contains("MATCH")
+            {
+                return Html(format!(
+                    r#"<div class="empty-state">Invalid search syntax: {}<br><br>
+                    <small>Try: simple words, "exact phrase", prefix*, title:word, AND/OR/NOT</small></div>"#,
+                    html_escape(&error_msg)
+                ));
+            }
+            Html(format!(
+                r#"<div class="empty-state">Search error: {}</div>"#,
+                html_escape(&error_msg)
+            ))
+        }
+    }
+}
+
+/// Render search results as cards with highlighted title, description, and
+/// attachment snippets.
+fn render_search_results(query: &str, results: &[SearchResult]) -> String {
+    if results.is_empty() {
+        return format!(
+            r#"<div class="empty-state">No tasks found matching "{}"</div>"#,
+            html_escape(query)
+        );
+    }
+
+    let mut html = format!(
+        r#"<div class="search-summary">Found {} result{} for "{}"</div>"#,
+        results.len(),
+        if results.len() == 1 { "" } else { "s" },
+        html_escape(query)
+    );
+
+    for result in results {
+        let badge_class = match result.status.as_str() {
+            "completed" => "badge-success",
+            "working" => "badge-info",
+            "failed" => "badge-error",
+            "pending" => "badge-pending",
+            "assigned" => "badge-assigned",
+            _ => "badge-warning",
+        };
+        let title = if result.title_snippet.is_empty() {
+            html_escape(&result.title)
+        } else {
+            highlight_snippet(&result.title_snippet)
+        };
+        let description = result
+            .description_snippet
+            .as_deref()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                format!(
+                    r#"<div class="search-snippet">{}</div>"#,
+                    highlight_snippet(s)
+                )
+            })
+            .unwrap_or_default();
+        let attachments: String = result
+            .attachment_matches
+            .iter()
+            .map(|m| {
+                format!(
+                    r#"<div class="search-snippet search-attachment"><span class="tag">{} #{}{}</span> {}</div>"#,
+                    html_escape(&m.attachment_type),
+                    m.sequence,
+                    if m.name.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" {}", html_escape(&m.name))
+                    },
+                    highlight_snippet(&m.content_snippet)
+                )
+            })
+            .collect();
+
+        html.push_str(&format!(
+            r#"<div class="search-result">
+                <div class="search-result-header">
+                    <a class="search-title" href="/tasks/{id}">{title}</a>
+                    <span class="badge {badge_class}">{status}</span>
+                </div>
+                <div class="task-id">{id} <span class="search-score">score {score:.2}</span></div>
+                {description}{attachments}
+            </div>"#,
+            id = html_escape(&result.task_id),
+            title = title,
+            badge_class = badge_class,
+            status = html_escape(&result.status),
+            // bm25 is negative, lower is more relevant
+            score = -result.score,
+            description = description,
+            attachments = attachments,
+        ));
+    }
+
+    html
+}
+
 /// Task search API endpoint for htmx - returns HTML fragment with search results.
 /// When query is empty, returns all tasks (filtered by status if provided).
 async fn api_tasks_search(
@@ -1309,7 +2220,7 @@ async fn api_tasks_search(
                 title = html_escape(&title_display),
                 badge_class = badge_class,
                 status = task.status,
-                priority = task.priority,
+                priority = html_escape(&state.priorities_config().display(task.priority)),
             ));
         }
 
@@ -1324,7 +2235,7 @@ async fn api_tasks_search(
 
     let results = match state
         .db()
-        .search_tasks(&query, Some(limit), 0, false, status_filter)
+        .search_tasks(&query, Some(limit), 0, false, status_filter, None)
     {
         Ok(r) => r,
         Err(e) => {
@@ -1387,13 +2298,7 @@ async fn api_tasks_search(
         let title_display = if result.title_snippet.is_empty() {
             html_escape(&result.title)
         } else {
-            // title_snippet already has HTML <mark> tags, so don't escape the marks
-            result
-                .title_snippet
-                .replace('<', "&lt;")
-                .replace('>', "&gt;")
-                .replace("&lt;mark&gt;", "<mark>")
-                .replace("&lt;/mark&gt;", "</mark>")
+            highlight_snippet(&result.title_snippet)
         };
 
         // Format score (lower is better in BM25)
@@ -1506,12 +2411,11 @@ async fn api_tasks_bulk(
                 }
             };
             for task_id in &request.task_ids {
-                match state
-                    .db()
-                    .dashboard_update_task(task_id, Some(status), None, None, None)
-                {
-                    Ok(()) => affected += 1,
-                    Err(e) => last_error = Some(e.to_string()),
+                let mut changes = Map::new();
+                changes.insert("status".to_string(), json!(status));
+                match apply_task_update(&state, task_id, changes, false).await {
+                    Ok(_) => affected += 1,
+                    Err(e) => last_error = Some(format!("{}: {}", task_id, e)),
                 }
             }
         }
@@ -1567,6 +2471,7 @@ async fn api_root() -> impl IntoResponse {
         "version": env!("CARGO_PKG_VERSION"),
         "endpoints": {
             "health": "/api/health",
+            "charts": "/api/charts/data?period=day|week&periods=N",
             "tasks": "/api/tasks (coming soon)",
             "agents": "/api/agents (coming soon)",
         }
@@ -1574,13 +2479,288 @@ async fn api_root() -> impl IntoResponse {
 }
 
 /// File marks page - serves the file marks coordination page.
-async fn file_marks_page() -> Html<&'static str> {
-    Html(templates::FILE_MARKS_TEMPLATE)
+async fn file_marks_page(State(state): State<DashboardServer>) -> Html<Cow<'static, str>> {
+    Html(state.template("file_marks.html"))
 }
 
 /// Metrics page - serves the metrics dashboard page.
-async fn metrics_page() -> Html<&'static str> {
-    Html(templates::METRICS_TEMPLATE)
+async fn metrics_page(State(state): State<DashboardServer>) -> Html<Cow<'static, str>> {
+    Html(state.template("metrics.html"))
+}
+
+/// Share link landing page - stores the read-only token and opens the dashboard.
+async fn share_landing(
+    State(state): State<DashboardServer>,
+    Path(token): Path<String>,
+) -> Response {
+    share::open_share_link(&state.share, &token)
+}
+
+/// Owner link landing page - stores the owner token and opens the dashboard.
+async fn owner_landing(
+    State(state): State<DashboardServer>,
+    Path(token): Path<String>,
+) -> Response {
+    share::open_owner_link(&state.access, &token)
+}
+
+/// Form data for generating a share link.
+#[derive(Debug, serde::Deserialize)]
+struct ShareLinkForm {
+    hours: Option<String>,
+}
+
+/// Share link API endpoint for htmx - issues a read-only link and returns it
+/// as an HTML fragment.
+async fn api_share_create(
+    State(state): State<DashboardServer>,
+    headers: axum::http::HeaderMap,
+    Form(form): Form<ShareLinkForm>,
+) -> Html<String> {
+    let config = &state.share_config;
+    let hours = match form.hours.as_deref().map(str::trim) {
+        None | Some("") => config.ttl_hours,
+        Some(hours) => match hours.parse::<u64>() {
+            Ok(hours) if hours > 0 && hours <= config.max_ttl_hours => hours,
+            _ => {
+                return Html(format!(
+                    r#"<span class="badge badge-error">Hours must be between 1 and {}</span>"#,
+                    config.max_ttl_hours
+                ));
+            }
+        },
+    };
+
+    let expires = share::unix_now() + (hours * 3600) as i64;
+    let token = state.share.issue(expires);
+    let base = match &config.base_url {
+        Some(base) => base.trim_end_matches('/').to_string(),
+        None => {
+            let host = headers
+                .get(header::HOST)
+                .and_then(|h| h.to_str().ok())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("127.0.0.1:{}", state.port()));
+            format!("http://{}", host)
+        }
+    };
+    let url = format!("{}{}{}", base, share::SHARE_PATH, token);
+    Html(format!(
+        r#"<input type="text" class="share-url" readonly value="{}" onclick="this.select()"> <span class="share-note">read-only, expires in {}h</span>"#,
+        html_escape(&url),
+        hours
+    ))
+}
+
+/// Query parameters for the snapshot download endpoint.
+#[derive(Debug, serde::Deserialize)]
+struct ExportParams {
+    #[serde(default)]
+    gzip: bool,
+    #[serde(default)]
+    exclude_deleted: bool,
+}
+
+/// Snapshot download endpoint - returns the project data as a snapshot file
+/// (the same format as `task-graph-mcp export`). A full dump, so share-link
+/// viewers are refused.
+async fn api_export(
+    State(state): State<DashboardServer>,
+    access: Option<axum::Extension<share::Access>>,
+    Query(params): Query<ExportParams>,
+) -> Response {
+    if let Some(axum::Extension(share::Access::Viewer)) = access {
+        return import_error(StatusCode::FORBIDDEN, "Exporting needs owner access");
+    }
+    let options = ExportOptions {
+        exclude_deleted: params.exclude_deleted,
+        tables: None,
+        snapshot_at: None,
+        states_config: state.states_config().clone(),
+    };
+    let snapshot = state
+        .db()
+        .export_tables(&options)
+        .and_then(|tables| Ok(Snapshot::from_tables(tables)?));
+    let body = snapshot.and_then(|snapshot| {
+        if params.gzip {
+            snapshot.to_json_gzip()
+        } else {
+            Ok(snapshot.to_json_pretty()?.into_bytes())
+        }
+    });
+
+    match body {
+        Ok(body) => {
+            let (content_type, extension) = if params.gzip {
+                ("application/gzip", "json.gz")
+            } else {
+                ("application/json", "json")
+            };
+            let filename = format!(
+                "task-graph-{}.{}",
+                chrono::Utc::now().format("%Y%m%d-%H%M%S"),
+                extension
+            );
+            (
+                [
+                    (header::CONTENT_TYPE, content_type.to_string()),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        format!(r#"attachment; filename="{}""#, filename),
+                    ),
+                ],
+                body,
+            )
+                .into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Export failed: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+/// Query parameters for the snapshot upload endpoint.
+#[derive(Debug, serde::Deserialize)]
+struct ImportParams {
+    /// `merge` (default) or `replace`.
+    mode: Option<String>,
+    #[serde(default)]
+    remap_ids: bool,
+    parent: Option<String>,
+    /// Allow `replace` over existing data.
+    #[serde(default)]
+    force: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Largest snapshot the upload endpoint accepts.
+const IMPORT_BODY_LIMIT: usize = 256 * 1024 * 1024;
+
+/// Snapshot upload endpoint - imports a plain or gzipped snapshot posted as
+/// the request body. Needs owner access and the configured admin token in the
+/// `X-Admin-Token` header; share-link viewers are refused.
+async fn api_import(
+    State(state): State<DashboardServer>,
+    access: Option<axum::Extension<share::Access>>,
+    headers: axum::http::HeaderMap,
+    Query(params): Query<ImportParams>,
+    body: axum::body::Bytes,
+) -> Response {
+    if let Some(axum::Extension(share::Access::Viewer)) = access {
+        return import_error(StatusCode::FORBIDDEN, "Importing needs owner access");
+    }
+    let admin_token = headers
+        .get(share::ADMIN_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if !state.access.is_admin_token(admin_token) {
+        let message = if !state.access.import_enabled() {
+            "Importing is disabled until ui.admin_token is configured"
+        } else {
+            "Importing needs the admin token in the X-Admin-Token header"
+        };
+        return import_error(StatusCode::FORBIDDEN, message);
+    }
+    // Parsing and importing a large snapshot must not hold a runtime worker
+    let result = tokio::task::spawn_blocking(move || run_import(&state, &params, &body)).await;
+    match result {
+        Ok(Ok(result)) => Json(result).into_response(),
+        Ok(Err(e)) => import_error(StatusCode::BAD_REQUEST, &e.to_string()),
+        Err(e) => import_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+fn import_error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+/// Import an uploaded snapshot following the same rules as the CLI: replace
+/// needs `force` when the database has data, and remapped IDs merge.
+fn run_import(
+    state: &DashboardServer,
+    params: &ImportParams,
+    body: &[u8],
+) -> anyhow::Result<Value> {
+    let db = state.db();
+    let mut snapshot = Snapshot::from_bytes(body)?;
+    let mut warnings = Vec::new();
+    if !snapshot.is_schema_compatible() {
+        warnings.push(format!(
+            "Snapshot schema version {} differs from current version {}",
+            snapshot.schema_version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    let mut options = match params.mode.as_deref().unwrap_or("merge") {
+        "merge" => ImportOptions::merge(),
+        "replace" => ImportOptions::replace(),
+        other => anyhow::bail!("Unknown import mode '{}'. Use merge or replace.", other),
+    };
+    options.parent_id = params.parent.clone();
+
+    let ids = &state.config().ids;
+    options.remap_ids = params.remap_ids;
+
+    if params.dry_run {
+        // Preview the remap without consuming sequence numbers
+        let id_remap = if params.remap_ids {
+            let (remapped, id_map) =
+                db.preview_remap(&snapshot, ids, |s, next| remap_snapshot_with(s, next))?;
+            snapshot = remapped;
+            Some(id_map)
+        } else {
+            None
+        };
+        let preview = db.preview_import(&snapshot, &options);
+        warnings.extend(preview.warnings);
+        return Ok(json!({
+            "dry_run": true,
+            "mode": format!("{:?}", preview.mode).to_lowercase(),
+            "would_succeed": preview.would_succeed,
+            "failure_reason": preview.failure_reason,
+            "would_insert": preview.would_insert,
+            "would_skip": preview.would_skip,
+            "would_delete": preview.would_delete,
+            "id_remap": id_remap,
+            "warnings": warnings,
+        }));
+    }
+
+    if options.mode == ImportMode::Replace && !params.force && !params.remap_ids {
+        let preview = db.preview_import(&snapshot, &options);
+        if !preview.database_is_empty {
+            anyhow::bail!(
+                "Database contains existing data. Use force to replace, or merge to add."
+            );
+        }
+    }
+
+    let result = if params.remap_ids {
+        db.import_snapshot_remapped(&snapshot, &options, ids, |s, next| {
+            remap_snapshot_with(s, next)
+        })?
+    } else {
+        db.import_snapshot(&snapshot, &options)?
+    };
+    warnings.extend(result.warnings);
+    Ok(json!({
+        "mode": format!("{:?}", options.mode).to_lowercase(),
+        "rows_imported": result.rows_imported,
+        "rows_skipped": result.rows_skipped,
+        "rows_deleted": result.rows_deleted,
+        "parent_linked_roots": result.parent_linked_roots,
+        "id_remap": result.id_remap,
+        "warnings": warnings,
+    }))
+}
+
+/// Charts page - serves the trend charts page.
+async fn charts_page(State(state): State<DashboardServer>) -> Html<Cow<'static, str>> {
+    Html(state.template("charts.html"))
 }
 
 /// File marks stats API endpoint for htmx - returns HTML fragment with stats.
@@ -1779,16 +2959,23 @@ fn format_duration(ms: i64) -> String {
 
 /// Metrics overview API endpoint for htmx - returns HTML fragment with key stats.
 async fn api_metrics_overview(State(state): State<DashboardServer>) -> Html<String> {
-    let overview = state.db().get_metrics_overview().unwrap_or({
-        crate::db::dashboard::MetricsOverview {
-            total_tasks: 0,
-            completed_tasks: 0,
-            total_cost_usd: 0.0,
-            total_time_ms: 0,
-            total_points: 0,
-            completed_points: 0,
-        }
-    });
+    let db = state.db();
+    let overview = db
+        .cached(
+            "dashboard://metrics/overview",
+            &[MutationKind::TaskChanged],
+            || db.get_metrics_overview(),
+        )
+        .unwrap_or({
+            crate::db::dashboard::MetricsOverview {
+                total_tasks: 0,
+                completed_tasks: 0,
+                total_cost_usd: 0.0,
+                total_time_ms: 0,
+                total_points: 0,
+                completed_points: 0,
+            }
+        });
 
     let time_str = format_duration(overview.total_time_ms);
     let cost_str = if overview.total_cost_usd > 0.0 {
@@ -1958,6 +3145,36 @@ async fn api_metrics_velocity(
     Html(html)
 }
 
+/// Query parameters for the charts data endpoint.
+#[derive(Debug, serde::Deserialize)]
+struct ChartsParams {
+    period: Option<String>,
+    periods: Option<i32>,
+}
+
+/// Charts data API endpoint - returns open/closed counts, points burndown,
+/// and per-agent throughput as JSON.
+async fn api_charts_data(
+    State(state): State<DashboardServer>,
+    Query(params): Query<ChartsParams>,
+) -> Response {
+    let period = params.period.as_deref().unwrap_or("day");
+    let default_periods = if period == "week" { 12 } else { 14 };
+    let num_periods = params.periods.unwrap_or(default_periods).clamp(1, 90);
+
+    match state
+        .db()
+        .get_trends(period, num_periods, state.states_config())
+    {
+        Ok(trends) => Json(trends).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
 /// Metrics time-in-status API endpoint for htmx - returns time stats table.
 async fn api_metrics_time_in_status(State(state): State<DashboardServer>) -> Html<String> {
     let time_stats = state.db().get_time_in_status().unwrap_or_default();
@@ -2048,7 +3265,7 @@ async fn api_metrics_cost_by_agent(State(state): State<DashboardServer>) -> Html
                 <td class="number">{}</td>
                 <td class="time">{}</td>
             </tr>"#,
-            html_escape(&stat.worker_id),
+            worker_link(&stat.worker_id),
             cost_str,
             stat.task_count,
             stat.completed_count,
@@ -2093,8 +3310,8 @@ async fn api_metrics_custom(State(state): State<DashboardServer>) -> Html<String
 // ========== DEPENDENCY GRAPH HANDLERS ==========
 
 /// Dependency graph page - serves the graph visualization page.
-async fn graph_page() -> Html<&'static str> {
-    Html(templates::DEP_GRAPH_TEMPLATE)
+async fn graph_page(State(state): State<DashboardServer>) -> Html<Cow<'static, str>> {
+    Html(state.template("dep_graph.html"))
 }
 
 /// Query parameters for graph mermaid endpoint.
@@ -2288,8 +3505,8 @@ async fn api_graph_stats(State(state): State<DashboardServer>) -> Html<String> {
 }
 
 /// SQL query page - serves the SQL query interface for power users.
-async fn sql_query_page() -> Html<&'static str> {
-    Html(templates::SQL_QUERY_TEMPLATE)
+async fn sql_query_page(State(state): State<DashboardServer>) -> Html<Cow<'static, str>> {
+    Html(state.template("sql_query.html"))
 }
 
 /// SQL query form data.
@@ -2352,7 +3569,7 @@ async fn api_sql_execute(
     let limit = form.limit.map(|l| l.clamp(1, 1000)).unwrap_or(100);
 
     // Execute the query
-    let result = state.db().with_conn(|conn| {
+    let result = state.db().with_read_conn(|conn| {
         conn.busy_timeout(Duration::from_secs(5))?;
 
         let mut stmt = conn.prepare(sql)?;
@@ -2511,16 +3728,23 @@ async fn api_sql_schema(State(state): State<DashboardServer>) -> Html<String> {
 
 /// Build the router with all routes.
 fn build_router(state: DashboardServer) -> Router {
-    // Configure CORS for development
+    // The dashboard's own pages are same-origin; other origins may only read,
+    // and only the public base URL (if set)
+    let origins = state
+        .share_config
+        .base_url
+        .as_deref()
+        .and_then(share::origin_of)
+        .and_then(|origin| origin.parse().ok());
     let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::HEAD]);
 
     Router::new()
         // Page routes
         .route("/", get(root))
         .route("/workers", get(workers_page))
+        .route("/workers/{worker_id}", get(worker_detail_page))
         .route("/tasks", get(tasks_page))
         .route(
             "/tasks/{task_id}",
@@ -2531,24 +3755,44 @@ fn build_router(state: DashboardServer) -> Router {
         .route("/activity", get(activity_page))
         .route("/file-marks", get(file_marks_page))
         .route("/metrics", get(metrics_page))
+        .route("/charts", get(charts_page))
+        .route("/share/{token}", get(share_landing))
+        .route("/owner/{token}", get(owner_landing))
+        .route("/search", get(search_page))
         .route("/graph", get(graph_page))
         .route("/sql", get(sql_query_page))
+        .route("/static/{*path}", get(static_asset))
         // htmx fragment routes (for periodic refresh)
         .route("/api/stats", get(api_stats))
         .route("/api/tasks/recent", get(api_recent_tasks))
+        .route("/api/tasks/blocked", get(api_blocked_tasks))
         .route("/api/tasks/list", get(api_tasks_list))
         .route("/api/tasks/search", get(api_tasks_search))
+        .route("/api/search/results", get(api_search_results))
         .route("/api/tasks/phases", get(api_tasks_phases))
         .route("/api/states/config", get(api_states_config))
         .route("/api/tasks/bulk", post(api_tasks_bulk))
-        .route("/api/workers/active", get(api_active_workers))
+        .route("/api/workers/presence", get(api_agent_presence))
         .route("/api/workers/list", get(api_workers_list))
         .route("/api/workers/{worker_id}/details", get(api_worker_details))
+        .route(
+            "/api/workers/{worker_id}/overview",
+            get(api_worker_overview),
+        )
         .route(
             "/api/workers/{worker_id}/disconnect",
             post(api_worker_disconnect),
         )
         .route("/api/workers/cleanup", post(api_workers_cleanup))
+        // Attachment previews
+        .route(
+            "/api/attachments/{task_id}/{attachment_type}/{sequence}/content",
+            get(api_attachment_content),
+        )
+        .route(
+            "/api/attachments/{task_id}/{attachment_type}/{sequence}/thumbnail",
+            get(api_attachment_thumbnail),
+        )
         .route("/api/activity/stats", get(api_activity_stats))
         .route("/api/activity/list", get(api_activity_list))
         .route("/api/file-marks/stats", get(api_file_marks_stats))
@@ -2567,6 +3811,13 @@ fn build_router(state: DashboardServer) -> Router {
         )
         .route("/api/metrics/cost-by-agent", get(api_metrics_cost_by_agent))
         .route("/api/metrics/custom", get(api_metrics_custom))
+        .route("/api/charts/data", get(api_charts_data))
+        .route("/api/share", post(api_share_create))
+        .route("/api/export", get(api_export))
+        .route(
+            "/api/import",
+            post(api_import).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        )
         // Graph routes
         .route("/api/graph/mermaid", get(api_graph_mermaid))
         .route("/api/graph/stats", get(api_graph_stats))
@@ -2577,6 +3828,10 @@ fn build_router(state: DashboardServer) -> Router {
         .route("/api", get(api_root))
         .route("/api/health", get(health))
         // Add middleware
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::clone(&state.access),
+            share::share_guard,
+        ))
         .layer(cors)
         .layer(TraceLayer::new_for_http())
         .with_state(state)
@@ -2615,23 +3870,39 @@ impl DashboardHandle {
     }
 }
 
-/// Start the HTTP server on the specified port.
+/// Start the HTTP server on the configured address and port.
 ///
 /// Returns a oneshot sender that can be used to signal shutdown,
 /// and the actual address the server is bound to.
 pub async fn start_server(
     db: Arc<Database>,
-    port: u16,
-    states_config: Arc<StatesConfig>,
+    ui_config: &UiConfig,
+    config: AppConfig,
+    media_dir: PathBuf,
+    templates: TemplateOverrides,
 ) -> anyhow::Result<(oneshot::Sender<()>, SocketAddr)> {
-    let state = DashboardServer::new(db, port, states_config);
+    let overridden = templates.overridden();
+    let state = DashboardServer::new(db, ui_config, config, media_dir, templates);
+    if state.access.owner_locked_out() {
+        tracing::warn!(
+            "Dashboard is reachable beyond localhost (ui.bind or ui.share.base_url) but \
+             ui.owner_token is unset; no request gets write access"
+        );
+    }
     let app = build_router(state);
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let addr = SocketAddr::new(ui_config.bind, ui_config.port);
     let listener = tokio::net::TcpListener::bind(addr).await?;
     let bound_addr = listener.local_addr()?;
 
     info!("Dashboard server listening on http://{}", bound_addr);
+    for (name, path) in overridden {
+        info!(
+            "Dashboard template {} overridden by {}",
+            name,
+            path.display()
+        );
+    }
 
     let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
 
@@ -2681,13 +3952,17 @@ fn compute_jittered_delay(base_ms: u64, jitter_ms: u64) -> Duration {
 /// # Arguments
 /// * `db` - Database handle
 /// * `ui_config` - UI configuration including port and retry settings
-/// * `states_config` - States configuration for the dashboard
+/// * `config` - Application configuration (states, priorities, workflows, attachments)
+/// * `media_dir` - Media directory for attachment previews and the thumbnail cache
 pub fn start_server_with_retry(
     db: Arc<Database>,
     ui_config: &UiConfig,
-    states_config: Arc<StatesConfig>,
+    config: AppConfig,
+    media_dir: PathBuf,
+    templates: TemplateOverrides,
 ) -> DashboardHandle {
     let port = ui_config.port;
+    let ui_config = ui_config.clone();
     let retry_initial_ms = ui_config.retry_initial_ms;
     let retry_jitter_ms = ui_config.retry_jitter_ms;
     let retry_max_ms = ui_config.retry_max_ms;
@@ -2697,7 +3972,6 @@ pub fn start_server_with_retry(
     let (handle_shutdown_tx, mut handle_shutdown_rx) = oneshot::channel::<()>();
 
     let db_clone = Arc::clone(&db);
-    let states_config_clone = Arc::clone(&states_config);
 
     tokio::spawn(async move {
         let mut current_delay_ms = retry_initial_ms;
@@ -2720,8 +3994,10 @@ pub fn start_server_with_retry(
             // Try to start the server
             match start_server(
                 Arc::clone(&db_clone),
-                port,
-                Arc::clone(&states_config_clone),
+                &ui_config,
+                config.clone(),
+                media_dir.clone(),
+                templates.clone(),
             )
             .await
             {
@@ -2770,6 +4046,136 @@ pub fn start_server_with_retry(
 mod tests {
     use super::*;
 
+    fn task(status: &str) -> Task {
+        serde_json::from_value(json!({
+            "id": "t1",
+            "title": "Fix typo",
+            "description": "Line one",
+            "status": status,
+            "priority": 5,
+            "needed_tags": [],
+            "wanted_tags": [],
+            "tags": ["docs"],
+            "cost_usd": 0.0,
+            "metrics": [0, 0, 0, 0, 0, 0, 0, 0],
+            "created_at": 0,
+            "updated_at": 0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_task_form_changes_only_sends_edited_fields() {
+        let unchanged = TaskUpdateForm {
+            title: Some(" Fix typo ".to_string()),
+            status: Some("pending".to_string()),
+            priority: Some("5".to_string()),
+            tags: Some("docs, ".to_string()),
+            description: Some("Line one".to_string()),
+            force: None,
+        };
+        assert!(task_form_changes(&task("pending"), &unchanged).is_empty());
+
+        let edited = TaskUpdateForm {
+            title: Some("Fix the typo".to_string()),
+            status: Some("cancelled".to_string()),
+            priority: Some("high".to_string()),
+            tags: Some("docs, ui".to_string()),
+            description: Some(" ".to_string()),
+            force: None,
+        };
+        let changes = task_form_changes(&task("pending"), &edited);
+        assert_eq!(changes["title"], json!("Fix the typo"));
+        assert_eq!(changes["status"], json!("cancelled"));
+        assert_eq!(changes["priority"], json!("high"));
+        assert_eq!(changes["tags"], json!(["docs", "ui"]));
+        assert_eq!(changes["description"], Value::Null);
+    }
+
+    #[test]
+    fn test_status_options_follow_transitions() {
+        let html = status_options(&StatesConfig::default(), "pending");
+        assert!(html.starts_with(r#"<option value="pending" selected>"#));
+        assert!(html.contains(r#"<option value="cancelled">"#));
+        assert!(html.contains(r#"<option value="working" disabled"#));
+        assert!(!html.contains(r#"value="completed""#));
+    }
+
+    #[test]
+    fn test_update_warnings_collects_warning_keys() {
+        let response = json!({
+            "id": "t1",
+            "tag_warnings": ["Unknown tag: ui"],
+            "phase_warning": "Unknown phase",
+            "unblocked": ["t2"]
+        });
+        assert_eq!(
+            update_warnings(&response),
+            vec!["Unknown phase", "Unknown tag: ui"]
+        );
+    }
+
+    #[test]
+    fn test_render_presence_highlights_silent_agents() {
+        use crate::db::dashboard::WorkerClaimedTask;
+
+        let now = 10 * STALE_HEARTBEAT_MS;
+        let workers = vec![
+            AgentPresence {
+                id: "quiet".to_string(),
+                last_heartbeat: now - STALE_HEARTBEAT_MS,
+                current_thought: None,
+                claims: Vec::new(),
+            },
+            AgentPresence {
+                id: "busy".to_string(),
+                last_heartbeat: now - 5_000,
+                current_thought: Some("Reading <docs>".to_string()),
+                claims: vec![WorkerClaimedTask {
+                    id: "t1".to_string(),
+                    title: Some("Write docs".to_string()),
+                    status: "working".to_string(),
+                    current_thought: Some("Reading <docs>".to_string()),
+                }],
+            },
+        ];
+
+        let html = render_presence(&workers, now);
+        assert!(html.contains("1 of 2 silent for 5m+"));
+        assert_eq!(html.matches(r#"class="presence-stale""#).count(), 1);
+        assert!(html.contains(r#"<span class="status-dot stale"></span><a href="/workers/quiet""#));
+        assert!(html.contains(r#"<a href="/tasks/t1" title="Write docs">t1</a>"#));
+        assert!(html.contains("Reading &lt;docs&gt;"));
+
+        assert!(render_presence(&[], now).contains("No connected agents"));
+    }
+
+    #[test]
+    fn test_search_snippets_escape_text_but_keep_highlights() {
+        assert_eq!(
+            highlight_snippet("Fix <b> & <mark>parser</mark>"),
+            "Fix &lt;b&gt; &amp; <mark>parser</mark>"
+        );
+
+        let results = vec![SearchResult {
+            task_id: "t1".to_string(),
+            title: "Parser".to_string(),
+            description: None,
+            status: "pending".to_string(),
+            score: -1.5,
+            title_snippet: "<mark>Parser</mark>".to_string(),
+            description_snippet: Some("<script>".to_string()),
+            attachment_matches: vec![],
+        }];
+        let html = render_search_results("parser", &results);
+        assert!(html.contains("Found 1 result for"));
+        assert!(
+            html.contains(r#"<a class="search-title" href="/tasks/t1"><mark>Parser</mark></a>"#)
+        );
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(html.contains("score 1.50"));
+    }
+
     #[test]
     fn test_health_response_serialization() {
         let response = HealthResponse {
@@ -0,0 +1,507 @@
+//! Read-only share links for the dashboard.
+//!
+//! A share token is `<expires>.<signature>`: the Unix time (seconds) at which
+//! the link stops working, and a base64url HMAC-SHA256 of it under the share
+//! key. Opening `/share/<token>` stores the token in a cookie, and every request
+//! carrying it is served read-only (GET and HEAD only).
+//!
+//! Full access needs the configured owner token, sent as a bearer token or in
+//! the cookie set by opening `/owner/<token>`. Without an owner token, requests
+//! without a share token get full access only when the configuration keeps the
+//! dashboard local: bound to loopback with no public `base_url`. The peer
+//! address is not trusted, since a tunnel or reverse proxy makes every request
+//! arrive from loopback.
+
+use crate::config::UiConfig;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, Method, StatusCode, header},
+    middleware::Next,
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// Cookie the share token is kept in after opening a share link.
+pub const SHARE_COOKIE: &str = "task_graph_share";
+
+/// Path prefix of share links; reachable without a token.
+pub const SHARE_PATH: &str = "/share/";
+
+/// Cookie the owner token is kept in after opening an owner link.
+pub const OWNER_COOKIE: &str = "task_graph_owner";
+
+/// Path prefix of owner links; reachable without a token.
+pub const OWNER_PATH: &str = "/owner/";
+
+/// Header carrying the admin token that snapshot import requires.
+pub const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+/// Why a share token was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareError {
+    /// Request without a token where the owner needs to be identified.
+    Missing,
+    Malformed,
+    BadSignature,
+    Expired,
+    /// Owner credential that does not match `ui.owner_token`.
+    BadOwnerToken,
+}
+
+impl ShareError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            ShareError::Missing => "Viewing this dashboard needs a share link or the owner token.",
+            ShareError::Malformed => "This share link is not valid.",
+            ShareError::BadSignature => {
+                "This share link was not issued by this dashboard (or its key changed)."
+            }
+            ShareError::Expired => "This share link has expired.",
+            ShareError::BadOwnerToken => "This owner token is not valid.",
+        }
+    }
+}
+
+/// Signs and checks share tokens.
+pub struct ShareSigner {
+    key: Vec<u8>,
+}
+
+impl ShareSigner {
+    /// Use `secret` as the signing key, or a random key when `None`.
+    pub fn new(secret: Option<&str>) -> Self {
+        let key = match secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => random_key(),
+        };
+        Self { key }
+    }
+
+    /// Token valid until `expires` (Unix seconds).
+    pub fn issue(&self, expires: i64) -> String {
+        let signature = self.mac(expires).finalize().into_bytes();
+        format!("{}.{}", expires, URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// Check a token at `now` (Unix seconds); returns its expiry.
+    pub fn verify(&self, token: &str, now: i64) -> Result<i64, ShareError> {
+        let (expires, signature) = token.split_once('.').ok_or(ShareError::Malformed)?;
+        let expires: i64 = expires.parse().map_err(|_| ShareError::Malformed)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| ShareError::Malformed)?;
+        self.mac(expires)
+            .verify_slice(&signature)
+            .map_err(|_| ShareError::BadSignature)?;
+        if expires <= now {
+            return Err(ShareError::Expired);
+        }
+        Ok(expires)
+    }
+
+    fn mac(&self, expires: i64) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(format!("share:v1:{}", expires).as_bytes());
+        mac
+    }
+}
+
+/// 256-bit key from the randomness in a few ULIDs.
+fn random_key() -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    for _ in 0..4 {
+        hasher.update(ulid::Ulid::new().to_bytes());
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Value of the cookie `name`, if any.
+pub fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(cookie_name, _)| *cookie_name == name)
+        .map(|(_, value)| value)
+}
+
+/// Owner credential of a request: an `Authorization: Bearer` token, else the
+/// `task_graph_owner` cookie.
+pub fn owner_credential(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .or_else(|| cookie(headers, OWNER_COOKIE))
+}
+
+/// `Set-Cookie` value storing `token` until it expires.
+pub fn share_cookie(token: &str, max_age: i64) -> String {
+    format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+        SHARE_COOKIE,
+        token,
+        max_age.max(0)
+    )
+}
+
+/// `Set-Cookie` value storing the owner token (empty to clear it). Strict, so
+/// other sites cannot make requests with it.
+pub fn owner_cookie(token: &str) -> String {
+    let max_age = if token.is_empty() { "; Max-Age=0" } else { "" };
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict{}",
+        OWNER_COOKIE, token, max_age
+    )
+}
+
+/// Who a request comes from, as far as write access goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Request with the owner token, or without any token on a local-only
+    /// dashboard.
+    Owner,
+    /// Request with a valid share token.
+    Viewer,
+}
+
+/// Who gets which access, from the dashboard configuration.
+pub struct AccessPolicy {
+    signer: Arc<ShareSigner>,
+    owner_token: Option<String>,
+    admin_token: Option<String>,
+    /// Origin of `share.base_url`, accepted for writes besides the request's
+    /// own host.
+    public_origin: Option<String>,
+    /// Requests without any token are the owner's: no owner token is set, the
+    /// dashboard listens on loopback only, and no public `base_url` is set.
+    local_owner: bool,
+}
+
+impl AccessPolicy {
+    pub fn new(signer: Arc<ShareSigner>, ui: &UiConfig) -> Self {
+        Self {
+            signer,
+            owner_token: ui.owner_token.clone(),
+            admin_token: ui.admin_token.clone(),
+            public_origin: ui.share.base_url.as_deref().and_then(origin_of),
+            local_owner: ui.owner_token.is_none()
+                && ui.share.base_url.is_none()
+                && ui.bind.is_loopback(),
+        }
+    }
+
+    /// True when no request can get owner access: the dashboard is reachable
+    /// from elsewhere but no owner token is configured.
+    pub fn owner_locked_out(&self) -> bool {
+        self.owner_token.is_none() && !self.local_owner
+    }
+
+    /// Whether `given` is the configured owner token.
+    pub fn is_owner_token(&self, given: &str) -> bool {
+        token_matches(self.owner_token.as_deref(), given)
+    }
+
+    /// Whether an admin token is configured, without which import is disabled.
+    pub fn import_enabled(&self) -> bool {
+        self.admin_token.is_some()
+    }
+
+    /// Whether `given` is the configured admin token; false while none is set.
+    pub fn is_admin_token(&self, given: &str) -> bool {
+        token_matches(self.admin_token.as_deref(), given)
+    }
+
+    /// Whether a request may change anything given where it was sent from: it
+    /// has no `Origin` header (not sent by a browser page), or the origin is
+    /// the dashboard itself (the request's host or the public `base_url`).
+    /// Stops other web pages from posting to the dashboard.
+    pub fn same_origin(&self, headers: &HeaderMap) -> bool {
+        let Some(origin) = headers.get(header::ORIGIN) else {
+            return true;
+        };
+        let Ok(origin) = origin.to_str() else {
+            return false;
+        };
+        let host = headers.get(header::HOST).and_then(|h| h.to_str().ok());
+        let authority = origin.split_once("://").map(|(_, authority)| authority);
+        (authority.is_some() && authority == host) || self.public_origin.as_deref() == Some(origin)
+    }
+
+    /// Decide the access of a request with an optional owner credential and
+    /// share token. The owner credential wins over a share token.
+    pub fn access(
+        &self,
+        owner: Option<&str>,
+        token: Option<&str>,
+        now: i64,
+    ) -> Result<Access, ShareError> {
+        match (owner, token) {
+            (Some(owner), _) if self.is_owner_token(owner) => Ok(Access::Owner),
+            (Some(_), _) => Err(ShareError::BadOwnerToken),
+            (None, Some(token)) => self.signer.verify(token, now).map(|_| Access::Viewer),
+            (None, None) if self.local_owner => Ok(Access::Owner),
+            (None, None) => Err(ShareError::Missing),
+        }
+    }
+}
+
+/// Compare digests so the time taken does not reveal how much matched.
+fn token_matches(expected: Option<&str>, given: &str) -> bool {
+    expected.is_some_and(|token| Sha256::digest(token) == Sha256::digest(given))
+}
+
+/// `scheme://host[:port]` of a URL, as browsers send it in `Origin`.
+pub fn origin_of(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = rest.split('/').next().filter(|a| !a.is_empty())?;
+    Some(format!("{}://{}", scheme, authority))
+}
+
+/// Middleware enforcing dashboard access: requests need the owner token or a
+/// valid share token (unless the dashboard is local-only), and share token
+/// holders may only read. Writes sent from other web pages are refused. The
+/// request's `Access` is added to its extensions for handlers that need the
+/// owner.
+pub async fn share_guard(
+    State(policy): State<Arc<AccessPolicy>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    if path.starts_with(SHARE_PATH) || path.starts_with(OWNER_PATH) {
+        return next.run(request).await;
+    }
+
+    let read = matches!(*request.method(), Method::GET | Method::HEAD);
+    if !read && !policy.same_origin(request.headers()) {
+        return (
+            StatusCode::FORBIDDEN,
+            "Changes can only be made from the dashboard itself",
+        )
+            .into_response();
+    }
+
+    let owner = owner_credential(request.headers());
+    let token = cookie(request.headers(), SHARE_COOKIE);
+    match policy.access(owner, token, unix_now()) {
+        Ok(Access::Owner) => {
+            request.extensions_mut().insert(Access::Owner);
+            next.run(request).await
+        }
+        Ok(Access::Viewer) if read => {
+            request.extensions_mut().insert(Access::Viewer);
+            next.run(request).await
+        }
+        Ok(Access::Viewer) => (
+            StatusCode::FORBIDDEN,
+            "This dashboard was opened with a read-only share link",
+        )
+            .into_response(),
+        Err(ShareError::Missing) => denied(StatusCode::UNAUTHORIZED, ShareError::Missing.message()),
+        Err(ShareError::BadOwnerToken) => {
+            let mut response = denied(StatusCode::FORBIDDEN, ShareError::BadOwnerToken.message());
+            if let Ok(value) = owner_cookie("").parse() {
+                response.headers_mut().insert(header::SET_COOKIE, value);
+            }
+            response
+        }
+        Err(e) => {
+            let mut response = denied(StatusCode::FORBIDDEN, e.message());
+            // Drop the dead token so it does not shadow the owner's access
+            if let Ok(value) = share_cookie("", 0).parse() {
+                response.headers_mut().insert(header::SET_COOKIE, value);
+            }
+            response
+        }
+    }
+}
+
+/// Landing page of a share link: store the token and go to the dashboard.
+pub fn open_share_link(signer: &ShareSigner, token: &str) -> Response {
+    let now = unix_now();
+    match signer.verify(token, now) {
+        Ok(expires) => {
+            let mut response = Redirect::to("/").into_response();
+            if let Ok(value) = share_cookie(token, expires - now).parse() {
+                response.headers_mut().insert(header::SET_COOKIE, value);
+            }
+            response
+        }
+        Err(e) => denied(StatusCode::FORBIDDEN, e.message()),
+    }
+}
+
+/// Landing page of an owner link: store the owner token and go to the
+/// dashboard.
+pub fn open_owner_link(policy: &AccessPolicy, token: &str) -> Response {
+    if !policy.is_owner_token(token) {
+        return denied(StatusCode::FORBIDDEN, ShareError::BadOwnerToken.message());
+    }
+    let mut response = Redirect::to("/").into_response();
+    if let Ok(value) = owner_cookie(token).parse() {
+        response.headers_mut().insert(header::SET_COOKIE, value);
+    }
+    response
+}
+
+fn denied(status: StatusCode, message: &str) -> Response {
+    let body = format!(
+        r#"<!DOCTYPE html><html><head><title>Task Graph</title></head><body style="font-family: system-ui, sans-serif; background: #1a1a2e; color: #eaeaea; padding: 2rem;"><h1>Access denied</h1><p>{}</p><p>Ask the project owner for a new share link.</p></body></html>"#,
+        message
+    );
+    (status, Html(body)).into_response()
+}
+
+/// Current Unix time in seconds.
+pub fn unix_now() -> i64 {
+    crate::db::now_ms() / 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_verify_until_expiry() {
+        let signer = ShareSigner::new(Some("secret"));
+        let token = signer.issue(1_000);
+        assert_eq!(signer.verify(&token, 999), Ok(1_000));
+        assert_eq!(signer.verify(&token, 1_000), Err(ShareError::Expired));
+    }
+
+    #[test]
+    fn test_tampered_or_foreign_tokens_are_rejected() {
+        let signer = ShareSigner::new(Some("secret"));
+        let token = signer.issue(1_000);
+        let (_, signature) = token.split_once('.').unwrap();
+        let extended = format!("9999.{}", signature);
+        assert_eq!(signer.verify(&extended, 0), Err(ShareError::BadSignature));
+        assert_eq!(
+            ShareSigner::new(None).verify(&token, 0),
+            Err(ShareError::BadSignature)
+        );
+        assert_eq!(signer.verify("garbage", 0), Err(ShareError::Malformed));
+    }
+
+    fn policy(ui: &UiConfig) -> AccessPolicy {
+        AccessPolicy::new(Arc::new(ShareSigner::new(Some("secret"))), ui)
+    }
+
+    #[test]
+    fn test_tokenless_owner_only_on_local_dashboard() {
+        let local = policy(&UiConfig::default());
+        let token = local.signer.issue(100);
+        assert_eq!(local.access(None, None, 0), Ok(Access::Owner));
+        // A share token makes the request read-only
+        assert_eq!(local.access(None, Some(&token), 0), Ok(Access::Viewer));
+        assert_eq!(
+            local.access(None, Some(&token), 100),
+            Err(ShareError::Expired)
+        );
+
+        // Behind a tunnel every request looks local, so base_url ends tokenless access
+        let mut tunneled = UiConfig::default();
+        tunneled.share.base_url = Some("https://tasks.example.com".to_string());
+        let tunneled = policy(&tunneled);
+        assert_eq!(tunneled.access(None, None, 0), Err(ShareError::Missing));
+        assert!(tunneled.owner_locked_out());
+
+        let exposed = policy(&UiConfig {
+            bind: "0.0.0.0".parse().unwrap(),
+            ..UiConfig::default()
+        });
+        assert_eq!(exposed.access(None, None, 0), Err(ShareError::Missing));
+        assert_eq!(exposed.access(None, Some(&token), 0), Ok(Access::Viewer));
+    }
+
+    #[test]
+    fn test_owner_token_is_required_once_configured() {
+        let owned = policy(&UiConfig {
+            bind: "0.0.0.0".parse().unwrap(),
+            owner_token: Some("hunter2".to_string()),
+            ..UiConfig::default()
+        });
+        let token = owned.signer.issue(100);
+        assert!(!owned.owner_locked_out());
+        assert_eq!(owned.access(Some("hunter2"), None, 0), Ok(Access::Owner));
+        assert_eq!(
+            owned.access(Some("hunter2"), Some(&token), 0),
+            Ok(Access::Owner)
+        );
+        assert_eq!(
+            owned.access(Some("guess"), None, 0),
+            Err(ShareError::BadOwnerToken)
+        );
+        assert_eq!(owned.access(None, None, 0), Err(ShareError::Missing));
+
+        // Setting a token ends tokenless access on loopback too
+        let local = policy(&UiConfig {
+            owner_token: Some("hunter2".to_string()),
+            ..UiConfig::default()
+        });
+        assert_eq!(local.access(None, None, 0), Err(ShareError::Missing));
+    }
+
+    #[test]
+    fn test_admin_token_unset_matches_nothing() {
+        assert!(!policy(&UiConfig::default()).is_admin_token(""));
+        let admin = policy(&UiConfig {
+            admin_token: Some("root".to_string()),
+            ..UiConfig::default()
+        });
+        assert!(admin.is_admin_token("root"));
+        assert!(!admin.is_admin_token("roo"));
+    }
+
+    #[test]
+    fn test_writes_only_from_the_dashboard_origin() {
+        let mut ui = UiConfig::default();
+        ui.share.base_url = Some("https://tasks.example.com/board/".to_string());
+        let policy = policy(&ui);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, "127.0.0.1:31994".parse().unwrap());
+        assert!(policy.same_origin(&headers));
+        for (origin, allowed) in [
+            ("http://127.0.0.1:31994", true),
+            ("https://tasks.example.com", true),
+            ("https://evil.example", false),
+            ("null", false),
+        ] {
+            headers.insert(header::ORIGIN, origin.parse().unwrap());
+            assert_eq!(policy.same_origin(&headers), allowed, "{}", origin);
+        }
+    }
+
+    #[test]
+    fn test_token_read_from_cookie_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            "theme=dark; task_graph_share=5.abc".parse().unwrap(),
+        );
+        assert_eq!(cookie(&headers, SHARE_COOKIE), Some("5.abc"));
+        assert_eq!(cookie(&HeaderMap::new(), SHARE_COOKIE), None);
+    }
+
+    #[test]
+    fn test_owner_credential_prefers_bearer_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            "task_graph_owner=from-cookie".parse().unwrap(),
+        );
+        assert_eq!(owner_credential(&headers), Some("from-cookie"));
+        headers.insert(header::AUTHORIZATION, "Bearer from-header".parse().unwrap());
+        assert_eq!(owner_credential(&headers), Some("from-header"));
+    }
+}
@@ -2,6 +2,18 @@
 //!
 //! Templates are embedded at compile time using `include_str!`.
 //! This module provides functions to serve the templates.
+//!
+//! Templates and static assets can be overridden by placing files in:
+//! - `~/.task-graph/dashboard/` (user-level, highest priority)
+//! - `task-graph/dashboard/` (project-level)
+//! - `.task-graph/dashboard/` (project-level, deprecated)
+//!
+//! An override template has the embedded template's file name (e.g.
+//! `index.html`). Static assets live under `static/` and are served at
+//! `/static/...`. Files are read on each request, so edits show up on reload.
+
+use std::borrow::Cow;
+use std::path::{Component, Path, PathBuf};
 
 /// The base HTML template with navigation and layout.
 pub const BASE_TEMPLATE: &str = include_str!("templates/base.html");
@@ -12,9 +24,15 @@ pub const INDEX_TEMPLATE: &str = include_str!("templates/index.html");
 /// The workers list page template.
 pub const WORKERS_TEMPLATE: &str = include_str!("templates/workers.html");
 
+/// The worker detail page template (overview is loaded via htmx).
+pub const WORKER_DETAIL_TEMPLATE: &str = include_str!("templates/worker_detail.html");
+
 /// The tasks list page template with filters and pagination.
 pub const TASKS_TEMPLATE: &str = include_str!("templates/tasks.html");
 
+/// The full-text search page template with status, tag, and agent filters.
+pub const SEARCH_TEMPLATE: &str = include_str!("templates/search.html");
+
 /// The task detail page template with full task info and edit form.
 pub const TASK_DETAIL_TEMPLATE: &str = include_str!("templates/task_detail.html");
 
@@ -27,8 +45,172 @@ pub const FILE_MARKS_TEMPLATE: &str = include_str!("templates/file_marks.html");
 /// The metrics dashboard page template with project health overview.
 pub const METRICS_TEMPLATE: &str = include_str!("templates/metrics.html");
 
+/// The trend charts page template (burndown and throughput).
+pub const CHARTS_TEMPLATE: &str = include_str!("templates/charts.html");
+
 /// The dependency graph visualization page template.
 pub const DEP_GRAPH_TEMPLATE: &str = include_str!("templates/dep_graph.html");
 
 /// The SQL query interface page template for power users.
 pub const SQL_QUERY_TEMPLATE: &str = include_str!("templates/sql_query.html");
+
+/// Directory, under each config tier, holding dashboard overrides.
+pub const OVERRIDE_DIR: &str = "dashboard";
+
+/// Embedded templates by file name.
+pub const TEMPLATES: &[(&str, &str)] = &[
+    ("base.html", BASE_TEMPLATE),
+    ("index.html", INDEX_TEMPLATE),
+    ("workers.html", WORKERS_TEMPLATE),
+    ("worker_detail.html", WORKER_DETAIL_TEMPLATE),
+    ("tasks.html", TASKS_TEMPLATE),
+    ("search.html", SEARCH_TEMPLATE),
+    ("task_detail.html", TASK_DETAIL_TEMPLATE),
+    ("activity.html", ACTIVITY_TEMPLATE),
+    ("file_marks.html", FILE_MARKS_TEMPLATE),
+    ("metrics.html", METRICS_TEMPLATE),
+    ("charts.html", CHARTS_TEMPLATE),
+    ("dep_graph.html", DEP_GRAPH_TEMPLATE),
+    ("sql_query.html", SQL_QUERY_TEMPLATE),
+];
+
+/// Get the embedded template by file name.
+pub fn embedded(name: &str) -> Option<&'static str> {
+    TEMPLATES
+        .iter()
+        .find(|(template, _)| *template == name)
+        .map(|(_, content)| *content)
+}
+
+/// Template and static asset overrides, searched first-found-wins from the
+/// highest tier down before falling back to the embedded templates.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateOverrides {
+    dirs: Vec<PathBuf>,
+}
+
+impl TemplateOverrides {
+    /// Overrides from `dirs`, highest priority first.
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Self { dirs }
+    }
+
+    /// The override directories, highest priority first.
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// Get template `name`, checking for overrides first.
+    pub fn template(&self, name: &str) -> Cow<'static, str> {
+        if let Some(path) = self.find(name) {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => return Cow::Owned(content),
+                Err(e) => tracing::warn!(
+                    "Failed to read dashboard template override {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        Cow::Borrowed(embedded(name).unwrap_or_default())
+    }
+
+    /// Path of static asset `path` (relative to `static/`), if any tier has it.
+    pub fn static_file(&self, path: &str) -> Option<PathBuf> {
+        self.find(&format!("static/{}", path))
+    }
+
+    /// Templates replaced by an override, with the file that replaces them.
+    pub fn overridden(&self) -> Vec<(&'static str, PathBuf)> {
+        TEMPLATES
+            .iter()
+            .filter_map(|(name, _)| self.find(name).map(|path| (*name, path)))
+            .collect()
+    }
+
+    /// First file at `relative` across the override directories. Paths that
+    /// could leave the directory are rejected.
+    fn find(&self, relative: &str) -> Option<PathBuf> {
+        let relative = Path::new(relative);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return None;
+        }
+        self.dirs
+            .iter()
+            .map(|dir| dir.join(relative))
+            .find(|path| path.is_file())
+    }
+}
+
+/// Content type of a static asset, from its extension.
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_overrides_first_found_wins() {
+        let temp = TempDir::new().unwrap();
+        let user = temp.path().join("user");
+        let project = temp.path().join("project");
+        std::fs::create_dir_all(user.join("static")).unwrap();
+        std::fs::create_dir_all(project.join("static")).unwrap();
+        std::fs::write(user.join("index.html"), "user index").unwrap();
+        std::fs::write(project.join("index.html"), "project index").unwrap();
+        std::fs::write(project.join("tasks.html"), "project tasks").unwrap();
+        std::fs::write(project.join("static/brand.css"), "body {}").unwrap();
+
+        let overrides = TemplateOverrides::new(vec![user, project.clone()]);
+        assert_eq!(overrides.template("index.html"), "user index");
+        assert_eq!(overrides.template("tasks.html"), "project tasks");
+        assert_eq!(overrides.template("metrics.html"), METRICS_TEMPLATE);
+        assert_eq!(
+            overrides.static_file("brand.css"),
+            Some(project.join("static/brand.css"))
+        );
+        let names: Vec<&str> = overrides.overridden().iter().map(|(n, _)| *n).collect();
+        assert_eq!(names, vec!["index.html", "tasks.html"]);
+    }
+
+    #[test]
+    fn test_static_paths_cannot_leave_override_dir() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("dashboard");
+        std::fs::create_dir_all(dir.join("static")).unwrap();
+        std::fs::write(temp.path().join("secret.txt"), "secret").unwrap();
+
+        let overrides = TemplateOverrides::new(vec![dir]);
+        assert_eq!(overrides.static_file("../../secret.txt"), None);
+        assert_eq!(overrides.static_file("/etc/passwd"), None);
+        assert_eq!(
+            content_type(Path::new("app.JS")),
+            "text/javascript; charset=utf-8"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Thumbnails for image attachments shown on the task detail page.
+//!
+//! Thumbnails are PNGs no larger than [`THUMBNAIL_SIZE`] pixels on either side,
+//! generated on first request and cached under `<media_dir>/.thumbs/`. The cache
+//! key is the SHA-256 digest of the source image, so blob attachments reuse their
+//! `content_hash` and identical images share one thumbnail.
+
+use crate::blob_store::sha256_hex;
+use anyhow::{Result, anyhow};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Subdirectory of the media dir holding cached thumbnails.
+pub const THUMB_DIR: &str = ".thumbs";
+
+/// Maximum width and height of a thumbnail, in pixels.
+pub const THUMBNAIL_SIZE: u32 = 240;
+
+/// Images larger than this are not decoded for thumbnails.
+const MAX_SOURCE_BYTES: usize = 32 * 1024 * 1024;
+
+/// Whether the dashboard can render a thumbnail for this MIME type.
+pub fn is_thumbnailable(mime_type: &str) -> bool {
+    matches!(
+        mime_type,
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp" | "image/bmp"
+    )
+}
+
+/// Whether browsers can display this MIME type directly in an `<img>` tag.
+pub fn is_previewable(mime_type: &str) -> bool {
+    is_thumbnailable(mime_type) || mime_type == "image/svg+xml"
+}
+
+/// Path of the cached thumbnail for an image with the given digest.
+pub fn thumbnail_path(media_dir: &Path, hash: &str) -> PathBuf {
+    media_dir.join(THUMB_DIR).join(format!("{}.png", hash))
+}
+
+/// Get the PNG thumbnail for an image, generating and caching it if needed.
+///
+/// `hash` is the image's SHA-256 digest when already known (blob attachments);
+/// otherwise it is computed from `bytes`.
+pub fn get_or_create_thumbnail(
+    media_dir: &Path,
+    hash: Option<&str>,
+    bytes: &[u8],
+) -> Result<Vec<u8>> {
+    let hash = hash
+        .map(str::to_string)
+        .unwrap_or_else(|| sha256_hex(bytes));
+    let path = thumbnail_path(media_dir, &hash);
+    if let Ok(cached) = std::fs::read(&path) {
+        return Ok(cached);
+    }
+
+    let png = render_thumbnail(bytes)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // Write to a temp file and rename so concurrent requests never read a partial PNG
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&tmp, &png)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(png)
+}
+
+/// Decode an image and scale it to fit within [`THUMBNAIL_SIZE`], encoded as PNG.
+pub fn render_thumbnail(bytes: &[u8]) -> Result<Vec<u8>> {
+    if bytes.len() > MAX_SOURCE_BYTES {
+        return Err(anyhow!(
+            "image is too large to thumbnail ({} bytes)",
+            bytes.len()
+        ));
+    }
+    let image = image::load_from_memory(bytes)?;
+    let thumbnail = if image.width() > THUMBNAIL_SIZE || image.height() > THUMBNAIL_SIZE {
+        image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+    } else {
+        image
+    };
+    let mut png = Vec::new();
+    thumbnail.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbImage::from_pixel(width, height, image::Rgb([200, 30, 30]));
+        let mut bytes = Vec::new();
+        image::DynamicImage::ImageRgb8(image)
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_render_thumbnail_fits_within_bounds() {
+        let thumbnail = render_thumbnail(&png(960, 480)).unwrap();
+        let decoded = image::load_from_memory(&thumbnail).unwrap();
+        assert_eq!(decoded.width(), THUMBNAIL_SIZE);
+        assert_eq!(decoded.height(), THUMBNAIL_SIZE / 2);
+    }
+
+    #[test]
+    fn test_small_images_are_not_upscaled() {
+        let thumbnail = render_thumbnail(&png(16, 8)).unwrap();
+        let decoded = image::load_from_memory(&thumbnail).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (16, 8));
+    }
+
+    #[test]
+    fn test_thumbnails_are_cached_by_digest() {
+        let media = tempfile::TempDir::new().unwrap();
+        let source = png(500, 500);
+
+        let first = get_or_create_thumbnail(media.path(), None, &source).unwrap();
+        let cached = thumbnail_path(media.path(), &sha256_hex(&source));
+        assert_eq!(std::fs::read(&cached).unwrap(), first);
+
+        // A cached thumbnail is served without decoding the source again
+        let again = get_or_create_thumbnail(media.path(), None, &source).unwrap();
+        assert_eq!(again, first);
+        assert!(render_thumbnail(b"not an image").is_err());
+    }
+}
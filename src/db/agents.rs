@@ -1,8 +1,14 @@
 //! Worker CRUD operations.
 
+use super::groups::is_group_member;
+use super::state_transitions::record_state_transition;
+use super::tasks::get_task_internal;
 use super::{Database, now_ms};
-use crate::config::IdsConfig;
-use crate::types::{CleanupSummary, DisconnectSummary, Worker};
+use crate::config::{IdsConfig, StaleAction, StalePolicy, StatesConfig};
+use crate::types::{
+    CleanupSummary, DEFAULT_PROJECT, DisconnectSummary, Reassignment, ResumedClaim, ResumedSession,
+    StaleEviction, Worker, has_tag,
+};
 use anyhow::{Result, anyhow};
 use petname::{Generator, Petnames};
 use rusqlite::{Connection, params};
@@ -10,6 +16,9 @@ use rusqlite::{Connection, params};
 /// Maximum length for worker IDs (4-word petnames can be ~50 chars).
 pub const MAX_WORKER_ID_LEN: usize = 64;
 
+/// State that reassigned stale claims are handed over in (owned, but untimed).
+const ASSIGNED_STATE: &str = "assigned";
+
 /// Generate a petname-based agent ID using the large wordlist with configured case style.
 /// With 4 words from a large wordlist, collisions are extremely unlikely.
 fn generate_agent_id(ids_config: &IdsConfig) -> String {
@@ -35,8 +44,8 @@ fn parse_overlays(overlays_json: &Option<String>) -> Vec<String> {
 
 /// Internal helper to get a worker using an existing connection (avoids deadlock).
 fn get_worker_internal(conn: &Connection, worker_id: &str) -> Result<Option<Worker>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, tags, max_claims, registered_at, last_heartbeat, last_status, last_phase, workflow, overlays
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, tags, max_claims, registered_at, last_heartbeat, last_status, last_phase, workflow, overlays, project
          FROM workers WHERE id = ?1",
     )?;
 
@@ -50,6 +59,7 @@ fn get_worker_internal(conn: &Connection, worker_id: &str) -> Result<Option<Work
         let last_phase: Option<String> = row.get(6)?;
         let workflow: Option<String> = row.get(7)?;
         let overlays_json: Option<String> = row.get(8)?;
+        let project: String = row.get(9)?;
 
         Ok((
             id,
@@ -61,6 +71,7 @@ fn get_worker_internal(conn: &Connection, worker_id: &str) -> Result<Option<Work
             last_phase,
             workflow,
             overlays_json,
+            project,
         ))
     });
 
@@ -75,6 +86,7 @@ fn get_worker_internal(conn: &Connection, worker_id: &str) -> Result<Option<Work
             last_phase,
             workflow,
             overlays_json,
+            project,
         )) => {
             let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
             let overlays = parse_overlays(&overlays_json);
@@ -88,6 +100,7 @@ fn get_worker_internal(conn: &Connection, worker_id: &str) -> Result<Option<Work
                 last_phase,
                 workflow,
                 overlays,
+                project,
             }))
         }
         Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -148,23 +161,29 @@ impl Database {
 
             // Check if worker ID already exists
             let exists: bool = conn
-                .query_row("SELECT 1 FROM workers WHERE id = ?1", params![&id], |_| Ok(true))
+                .prepare_cached(
+                    "SELECT 1 FROM workers WHERE id = ?1",
+                )?
+                .query_row( params![&id], |_| Ok(true))
                 .unwrap_or(false);
 
             // Get current max claim sequence + 1 to initialize poll position.
             // This ensures first poll returns empty (no events since registration).
             // The +1 is needed because we now query with `id >= last_seq`.
             let current_max_sequence: i64 = conn
-                .query_row("SELECT COALESCE(MAX(id), 0) FROM claim_sequence", [], |row| row.get(0))
+                .prepare_cached(
+                    "SELECT COALESCE(MAX(id), 0) FROM claim_sequence",
+                )?
+                .query_row( [], |row| row.get(0))
                 .unwrap_or(0);
             let initial_sequence = current_max_sequence + 1;
 
             if exists {
                 if force {
-                    // Force reconnection: update existing worker and reset poll position, including workflow and overlays
+                    // Force reconnection: update existing worker and reset poll position, including workflow, overlays, and project
                     conn.execute(
-                        "UPDATE workers SET tags = ?1, max_claims = ?2, last_heartbeat = ?3, last_claim_sequence = ?4, workflow = ?5, overlays = ?6 WHERE id = ?7",
-                        params![tags_json, max_claims, now, initial_sequence, &workflow, &overlays_json, &id],
+                        "UPDATE workers SET tags = ?1, max_claims = ?2, last_heartbeat = ?3, last_claim_sequence = ?4, workflow = ?5, overlays = ?6, project = ?7 WHERE id = ?8",
+                        params![tags_json, max_claims, now, initial_sequence, &workflow, &overlays_json, DEFAULT_PROJECT, &id],
                     )?;
                 } else {
                     return Err(anyhow!("Worker ID '{}' already registered. Use force=true to reconnect.", id));
@@ -187,13 +206,111 @@ impl Database {
                 last_phase: None,
                 workflow,
                 overlays,
+                project: DEFAULT_PROJECT.to_string(),
             })
         })
     }
 
+    /// Resume a worker's existing session if it heartbeated within `grace_seconds`.
+    ///
+    /// Unlike a forced re-registration, the poll position is kept so the worker still
+    /// receives claim events it missed while offline. Tags, workflow, and overlays are
+    /// replaced only when given. Returns `None` if the worker is unknown or its session
+    /// has expired.
+    pub fn resume_worker(
+        &self,
+        worker_id: &str,
+        tags: Option<Vec<String>>,
+        workflow: Option<String>,
+        overlays: Option<Vec<String>>,
+        grace_seconds: i64,
+    ) -> Result<Option<(Worker, ResumedSession)>> {
+        let now = now_ms();
+        let tags_json = tags.as_ref().map(serde_json::to_string).transpose()?;
+        let overlays_json = match &overlays {
+            Some(o) if !o.is_empty() => Some(serde_json::to_string(o)?),
+            _ => None,
+        };
+
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            let Some(previous) = get_worker_internal(&tx, worker_id)? else {
+                return Ok(None);
+            };
+            let offline_ms = now - previous.last_heartbeat;
+            if offline_ms > grace_seconds * 1000 {
+                return Ok(None);
+            }
+
+            tx.execute(
+                "UPDATE workers SET tags = COALESCE(?1, tags), workflow = COALESCE(?2, workflow),
+                        overlays = CASE WHEN ?3 THEN ?4 ELSE overlays END, last_heartbeat = ?5
+                 WHERE id = ?6",
+                params![
+                    tags_json,
+                    workflow,
+                    overlays.is_some(),
+                    overlays_json,
+                    now,
+                    worker_id
+                ],
+            )?;
+
+            let claims = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT id, title, status, current_thought, claimed_at FROM tasks
+                     WHERE worker_id = ?1 ORDER BY claimed_at, id",
+                )?;
+                stmt.query_map(params![worker_id], |row| {
+                    Ok(ResumedClaim {
+                        id: row.get(0)?,
+                        title: row.get(1)?,
+                        status: row.get(2)?,
+                        current_thought: row.get(3)?,
+                        claimed_at: row.get(4)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            let strings = |sql: &str| -> Result<Vec<String>> {
+                let mut stmt = tx.prepare_cached(sql)?;
+                let values = stmt
+                    .query_map(params![worker_id], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(values)
+            };
+            let marks = strings(
+                "SELECT file_path FROM file_locks WHERE worker_id = ?1 ORDER BY file_path",
+            )?;
+            let waiting_for = strings(
+                "SELECT task_id FROM task_waiters WHERE worker_id = ?1 AND notified_at IS NULL
+                 ORDER BY registered_at, task_id",
+            )?;
+            let mark_waits = strings(
+                "SELECT file_path FROM mark_waiters WHERE worker_id = ?1 ORDER BY file_path",
+            )?;
+
+            let worker =
+                get_worker_internal(&tx, worker_id)?.ok_or_else(|| anyhow!("Worker not found"))?;
+            tx.commit()?;
+
+            Ok(Some((
+                worker,
+                ResumedSession {
+                    offline_ms,
+                    claims,
+                    marks,
+                    waiting_for,
+                    mark_waits,
+                },
+            )))
+        })
+    }
+
     /// Get a worker by ID.
     pub fn get_worker(&self, worker_id: &str) -> Result<Option<Worker>> {
-        self.with_conn(|conn| get_worker_internal(conn, worker_id))
+        self.with_read_conn(|conn| get_worker_internal(conn, worker_id))
     }
 
     /// Check if a worker exists. Returns error if not found.
@@ -232,6 +349,7 @@ impl Database {
                 last_phase: worker.last_phase,
                 workflow: worker.workflow,
                 overlays: worker.overlays,
+                project: worker.project,
             })
         })
     }
@@ -270,11 +388,8 @@ impl Database {
         self.with_conn(|conn| {
             // Get current state
             let (old_status, old_phase): (Option<String>, Option<String>) = conn
-                .query_row(
-                    "SELECT last_status, last_phase FROM workers WHERE id = ?1",
-                    params![worker_id],
-                    |row| Ok((row.get(0)?, row.get(1)?)),
-                )
+                .prepare_cached("SELECT last_status, last_phase FROM workers WHERE id = ?1")?
+                .query_row(params![worker_id], |row| Ok((row.get(0)?, row.get(1)?)))
                 .map_err(|e| match e {
                     rusqlite::Error::QueryReturnedNoRows => anyhow!("Worker not found"),
                     e => e.into(),
@@ -305,11 +420,11 @@ impl Database {
             }
 
             // Return current claim count
-            let count: i32 = conn.query_row(
-                "SELECT COUNT(*) FROM tasks WHERE worker_id = ?1 AND status = 'working'",
-                params![worker_id],
-                |row| row.get(0),
-            )?;
+            let count: i32 = conn
+                .prepare_cached(
+                    "SELECT COUNT(*) FROM tasks WHERE worker_id = ?1 AND status = 'working'",
+                )?
+                .query_row(params![worker_id], |row| row.get(0))?;
 
             Ok(count)
         })
@@ -352,9 +467,9 @@ impl Database {
 
     /// List all workers.
     pub fn list_workers(&self) -> Result<Vec<Worker>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT id, tags, max_claims, registered_at, last_heartbeat, last_status, last_phase, workflow, overlays
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, tags, max_claims, registered_at, last_heartbeat, last_status, last_phase, workflow, overlays, project
                  FROM workers ORDER BY registered_at DESC",
             )?;
 
@@ -369,6 +484,7 @@ impl Database {
                     let last_phase: Option<String> = row.get(6)?;
                     let workflow: Option<String> = row.get(7)?;
                     let overlays_json: Option<String> = row.get(8)?;
+                    let project: String = row.get(9)?;
 
                     Ok((
                         id,
@@ -380,6 +496,7 @@ impl Database {
                         last_phase,
                         workflow,
                         overlays_json,
+                        project,
                     ))
                 })?
                 .filter_map(|r| r.ok())
@@ -394,6 +511,7 @@ impl Database {
                         last_phase,
                         workflow,
                         overlays_json,
+                        project,
                     )| {
                         let tags: Vec<String> =
                             serde_json::from_str(&tags_json).unwrap_or_default();
@@ -408,6 +526,7 @@ impl Database {
                             last_phase,
                             workflow,
                             overlays,
+                            project,
                         }
                     },
                 )
@@ -419,12 +538,15 @@ impl Database {
 
     /// List all workers with extended info (claim count, current thought).
     pub fn list_workers_info(&self) -> Result<Vec<crate::types::WorkerInfo>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT w.id, w.tags, w.max_claims, w.registered_at, w.last_heartbeat,
                         (SELECT COUNT(*) FROM tasks WHERE worker_id = w.id AND status = 'working') as claim_count,
                         (SELECT current_thought FROM tasks WHERE worker_id = w.id AND status = 'working' AND current_thought IS NOT NULL LIMIT 1) as current_thought,
-                        w.last_status, w.last_phase, w.workflow, w.overlays
+                        w.last_status, w.last_phase, w.workflow, w.overlays,
+                        (SELECT json_group_array(group_name) FROM
+                            (SELECT group_name FROM worker_groups WHERE worker_id = w.id ORDER BY group_name)) as groups,
+                        w.project
                  FROM workers w ORDER BY w.registered_at DESC",
             )?;
 
@@ -440,13 +562,16 @@ impl Database {
                 let last_phase: Option<String> = row.get(8)?;
                 let workflow: Option<String> = row.get(9)?;
                 let overlays_json: Option<String> = row.get(10)?;
+                let groups_json: String = row.get(11)?;
+                let project: String = row.get(12)?;
 
-                Ok((id, tags_json, max_claims, registered_at, last_heartbeat, claim_count, current_thought, last_status, last_phase, workflow, overlays_json))
+                Ok((id, tags_json, max_claims, registered_at, last_heartbeat, claim_count, current_thought, last_status, last_phase, workflow, overlays_json, groups_json, project))
             })?
             .filter_map(|r| r.ok())
-            .map(|(id, tags_json, max_claims, registered_at, last_heartbeat, claim_count, current_thought, last_status, last_phase, workflow, overlays_json)| {
+            .map(|(id, tags_json, max_claims, registered_at, last_heartbeat, claim_count, current_thought, last_status, last_phase, workflow, overlays_json, groups_json, project)| {
                 let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
                 let overlays = parse_overlays(&overlays_json);
+                let groups: Vec<String> = serde_json::from_str(&groups_json).unwrap_or_default();
                 crate::types::WorkerInfo {
                     id,
                     tags,
@@ -459,6 +584,8 @@ impl Database {
                     last_phase,
                     workflow,
                     overlays,
+                    groups,
+                    project,
                 }
             })
             .collect();
@@ -473,20 +600,25 @@ impl Database {
     /// - `file`: Workers that have claimed this file
     /// - `task_id`: Workers working on tasks related to this task
     /// - `depth`: Task relationship depth (-3 to 3). Negative: ancestors, positive: descendants
+    /// - `group`: Workers in this agent group
     pub fn list_workers_filtered(
         &self,
         tags: Option<&Vec<String>>,
         file: Option<&str>,
         task_id: Option<&str>,
         depth: i32,
+        group: Option<&str>,
     ) -> Result<Vec<crate::types::WorkerInfo>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // Start with base query
             let mut sql = String::from(
                 "SELECT DISTINCT w.id, w.tags, w.max_claims, w.registered_at, w.last_heartbeat,
                         (SELECT COUNT(*) FROM tasks WHERE worker_id = w.id AND status = 'working') as claim_count,
                         (SELECT current_thought FROM tasks WHERE worker_id = w.id AND status = 'working' AND current_thought IS NOT NULL LIMIT 1) as current_thought,
-                        w.last_status, w.last_phase, w.workflow, w.overlays
+                        w.last_status, w.last_phase, w.workflow, w.overlays,
+                        (SELECT json_group_array(group_name) FROM
+                            (SELECT group_name FROM worker_groups WHERE worker_id = w.id ORDER BY group_name)) as groups,
+                        w.project
                  FROM workers w WHERE 1=1",
             );
             let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
@@ -497,6 +629,12 @@ impl Database {
                 params_vec.push(Box::new(f.to_string()));
             }
 
+            // Filter by group membership
+            if let Some(g) = group {
+                sql.push_str(" AND w.id IN (SELECT worker_id FROM worker_groups WHERE group_name = ?)");
+                params_vec.push(Box::new(g.to_string()));
+            }
+
             // Filter by related task (with depth traversal)
             if let Some(tid) = task_id {
                 // Get all related task IDs at the given depth
@@ -521,7 +659,7 @@ impl Database {
             let params_refs: Vec<&dyn rusqlite::ToSql> =
                 params_vec.iter().map(|b| b.as_ref()).collect();
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
             let workers: Vec<crate::types::WorkerInfo> = stmt
                 .query_map(params_refs.as_slice(), |row| {
                     let id: String = row.get(0)?;
@@ -535,13 +673,16 @@ impl Database {
                     let last_phase: Option<String> = row.get(8)?;
                     let workflow: Option<String> = row.get(9)?;
                     let overlays_json: Option<String> = row.get(10)?;
+                    let groups_json: String = row.get(11)?;
+                    let project: String = row.get(12)?;
 
-                    Ok((id, tags_json, max_claims, registered_at, last_heartbeat, claim_count, current_thought, last_status, last_phase, workflow, overlays_json))
+                    Ok((id, tags_json, max_claims, registered_at, last_heartbeat, claim_count, current_thought, last_status, last_phase, workflow, overlays_json, groups_json, project))
                 })?
                 .filter_map(|r| r.ok())
-                .map(|(id, tags_json, max_claims, registered_at, last_heartbeat, claim_count, current_thought, last_status, last_phase, workflow, overlays_json)| {
+                .map(|(id, tags_json, max_claims, registered_at, last_heartbeat, claim_count, current_thought, last_status, last_phase, workflow, overlays_json, groups_json, project)| {
                     let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
                     let overlays = parse_overlays(&overlays_json);
+                    let groups: Vec<String> = serde_json::from_str(&groups_json).unwrap_or_default();
                     crate::types::WorkerInfo {
                         id,
                         tags,
@@ -554,6 +695,8 @@ impl Database {
                         last_phase,
                         workflow,
                         overlays,
+                        groups,
+                        project,
                     }
                 })
                 .collect();
@@ -562,7 +705,7 @@ impl Database {
             let workers = if let Some(required_tags) = tags {
                 workers
                     .into_iter()
-                    .filter(|w| required_tags.iter().all(|t| w.tags.contains(t)))
+                    .filter(|w| required_tags.iter().all(|t| has_tag(&w.tags, t)))
                     .collect()
             } else {
                 workers
@@ -601,15 +744,17 @@ impl Database {
             for tid in &current_level {
                 let related: Vec<String> = if depth > 0 {
                     // Descendants: tasks where this task is the from_task_id (children, blocked tasks)
-                    let mut stmt = conn
-                        .prepare("SELECT to_task_id FROM dependencies WHERE from_task_id = ?1")?;
+                    let mut stmt = conn.prepare_cached(
+                        "SELECT to_task_id FROM dependencies WHERE from_task_id = ?1",
+                    )?;
                     stmt.query_map(params![tid], |row| row.get(0))?
                         .filter_map(|r| r.ok())
                         .collect()
                 } else {
                     // Ancestors: tasks where this task is the to_task_id (parents, blockers)
-                    let mut stmt = conn
-                        .prepare("SELECT from_task_id FROM dependencies WHERE to_task_id = ?1")?;
+                    let mut stmt = conn.prepare_cached(
+                        "SELECT from_task_id FROM dependencies WHERE to_task_id = ?1",
+                    )?;
                     stmt.query_map(params![tid], |row| row.get(0))?
                         .filter_map(|r| r.ok())
                         .collect()
@@ -633,9 +778,9 @@ impl Database {
     pub fn get_stale_workers(&self, timeout_seconds: i64) -> Result<Vec<Worker>> {
         let cutoff = now_ms() - (timeout_seconds * 1000);
 
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT id, tags, max_claims, registered_at, last_heartbeat, last_status, last_phase, workflow, overlays
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, tags, max_claims, registered_at, last_heartbeat, last_status, last_phase, workflow, overlays, project
                  FROM workers WHERE last_heartbeat < ?1",
             )?;
 
@@ -650,6 +795,7 @@ impl Database {
                     let last_phase: Option<String> = row.get(6)?;
                     let workflow: Option<String> = row.get(7)?;
                     let overlays_json: Option<String> = row.get(8)?;
+                    let project: String = row.get(9)?;
 
                     Ok((
                         id,
@@ -661,6 +807,7 @@ impl Database {
                         last_phase,
                         workflow,
                         overlays_json,
+                        project,
                     ))
                 })?
                 .filter_map(|r| r.ok())
@@ -675,6 +822,7 @@ impl Database {
                         last_phase,
                         workflow,
                         overlays_json,
+                        project,
                     )| {
                         let tags: Vec<String> =
                             serde_json::from_str(&tags_json).unwrap_or_default();
@@ -689,6 +837,7 @@ impl Database {
                             last_phase,
                             workflow,
                             overlays,
+                            project,
                         }
                     },
                 )
@@ -729,17 +878,155 @@ impl Database {
             files_released: total_files_released,
             final_status: final_status.to_string(),
             evicted_worker_ids,
+            tasks_reassigned: 0,
+            evictions: Vec::new(),
+        })
+    }
+
+    /// Evict a stale worker, applying a stale-claim policy to its claims.
+    ///
+    /// With `reassign`, each claim goes to the least-loaded worker in `reassign_to`
+    /// that may take it (tags and group), in the `assigned` state. Every other claim
+    /// is released with the policy's status. Escalation notes are left to the caller.
+    pub fn evict_worker_with_policy(
+        &self,
+        worker_id: &str,
+        policy: &StalePolicy,
+        reassign_to: &[String],
+        states_config: &StatesConfig,
+    ) -> Result<StaleEviction> {
+        let status = policy.release_status(states_config).to_string();
+        let can_reassign = policy.action == StaleAction::Reassign
+            && states_config.is_valid_state(ASSIGNED_STATE)
+            && !reassign_to.is_empty();
+
+        // Close claim_sequence records before the marks are deleted
+        let _ = self.release_worker_locks(worker_id);
+
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let now = now_ms();
+
+            let claimed: Vec<String> = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT id FROM tasks WHERE worker_id = ?1 AND deleted_at IS NULL ORDER BY claimed_at, id",
+                )?;
+                stmt.query_map(params![worker_id], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?
+            };
+
+            let mut reassigned = Vec::new();
+            if can_reassign {
+                let mut candidates = Vec::new();
+                for id in reassign_to {
+                    if let Some(worker) = get_worker_internal(&tx, id)? {
+                        let load: i64 = tx
+                            .prepare_cached(
+                                "SELECT COUNT(*) FROM tasks WHERE worker_id = ?1",
+                            )?
+                            .query_row(
+                                params![id],
+                                |row| row.get(0),
+                            )?;
+                        candidates.push((worker, load));
+                    }
+                }
+
+                let reason = format!("Reassigned from stale worker '{}'", worker_id);
+                for task_id in &claimed {
+                    let Some(task) = get_task_internal(&tx, task_id)? else {
+                        continue;
+                    };
+                    let mut best: Option<usize> = None;
+                    for (i, (candidate, load)) in candidates.iter().enumerate() {
+                        let tags_ok = task.needed_tags.iter().all(|t| has_tag(&candidate.tags, t))
+                            && (task.wanted_tags.is_empty()
+                                || task.wanted_optional
+                                || task.wanted_tags.iter().any(|t| has_tag(&candidate.tags, t)));
+                        let group_ok = match &task.needed_group {
+                            Some(group) => is_group_member(&tx, &candidate.id, group)?,
+                            None => true,
+                        };
+                        if tags_ok && group_ok && best.is_none_or(|b| *load < candidates[b].1) {
+                            best = Some(i);
+                        }
+                    }
+                    let Some(i) = best else {
+                        continue;
+                    };
+
+                    let new_owner = candidates[i].0.id.clone();
+                    record_state_transition(
+                        &tx,
+                        task_id,
+                        ASSIGNED_STATE,
+                        Some(&new_owner),
+                        Some(&reason),
+                        states_config,
+                    )?;
+                    tx.execute(
+                        "UPDATE tasks SET worker_id = ?1, claimed_at = ?2, status = ?3, updated_at = ?2
+                         WHERE id = ?4",
+                        params![&new_owner, now, ASSIGNED_STATE, task_id],
+                    )?;
+                    candidates[i].1 += 1;
+                    reassigned.push(Reassignment {
+                        task_id: task_id.clone(),
+                        worker_id: new_owner,
+                    });
+                }
+            }
+
+            // Release everything that was not reassigned
+            let reason = format!("Released from stale worker '{}'", worker_id);
+            let mut released = Vec::new();
+            for task_id in claimed {
+                if reassigned.iter().any(|r| r.task_id == task_id) {
+                    continue;
+                }
+                record_state_transition(
+                    &tx,
+                    &task_id,
+                    &status,
+                    Some(worker_id),
+                    Some(&reason),
+                    states_config,
+                )?;
+                tx.execute(
+                    "UPDATE tasks SET worker_id = NULL, claimed_at = NULL, status = ?1, updated_at = ?2
+                     WHERE id = ?3",
+                    params![&status, now, &task_id],
+                )?;
+                released.push(task_id);
+            }
+
+            let files_released = tx.execute(
+                "DELETE FROM file_locks WHERE worker_id = ?1",
+                params![worker_id],
+            )? as i32;
+            tx.execute("DELETE FROM workers WHERE id = ?1", params![worker_id])?;
+            tx.commit()?;
+
+            Ok(StaleEviction {
+                worker_id: worker_id.to_string(),
+                action: policy.action.as_str().to_string(),
+                status,
+                tasks_released: released,
+                reassigned,
+                files_released,
+                escalated_to: None,
+            })
         })
     }
 
     /// Get claim count for a worker.
     pub fn get_claim_count(&self, worker_id: &str) -> Result<i32> {
-        self.with_conn(|conn| {
-            let count: i32 = conn.query_row(
-                "SELECT COUNT(*) FROM tasks WHERE worker_id = ?1 AND status = 'working'",
-                params![worker_id],
-                |row| row.get(0),
-            )?;
+        self.with_read_conn(|conn| {
+            let count: i32 = conn
+                .prepare_cached(
+                    "SELECT COUNT(*) FROM tasks WHERE worker_id = ?1 AND status = 'working'",
+                )?
+                .query_row(params![worker_id], |row| row.get(0))?;
             Ok(count)
         })
     }
@@ -1,9 +1,11 @@
 //! Attachment storage operations.
 
 use super::{Database, now_ms};
-use crate::types::{Attachment, AttachmentMeta};
+use crate::types::{Attachment, AttachmentMeta, AttachmentUpload};
 use anyhow::{Result, anyhow};
 use rusqlite::params;
+use std::collections::HashSet;
+use ulid::Ulid;
 
 impl Database {
     /// Add an attachment to a task with auto-increment sequence per type.
@@ -18,8 +20,90 @@ impl Database {
         mime_type: Option<String>,
         file_path: Option<String>,
     ) -> Result<i32> {
+        self.insert_attachment(
+            task_id,
+            attachment_type,
+            name,
+            content,
+            mime_type,
+            file_path,
+            None,
+            false,
+        )
+        .map(|(sequence, _)| sequence)
+    }
+
+    /// Add an attachment whose content is a content-addressed blob at `blob_path`.
+    /// Only metadata (digest and size) is stored in the database.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_blob_attachment(
+        &self,
+        task_id: &str,
+        attachment_type: String,
+        name: String,
+        mime_type: Option<String>,
+        blob_path: String,
+        content_hash: String,
+        size_bytes: i64,
+    ) -> Result<i32> {
+        self.insert_attachment(
+            task_id,
+            attachment_type,
+            name,
+            String::new(),
+            mime_type,
+            Some(blob_path),
+            Some((content_hash, size_bytes)),
+            false,
+        )
+        .map(|(sequence, _)| sequence)
+    }
+
+    /// Add a new version of the attachment named `name` on (task_id, attachment_type).
+    /// The row gets the next sequence as usual and a version one higher than the
+    /// latest existing version of that name. `blob` carries (content_hash, size_bytes)
+    /// for blob content. Returns (sequence, version).
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_attachment_version(
+        &self,
+        task_id: &str,
+        attachment_type: String,
+        name: String,
+        content: String,
+        mime_type: Option<String>,
+        file_path: Option<String>,
+        blob: Option<(String, i64)>,
+    ) -> Result<(i32, i32)> {
+        self.insert_attachment(
+            task_id,
+            attachment_type,
+            name,
+            content,
+            mime_type,
+            file_path,
+            blob,
+            true,
+        )
+    }
+
+    /// Insert an attachment row; `blob` carries (content_hash, size_bytes) for blobs.
+    /// With `versioned`, the row becomes the next version of its name.
+    /// Returns (sequence, version).
+    #[allow(clippy::too_many_arguments)]
+    fn insert_attachment(
+        &self,
+        task_id: &str,
+        attachment_type: String,
+        name: String,
+        content: String,
+        mime_type: Option<String>,
+        file_path: Option<String>,
+        blob: Option<(String, i64)>,
+        versioned: bool,
+    ) -> Result<(i32, i32)> {
         let now = now_ms();
         let mime_type = mime_type.unwrap_or_else(|| "text/plain".to_string());
+        let (content_hash, size_bytes) = blob.unzip();
 
         self.with_conn_mut(|conn| {
             let tx = conn.transaction()?;
@@ -45,9 +129,22 @@ impl Database {
             )?;
             let sequence = max_seq.unwrap_or(-1) + 1;
 
+            let version = if versioned {
+                let max_version: Option<i32> = tx.query_row(
+                    "SELECT MAX(version) FROM attachments
+                     WHERE task_id = ?1 AND attachment_type = ?2 AND name = ?3",
+                    params![task_id, attachment_type, name],
+                    |row| row.get(0),
+                )?;
+                max_version.unwrap_or(0) + 1
+            } else {
+                1
+            };
+
             tx.execute(
-                "INSERT INTO attachments (task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                "INSERT INTO attachments (task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at,
+                                          content_hash, size_bytes, version)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
                 params![
                     task_id,
                     attachment_type,
@@ -57,11 +154,28 @@ impl Database {
                     content,
                     file_path,
                     now,
+                    content_hash,
+                    size_bytes,
+                    version,
                 ],
             )?;
 
             tx.commit()?;
-            Ok(sequence)
+            Ok((sequence, version))
+        })
+    }
+
+    /// Content hashes of all blobs still referenced by an attachment.
+    pub fn get_referenced_blob_hashes(&self) -> Result<HashSet<String>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT DISTINCT content_hash FROM attachments WHERE content_hash IS NOT NULL",
+            )?;
+            let hashes = stmt
+                .query_map([], |row| row.get(0))?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(hashes)
         })
     }
 
@@ -72,9 +186,10 @@ impl Database {
         task_id: &str,
         include_content: bool,
     ) -> Result<Vec<Attachment>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at,
+                        content_hash, size_bytes, version
                  FROM attachments WHERE task_id = ?1 ORDER BY attachment_type, sequence",
             )?;
 
@@ -88,6 +203,9 @@ impl Database {
                     let content: String = row.get(5)?;
                     let file_path: Option<String> = row.get(6)?;
                     let created_at: i64 = row.get(7)?;
+                    let content_hash: Option<String> = row.get(8)?;
+                    let size_bytes: Option<i64> = row.get(9)?;
+                    let version: i32 = row.get(10)?;
 
                     Ok(Attachment {
                         task_id,
@@ -102,6 +220,9 @@ impl Database {
                         },
                         file_path,
                         created_at,
+                        content_hash,
+                        size_bytes,
+                        version,
                     })
                 })?
                 .filter_map(|r| r.ok())
@@ -125,10 +246,11 @@ impl Database {
         type_pattern: Option<&str>,
         mime_pattern: Option<&str>,
     ) -> Result<Vec<AttachmentMeta>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // Build query with optional filters
             let mut sql = String::from(
-                "SELECT task_id, attachment_type, sequence, name, mime_type, file_path, created_at
+                "SELECT task_id, attachment_type, sequence, name, mime_type, file_path, created_at,
+                        content_hash, size_bytes, version
                  FROM attachments WHERE task_id = ?1",
             );
 
@@ -149,7 +271,7 @@ impl Database {
 
             sql.push_str(" ORDER BY attachment_type, sequence");
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
 
             // Bind parameters based on which filters are present
             let attachments: Vec<AttachmentMeta> = match (&type_like, mime_pattern) {
@@ -193,6 +315,9 @@ impl Database {
             mime_type: row.get(4)?,
             file_path: row.get(5)?,
             created_at: row.get(6)?,
+            content_hash: row.get(7)?,
+            size_bytes: row.get(8)?,
+            version: row.get(9)?,
         })
     }
 
@@ -205,9 +330,10 @@ impl Database {
         attachment_type: &str,
         sequence: i32,
     ) -> Result<Option<Attachment>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at,
+                        content_hash, size_bytes, version
                  FROM attachments WHERE task_id = ?1 AND attachment_type = ?2 AND sequence = ?3",
             )?;
 
@@ -220,6 +346,9 @@ impl Database {
                 let content: String = row.get(5)?;
                 let file_path: Option<String> = row.get(6)?;
                 let created_at: i64 = row.get(7)?;
+                let content_hash: Option<String> = row.get(8)?;
+                let size_bytes: Option<i64> = row.get(9)?;
+                let version: i32 = row.get(10)?;
 
                 Ok(Attachment {
                     task_id,
@@ -230,6 +359,9 @@ impl Database {
                     content,
                     file_path,
                     created_at,
+                    content_hash,
+                    size_bytes,
+                    version,
                 })
             });
 
@@ -247,8 +379,8 @@ impl Database {
         task_id: &str,
         attachment_type: &str,
     ) -> Result<Vec<String>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT file_path FROM attachments WHERE task_id = ?1 AND attachment_type = ?2 AND file_path IS NOT NULL",
             )?;
 
@@ -278,8 +410,28 @@ impl Database {
         })
     }
 
+    /// Point an attachment at a different file.
+    pub fn set_attachment_file_path(
+        &self,
+        task_id: &str,
+        attachment_type: &str,
+        sequence: i32,
+        file_path: &str,
+    ) -> Result<bool> {
+        self.with_conn(|conn| {
+            let updated = conn.execute(
+                "UPDATE attachments SET file_path = ?4
+                 WHERE task_id = ?1 AND attachment_type = ?2 AND sequence = ?3",
+                params![task_id, attachment_type, sequence, file_path],
+            )?;
+
+            Ok(updated > 0)
+        })
+    }
+
     /// Delete all attachments of a given type (for replace behavior).
-    /// Returns the file_paths of deleted attachments (for cleanup).
+    /// Returns the file_paths of deleted attachments (for cleanup). Shared blobs are
+    /// excluded; they are garbage-collected once unreferenced.
     pub fn delete_attachments_by_type(
         &self,
         task_id: &str,
@@ -288,8 +440,9 @@ impl Database {
         self.with_conn(|conn| {
             // First get all file_paths
             let file_paths = {
-                let mut stmt = conn.prepare(
-                    "SELECT file_path FROM attachments WHERE task_id = ?1 AND attachment_type = ?2 AND file_path IS NOT NULL",
+                let mut stmt = conn.prepare_cached(
+                    "SELECT file_path FROM attachments
+                     WHERE task_id = ?1 AND attachment_type = ?2 AND file_path IS NOT NULL AND content_hash IS NULL",
                 )?;
                 stmt.query_map(params![task_id, attachment_type], |row| row.get(0))?
                     .filter_map(|r| r.ok())
@@ -307,7 +460,7 @@ impl Database {
     }
 
     /// Delete all attachments of a given type and return count plus file_paths.
-    /// Returns (deleted_count, file_paths).
+    /// Returns (deleted_count, file_paths), excluding shared blobs.
     pub fn delete_attachments_by_type_ex(
         &self,
         task_id: &str,
@@ -316,8 +469,9 @@ impl Database {
         self.with_conn(|conn| {
             // First get all file_paths
             let file_paths = {
-                let mut stmt = conn.prepare(
-                    "SELECT file_path FROM attachments WHERE task_id = ?1 AND attachment_type = ?2 AND file_path IS NOT NULL",
+                let mut stmt = conn.prepare_cached(
+                    "SELECT file_path FROM attachments
+                     WHERE task_id = ?1 AND attachment_type = ?2 AND file_path IS NOT NULL AND content_hash IS NULL",
                 )?;
                 stmt.query_map(params![task_id, attachment_type], |row| row.get(0))?
                     .filter_map(|r| r.ok())
@@ -333,4 +487,282 @@ impl Database {
             Ok((deleted, file_paths))
         })
     }
+
+    /// Start a chunked upload for a task. Returns the new upload.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_attachment_upload(
+        &self,
+        task_id: &str,
+        attachment_type: &str,
+        name: &str,
+        mime_type: &str,
+        mode: &str,
+        expected_size: Option<i64>,
+        expected_hash: Option<&str>,
+    ) -> Result<AttachmentUpload> {
+        let now = now_ms();
+        let id = Ulid::new().to_string();
+
+        self.with_conn(|conn| {
+            let exists: bool = conn
+                .query_row(
+                    "SELECT 1 FROM tasks WHERE id = ?1",
+                    params![task_id],
+                    |_| Ok(true),
+                )
+                .unwrap_or(false);
+            if !exists {
+                return Err(anyhow!("Task not found"));
+            }
+
+            conn.execute(
+                "INSERT INTO attachment_uploads
+                 (id, task_id, attachment_type, name, mime_type, mode, expected_size, expected_hash, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
+                params![
+                    id,
+                    task_id,
+                    attachment_type,
+                    name,
+                    mime_type,
+                    mode,
+                    expected_size,
+                    expected_hash,
+                    now,
+                ],
+            )?;
+            Ok(())
+        })?;
+
+        self.get_attachment_upload(&id)?
+            .ok_or_else(|| anyhow!("Upload not found after insert"))
+    }
+
+    /// Get an in-progress upload by ID.
+    pub fn get_attachment_upload(&self, upload_id: &str) -> Result<Option<AttachmentUpload>> {
+        self.with_read_conn(|conn| {
+            let result = conn.query_row(
+                "SELECT id, task_id, attachment_type, name, mime_type, mode, expected_size, expected_hash,
+                        received_bytes, next_chunk, created_at, updated_at
+                 FROM attachment_uploads WHERE id = ?1",
+                params![upload_id],
+                |row| {
+                    Ok(AttachmentUpload {
+                        id: row.get(0)?,
+                        task_id: row.get(1)?,
+                        attachment_type: row.get(2)?,
+                        name: row.get(3)?,
+                        mime_type: row.get(4)?,
+                        mode: row.get(5)?,
+                        expected_size: row.get(6)?,
+                        expected_hash: row.get(7)?,
+                        received_bytes: row.get(8)?,
+                        next_chunk: row.get(9)?,
+                        created_at: row.get(10)?,
+                        updated_at: row.get(11)?,
+                    })
+                },
+            );
+
+            match result {
+                Ok(upload) => Ok(Some(upload)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
+    /// Claim chunk `chunk_index` of an upload, adding `len` to the received byte count.
+    /// Returns false if the upload does not exist or expects a different chunk, so two
+    /// callers can never append the same chunk.
+    pub fn advance_attachment_upload(
+        &self,
+        upload_id: &str,
+        chunk_index: i64,
+        len: i64,
+    ) -> Result<bool> {
+        self.with_conn(|conn| {
+            let updated = conn.execute(
+                "UPDATE attachment_uploads
+                 SET next_chunk = next_chunk + 1, received_bytes = received_bytes + ?3, updated_at = ?4
+                 WHERE id = ?1 AND next_chunk = ?2",
+                params![upload_id, chunk_index, len, now_ms()],
+            )?;
+            Ok(updated > 0)
+        })
+    }
+
+    /// Delete an upload record. Returns true if it existed.
+    pub fn delete_attachment_upload(&self, upload_id: &str) -> Result<bool> {
+        self.with_conn(|conn| {
+            let deleted = conn.execute(
+                "DELETE FROM attachment_uploads WHERE id = ?1",
+                params![upload_id],
+            )?;
+            Ok(deleted > 0)
+        })
+    }
+
+    /// Delete uploads with no activity since `idle_before` (ms timestamp).
+    /// Returns the IDs of the removed uploads so their partial files can be deleted.
+    pub fn reap_stale_attachment_uploads(&self, idle_before: i64) -> Result<Vec<String>> {
+        self.with_conn(|conn| {
+            let ids: Vec<String> = {
+                let mut stmt =
+                    conn.prepare_cached("SELECT id FROM attachment_uploads WHERE updated_at < ?1")?;
+                stmt.query_map(params![idle_before], |row| row.get(0))?
+                    .filter_map(|r| r.ok())
+                    .collect()
+            };
+            conn.execute(
+                "DELETE FROM attachment_uploads WHERE updated_at < ?1",
+                params![idle_before],
+            )?;
+            Ok(ids)
+        })
+    }
+
+    /// Get a version of a named attachment: `version`, or the latest when `None`.
+    pub fn get_attachment_version(
+        &self,
+        task_id: &str,
+        attachment_type: &str,
+        name: &str,
+        version: Option<i32>,
+    ) -> Result<Option<Attachment>> {
+        let sequence: Option<i32> = self.with_read_conn(|conn| {
+            let result = conn.query_row(
+                "SELECT sequence FROM attachments
+                 WHERE task_id = ?1 AND attachment_type = ?2 AND name = ?3
+                   AND (?4 IS NULL OR version = ?4)
+                 ORDER BY version DESC, sequence DESC LIMIT 1",
+                params![task_id, attachment_type, name, version],
+                |row| row.get(0),
+            );
+            match result {
+                Ok(sequence) => Ok(Some(sequence)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })?;
+
+        match sequence {
+            Some(sequence) => self.get_attachment(task_id, attachment_type, sequence),
+            None => Ok(None),
+        }
+    }
+
+    /// Delete all but the newest `keep` versions of a named attachment.
+    /// Returns the file_paths of deleted non-blob attachments (for cleanup).
+    pub fn prune_attachment_versions(
+        &self,
+        task_id: &str,
+        attachment_type: &str,
+        name: &str,
+        keep: usize,
+    ) -> Result<Vec<String>> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let stale: Vec<(i32, Option<String>, bool)> = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT sequence, file_path, content_hash IS NOT NULL FROM attachments
+                     WHERE task_id = ?1 AND attachment_type = ?2 AND name = ?3
+                     ORDER BY version DESC, sequence DESC LIMIT -1 OFFSET ?4",
+                )?;
+                stmt.query_map(
+                    params![task_id, attachment_type, name, keep as i64],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )?
+                .filter_map(|r| r.ok())
+                .collect()
+            };
+
+            for (sequence, _, _) in &stale {
+                tx.execute(
+                    "DELETE FROM attachments WHERE task_id = ?1 AND attachment_type = ?2 AND sequence = ?3",
+                    params![task_id, attachment_type, sequence],
+                )?;
+            }
+            tx.commit()?;
+
+            Ok(stale
+                .into_iter()
+                .filter(|(_, _, is_blob)| !is_blob)
+                .filter_map(|(_, file_path, _)| file_path)
+                .collect())
+        })
+    }
+
+    /// Total attachment bytes stored by the server: blob sizes plus inline content.
+    /// Optionally scoped to one task and/or one attachment type.
+    pub fn get_attachment_usage(
+        &self,
+        task_id: Option<&str>,
+        attachment_type: Option<&str>,
+    ) -> Result<i64> {
+        self.with_read_conn(|conn| {
+            let used: i64 = conn.query_row(
+                "SELECT COALESCE(SUM(COALESCE(size_bytes, length(CAST(content AS BLOB)))), 0)
+                 FROM attachments
+                 WHERE (?1 IS NULL OR task_id = ?1) AND (?2 IS NULL OR attachment_type = ?2)",
+                params![task_id, attachment_type],
+                |row| row.get(0),
+            )?;
+            Ok(used)
+        })
+    }
+
+    /// Attachments of a type that fall outside a retention policy: all but the
+    /// newest `keep_last` per task, plus any created before `created_before`.
+    /// Optionally scoped to one task.
+    pub fn get_expired_attachments(
+        &self,
+        task_id: Option<&str>,
+        attachment_type: &str,
+        keep_last: Option<usize>,
+        created_before: Option<i64>,
+    ) -> Result<Vec<AttachmentMeta>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT task_id, attachment_type, sequence, name, mime_type, file_path, created_at,
+                        content_hash, size_bytes, version
+                 FROM (
+                     SELECT *, ROW_NUMBER() OVER (PARTITION BY task_id ORDER BY sequence DESC) AS recency
+                     FROM attachments
+                     WHERE attachment_type = ?1 AND (?2 IS NULL OR task_id = ?2)
+                 )
+                 WHERE (?3 IS NOT NULL AND recency > ?3) OR (?4 IS NOT NULL AND created_at < ?4)
+                 ORDER BY task_id, sequence",
+            )?;
+            let expired = stmt
+                .query_map(
+                    params![
+                        attachment_type,
+                        task_id,
+                        keep_last.map(|k| k as i64),
+                        created_before
+                    ],
+                    Self::map_attachment_meta,
+                )?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(expired)
+        })
+    }
+
+    /// Delete the given attachments in one transaction. Returns the number deleted.
+    pub fn delete_attachments(&self, attachments: &[AttachmentMeta]) -> Result<usize> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let mut deleted = 0;
+            for a in attachments {
+                deleted += tx.execute(
+                    "DELETE FROM attachments WHERE task_id = ?1 AND attachment_type = ?2 AND sequence = ?3",
+                    params![a.task_id, a.attachment_type, a.sequence],
+                )?;
+            }
+            tx.commit()?;
+            Ok(deleted)
+        })
+    }
 }
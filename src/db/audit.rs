@@ -0,0 +1,113 @@
+//! Audit log of mutating tool calls.
+
+use super::{Database, now_ms};
+use crate::types::AuditEntry;
+use anyhow::Result;
+use rusqlite::{Row, params};
+
+/// Filters for reading the audit log. Entries come back newest first.
+#[derive(Debug, Default)]
+pub struct AuditQuery<'a> {
+    pub worker_id: Option<&'a str>,
+    pub tool: Option<&'a str>,
+    /// Only calls that named or created this task.
+    pub task_id: Option<&'a str>,
+    /// `ok`, `error` (any failure), or a specific error code.
+    pub outcome: Option<&'a str>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: Option<i32>,
+    pub offset: i32,
+}
+
+/// Parse an `audit_log` row selected with `SELECT *`.
+pub(crate) fn parse_audit_row(row: &Row) -> rusqlite::Result<AuditEntry> {
+    let task_ids: String = row.get("task_ids")?;
+    Ok(AuditEntry {
+        id: row.get("id")?,
+        worker_id: row.get("worker_id")?,
+        tool: row.get("tool")?,
+        args_hash: row.get("args_hash")?,
+        task_ids: serde_json::from_str(&task_ids).unwrap_or_default(),
+        outcome: row.get("outcome")?,
+        error: row.get("error")?,
+        timestamp: row.get("timestamp")?,
+    })
+}
+
+impl Database {
+    /// Record one mutating tool call. Returns the entry ID.
+    pub fn record_audit(
+        &self,
+        worker_id: Option<&str>,
+        tool: &str,
+        args_hash: &str,
+        task_ids: &[String],
+        outcome: &str,
+        error: Option<&str>,
+    ) -> Result<i64> {
+        let task_ids = serde_json::to_string(task_ids)?;
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO audit_log (worker_id, tool, args_hash, task_ids, outcome, error, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![worker_id, tool, args_hash, task_ids, outcome, error, now_ms()],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
+    }
+
+    /// Read audit entries matching all given filters, newest first.
+    pub fn get_audit_log(&self, query: AuditQuery<'_>) -> Result<Vec<AuditEntry>> {
+        self.with_read_conn(|conn| {
+            let mut sql = String::from("SELECT * FROM audit_log WHERE 1 = 1");
+            let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+            if let Some(w) = query.worker_id {
+                sql.push_str(" AND worker_id = ?");
+                params_vec.push(Box::new(w.to_string()));
+            }
+            if let Some(t) = query.tool {
+                sql.push_str(" AND tool = ?");
+                params_vec.push(Box::new(t.to_string()));
+            }
+            if let Some(id) = query.task_id {
+                sql.push_str(" AND EXISTS (SELECT 1 FROM json_each(task_ids) WHERE value = ?)");
+                params_vec.push(Box::new(id.to_string()));
+            }
+            match query.outcome {
+                Some("error") => sql.push_str(" AND outcome != 'ok'"),
+                Some(o) => {
+                    sql.push_str(" AND outcome = ?");
+                    params_vec.push(Box::new(o.to_string()));
+                }
+                None => {}
+            }
+            if let Some(since) = query.since {
+                sql.push_str(" AND timestamp >= ?");
+                params_vec.push(Box::new(since));
+            }
+            if let Some(until) = query.until {
+                sql.push_str(" AND timestamp <= ?");
+                params_vec.push(Box::new(until));
+            }
+
+            sql.push_str(" ORDER BY id DESC");
+            if let Some(l) = query.limit {
+                sql.push_str(&format!(" LIMIT {}", l));
+            }
+            if query.offset > 0 {
+                sql.push_str(&format!(" OFFSET {}", query.offset));
+            }
+
+            let params_refs: Vec<&dyn rusqlite::ToSql> =
+                params_vec.iter().map(|b| b.as_ref()).collect();
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let entries = stmt
+                .query_map(params_refs.as_slice(), parse_audit_row)?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(entries)
+        })
+    }
+}
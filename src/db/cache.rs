@@ -0,0 +1,142 @@
+//! Cache for expensive aggregate reads.
+//!
+//! `query://stats/summary`, `query://tasks/blocked`, and the dashboard overview
+//! are polled far more often than the data under them changes. Their results
+//! are cached per key and reused until one of these happens:
+//!
+//! - a mutation of a kind the entry depends on is reported through
+//!   `AggregateCache::invalidate` (the same `MutationKind` events that drive
+//!   resource subscription notifications),
+//! - the change feed head moves, which catches writes made by other processes
+//!   sharing the database and in-process writes that report no event,
+//! - the entry is older than `CACHE_TTL`, for views that change with time alone
+//!   (dependency lags, heartbeat windows) or with tables the feed does not track.
+
+use super::Database;
+use crate::subscriptions::MutationKind;
+use anyhow::Result;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Longest time an entry is reused without any invalidating event.
+pub const CACHE_TTL: Duration = Duration::from_secs(5);
+
+struct Entry {
+    value: Arc<dyn Any + Send + Sync>,
+    /// Generations of the entry's mutation kinds when it was computed.
+    generations: Vec<u64>,
+    feed_head: i64,
+    computed_at: Instant,
+}
+
+#[derive(Default)]
+struct CacheState {
+    generations: HashMap<MutationKind, u64>,
+    entries: HashMap<String, Entry>,
+}
+
+impl CacheState {
+    fn generations(&self, deps: &[MutationKind]) -> Vec<u64> {
+        deps.iter()
+            .map(|kind| self.generations.get(kind).copied().unwrap_or(0))
+            .collect()
+    }
+}
+
+/// Aggregate results keyed by name, invalidated by mutation events.
+#[derive(Default)]
+pub struct AggregateCache {
+    state: Mutex<CacheState>,
+}
+
+impl AggregateCache {
+    /// Invalidate every entry depending on any of `mutations`.
+    pub fn invalidate(&self, mutations: &[MutationKind]) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        for kind in mutations {
+            *state.generations.entry(*kind).or_default() += 1;
+        }
+    }
+
+    /// Drop every entry, e.g. after the configuration they were computed with changed.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.entries.clear();
+    }
+
+    /// The cached value of `key` if it is still valid at `feed_head`.
+    fn lookup<T: Clone + 'static>(
+        &self,
+        key: &str,
+        deps: &[MutationKind],
+        feed_head: i64,
+    ) -> Option<T> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = state.entries.get(key)?;
+        let fresh = entry.feed_head == feed_head
+            && entry.computed_at.elapsed() < CACHE_TTL
+            && entry.generations == state.generations(deps);
+        if !fresh {
+            return None;
+        }
+        entry.value.downcast_ref::<T>().cloned()
+    }
+}
+
+impl Database {
+    /// Cache of aggregate reads shared by everything using this database.
+    pub fn aggregates(&self) -> &AggregateCache {
+        &self.aggregates
+    }
+
+    /// ID of the newest change feed entry (0 when the feed is empty).
+    pub fn change_feed_head(&self) -> Result<i64> {
+        self.with_read_conn(|conn| {
+            Ok(conn
+                .prepare_cached("SELECT COALESCE(MAX(id), 0) FROM change_feed")?
+                .query_row([], |row| row.get(0))?)
+        })
+    }
+
+    /// Return the cached value of `key`, or compute and cache it. `deps` are the
+    /// mutation kinds that can change the result.
+    pub fn cached<T, F>(&self, key: &str, deps: &[MutationKind], compute: F) -> Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Result<T>,
+    {
+        let feed_head = self.change_feed_head()?;
+        if let Some(value) = self.aggregates.lookup(key, deps, feed_head) {
+            return Ok(value);
+        }
+
+        // Snapshot generations first so a mutation during compute invalidates the result
+        let generations = {
+            let state = self
+                .aggregates
+                .state
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            state.generations(deps)
+        };
+        let value = compute()?;
+
+        let mut state = self
+            .aggregates
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        state.entries.insert(
+            key.to_string(),
+            Entry {
+                value: Arc::new(value.clone()),
+                generations,
+                feed_head,
+                computed_at: Instant::now(),
+            },
+        );
+        Ok(value)
+    }
+}
@@ -0,0 +1,135 @@
+//! Field-level change history of task updates.
+
+use super::{Database, now_ms};
+use crate::types::{Task, TaskChange};
+use anyhow::Result;
+use rusqlite::{Connection, Row, params};
+use serde_json::{Map, Value, json};
+
+/// Prefix of custom field names in `task_changes.field`.
+pub const CUSTOM_FIELD_PREFIX: &str = "fields.";
+
+/// The task columns an update can change, with their current values.
+fn tracked_values(task: &Task) -> [(&'static str, Value); 16] {
+    [
+        ("title", json!(task.title)),
+        ("description", json!(task.description)),
+        ("status", json!(task.status)),
+        ("phase", json!(task.phase)),
+        ("priority", json!(task.priority)),
+        ("worker_id", json!(task.worker_id)),
+        ("tags", json!(task.tags)),
+        ("needed_tags", json!(task.needed_tags)),
+        ("wanted_tags", json!(task.wanted_tags)),
+        ("wanted_optional", json!(task.wanted_optional)),
+        ("needed_group", json!(task.needed_group)),
+        ("required_child_tags", json!(task.required_child_tags)),
+        ("points", json!(task.points)),
+        ("time_estimate_ms", json!(task.time_estimate_ms)),
+        ("blocked_reason", json!(task.blocked_reason)),
+        ("blocked_note", json!(task.blocked_note)),
+    ]
+}
+
+/// Fields that differ between two versions of a task, as (field, old, new).
+/// Task columns come first in a fixed order, then custom fields by name.
+pub fn diff_task(
+    before: &Task,
+    before_fields: &Map<String, Value>,
+    after: &Task,
+    after_fields: &Map<String, Value>,
+) -> Vec<(String, Value, Value)> {
+    let mut changes: Vec<(String, Value, Value)> = tracked_values(before)
+        .into_iter()
+        .zip(tracked_values(after))
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((field, old), (_, new))| (field.to_string(), old, new))
+        .collect();
+
+    let mut names: Vec<&String> = before_fields.keys().chain(after_fields.keys()).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let old = before_fields.get(name).cloned().unwrap_or(Value::Null);
+        let new = after_fields.get(name).cloned().unwrap_or(Value::Null);
+        if old != new {
+            changes.push((format!("{}{}", CUSTOM_FIELD_PREFIX, name), old, new));
+        }
+    }
+    changes
+}
+
+/// Record one field change of a task. Null values are stored as NULL.
+pub(crate) fn insert_task_change(
+    conn: &Connection,
+    task_id: &str,
+    worker_id: Option<&str>,
+    field: &str,
+    old: &Value,
+    new: &Value,
+    timestamp: i64,
+) -> Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO task_changes (task_id, worker_id, field, old_value, new_value, timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    let old = (!old.is_null()).then(|| old.to_string());
+    let new = (!new.is_null()).then(|| new.to_string());
+    stmt.execute(params![task_id, worker_id, field, old, new, timestamp])?;
+    Ok(())
+}
+
+/// Parse a `task_changes` row selected with `SELECT *`.
+pub(crate) fn parse_change_row(row: &Row) -> rusqlite::Result<TaskChange> {
+    let parse = |s: Option<String>| {
+        s.and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or(Value::Null)
+    };
+    Ok(TaskChange {
+        id: row.get("id")?,
+        task_id: row.get("task_id")?,
+        worker_id: row.get("worker_id")?,
+        field: row.get("field")?,
+        old_value: parse(row.get("old_value")?),
+        new_value: parse(row.get("new_value")?),
+        timestamp: row.get("timestamp")?,
+    })
+}
+
+impl Database {
+    /// Record the fields an update changed. Returns the number of rows written.
+    pub fn record_task_changes(
+        &self,
+        worker_id: Option<&str>,
+        before: &Task,
+        before_fields: &Map<String, Value>,
+        after: &Task,
+        after_fields: &Map<String, Value>,
+    ) -> Result<usize> {
+        let changes = diff_task(before, before_fields, after, after_fields);
+        if changes.is_empty() {
+            return Ok(0);
+        }
+        let now = now_ms();
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            for (field, old, new) in &changes {
+                insert_task_change(&tx, &after.id, worker_id, field, old, new, now)?;
+            }
+            tx.commit()?;
+            Ok(changes.len())
+        })
+    }
+
+    /// Field changes of a task, oldest first.
+    pub fn get_task_changes(&self, task_id: &str) -> Result<Vec<TaskChange>> {
+        self.with_read_conn(|conn| {
+            let mut stmt =
+                conn.prepare_cached("SELECT * FROM task_changes WHERE task_id = ?1 ORDER BY id")?;
+            let changes = stmt
+                .query_map(params![task_id], parse_change_row)?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(changes)
+        })
+    }
+}
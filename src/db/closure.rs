@@ -0,0 +1,38 @@
+//! Transitive closure of the `contains` hierarchy.
+//!
+//! `task_closure` holds one row per (ancestor, descendant) pair with the
+//! distance between them, so tree reads, ancestor chains, subtree rollups, and
+//! cascades are single indexed lookups rather than recursive walks. Triggers on
+//! `dependencies` (migration V026) keep it current; `rebuild_task_closure`
+//! recomputes it from the edges for repair.
+
+use super::Database;
+use anyhow::Result;
+
+impl Database {
+    /// Recompute `task_closure` from the `contains` edges. Returns the number
+    /// of pairs written.
+    pub fn rebuild_task_closure(&self) -> Result<usize> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM task_closure", [])?;
+            let pairs = tx.execute(
+                "INSERT INTO task_closure (ancestor_id, descendant_id, depth)
+                 WITH RECURSIVE pairs(ancestor_id, descendant_id, depth) AS (
+                     SELECT from_task_id, to_task_id, 1 FROM dependencies
+                     WHERE dep_type = 'contains'
+                     UNION
+                     SELECT p.ancestor_id, d.to_task_id, p.depth + 1
+                     FROM pairs p
+                     INNER JOIN dependencies d ON d.from_task_id = p.descendant_id
+                     WHERE d.dep_type = 'contains'
+                 )
+                 SELECT ancestor_id, descendant_id, MIN(depth) FROM pairs
+                 GROUP BY ancestor_id, descendant_id",
+                [],
+            )?;
+            tx.commit()?;
+            Ok(pairs)
+        })
+    }
+}
@@ -3,8 +3,10 @@
 //! These methods provide efficient queries for the web dashboard UI.
 
 use super::Database;
+use crate::config::StatesConfig;
 use anyhow::Result;
 use rusqlite::params;
+use serde::Serialize;
 use std::collections::HashMap;
 
 /// Simple task info for dashboard display.
@@ -120,12 +122,15 @@ pub struct ActivityStats {
     pub events_by_status: HashMap<String, i64>,
 }
 
-/// Simple worker info for dashboard display.
+/// A connected worker's liveness and current work, for the presence panel.
 #[derive(Debug, Clone)]
-pub struct DashboardWorker {
+pub struct AgentPresence {
     pub id: String,
+    pub last_heartbeat: i64,
+    /// Thought of the most recently claimed task that has one.
     pub current_thought: Option<String>,
-    pub claim_count: i32,
+    /// Tasks the worker is working on, most recently claimed first.
+    pub claims: Vec<WorkerClaimedTask>,
 }
 
 /// Extended task info for worker detail view.
@@ -137,10 +142,38 @@ pub struct WorkerClaimedTask {
     pub current_thought: Option<String>,
 }
 
+/// A task recently completed by a worker, for the agent detail page.
+#[derive(Debug, Clone)]
+pub struct WorkerCompletion {
+    pub task_id: String,
+    pub title: String,
+    pub completed_at: i64,
+    pub time_actual_ms: Option<i64>,
+    pub cost_usd: f64,
+}
+
+/// An entry in a worker's thought stream.
+#[derive(Debug, Clone)]
+pub struct WorkerThought {
+    pub task_id: Option<String>,
+    pub thought: String,
+    pub created_at: i64,
+}
+
+/// Cost and metric totals over every task a worker has worked on.
+#[derive(Debug, Clone, Default)]
+pub struct WorkerMetrics {
+    pub task_count: i64,
+    pub completed_count: i64,
+    pub total_cost_usd: f64,
+    pub total_time_ms: i64,
+    pub metrics: [i64; 8],
+}
+
 impl Database {
     /// Get task statistics for the dashboard (total, working, completed).
     pub fn get_task_stats(&self) -> Result<(i64, i64, i64)> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let total: i64 = conn.query_row(
                 "SELECT COUNT(*) FROM tasks WHERE deleted_at IS NULL",
                 [],
@@ -165,7 +198,7 @@ impl Database {
 
     /// Get count of active workers (those with recent heartbeats).
     pub fn get_active_worker_count(&self) -> Result<i64> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // Consider workers active if heartbeat within last 5 minutes
             let cutoff = super::now_ms() - (5 * 60 * 1000);
             let count: i64 = conn.query_row(
@@ -179,8 +212,8 @@ impl Database {
 
     /// Get recent tasks for dashboard display.
     pub fn get_recent_tasks(&self, limit: i32) -> Result<Vec<DashboardTask>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT id, title, status, priority
                  FROM tasks
                  WHERE deleted_at IS NULL
@@ -208,29 +241,50 @@ impl Database {
         })
     }
 
-    /// Get active workers for dashboard display.
-    pub fn get_active_workers(&self) -> Result<Vec<DashboardWorker>> {
-        self.with_conn(|conn| {
-            // Consider workers active if heartbeat within last 5 minutes
-            let cutoff = super::now_ms() - (5 * 60 * 1000);
-
-            let mut stmt = conn.prepare(
-                "SELECT w.id,
-                        (SELECT current_thought FROM tasks WHERE worker_id = w.id AND status = 'working' AND current_thought IS NOT NULL LIMIT 1),
-                        (SELECT COUNT(*) FROM tasks WHERE worker_id = w.id AND status = 'working')
-                 FROM workers w
-                 WHERE w.last_heartbeat > ?1
-                 ORDER BY w.last_heartbeat DESC"
+    /// Get every connected worker with its heartbeat and claimed tasks for the
+    /// presence panel, longest-silent first.
+    pub fn get_agent_presence(&self) -> Result<Vec<AgentPresence>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, title, status, current_thought, worker_id
+                 FROM tasks
+                 WHERE worker_id IS NOT NULL AND status = 'working' AND deleted_at IS NULL
+                 ORDER BY claimed_at DESC",
             )?;
+            let mut claims: HashMap<String, Vec<WorkerClaimedTask>> = HashMap::new();
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(4)?,
+                    WorkerClaimedTask {
+                        id: row.get(0)?,
+                        title: row.get(1)?,
+                        status: row.get(2)?,
+                        current_thought: row.get(3)?,
+                    },
+                ))
+            })?;
+            for (worker_id, task) in rows.filter_map(|r| r.ok()) {
+                claims.entry(worker_id).or_default().push(task);
+            }
 
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, last_heartbeat FROM workers ORDER BY last_heartbeat, id",
+            )?;
             let workers = stmt
-                .query_map(params![cutoff], |row| {
-                    let id: String = row.get(0)?;
-                    let current_thought: Option<String> = row.get(1)?;
-                    let claim_count: i32 = row.get(2)?;
-                    Ok(DashboardWorker { id, current_thought, claim_count })
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
                 })?
                 .filter_map(|r| r.ok())
+                .map(|(id, last_heartbeat)| {
+                    let claims = claims.remove(&id).unwrap_or_default();
+                    let current_thought = claims.iter().find_map(|t| t.current_thought.clone());
+                    AgentPresence {
+                        id,
+                        last_heartbeat,
+                        current_thought,
+                        claims,
+                    }
+                })
                 .collect();
 
             Ok(workers)
@@ -239,7 +293,7 @@ impl Database {
 
     /// Query tasks with filters for the task list view.
     pub fn query_tasks(&self, query: &TaskListQuery) -> Result<TaskListResult> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut sql = String::from(
                 "SELECT t.id, t.title, t.status, t.priority, t.worker_id, t.tags, t.created_at, t.updated_at
                  FROM tasks t
@@ -325,7 +379,7 @@ impl Database {
 
             // Get tasks
             let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|b| b.as_ref()).collect();
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
 
             let tasks = stmt
                 .query_map(params_refs.as_slice(), |row| {
@@ -357,8 +411,8 @@ impl Database {
 
     /// Get tasks claimed by a specific worker for the detail view.
     pub fn get_worker_claimed_tasks(&self, worker_id: &str) -> Result<Vec<WorkerClaimedTask>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT id, title, status, current_thought
                  FROM tasks
                  WHERE worker_id = ?1 AND status = 'working' AND deleted_at IS NULL
@@ -385,73 +439,99 @@ impl Database {
         })
     }
 
-    /// Simple task update for dashboard (bypasses state machine validation).
-    /// This is an admin-level operation that allows direct field updates.
-    pub fn dashboard_update_task(
+    /// Get the tasks a worker most recently moved to `completed`, newest first.
+    pub fn get_worker_completions(
         &self,
-        task_id: &str,
-        status: Option<&str>,
-        priority: Option<i32>,
-        description: Option<&str>,
-        tags: Option<Vec<String>>,
-    ) -> Result<()> {
-        let now = super::now_ms();
-
-        self.with_conn(|conn| {
-            // Build dynamic update query
-            let mut updates = vec!["updated_at = ?1".to_string()];
-            let mut param_idx = 2;
+        worker_id: &str,
+        limit: i32,
+    ) -> Result<Vec<WorkerCompletion>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT t.id, t.title, MAX(s.timestamp) AS completed_at, t.time_actual_ms, t.cost_usd
+                 FROM task_sequence s
+                 JOIN tasks t ON t.id = s.task_id
+                 WHERE s.worker_id = ?1 AND s.status = 'completed' AND t.deleted_at IS NULL
+                 GROUP BY t.id
+                 ORDER BY completed_at DESC
+                 LIMIT ?2",
+            )?;
 
-            if status.is_some() {
-                updates.push(format!("status = ?{}", param_idx));
-                param_idx += 1;
-            }
-            if priority.is_some() {
-                updates.push(format!("priority = ?{}", param_idx));
-                param_idx += 1;
-            }
-            if description.is_some() {
-                updates.push(format!("description = ?{}", param_idx));
-                param_idx += 1;
-            }
-            if tags.is_some() {
-                updates.push(format!("tags = ?{}", param_idx));
-                param_idx += 1;
-            }
+            let completions = stmt
+                .query_map(params![worker_id, limit], |row| {
+                    Ok(WorkerCompletion {
+                        task_id: row.get(0)?,
+                        title: row.get(1)?,
+                        completed_at: row.get(2)?,
+                        time_actual_ms: row.get(3)?,
+                        cost_usd: row.get(4)?,
+                    })
+                })?
+                .filter_map(|r| r.ok())
+                .collect();
 
-            let sql = format!(
-                "UPDATE tasks SET {} WHERE id = ?{}",
-                updates.join(", "),
-                param_idx
-            );
+            Ok(completions)
+        })
+    }
 
-            // Build params list dynamically
-            let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-            params_vec.push(Box::new(now));
+    /// Get a worker's most recent thoughts, newest first.
+    pub fn get_worker_thoughts(&self, worker_id: &str, limit: i32) -> Result<Vec<WorkerThought>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT task_id, thought, created_at FROM thought_log
+                 WHERE worker_id = ?1
+                 ORDER BY id DESC
+                 LIMIT ?2",
+            )?;
 
-            if let Some(s) = status {
-                params_vec.push(Box::new(s.to_string()));
-            }
-            if let Some(p) = priority {
-                params_vec.push(Box::new(p));
-            }
-            if let Some(d) = description {
-                params_vec.push(Box::new(d.to_string()));
-            }
-            if let Some(t) = tags {
-                params_vec.push(Box::new(serde_json::to_string(&t)?));
-            }
-            params_vec.push(Box::new(task_id.to_string()));
+            let thoughts = stmt
+                .query_map(params![worker_id, limit], |row| {
+                    Ok(WorkerThought {
+                        task_id: row.get(0)?,
+                        thought: row.get(1)?,
+                        created_at: row.get(2)?,
+                    })
+                })?
+                .filter_map(|r| r.ok())
+                .collect();
 
-            let params_refs: Vec<&dyn rusqlite::ToSql> =
-                params_vec.iter().map(|b| b.as_ref()).collect();
-            let rows_affected = conn.execute(&sql, params_refs.as_slice())?;
+            Ok(thoughts)
+        })
+    }
 
-            if rows_affected == 0 {
-                return Err(anyhow::anyhow!("Task not found"));
-            }
+    /// Get cost and metric totals for the tasks a worker has held or transitioned.
+    pub fn get_worker_metrics(&self, worker_id: &str) -> Result<WorkerMetrics> {
+        self.with_read_conn(|conn| {
+            let metrics = conn.query_row(
+                "SELECT
+                    COUNT(*),
+                    SUM(CASE WHEN status = 'completed' THEN 1 ELSE 0 END),
+                    COALESCE(SUM(cost_usd), 0.0),
+                    COALESCE(SUM(time_actual_ms), 0),
+                    COALESCE(SUM(metric_0), 0), COALESCE(SUM(metric_1), 0),
+                    COALESCE(SUM(metric_2), 0), COALESCE(SUM(metric_3), 0),
+                    COALESCE(SUM(metric_4), 0), COALESCE(SUM(metric_5), 0),
+                    COALESCE(SUM(metric_6), 0), COALESCE(SUM(metric_7), 0)
+                 FROM tasks t
+                 WHERE t.deleted_at IS NULL
+                 AND (t.worker_id = ?1 OR EXISTS (
+                     SELECT 1 FROM task_sequence s WHERE s.task_id = t.id AND s.worker_id = ?1))",
+                params![worker_id],
+                |row| {
+                    let mut metrics = [0i64; 8];
+                    for (i, slot) in metrics.iter_mut().enumerate() {
+                        *slot = row.get(4 + i)?;
+                    }
+                    Ok(WorkerMetrics {
+                        task_count: row.get(0)?,
+                        completed_count: row.get::<_, Option<i64>>(1)?.unwrap_or(0),
+                        total_cost_usd: row.get(2)?,
+                        total_time_ms: row.get(3)?,
+                        metrics,
+                    })
+                },
+            )?;
 
-            Ok(())
+            Ok(metrics)
         })
     }
 
@@ -514,7 +594,7 @@ impl Database {
         let now = super::now_ms();
         let cutoff_24h = now - (24 * 60 * 60 * 1000);
 
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // Count task state transitions in last 24h
             let transitions_24h: i64 = conn.query_row(
                 "SELECT COUNT(*) FROM task_sequence WHERE timestamp >= ?1",
@@ -541,7 +621,7 @@ impl Database {
 
             // Get transition counts by status in last 24h
             let mut events_by_status = HashMap::new();
-            let mut stmt = conn.prepare(
+            let mut stmt = conn.prepare_cached(
                 "SELECT status, COUNT(*) FROM task_sequence
                  WHERE timestamp >= ?1 AND status IS NOT NULL GROUP BY status",
             )?;
@@ -564,7 +644,7 @@ impl Database {
 
     /// Query activity events with filters and pagination.
     pub fn query_activity(&self, query: &ActivityListQuery) -> Result<ActivityListResult> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // We need to combine task_sequence and claim_sequence into a unified view
             // Use UNION ALL for efficiency
 
@@ -635,7 +715,7 @@ impl Database {
 
                 let params_refs: Vec<&dyn rusqlite::ToSql> =
                     params_vec.iter().map(|b| b.as_ref()).collect();
-                let mut stmt = conn.prepare(&sql)?;
+                let mut stmt = conn.prepare_cached(&sql)?;
                 let mut rows = stmt.query(params_refs.as_slice())?;
 
                 while let Some(row) = rows.next()? {
@@ -696,7 +776,7 @@ impl Database {
                 {
                     let params_refs: Vec<&dyn rusqlite::ToSql> =
                         params_vec.iter().map(|b| b.as_ref()).collect();
-                    let mut stmt = conn.prepare(&sql)?;
+                    let mut stmt = conn.prepare_cached(&sql)?;
                     let mut rows = stmt.query(params_refs.as_slice())?;
 
                     while let Some(row) = rows.next()? {
@@ -729,7 +809,7 @@ impl Database {
             }
 
             // Sort all events by timestamp descending
-            events.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+            events.sort_by_key(|b| std::cmp::Reverse(b.timestamp));
 
             // Apply pagination to combined results
             let offset = ((query.page - 1) * query.limit) as usize;
@@ -751,8 +831,8 @@ impl Database {
 
     /// Get all file marks with full details for the dashboard.
     pub fn get_all_file_marks(&self) -> Result<Vec<DashboardFileMark>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT file_path, worker_id, reason, locked_at, task_id
                  FROM file_locks
                  ORDER BY locked_at DESC",
@@ -777,7 +857,7 @@ impl Database {
 
     /// Get file marks statistics for the dashboard.
     pub fn get_file_marks_stats(&self) -> Result<FileMarksStats> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let total_marks: i64 =
                 conn.query_row("SELECT COUNT(*) FROM file_locks", [], |row| row.get(0))?;
 
@@ -865,7 +945,7 @@ impl Database {
 
     /// Get metrics overview statistics.
     pub fn get_metrics_overview(&self) -> Result<MetricsOverview> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let row: (i64, i64, f64, i64, i64, i64) = conn.query_row(
                 "SELECT
                     COUNT(*) as total_tasks,
@@ -900,8 +980,8 @@ impl Database {
 
     /// Get task counts by status for distribution chart.
     pub fn get_status_distribution(&self) -> Result<HashMap<String, i64>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT status, COUNT(*) as count
                  FROM tasks
                  WHERE deleted_at IS NULL
@@ -927,7 +1007,7 @@ impl Database {
     /// Get velocity data (completed tasks per period).
     /// Period can be "day" or "week".
     pub fn get_velocity(&self, period: &str, num_periods: i32) -> Result<Vec<VelocityDataPoint>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let now = super::now_ms();
             let period_ms: i64 = match period {
                 "week" => 7 * 24 * 60 * 60 * 1000,
@@ -951,25 +1031,8 @@ impl Database {
                     |row| Ok((row.get(0)?, row.get(1)?)),
                 )?;
 
-                // Generate label (e.g., "Jan 15" or "Week 3")
-                let label = if period == "week" {
-                    if i == 0 {
-                        "This week".to_string()
-                    } else if i == 1 {
-                        "Last week".to_string()
-                    } else {
-                        format!("{} weeks ago", i)
-                    }
-                } else if i == 0 {
-                    "Today".to_string()
-                } else if i == 1 {
-                    "Yesterday".to_string()
-                } else {
-                    format!("{} days ago", i)
-                };
-
                 data_points.push(VelocityDataPoint {
-                    period_label: label,
+                    period_label: period_label(period, i as usize),
                     completed_count: count,
                     total_points: points,
                 });
@@ -981,10 +1044,120 @@ impl Database {
         })
     }
 
+    /// Get open/closed counts, open points, and per-agent completions over time.
+    ///
+    /// Replays status changes from `task_sequence` to sample the state of every
+    /// task at the end of each period (oldest first). Tasks count as open while
+    /// in a blocking state; tasks without recorded transitions are in the
+    /// initial state. Period can be "day" or "week".
+    pub fn get_trends(
+        &self,
+        period: &str,
+        num_periods: i32,
+        states: &StatesConfig,
+    ) -> Result<Trends> {
+        self.with_read_conn(|conn| {
+            let now = super::now_ms();
+            let period_ms: i64 = match period {
+                "week" => 7 * 24 * 60 * 60 * 1000,
+                _ => 24 * 60 * 60 * 1000, // day
+            };
+            let num_periods = num_periods.max(1) as usize;
+            let start = now - num_periods as i64 * period_ms;
+
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, created_at, deleted_at, COALESCE(points, 0) FROM tasks
+                 WHERE created_at <= ?1 AND (deleted_at IS NULL OR deleted_at > ?2)",
+            )?;
+            let tasks: Vec<(String, i64, Option<i64>, i64)> = stmt
+                .query_map(params![now, start], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })?
+                .filter_map(|r| r.ok())
+                .collect();
+
+            let mut stmt = conn.prepare_cached(
+                "SELECT task_id, worker_id, status, timestamp FROM task_sequence
+                 WHERE status IS NOT NULL AND timestamp <= ?1
+                 ORDER BY timestamp, id",
+            )?;
+            let events: Vec<(String, Option<String>, String, i64)> = stmt
+                .query_map(params![now], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })?
+                .filter_map(|r| r.ok())
+                .collect();
+
+            let mut status: HashMap<&str, &str> = HashMap::new();
+            let mut agents: HashMap<String, Vec<i64>> = HashMap::new();
+            let mut points = Vec::with_capacity(num_periods);
+            let mut events = events.iter().peekable();
+
+            for i in 0..num_periods {
+                let period_end = start + (i as i64 + 1) * period_ms;
+                let period_start = period_end - period_ms;
+
+                while let Some((task_id, worker_id, new_status, timestamp)) =
+                    events.next_if(|e| e.3 <= period_end)
+                {
+                    status.insert(task_id, new_status);
+                    if new_status == "completed"
+                        && *timestamp > period_start
+                        && let Some(worker_id) = worker_id
+                    {
+                        agents
+                            .entry(worker_id.clone())
+                            .or_insert_with(|| vec![0; num_periods])[i] += 1;
+                    }
+                }
+
+                let mut point = TrendPoint {
+                    timestamp: period_end,
+                    label: period_label(period, num_periods - 1 - i),
+                    open: 0,
+                    closed: 0,
+                    open_points: 0,
+                };
+                for (id, created_at, deleted_at, task_points) in &tasks {
+                    if *created_at > period_end || deleted_at.is_some_and(|d| d <= period_end) {
+                        continue;
+                    }
+                    let current = status
+                        .get(id.as_str())
+                        .copied()
+                        .unwrap_or(states.initial.as_str());
+                    if states.is_blocking_state(current) {
+                        point.open += 1;
+                        point.open_points += task_points;
+                    } else {
+                        point.closed += 1;
+                    }
+                }
+                points.push(point);
+            }
+
+            let mut agents: Vec<AgentThroughput> = agents
+                .into_iter()
+                .map(|(worker_id, completed)| AgentThroughput {
+                    total: completed.iter().sum(),
+                    worker_id,
+                    completed,
+                })
+                .collect();
+            agents.sort_by(|a, b| b.total.cmp(&a.total).then(a.worker_id.cmp(&b.worker_id)));
+
+            Ok(Trends {
+                period: if period == "week" { "week" } else { "day" }.to_string(),
+                points,
+                agents,
+            })
+        })
+    }
+
     /// Get average time spent in each status.
     pub fn get_time_in_status(&self) -> Result<Vec<TimeInStatusStats>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT
                     status,
                     AVG(COALESCE(end_timestamp, ?1) - timestamp) as avg_duration,
@@ -1015,8 +1188,8 @@ impl Database {
 
     /// Get cost breakdown by agent/worker.
     pub fn get_cost_by_agent(&self) -> Result<Vec<AgentCostStats>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT
                     worker_id,
                     COALESCE(SUM(cost_usd), 0.0) as total_cost,
@@ -1048,7 +1221,7 @@ impl Database {
 
     /// Get aggregate of custom metrics (metric_0 through metric_7).
     pub fn get_custom_metrics(&self) -> Result<CustomMetricsAggregate> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let row: (i64, i64, i64, i64, i64, i64, i64, i64) = conn.query_row(
                 "SELECT
                     COALESCE(SUM(metric_0), 0),
@@ -1092,7 +1265,7 @@ impl Database {
         focus_task: Option<&str>,
         depth: i32,
     ) -> Result<DependencyGraph> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut nodes: Vec<GraphNode> = Vec::new();
             let mut edges: Vec<GraphEdge> = Vec::new();
             let mut seen_tasks: std::collections::HashSet<String> = std::collections::HashSet::new();
@@ -1139,7 +1312,7 @@ impl Database {
                             type_clause
                         );
 
-                        let mut stmt = conn.prepare(&sql)?;
+                        let mut stmt = conn.prepare_cached(&sql)?;
                         let rows = stmt.query_map(params![tid], |row| {
                             Ok((
                                 row.get::<_, String>(0)?,
@@ -1190,7 +1363,7 @@ impl Database {
                             type_clause
                         );
 
-                        let mut stmt = conn.prepare(&sql)?;
+                        let mut stmt = conn.prepare_cached(&sql)?;
                         let rows = stmt.query_map(params![tid], |row| {
                             Ok((
                                 row.get::<_, String>(0)?,
@@ -1236,7 +1409,7 @@ impl Database {
                     type_clause
                 );
 
-                let mut stmt = conn.prepare(&sql)?;
+                let mut stmt = conn.prepare_cached(&sql)?;
                 let edge_rows = stmt.query_map([], |row| {
                     Ok(GraphEdge {
                         from_id: row.get(0)?,
@@ -1265,7 +1438,7 @@ impl Database {
                         placeholders
                     );
 
-                    let mut stmt = conn.prepare(&node_sql)?;
+                    let mut stmt = conn.prepare_cached(&node_sql)?;
                     let params_vec: Vec<String> = seen_tasks.iter().cloned().collect();
                     let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec
                         .iter()
@@ -1293,7 +1466,7 @@ impl Database {
 
     /// Get dependency graph statistics.
     pub fn get_dependency_graph_stats(&self) -> Result<DependencyGraphStats> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let total_tasks: i64 = conn.query_row(
                 "SELECT COUNT(*) FROM tasks WHERE deleted_at IS NULL",
                 [],
@@ -1348,8 +1521,8 @@ impl Database {
 
     /// Get all unique phases used by tasks.
     pub fn get_available_phases(&self) -> Result<Vec<String>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT DISTINCT phase FROM tasks WHERE phase IS NOT NULL AND deleted_at IS NULL ORDER BY phase"
             )?;
 
@@ -1395,6 +1568,23 @@ pub struct MetricsOverview {
     pub completed_points: i64,
 }
 
+/// Label for the period `ago` periods before the current one (e.g., "Yesterday").
+fn period_label(period: &str, ago: usize) -> String {
+    if period == "week" {
+        match ago {
+            0 => "This week".to_string(),
+            1 => "Last week".to_string(),
+            _ => format!("{} weeks ago", ago),
+        }
+    } else {
+        match ago {
+            0 => "Today".to_string(),
+            1 => "Yesterday".to_string(),
+            _ => format!("{} days ago", ago),
+        }
+    }
+}
+
 /// Velocity data point for the chart.
 #[derive(Debug, Clone)]
 pub struct VelocityDataPoint {
@@ -1403,6 +1593,39 @@ pub struct VelocityDataPoint {
     pub total_points: i64,
 }
 
+/// Task counts sampled at the end of a period.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendPoint {
+    /// End of the period (ms since epoch).
+    pub timestamp: i64,
+    pub label: String,
+    /// Tasks in a blocking state.
+    pub open: i64,
+    /// Tasks in a non-blocking state.
+    pub closed: i64,
+    /// Sum of points of open tasks (the burndown line).
+    pub open_points: i64,
+}
+
+/// Tasks an agent completed in each period.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentThroughput {
+    pub worker_id: String,
+    /// Completions per period, aligned with `Trends::points`.
+    pub completed: Vec<i64>,
+    pub total: i64,
+}
+
+/// Trend data backing the dashboard charts.
+#[derive(Debug, Clone, Serialize)]
+pub struct Trends {
+    pub period: String,
+    /// One point per period, oldest first.
+    pub points: Vec<TrendPoint>,
+    /// Agents that completed tasks in the window, busiest first.
+    pub agents: Vec<AgentThroughput>,
+}
+
 /// Time in status statistics.
 #[derive(Debug, Clone)]
 pub struct TimeInStatusStats {
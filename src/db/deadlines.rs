@@ -0,0 +1,214 @@
+//! Deadline reminders: firing reminders before claimed tasks' `due_at`
+//! deadlines and tagging overdue tasks.
+
+use super::changes::insert_task_change;
+use super::scheduling::{DEADLINE_FIELD, parse_deadline};
+use super::tasks::sync_task_tags;
+use super::{Database, now_ms};
+use crate::config::{DeadlineRemindersConfig, StatesConfig};
+use crate::types::{DeadlineReminder, TaskDeadline};
+use anyhow::Result;
+use rusqlite::{Connection, params};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// What one deadline sweep did.
+#[derive(Debug, Clone, Default)]
+pub struct DeadlineSweep {
+    /// Reminders that came due, for the caller to deliver.
+    pub reminders: Vec<DeadlineReminder>,
+    /// Tasks the overdue tag was put on or taken off.
+    pub retagged: Vec<String>,
+}
+
+/// A live task with a deadline or the overdue tag.
+struct DeadlineRow {
+    id: String,
+    title: String,
+    status: String,
+    worker_id: Option<String>,
+    tags: Vec<String>,
+    due_at: Option<i64>,
+}
+
+/// Live tasks with a parseable deadline, plus any carrying `overdue_tag`
+/// (whose deadline may since have been removed).
+fn deadline_rows(conn: &Connection, overdue_tag: Option<&str>) -> Result<Vec<DeadlineRow>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT t.id, t.title, t.status, t.worker_id, t.tags, f.value
+         FROM tasks t
+         LEFT JOIN task_fields f ON f.task_id = t.id AND f.name = ?1
+         WHERE t.deleted_at IS NULL
+           AND (f.value IS NOT NULL OR EXISTS (
+               SELECT 1 FROM task_tags tt WHERE tt.task_id = t.id AND tt.tag = ?2))",
+    )?;
+    let rows = stmt
+        .query_map(params![DEADLINE_FIELD, overdue_tag], |row| {
+            let tags: String = row.get(4)?;
+            let due: Option<String> = row.get(5)?;
+            Ok(DeadlineRow {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                status: row.get(2)?,
+                worker_id: row.get(3)?,
+                tags: serde_json::from_str(&tags).unwrap_or_default(),
+                due_at: due
+                    .and_then(|v| serde_json::from_str::<Value>(&v).ok())
+                    .and_then(|v| parse_deadline(&v)),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// Sent reminders by task: offset to the deadline it was sent for.
+fn sent_reminders(conn: &Connection) -> Result<HashMap<String, HashMap<i64, i64>>> {
+    let mut stmt =
+        conn.prepare_cached("SELECT task_id, offset_ms, due_at FROM deadline_reminders")?;
+    let mut sent: HashMap<String, HashMap<i64, i64>> = HashMap::new();
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+        ))
+    })?;
+    for row in rows {
+        let (task_id, offset, due) = row?;
+        sent.entry(task_id).or_default().insert(offset, due);
+    }
+    Ok(sent)
+}
+
+impl Database {
+    /// Fire the reminders that have come due at `now` and keep the overdue tag
+    /// in step with deadlines.
+    ///
+    /// A reminder fires once per deadline for a claimed, unfinished task when
+    /// `now` reaches `due_at - offset`. When several offsets have passed since
+    /// the last sweep (say the task was claimed an hour before its deadline),
+    /// only the latest fires and the ones it overtook are recorded as sent.
+    /// Unfinished tasks past their deadline get the overdue tag, and lose it
+    /// once they finish or the deadline moves out or goes away.
+    pub fn sweep_deadlines(
+        &self,
+        config: &DeadlineRemindersConfig,
+        states_config: &StatesConfig,
+        now: i64,
+    ) -> Result<DeadlineSweep> {
+        let offsets = config.offsets_ms();
+        let overdue_tag = config.overdue_tag.as_deref();
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let rows = deadline_rows(&tx, overdue_tag)?;
+            let sent = sent_reminders(&tx)?;
+            let mut sweep = DeadlineSweep::default();
+
+            for row in rows {
+                let open = states_config.is_blocking_state(&row.status);
+                let overdue = row.due_at.is_some_and(|due| now > due);
+
+                if let Some(tag) = overdue_tag {
+                    let tagged = row.tags.iter().any(|t| t == tag);
+                    if tagged != (open && overdue) {
+                        let mut tags = row.tags.clone();
+                        if tagged {
+                            tags.retain(|t| t != tag);
+                        } else {
+                            tags.push(tag.to_string());
+                        }
+                        tx.execute(
+                            "UPDATE tasks SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+                            params![serde_json::to_string(&tags)?, now, row.id],
+                        )?;
+                        sync_task_tags(&tx, &row.id, &tags)?;
+                        // Record it in the task's history like an update
+                        // would, with no worker since the server made it
+                        insert_task_change(
+                            &tx,
+                            &row.id,
+                            None,
+                            "tags",
+                            &json!(row.tags),
+                            &json!(tags),
+                            now,
+                        )?;
+                        sweep.retagged.push(row.id.clone());
+                    }
+                }
+
+                // Reminders go to the worker holding the task
+                let (Some(due), Some(worker_id), true) = (row.due_at, &row.worker_id, open) else {
+                    continue;
+                };
+                let task_sent = sent.get(&row.id);
+                let pending: Vec<i64> = offsets
+                    .iter()
+                    .copied()
+                    .filter(|offset| now >= due - offset)
+                    .filter(|offset| task_sent.and_then(|s| s.get(offset)) != Some(&due))
+                    .collect();
+                // Offsets run largest first, so the last is the latest reminder
+                let Some(&offset) = pending.last() else {
+                    continue;
+                };
+                for offset in &pending {
+                    tx.execute(
+                        "INSERT INTO deadline_reminders (task_id, offset_ms, due_at, sent_at)
+                         VALUES (?1, ?2, ?3, ?4)
+                         ON CONFLICT(task_id, offset_ms) DO UPDATE
+                         SET due_at = excluded.due_at, sent_at = excluded.sent_at",
+                        params![row.id, offset, due, now],
+                    )?;
+                }
+                sweep.reminders.push(DeadlineReminder {
+                    task_id: row.id.clone(),
+                    title: row.title.clone(),
+                    worker_id: worker_id.clone(),
+                    due_at: due,
+                    offset_ms: offset,
+                    overdue,
+                });
+            }
+
+            tx.commit()?;
+            Ok(sweep)
+        })
+    }
+
+    /// Deadlines of claimed, unfinished tasks, soonest first, with the
+    /// reminders fired for each.
+    pub fn get_claimed_deadlines(&self, states_config: &StatesConfig) -> Result<Vec<TaskDeadline>> {
+        let now = now_ms();
+        self.with_read_conn(|conn| {
+            let sent = sent_reminders(conn)?;
+            let mut deadlines: Vec<TaskDeadline> = deadline_rows(conn, None)?
+                .into_iter()
+                .filter(|row| states_config.is_blocking_state(&row.status))
+                .filter_map(|row| {
+                    let due = row.due_at?;
+                    let worker_id = row.worker_id?;
+                    let mut reminded: Vec<i64> = sent
+                        .get(&row.id)
+                        .into_iter()
+                        .flatten()
+                        .filter(|(_, sent_due)| **sent_due == due)
+                        .map(|(offset, _)| *offset)
+                        .collect();
+                    reminded.sort_unstable_by(|a, b| b.cmp(a));
+                    Some(TaskDeadline {
+                        task_id: row.id,
+                        title: row.title,
+                        status: row.status,
+                        worker_id,
+                        due_at: due,
+                        overdue: now > due,
+                        reminded,
+                    })
+                })
+                .collect();
+            deadlines.sort_by(|a, b| a.due_at.cmp(&b.due_at).then(a.task_id.cmp(&b.task_id)));
+            Ok(deadlines)
+        })
+    }
+}
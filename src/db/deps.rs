@@ -1,8 +1,10 @@
 //! Dependency operations and cycle detection with typed dependencies.
 
-use super::Database;
+use super::filter::{TaskFilter, filter_clause};
+use super::visibility::scope_clause;
+use super::{Database, tag_match_sql};
 use crate::config::{AutoAdvanceConfig, DependenciesConfig, DependencyDisplay, StatesConfig};
-use crate::types::{Dependency, Task};
+use crate::types::{DEFAULT_PROJECT, Dependency, Task, TaskScope, with_tag_ancestors};
 use anyhow::{Result, anyhow};
 use rusqlite::{Connection, OptionalExtension, params};
 use std::collections::{HashSet, VecDeque};
@@ -16,6 +18,140 @@ pub struct RelinkResult {
     pub linked: Vec<(String, String)>,
 }
 
+/// Outcome of linking a batch of dependencies in one transaction.
+#[derive(Debug, Default)]
+pub struct LinkResult {
+    /// Dependencies created (from, to).
+    pub created: Vec<(String, String)>,
+    /// Existing dependencies whose lag was set (from, to).
+    pub updated: Vec<(String, String)>,
+    /// Existing dependencies left unchanged (from, to).
+    pub existing: Vec<(String, String)>,
+    /// Source task IDs that do not exist; their edges were skipped.
+    pub missing_from: Vec<String>,
+    /// Target task IDs that do not exist; their edges were skipped.
+    pub missing_to: Vec<String>,
+    /// Edges that failed validation (from, to, error). When any fail,
+    /// nothing in the batch is applied.
+    pub errors: Vec<(String, String, String)>,
+}
+
+/// Dependency type that orders siblings in a sequence.
+pub const SEQUENCE_DEP_TYPE: &str = "blocks";
+
+/// Outcome of setting the order of a parent's children.
+#[derive(Debug, Default)]
+pub struct SequenceResult {
+    /// The parent whose children were ordered.
+    pub parent: String,
+    /// Sibling edges removed (from, to).
+    pub unlinked: Vec<(String, String)>,
+    /// Sibling edges added (from, to).
+    pub linked: Vec<(String, String)>,
+}
+
+/// A task's place in its parent's sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequencePosition {
+    pub parent: String,
+    /// 1-based position.
+    pub position: usize,
+    /// Number of tasks in the sequence.
+    pub length: usize,
+    pub previous: Option<String>,
+    pub next: Option<String>,
+}
+
+/// A task in a dependency matrix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixTask {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    /// Tasks in the matrix that block this one directly.
+    pub blockers: usize,
+    /// Tasks in the matrix that block this one directly or through others.
+    pub transitive_blockers: usize,
+}
+
+/// The start-blocking graph of a subtree (or of every live task).
+#[derive(Debug, Clone, Default)]
+pub struct DependencyMatrix {
+    /// Root of the subtree, or `None` for every live task.
+    pub root: Option<String>,
+    /// Live tasks, the root first and then oldest first.
+    pub tasks: Vec<MatrixTask>,
+    /// Blocking edges between the tasks (from, to, dep_type).
+    pub edges: Vec<(String, String, String)>,
+}
+
+/// Order sibling IDs so each comes after the siblings it depends on via
+/// `edges`: sequenced siblings first, then the unordered ones, otherwise
+/// keeping the given order. Siblings caught in a cycle keep their given
+/// order at the end.
+pub(crate) fn order_siblings(ids: &[String], edges: &[(String, String)]) -> Vec<String> {
+    let sequenced = |id: &String| {
+        edges
+            .iter()
+            .any(|(from, to)| (from == id && ids.contains(to)) || (to == id && ids.contains(from)))
+    };
+    let mut remaining: Vec<&String> = ids.iter().collect();
+    remaining.sort_by_key(|id| !sequenced(id));
+    let mut ordered = Vec::with_capacity(ids.len());
+    while !remaining.is_empty() {
+        let next = remaining.iter().position(|id| {
+            !edges
+                .iter()
+                .any(|(from, to)| to == *id && remaining.contains(&from))
+        });
+        match next {
+            Some(i) => ordered.push(remaining.remove(i).clone()),
+            None => {
+                ordered.extend(remaining.drain(..).cloned());
+            }
+        }
+    }
+    ordered
+}
+
+/// Sequence edges between children of the same parent, among `child_ids`.
+fn sibling_edges(
+    conn: &Connection,
+    parent_id: &str,
+    child_ids: &[String],
+) -> Result<Vec<(String, String)>> {
+    let ids = serde_json::to_string(child_ids)?;
+    let mut stmt = conn.prepare_cached(
+        "SELECT d.from_task_id, d.to_task_id FROM dependencies d
+         JOIN dependencies pf ON pf.to_task_id = d.from_task_id AND pf.dep_type = 'contains'
+         JOIN dependencies pt ON pt.to_task_id = d.to_task_id AND pt.dep_type = 'contains'
+         WHERE d.dep_type = ?1 AND pf.from_task_id = ?2 AND pt.from_task_id = ?2
+           AND d.from_task_id IN (SELECT value FROM json_each(?3))
+           AND d.to_task_id IN (SELECT value FROM json_each(?3))
+         ORDER BY d.from_task_id, d.to_task_id",
+    )?;
+    let edges = stmt
+        .query_map(params![SEQUENCE_DEP_TYPE, parent_id, ids], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(edges)
+}
+
+/// Live children of a task, oldest first.
+fn live_child_ids(conn: &Connection, parent_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT t.id FROM tasks t
+         JOIN dependencies d ON d.to_task_id = t.id AND d.dep_type = 'contains'
+         WHERE d.from_task_id = ?1 AND t.deleted_at IS NULL
+         ORDER BY t.created_at, t.id",
+    )?;
+    let ids = stmt
+        .query_map(params![parent_id], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(ids)
+}
+
 /// Check if adding a dependency would create a cycle (transaction-safe version).
 fn would_create_cycle_in_tx(
     tx: &rusqlite::Transaction,
@@ -47,7 +183,7 @@ fn would_create_cycle_in_tx(
         // Get all tasks that current points to (in the relevant graph)
         let deps: Vec<String> = if def.display == DependencyDisplay::Vertical {
             // For vertical deps, only check containment relationships
-            let mut stmt = tx.prepare(
+            let mut stmt = tx.prepare_cached(
                 "SELECT to_task_id FROM dependencies d
                  JOIN (SELECT value FROM json_each(?1)) types
                  WHERE d.from_task_id = ?2 AND d.dep_type = types.value",
@@ -59,7 +195,7 @@ fn would_create_cycle_in_tx(
                 .collect()
         } else {
             // For horizontal deps, check all start-blocking relationships
-            let mut stmt = tx.prepare(
+            let mut stmt = tx.prepare_cached(
                 "SELECT to_task_id FROM dependencies d
                  JOIN (SELECT value FROM json_each(?1)) types
                  WHERE d.from_task_id = ?2 AND d.dep_type = types.value",
@@ -81,6 +217,30 @@ fn would_create_cycle_in_tx(
     Ok(false)
 }
 
+/// SQL condition over `d` (the dependency) and `blocker` (its source task) that holds while
+/// the edge is unsatisfied: the blocker is in a blocking state, or it completed less than
+/// `d.lag_ms` ago. An edge in an any-of group stays unsatisfied only while every edge of
+/// its group is. `now_param` is the placeholder index bound to the current time in ms.
+fn blocker_unsatisfied_sql(state_clause: &str, now_param: usize) -> String {
+    format!(
+        "({} AND (d.any_of IS NULL OR NOT EXISTS (
+             SELECT 1 FROM dependencies alt_d
+             INNER JOIN tasks alt ON alt.id = alt_d.from_task_id
+             WHERE alt_d.to_task_id = d.to_task_id AND alt_d.dep_type = d.dep_type
+             AND alt_d.any_of = d.any_of AND NOT {})))",
+        edge_unsatisfied_sql("d", "blocker", state_clause, now_param),
+        edge_unsatisfied_sql("alt_d", "alt", state_clause, now_param)
+    )
+}
+
+/// SQL condition over one edge and its source task, ignoring any-of groups.
+fn edge_unsatisfied_sql(edge: &str, source: &str, state_clause: &str, now_param: usize) -> String {
+    format!(
+        "({1}.status IN ({2}) OR ({0}.lag_ms IS NOT NULL AND {1}.completed_at IS NOT NULL AND {1}.completed_at + {0}.lag_ms > ?{3}))",
+        edge, source, state_clause, now_param
+    )
+}
+
 /// Build an ORDER BY clause from sort_by and sort_order parameters.
 /// Returns a safe SQL ORDER BY expression.
 fn build_order_clause(sort_by: Option<&str>, sort_order: Option<&str>) -> String {
@@ -119,12 +279,10 @@ pub enum AddDependencyResult {
 impl Database {
     /// Check if a task exists by ID.
     pub fn task_exists(&self, task_id: &str) -> Result<bool> {
-        self.with_conn(|conn| {
-            let count: i64 = conn.query_row(
-                "SELECT COUNT(*) FROM tasks WHERE id = ?1",
-                params![task_id],
-                |row| row.get(0),
-            )?;
+        self.with_read_conn(|conn| {
+            let count: i64 = conn
+                .prepare_cached("SELECT COUNT(*) FROM tasks WHERE id = ?1")?
+                .query_row(params![task_id], |row| row.get(0))?;
             Ok(count > 0)
         })
     }
@@ -175,6 +333,19 @@ impl Database {
             return Ok(AddDependencyResult::ToTaskNotFound);
         }
 
+        // Projects are isolated graphs
+        let from_project = self.get_task_project(from_task_id)?;
+        let to_project = self.get_task_project(to_task_id)?;
+        if from_project != to_project {
+            return Err(anyhow!(
+                "Cannot link tasks across projects: {} is in '{}', {} is in '{}'",
+                from_task_id,
+                from_project.unwrap_or_default(),
+                to_task_id,
+                to_project.unwrap_or_default()
+            ));
+        }
+
         // For vertical (contains) dependencies, check single-parent constraint
         let def = deps_config
             .get_definition(dep_type)
@@ -208,6 +379,254 @@ impl Database {
         })
     }
 
+    /// Add a batch of typed dependencies in one transaction, each validated
+    /// against the edges added before it, so a chain that loops back on itself
+    /// is caught. Edges naming a missing task are skipped; any other failure
+    /// (cycle, second parent, cross-project) rolls back the whole batch.
+    /// `lag_ms` and `any_of` are set on every created or existing edge when
+    /// given; created edges of a `readiness: any` type otherwise join the
+    /// type's default any-of group.
+    pub fn link_many(
+        &self,
+        edges: &[(String, String)],
+        dep_type: &str,
+        lag_ms: Option<i64>,
+        any_of: Option<&str>,
+        deps_config: &DependenciesConfig,
+    ) -> Result<LinkResult> {
+        if !deps_config.is_valid_dep_type(dep_type) {
+            return Err(anyhow!(
+                "Invalid dependency type '{}'. Valid types: {:?}",
+                dep_type,
+                deps_config.dep_type_names()
+            ));
+        }
+        let def = deps_config
+            .get_definition(dep_type)
+            .ok_or_else(|| anyhow!("Unknown dependency type: {}", dep_type))?;
+        let is_vertical = def.display == DependencyDisplay::Vertical;
+        let new_any_of = any_of.or(deps_config.default_any_of(dep_type));
+
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let mut result = LinkResult::default();
+
+            for (from_id, to_id) in edges {
+                let project_of = |id: &str| -> Result<Option<Option<String>>> {
+                    Ok(tx
+                        .prepare_cached("SELECT project FROM tasks WHERE id = ?1")?
+                        .query_row(params![id], |row| row.get(0))
+                        .optional()?)
+                };
+                let Some(from_project) = project_of(from_id)? else {
+                    if !result.missing_from.contains(from_id) {
+                        result.missing_from.push(from_id.clone());
+                    }
+                    continue;
+                };
+                let Some(to_project) = project_of(to_id)? else {
+                    if !result.missing_to.contains(to_id) {
+                        result.missing_to.push(to_id.clone());
+                    }
+                    continue;
+                };
+
+                // Projects are isolated graphs
+                if from_project != to_project {
+                    result.errors.push((
+                        from_id.clone(),
+                        to_id.clone(),
+                        format!(
+                            "Cannot link tasks across projects: {} is in '{}', {} is in '{}'",
+                            from_id,
+                            from_project.unwrap_or_default(),
+                            to_id,
+                            to_project.unwrap_or_default()
+                        ),
+                    ));
+                    continue;
+                }
+
+                if is_vertical {
+                    let existing_parent: Option<String> = tx
+                        .prepare_cached(
+                            "SELECT from_task_id FROM dependencies WHERE to_task_id = ?1 AND dep_type = 'contains'",
+                        )?
+                        .query_row(params![to_id], |row| row.get(0))
+                        .optional()?;
+                    if let Some(parent) = existing_parent
+                        && parent != *from_id
+                    {
+                        result.errors.push((
+                            from_id.clone(),
+                            to_id.clone(),
+                            format!("Task {} already has parent {}", to_id, parent),
+                        ));
+                        continue;
+                    }
+                }
+
+                if would_create_cycle_in_tx(&tx, from_id, to_id, dep_type, deps_config)? {
+                    result.errors.push((
+                        from_id.clone(),
+                        to_id.clone(),
+                        "Adding this dependency would create a cycle".to_string(),
+                    ));
+                    continue;
+                }
+
+                let changes = tx
+                    .prepare_cached(
+                        "INSERT OR IGNORE INTO dependencies (from_task_id, to_task_id, dep_type, any_of) VALUES (?1, ?2, ?3, ?4)",
+                    )?
+                    .execute(params![from_id, to_id, dep_type, new_any_of])?;
+                if lag_ms.is_some() {
+                    tx.prepare_cached(
+                        "UPDATE dependencies SET lag_ms = ?4 WHERE from_task_id = ?1 AND to_task_id = ?2 AND dep_type = ?3",
+                    )?
+                    .execute(params![from_id, to_id, dep_type, lag_ms])?;
+                }
+                if any_of.is_some() {
+                    tx.prepare_cached(
+                        "UPDATE dependencies SET any_of = ?4 WHERE from_task_id = ?1 AND to_task_id = ?2 AND dep_type = ?3",
+                    )?
+                    .execute(params![from_id, to_id, dep_type, any_of])?;
+                }
+                let edge = (from_id.clone(), to_id.clone());
+                if changes > 0 {
+                    result.created.push(edge);
+                } else if lag_ms.is_some() || any_of.is_some() {
+                    result.updated.push(edge);
+                } else {
+                    result.existing.push(edge);
+                }
+            }
+
+            if result.errors.is_empty() {
+                tx.commit()?;
+            } else {
+                result.created.clear();
+                result.updated.clear();
+                result.existing.clear();
+            }
+            Ok(result)
+        })
+    }
+
+    /// The sequenced children of a parent, in order: the children linked to a
+    /// sibling by a sequence edge. Empty when the children are unordered.
+    pub fn get_sequence(&self, parent_id: &str) -> Result<Vec<String>> {
+        self.with_read_conn(|conn| {
+            let children = live_child_ids(conn, parent_id)?;
+            let edges = sibling_edges(conn, parent_id, &children)?;
+            let sequenced: Vec<String> = children
+                .into_iter()
+                .filter(|id| edges.iter().any(|(from, to)| from == id || to == id))
+                .collect();
+            Ok(order_siblings(&sequenced, &edges))
+        })
+    }
+
+    /// Live children of a task in sequence order; unsequenced children keep
+    /// their creation order.
+    pub fn get_ordered_child_ids(&self, parent_id: &str) -> Result<Vec<String>> {
+        self.with_read_conn(|conn| {
+            let children = live_child_ids(conn, parent_id)?;
+            let edges = sibling_edges(conn, parent_id, &children)?;
+            Ok(order_siblings(&children, &edges))
+        })
+    }
+
+    /// Where a task sits in its parent's sequence, if it is in one.
+    pub fn get_sequence_position(&self, task_id: &str) -> Result<Option<SequencePosition>> {
+        let Some(parent) = self.get_parent(task_id)? else {
+            return Ok(None);
+        };
+        let sequence = self.get_sequence(&parent)?;
+        let Some(index) = sequence.iter().position(|id| id == task_id) else {
+            return Ok(None);
+        };
+        Ok(Some(SequencePosition {
+            position: index + 1,
+            length: sequence.len(),
+            previous: index.checked_sub(1).map(|i| sequence[i].clone()),
+            next: sequence.get(index + 1).cloned(),
+            parent,
+        }))
+    }
+
+    /// Make `order` the sequence of a parent's children in one transaction:
+    /// sequence edges between the parent's children are replaced by one edge
+    /// from each listed task to the next. Children left out run unordered;
+    /// an empty or one-task `order` clears the sequence.
+    pub fn set_sequence(
+        &self,
+        parent_id: &str,
+        order: &[String],
+        deps_config: &DependenciesConfig,
+    ) -> Result<SequenceResult> {
+        if !deps_config.is_valid_dep_type(SEQUENCE_DEP_TYPE) {
+            return Err(anyhow!(
+                "Sequences need the '{}' dependency type",
+                SEQUENCE_DEP_TYPE
+            ));
+        }
+
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            let children = live_child_ids(&tx, parent_id)?;
+            for (i, id) in order.iter().enumerate() {
+                if !children.contains(id) {
+                    return Err(anyhow!("Task {} is not a child of {}", id, parent_id));
+                }
+                if order[..i].contains(id) {
+                    return Err(anyhow!("Task {} appears twice in the sequence", id));
+                }
+            }
+
+            let wanted: Vec<(String, String)> = order
+                .windows(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect();
+            let mut result = SequenceResult {
+                parent: parent_id.to_string(),
+                ..Default::default()
+            };
+
+            for edge in sibling_edges(&tx, parent_id, &children)? {
+                if !wanted.contains(&edge) {
+                    tx.execute(
+                        "DELETE FROM dependencies WHERE from_task_id = ?1 AND to_task_id = ?2 AND dep_type = ?3",
+                        params![edge.0, edge.1, SEQUENCE_DEP_TYPE],
+                    )?;
+                    result.unlinked.push(edge);
+                }
+            }
+
+            for (from_id, to_id) in wanted {
+                // Other edges may still order these tasks the other way
+                if would_create_cycle_in_tx(&tx, &from_id, &to_id, SEQUENCE_DEP_TYPE, deps_config)? {
+                    return Err(anyhow!(
+                        "Sequencing {} before {} would create a cycle",
+                        from_id,
+                        to_id
+                    ));
+                }
+                let changes = tx.execute(
+                    "INSERT OR IGNORE INTO dependencies (from_task_id, to_task_id, dep_type) VALUES (?1, ?2, ?3)",
+                    params![from_id, to_id, SEQUENCE_DEP_TYPE],
+                )?;
+                if changes > 0 {
+                    result.linked.push((from_id, to_id));
+                }
+            }
+
+            tx.commit()?;
+            Ok(result)
+        })
+    }
+
     /// Check if adding a dependency would create a cycle.
     /// For horizontal deps: check cycle in the start-blocking graph.
     /// For vertical deps: check containment cycle.
@@ -222,7 +641,7 @@ impl Database {
             .get_definition(dep_type)
             .ok_or_else(|| anyhow!("Unknown dependency type: {}", dep_type))?;
 
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // A cycle would occur if to_task can already reach from_task
             // through the same "graph" (horizontal or vertical)
             let mut visited: HashSet<String> = HashSet::new();
@@ -242,7 +661,7 @@ impl Database {
                 // Get all tasks that current points to (in the relevant graph)
                 let deps: Vec<String> = if def.display == DependencyDisplay::Vertical {
                     // For vertical deps, only check containment relationships
-                    let mut stmt = conn.prepare(
+                    let mut stmt = conn.prepare_cached(
                         "SELECT to_task_id FROM dependencies d
                          JOIN (SELECT value FROM json_each(?1)) types
                          WHERE d.from_task_id = ?2 AND d.dep_type = types.value",
@@ -254,7 +673,7 @@ impl Database {
                         .collect()
                 } else {
                     // For horizontal deps, check all start-blocking relationships
-                    let mut stmt = conn.prepare(
+                    let mut stmt = conn.prepare_cached(
                         "SELECT to_task_id FROM dependencies d
                          JOIN (SELECT value FROM json_each(?1)) types
                          WHERE d.from_task_id = ?2 AND d.dep_type = types.value",
@@ -277,6 +696,23 @@ impl Database {
         })
     }
 
+    /// Set or clear the lag on an existing dependency. Returns true if the dependency exists.
+    pub fn set_dependency_lag(
+        &self,
+        from_task_id: &str,
+        to_task_id: &str,
+        dep_type: &str,
+        lag_ms: Option<i64>,
+    ) -> Result<bool> {
+        self.with_conn(|conn| {
+            let rows = conn.execute(
+                "UPDATE dependencies SET lag_ms = ?4 WHERE from_task_id = ?1 AND to_task_id = ?2 AND dep_type = ?3",
+                params![from_task_id, to_task_id, dep_type, lag_ms],
+            )?;
+            Ok(rows > 0)
+        })
+    }
+
     /// Remove a typed dependency. Returns true if a row was deleted.
     pub fn remove_dependency(
         &self,
@@ -305,8 +741,8 @@ impl Database {
 
             // First get the dependencies that will be removed
             let deps: Vec<Dependency> = {
-                let mut stmt = tx.prepare(
-                    "SELECT from_task_id, to_task_id, dep_type FROM dependencies WHERE from_task_id = ?1 AND dep_type = ?2"
+                let mut stmt = tx.prepare_cached(
+                    "SELECT from_task_id, to_task_id, dep_type, lag_ms, any_of FROM dependencies WHERE from_task_id = ?1 AND dep_type = ?2"
                 )?;
                 stmt
                     .query_map(params![from_task_id, dep_type], |row| {
@@ -314,6 +750,8 @@ impl Database {
                             from_task_id: row.get(0)?,
                             to_task_id: row.get(1)?,
                             dep_type: row.get(2)?,
+                            lag_ms: row.get(3)?,
+                            any_of: row.get(4)?,
                         })
                     })?
                     .filter_map(|r| r.ok())
@@ -343,8 +781,8 @@ impl Database {
 
             // First get the dependencies that will be removed
             let deps: Vec<Dependency> = {
-                let mut stmt = tx.prepare(
-                    "SELECT from_task_id, to_task_id, dep_type FROM dependencies WHERE to_task_id = ?1 AND dep_type = ?2"
+                let mut stmt = tx.prepare_cached(
+                    "SELECT from_task_id, to_task_id, dep_type, lag_ms, any_of FROM dependencies WHERE to_task_id = ?1 AND dep_type = ?2"
                 )?;
                 stmt
                     .query_map(params![to_task_id, dep_type], |row| {
@@ -352,6 +790,8 @@ impl Database {
                             from_task_id: row.get(0)?,
                             to_task_id: row.get(1)?,
                             dep_type: row.get(2)?,
+                            lag_ms: row.get(3)?,
+                            any_of: row.get(4)?,
                         })
                     })?
                     .filter_map(|r| r.ok())
@@ -371,9 +811,10 @@ impl Database {
 
     /// Get all dependencies.
     pub fn get_all_dependencies(&self) -> Result<Vec<Dependency>> {
-        self.with_conn(|conn| {
-            let mut stmt =
-                conn.prepare("SELECT from_task_id, to_task_id, dep_type FROM dependencies")?;
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT from_task_id, to_task_id, dep_type, lag_ms, any_of FROM dependencies",
+            )?;
 
             let deps = stmt
                 .query_map([], |row| {
@@ -384,6 +825,8 @@ impl Database {
                         from_task_id: from,
                         to_task_id: to,
                         dep_type,
+                        lag_ms: row.get(3)?,
+                        any_of: row.get(4)?,
                     })
                 })?
                 .filter_map(|r| r.ok())
@@ -400,14 +843,14 @@ impl Database {
         dep_type: &str,
         direction: &str,
     ) -> Result<Vec<Dependency>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let sql = if direction == "incoming" {
-                "SELECT from_task_id, to_task_id, dep_type FROM dependencies WHERE to_task_id = ?1 AND dep_type = ?2"
+                "SELECT from_task_id, to_task_id, dep_type, lag_ms, any_of FROM dependencies WHERE to_task_id = ?1 AND dep_type = ?2"
             } else {
-                "SELECT from_task_id, to_task_id, dep_type FROM dependencies WHERE from_task_id = ?1 AND dep_type = ?2"
+                "SELECT from_task_id, to_task_id, dep_type, lag_ms, any_of FROM dependencies WHERE from_task_id = ?1 AND dep_type = ?2"
             };
 
-            let mut stmt = conn.prepare(sql)?;
+            let mut stmt = conn.prepare_cached(sql)?;
 
             let deps = stmt
                 .query_map(params![task_id, dep_type], |row| {
@@ -418,6 +861,8 @@ impl Database {
                         from_task_id: from,
                         to_task_id: to,
                         dep_type,
+                        lag_ms: row.get(3)?,
+                        any_of: row.get(4)?,
                     })
                 })?
                 .filter_map(|r| r.ok())
@@ -438,7 +883,7 @@ impl Database {
             return Ok(vec![]);
         }
 
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let placeholders: String = start_blocking_types
                 .iter()
                 .enumerate()
@@ -459,7 +904,7 @@ impl Database {
             let params_refs: Vec<&dyn rusqlite::ToSql> =
                 params_vec.iter().map(|b| b.as_ref()).collect();
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
             let blockers = stmt
                 .query_map(params_refs.as_slice(), |row| {
                     let id: String = row.get(0)?;
@@ -484,7 +929,7 @@ impl Database {
             return Ok(vec![]);
         }
 
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let placeholders: String = completion_blocking_types
                 .iter()
                 .enumerate()
@@ -507,7 +952,7 @@ impl Database {
             let params_refs: Vec<&dyn rusqlite::ToSql> =
                 params_vec.iter().map(|b| b.as_ref()).collect();
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
             let blockers = stmt
                 .query_map(params_refs.as_slice(), |row| {
                     let id: String = row.get(0)?;
@@ -522,12 +967,15 @@ impl Database {
 
     /// Get the parent of a task (via 'contains' dependency).
     pub fn get_parent(&self, task_id: &str) -> Result<Option<String>> {
-        self.with_conn(|conn| {
-            let result: Result<String, rusqlite::Error> = conn.query_row(
-                "SELECT from_task_id FROM dependencies WHERE to_task_id = ?1 AND dep_type = 'contains'",
-                params![task_id],
-                |row| row.get(0),
-            );
+        self.with_read_conn(|conn| {
+            let result: Result<String, rusqlite::Error> = conn
+                .prepare_cached(
+                    "SELECT from_task_id FROM dependencies WHERE to_task_id = ?1 AND dep_type = 'contains'",
+                )?
+                .query_row(
+                    params![task_id],
+                    |row| row.get(0),
+                );
 
             match result {
                 Ok(parent_id) => Ok(Some(parent_id)),
@@ -539,8 +987,8 @@ impl Database {
 
     /// Get children of a task (via 'contains' dependency).
     pub fn get_children_ids(&self, task_id: &str) -> Result<Vec<String>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT to_task_id FROM dependencies WHERE from_task_id = ?1 AND dep_type = 'contains'"
             )?;
 
@@ -559,8 +1007,8 @@ impl Database {
     /// Get all tasks that block a given task (backwards compatible).
     /// Returns tasks from both 'blocks' and 'follows' dependencies.
     pub fn get_blockers(&self, task_id: &str) -> Result<Vec<String>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT from_task_id FROM dependencies 
                  WHERE to_task_id = ?1 AND dep_type IN ('blocks', 'follows')",
             )?;
@@ -578,10 +1026,9 @@ impl Database {
     }
 
     /// Get tasks that a given task blocks.
-    #[allow(dead_code)]
     pub fn get_blocking(&self, task_id: &str) -> Result<Vec<String>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT to_task_id FROM dependencies 
                  WHERE from_task_id = ?1 AND dep_type IN ('blocks', 'follows')",
             )?;
@@ -600,20 +1047,21 @@ impl Database {
 
     /// Get tasks that are blocked by incomplete start dependencies.
     /// A task is blocked if any of its start-blocking dependencies are in a blocking state.
-    /// Excludes soft-deleted tasks.
+    /// Optionally limited to a visibility scope. Excludes soft-deleted tasks.
     pub fn get_blocked_tasks(
         &self,
         states_config: &StatesConfig,
         deps_config: &DependenciesConfig,
         sort_by: Option<&str>,
         sort_order: Option<&str>,
+        scope: Option<&TaskScope>,
     ) -> Result<Vec<Task>> {
         let start_blocking_types = deps_config.start_blocking_types();
         if start_blocking_types.is_empty() {
             return Ok(vec![]);
         }
 
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // Build IN clause from blocking_states
             let state_placeholders: Vec<String> = states_config
                 .blocking_states
@@ -634,6 +1082,19 @@ impl Database {
 
             // Build ORDER BY clause
             let order_clause = build_order_clause(sort_by, sort_order);
+            let unsatisfied =
+                blocker_unsatisfied_sql(&state_clause, type_start + start_blocking_types.len());
+
+            // Build params: initial state + blocking states + start_blocking_types + now
+            let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+            params_vec.push(Box::new(states_config.initial.clone()));
+            for state in &states_config.blocking_states {
+                params_vec.push(Box::new(state.clone()));
+            }
+            for t in &start_blocking_types {
+                params_vec.push(Box::new(t.to_string()));
+            }
+            params_vec.push(Box::new(super::now_ms()));
 
             let sql = format!(
                 "SELECT DISTINCT t.*
@@ -641,24 +1102,18 @@ impl Database {
                  INNER JOIN dependencies d ON t.id = d.to_task_id
                  INNER JOIN tasks blocker ON d.from_task_id = blocker.id
                  WHERE d.dep_type IN ({})
-                 AND blocker.status IN ({})
+                 AND {}
                  AND t.status = ?1
                  AND t.deleted_at IS NULL
+                 {}
                  ORDER BY {}",
-                type_clause, state_clause, order_clause
+                type_clause,
+                unsatisfied,
+                scope_clause(&mut params_vec, scope)?,
+                order_clause
             );
 
-            let mut stmt = conn.prepare(&sql)?;
-
-            // Build params: initial state + blocking states + start_blocking_types
-            let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-            params_vec.push(Box::new(states_config.initial.clone()));
-            for state in &states_config.blocking_states {
-                params_vec.push(Box::new(state.clone()));
-            }
-            for t in &start_blocking_types {
-                params_vec.push(Box::new(t.to_string()));
-            }
+            let mut stmt = conn.prepare_cached(&sql)?;
             let params_refs: Vec<&dyn rusqlite::ToSql> =
                 params_vec.iter().map(|b| b.as_ref()).collect();
 
@@ -672,8 +1127,11 @@ impl Database {
     }
 
     /// Get tasks that are ready to be claimed (all start dependencies satisfied).
-    /// A task is ready if it's in the initial state, unclaimed, and all start-blocking deps are not blocking.
-    /// When agent_id is provided, also filters by agent's tag qualifications using junction tables.
+    /// A task is ready if it's in the initial state, unclaimed, all start-blocking deps are not blocking,
+    /// and no task linked by an exclusive dependency is currently in a timed state.
+    /// When agent_id is provided, also filters by agent's tag qualifications using junction tables,
+    /// drops tasks whose needed_group the agent is not in, and keeps only the agent's project.
+    /// A scope further limits the result to the tasks it makes visible.
     /// Excludes soft-deleted tasks.
     pub fn get_ready_tasks(
         &self,
@@ -682,17 +1140,19 @@ impl Database {
         deps_config: &DependenciesConfig,
         sort_by: Option<&str>,
         sort_order: Option<&str>,
+        scope: Option<&TaskScope>,
     ) -> Result<Vec<Task>> {
         let start_blocking_types = deps_config.start_blocking_types();
 
-        // Get agent tags if agent_id is provided (for junction table filtering)
+        // Get agent tags if agent_id is provided (for junction table filtering),
+        // with their ancestors so a child tag meets a need for its parent
         let agent_tags: Option<Vec<String>> = if let Some(aid) = agent_id {
-            Some(self.get_agent_tags(aid)?)
+            Some(with_tag_ancestors(&self.get_agent_tags(aid)?))
         } else {
             None
         };
 
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // Build IN clause from blocking_states
             let state_placeholders: Vec<String> = states_config
                 .blocking_states
@@ -719,8 +1179,27 @@ impl Database {
                 "CAST(t.priority AS INTEGER) DESC, t.created_at DESC".to_string()
             };
 
+            // Exclusive (anti-dependency) partners must not be in a timed state
+            let exclusive_start = type_start + start_blocking_types.len();
+            let exclusive_clause = format!(
+                "AND NOT EXISTS (
+                     SELECT 1 FROM dependencies x
+                     INNER JOIN tasks other ON other.id =
+                         CASE WHEN x.from_task_id = t.id THEN x.to_task_id ELSE x.from_task_id END
+                     WHERE (x.from_task_id = t.id OR x.to_task_id = t.id)
+                     AND x.dep_type IN (SELECT value FROM json_each(?{}))
+                     AND other.status IN (SELECT value FROM json_each(?{}))
+                     AND other.deleted_at IS NULL
+                 )",
+                exclusive_start,
+                exclusive_start + 1
+            );
+
+            // Blockers that completed within their edge's lag still block
+            let unsatisfied = blocker_unsatisfied_sql(&state_clause, exclusive_start + 2);
+
             // Track param index for agent tag filters
-            let mut param_idx = type_start + start_blocking_types.len();
+            let mut param_idx = exclusive_start + 3;
 
             // Build agent qualification filters using junction tables
             let (agent_needed_clause, agent_wanted_clause) = if let Some(ref tags) = agent_tags {
@@ -762,14 +1241,16 @@ impl Database {
                     .collect();
 
                 let wanted_clause = if wanted_placeholders.is_empty() {
-                    // Agent has no tags - only match tasks with no wanted_tags
-                    "AND NOT EXISTS (SELECT 1 FROM task_wanted_tags WHERE task_id = t.id)"
+                    // Agent has no tags - only match tasks with no (or optional) wanted_tags
+                    "AND (t.wanted_optional = 1
+                        OR NOT EXISTS (SELECT 1 FROM task_wanted_tags WHERE task_id = t.id))"
                         .to_string()
                 } else {
-                    // Task must have no wanted_tags OR agent must have at least one
+                    // Task must have no (or optional) wanted_tags OR agent must have at least one
                     format!(
                         "AND (
-                            NOT EXISTS (SELECT 1 FROM task_wanted_tags WHERE task_id = t.id)
+                            t.wanted_optional = 1
+                            OR NOT EXISTS (SELECT 1 FROM task_wanted_tags WHERE task_id = t.id)
                             OR EXISTS (
                                 SELECT 1 FROM task_wanted_tags 
                                 WHERE task_id = t.id AND tag IN ({})
@@ -784,6 +1265,56 @@ impl Database {
                 (String::new(), String::new())
             };
 
+            // Tasks needing a group are only ready for that group's members,
+            // tasks are only ready for agents in the same project, and tasks
+            // another worker has reserved are hidden
+            let agent_group_clause = if agent_id.is_some() {
+                let group_idx = exclusive_start + 3 + 2 * agent_tags.as_ref().map_or(0, |t| t.len());
+                format!(
+                    "AND (t.needed_group IS NULL
+                          OR t.needed_group IN (SELECT group_name FROM worker_groups WHERE worker_id = ?{0}))
+                     AND t.project = COALESCE((SELECT project FROM workers WHERE id = ?{0}), '{1}')
+                     AND NOT EXISTS (
+                         SELECT 1 FROM task_reservations r
+                         WHERE r.task_id = t.id AND r.worker_id != ?{0} AND r.expires_at > ?{2}
+                     )",
+                    group_idx,
+                    DEFAULT_PROJECT,
+                    exclusive_start + 2
+                )
+            } else {
+                String::new()
+            };
+
+            // Build params: initial state + blocking states + types + agent tags (twice if present)
+            let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+            params_vec.push(Box::new(states_config.initial.clone()));
+            for state in &states_config.blocking_states {
+                params_vec.push(Box::new(state.clone()));
+            }
+            for t in &start_blocking_types {
+                params_vec.push(Box::new(t.to_string()));
+            }
+            params_vec.push(Box::new(serde_json::to_string(
+                &deps_config.exclusive_types(),
+            )?));
+            params_vec.push(Box::new(serde_json::to_string(
+                &states_config.timed_state_names(),
+            )?));
+            params_vec.push(Box::new(super::now_ms()));
+            // Add agent tags twice (once for needed_tags check, once for wanted_tags check)
+            if let Some(ref tags) = agent_tags {
+                for tag in tags {
+                    params_vec.push(Box::new(tag.clone()));
+                }
+                for tag in tags {
+                    params_vec.push(Box::new(tag.clone()));
+                }
+            }
+            if let Some(aid) = agent_id {
+                params_vec.push(Box::new(aid.to_string()));
+            }
+
             let sql = format!(
                 "SELECT t.*
                  FROM tasks t
@@ -795,7 +1326,7 @@ impl Database {
                      INNER JOIN tasks blocker ON d.from_task_id = blocker.id
                      WHERE d.to_task_id = t.id
                      AND d.dep_type IN ({})
-                     AND blocker.status IN ({})
+                     AND {}
                  )
                  AND t.id NOT IN (
                      SELECT from_task_id FROM dependencies
@@ -803,30 +1334,21 @@ impl Database {
                  )
                  {}
                  {}
+                 {}
+                 {}
+                 {}
                  ORDER BY {}",
-                type_clause, state_clause, agent_needed_clause, agent_wanted_clause, order_clause
+                type_clause,
+                unsatisfied,
+                exclusive_clause,
+                agent_needed_clause,
+                agent_wanted_clause,
+                agent_group_clause,
+                scope_clause(&mut params_vec, scope)?,
+                order_clause
             );
 
-            let mut stmt = conn.prepare(&sql)?;
-
-            // Build params: initial state + blocking states + types + agent tags (twice if present)
-            let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-            params_vec.push(Box::new(states_config.initial.clone()));
-            for state in &states_config.blocking_states {
-                params_vec.push(Box::new(state.clone()));
-            }
-            for t in &start_blocking_types {
-                params_vec.push(Box::new(t.to_string()));
-            }
-            // Add agent tags twice (once for needed_tags check, once for wanted_tags check)
-            if let Some(ref tags) = agent_tags {
-                for tag in tags {
-                    params_vec.push(Box::new(tag.clone()));
-                }
-                for tag in tags {
-                    params_vec.push(Box::new(tag.clone()));
-                }
-            }
+            let mut stmt = conn.prepare_cached(&sql)?;
             let params_refs: Vec<&dyn rusqlite::ToSql> =
                 params_vec.iter().map(|b| b.as_ref()).collect();
 
@@ -839,6 +1361,36 @@ impl Database {
         })
     }
 
+    /// Get tasks that a soft start dependency says should finish first but have not.
+    /// These never block claiming; they are reported as warnings instead.
+    pub fn get_unsatisfied_soft_blockers(
+        &self,
+        task_id: &str,
+        states_config: &StatesConfig,
+        deps_config: &DependenciesConfig,
+    ) -> Result<Vec<String>> {
+        self.with_read_conn(|conn| {
+            get_unsatisfied_blockers_of_types(
+                conn,
+                task_id,
+                states_config,
+                &deps_config.soft_start_types(),
+            )
+        })
+    }
+
+    /// Get tasks linked to a task by an exclusive dependency that are currently being worked.
+    pub fn get_exclusive_conflicts(
+        &self,
+        task_id: &str,
+        states_config: &StatesConfig,
+        deps_config: &DependenciesConfig,
+    ) -> Result<Vec<String>> {
+        self.with_read_conn(|conn| {
+            get_exclusive_conflicts_in_tx(conn, task_id, states_config, deps_config)
+        })
+    }
+
     /// Check if a task has unmet start dependencies.
     #[allow(dead_code)]
     pub fn has_unmet_start_dependencies(
@@ -852,7 +1404,7 @@ impl Database {
             return Ok(false);
         }
 
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // Build IN clause from blocking_states
             let state_placeholders: Vec<String> = states_config
                 .blocking_states
@@ -876,8 +1428,9 @@ impl Database {
                  INNER JOIN tasks blocker ON d.from_task_id = blocker.id
                  WHERE d.to_task_id = ?1 
                  AND d.dep_type IN ({})
-                 AND blocker.status IN ({})",
-                type_clause, state_clause
+                 AND {}",
+                type_clause,
+                blocker_unsatisfied_sql(&state_clause, type_start + start_blocking_types.len())
             );
 
             let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
@@ -888,6 +1441,7 @@ impl Database {
             for t in &start_blocking_types {
                 params_vec.push(Box::new(t.to_string()));
             }
+            params_vec.push(Box::new(super::now_ms()));
             let params_refs: Vec<&dyn rusqlite::ToSql> =
                 params_vec.iter().map(|b| b.as_ref()).collect();
 
@@ -903,7 +1457,7 @@ impl Database {
         task_id: &str,
         states_config: &StatesConfig,
     ) -> Result<bool> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // Build IN clause from blocking_states
             let state_placeholders: Vec<String> = states_config
                 .blocking_states
@@ -940,6 +1494,7 @@ impl Database {
     /// - `tags_any`: Task must have at least one of these tags (OR)
     /// - `tags_all`: Task must have all of these tags (AND)
     /// - `qualified_for_agent_tags`: If provided, only return tasks where these tags satisfy the task's agent_tags_all/agent_tags_any
+    /// - `scope`: If provided, only return tasks inside this visibility scope
     ///
     /// Excludes soft-deleted tasks.
     #[allow(clippy::too_many_arguments)]
@@ -955,8 +1510,10 @@ impl Database {
         offset: i32,
         sort_by: Option<&str>,
         sort_order: Option<&str>,
+        scope: Option<&TaskScope>,
+        filter: Option<&TaskFilter>,
     ) -> Result<Vec<Task>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut sql = String::from("SELECT t.* FROM tasks t WHERE t.deleted_at IS NULL");
             let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
             let mut param_idx = 1;
@@ -1003,17 +1560,17 @@ impl Database {
                 }
             }
 
-            // tags_any: Task must have at least one of these tags (OR) - uses task_tags junction
+            // tags_any: Task must have at least one of these tags or their children (OR) - uses task_tags junction
             if let Some(ref any_tags) = tags_any
                 && !any_tags.is_empty() {
-                    let placeholders: Vec<String> = any_tags
+                    let matches: Vec<String> = any_tags
                         .iter()
                         .enumerate()
-                        .map(|(i, _)| format!("?{}", param_idx + i))
+                        .map(|(i, _)| tag_match_sql("tag", &format!("?{}", param_idx + i)))
                         .collect();
                     sql.push_str(&format!(
-                        " AND EXISTS (SELECT 1 FROM task_tags WHERE task_id = t.id AND tag IN ({}))",
-                        placeholders.join(", ")
+                        " AND EXISTS (SELECT 1 FROM task_tags WHERE task_id = t.id AND ({}))",
+                        matches.join(" OR ")
                     ));
                     for tag in any_tags {
                         params_vec.push(Box::new(tag.clone()));
@@ -1021,20 +1578,15 @@ impl Database {
                     param_idx += any_tags.len();
                 }
 
-            // tags_all: Task must have all of these tags (AND) - uses task_tags junction
+            // tags_all: Task must have each of these tags or one of its children (AND) - uses task_tags junction
             if let Some(ref all_tags) = tags_all
                 && !all_tags.is_empty() {
-                    let placeholders: Vec<String> = all_tags
-                        .iter()
-                        .enumerate()
-                        .map(|(i, _)| format!("?{}", param_idx + i))
-                        .collect();
-                    // Count matching tags must equal total required tags
-                    sql.push_str(&format!(
-                        " AND (SELECT COUNT(*) FROM task_tags WHERE task_id = t.id AND tag IN ({})) = {}",
-                        placeholders.join(", "),
-                        all_tags.len()
-                    ));
+                    for i in 0..all_tags.len() {
+                        sql.push_str(&format!(
+                            " AND EXISTS (SELECT 1 FROM task_tags WHERE task_id = t.id AND {})",
+                            tag_match_sql("tag", &format!("?{}", param_idx + i))
+                        ));
+                    }
                     for tag in all_tags {
                         params_vec.push(Box::new(tag.clone()));
                     }
@@ -1042,13 +1594,14 @@ impl Database {
                 }
 
             // qualified_for: Agent's tags must satisfy task's requirements - uses junction tables
+            let qualified_for_agent_tags = qualified_for_agent_tags.map(|tags| with_tag_ancestors(&tags));
             if let Some(ref agent_tags) = qualified_for_agent_tags {
                 // Agent must have ALL of task's agent_tags_all
                 if agent_tags.is_empty() {
                     // Agent has no tags - only match tasks with no needed_tags
                     sql.push_str(" AND NOT EXISTS (SELECT 1 FROM task_needed_tags WHERE task_id = t.id)");
-                    // And no wanted_tags
-                    sql.push_str(" AND NOT EXISTS (SELECT 1 FROM task_wanted_tags WHERE task_id = t.id)");
+                    // And no wanted_tags, unless they are optional
+                    sql.push_str(" AND (t.wanted_optional = 1 OR NOT EXISTS (SELECT 1 FROM task_wanted_tags WHERE task_id = t.id))");
                 } else {
                     // For needed_tags (AND): task must have no needed_tags OR agent has all
                     let needed_placeholders: Vec<String> = agent_tags
@@ -1073,7 +1626,7 @@ impl Database {
                     }
                     param_idx += agent_tags.len();
 
-                    // For wanted_tags (OR): task must have no wanted_tags OR agent has at least one
+                    // For wanted_tags (OR): task must have no (or optional) wanted_tags OR agent has at least one
                     let wanted_placeholders: Vec<String> = agent_tags
                         .iter()
                         .enumerate()
@@ -1081,7 +1634,8 @@ impl Database {
                         .collect();
                     sql.push_str(&format!(
                         " AND (
-                            NOT EXISTS (SELECT 1 FROM task_wanted_tags WHERE task_id = t.id)
+                            t.wanted_optional = 1
+                            OR NOT EXISTS (SELECT 1 FROM task_wanted_tags WHERE task_id = t.id)
                             OR EXISTS (
                                 SELECT 1 FROM task_wanted_tags 
                                 WHERE task_id = t.id AND tag IN ({})
@@ -1096,6 +1650,10 @@ impl Database {
                 }
             }
 
+            // Visibility scope and filter bind after all numbered params above
+            sql.push_str(&scope_clause(&mut params_vec, scope)?);
+            sql.push_str(&filter_clause(&mut params_vec, filter));
+
             // Build ORDER BY clause
             let order_clause = build_order_clause(sort_by, sort_order);
             sql.push_str(&format!(" ORDER BY {}", order_clause));
@@ -1112,7 +1670,7 @@ impl Database {
             let params_refs: Vec<&dyn rusqlite::ToSql> =
                 params_vec.iter().map(|b| b.as_ref()).collect();
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
             let tasks: Vec<Task> = stmt
                 .query_map(params_refs.as_slice(), super::tasks::parse_task_row)?
                 .filter_map(|r| r.ok())
@@ -1124,12 +1682,10 @@ impl Database {
 
     /// Get agent tags by agent ID.
     pub fn get_agent_tags(&self, agent_id: &str) -> Result<Vec<String>> {
-        self.with_conn(|conn| {
-            let result: Result<String, rusqlite::Error> = conn.query_row(
-                "SELECT tags FROM workers WHERE id = ?1",
-                params![agent_id],
-                |row| row.get(0),
-            );
+        self.with_read_conn(|conn| {
+            let result: Result<String, rusqlite::Error> = conn
+                .prepare_cached("SELECT tags FROM workers WHERE id = ?1")?
+                .query_row(params![agent_id], |row| row.get(0));
 
             match result {
                 Ok(tags_json) => {
@@ -1149,10 +1705,10 @@ impl Database {
         to_task_id: &str,
         dep_type: &str,
     ) -> Result<()> {
-        conn.execute(
+        conn.prepare_cached(
             "INSERT OR IGNORE INTO dependencies (from_task_id, to_task_id, dep_type) VALUES (?1, ?2, ?3)",
-            params![from_task_id, to_task_id, dep_type],
-        )?;
+        )?
+        .execute(params![from_task_id, to_task_id, dep_type])?;
         Ok(())
     }
 
@@ -1207,11 +1763,14 @@ impl Database {
                 for to_id in to_ids {
                     // For vertical deps, check single-parent constraint
                     if is_vertical {
-                        let existing_parent: Option<String> = tx.query_row(
-                            "SELECT from_task_id FROM dependencies WHERE to_task_id = ?1 AND dep_type = 'contains'",
-                            params![to_id],
-                            |row| row.get(0),
-                        ).optional()?;
+                        let existing_parent: Option<String> = tx
+                            .prepare_cached(
+                                "SELECT from_task_id FROM dependencies WHERE to_task_id = ?1 AND dep_type = 'contains'",
+                            )?
+                            .query_row(
+                                params![to_id],
+                                |row| row.get(0),
+                            ).optional()?;
 
                         if let Some(ref parent) = existing_parent
                             && parent != from_id {
@@ -1262,7 +1821,7 @@ impl Database {
             return Ok(vec![]);
         }
 
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut visited: HashSet<String> = HashSet::new();
             let mut result: Vec<Task> = Vec::new();
             let mut current_level: Vec<String> = vec![task_id.to_string()];
@@ -1273,7 +1832,7 @@ impl Database {
 
                 for tid in &current_level {
                     // Get tasks that block this one (from_task_id blocks to_task_id)
-                    let mut stmt = conn.prepare(
+                    let mut stmt = conn.prepare_cached(
                         "SELECT DISTINCT d.from_task_id FROM dependencies d
                          WHERE d.to_task_id = ?1 AND d.dep_type IN ('blocks', 'follows')",
                     )?;
@@ -1309,7 +1868,7 @@ impl Database {
             return Ok(vec![]);
         }
 
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut visited: HashSet<String> = HashSet::new();
             let mut result: Vec<Task> = Vec::new();
             let mut current_level: Vec<String> = vec![task_id.to_string()];
@@ -1320,7 +1879,7 @@ impl Database {
 
                 for tid in &current_level {
                     // Get tasks that this one blocks (from_task_id blocks to_task_id)
-                    let mut stmt = conn.prepare(
+                    let mut stmt = conn.prepare_cached(
                         "SELECT DISTINCT d.to_task_id FROM dependencies d
                          WHERE d.from_task_id = ?1 AND d.dep_type IN ('blocks', 'follows')",
                     )?;
@@ -1349,93 +1908,145 @@ impl Database {
         })
     }
 
-    /// Get ancestors (parent chain) via contains dependency.
+    /// The start-blocking edges between the live tasks of a subtree (the root
+    /// and its descendants), or between all live tasks when `root` is `None`,
+    /// with each task's direct and transitive blocker counts. Edges leaving
+    /// the subtree (or the visibility scope) are left out, so the counts cover
+    /// the tasks in the matrix only.
+    pub fn dependency_matrix(
+        &self,
+        root: Option<&str>,
+        deps_config: &DependenciesConfig,
+        scope: Option<&TaskScope>,
+    ) -> Result<DependencyMatrix> {
+        let blocking_types = serde_json::to_string(&deps_config.start_blocking_types())?;
+        let (tasks, edges) = self.with_read_conn(|conn| {
+            let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> =
+                vec![Box::new(root.map(String::from))];
+            let sql = format!(
+                "SELECT t.id, t.title, t.status FROM tasks t
+                 WHERE t.deleted_at IS NULL
+                   AND (?1 IS NULL OR t.id = ?1 OR t.id IN
+                        (SELECT descendant_id FROM task_closure WHERE ancestor_id = ?1)){}
+                 ORDER BY t.id = ?1 DESC, t.created_at, t.id",
+                scope_clause(&mut params_vec, scope)?
+            );
+            let params_refs: Vec<&dyn rusqlite::ToSql> =
+                params_vec.iter().map(|b| b.as_ref()).collect();
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let tasks: Vec<(String, String, String)> = stmt
+                .query_map(params_refs.as_slice(), |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+
+            let ids = serde_json::to_string(&tasks.iter().map(|t| &t.0).collect::<Vec<_>>())?;
+            let mut stmt = conn.prepare_cached(
+                "SELECT from_task_id, to_task_id, dep_type FROM dependencies
+                 WHERE dep_type IN (SELECT value FROM json_each(?1))
+                   AND from_task_id IN (SELECT value FROM json_each(?2))
+                   AND to_task_id IN (SELECT value FROM json_each(?2))
+                 ORDER BY from_task_id, to_task_id, dep_type",
+            )?;
+            let edges: Vec<(String, String, String)> = stmt
+                .query_map(params![blocking_types, ids], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok((tasks, edges))
+        })?;
+
+        if let Some(root) = root
+            && tasks.is_empty()
+        {
+            return Err(anyhow!("Task not found: {}", root));
+        }
+
+        let tasks = tasks
+            .into_iter()
+            .map(|(id, title, status)| {
+                let blockers: HashSet<&str> = edges
+                    .iter()
+                    .filter(|(_, to, _)| *to == id)
+                    .map(|(from, _, _)| from.as_str())
+                    .collect();
+                // Walk the blockers back to every task upstream
+                let mut seen: HashSet<&str> = HashSet::new();
+                let mut queue: VecDeque<&str> = blockers.iter().copied().collect();
+                while let Some(blocker) = queue.pop_front() {
+                    if blocker == id || !seen.insert(blocker) {
+                        continue;
+                    }
+                    queue.extend(
+                        edges
+                            .iter()
+                            .filter(|(_, to, _)| to == blocker)
+                            .map(|(from, _, _)| from.as_str()),
+                    );
+                }
+                MatrixTask {
+                    blockers: blockers.len(),
+                    transitive_blockers: seen.len(),
+                    id,
+                    title,
+                    status,
+                }
+            })
+            .collect();
+
+        Ok(DependencyMatrix {
+            root: root.map(String::from),
+            tasks,
+            edges,
+        })
+    }
+
+    /// Get ancestors (parent chain) via contains dependency, nearest first.
     /// depth: 0 = none, N = N levels up, -1 = all
     pub fn get_ancestors(&self, task_id: &str, depth: i32) -> Result<Vec<Task>> {
         if depth == 0 {
             return Ok(vec![]);
         }
 
-        self.with_conn(|conn| {
-            let mut result: Vec<Task> = Vec::new();
-            let mut current_id = task_id.to_string();
-            let mut levels_remaining = if depth < 0 { i32::MAX } else { depth };
-
-            while levels_remaining > 0 {
-                // Get parent (from_task_id contains to_task_id)
-                let parent_result: Result<String, rusqlite::Error> = conn.query_row(
-                    "SELECT from_task_id FROM dependencies WHERE to_task_id = ?1 AND dep_type = 'contains'",
-                    params![&current_id],
-                    |row| row.get(0),
-                );
-
-                match parent_result {
-                    Ok(parent_id) => {
-                        if let Some(task) = get_task_by_id_internal(conn, &parent_id)? {
-                            result.push(task);
-                        }
-                        current_id = parent_id;
-                        levels_remaining -= 1;
-                    }
-                    Err(rusqlite::Error::QueryReturnedNoRows) => break,
-                    Err(e) => return Err(e.into()),
-                }
-            }
-
-            Ok(result)
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT t.* FROM task_closure c
+                 INNER JOIN tasks t ON t.id = c.ancestor_id
+                 WHERE c.descendant_id = ?1 AND (?2 < 0 OR c.depth <= ?2)
+                 ORDER BY c.depth",
+            )?;
+            let tasks = stmt
+                .query_map(params![task_id, depth], super::tasks::parse_task_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(tasks)
         })
     }
 
-    /// Get descendants (children tree) via contains dependency.
+    /// Get descendants (children tree) via contains dependency, level by level.
     /// depth: 0 = none, N = N levels down, -1 = all
     pub fn get_descendants(&self, task_id: &str, depth: i32) -> Result<Vec<Task>> {
         if depth == 0 {
             return Ok(vec![]);
         }
 
-        self.with_conn(|conn| {
-            let mut visited: HashSet<String> = HashSet::new();
-            let mut result: Vec<Task> = Vec::new();
-            let mut current_level: Vec<String> = vec![task_id.to_string()];
-            let mut levels_remaining = if depth < 0 { i32::MAX } else { depth };
-
-            while !current_level.is_empty() && levels_remaining > 0 {
-                let mut next_level: Vec<String> = Vec::new();
-
-                for tid in &current_level {
-                    // Get children (from_task_id contains to_task_id)
-                    let mut stmt = conn.prepare(
-                        "SELECT to_task_id FROM dependencies WHERE from_task_id = ?1 AND dep_type = 'contains'"
-                    )?;
-
-                    let children: Vec<String> = stmt
-                        .query_map(params![tid], |row| row.get(0))?
-                        .filter_map(|r| r.ok())
-                        .collect();
-
-                    for child_id in children {
-                        if !visited.contains(&child_id) {
-                            visited.insert(child_id.clone());
-                            if let Some(task) = get_task_by_id_internal(conn, &child_id)? {
-                                result.push(task);
-                            }
-                            next_level.push(child_id);
-                        }
-                    }
-                }
-
-                current_level = next_level;
-                levels_remaining -= 1;
-            }
-
-            Ok(result)
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT t.* FROM task_closure c
+                 INNER JOIN tasks t ON t.id = c.descendant_id
+                 WHERE c.ancestor_id = ?1 AND (?2 < 0 OR c.depth <= ?2)
+                 ORDER BY c.depth, t.created_at",
+            )?;
+            let tasks = stmt
+                .query_map(params![task_id, depth], super::tasks::parse_task_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(tasks)
         })
     }
 }
 
 /// Helper to get a task by ID within a connection context.
 fn get_task_by_id_internal(conn: &Connection, task_id: &str) -> Result<Option<Task>> {
-    let mut stmt = conn.prepare("SELECT * FROM tasks WHERE id = ?1")?;
+    let mut stmt = conn.prepare_cached("SELECT * FROM tasks WHERE id = ?1")?;
     let task = stmt
         .query_row(params![task_id], super::tasks::parse_task_row)
         .optional()?;
@@ -1451,8 +2062,61 @@ pub(crate) fn get_unsatisfied_start_blockers_in_tx(
     states_config: &StatesConfig,
     deps_config: &DependenciesConfig,
 ) -> Result<Vec<String>> {
-    let start_blocking_types = deps_config.start_blocking_types();
-    if start_blocking_types.is_empty() {
+    get_unsatisfied_blockers_of_types(
+        conn,
+        task_id,
+        states_config,
+        &deps_config.start_blocking_types(),
+    )
+}
+
+/// Get the IDs of tasks linked to a given task by an exclusive (anti-dependency) type
+/// that are currently in a timed state. Links are symmetric, so both directions count.
+pub(crate) fn get_exclusive_conflicts_in_tx(
+    conn: &Connection,
+    task_id: &str,
+    states_config: &StatesConfig,
+    deps_config: &DependenciesConfig,
+) -> Result<Vec<String>> {
+    let exclusive_types = deps_config.exclusive_types();
+    let timed_states = states_config.timed_state_names();
+    if exclusive_types.is_empty() || timed_states.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT DISTINCT other.id FROM dependencies d
+         INNER JOIN tasks other ON other.id =
+             CASE WHEN d.from_task_id = ?1 THEN d.to_task_id ELSE d.from_task_id END
+         WHERE (d.from_task_id = ?1 OR d.to_task_id = ?1)
+         AND d.dep_type IN (SELECT value FROM json_each(?2))
+         AND other.status IN (SELECT value FROM json_each(?3))
+         AND other.deleted_at IS NULL
+         ORDER BY other.id",
+    )?;
+    let conflicts = stmt
+        .query_map(
+            params![
+                task_id,
+                serde_json::to_string(&exclusive_types)?,
+                serde_json::to_string(&timed_states)?
+            ],
+            |row| row.get(0),
+        )?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(conflicts)
+}
+
+/// Get the IDs of tasks in a blocking state that point at `task_id` via one of `dep_types`.
+fn get_unsatisfied_blockers_of_types(
+    conn: &Connection,
+    task_id: &str,
+    states_config: &StatesConfig,
+    dep_types: &[&str],
+) -> Result<Vec<String>> {
+    if dep_types.is_empty() {
         return Ok(vec![]);
     }
 
@@ -1465,9 +2129,9 @@ pub(crate) fn get_unsatisfied_start_blockers_in_tx(
         .collect();
     let state_clause = state_placeholders.join(", ");
 
-    // Build IN clause from types
+    // Build IN clause from dependency types
     let type_start = states_config.blocking_states.len() + 2;
-    let type_placeholders: Vec<String> = start_blocking_types
+    let type_placeholders: Vec<String> = dep_types
         .iter()
         .enumerate()
         .map(|(i, _)| format!("?{}", type_start + i))
@@ -1479,8 +2143,9 @@ pub(crate) fn get_unsatisfied_start_blockers_in_tx(
          INNER JOIN tasks blocker ON d.from_task_id = blocker.id
          WHERE d.to_task_id = ?1 
          AND d.dep_type IN ({})
-         AND blocker.status IN ({})",
-        type_clause, state_clause
+         AND {}",
+        type_clause,
+        blocker_unsatisfied_sql(&state_clause, type_start + dep_types.len())
     );
 
     let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
@@ -1488,12 +2153,13 @@ pub(crate) fn get_unsatisfied_start_blockers_in_tx(
     for state in &states_config.blocking_states {
         params_vec.push(Box::new(state.clone()));
     }
-    for t in &start_blocking_types {
+    for t in dep_types {
         params_vec.push(Box::new(t.to_string()));
     }
+    params_vec.push(Box::new(super::now_ms()));
     let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|b| b.as_ref()).collect();
 
-    let mut stmt = conn.prepare(&sql)?;
+    let mut stmt = conn.prepare_cached(&sql)?;
     let blockers = stmt
         .query_map(params_refs.as_slice(), |row| {
             let id: String = row.get(0)?;
@@ -1555,7 +2221,7 @@ pub(crate) fn propagate_unblock_effects(
     }
     let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|b| b.as_ref()).collect();
 
-    let mut stmt = conn.prepare(&sql)?;
+    let mut stmt = conn.prepare_cached(&sql)?;
     let dependent_task_ids: Vec<String> = stmt
         .query_map(params_refs.as_slice(), |row| row.get(0))?
         .filter_map(|r| r.ok())
@@ -1603,12 +2269,12 @@ pub(crate) fn propagate_unblock_effects(
             .blocking_states
             .iter()
             .enumerate()
-            .map(|(i, _)| format!("?{}", i + 3))
+            .map(|(i, _)| format!("?{}", i + 2))
             .collect();
         let state_clause = state_placeholders.join(", ");
 
         // Reuse type_placeholders from above
-        let type_start = states_config.blocking_states.len() + 3;
+        let type_start = states_config.blocking_states.len() + 2;
         let type_placeholders2: Vec<String> = start_blocking_types
             .iter()
             .enumerate()
@@ -1616,25 +2282,28 @@ pub(crate) fn propagate_unblock_effects(
             .collect();
         let type_clause2 = type_placeholders2.join(", ");
 
+        // The completed task itself can still block through a lag on its edge,
+        // and completing one task of an any-of group satisfies the group
+        let now_param = type_start + start_blocking_types.len();
         let blocker_sql = format!(
             "SELECT COUNT(*) FROM dependencies d
              INNER JOIN tasks blocker ON d.from_task_id = blocker.id
              WHERE d.to_task_id = ?1
-             AND d.from_task_id != ?2
              AND d.dep_type IN ({})
-             AND blocker.status IN ({})",
-            type_clause2, state_clause
+             AND {}",
+            type_clause2,
+            blocker_unsatisfied_sql(&state_clause, now_param)
         );
 
         let mut blocker_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
         blocker_params.push(Box::new(task_id.clone()));
-        blocker_params.push(Box::new(completed_task_id.to_string()));
         for state in &states_config.blocking_states {
             blocker_params.push(Box::new(state.clone()));
         }
         for t in &start_blocking_types {
             blocker_params.push(Box::new(t.to_string()));
         }
+        blocker_params.push(Box::new(now));
         let blocker_refs: Vec<&dyn rusqlite::ToSql> =
             blocker_params.iter().map(|b| b.as_ref()).collect();
 
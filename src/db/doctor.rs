@@ -0,0 +1,150 @@
+//! Query plan audit for `task-graph doctor`.
+//!
+//! Runs `EXPLAIN QUERY PLAN` over the query shapes the tools rely on and flags
+//! any that fall back to scanning a whole table.
+
+use super::Database;
+use anyhow::Result;
+use rusqlite::Connection;
+
+/// A hot query shape and the SQL it is checked with.
+pub struct PlanCheck {
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// Query shapes that must be served by an index. Parameters are left unbound.
+pub const PLAN_CHECKS: &[PlanCheck] = &[
+    PlanCheck {
+        name: "ready listing",
+        sql: "SELECT t.* FROM tasks t
+              WHERE t.status = ?1 AND t.worker_id IS NULL AND t.deleted_at IS NULL
+              ORDER BY CAST(t.priority AS INTEGER) DESC, t.created_at DESC",
+    },
+    PlanCheck {
+        name: "claimed by worker",
+        sql: "SELECT id, title, status, current_thought, claimed_at FROM tasks
+              WHERE worker_id = ?1 ORDER BY claimed_at, id",
+    },
+    PlanCheck {
+        name: "tasks updated since",
+        sql: "SELECT * FROM tasks WHERE updated_at > ?1 ORDER BY updated_at",
+    },
+    PlanCheck {
+        name: "recently updated",
+        sql: "SELECT id, title, status, priority FROM tasks
+              WHERE deleted_at IS NULL ORDER BY updated_at DESC LIMIT ?1",
+    },
+    PlanCheck {
+        name: "blockers of task",
+        sql: "SELECT blocker.id, blocker.status FROM dependencies d
+              INNER JOIN tasks blocker ON d.from_task_id = blocker.id
+              WHERE d.to_task_id = ?1 AND d.dep_type IN ('blocks', 'follows')",
+    },
+    PlanCheck {
+        name: "children of task",
+        sql: "SELECT to_task_id FROM dependencies
+              WHERE from_task_id = ?1 AND dep_type = 'contains'",
+    },
+    PlanCheck {
+        name: "subtree of task",
+        sql: "SELECT t.id FROM task_closure c INNER JOIN tasks t ON t.id = c.descendant_id
+              WHERE c.ancestor_id = ?1",
+    },
+    PlanCheck {
+        name: "ancestors of task",
+        sql: "SELECT t.id FROM task_closure c INNER JOIN tasks t ON t.id = c.ancestor_id
+              WHERE c.descendant_id = ?1 ORDER BY c.depth",
+    },
+    PlanCheck {
+        name: "tasks by status",
+        sql: "SELECT id FROM tasks WHERE status = ?1 AND deleted_at IS NULL",
+    },
+    PlanCheck {
+        name: "tasks by tag",
+        sql: "SELECT t.id FROM task_tags tt INNER JOIN tasks t ON t.id = tt.task_id
+              WHERE tt.tag = ?1",
+    },
+    PlanCheck {
+        name: "tasks in project",
+        sql: "SELECT id FROM tasks WHERE project = ?1 AND deleted_at IS NULL",
+    },
+];
+
+/// Query plan of one check.
+#[derive(Debug, Clone)]
+pub struct PlanReport {
+    pub name: &'static str,
+    /// `detail` column of each `EXPLAIN QUERY PLAN` row.
+    pub plan: Vec<String>,
+    /// Plan steps that scan a whole table.
+    pub full_scans: Vec<String>,
+}
+
+impl PlanReport {
+    pub fn is_ok(&self) -> bool {
+        self.full_scans.is_empty()
+    }
+}
+
+/// `EXPLAIN QUERY PLAN` details for `sql`, in plan order.
+pub fn query_plan(conn: &Connection, sql: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql))?;
+    // raw_query leaves parameters unbound (NULL), which is all planning needs
+    let mut rows = stmt.raw_query();
+    let mut plan = Vec::new();
+    while let Some(row) = rows.next()? {
+        plan.push(row.get("detail")?);
+    }
+    Ok(plan)
+}
+
+/// Plan steps that read every row of a table. Scans of an index (`USING ...`),
+/// of table-valued functions, and of constant rows are not full scans.
+pub fn full_scans(plan: &[String]) -> Vec<String> {
+    plan.iter()
+        .filter(|step| {
+            step.starts_with("SCAN ")
+                && !step.contains(" USING ")
+                && !step.contains("VIRTUAL TABLE")
+                && step != &"SCAN CONSTANT ROW"
+        })
+        .cloned()
+        .collect()
+}
+
+impl Database {
+    /// Check every query in `PLAN_CHECKS` against the current schema.
+    pub fn audit_query_plans(&self) -> Result<Vec<PlanReport>> {
+        self.with_read_conn(|conn| {
+            PLAN_CHECKS
+                .iter()
+                .map(|check| {
+                    let plan = query_plan(conn, check.sql)?;
+                    Ok(PlanReport {
+                        name: check.name,
+                        full_scans: full_scans(&plan),
+                        plan,
+                    })
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_scans_are_not_full_scans() {
+        let plan = vec![
+            "SCAN t USING INDEX idx_tasks_updated".to_string(),
+            "SEARCH d USING INDEX idx_deps_to_type (to_task_id=?)".to_string(),
+            "SCAN json_each VIRTUAL TABLE INDEX 0:".to_string(),
+            "SCAN tasks".to_string(),
+            "USE TEMP B-TREE FOR ORDER BY".to_string(),
+        ];
+        assert_eq!(full_scans(&plan), vec!["SCAN tasks".to_string()]);
+    }
+}
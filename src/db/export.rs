@@ -10,14 +10,16 @@
 /// - `workers`: Session-based worker registrations
 /// - `file_locks`: Active file marks (advisory locks)
 /// - `claim_sequence`: File lock audit log (runtime coordination)
-/// - `tasks_fts`: Full-text search virtual table (rebuilt on import)
-/// - `attachments_fts`: Full-text search virtual table (rebuilt on import)
+/// - `tasks_fts`: Full-text search virtual table (maintained by triggers, re-indexed on import)
+/// - `attachments_fts`: Full-text search virtual table (maintained by triggers, re-indexed on import)
+/// - `task_closure`: Ancestor/descendant pairs derived from `contains` dependencies
 pub const EPHEMERAL_TABLES: &[&str] = &[
     "workers",
     "file_locks",
     "claim_sequence",
     "tasks_fts",
     "attachments_fts",
+    "task_closure",
 ];
 
 /// Tables included in export (project data).
@@ -30,17 +32,43 @@ pub const PROJECT_TABLES: &[&str] = &[
     "task_tags",
     "task_needed_tags",
     "task_wanted_tags",
+    "task_fields",
     "task_sequence",
+    "task_changes",
+    "task_thoughts",
+    "audit_log",
 ];
 
+use crate::config::StatesConfig;
 use crate::types::{
-    Attachment, Dependency, ExportTables, TaskNeededTagRow, TaskSequenceEvent, TaskTagRow,
-    TaskWantedTagRow,
+    Attachment, AuditEntry, Dependency, ExportTables, Task, TaskChange, TaskFieldRow,
+    TaskNeededTagRow, TaskSequenceEvent, TaskTagRow, TaskThought, TaskWantedTagRow,
 };
 use anyhow::Result;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
 
 use super::Database;
-use super::tasks::parse_task_row;
+use super::audit::parse_audit_row;
+use super::changes::parse_change_row;
+use super::fields::encode_field_value;
+use super::tasks::{parse_task_row, parse_thought_row};
+
+/// Tag rows of rebuilt tasks, sorted by task_id then tag like the live export.
+fn past_tag_rows<T>(
+    past: &[(Task, Map<String, Value>)],
+    tags: impl Fn(&Task) -> &Vec<String>,
+    row: impl Fn(String, String) -> T,
+) -> Vec<T> {
+    past.iter()
+        .flat_map(|(task, _)| {
+            let mut sorted = tags(task).clone();
+            sorted.sort();
+            sorted.dedup();
+            sorted.into_iter().map(|tag| row(task.id.clone(), tag))
+        })
+        .collect()
+}
 
 /// Options for controlling export behavior.
 #[derive(Debug, Clone, Default)]
@@ -49,6 +77,11 @@ pub struct ExportOptions {
     pub exclude_deleted: bool,
     /// Optional list of specific tables to export. If None, export all tables.
     pub tables: Option<Vec<String>>,
+    /// If set, export the project as it stood at this time (epoch ms): tasks are
+    /// rebuilt from their history and later rows are left out.
+    pub snapshot_at: Option<i64>,
+    /// State definitions, used to work out task ownership at `snapshot_at`.
+    pub states_config: StatesConfig,
 }
 
 impl Database {
@@ -61,7 +94,11 @@ impl Database {
     /// - task_tags: ORDER BY task_id, tag
     /// - task_needed_tags: ORDER BY task_id, tag
     /// - task_wanted_tags: ORDER BY task_id, tag
+    /// - task_fields: ORDER BY task_id, name
     /// - task_sequence: ORDER BY task_id, id
+    /// - task_changes: ORDER BY task_id, id
+    /// - task_thoughts: ORDER BY task_id, id
+    /// - audit_log: ORDER BY id
     pub fn export_tables(&self, options: &ExportOptions) -> Result<ExportTables> {
         let tables_to_export = options.tables.as_ref();
 
@@ -94,16 +131,119 @@ impl Database {
             export.task_wanted_tags = Some(self.export_task_wanted_tags()?);
         }
 
+        if should_export("task_fields") {
+            export.task_fields = Some(self.export_task_fields()?);
+        }
+
         if should_export("task_sequence") {
             export.task_sequence = Some(self.export_task_sequence()?);
         }
 
+        if should_export("task_changes") {
+            export.task_changes = Some(self.export_task_changes()?);
+        }
+
+        if should_export("task_thoughts") {
+            export.task_thoughts = Some(self.export_task_thoughts()?);
+        }
+
+        if should_export("audit_log") {
+            export.audit_log = Some(self.export_audit_log()?);
+        }
+
+        if let Some(at) = options.snapshot_at {
+            self.rewind_export(&mut export, at, &options.states_config)?;
+        }
+
         Ok(export)
     }
 
+    /// Replace exported tables with their state at `at`.
+    ///
+    /// Tasks, tags, and custom fields are rebuilt as of that time; dependencies
+    /// and attachments are kept between tasks that existed then; history rows
+    /// recorded later are dropped. Tasks deleted after `at` are included.
+    fn rewind_export(
+        &self,
+        export: &mut ExportTables,
+        at: i64,
+        states_config: &StatesConfig,
+    ) -> Result<()> {
+        let past = self.get_tasks_as_of(at, states_config)?;
+        let ids: HashSet<&str> = past.iter().map(|(t, _)| t.id.as_str()).collect();
+
+        if let Some(deps) = export.dependencies.as_mut() {
+            deps.retain(|d| {
+                ids.contains(d.from_task_id.as_str()) && ids.contains(d.to_task_id.as_str())
+            });
+        }
+        if let Some(attachments) = export.attachments.as_mut() {
+            attachments.retain(|a| a.created_at <= at && ids.contains(a.task_id.as_str()));
+        }
+
+        if export.task_tags.is_some() {
+            export.task_tags = Some(past_tag_rows(
+                &past,
+                |t| &t.tags,
+                |task_id, tag| TaskTagRow { task_id, tag },
+            ));
+        }
+        if export.task_needed_tags.is_some() {
+            export.task_needed_tags = Some(past_tag_rows(
+                &past,
+                |t| &t.needed_tags,
+                |task_id, tag| TaskNeededTagRow { task_id, tag },
+            ));
+        }
+        if export.task_wanted_tags.is_some() {
+            export.task_wanted_tags = Some(past_tag_rows(
+                &past,
+                |t| &t.wanted_tags,
+                |task_id, tag| TaskWantedTagRow { task_id, tag },
+            ));
+        }
+        if export.task_fields.is_some() {
+            export.task_fields = Some(
+                past.iter()
+                    .flat_map(|(task, fields)| {
+                        fields.iter().map(|(name, value)| TaskFieldRow {
+                            task_id: task.id.clone(),
+                            name: name.clone(),
+                            value: encode_field_value(value),
+                        })
+                    })
+                    .collect(),
+            );
+        }
+
+        if let Some(events) = export.task_sequence.as_mut() {
+            events.retain(|e| e.timestamp <= at && ids.contains(e.task_id.as_str()));
+            for event in events.iter_mut() {
+                // Transitions still open at `at`
+                if event.end_timestamp.is_some_and(|end| end > at) {
+                    event.end_timestamp = None;
+                }
+            }
+        }
+        if let Some(changes) = export.task_changes.as_mut() {
+            changes.retain(|c| c.timestamp <= at && ids.contains(c.task_id.as_str()));
+        }
+        if let Some(thoughts) = export.task_thoughts.as_mut() {
+            thoughts.retain(|t| t.created_at <= at && ids.contains(t.task_id.as_str()));
+        }
+        if let Some(entries) = export.audit_log.as_mut() {
+            entries.retain(|e| e.timestamp <= at);
+        }
+
+        if export.tasks.is_some() {
+            export.tasks = Some(past.into_iter().map(|(task, _)| task).collect());
+        }
+        Ok(())
+    }
+
     /// Export all tasks ordered by id.
-    fn export_tasks(&self, exclude_deleted: bool) -> Result<Vec<crate::types::Task>> {
-        self.with_conn(|conn| {
+    fn export_tasks(&self, exclude_deleted: bool) -> Result<Vec<Task>> {
+        self.with_read_conn(|conn| {
             let sql = if exclude_deleted {
                 "SELECT * FROM tasks WHERE deleted_at IS NULL ORDER BY id"
             } else {
@@ -121,9 +261,9 @@ impl Database {
 
     /// Export all dependencies ordered by from_task_id, to_task_id, dep_type.
     fn export_dependencies(&self) -> Result<Vec<Dependency>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT from_task_id, to_task_id, dep_type 
+                "SELECT from_task_id, to_task_id, dep_type, lag_ms, any_of
                  FROM dependencies 
                  ORDER BY from_task_id, to_task_id, dep_type",
             )?;
@@ -134,6 +274,8 @@ impl Database {
                         from_task_id: row.get(0)?,
                         to_task_id: row.get(1)?,
                         dep_type: row.get(2)?,
+                        lag_ms: row.get(3)?,
+                        any_of: row.get(4)?,
                     })
                 })?
                 .filter_map(|r| r.ok())
@@ -145,9 +287,10 @@ impl Database {
 
     /// Export all attachments ordered by task_id, attachment_type, sequence.
     fn export_attachments(&self) -> Result<Vec<Attachment>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at
+                "SELECT task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at,
+                        content_hash, size_bytes, version
                  FROM attachments
                  ORDER BY task_id, attachment_type, sequence",
             )?;
@@ -163,6 +306,9 @@ impl Database {
                         content: row.get(5)?,
                         file_path: row.get(6)?,
                         created_at: row.get(7)?,
+                        content_hash: row.get(8)?,
+                        size_bytes: row.get(9)?,
+                        version: row.get(10)?,
                     })
                 })?
                 .filter_map(|r| r.ok())
@@ -174,7 +320,7 @@ impl Database {
 
     /// Export all task tags ordered by task_id, tag.
     fn export_task_tags(&self) -> Result<Vec<TaskTagRow>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut stmt =
                 conn.prepare("SELECT task_id, tag FROM task_tags ORDER BY task_id, tag")?;
 
@@ -194,7 +340,7 @@ impl Database {
 
     /// Export all task needed tags ordered by task_id, tag.
     fn export_task_needed_tags(&self) -> Result<Vec<TaskNeededTagRow>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut stmt =
                 conn.prepare("SELECT task_id, tag FROM task_needed_tags ORDER BY task_id, tag")?;
 
@@ -214,7 +360,7 @@ impl Database {
 
     /// Export all task wanted tags ordered by task_id, tag.
     fn export_task_wanted_tags(&self) -> Result<Vec<TaskWantedTagRow>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut stmt =
                 conn.prepare("SELECT task_id, tag FROM task_wanted_tags ORDER BY task_id, tag")?;
 
@@ -232,9 +378,30 @@ impl Database {
         })
     }
 
+    /// Export all task custom fields ordered by task_id, name.
+    fn export_task_fields(&self) -> Result<Vec<TaskFieldRow>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn
+                .prepare("SELECT task_id, name, value FROM task_fields ORDER BY task_id, name")?;
+
+            let fields = stmt
+                .query_map([], |row| {
+                    Ok(TaskFieldRow {
+                        task_id: row.get(0)?,
+                        name: row.get(1)?,
+                        value: row.get(2)?,
+                    })
+                })?
+                .filter_map(|r| r.ok())
+                .collect();
+
+            Ok(fields)
+        })
+    }
+
     /// Export all task sequence events ordered by task_id, id.
     fn export_task_sequence(&self) -> Result<Vec<TaskSequenceEvent>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut stmt = conn.prepare(
                 "SELECT id, task_id, worker_id, status, phase, reason, timestamp, end_timestamp
                  FROM task_sequence
@@ -260,6 +427,45 @@ impl Database {
             Ok(events)
         })
     }
+
+    /// Export all field changes ordered by task_id, id.
+    fn export_task_changes(&self) -> Result<Vec<TaskChange>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM task_changes ORDER BY task_id, id")?;
+            let changes = stmt
+                .query_map([], parse_change_row)?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(changes)
+        })
+    }
+
+    /// Export all task journal thoughts ordered by task_id, id.
+    fn export_task_thoughts(&self) -> Result<Vec<TaskThought>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, task_id, worker_id, thought, created_at
+                 FROM task_thoughts ORDER BY task_id, id",
+            )?;
+            let thoughts = stmt
+                .query_map([], parse_thought_row)?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(thoughts)
+        })
+    }
+
+    /// Export all audit log entries ordered by id.
+    fn export_audit_log(&self) -> Result<Vec<AuditEntry>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM audit_log ORDER BY id")?;
+            let entries = stmt
+                .query_map([], parse_audit_row)?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(entries)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -287,7 +493,9 @@ mod tests {
         assert!(export.task_tags.as_ref().unwrap().is_empty());
         assert!(export.task_needed_tags.as_ref().unwrap().is_empty());
         assert!(export.task_wanted_tags.as_ref().unwrap().is_empty());
+        assert!(export.task_fields.as_ref().unwrap().is_empty());
         assert!(export.task_sequence.as_ref().unwrap().is_empty());
+        assert!(export.audit_log.as_ref().unwrap().is_empty());
     }
 
     #[test]
@@ -296,6 +504,7 @@ mod tests {
         let options = ExportOptions {
             exclude_deleted: false,
             tables: Some(vec!["tasks".to_string(), "dependencies".to_string()]),
+            ..Default::default()
         };
         let export = db.export_tables(&options).unwrap();
 
@@ -424,6 +633,7 @@ mod tests {
         let options = ExportOptions {
             exclude_deleted: false,
             tables: None,
+            ..Default::default()
         };
         let export = db.export_tables(&options).unwrap();
         assert_eq!(export.tasks.as_ref().unwrap().len(), 2);
@@ -432,6 +642,7 @@ mod tests {
         let options = ExportOptions {
             exclude_deleted: true,
             tables: None,
+            ..Default::default()
         };
         let export = db.export_tables(&options).unwrap();
         assert_eq!(export.tasks.as_ref().unwrap().len(), 1);
@@ -0,0 +1,71 @@
+//! Change feed: an ordered log of mutations written by triggers.
+
+use super::Database;
+use crate::types::ChangeFeedEntry;
+use anyhow::Result;
+use rusqlite::{Row, params};
+
+/// Parse a `change_feed` row selected with `SELECT *`.
+fn parse_feed_row(row: &Row) -> rusqlite::Result<ChangeFeedEntry> {
+    Ok(ChangeFeedEntry {
+        id: row.get("id")?,
+        entity: row.get("entity")?,
+        action: row.get("action")?,
+        task_id: row.get("task_id")?,
+        related_id: row.get("related_id")?,
+        detail: row.get("detail")?,
+        timestamp: row.get("timestamp")?,
+    })
+}
+
+impl Database {
+    /// Up to `limit` feed entries after `cursor`, oldest first.
+    pub fn get_changes_since(&self, cursor: i64, limit: usize) -> Result<Vec<ChangeFeedEntry>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn
+                .prepare_cached("SELECT * FROM change_feed WHERE id > ?1 ORDER BY id LIMIT ?2")?;
+            let entries = stmt
+                .query_map(params![cursor, limit as i64], parse_feed_row)?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(entries)
+        })
+    }
+
+    /// Feed entries of the given entities that concern a task, oldest first.
+    /// Dependency entries match either end of the edge.
+    pub fn get_task_feed(&self, task_id: &str, entities: &[&str]) -> Result<Vec<ChangeFeedEntry>> {
+        if entities.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with_read_conn(|conn| {
+            let placeholders: Vec<String> =
+                (0..entities.len()).map(|i| format!("?{}", i + 2)).collect();
+            let sql = format!(
+                "SELECT * FROM change_feed
+                 WHERE entity IN ({})
+                   AND (task_id = ?1 OR (entity = 'dependency' AND related_id = ?1))
+                 ORDER BY id",
+                placeholders.join(", ")
+            );
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let params: Vec<&dyn rusqlite::ToSql> =
+                std::iter::once(&task_id as &dyn rusqlite::ToSql)
+                    .chain(entities.iter().map(|e| e as &dyn rusqlite::ToSql))
+                    .collect();
+            let entries = stmt
+                .query_map(params.as_slice(), parse_feed_row)?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(entries)
+        })
+    }
+
+    /// Cursor of the latest feed entry (0 when the feed is empty).
+    pub fn get_feed_head(&self) -> Result<i64> {
+        self.with_read_conn(|conn| {
+            let head = conn.query_row("SELECT COALESCE(MAX(id), 0) FROM change_feed", [], |r| {
+                r.get(0)
+            })?;
+            Ok(head)
+        })
+    }
+}
@@ -0,0 +1,140 @@
+//! Custom field storage operations.
+
+use super::tasks::parse_task_row;
+use super::{Database, now_ms};
+use crate::types::Task;
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, params};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Encode a field value for storage and equality filtering.
+pub fn encode_field_value(value: &Value) -> String {
+    value.to_string()
+}
+
+/// Upsert field values for a task; null values remove the field.
+pub(crate) fn set_task_fields_internal(
+    conn: &Connection,
+    task_id: &str,
+    fields: &Map<String, Value>,
+) -> Result<()> {
+    for (name, value) in fields {
+        if value.is_null() {
+            conn.execute(
+                "DELETE FROM task_fields WHERE task_id = ?1 AND name = ?2",
+                params![task_id, name],
+            )?;
+        } else {
+            conn.execute(
+                "INSERT INTO task_fields (task_id, name, value) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(task_id, name) DO UPDATE SET value = excluded.value",
+                params![task_id, name, encode_field_value(value)],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn decode_field_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+impl Database {
+    /// Set custom field values on a task. Null values remove the field.
+    pub fn set_task_fields(&self, task_id: &str, fields: &Map<String, Value>) -> Result<()> {
+        if fields.is_empty() {
+            return Ok(());
+        }
+
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            let updated = tx.execute(
+                "UPDATE tasks SET updated_at = ?1 WHERE id = ?2",
+                params![now_ms(), task_id],
+            )?;
+            if updated == 0 {
+                return Err(anyhow!("Task not found"));
+            }
+
+            set_task_fields_internal(&tx, task_id, fields)?;
+
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Get all custom field values of a task.
+    pub fn get_task_fields(&self, task_id: &str) -> Result<Map<String, Value>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT name, value FROM task_fields WHERE task_id = ?1 ORDER BY name",
+            )?;
+            let fields = stmt
+                .query_map(params![task_id], |row| {
+                    let name: String = row.get(0)?;
+                    let value: String = row.get(1)?;
+                    Ok((name, decode_field_value(&value)))
+                })?
+                .collect::<rusqlite::Result<Map<String, Value>>>()?;
+            Ok(fields)
+        })
+    }
+
+    /// Get custom field values for several tasks at once.
+    /// Tasks without fields are absent from the result.
+    pub fn get_task_fields_batch(
+        &self,
+        task_ids: &[String],
+    ) -> Result<HashMap<String, Map<String, Value>>> {
+        if task_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let ids_json = serde_json::to_string(task_ids)?;
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT task_id, name, value FROM task_fields
+                 WHERE task_id IN (SELECT value FROM json_each(?1))
+                 ORDER BY task_id, name",
+            )?;
+            let mut result: HashMap<String, Map<String, Value>> = HashMap::new();
+            let rows = stmt.query_map(params![ids_json], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?;
+            for row in rows {
+                let (task_id, name, value) = row?;
+                result
+                    .entry(task_id)
+                    .or_default()
+                    .insert(name, decode_field_value(&value));
+            }
+            Ok(result)
+        })
+    }
+
+    /// Live tasks that have a value for the named custom field, with that value,
+    /// ordered by ID.
+    pub fn get_tasks_with_field(&self, name: &str) -> Result<Vec<(Task, Value)>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT t.*, f.value AS field_value FROM tasks t
+                 JOIN task_fields f ON f.task_id = t.id
+                 WHERE f.name = ?1 AND t.deleted_at IS NULL
+                 ORDER BY t.id",
+            )?;
+            let tasks = stmt
+                .query_map(params![name], |row| {
+                    let value: String = row.get("field_value")?;
+                    Ok((parse_task_row(row)?, decode_field_value(&value)))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(tasks)
+        })
+    }
+}
@@ -0,0 +1,589 @@
+//! Filter expressions for task lists (the `where` argument of `list_tasks`).
+//!
+//! An expression combines comparisons with `AND`, `OR`, `NOT`, and parentheses
+//! (`AND` binds tighter than `OR`; keywords are case-insensitive):
+//!
+//! ```text
+//! status=working AND tag:backend AND priority>=7
+//! (owner=null OR status=blocked) AND NOT title~"flaky"
+//! fields.estimate>3 AND parent=epic-1
+//! ```
+//!
+//! A comparison is `field op value` with `=`, `!=`, `<`, `<=`, `>`, `>=`, or
+//! `~` (case-insensitive substring). Values are bare words or double-quoted
+//! strings, and `null` matches a missing value. `tag:name` matches a tag.
+//! Expressions compile to SQL with every value bound as a parameter.
+
+use super::Database;
+use crate::config::PrioritiesConfig;
+use crate::types::Task;
+use anyhow::{Result, anyhow, bail};
+use rusqlite::ToSql;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Deepest nesting of `NOT` and parentheses an expression may use.
+const MAX_DEPTH: usize = 32;
+
+/// A parsed filter expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskFilter {
+    And(Box<TaskFilter>, Box<TaskFilter>),
+    Or(Box<TaskFilter>, Box<TaskFilter>),
+    Not(Box<TaskFilter>),
+    /// The task has this tag.
+    Tag(String),
+    Compare {
+        field: FilterField,
+        op: CompareOp,
+        value: FilterValue,
+    },
+}
+
+/// What a comparison looks at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterField {
+    /// A `tasks` column.
+    Column(&'static str),
+    /// The containing task.
+    Parent,
+    /// A custom field, as `fields.<name>`.
+    Custom(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Null,
+    Int(i64),
+    Real(f64),
+    Text(String),
+}
+
+/// Task columns a filter can name: (filter name, column, numeric).
+const COLUMNS: &[(&str, &str, bool)] = &[
+    ("id", "id", false),
+    ("title", "title", false),
+    ("description", "description", false),
+    ("status", "status", false),
+    ("phase", "phase", false),
+    ("owner", "worker_id", false),
+    ("worker_id", "worker_id", false),
+    ("priority", "priority", true),
+    ("points", "points", true),
+    ("project", "project", false),
+    ("needed_group", "needed_group", false),
+    ("group", "needed_group", false),
+    ("blocked_reason", "blocked_reason", false),
+    ("created_at", "created_at", true),
+    ("updated_at", "updated_at", true),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Op(&'static str),
+    Word(String),
+    Quoted(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    const OPS: &[&str] = &["!=", "<=", ">=", "=", "<", ">", "~", ":"];
+    let mut tokens = Vec::new();
+    let mut rest = input.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '(' || c == ')' {
+            tokens.push(if c == '(' {
+                Token::LParen
+            } else {
+                Token::RParen
+            });
+            rest = &rest[1..];
+        } else if c == '"' {
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, escaped)) => value.push(escaped),
+                        None => bail!("Unterminated string"),
+                    },
+                    Some((_, other)) => value.push(other),
+                    None => bail!("Unterminated string"),
+                }
+            };
+            tokens.push(Token::Quoted(value));
+            rest = &rest[end..];
+        } else if let Some(op) = OPS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || "()\"!=<>~:".contains(c))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                bail!("Unexpected character '{}'", c);
+            }
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    priorities: &'a PrioritiesConfig,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Consume a keyword (a bare word, case-insensitive).
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn or(&mut self, depth: usize) -> Result<TaskFilter> {
+        let mut left = self.and(depth)?;
+        while self.keyword("OR") {
+            left = TaskFilter::Or(Box::new(left), Box::new(self.and(depth)?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self, depth: usize) -> Result<TaskFilter> {
+        let mut left = self.unary(depth)?;
+        while self.keyword("AND") {
+            left = TaskFilter::And(Box::new(left), Box::new(self.unary(depth)?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self, depth: usize) -> Result<TaskFilter> {
+        if depth > MAX_DEPTH {
+            bail!("Expression is nested too deeply");
+        }
+        if self.keyword("NOT") {
+            return Ok(TaskFilter::Not(Box::new(self.unary(depth + 1)?)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let inner = self.or(depth + 1)?;
+            if self.next() != Some(Token::RParen) {
+                bail!("Expected ')'");
+            }
+            return Ok(inner);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<TaskFilter> {
+        let name = match self.next() {
+            Some(Token::Word(name)) => name,
+            Some(other) => bail!("Expected a field name, got {:?}", other),
+            None => bail!("Expected a field name at end of expression"),
+        };
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => bail!("Expected an operator after '{}'", name),
+        };
+        let (value, quoted) = match self.next() {
+            Some(Token::Word(w)) => (w, false),
+            Some(Token::Quoted(s)) => (s, true),
+            _ => bail!("Expected a value after '{}{}'", name, op),
+        };
+
+        let custom = name.strip_prefix("fields.").map(String::from);
+        let name = name.to_lowercase();
+        if name == "tag" || name == "tags" {
+            return match op {
+                ":" | "=" => Ok(TaskFilter::Tag(value)),
+                "!=" => Ok(TaskFilter::Not(Box::new(TaskFilter::Tag(value)))),
+                _ => bail!("Tags only support tag:name and tag!=name"),
+            };
+        }
+        let op = match op {
+            "=" => CompareOp::Eq,
+            "!=" => CompareOp::Ne,
+            "<" => CompareOp::Lt,
+            "<=" => CompareOp::Le,
+            ">" => CompareOp::Gt,
+            ">=" => CompareOp::Ge,
+            "~" => CompareOp::Contains,
+            _ => bail!("'{}' is only valid after tag", op),
+        };
+
+        let is_null = !quoted && value.eq_ignore_ascii_case("null");
+        let (field, value) = if let Some(custom) = custom {
+            let value = if is_null {
+                FilterValue::Null
+            } else if quoted {
+                FilterValue::Text(value)
+            } else {
+                bare_value(value)
+            };
+            (FilterField::Custom(custom), value)
+        } else if name == "parent" {
+            if !matches!(op, CompareOp::Eq | CompareOp::Ne) {
+                bail!("parent only supports = and !=");
+            }
+            let value = if is_null {
+                FilterValue::Null
+            } else {
+                FilterValue::Text(value)
+            };
+            (FilterField::Parent, value)
+        } else {
+            let Some(&(_, column, numeric)) = COLUMNS.iter().find(|(n, _, _)| *n == name) else {
+                bail!("Unknown field '{}'", name);
+            };
+            let value = if is_null {
+                FilterValue::Null
+            } else if column == "priority" {
+                let priority = self.priorities.parse(&Value::String(value))?;
+                FilterValue::Int(priority.into())
+            } else if numeric {
+                match bare_value(value) {
+                    FilterValue::Text(v) => bail!("{} needs a number, got '{}'", name, v),
+                    number => number,
+                }
+            } else {
+                FilterValue::Text(value)
+            };
+            (FilterField::Column(column), value)
+        };
+
+        if value == FilterValue::Null && !matches!(op, CompareOp::Eq | CompareOp::Ne) {
+            bail!("null only supports = and !=");
+        }
+        Ok(TaskFilter::Compare { field, op, value })
+    }
+}
+
+/// An unquoted value: a number if it reads as one, else text.
+fn bare_value(value: String) -> FilterValue {
+    if let Ok(n) = value.parse::<i64>() {
+        FilterValue::Int(n)
+    } else if let Ok(n) = value.parse::<f64>() {
+        FilterValue::Real(n)
+    } else {
+        FilterValue::Text(value)
+    }
+}
+
+impl TaskFilter {
+    /// Parse an expression. Priority values may use configured labels.
+    pub fn parse(input: &str, priorities: &PrioritiesConfig) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            pos: 0,
+            priorities,
+        };
+        if parser.tokens.is_empty() {
+            bail!("Expression is empty");
+        }
+        let filter = parser.or(0)?;
+        if let Some(token) = parser.peek() {
+            return Err(anyhow!("Unexpected {:?} (missing AND/OR?)", token));
+        }
+        Ok(filter)
+    }
+
+    /// Rewrite every `tag:` term with `f`.
+    pub fn map_tags(&mut self, f: &dyn Fn(&str) -> String) {
+        match self {
+            TaskFilter::And(a, b) | TaskFilter::Or(a, b) => {
+                a.map_tags(f);
+                b.map_tags(f);
+            }
+            TaskFilter::Not(inner) => inner.map_tags(f),
+            TaskFilter::Tag(tag) => *tag = f(tag),
+            TaskFilter::Compare { .. } => {}
+        }
+    }
+
+    /// SQL condition over the `tasks` row aliased `t`, binding values after
+    /// those already in `params_vec`.
+    fn to_sql(&self, params_vec: &mut Vec<Box<dyn ToSql>>) -> String {
+        match self {
+            TaskFilter::And(a, b) => {
+                format!("({} AND {})", a.to_sql(params_vec), b.to_sql(params_vec))
+            }
+            TaskFilter::Or(a, b) => {
+                format!("({} OR {})", a.to_sql(params_vec), b.to_sql(params_vec))
+            }
+            // A comparison with a missing value is NULL, which NOT keeps false
+            TaskFilter::Not(inner) => format!("NOT COALESCE({}, 0)", inner.to_sql(params_vec)),
+            TaskFilter::Tag(tag) => format!(
+                "EXISTS (SELECT 1 FROM task_tags ft WHERE ft.task_id = t.id AND {})",
+                super::tag_match_sql("ft.tag", &bind(params_vec, &FilterValue::Text(tag.clone())))
+            ),
+            TaskFilter::Compare { field, op, value } => compare_sql(params_vec, field, *op, value),
+        }
+    }
+}
+
+/// Bind a value, returning its numbered placeholder.
+fn bind(params_vec: &mut Vec<Box<dyn ToSql>>, value: &FilterValue) -> String {
+    match value {
+        FilterValue::Null => params_vec.push(Box::new(rusqlite::types::Null)),
+        FilterValue::Int(n) => params_vec.push(Box::new(*n)),
+        FilterValue::Real(n) => params_vec.push(Box::new(*n)),
+        FilterValue::Text(s) => params_vec.push(Box::new(s.clone())),
+    }
+    format!("?{}", params_vec.len())
+}
+
+/// `expr op value` for a plain SQL expression. `!=` also matches missing
+/// values, and `~` is a case-insensitive substring match.
+fn compare_expr(
+    params_vec: &mut Vec<Box<dyn ToSql>>,
+    expr: &str,
+    op: CompareOp,
+    value: &FilterValue,
+) -> String {
+    if let FilterValue::Text(text) = value
+        && op == CompareOp::Contains
+    {
+        let pattern = format!(
+            "%{}%",
+            text.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        let placeholder = bind(params_vec, &FilterValue::Text(pattern));
+        return format!("{} LIKE {} ESCAPE '\\'", expr, placeholder);
+    }
+    let sql_op = match (op, value) {
+        (CompareOp::Eq, FilterValue::Null) => "IS",
+        (CompareOp::Ne, _) => "IS NOT",
+        (CompareOp::Eq | CompareOp::Contains, _) => "=",
+        (CompareOp::Lt, _) => "<",
+        (CompareOp::Le, _) => "<=",
+        (CompareOp::Gt, _) => ">",
+        (CompareOp::Ge, _) => ">=",
+    };
+    let placeholder = bind(params_vec, value);
+    format!("{} {} {}", expr, sql_op, placeholder)
+}
+
+fn compare_sql(
+    params_vec: &mut Vec<Box<dyn ToSql>>,
+    field: &FilterField,
+    op: CompareOp,
+    value: &FilterValue,
+) -> String {
+    match field {
+        FilterField::Column(column) => {
+            compare_expr(params_vec, &format!("t.{}", column), op, value)
+        }
+        FilterField::Parent => {
+            let children = match value {
+                FilterValue::Null => {
+                    "SELECT to_task_id FROM dependencies WHERE dep_type = 'contains'".to_string()
+                }
+                value => format!(
+                    "SELECT to_task_id FROM dependencies WHERE dep_type = 'contains' AND from_task_id = {}",
+                    bind(params_vec, value)
+                ),
+            };
+            // parent=null means "contained by nothing"
+            let inside = (op == CompareOp::Eq) != (*value == FilterValue::Null);
+            format!("t.id {}IN ({})", if inside { "" } else { "NOT " }, children)
+        }
+        FilterField::Custom(name) => {
+            let name = bind(params_vec, &FilterValue::Text(name.clone()));
+            let exists = |condition: &str| {
+                format!(
+                    "EXISTS (SELECT 1 FROM task_fields ff WHERE ff.task_id = t.id AND ff.name = {}{})",
+                    name, condition
+                )
+            };
+            match (op, value) {
+                (CompareOp::Eq, FilterValue::Null) => format!("NOT {}", exists("")),
+                (CompareOp::Ne, FilterValue::Null) => exists(""),
+                // A task without the field differs from any value
+                (CompareOp::Ne, value) => {
+                    let condition = compare_expr(
+                        params_vec,
+                        "json_extract(ff.value, '$')",
+                        CompareOp::Eq,
+                        value,
+                    );
+                    format!("NOT {}", exists(&format!(" AND {}", condition)))
+                }
+                (op, value) => {
+                    let condition =
+                        compare_expr(params_vec, "json_extract(ff.value, '$')", op, value);
+                    exists(&format!(" AND {}", condition))
+                }
+            }
+        }
+    }
+}
+
+/// `AND <filter>` for a query whose parameters so far are exactly
+/// `params_vec`, binding the filter's values after them. Empty without a filter.
+pub(crate) fn filter_clause(
+    params_vec: &mut Vec<Box<dyn ToSql>>,
+    filter: Option<&TaskFilter>,
+) -> String {
+    match filter {
+        Some(filter) => format!(" AND {}", filter.to_sql(params_vec)),
+        None => String::new(),
+    }
+}
+
+impl Database {
+    /// Keep only the tasks matching a filter, evaluated in the database.
+    /// For task lists not produced by a single query (e.g. ready tasks).
+    pub fn retain_matching(&self, tasks: &mut Vec<Task>, filter: &TaskFilter) -> Result<()> {
+        if tasks.is_empty() {
+            return Ok(());
+        }
+        let ids = serde_json::to_string(&tasks.iter().map(|t| &t.id).collect::<Vec<_>>())?;
+
+        let matching: HashSet<String> = self.with_read_conn(|conn| {
+            let mut params_vec: Vec<Box<dyn ToSql>> = vec![Box::new(ids)];
+            let sql = format!(
+                "SELECT t.id FROM tasks t WHERE t.id IN (SELECT value FROM json_each(?1)){}",
+                filter_clause(&mut params_vec, Some(filter))
+            );
+
+            let params_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| b.as_ref()).collect();
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let matching = stmt
+                .query_map(params_refs.as_slice(), |row| row.get(0))?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(matching)
+        })?;
+
+        tasks.retain(|t| matching.contains(&t.id));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<TaskFilter> {
+        TaskFilter::parse(input, &PrioritiesConfig::default())
+    }
+
+    fn column(column: &'static str, op: CompareOp, value: FilterValue) -> TaskFilter {
+        TaskFilter::Compare {
+            field: FilterField::Column(column),
+            op,
+            value,
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let filter = parse("status=working OR tag:backend and priority>=7").unwrap();
+        assert_eq!(
+            filter,
+            TaskFilter::Or(
+                Box::new(column(
+                    "status",
+                    CompareOp::Eq,
+                    FilterValue::Text("working".into())
+                )),
+                Box::new(TaskFilter::And(
+                    Box::new(TaskFilter::Tag("backend".into())),
+                    Box::new(column("priority", CompareOp::Ge, FilterValue::Int(7))),
+                )),
+            )
+        );
+    }
+
+    #[test]
+    fn parses_quoted_values_null_and_custom_fields() {
+        assert_eq!(
+            parse(r#"title~"say \"hi\"""#).unwrap(),
+            column(
+                "title",
+                CompareOp::Contains,
+                FilterValue::Text("say \"hi\"".into())
+            )
+        );
+        assert_eq!(
+            parse("NOT (owner = null)").unwrap(),
+            TaskFilter::Not(Box::new(column(
+                "worker_id",
+                CompareOp::Eq,
+                FilterValue::Null
+            )))
+        );
+        assert_eq!(
+            parse("fields.estimate>2.5").unwrap(),
+            TaskFilter::Compare {
+                field: FilterField::Custom("estimate".into()),
+                op: CompareOp::Gt,
+                value: FilterValue::Real(2.5),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for input in [
+            "",
+            "status",
+            "status=",
+            "color=red",
+            "status=working AND",
+            "(status=working",
+            "status=working owner=w1",
+            "priority>=high-ish",
+            "points>=many",
+            "owner<null",
+            "parent~x",
+            "title=\"open",
+        ] {
+            assert!(parse(input).is_err(), "{} should not parse", input);
+        }
+        let deep = format!("{}status=working{}", "(".repeat(40), ")".repeat(40));
+        assert!(parse(&deep).is_err());
+    }
+
+    #[test]
+    fn values_are_bound_not_inlined() {
+        let filter = parse("title~\"50%_off' OR 1=1\"").unwrap();
+        let mut params_vec: Vec<Box<dyn ToSql>> = vec![Box::new(0)];
+        let sql = filter_clause(&mut params_vec, Some(&filter));
+        assert_eq!(sql, " AND t.title LIKE ?2 ESCAPE '\\'");
+        assert_eq!(params_vec.len(), 2);
+    }
+}
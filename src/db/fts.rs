@@ -0,0 +1,190 @@
+//! Maintenance of the full-text search tables.
+//!
+//! Triggers keep `tasks_fts` and `attachments_fts` in step with ordinary
+//! writes. After bulk writes (import) or to repair drift, `sync_fts_rows`
+//! re-indexes just the tasks involved instead of wiping and repopulating both
+//! tables; `rebuild_fts_indexes` remains for repairing the whole index.
+
+use super::Database;
+use anyhow::Result;
+use rusqlite::{Connection, params};
+
+/// Pages FTS5 may write per incremental `merge` after a sync. Small enough to
+/// keep a sync cheap, large enough to stop segments piling up across imports.
+const FTS_MERGE_PAGES: i64 = 500;
+
+/// Re-index the FTS rows of `task_ids` from the base tables.
+///
+/// Stale rows for those tasks are dropped (including rows for tasks that no
+/// longer exist) and the current title, description and text attachments are
+/// inserted. Returns the number of task IDs synced.
+pub fn sync_fts_rows(conn: &Connection, task_ids: &[String]) -> Result<usize> {
+    if task_ids.is_empty() {
+        return Ok(0);
+    }
+    let ids = serde_json::to_string(task_ids)?;
+
+    conn.prepare_cached(
+        "DELETE FROM tasks_fts WHERE task_id IN (SELECT value FROM json_each(?1))",
+    )?
+    .execute(params![ids])?;
+    conn.prepare_cached(
+        "INSERT INTO tasks_fts(task_id, title, description)
+         SELECT id, title, COALESCE(description, '')
+         FROM tasks
+         WHERE id IN (SELECT value FROM json_each(?1))",
+    )?
+    .execute(params![ids])?;
+
+    conn.prepare_cached(
+        "DELETE FROM attachments_fts WHERE task_id IN (SELECT value FROM json_each(?1))",
+    )?
+    .execute(params![ids])?;
+    conn.prepare_cached(
+        "INSERT INTO attachments_fts(task_id, attachment_type, sequence, name, content)
+         SELECT task_id, attachment_type, sequence, name, content
+         FROM attachments
+         WHERE mime_type LIKE 'text/%' AND task_id IN (SELECT value FROM json_each(?1))",
+    )?
+    .execute(params![ids])?;
+
+    Ok(task_ids.len())
+}
+
+/// Run a bounded incremental `merge` over both FTS tables, folding the small
+/// segments left by a sync into larger ones.
+pub fn merge_fts_segments(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tasks_fts(tasks_fts, rank) VALUES('merge', ?1)",
+        params![FTS_MERGE_PAGES],
+    )?;
+    conn.execute(
+        "INSERT INTO attachments_fts(attachments_fts, rank) VALUES('merge', ?1)",
+        params![FTS_MERGE_PAGES],
+    )?;
+    Ok(())
+}
+
+impl Database {
+    /// Re-index the FTS rows of `task_ids` in one transaction, then merge
+    /// segments. Returns the number of task IDs synced.
+    pub fn sync_fts_for_tasks(&self, task_ids: &[String]) -> Result<usize> {
+        if task_ids.is_empty() {
+            return Ok(0);
+        }
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let synced = sync_fts_rows(&tx, task_ids)?;
+            tx.commit()?;
+            merge_fts_segments(conn)?;
+            Ok(synced)
+        })
+    }
+
+    /// Rebuild both FTS tables from the base tables.
+    ///
+    /// Triggers keep the index current during normal operation and import, so
+    /// this is only needed to repair an index that has drifted or been cleared.
+    /// It rewrites every row; prefer `sync_fts_for_tasks` when the affected
+    /// tasks are known.
+    pub fn rebuild_fts_indexes(&self) -> Result<()> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            tx.execute("DELETE FROM tasks_fts", [])?;
+            tx.execute(
+                "INSERT INTO tasks_fts(task_id, title, description)
+                 SELECT id, title, COALESCE(description, '')
+                 FROM tasks",
+                [],
+            )?;
+
+            // Only text content is indexed
+            tx.execute("DELETE FROM attachments_fts", [])?;
+            tx.execute(
+                "INSERT INTO attachments_fts(task_id, attachment_type, sequence, name, content)
+                 SELECT task_id, attachment_type, sequence, name, content
+                 FROM attachments
+                 WHERE mime_type LIKE 'text/%'",
+                [],
+            )?;
+
+            tx.commit()?;
+
+            // A full rewrite leaves many segments; merge them all at once
+            optimize_fts_segments(conn)
+        })
+    }
+}
+
+/// Merge every segment of both FTS tables into one.
+fn optimize_fts_segments(conn: &Connection) -> Result<()> {
+    conn.execute("INSERT INTO tasks_fts(tasks_fts) VALUES('optimize')", [])?;
+    conn.execute(
+        "INSERT INTO attachments_fts(attachments_fts) VALUES('optimize')",
+        [],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{IdsConfig, StatesConfig};
+
+    fn fts_titles(db: &Database, task_id: &str) -> Vec<String> {
+        db.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT title FROM tasks_fts WHERE task_id = ?1")?;
+            let titles = stmt
+                .query_map(params![task_id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(titles)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sync_repairs_only_given_tasks() {
+        let db = Database::open_in_memory().unwrap();
+        for (id, title) in [("a", "Alpha"), ("b", "Beta")] {
+            db.create_task(
+                Some(id.to_string()),
+                title.to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &StatesConfig::default(),
+                &IdsConfig::default(),
+            )
+            .unwrap();
+        }
+
+        // Drift both tasks' FTS rows away from the base table
+        db.with_conn(|conn| {
+            conn.execute("DELETE FROM tasks_fts", [])?;
+            conn.execute(
+                "INSERT INTO tasks_fts(task_id, title, description)
+                 VALUES ('a', 'Wrong', ''), ('a', 'Dup', ''), ('b', 'Wrong', '')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        let synced = db
+            .sync_fts_for_tasks(&["a".to_string(), "gone".to_string()])
+            .unwrap();
+        assert_eq!(synced, 2);
+        assert_eq!(fts_titles(&db, "a"), vec!["Alpha".to_string()]);
+        assert_eq!(fts_titles(&db, "b"), vec!["Wrong".to_string()]);
+
+        db.rebuild_fts_indexes().unwrap();
+        assert_eq!(fts_titles(&db, "b"), vec!["Beta".to_string()]);
+    }
+}
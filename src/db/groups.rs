@@ -0,0 +1,161 @@
+//! Agent group (team) membership and per-group workload.
+
+use super::{Database, now_ms};
+use crate::config::StatesConfig;
+use crate::types::GroupStats;
+use anyhow::Result;
+use rusqlite::{Connection, params};
+
+/// Check whether a worker belongs to a group, using an existing connection.
+pub(crate) fn is_group_member(conn: &Connection, worker_id: &str, group: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM worker_groups WHERE worker_id = ?1 AND group_name = ?2",
+        params![worker_id, group],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+fn group_members_internal(conn: &Connection, group: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT worker_id FROM worker_groups WHERE group_name = ?1 ORDER BY worker_id",
+    )?;
+    let members = stmt
+        .query_map(params![group], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(members)
+}
+
+impl Database {
+    /// Add a worker to groups. Returns the groups it was not already in.
+    pub fn join_groups(&self, worker_id: &str, groups: &[String]) -> Result<Vec<String>> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let now = now_ms();
+            let mut joined = Vec::new();
+            for group in groups {
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO worker_groups (worker_id, group_name, joined_at)
+                     VALUES (?1, ?2, ?3)",
+                    params![worker_id, group, now],
+                )?;
+                if inserted > 0 {
+                    joined.push(group.clone());
+                }
+            }
+            tx.commit()?;
+            Ok(joined)
+        })
+    }
+
+    /// Remove a worker from groups. Returns the groups it actually left.
+    pub fn leave_groups(&self, worker_id: &str, groups: &[String]) -> Result<Vec<String>> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let mut left = Vec::new();
+            for group in groups {
+                let deleted = tx.execute(
+                    "DELETE FROM worker_groups WHERE worker_id = ?1 AND group_name = ?2",
+                    params![worker_id, group],
+                )?;
+                if deleted > 0 {
+                    left.push(group.clone());
+                }
+            }
+            tx.commit()?;
+            Ok(left)
+        })
+    }
+
+    /// Get the groups a worker belongs to, sorted by name.
+    pub fn get_worker_groups(&self, worker_id: &str) -> Result<Vec<String>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT group_name FROM worker_groups WHERE worker_id = ?1 ORDER BY group_name",
+            )?;
+            let groups = stmt
+                .query_map(params![worker_id], |row| row.get(0))?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(groups)
+        })
+    }
+
+    /// Get the workers in a group, sorted by ID.
+    pub fn get_group_members(&self, group: &str) -> Result<Vec<String>> {
+        self.with_read_conn(|conn| group_members_internal(conn, group))
+    }
+
+    /// Get workload stats for every group with members or tasks, plus `extra_groups`
+    /// (e.g. configured groups nobody has joined yet). Sorted by group name.
+    /// Task counts exclude soft-deleted tasks.
+    pub fn get_group_stats(
+        &self,
+        extra_groups: &[&str],
+        states_config: &StatesConfig,
+    ) -> Result<Vec<GroupStats>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT group_name FROM worker_groups
+                 UNION
+                 SELECT needed_group FROM tasks
+                 WHERE needed_group IS NOT NULL AND deleted_at IS NULL",
+            )?;
+            let mut names: Vec<String> = stmt
+                .query_map([], |row| row.get(0))?
+                .filter_map(|r| r.ok())
+                .collect();
+            names.extend(extra_groups.iter().map(|g| g.to_string()));
+            names.sort();
+            names.dedup();
+
+            let mut task_stmt = conn.prepare_cached(
+                "SELECT status, worker_id IS NULL, COUNT(*), COALESCE(SUM(points), 0)
+                 FROM tasks WHERE needed_group = ?1 AND deleted_at IS NULL
+                 GROUP BY status, worker_id IS NULL",
+            )?;
+
+            let mut stats = Vec::with_capacity(names.len());
+            for group in names {
+                let mut entry = GroupStats {
+                    members: group_members_internal(conn, &group)?,
+                    open_tasks: 0,
+                    unclaimed_tasks: 0,
+                    open_points: 0,
+                    completed_tasks: 0,
+                    claimed_by_members: conn.query_row(
+                        "SELECT COUNT(*) FROM tasks
+                         WHERE deleted_at IS NULL AND worker_id IN
+                             (SELECT worker_id FROM worker_groups WHERE group_name = ?1)",
+                        params![group],
+                        |row| row.get(0),
+                    )?,
+                    group,
+                };
+
+                let rows = task_stmt.query_map(params![entry.group], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, bool>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, i64>(3)?,
+                    ))
+                })?;
+                for (status, unclaimed, count, points) in rows.filter_map(|r| r.ok()) {
+                    if states_config.is_blocking_state(&status) {
+                        entry.open_tasks += count;
+                        entry.open_points += points;
+                        if unclaimed {
+                            entry.unclaimed_tasks += count;
+                        }
+                    } else {
+                        entry.completed_tasks += count;
+                    }
+                }
+                stats.push(entry);
+            }
+            Ok(stats)
+        })
+    }
+}
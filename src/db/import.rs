@@ -14,12 +14,25 @@
 
 use crate::config::IdsConfig;
 use crate::export::{CURRENT_SCHEMA_VERSION, Snapshot};
+use crate::types::DEFAULT_PROJECT;
 use anyhow::{Context, Result, anyhow};
 use rusqlite::params;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use super::Database;
+use super::fts::{merge_fts_segments, sync_fts_rows};
+use super::tasks::{generate_task_id, generate_unsequenced_task_id};
+
+/// Remaps a snapshot's task IDs with a fresh-ID generator (e.g.
+/// [`remap_snapshot_with`]), returning the new snapshot and old->new mapping.
+type RemapStep<'a> = Box<
+    dyn FnOnce(
+            &Snapshot,
+            &mut dyn FnMut() -> Result<String>,
+        ) -> Result<(Snapshot, HashMap<String, String>)>
+        + 'a,
+>;
 
 /// Import mode determining how to handle existing data.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -109,8 +122,8 @@ pub struct ImportResult {
     pub rows_deleted: std::collections::BTreeMap<String, usize>,
     /// Number of rows skipped per table (for merge mode).
     pub rows_skipped: std::collections::BTreeMap<String, usize>,
-    /// Whether FTS indexes were rebuilt.
-    pub fts_rebuilt: bool,
+    /// Number of tasks whose full-text search rows were re-indexed.
+    pub fts_tasks_synced: usize,
     /// Any warnings encountered during import.
     pub warnings: Vec<String>,
     /// ID remapping table (old_id -> new_id), populated when remap_ids is used.
@@ -126,7 +139,7 @@ impl ImportResult {
             rows_imported: std::collections::BTreeMap::new(),
             rows_deleted: std::collections::BTreeMap::new(),
             rows_skipped: std::collections::BTreeMap::new(),
-            fts_rebuilt: false,
+            fts_tasks_synced: 0,
             warnings: Vec::new(),
             id_remap: None,
             parent_linked_roots: Vec::new(),
@@ -205,35 +218,31 @@ impl ImportOptions {
     }
 }
 
-/// Generate a fresh petname ID for use in ID remapping.
-/// Uses the same approach as generate_task_id in db/tasks.rs.
-fn generate_remap_id(ids_config: &IdsConfig) -> String {
-    use petname::{Generator, Petnames};
-
-    let words = ids_config.task_id_words;
-    let case = ids_config.id_case;
-
-    let base = Petnames::medium()
-        .generate_one(words, "-")
-        .unwrap_or_else(|| format!("task-{}", chrono::Utc::now().timestamp_millis()));
-
-    case.convert(&base)
-}
-
-/// Remap all task IDs in a snapshot, generating fresh petname IDs for each task
+/// Remap all task IDs in a snapshot, generating fresh IDs for each task
 /// and updating all references (dependencies, attachments, tags, state history).
 ///
 /// Returns a new snapshot with remapped IDs and the old->new ID mapping table.
+/// Sequential ID schemes need the database counter; use
+/// [`Database::import_snapshot_remapped`] with [`remap_snapshot_with`] for those.
 ///
 /// # Arguments
 /// * `snapshot` - The original snapshot to remap
-/// * `ids_config` - ID generation configuration (word count, case style)
+/// * `ids_config` - ID generation configuration (scheme, word count, case style)
 ///
 /// # Returns
 /// * `(Snapshot, HashMap<String, String>)` - The remapped snapshot and the old->new mapping
 pub fn remap_snapshot(
     snapshot: &Snapshot,
     ids_config: &IdsConfig,
+) -> Result<(Snapshot, HashMap<String, String>)> {
+    remap_snapshot_with(snapshot, || generate_unsequenced_task_id(ids_config))
+}
+
+/// Remap all task IDs in a snapshot using `next_id` to produce each fresh ID.
+/// See [`remap_snapshot`].
+pub fn remap_snapshot_with(
+    snapshot: &Snapshot,
+    mut next_id: impl FnMut() -> Result<String>,
 ) -> Result<(Snapshot, HashMap<String, String>)> {
     let mut remapped = snapshot.clone();
     let mut id_map: HashMap<String, String> = HashMap::new();
@@ -246,11 +255,11 @@ pub fn remap_snapshot(
 
         for task_row in tasks {
             if let Some(old_id) = task_row.get("id").and_then(|v| v.as_str()) {
-                let mut new_id = generate_remap_id(ids_config);
+                let mut new_id = next_id()?;
                 // Ensure uniqueness (retry on collision)
                 let mut attempts = 0;
                 while used_ids.contains(&new_id) {
-                    new_id = generate_remap_id(ids_config);
+                    new_id = next_id()?;
                     attempts += 1;
                     if attempts > 100 {
                         return Err(anyhow!(
@@ -334,6 +343,15 @@ pub fn remap_snapshot(
         }
     }
 
+    // task_fields: remap "task_id"
+    if let Some(fields) = remapped.tables.get_mut("task_fields") {
+        for field_row in fields.iter_mut() {
+            if let Some(obj) = field_row.as_object_mut() {
+                remap_field(obj, "task_id");
+            }
+        }
+    }
+
     // task_sequence: remap "task_id"
     if let Some(events) = remapped.tables.get_mut("task_sequence") {
         for event_row in events.iter_mut() {
@@ -343,6 +361,37 @@ pub fn remap_snapshot(
         }
     }
 
+    // task_changes: remap "task_id"
+    if let Some(changes) = remapped.tables.get_mut("task_changes") {
+        for change_row in changes.iter_mut() {
+            if let Some(obj) = change_row.as_object_mut() {
+                remap_field(obj, "task_id");
+            }
+        }
+    }
+
+    // task_thoughts: remap "task_id"
+    if let Some(thoughts) = remapped.tables.get_mut("task_thoughts") {
+        for thought_row in thoughts.iter_mut() {
+            if let Some(obj) = thought_row.as_object_mut() {
+                remap_field(obj, "task_id");
+            }
+        }
+    }
+
+    // audit_log: remap each entry of "task_ids"
+    if let Some(entries) = remapped.tables.get_mut("audit_log") {
+        for entry_row in entries.iter_mut() {
+            if let Some(Value::Array(ids)) = entry_row.get_mut("task_ids") {
+                for id in ids.iter_mut() {
+                    if let Some(new_id) = id.as_str().and_then(|old| id_map.get(old)) {
+                        *id = Value::String(new_id.clone());
+                    }
+                }
+            }
+        }
+    }
+
     Ok((remapped, id_map))
 }
 
@@ -408,7 +457,11 @@ const IMPORT_ORDER: &[&str] = &[
     "task_tags",
     "task_needed_tags",
     "task_wanted_tags",
+    "task_fields",
     "task_sequence",
+    "task_changes",
+    "task_thoughts",
+    "audit_log",
 ];
 
 impl Database {
@@ -421,7 +474,7 @@ impl Database {
     ///    - Replace: Clears existing project data (preserves runtime tables)
     ///    - Merge: Keeps existing data, adds only new items
     /// 3. Inserts all rows in the correct order (respecting foreign keys)
-    /// 4. Rebuilds FTS indexes
+    /// 4. Re-indexes full-text search rows for the tasks it touched
     ///
     /// # Arguments
     /// * `snapshot` - The snapshot to import
@@ -434,6 +487,52 @@ impl Database {
         &self,
         snapshot: &Snapshot,
         options: &ImportOptions,
+    ) -> Result<ImportResult> {
+        self.import_snapshot_inner(snapshot, options, None)
+    }
+
+    /// Import a snapshot after `remap` gives its tasks fresh IDs.
+    ///
+    /// `remap` receives a generator for IDs in the configured scheme that
+    /// draws on the import's own transaction, so sequential IDs are only
+    /// consumed when the import commits. The mapping is returned in
+    /// [`ImportResult::id_remap`].
+    pub fn import_snapshot_remapped(
+        &self,
+        snapshot: &Snapshot,
+        options: &ImportOptions,
+        ids_config: &IdsConfig,
+        remap: impl FnOnce(
+            &Snapshot,
+            &mut dyn FnMut() -> Result<String>,
+        ) -> Result<(Snapshot, HashMap<String, String>)>,
+    ) -> Result<ImportResult> {
+        self.import_snapshot_inner(snapshot, options, Some((ids_config, Box::new(remap))))
+    }
+
+    /// Run `remap` as [`Database::import_snapshot_remapped`] would, without
+    /// importing anything. The ID generator runs in a transaction that is
+    /// rolled back, so previews and dry runs leave the sequence untouched.
+    pub fn preview_remap(
+        &self,
+        snapshot: &Snapshot,
+        ids_config: &IdsConfig,
+        remap: impl FnOnce(
+            &Snapshot,
+            &mut dyn FnMut() -> Result<String>,
+        ) -> Result<(Snapshot, HashMap<String, String>)>,
+    ) -> Result<(Snapshot, HashMap<String, String>)> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            remap(snapshot, &mut || generate_task_id(&tx, ids_config))
+        })
+    }
+
+    fn import_snapshot_inner(
+        &self,
+        snapshot: &Snapshot,
+        options: &ImportOptions,
+        remap: Option<(&IdsConfig, RemapStep<'_>)>,
     ) -> Result<ImportResult> {
         // Validate schema version
         if snapshot.schema_version != CURRENT_SCHEMA_VERSION {
@@ -463,20 +562,29 @@ impl Database {
         }
 
         // Import tables in order
-        self.with_conn_mut(|conn| {
+        let remapped = self.with_conn_mut(|conn| {
             // Disable foreign key checks during import for performance
             // (we're importing in the correct order anyway)
             conn.execute("PRAGMA foreign_keys = OFF", [])?;
 
             // Use a transaction for atomicity
             let tx = conn.transaction()?;
+            let mut touched = BTreeSet::new();
+
+            let remapped = match remap {
+                Some((ids_config, remap)) => {
+                    Some(remap(snapshot, &mut || generate_task_id(&tx, ids_config))?)
+                }
+                None => None,
+            };
+            let snapshot = remapped.as_ref().map_or(snapshot, |(s, _)| s);
 
             for table_name in IMPORT_ORDER {
                 if let Some(rows) = snapshot.tables.get(*table_name) {
                     let (imported, skipped) = if options.mode == ImportMode::Merge {
-                        merge_table(&tx, table_name, rows)?
+                        merge_table(&tx, table_name, rows, &mut touched)?
                     } else {
-                        let count = import_table(&tx, table_name, rows)?;
+                        let count = import_table(&tx, table_name, rows, &mut touched)?;
                         (count, 0)
                     };
                     result
@@ -488,17 +596,30 @@ impl Database {
                 }
             }
 
+            // Triggers index rows as they are inserted; resyncing the tasks
+            // this import wrote also drops any stale rows under their IDs,
+            // without touching the rest of the index.
+            let touched: Vec<String> = touched.into_iter().collect();
+            result.fts_tasks_synced = sync_fts_rows(&tx, &touched)?;
+
             tx.commit()?;
 
             // Re-enable foreign keys
             conn.execute("PRAGMA foreign_keys = ON", [])?;
 
-            Ok(())
-        })?;
+            if result.fts_tasks_synced > 0 {
+                merge_fts_segments(conn)?;
+            }
 
-        // Rebuild FTS indexes
-        self.rebuild_fts_indexes()?;
-        result.fts_rebuilt = true;
+            Ok(remapped)
+        })?;
+        let snapshot = match remapped {
+            Some((remapped, id_map)) => {
+                result.id_remap = Some(id_map);
+                std::borrow::Cow::Owned(remapped)
+            }
+            None => std::borrow::Cow::Borrowed(snapshot),
+        };
 
         // If a parent task ID is specified, attach root tasks from the snapshot
         // under the parent with "contains" dependencies.
@@ -511,7 +632,7 @@ impl Database {
                 ));
             }
 
-            let root_ids = snapshot_root_task_ids(snapshot);
+            let root_ids = snapshot_root_task_ids(&snapshot);
             if !root_ids.is_empty() {
                 self.with_conn(|conn| {
                     for root_id in &root_ids {
@@ -614,7 +735,7 @@ impl Database {
 
     /// Preview what a merge import would do.
     fn preview_merge(&self, snapshot: &Snapshot, result: &mut DryRunResult) -> Result<()> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             for table_name in IMPORT_ORDER {
                 if let Some(rows) = snapshot.tables.get(*table_name) {
                     let (would_insert, would_skip) = preview_merge_table(conn, table_name, rows)?;
@@ -634,7 +755,7 @@ impl Database {
 
     /// Get the row count for each project data table.
     fn get_table_row_counts(&self) -> Result<std::collections::BTreeMap<String, usize>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut counts = std::collections::BTreeMap::new();
             for table in IMPORT_ORDER {
                 let count: i64 =
@@ -649,7 +770,7 @@ impl Database {
 
     /// Validate that the database is empty (no project data).
     fn validate_empty_database(&self) -> Result<()> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             for table in IMPORT_ORDER {
                 let count: i64 = conn.query_row(
                     &format!("SELECT COUNT(*) FROM {}", table),
@@ -690,6 +811,12 @@ impl Database {
             // Use a transaction for atomicity
             let tx = conn.transaction()?;
 
+            // Clear FTS and closure tables first so the per-row delete triggers
+            // below find nothing to remove; import re-indexes the rows it writes
+            tx.execute("DELETE FROM tasks_fts", [])?;
+            tx.execute("DELETE FROM attachments_fts", [])?;
+            tx.execute("DELETE FROM task_closure", [])?;
+
             // Delete in reverse order to respect foreign key constraints
             // (children first, then parents)
             for table_name in IMPORT_ORDER.iter().rev() {
@@ -704,14 +831,10 @@ impl Database {
                 }
             }
 
-            // Also clear FTS tables (they'll be rebuilt after import)
-            tx.execute("DELETE FROM tasks_fts", [])?;
-            tx.execute("DELETE FROM attachments_fts", [])?;
-
-            // Reset auto-increment counter for task_sequence
+            // Reset auto-increment counters for the history tables
             // This ensures imported IDs don't conflict with auto-generated ones
             tx.execute(
-                "DELETE FROM sqlite_sequence WHERE name = 'task_sequence'",
+                "DELETE FROM sqlite_sequence WHERE name IN ('task_sequence', 'task_changes', 'task_thoughts', 'audit_log')",
                 [],
             )?;
 
@@ -725,74 +848,59 @@ impl Database {
 
         Ok(deleted)
     }
-
-    /// Rebuild FTS indexes from the base tables.
-    ///
-    /// This is called after import to populate the FTS virtual tables
-    /// since triggers don't fire during bulk import.
-    pub fn rebuild_fts_indexes(&self) -> Result<()> {
-        self.with_conn(|conn| {
-            // Rebuild tasks_fts
-            conn.execute("DELETE FROM tasks_fts", [])?;
-            conn.execute(
-                "INSERT INTO tasks_fts(task_id, title, description)
-                 SELECT id, title, COALESCE(description, '')
-                 FROM tasks",
-                [],
-            )?;
-
-            // Rebuild attachments_fts (only text content)
-            conn.execute("DELETE FROM attachments_fts", [])?;
-            conn.execute(
-                "INSERT INTO attachments_fts(task_id, attachment_type, sequence, name, content)
-                 SELECT task_id, attachment_type, sequence, name, content
-                 FROM attachments
-                 WHERE mime_type LIKE 'text/%'",
-                [],
-            )?;
-
-            Ok(())
-        })
-    }
 }
 
 /// Import rows into a specific table.
-fn import_table(conn: &rusqlite::Connection, table_name: &str, rows: &[Value]) -> Result<usize> {
+/// Task IDs whose tasks or attachments were written are added to `touched`.
+fn import_table(
+    conn: &rusqlite::Connection,
+    table_name: &str,
+    rows: &[Value],
+    touched: &mut BTreeSet<String>,
+) -> Result<usize> {
     if rows.is_empty() {
         return Ok(0);
     }
 
     match table_name {
-        "tasks" => import_tasks(conn, rows),
+        "tasks" => import_tasks(conn, rows, touched),
         "dependencies" => import_dependencies(conn, rows),
-        "attachments" => import_attachments(conn, rows),
+        "attachments" => import_attachments(conn, rows, touched),
         "task_tags" => import_task_tags(conn, rows),
         "task_needed_tags" => import_task_needed_tags(conn, rows),
         "task_wanted_tags" => import_task_wanted_tags(conn, rows),
+        "task_fields" => import_task_fields(conn, rows),
         "task_sequence" => import_task_sequence(conn, rows),
+        "task_changes" => import_task_changes(conn, rows),
+        "task_thoughts" => import_task_thoughts(conn, rows),
+        "audit_log" => import_audit_log(conn, rows),
         _ => Err(anyhow!("Unknown table: {}", table_name)),
     }
 }
 
 /// Merge rows into a specific table (skip existing, insert new).
-/// Returns (imported_count, skipped_count).
+/// Returns (imported_count, skipped_count). Task IDs whose tasks or attachments
+/// were written are added to `touched`.
 fn merge_table(
     conn: &rusqlite::Connection,
     table_name: &str,
     rows: &[Value],
+    touched: &mut BTreeSet<String>,
 ) -> Result<(usize, usize)> {
     if rows.is_empty() {
         return Ok((0, 0));
     }
 
     match table_name {
-        "tasks" => merge_tasks(conn, rows),
+        "tasks" => merge_tasks(conn, rows, touched),
         "dependencies" => merge_dependencies(conn, rows),
-        "attachments" => merge_attachments(conn, rows),
+        "attachments" => merge_attachments(conn, rows, touched),
         "task_tags" => merge_task_tags(conn, rows),
         "task_needed_tags" => merge_task_needed_tags(conn, rows),
         "task_wanted_tags" => merge_task_wanted_tags(conn, rows),
+        "task_fields" => merge_task_fields(conn, rows),
         "task_sequence" => merge_task_sequence(conn, rows),
+        "task_changes" | "task_thoughts" | "audit_log" => Ok((0, rows.len())), // History: skipped like task_sequence
         _ => Err(anyhow!("Unknown table: {}", table_name)),
     }
 }
@@ -815,7 +923,9 @@ fn preview_merge_table(
         "task_tags" => preview_merge_task_tags(conn, rows),
         "task_needed_tags" => preview_merge_task_needed_tags(conn, rows),
         "task_wanted_tags" => preview_merge_task_wanted_tags(conn, rows),
+        "task_fields" => preview_merge_task_fields(conn, rows),
         "task_sequence" => Ok((0, rows.len())), // Always skip in merge mode
+        "task_changes" | "task_thoughts" | "audit_log" => Ok((0, rows.len())),
         _ => Err(anyhow!("Unknown table: {}", table_name)),
     }
 }
@@ -829,13 +939,9 @@ fn preview_merge_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(u
         let obj = row.as_object().context("Task row must be an object")?;
         let task_id = get_string(obj, "id")?;
 
-        let exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM tasks WHERE id = ?1",
-                params![&task_id],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+        let exists = conn
+            .prepare_cached("SELECT 1 FROM tasks WHERE id = ?1")?
+            .exists(params![&task_id])?;
 
         if exists {
             would_skip += 1;
@@ -863,13 +969,11 @@ fn preview_merge_dependencies(
         let to_id = get_string(obj, "to_task_id")?;
         let dep_type = get_string(obj, "dep_type")?;
 
-        let exists: bool = conn
-            .query_row(
+        let exists = conn
+            .prepare_cached(
                 "SELECT 1 FROM dependencies WHERE from_task_id = ?1 AND to_task_id = ?2 AND dep_type = ?3",
-                params![&from_id, &to_id, &dep_type],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+            )?
+            .exists(params![&from_id, &to_id, &dep_type])?;
 
         if exists {
             would_skip += 1;
@@ -897,13 +1001,11 @@ fn preview_merge_attachments(
         let attachment_type = get_string(obj, "attachment_type")?;
         let sequence = get_i32(obj, "sequence")?;
 
-        let exists: bool = conn
-            .query_row(
+        let exists = conn
+            .prepare_cached(
                 "SELECT 1 FROM attachments WHERE task_id = ?1 AND attachment_type = ?2 AND sequence = ?3",
-                params![&task_id, &attachment_type, sequence],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+            )?
+            .exists(params![&task_id, &attachment_type, sequence])?;
 
         if exists {
             would_skip += 1;
@@ -925,13 +1027,9 @@ fn preview_merge_task_tags(conn: &rusqlite::Connection, rows: &[Value]) -> Resul
         let task_id = get_string(obj, "task_id")?;
         let tag = get_string(obj, "tag")?;
 
-        let exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM task_tags WHERE task_id = ?1 AND tag = ?2",
-                params![&task_id, &tag],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+        let exists = conn
+            .prepare_cached("SELECT 1 FROM task_tags WHERE task_id = ?1 AND tag = ?2")?
+            .exists(params![&task_id, &tag])?;
 
         if exists {
             would_skip += 1;
@@ -958,13 +1056,9 @@ fn preview_merge_task_needed_tags(
         let task_id = get_string(obj, "task_id")?;
         let tag = get_string(obj, "tag")?;
 
-        let exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM task_needed_tags WHERE task_id = ?1 AND tag = ?2",
-                params![&task_id, &tag],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+        let exists = conn
+            .prepare_cached("SELECT 1 FROM task_needed_tags WHERE task_id = ?1 AND tag = ?2")?
+            .exists(params![&task_id, &tag])?;
 
         if exists {
             would_skip += 1;
@@ -991,13 +1085,36 @@ fn preview_merge_task_wanted_tags(
         let task_id = get_string(obj, "task_id")?;
         let tag = get_string(obj, "tag")?;
 
-        let exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM task_wanted_tags WHERE task_id = ?1 AND tag = ?2",
-                params![&task_id, &tag],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+        let exists = conn
+            .prepare_cached("SELECT 1 FROM task_wanted_tags WHERE task_id = ?1 AND tag = ?2")?
+            .exists(params![&task_id, &tag])?;
+
+        if exists {
+            would_skip += 1;
+        } else {
+            would_insert += 1;
+        }
+    }
+
+    Ok((would_insert, would_skip))
+}
+
+/// Preview merge for task_fields - count how many would be inserted vs skipped.
+fn preview_merge_task_fields(
+    conn: &rusqlite::Connection,
+    rows: &[Value],
+) -> Result<(usize, usize)> {
+    let mut would_insert = 0;
+    let mut would_skip = 0;
+
+    for row in rows {
+        let obj = row.as_object().context("TaskField row must be an object")?;
+        let task_id = get_string(obj, "task_id")?;
+        let name = get_string(obj, "name")?;
+
+        let exists = conn
+            .prepare_cached("SELECT 1 FROM task_fields WHERE task_id = ?1 AND name = ?2")?
+            .exists(params![&task_id, &name])?;
 
         if exists {
             would_skip += 1;
@@ -1010,7 +1127,11 @@ fn preview_merge_task_wanted_tags(
 }
 
 /// Merge tasks - skip if ID exists, insert if new.
-fn merge_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, usize)> {
+fn merge_tasks(
+    conn: &rusqlite::Connection,
+    rows: &[Value],
+    touched: &mut BTreeSet<String>,
+) -> Result<(usize, usize)> {
     let mut insert_stmt = conn.prepare(
         "INSERT INTO tasks (
             id, title, description, status, priority, worker_id, claimed_at,
@@ -1020,7 +1141,8 @@ fn merge_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, us
             metric_0, metric_1, metric_2, metric_3, metric_4, metric_5, metric_6, metric_7,
             cost_usd,
             deleted_at, deleted_by, deleted_reason,
-            created_at, updated_at
+            created_at, updated_at, phase,
+            blocked_reason, blocked_note, needed_group, project, required_child_tags, wanted_optional
         ) VALUES (
             ?1, ?2, ?3, ?4, ?5, ?6, ?7,
             ?8, ?9, ?10,
@@ -1029,7 +1151,8 @@ fn merge_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, us
             ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24,
             ?25,
             ?26, ?27, ?28,
-            ?29, ?30
+            ?29, ?30, ?31,
+            ?32, ?33, ?34, ?35, ?36, ?37
         )",
     )?;
 
@@ -1041,13 +1164,9 @@ fn merge_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, us
         let task_id = get_string(obj, "id")?;
 
         // Check if task already exists
-        let exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM tasks WHERE id = ?1",
-                params![&task_id],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+        let exists = conn
+            .prepare_cached("SELECT 1 FROM tasks WHERE id = ?1")?
+            .exists(params![&task_id])?;
 
         if exists {
             skipped += 1;
@@ -1059,7 +1178,7 @@ fn merge_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, us
             get_string(obj, "title")?,
             get_opt_string(obj, "description"),
             get_string(obj, "status")?,
-            get_string(obj, "priority")?,
+            get_priority(obj),
             get_opt_string(obj, "worker_id"),
             get_opt_i64(obj, "claimed_at"),
             get_opt_string(obj, "needed_tags"),
@@ -1085,7 +1204,15 @@ fn merge_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, us
             get_opt_string(obj, "deleted_reason"),
             get_i64(obj, "created_at")?,
             get_i64(obj, "updated_at")?,
+            get_opt_string(obj, "phase"),
+            get_opt_string(obj, "blocked_reason"),
+            get_opt_string(obj, "blocked_note"),
+            get_opt_string(obj, "needed_group"),
+            get_opt_string(obj, "project").unwrap_or_else(|| DEFAULT_PROJECT.to_string()),
+            get_opt_string(obj, "required_child_tags"),
+            get_i64_or_default(obj, "wanted_optional"),
         ])?;
+        touched.insert(task_id);
         imported += 1;
     }
 
@@ -1095,8 +1222,8 @@ fn merge_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, us
 /// Merge dependencies - skip if exact match exists.
 fn merge_dependencies(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, usize)> {
     let mut insert_stmt = conn.prepare(
-        "INSERT INTO dependencies (from_task_id, to_task_id, dep_type)
-         VALUES (?1, ?2, ?3)",
+        "INSERT INTO dependencies (from_task_id, to_task_id, dep_type, lag_ms, any_of)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
     )?;
 
     let mut imported = 0;
@@ -1111,20 +1238,24 @@ fn merge_dependencies(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(us
         let dep_type = get_string(obj, "dep_type")?;
 
         // Check if exact dependency already exists
-        let exists: bool = conn
-            .query_row(
+        let exists = conn
+            .prepare_cached(
                 "SELECT 1 FROM dependencies WHERE from_task_id = ?1 AND to_task_id = ?2 AND dep_type = ?3",
-                params![&from_id, &to_id, &dep_type],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+            )?
+            .exists(params![&from_id, &to_id, &dep_type])?;
 
         if exists {
             skipped += 1;
             continue;
         }
 
-        insert_stmt.execute(params![from_id, to_id, dep_type])?;
+        insert_stmt.execute(params![
+            from_id,
+            to_id,
+            dep_type,
+            get_opt_i64(obj, "lag_ms"),
+            get_opt_string(obj, "any_of")
+        ])?;
         imported += 1;
     }
 
@@ -1132,10 +1263,15 @@ fn merge_dependencies(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(us
 }
 
 /// Merge attachments - skip if exact match (task_id + attachment_type + sequence) exists.
-fn merge_attachments(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, usize)> {
+fn merge_attachments(
+    conn: &rusqlite::Connection,
+    rows: &[Value],
+    touched: &mut BTreeSet<String>,
+) -> Result<(usize, usize)> {
     let mut insert_stmt = conn.prepare(
-        "INSERT INTO attachments (task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        "INSERT INTO attachments (task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at,
+                                  content_hash, size_bytes, version)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
     )?;
 
     let mut imported = 0;
@@ -1150,13 +1286,11 @@ fn merge_attachments(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usi
         let sequence = get_i32(obj, "sequence")?;
 
         // Check if attachment already exists (by task_id + attachment_type + sequence)
-        let exists: bool = conn
-            .query_row(
+        let exists = conn
+            .prepare_cached(
                 "SELECT 1 FROM attachments WHERE task_id = ?1 AND attachment_type = ?2 AND sequence = ?3",
-                params![&task_id, &attachment_type, sequence],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+            )?
+            .exists(params![&task_id, &attachment_type, sequence])?;
 
         if exists {
             skipped += 1;
@@ -1172,7 +1306,11 @@ fn merge_attachments(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usi
             get_string(obj, "content")?,
             get_opt_string(obj, "file_path"),
             get_i64(obj, "created_at")?,
+            get_opt_string(obj, "content_hash"),
+            get_opt_i64(obj, "size_bytes"),
+            get_opt_i64(obj, "version").unwrap_or(1),
         ])?;
+        touched.insert(task_id);
         imported += 1;
     }
 
@@ -1192,13 +1330,9 @@ fn merge_task_tags(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize
         let tag = get_string(obj, "tag")?;
 
         // Check if tag already exists
-        let exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM task_tags WHERE task_id = ?1 AND tag = ?2",
-                params![&task_id, &tag],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+        let exists = conn
+            .prepare_cached("SELECT 1 FROM task_tags WHERE task_id = ?1 AND tag = ?2")?
+            .exists(params![&task_id, &tag])?;
 
         if exists {
             skipped += 1;
@@ -1228,13 +1362,9 @@ fn merge_task_needed_tags(conn: &rusqlite::Connection, rows: &[Value]) -> Result
         let tag = get_string(obj, "tag")?;
 
         // Check if tag already exists
-        let exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM task_needed_tags WHERE task_id = ?1 AND tag = ?2",
-                params![&task_id, &tag],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+        let exists = conn
+            .prepare_cached("SELECT 1 FROM task_needed_tags WHERE task_id = ?1 AND tag = ?2")?
+            .exists(params![&task_id, &tag])?;
 
         if exists {
             skipped += 1;
@@ -1264,13 +1394,9 @@ fn merge_task_wanted_tags(conn: &rusqlite::Connection, rows: &[Value]) -> Result
         let tag = get_string(obj, "tag")?;
 
         // Check if tag already exists
-        let exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM task_wanted_tags WHERE task_id = ?1 AND tag = ?2",
-                params![&task_id, &tag],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+        let exists = conn
+            .prepare_cached("SELECT 1 FROM task_wanted_tags WHERE task_id = ?1 AND tag = ?2")?
+            .exists(params![&task_id, &tag])?;
 
         if exists {
             skipped += 1;
@@ -1284,6 +1410,36 @@ fn merge_task_wanted_tags(conn: &rusqlite::Connection, rows: &[Value]) -> Result
     Ok((imported, skipped))
 }
 
+/// Merge task_fields - skip if the task already has a value for the field.
+fn merge_task_fields(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, usize)> {
+    let mut insert_stmt =
+        conn.prepare("INSERT INTO task_fields (task_id, name, value) VALUES (?1, ?2, ?3)")?;
+
+    let mut imported = 0;
+    let mut skipped = 0;
+
+    for row in rows {
+        let obj = row.as_object().context("TaskField row must be an object")?;
+        let task_id = get_string(obj, "task_id")?;
+        let name = get_string(obj, "name")?;
+
+        // Keep the existing value if the field is already set
+        let exists = conn
+            .prepare_cached("SELECT 1 FROM task_fields WHERE task_id = ?1 AND name = ?2")?
+            .exists(params![&task_id, &name])?;
+
+        if exists {
+            skipped += 1;
+            continue;
+        }
+
+        insert_stmt.execute(params![task_id, name, get_string(obj, "value")?])?;
+        imported += 1;
+    }
+
+    Ok((imported, skipped))
+}
+
 /// Merge task_sequence - skip all in merge mode to preserve existing history.
 /// State history from the snapshot is not imported to avoid conflicts with existing history.
 fn merge_task_sequence(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(usize, usize)> {
@@ -1295,7 +1451,11 @@ fn merge_task_sequence(conn: &rusqlite::Connection, rows: &[Value]) -> Result<(u
 }
 
 /// Import tasks table.
-fn import_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
+fn import_tasks(
+    conn: &rusqlite::Connection,
+    rows: &[Value],
+    touched: &mut BTreeSet<String>,
+) -> Result<usize> {
     let mut stmt = conn.prepare(
         "INSERT INTO tasks (
             id, title, description, status, priority, worker_id, claimed_at,
@@ -1305,7 +1465,8 @@ fn import_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
             metric_0, metric_1, metric_2, metric_3, metric_4, metric_5, metric_6, metric_7,
             cost_usd,
             deleted_at, deleted_by, deleted_reason,
-            created_at, updated_at
+            created_at, updated_at, phase,
+            blocked_reason, blocked_note, needed_group, project, required_child_tags, wanted_optional
         ) VALUES (
             ?1, ?2, ?3, ?4, ?5, ?6, ?7,
             ?8, ?9, ?10,
@@ -1314,7 +1475,8 @@ fn import_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
             ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24,
             ?25,
             ?26, ?27, ?28,
-            ?29, ?30
+            ?29, ?30, ?31,
+            ?32, ?33, ?34, ?35, ?36, ?37
         )",
     )?;
 
@@ -1322,12 +1484,14 @@ fn import_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
     for row in rows {
         let obj = row.as_object().context("Task row must be an object")?;
 
+        let task_id = get_string(obj, "id")?;
+
         stmt.execute(params![
-            get_string(obj, "id")?,
+            task_id,
             get_string(obj, "title")?,
             get_opt_string(obj, "description"),
             get_string(obj, "status")?,
-            get_string(obj, "priority")?,
+            get_priority(obj),
             get_opt_string(obj, "worker_id"),
             get_opt_i64(obj, "claimed_at"),
             get_opt_string(obj, "needed_tags"),
@@ -1353,7 +1517,15 @@ fn import_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
             get_opt_string(obj, "deleted_reason"),
             get_i64(obj, "created_at")?,
             get_i64(obj, "updated_at")?,
+            get_opt_string(obj, "phase"),
+            get_opt_string(obj, "blocked_reason"),
+            get_opt_string(obj, "blocked_note"),
+            get_opt_string(obj, "needed_group"),
+            get_opt_string(obj, "project").unwrap_or_else(|| DEFAULT_PROJECT.to_string()),
+            get_opt_string(obj, "required_child_tags"),
+            get_i64_or_default(obj, "wanted_optional"),
         ])?;
+        touched.insert(task_id);
         count += 1;
     }
 
@@ -1363,8 +1535,8 @@ fn import_tasks(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
 /// Import dependencies table.
 fn import_dependencies(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
     let mut stmt = conn.prepare(
-        "INSERT INTO dependencies (from_task_id, to_task_id, dep_type)
-         VALUES (?1, ?2, ?3)",
+        "INSERT INTO dependencies (from_task_id, to_task_id, dep_type, lag_ms, any_of)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
     )?;
 
     let mut count = 0;
@@ -1377,6 +1549,8 @@ fn import_dependencies(conn: &rusqlite::Connection, rows: &[Value]) -> Result<us
             get_string(obj, "from_task_id")?,
             get_string(obj, "to_task_id")?,
             get_string(obj, "dep_type")?,
+            get_opt_i64(obj, "lag_ms"),
+            get_opt_string(obj, "any_of"),
         ])?;
         count += 1;
     }
@@ -1385,10 +1559,15 @@ fn import_dependencies(conn: &rusqlite::Connection, rows: &[Value]) -> Result<us
 }
 
 /// Import attachments table.
-fn import_attachments(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
+fn import_attachments(
+    conn: &rusqlite::Connection,
+    rows: &[Value],
+    touched: &mut BTreeSet<String>,
+) -> Result<usize> {
     let mut stmt = conn.prepare(
-        "INSERT INTO attachments (task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        "INSERT INTO attachments (task_id, attachment_type, sequence, name, mime_type, content, file_path, created_at,
+                                  content_hash, size_bytes, version)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
     )?;
 
     let mut count = 0;
@@ -1397,8 +1576,10 @@ fn import_attachments(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usi
             .as_object()
             .context("Attachment row must be an object")?;
 
+        let task_id = get_string(obj, "task_id")?;
+
         stmt.execute(params![
-            get_string(obj, "task_id")?,
+            task_id,
             get_string(obj, "attachment_type")?,
             get_i32(obj, "sequence")?,
             get_string(obj, "name")?,
@@ -1406,7 +1587,11 @@ fn import_attachments(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usi
             get_string(obj, "content")?,
             get_opt_string(obj, "file_path"),
             get_i64(obj, "created_at")?,
+            get_opt_string(obj, "content_hash"),
+            get_opt_i64(obj, "size_bytes"),
+            get_opt_i64(obj, "version").unwrap_or(1),
         ])?;
+        touched.insert(task_id);
         count += 1;
     }
 
@@ -1471,6 +1656,26 @@ fn import_task_wanted_tags(conn: &rusqlite::Connection, rows: &[Value]) -> Resul
     Ok(count)
 }
 
+/// Import task_fields table.
+fn import_task_fields(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
+    let mut stmt =
+        conn.prepare("INSERT INTO task_fields (task_id, name, value) VALUES (?1, ?2, ?3)")?;
+
+    let mut count = 0;
+    for row in rows {
+        let obj = row.as_object().context("TaskField row must be an object")?;
+
+        stmt.execute(params![
+            get_string(obj, "task_id")?,
+            get_string(obj, "name")?,
+            get_string(obj, "value")?,
+        ])?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
 /// Import task_sequence table.
 fn import_task_sequence(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
     let mut stmt = conn.prepare(
@@ -1500,6 +1705,96 @@ fn import_task_sequence(conn: &rusqlite::Connection, rows: &[Value]) -> Result<u
     Ok(count)
 }
 
+/// Import task_changes table.
+fn import_task_changes(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO task_changes (id, task_id, worker_id, field, old_value, new_value, timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+
+    // Values are stored as JSON text, NULL when unset
+    let json_text = |v: Option<&Value>| v.filter(|v| !v.is_null()).map(Value::to_string);
+
+    let mut count = 0;
+    for row in rows {
+        let obj = row
+            .as_object()
+            .context("TaskChange row must be an object")?;
+
+        stmt.execute(params![
+            get_i64(obj, "id")?,
+            get_string(obj, "task_id")?,
+            get_opt_string(obj, "worker_id"),
+            get_string(obj, "field")?,
+            json_text(obj.get("old_value")),
+            json_text(obj.get("new_value")),
+            get_i64(obj, "timestamp")?,
+        ])?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Import task_thoughts table.
+fn import_task_thoughts(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO task_thoughts (id, task_id, worker_id, thought, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+
+    let mut count = 0;
+    for row in rows {
+        let obj = row
+            .as_object()
+            .context("TaskThought row must be an object")?;
+
+        stmt.execute(params![
+            get_i64(obj, "id")?,
+            get_string(obj, "task_id")?,
+            get_string(obj, "worker_id")?,
+            get_string(obj, "thought")?,
+            get_i64(obj, "created_at")?,
+        ])?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Import audit_log table.
+fn import_audit_log(conn: &rusqlite::Connection, rows: &[Value]) -> Result<usize> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO audit_log (id, worker_id, tool, args_hash, task_ids, outcome, error, timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+
+    let mut count = 0;
+    for row in rows {
+        let obj = row
+            .as_object()
+            .context("AuditEntry row must be an object")?;
+        let task_ids = obj
+            .get("task_ids")
+            .cloned()
+            .unwrap_or_else(|| Value::Array(vec![]));
+
+        stmt.execute(params![
+            get_i64(obj, "id")?,
+            get_opt_string(obj, "worker_id"),
+            get_string(obj, "tool")?,
+            get_string(obj, "args_hash")?,
+            task_ids.to_string(),
+            get_string(obj, "outcome")?,
+            get_opt_string(obj, "error"),
+            get_i64(obj, "timestamp")?,
+        ])?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
 // ============================================================================
 // JSON value extraction helpers
 // ============================================================================
@@ -1512,6 +1807,16 @@ fn get_string(obj: &serde_json::Map<String, Value>, key: &str) -> Result<String>
         .ok_or_else(|| anyhow!("Missing or invalid string field: {}", key))
 }
 
+/// Get a task's priority as stored: exports write it as a number and leave it
+/// out when it is the default, older snapshots hold a string.
+fn get_priority(obj: &serde_json::Map<String, Value>) -> String {
+    match obj.get("priority") {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => crate::types::PRIORITY_DEFAULT.to_string(),
+    }
+}
+
 /// Get an optional string value from a JSON object.
 fn get_opt_string(obj: &serde_json::Map<String, Value>, key: &str) -> Option<String> {
     obj.get(key).and_then(|v| {
@@ -1583,7 +1888,7 @@ mod tests {
 
         let result = db.import_snapshot(&snapshot, &options).unwrap();
         assert_eq!(result.total_rows(), 0);
-        assert!(result.fts_rebuilt);
+        assert_eq!(result.fts_tasks_synced, 0);
     }
 
     #[test]
@@ -1631,10 +1936,10 @@ mod tests {
         let result = db.import_snapshot(&snapshot, &options).unwrap();
 
         assert_eq!(result.rows_imported.get("tasks"), Some(&1));
-        assert!(result.fts_rebuilt);
+        assert_eq!(result.fts_tasks_synced, 1);
 
         // Verify FTS was populated
-        let results = db.search_tasks("Test", None, 0, false, None).unwrap();
+        let results = db.search_tasks("Test", None, 0, false, None, None).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].task_id, "task-1");
     }
@@ -2056,7 +2361,9 @@ mod tests {
         assert_eq!(result.rows_imported.get("attachments"), Some(&1));
 
         // Verify attachment FTS was populated
-        let results = db.search_tasks("searchable", None, 0, true, None).unwrap();
+        let results = db
+            .search_tasks("searchable", None, 0, true, None, None)
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].attachment_matches.len(), 1);
     }
@@ -2227,7 +2534,9 @@ mod tests {
         }).unwrap();
 
         // FTS should have the task due to triggers
-        let results = db.search_tasks("Manual", None, 0, false, None).unwrap();
+        let results = db
+            .search_tasks("Manual", None, 0, false, None, None)
+            .unwrap();
         assert_eq!(results.len(), 1);
 
         // Now delete from FTS to simulate a corrupted/empty FTS state
@@ -2238,18 +2547,69 @@ mod tests {
         .unwrap();
 
         // Search should now find nothing
-        let results = db.search_tasks("Manual", None, 0, false, None).unwrap();
+        let results = db
+            .search_tasks("Manual", None, 0, false, None, None)
+            .unwrap();
         assert!(results.is_empty());
 
         // Rebuild FTS
         db.rebuild_fts_indexes().unwrap();
 
         // Now search should work again
-        let results = db.search_tasks("Manual", None, 0, false, None).unwrap();
+        let results = db
+            .search_tasks("Manual", None, 0, false, None, None)
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].task_id, "test-task");
     }
 
+    #[test]
+    fn test_merge_import_only_reindexes_touched_tasks() {
+        let db = Database::open_in_memory().unwrap();
+        let mut snapshot = Snapshot::new();
+        snapshot.tables.insert(
+            "tasks".to_string(),
+            vec![make_task_json("existing", "Existing task")],
+        );
+        db.import_snapshot(&snapshot, &ImportOptions::default())
+            .unwrap();
+
+        // Leave a stale FTS row for the existing task
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO tasks_fts(task_id, title, description) VALUES ('existing', 'Stale', '')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        snapshot.tables.insert(
+            "tasks".to_string(),
+            vec![
+                make_task_json("existing", "Existing task"),
+                make_task_json("added", "Added task"),
+            ],
+        );
+        let options = ImportOptions {
+            mode: ImportMode::Merge,
+            ..Default::default()
+        };
+        let result = db.import_snapshot(&snapshot, &options).unwrap();
+        assert_eq!(result.fts_tasks_synced, 1);
+
+        // The new task is indexed once despite the insert trigger also firing
+        let added = db
+            .search_tasks("Added", None, 0, false, None, None)
+            .unwrap();
+        assert_eq!(added.len(), 1);
+        // The skipped task's rows were left alone
+        let stale = db
+            .search_tasks("Stale", None, 0, false, None, None)
+            .unwrap();
+        assert_eq!(stale.len(), 1);
+    }
+
     #[test]
     fn test_import_mode_default() {
         // Default mode should be Fresh
@@ -3265,11 +3625,13 @@ mod tests {
         let new_beta = &id_map["task-beta"];
 
         // Search for the tasks in the database
-        let alpha_results = db.search_tasks("Alpha", None, 0, false, None).unwrap();
+        let alpha_results = db
+            .search_tasks("Alpha", None, 0, false, None, None)
+            .unwrap();
         assert_eq!(alpha_results.len(), 1);
         assert_eq!(alpha_results[0].task_id, *new_alpha);
 
-        let beta_results = db.search_tasks("Beta", None, 0, false, None).unwrap();
+        let beta_results = db.search_tasks("Beta", None, 0, false, None, None).unwrap();
         assert_eq!(beta_results.len(), 1);
         assert_eq!(beta_results[0].task_id, *new_beta);
     }
@@ -5,9 +5,20 @@
 //!   holds the mark, but allows it.
 //! - **Exclusive locks** (`lock:` prefix): `mark_file("lock:git-commit")` - rejects with
 //!   an error if another agent holds the lock. Used for mutual exclusion on shared resources.
+//!
+//! Advisory marks overlap when they name the same path or one path is a directory
+//! containing the other. `find_mark_conflicts` reports overlapping marks held by
+//! other agents, and `mark_waiters` rows let an agent wait for them to be released.
+//! The optional mark watcher records files changed on disk without any covering
+//! mark in `mark_violations`.
+//!
+//! Marks may carry a lease (`expires_at`); `reap_expired_marks` releases them once
+//! the lease passes so marks held by dead agents do not linger.
 
 use super::{Database, now_ms};
-use crate::types::{ClaimEvent, ClaimEventType, ClaimUpdates, FileLock};
+use crate::types::{
+    ClaimEvent, ClaimEventType, ClaimUpdates, DEFAULT_PROJECT, FileLock, MarkViolation,
+};
 use anyhow::Result;
 use rusqlite::params;
 use std::collections::{HashMap, HashSet};
@@ -22,6 +33,44 @@ pub enum ExclusiveLockResult {
     HeldByOther(String),
 }
 
+/// Whether two advisory mark paths overlap: the same path, or one is a directory
+/// containing the other.
+pub fn mark_paths_overlap(a: &str, b: &str) -> bool {
+    fn contains(dir: &str, path: &str) -> bool {
+        let dir = dir.trim_end_matches('/');
+        path.len() > dir.len() && path.starts_with(dir) && path[dir.len()..].starts_with('/')
+    }
+    a == b || contains(a, b) || contains(b, a)
+}
+
+/// Advisory marks held by agents other than `worker_id` that overlap `file_path`.
+fn overlapping_marks(
+    conn: &rusqlite::Connection,
+    file_path: &str,
+    worker_id: &str,
+) -> Result<Vec<FileLock>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT file_path, worker_id, reason, locked_at, task_id, expires_at, project FROM file_locks
+         WHERE worker_id != ?1 AND file_path NOT LIKE 'lock:%'",
+    )?;
+    let marks = stmt
+        .query_map(params![worker_id], |row| {
+            Ok(FileLock {
+                file_path: row.get(0)?,
+                worker_id: row.get(1)?,
+                reason: row.get(2)?,
+                locked_at: row.get(3)?,
+                task_id: row.get(4)?,
+                expires_at: row.get(5)?,
+                project: row.get(6)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .filter(|m| mark_paths_overlap(&m.file_path, file_path))
+        .collect();
+    Ok(marks)
+}
+
 impl Database {
     /// Acquire an exclusive lock on a resource.
     ///
@@ -46,8 +95,10 @@ impl Database {
 
             // Check if already locked
             let existing: Option<String> = tx
-                .query_row(
+                .prepare_cached(
                     "SELECT worker_id FROM file_locks WHERE file_path = ?1",
+                )?
+                .query_row(
                     params![&file_path],
                     |row| row.get(0),
                 )
@@ -68,8 +119,9 @@ impl Database {
             } else {
                 // Not locked - create new lock
                 tx.execute(
-                    "INSERT INTO file_locks (file_path, worker_id, reason, locked_at, task_id) VALUES (?1, ?2, ?3, ?4, ?5)",
-                    params![&file_path, worker_id, &reason, now, &task_id],
+                    "INSERT INTO file_locks (file_path, worker_id, reason, locked_at, task_id, project)
+                     VALUES (?1, ?2, ?3, ?4, ?5, COALESCE((SELECT project FROM workers WHERE id = ?2), ?6))",
+                    params![&file_path, worker_id, &reason, now, &task_id, DEFAULT_PROJECT],
                 )?;
 
                 // Record claim event for tracking
@@ -100,8 +152,10 @@ impl Database {
             let tx = conn.transaction()?;
             // Check if already locked
             let existing: Option<String> = tx
-                .query_row(
+                .prepare_cached(
                     "SELECT worker_id FROM file_locks WHERE file_path = ?1",
+                )?
+                .query_row(
                     params![&file_path],
                     |row| row.get(0),
                 )
@@ -122,8 +176,9 @@ impl Database {
             } else {
                 // Not locked - create new lock
                 tx.execute(
-                    "INSERT INTO file_locks (file_path, worker_id, reason, locked_at, task_id) VALUES (?1, ?2, ?3, ?4, ?5)",
-                    params![&file_path, worker_id, &reason, now, &task_id],
+                    "INSERT INTO file_locks (file_path, worker_id, reason, locked_at, task_id, project)
+                     VALUES (?1, ?2, ?3, ?4, ?5, COALESCE((SELECT project FROM workers WHERE id = ?2), ?6))",
+                    params![&file_path, worker_id, &reason, now, &task_id, DEFAULT_PROJECT],
                 )?;
 
                 // Record claim event for tracking
@@ -158,12 +213,15 @@ impl Database {
 
             if deleted > 0 {
                 // Find the claim_id for this file+worker (most recent claim)
-                let claim_id: Option<i64> = tx.query_row(
-                    "SELECT MAX(id) FROM claim_sequence
-                     WHERE file_path = ?1 AND worker_id = ?2 AND event = 'claimed'",
-                    params![file_path, worker_id],
-                    |row| row.get(0),
-                ).ok().flatten();
+                let claim_id: Option<i64> = tx
+                    .prepare_cached(
+                        "SELECT MAX(id) FROM claim_sequence
+                         WHERE file_path = ?1 AND worker_id = ?2 AND event = 'claimed'",
+                    )?
+                    .query_row(
+                        params![file_path, worker_id],
+                        |row| row.get(0),
+                    ).ok().flatten();
 
                 // Close any open claim for this file+worker
                 tx.execute(
@@ -207,12 +265,15 @@ impl Database {
 
                 if deleted > 0 {
                     // Find the claim_id for this file+worker (most recent claim)
-                    let claim_id: Option<i64> = tx.query_row(
-                        "SELECT MAX(id) FROM claim_sequence
-                         WHERE file_path = ?1 AND worker_id = ?2 AND event = 'claimed'",
-                        params![&file_path, worker_id],
-                        |row| row.get(0),
-                    ).ok().flatten();
+                    let claim_id: Option<i64> = tx
+                        .prepare_cached(
+                            "SELECT MAX(id) FROM claim_sequence
+                             WHERE file_path = ?1 AND worker_id = ?2 AND event = 'claimed'",
+                        )?
+                        .query_row(
+                            params![&file_path, worker_id],
+                            |row| row.get(0),
+                        ).ok().flatten();
 
                     // Close any open claim for this file+worker
                     tx.execute(
@@ -252,7 +313,7 @@ impl Database {
             // Get files locked by this worker before deleting
             let files_to_release: Vec<String> = {
                 let mut stmt =
-                    tx.prepare("SELECT file_path FROM file_locks WHERE worker_id = ?1")?;
+                    tx.prepare_cached("SELECT file_path FROM file_locks WHERE worker_id = ?1")?;
                 stmt.query_map(params![worker_id], |row| row.get::<_, String>(0))?
                     .filter_map(|r| r.ok())
                     .collect()
@@ -310,8 +371,9 @@ impl Database {
 
             // Get files locked by this task before deleting
             let files_to_release: Vec<(String, String)> = {
-                let mut stmt =
-                    tx.prepare("SELECT file_path, worker_id FROM file_locks WHERE task_id = ?1")?;
+                let mut stmt = tx.prepare_cached(
+                    "SELECT file_path, worker_id FROM file_locks WHERE task_id = ?1",
+                )?;
                 stmt.query_map(params![task_id], |row| {
                     Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
                 })?
@@ -358,16 +420,13 @@ impl Database {
         self.with_conn(|conn| {
             // Get worker's last sequence
             let last_seq: i64 = conn
-                .query_row(
-                    "SELECT last_claim_sequence FROM workers WHERE id = ?1",
-                    params![worker_id],
-                    |row| row.get(0),
-                )
+                .prepare_cached("SELECT last_claim_sequence FROM workers WHERE id = ?1")?
+                .query_row(params![worker_id], |row| row.get(0))
                 .unwrap_or(0);
 
             // Get all new events since last sequence.
             // We use >= because last_seq now represents "next event to fetch" (set to max+1 after each poll).
-            let mut stmt = conn.prepare(
+            let mut stmt = conn.prepare_cached(
                 "SELECT id, file_path, worker_id, event, reason, timestamp, end_timestamp, claim_id
                  FROM claim_sequence
                  WHERE id >= ?1
@@ -445,7 +504,7 @@ impl Database {
         agent_id: Option<&str>,
         task_id: Option<&str>,
     ) -> Result<HashMap<String, FileLock>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let locks = if let Some(paths) = file_paths {
                 if paths.is_empty() {
                     return Ok(HashMap::new());
@@ -453,7 +512,7 @@ impl Database {
 
                 let placeholders: Vec<String> = paths.iter().map(|_| "?".to_string()).collect();
                 let sql = format!(
-                    "SELECT file_path, worker_id, reason, locked_at, task_id FROM file_locks WHERE file_path IN ({})",
+                    "SELECT file_path, worker_id, reason, locked_at, task_id, expires_at, project FROM file_locks WHERE file_path IN ({})",
                     placeholders.join(", ")
                 );
 
@@ -465,7 +524,7 @@ impl Database {
                 let params_refs: Vec<&dyn rusqlite::ToSql> =
                     params_vec.iter().map(|b| b.as_ref()).collect();
 
-                let mut stmt = conn.prepare(&sql)?;
+                let mut stmt = conn.prepare_cached(&sql)?;
                 stmt.query_map(params_refs.as_slice(), |row| {
                     let file_path: String = row.get(0)?;
                     Ok((file_path.clone(), FileLock {
@@ -474,13 +533,15 @@ impl Database {
                         reason: row.get(2)?,
                         locked_at: row.get(3)?,
                         task_id: row.get(4)?,
+                        expires_at: row.get(5)?,
+                        project: row.get(6)?,
                     }))
                 })?
                 .filter_map(|r| r.ok())
                 .collect()
             } else if let Some(aid) = agent_id {
-                let mut stmt = conn.prepare(
-                    "SELECT file_path, worker_id, reason, locked_at, task_id FROM file_locks WHERE worker_id = ?1",
+                let mut stmt = conn.prepare_cached(
+                    "SELECT file_path, worker_id, reason, locked_at, task_id, expires_at, project FROM file_locks WHERE worker_id = ?1",
                 )?;
                 stmt.query_map(params![aid], |row| {
                     let file_path: String = row.get(0)?;
@@ -490,13 +551,15 @@ impl Database {
                         reason: row.get(2)?,
                         locked_at: row.get(3)?,
                         task_id: row.get(4)?,
+                        expires_at: row.get(5)?,
+                        project: row.get(6)?,
                     }))
                 })?
                 .filter_map(|r| r.ok())
                 .collect()
             } else if let Some(tid) = task_id {
-                let mut stmt = conn.prepare(
-                    "SELECT file_path, worker_id, reason, locked_at, task_id FROM file_locks WHERE task_id = ?1",
+                let mut stmt = conn.prepare_cached(
+                    "SELECT file_path, worker_id, reason, locked_at, task_id, expires_at, project FROM file_locks WHERE task_id = ?1",
                 )?;
                 stmt.query_map(params![tid], |row| {
                     let file_path: String = row.get(0)?;
@@ -506,6 +569,8 @@ impl Database {
                         reason: row.get(2)?,
                         locked_at: row.get(3)?,
                         task_id: row.get(4)?,
+                        expires_at: row.get(5)?,
+                        project: row.get(6)?,
                     }))
                 })?
                 .filter_map(|r| r.ok())
@@ -519,11 +584,298 @@ impl Database {
         })
     }
 
+    /// Set (or clear, with `None`) the lease expiry of a worker's mark.
+    pub fn set_mark_expiry(
+        &self,
+        file_path: &str,
+        worker_id: &str,
+        expires_at: Option<i64>,
+    ) -> Result<bool> {
+        self.with_conn(|conn| {
+            let updated = conn.execute(
+                "UPDATE file_locks SET expires_at = ?1 WHERE file_path = ?2 AND worker_id = ?3",
+                params![expires_at, file_path, worker_id],
+            )?;
+            Ok(updated > 0)
+        })
+    }
+
+    /// Release every mark whose lease has expired, logging a release event for each.
+    /// Returns a list of (file_path, worker_id) pairs for marks that were released.
+    pub fn reap_expired_marks(&self) -> Result<Vec<(String, String)>> {
+        let now = now_ms();
+
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            let expired: Vec<(String, String)> = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT file_path, worker_id FROM file_locks
+                     WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+                )?;
+                stmt.query_map(params![now], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .filter_map(|r| r.ok())
+                .collect()
+            };
+
+            for (file_path, worker_id) in &expired {
+                let claim_id: Option<i64> = tx
+                    .prepare_cached(
+                        "SELECT MAX(id) FROM claim_sequence
+                         WHERE file_path = ?1 AND worker_id = ?2 AND event = 'claimed'",
+                    )?
+                    .query_row(
+                        params![file_path, worker_id],
+                        |row| row.get(0),
+                    )
+                    .ok()
+                    .flatten();
+
+                tx.execute(
+                    "UPDATE claim_sequence SET end_timestamp = ?1
+                     WHERE file_path = ?2 AND worker_id = ?3 AND end_timestamp IS NULL",
+                    params![now, file_path, worker_id],
+                )?;
+
+                tx.execute(
+                    "INSERT INTO claim_sequence (file_path, worker_id, event, reason, timestamp, claim_id)
+                     VALUES (?1, ?2, 'released', 'lease expired', ?3, ?4)",
+                    params![file_path, worker_id, now, claim_id],
+                )?;
+
+                tx.execute(
+                    "DELETE FROM file_locks WHERE file_path = ?1 AND worker_id = ?2",
+                    params![file_path, worker_id],
+                )?;
+            }
+
+            tx.commit()?;
+            Ok(expired)
+        })
+    }
+
+    /// Get a worker's marks whose lease expires within `within_ms`, soonest first.
+    pub fn get_expiring_marks(&self, worker_id: &str, within_ms: i64) -> Result<Vec<FileLock>> {
+        let now = now_ms();
+
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT file_path, worker_id, reason, locked_at, task_id, expires_at, project FROM file_locks
+                 WHERE worker_id = ?1 AND expires_at IS NOT NULL AND expires_at <= ?2
+                 ORDER BY expires_at",
+            )?;
+            let marks = stmt
+                .query_map(params![worker_id, now + within_ms], |row| {
+                    Ok(FileLock {
+                        file_path: row.get(0)?,
+                        worker_id: row.get(1)?,
+                        reason: row.get(2)?,
+                        locked_at: row.get(3)?,
+                        task_id: row.get(4)?,
+                        expires_at: row.get(5)?,
+                        project: row.get(6)?,
+                    })
+                })?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(marks)
+        })
+    }
+
+    /// Find advisory marks held by other agents that overlap any of `file_paths`.
+    /// Returns (requested path, existing mark) pairs.
+    pub fn find_mark_conflicts(
+        &self,
+        file_paths: &[String],
+        worker_id: &str,
+    ) -> Result<Vec<(String, FileLock)>> {
+        self.with_read_conn(|conn| {
+            let mut conflicts = Vec::new();
+            for path in file_paths {
+                for mark in overlapping_marks(conn, path, worker_id)? {
+                    conflicts.push((path.clone(), mark));
+                }
+            }
+            Ok(conflicts)
+        })
+    }
+
+    /// Register a worker as waiting for conflicting marks on these paths to be released.
+    pub fn add_mark_waiters(&self, worker_id: &str, file_paths: &[String]) -> Result<()> {
+        let now = now_ms();
+        self.with_conn(|conn| {
+            for path in file_paths {
+                conn.execute(
+                    "INSERT OR REPLACE INTO mark_waiters (file_path, worker_id, registered_at)
+                     VALUES (?1, ?2, ?3)",
+                    params![path, worker_id, now],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Return and clear the paths a worker was waiting on that no other agent marks anymore.
+    pub fn take_available_mark_waits(&self, worker_id: &str) -> Result<Vec<String>> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let waiting: Vec<String> = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT file_path FROM mark_waiters WHERE worker_id = ?1 ORDER BY registered_at",
+                )?;
+                stmt.query_map(params![worker_id], |row| row.get::<_, String>(0))?
+                    .filter_map(|r| r.ok())
+                    .collect()
+            };
+
+            let mut available = Vec::new();
+            for path in waiting {
+                if overlapping_marks(&tx, &path, worker_id)?.is_empty() {
+                    tx.execute(
+                        "DELETE FROM mark_waiters WHERE file_path = ?1 AND worker_id = ?2",
+                        params![&path, worker_id],
+                    )?;
+                    available.push(path);
+                }
+            }
+
+            tx.commit()?;
+            Ok(available)
+        })
+    }
+
+    /// Of the given changed paths, record and return those not covered by any advisory mark.
+    pub fn record_mark_violations(&self, file_paths: &[String]) -> Result<Vec<String>> {
+        let now = now_ms();
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let marked: Vec<String> = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT file_path FROM file_locks WHERE file_path NOT LIKE 'lock:%'",
+                )?;
+                stmt.query_map([], |row| row.get::<_, String>(0))?
+                    .filter_map(|r| r.ok())
+                    .collect()
+            };
+
+            let mut violations = Vec::new();
+            for path in file_paths {
+                if marked.iter().any(|m| mark_paths_overlap(m, path)) {
+                    continue;
+                }
+                tx.execute(
+                    "INSERT INTO mark_violations (file_path, detected_at) VALUES (?1, ?2)",
+                    params![path, now],
+                )?;
+                violations.push(path.clone());
+            }
+
+            tx.commit()?;
+            Ok(violations)
+        })
+    }
+
+    /// Get the most recent mark violations, newest first.
+    pub fn get_mark_violations(&self, limit: i32) -> Result<Vec<MarkViolation>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, file_path, detected_at FROM mark_violations
+                 ORDER BY id DESC LIMIT ?1",
+            )?;
+            let violations = stmt
+                .query_map(params![limit], |row| {
+                    Ok(MarkViolation {
+                        id: row.get(0)?,
+                        file_path: row.get(1)?,
+                        detected_at: row.get(2)?,
+                    })
+                })?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(violations)
+        })
+    }
+
+    /// Get recent claim/release events from the `claim_sequence` audit trail, newest first.
+    ///
+    /// When `file_path` is given, events on that path and on any overlapping path
+    /// (enclosing or enclosed directories) are included; `lock:` resources match exactly.
+    pub fn get_claim_history(
+        &self,
+        file_path: Option<&str>,
+        worker_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<ClaimEvent>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, file_path, worker_id, event, reason, timestamp, end_timestamp, claim_id
+                 FROM claim_sequence
+                 WHERE ?1 IS NULL OR worker_id = ?1
+                 ORDER BY id DESC",
+            )?;
+            let events = stmt
+                .query_map(params![worker_id], |row| {
+                    Ok(ClaimEvent {
+                        id: row.get(0)?,
+                        file_path: row.get(1)?,
+                        worker_id: row.get(2)?,
+                        event: ClaimEventType::parse(&row.get::<_, String>(3)?)
+                            .unwrap_or(ClaimEventType::Claimed),
+                        reason: row.get(4)?,
+                        timestamp: row.get(5)?,
+                        end_timestamp: row.get(6)?,
+                        claim_id: row.get(7)?,
+                    })
+                })?
+                .filter_map(|r| r.ok())
+                .filter(|e| match file_path {
+                    None => true,
+                    Some(p) if p.starts_with("lock:") || e.file_path.starts_with("lock:") => {
+                        e.file_path == p
+                    }
+                    Some(p) => mark_paths_overlap(&e.file_path, p),
+                })
+                .take(limit)
+                .collect();
+            Ok(events)
+        })
+    }
+
+    /// Get the marks held within a project.
+    pub fn get_project_file_locks(&self, project: &str) -> Result<HashMap<String, FileLock>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT file_path, worker_id, reason, locked_at, task_id, expires_at, project FROM file_locks WHERE project = ?1",
+            )?;
+            let locks = stmt
+                .query_map(params![project], |row| {
+                    let file_path: String = row.get(0)?;
+                    Ok((
+                        file_path.clone(),
+                        FileLock {
+                            file_path,
+                            worker_id: row.get(1)?,
+                            reason: row.get(2)?,
+                            locked_at: row.get(3)?,
+                            task_id: row.get(4)?,
+                            expires_at: row.get(5)?,
+                            project: row.get(6)?,
+                        },
+                    ))
+                })?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(locks)
+        })
+    }
+
     /// Get all file locks as FileLock objects.
     pub fn get_all_file_locks(&self) -> Result<Vec<FileLock>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT file_path, worker_id, reason, locked_at, task_id FROM file_locks",
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT file_path, worker_id, reason, locked_at, task_id, expires_at, project FROM file_locks",
             )?;
 
             let locks = stmt
@@ -533,12 +885,16 @@ impl Database {
                     let reason: Option<String> = row.get(2)?;
                     let locked_at: i64 = row.get(3)?;
                     let task_id: Option<String> = row.get(4)?;
+                    let expires_at: Option<i64> = row.get(5)?;
+                    let project: String = row.get(6)?;
                     Ok(FileLock {
                         file_path,
                         worker_id,
                         reason,
                         locked_at,
                         task_id,
+                        expires_at,
+                        project,
                     })
                 })?
                 .filter_map(|r| r.ok())
@@ -577,8 +933,9 @@ impl Database {
         self.with_conn(|conn| {
             // Get files locked by this task before deleting
             let files_to_release: Vec<(String, String)> = {
-                let mut stmt =
-                    conn.prepare("SELECT file_path, worker_id FROM file_locks WHERE task_id = ?1")?;
+                let mut stmt = conn.prepare_cached(
+                    "SELECT file_path, worker_id FROM file_locks WHERE task_id = ?1",
+                )?;
                 stmt.query_map(params![task_id], |row| {
                     Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
                 })?
@@ -13,27 +13,57 @@
 //! - `src/tools/tracking.rs:34,215,338` - various unwraps on date/option handling
 //! - `src/tools/attachments.rs:247,250` - `content.unwrap()` assumes content is Some
 //! - `src/db/migrations.rs:323,556` - `expect()` on migration path validation
+//!
+//! # Connections
+//!
+//! A file database has one writer connection and a few read-only reader
+//! connections. WAL mode lets readers run while the writer holds a write
+//! transaction, so read-heavy agent swarms do not queue behind writes.
+//! `with_conn` and `with_conn_mut` use the writer; `with_read_conn` uses a
+//! reader, falling back to the writer for in-memory databases (whose data a
+//! second connection cannot see).
 
 pub mod agents;
 pub mod attachments;
+pub mod audit;
+pub mod cache;
+pub mod changes;
+pub mod closure;
 pub mod dashboard;
+pub mod deadlines;
 pub mod deps;
+pub mod doctor;
 pub mod export;
+pub mod feed;
+pub mod fields;
+pub mod filter;
+pub mod fts;
+pub mod groups;
 pub mod import;
 pub mod locks;
+pub mod patch;
+pub mod projects;
+pub mod reminders;
+pub mod reservations;
+pub mod scheduling;
 pub mod schema;
 pub mod search;
 pub mod state_transitions;
 pub mod stats;
 pub mod tasks;
 pub mod template;
+pub mod time_travel;
+pub mod trash;
+pub mod visibility;
+pub mod waiters;
 
-pub use deps::AddDependencyResult;
+pub use deps::{AddDependencyResult, DependencyMatrix, LinkResult, MatrixTask};
 pub use search::{AttachmentMatch, SearchResult};
 
 use anyhow::Result;
 use rusqlite::Connection;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
 mod embedded {
@@ -41,15 +71,56 @@ mod embedded {
     embed_migrations!("migrations");
 }
 
-/// Database handle wrapping a SQLite connection.
+/// Prepared statements kept per connection for `prepare_cached`.
+///
+/// Hot paths (task lookup, ready computation, tag joins) reuse their parsed
+/// statements; dynamic SQL built per filter shares the cache, so the default
+/// of 16 would evict the hot statements.
+const STATEMENT_CACHE_CAPACITY: usize = 256;
+
+/// Read-only connections opened next to the writer for file databases.
+const READER_CONNECTIONS: usize = 4;
+
+/// Database handle wrapping a SQLite writer connection and a reader pool.
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
+    readers: Arc<ReaderPool>,
+    aggregates: Arc<cache::AggregateCache>,
+}
+
+/// Read-only connections, handed out round-robin.
+#[derive(Default)]
+struct ReaderPool {
+    conns: Vec<Mutex<Connection>>,
+    next: AtomicUsize,
+}
+
+impl ReaderPool {
+    /// Open `count` query-only connections to the database at `path`.
+    fn open(path: &Path, count: usize) -> Result<Self> {
+        let conns = (0..count)
+            .map(|_| {
+                let conn = Connection::open(path)?;
+                conn.execute_batch(
+                    "PRAGMA query_only=ON;
+                     PRAGMA busy_timeout=5000;",
+                )?;
+                conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+                Ok(Mutex::new(conn))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            conns,
+            next: AtomicUsize::new(0),
+        })
+    }
 }
 
 impl Database {
     /// Open or create the database at the given path.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
         let conn = Connection::open(path)?;
 
         // Enable WAL mode for concurrent access
@@ -58,12 +129,17 @@ impl Database {
              PRAGMA foreign_keys=ON;
              PRAGMA busy_timeout=5000;",
         )?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
 
-        let db = Self {
+        let mut db = Self {
             conn: Arc::new(Mutex::new(conn)),
+            readers: Arc::default(),
+            aggregates: Arc::default(),
         };
 
+        // Readers open after migrations so they see the current schema
         db.run_migrations()?;
+        db.readers = Arc::new(ReaderPool::open(path, READER_CONNECTIONS)?);
 
         Ok(db)
     }
@@ -74,9 +150,12 @@ impl Database {
         let conn = Connection::open_in_memory()?;
 
         conn.execute_batch("PRAGMA foreign_keys=ON;")?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
 
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
+            readers: Arc::default(),
+            aggregates: Arc::default(),
         };
 
         db.run_migrations()?;
@@ -104,6 +183,37 @@ impl Database {
         f(&conn)
     }
 
+    /// Execute a read-only function on a reader connection.
+    ///
+    /// Takes the first idle reader, or waits for the next one in turn when all
+    /// are busy. Uses the writer when there are no readers (in-memory
+    /// databases). Readers are `query_only`, so writes fail.
+    pub fn with_read_conn<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Connection) -> Result<T>,
+    {
+        let readers = &self.readers.conns;
+        if readers.is_empty() {
+            return self.with_conn(f);
+        }
+
+        let start = self.readers.next.fetch_add(1, Ordering::Relaxed);
+        let idle = (0..readers.len())
+            .map(|offset| &readers[(start + offset) % readers.len()])
+            .find_map(|reader| match reader.try_lock() {
+                Ok(conn) => Some(conn),
+                Err(std::sync::TryLockError::Poisoned(e)) => Some(e.into_inner()),
+                Err(std::sync::TryLockError::WouldBlock) => None,
+            });
+        let conn = match idle {
+            Some(conn) => conn,
+            None => readers[start % readers.len()]
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()),
+        };
+        f(&conn)
+    }
+
     /// Execute a function with mutable access to the connection (for transactions).
     ///
     /// Recovers from poisoned mutex to prevent cascading failures if another
@@ -117,6 +227,17 @@ impl Database {
     }
 }
 
+/// SQL condition: the tag in `column` is the tag bound at `placeholder` or lies
+/// under it in the tag hierarchy (see [`crate::types::tag_matches`]).
+pub(crate) fn tag_match_sql(column: &str, placeholder: &str) -> String {
+    format!(
+        "({0} = {1} OR substr({0}, 1, length({1}) + 1) = {1} || '{2}')",
+        column,
+        placeholder,
+        crate::types::TAG_SEPARATOR
+    )
+}
+
 /// Get the current timestamp in milliseconds.
 pub fn now_ms() -> i64 {
     chrono::Utc::now().timestamp_millis()
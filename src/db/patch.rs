@@ -0,0 +1,265 @@
+//! Partial task updates: RFC 6902-style operations applied in one transaction.
+//!
+//! Operations edit a task relative to its current state (add a tag, append to
+//! the description) instead of replacing whole fields, so concurrent edits to
+//! tag lists and descriptions do not overwrite each other.
+
+use super::fields::set_task_fields_internal;
+use super::tasks::{get_task_internal, sync_needed_tags, sync_task_tags, sync_wanted_tags};
+use super::{Database, now_ms};
+use crate::config::LimitsConfig;
+use crate::error::ToolError;
+use crate::types::Task;
+use anyhow::{Result, anyhow};
+use rusqlite::{OptionalExtension, params};
+use serde_json::{Map, Value};
+
+/// A tag list an operation can edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagList {
+    Tags,
+    NeededTags,
+    WantedTags,
+}
+
+impl TagList {
+    fn from_path(path: &str) -> Option<Self> {
+        match path.strip_suffix("/-").unwrap_or(path) {
+            "/tags" => Some(Self::Tags),
+            "/needed_tags" => Some(Self::NeededTags),
+            "/wanted_tags" => Some(Self::WantedTags),
+            _ => None,
+        }
+    }
+
+    /// Name of the list, as used by `update` parameters.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Tags => "tags",
+            Self::NeededTags => "needed_tags",
+            Self::WantedTags => "wanted_tags",
+        }
+    }
+
+    fn of(self, task: &mut Task) -> &mut Vec<String> {
+        match self {
+            Self::Tags => &mut task.tags,
+            Self::NeededTags => &mut task.needed_tags,
+            Self::WantedTags => &mut task.wanted_tags,
+        }
+    }
+}
+
+/// One partial update of a task.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskOp {
+    /// Add tags to a list; tags already present are left alone.
+    AddTags(TagList, Vec<String>),
+    /// Remove tags from a list; each must be present.
+    RemoveTags(TagList, Vec<String>),
+    /// Append text to the description, on a new line when it is not empty.
+    AppendDescription(String),
+    /// Replace the description (`None` clears it).
+    SetDescription(Option<String>),
+    SetTitle(String),
+    /// Set a custom field.
+    SetField(String, Value),
+    /// Remove a custom field; it must be set.
+    RemoveField(String),
+}
+
+impl TaskOp {
+    /// Parse an `{op, path, value}` operation. Supported:
+    /// `add`/`remove` on `/tags`, `/needed_tags`, `/wanted_tags` (value: tag or
+    /// tags), `add` (append) and `replace` on `/description`, `replace` on
+    /// `/title`, and `add`/`replace`/`remove` on `/fields/<name>`.
+    pub fn parse(op: &Value) -> std::result::Result<Self, String> {
+        let kind = op.get("op").and_then(Value::as_str).ok_or("missing 'op'")?;
+        let path = op
+            .get("path")
+            .and_then(Value::as_str)
+            .ok_or("missing 'path'")?;
+        let value = op.get("value");
+        let text = || {
+            value
+                .and_then(Value::as_str)
+                .map(String::from)
+                .ok_or_else(|| format!("'{}' on '{}' needs a string value", kind, path))
+        };
+        let tags = || match value {
+            Some(Value::String(tag)) => Ok(vec![tag.clone()]),
+            Some(Value::Array(items)) if !items.is_empty() => items
+                .iter()
+                .map(|v| v.as_str().map(String::from))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| format!("'{}' on '{}' needs string tags", kind, path)),
+            _ => Err(format!("'{}' on '{}' needs a tag or tags", kind, path)),
+        };
+
+        if let Some(list) = TagList::from_path(path) {
+            return match kind {
+                "add" => Ok(Self::AddTags(list, tags()?)),
+                "remove" => Ok(Self::RemoveTags(list, tags()?)),
+                _ => Err(format!("'{}' is not supported on '{}'", kind, path)),
+            };
+        }
+        if let Some(name) = path.strip_prefix("/fields/") {
+            if name.is_empty() {
+                return Err("missing field name in path".to_string());
+            }
+            return match (kind, value) {
+                ("add" | "replace", Some(v)) if !v.is_null() => {
+                    Ok(Self::SetField(name.to_string(), v.clone()))
+                }
+                ("add" | "replace", _) => Err(format!(
+                    "'{}' on '{}' needs a value (use remove to unset)",
+                    kind, path
+                )),
+                ("remove", _) => Ok(Self::RemoveField(name.to_string())),
+                _ => Err(format!("'{}' is not supported on '{}'", kind, path)),
+            };
+        }
+        match (kind, path) {
+            ("add", "/description") => Ok(Self::AppendDescription(text()?)),
+            ("replace", "/description") if value.is_some_and(Value::is_null) => {
+                Ok(Self::SetDescription(None))
+            }
+            ("replace", "/description") => Ok(Self::SetDescription(Some(text()?))),
+            ("replace", "/title") => Ok(Self::SetTitle(text()?)),
+            _ => Err(format!("'{}' is not supported on '{}'", kind, path)),
+        }
+    }
+
+    /// The `update` parameter this operation edits.
+    pub fn target(&self) -> &'static str {
+        match self {
+            Self::AddTags(list, _) | Self::RemoveTags(list, _) => list.name(),
+            Self::AppendDescription(_) | Self::SetDescription(_) => "description",
+            Self::SetTitle(_) => "title",
+            Self::SetField(..) | Self::RemoveField(_) => "fields",
+        }
+    }
+}
+
+/// Error for the operation at `index`.
+fn op_error(index: usize, message: impl std::fmt::Display) -> anyhow::Error {
+    ToolError::invalid_value("ops", &format!("ops[{}]: {}", index, message)).into()
+}
+
+impl Database {
+    /// Apply operations to a task in one transaction, all or nothing.
+    ///
+    /// Only the owner of a claimed task may edit it, unless `force`. The
+    /// resulting title and description must fit `limits`. An operation that
+    /// cannot apply fails the whole batch with its index.
+    pub fn apply_task_ops(
+        &self,
+        task_id: &str,
+        agent_id: &str,
+        force: bool,
+        ops: &[TaskOp],
+        limits: &LimitsConfig,
+    ) -> Result<Task> {
+        let now = now_ms();
+
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let before =
+                get_task_internal(&tx, task_id)?.ok_or_else(|| anyhow!("Task not found"))?;
+            if let Some(ref owner) = before.worker_id
+                && owner != agent_id
+                && !force
+            {
+                return Err(anyhow!(
+                    "Task is claimed by agent '{}'. Only the owner can update claimed tasks (use force=true to override)",
+                    owner
+                ));
+            }
+
+            let mut task = before.clone();
+            let mut fields = Map::new();
+            for (i, op) in ops.iter().enumerate() {
+                match op {
+                    TaskOp::AddTags(list, tags) => {
+                        let current = list.of(&mut task);
+                        for tag in tags {
+                            if !current.contains(tag) {
+                                current.push(tag.clone());
+                            }
+                        }
+                    }
+                    TaskOp::RemoveTags(list, tags) => {
+                        let current = list.of(&mut task);
+                        for tag in tags {
+                            let Some(pos) = current.iter().position(|t| t == tag) else {
+                                return Err(op_error(
+                                    i,
+                                    format!("tag '{}' is not in {}", tag, list.name()),
+                                ));
+                            };
+                            current.remove(pos);
+                        }
+                    }
+                    TaskOp::AppendDescription(text) => {
+                        task.description = Some(match task.description.take() {
+                            Some(d) if !d.is_empty() => format!("{}\n{}", d, text),
+                            _ => text.clone(),
+                        });
+                    }
+                    TaskOp::SetDescription(text) => task.description = text.clone(),
+                    TaskOp::SetTitle(title) => task.title = title.clone(),
+                    TaskOp::SetField(name, value) => {
+                        fields.insert(name.clone(), value.clone());
+                    }
+                    TaskOp::RemoveField(name) => {
+                        let set = fields.get(name).is_some_and(|v| !v.is_null())
+                            || tx
+                                .prepare_cached(
+                                    "SELECT 1 FROM task_fields WHERE task_id = ?1 AND name = ?2",
+                                )?
+                                .query_row(params![task_id, name], |_| Ok(()))
+                                .optional()?
+                                .is_some();
+                        if !set {
+                            return Err(op_error(i, format!("field '{}' is not set", name)));
+                        }
+                        fields.insert(name.clone(), Value::Null);
+                    }
+                }
+            }
+            limits.check_title(&task.title)?;
+            if let Some(ref d) = task.description {
+                limits.check_description(d)?;
+            }
+
+            task.updated_at = now;
+            tx.execute(
+                "UPDATE tasks SET title = ?1, description = ?2, tags = ?3, needed_tags = ?4,
+                    wanted_tags = ?5, updated_at = ?6
+                 WHERE id = ?7",
+                params![
+                    task.title,
+                    task.description,
+                    serde_json::to_string(&task.tags)?,
+                    serde_json::to_string(&task.needed_tags)?,
+                    serde_json::to_string(&task.wanted_tags)?,
+                    now,
+                    task_id,
+                ],
+            )?;
+            if task.tags != before.tags {
+                sync_task_tags(&tx, task_id, &task.tags)?;
+            }
+            if task.needed_tags != before.needed_tags {
+                sync_needed_tags(&tx, task_id, &task.needed_tags)?;
+            }
+            if task.wanted_tags != before.wanted_tags {
+                sync_wanted_tags(&tx, task_id, &task.wanted_tags)?;
+            }
+            set_task_fields_internal(&tx, task_id, &fields)?;
+
+            tx.commit()?;
+            Ok(task)
+        })
+    }
+}
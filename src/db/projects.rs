@@ -0,0 +1,110 @@
+//! Project namespaces: isolated task graphs hosted by one server.
+
+use super::{Database, now_ms};
+use crate::types::{DEFAULT_PROJECT, ProjectSummary};
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::BTreeMap;
+
+/// Project a task belongs to; unknown tasks fall back to the default project.
+pub(crate) fn project_of(conn: &Connection, task_id: &str) -> Result<String> {
+    let project: Option<String> = conn
+        .prepare_cached("SELECT project FROM tasks WHERE id = ?1")?
+        .query_row(params![task_id], |row| row.get(0))
+        .optional()?;
+    Ok(project.unwrap_or_else(|| DEFAULT_PROJECT.to_string()))
+}
+
+fn summary_for(
+    projects: &mut BTreeMap<String, ProjectSummary>,
+    project: String,
+) -> &mut ProjectSummary {
+    projects
+        .entry(project.clone())
+        .or_insert_with(|| ProjectSummary {
+            project,
+            total_tasks: 0,
+            tasks_by_status: BTreeMap::new(),
+            workers: Vec::new(),
+        })
+}
+
+impl Database {
+    /// Project a task belongs to, or None if the task does not exist.
+    pub fn get_task_project(&self, task_id: &str) -> Result<Option<String>> {
+        self.with_read_conn(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT project FROM tasks WHERE id = ?1",
+                    params![task_id],
+                    |row| row.get(0),
+                )
+                .optional()?)
+        })
+    }
+
+    /// Move tasks into a project. Returns the number of tasks moved.
+    pub fn set_tasks_project(&self, task_ids: &[String], project: &str) -> Result<usize> {
+        let now = now_ms();
+
+        self.with_conn(|conn| {
+            let mut moved = 0;
+            for task_id in task_ids {
+                moved += conn.execute(
+                    "UPDATE tasks SET project = ?1, updated_at = ?2
+                     WHERE id = ?3 AND project != ?1",
+                    params![project, now, task_id],
+                )?;
+            }
+            Ok(moved)
+        })
+    }
+
+    /// Move a worker into a project. Returns false if the worker does not exist.
+    pub fn set_worker_project(&self, worker_id: &str, project: &str) -> Result<bool> {
+        self.with_conn(|conn| {
+            let updated = conn.execute(
+                "UPDATE workers SET project = ?1 WHERE id = ?2",
+                params![project, worker_id],
+            )?;
+            Ok(updated > 0)
+        })
+    }
+
+    /// Every project that has tasks or connected workers, with task counts by
+    /// status and its workers. Soft-deleted tasks are not counted.
+    pub fn list_projects(&self) -> Result<Vec<ProjectSummary>> {
+        self.with_read_conn(|conn| {
+            let mut projects: BTreeMap<String, ProjectSummary> = BTreeMap::new();
+
+            let mut stmt = conn.prepare_cached(
+                "SELECT project, status, COUNT(*) FROM tasks
+                 WHERE deleted_at IS NULL GROUP BY project, status",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?;
+            for row in rows {
+                let (project, status, count) = row?;
+                let summary = summary_for(&mut projects, project);
+                summary.total_tasks += count;
+                summary.tasks_by_status.insert(status, count);
+            }
+
+            let mut stmt = conn.prepare_cached("SELECT project, id FROM workers ORDER BY id")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            for row in rows {
+                let (project, worker_id) = row?;
+                summary_for(&mut projects, project).workers.push(worker_id);
+            }
+
+            Ok(projects.into_values().collect())
+        })
+    }
+}
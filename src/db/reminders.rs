@@ -0,0 +1,43 @@
+//! Delivery times of scheduled reminders, per worker.
+
+use super::{Database, now_ms};
+use anyhow::Result;
+use rusqlite::params;
+use std::collections::HashMap;
+
+impl Database {
+    /// When each reminder was last delivered to a worker, by reminder name.
+    pub fn get_reminder_deliveries(&self, worker_id: &str) -> Result<HashMap<String, i64>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT reminder, delivered_at FROM reminder_deliveries WHERE worker_id = ?1",
+            )?;
+            let deliveries = stmt
+                .query_map(params![worker_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<HashMap<String, i64>>>()?;
+            Ok(deliveries)
+        })
+    }
+
+    /// Record that reminders were delivered to a worker now.
+    pub fn record_reminder_deliveries(&self, worker_id: &str, reminders: &[String]) -> Result<()> {
+        if reminders.is_empty() {
+            return Ok(());
+        }
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let now = now_ms();
+            for reminder in reminders {
+                tx.execute(
+                    "INSERT INTO reminder_deliveries (worker_id, reminder, delivered_at)
+                     VALUES (?1, ?2, ?3)
+                     ON CONFLICT(worker_id, reminder) DO UPDATE
+                     SET delivered_at = excluded.delivered_at",
+                    params![worker_id, reminder, now],
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+}
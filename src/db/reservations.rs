@@ -0,0 +1,111 @@
+//! Task reservations: short-lived holds an agent places before claiming.
+
+use super::tasks::get_task_internal;
+use super::{Database, now_ms};
+use crate::types::Reservation;
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::HashMap;
+
+/// Fail if another worker holds an unexpired reservation on the task.
+pub(crate) fn check_reservation(conn: &Connection, task_id: &str, agent_id: &str) -> Result<()> {
+    let now = now_ms();
+    let holder: Option<(String, i64)> = conn
+        .prepare_cached(
+            "SELECT worker_id, expires_at FROM task_reservations
+             WHERE task_id = ?1 AND worker_id != ?2 AND expires_at > ?3",
+        )?
+        .query_row(params![task_id, agent_id, now], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .optional()?;
+    match holder {
+        Some((worker, expires_at)) => Err(anyhow!(
+            "Task is reserved by '{}' for another {}s",
+            worker,
+            (expires_at - now + 999) / 1000
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Drop any reservation on a task, once it has been claimed.
+pub(crate) fn clear_reservation(conn: &Connection, task_id: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM task_reservations WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    Ok(())
+}
+
+impl Database {
+    /// Reserve an unclaimed task for a worker for `ttl_ms`, or extend the
+    /// worker's existing reservation. Fails if the task is claimed or another
+    /// worker holds an unexpired reservation. Expired reservations are pruned.
+    pub fn reserve_task(&self, task_id: &str, worker_id: &str, ttl_ms: i64) -> Result<Reservation> {
+        let now = now_ms();
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "DELETE FROM task_reservations WHERE expires_at <= ?1",
+                params![now],
+            )?;
+
+            let task = get_task_internal(&tx, task_id)?.ok_or_else(|| anyhow!("Task not found"))?;
+            if let Some(ref owner) = task.worker_id {
+                return Err(anyhow!("Task is already claimed by '{}'", owner));
+            }
+            check_reservation(&tx, task_id, worker_id)?;
+
+            let reservation = Reservation {
+                task_id: task_id.to_string(),
+                worker_id: worker_id.to_string(),
+                reserved_at: now,
+                expires_at: now + ttl_ms,
+            };
+            tx.execute(
+                "INSERT INTO task_reservations (task_id, worker_id, reserved_at, expires_at)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(task_id) DO UPDATE
+                 SET worker_id = excluded.worker_id, reserved_at = excluded.reserved_at,
+                     expires_at = excluded.expires_at",
+                params![task_id, worker_id, now, reservation.expires_at],
+            )?;
+            tx.commit()?;
+            Ok(reservation)
+        })
+    }
+
+    /// Drop a worker's reservation on a task. Returns whether one was held.
+    pub fn release_reservation(&self, task_id: &str, worker_id: &str) -> Result<bool> {
+        self.with_conn(|conn| {
+            let removed = conn.execute(
+                "DELETE FROM task_reservations WHERE task_id = ?1 AND worker_id = ?2",
+                params![task_id, worker_id],
+            )?;
+            Ok(removed > 0)
+        })
+    }
+
+    /// Unexpired reservations, keyed by task ID.
+    pub fn get_active_reservations(&self) -> Result<HashMap<String, Reservation>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT task_id, worker_id, reserved_at, expires_at FROM task_reservations
+                 WHERE expires_at > ?1",
+            )?;
+            let reservations = stmt
+                .query_map(params![now_ms()], |row| {
+                    Ok(Reservation {
+                        task_id: row.get(0)?,
+                        worker_id: row.get(1)?,
+                        reserved_at: row.get(2)?,
+                        expires_at: row.get(3)?,
+                    })
+                })?
+                .map(|r| r.map(|r| (r.task_id.clone(), r)))
+                .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+            Ok(reservations)
+        })
+    }
+}
@@ -0,0 +1,305 @@
+//! Claim scheduling: next-task suggestion and fair-share accounting.
+
+use super::tasks::{NEUTRAL_SCORE, priority_scale_score, tag_match_score};
+use super::{Database, now_ms};
+use crate::config::{
+    DependenciesConfig, FairnessConfig, PrioritiesConfig, StatesConfig, TagsConfig,
+};
+use crate::types::{FairShare, Task, TaskSuggestion};
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate};
+use rusqlite::{params, params_from_iter};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Custom field holding a task's deadline (epoch ms, RFC 3339, or `YYYY-MM-DD`).
+pub const DEADLINE_FIELD: &str = "due_at";
+
+/// Weights of the suggestion score components (sum to 1.0).
+const PRIORITY_WEIGHT: f64 = 0.3;
+const TAG_WEIGHT: f64 = 0.2;
+const DEADLINE_WEIGHT: f64 = 0.25;
+const CRITICAL_PATH_WEIGHT: f64 = 0.25;
+
+/// Time to a deadline at which its score has halved.
+const DEADLINE_HALF_LIFE_MS: f64 = 24.0 * 3_600_000.0;
+
+/// Parse a deadline field value into epoch milliseconds.
+pub(crate) fn parse_deadline(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .map(|dt| dt.timestamp_millis())
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .ok()
+                    .and_then(|d| d.and_hms_opt(0, 0, 0))
+                    .map(|dt| dt.and_utc().timestamp_millis())
+            }),
+        _ => None,
+    }
+}
+
+/// Score a deadline by proximity: 1.0 when due or overdue, 0.5 a day out,
+/// approaching 0 as it recedes. Tasks without a deadline score 0.
+pub(crate) fn deadline_score(due_at: Option<i64>, now: i64) -> f64 {
+    match due_at {
+        Some(due) => {
+            let remaining = (due - now).max(0) as f64;
+            DEADLINE_HALF_LIFE_MS / (DEADLINE_HALF_LIFE_MS + remaining)
+        }
+        None => 0.0,
+    }
+}
+
+/// Length of the longest chain of unfinished tasks waiting on `task_id`.
+fn downstream_depth(
+    task_id: &str,
+    edges: &HashMap<String, Vec<String>>,
+    memo: &mut HashMap<String, usize>,
+    visiting: &mut HashSet<String>,
+) -> usize {
+    if let Some(&depth) = memo.get(task_id) {
+        return depth;
+    }
+    // Guard against cycles in hand-edited data
+    if !visiting.insert(task_id.to_string()) {
+        return 0;
+    }
+    let depth = edges
+        .get(task_id)
+        .map(|next| {
+            next.iter()
+                .map(|n| 1 + downstream_depth(n, edges, memo, visiting))
+                .max()
+                .unwrap_or(0)
+        })
+        .unwrap_or(0);
+    visiting.remove(task_id);
+    memo.insert(task_id.to_string(), depth);
+    depth
+}
+
+impl Database {
+    /// Start-blocking edges (blocker -> dependent) between unfinished tasks.
+    fn unfinished_blocking_edges(
+        &self,
+        states_config: &StatesConfig,
+        deps_config: &DependenciesConfig,
+    ) -> Result<HashMap<String, Vec<String>>> {
+        let types = deps_config.start_blocking_types();
+        if types.is_empty() || states_config.blocking_states.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let type_placeholders = vec!["?"; types.len()].join(", ");
+        let state_placeholders = vec!["?"; states_config.blocking_states.len()].join(", ");
+        let sql = format!(
+            "SELECT d.from_task_id, d.to_task_id FROM dependencies d
+             JOIN tasks t ON t.id = d.to_task_id
+             WHERE d.dep_type IN ({}) AND t.deleted_at IS NULL AND t.status IN ({})",
+            type_placeholders, state_placeholders
+        );
+        let params: Vec<&str> = types
+            .iter()
+            .copied()
+            .chain(states_config.blocking_states.iter().map(String::as_str))
+            .collect();
+
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+            let rows = stmt.query_map(params_from_iter(params), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            for row in rows {
+                let (from, to) = row?;
+                edges.entry(from).or_default().push(to);
+            }
+            Ok(edges)
+        })
+    }
+
+    /// Deadlines (epoch ms) from the `due_at` field of several tasks.
+    /// Tasks without a parseable deadline are absent from the result.
+    pub fn get_task_deadlines(&self, task_ids: &[String]) -> Result<HashMap<String, i64>> {
+        Ok(self
+            .get_task_fields_batch(task_ids)?
+            .into_iter()
+            .filter_map(|(id, fields)| {
+                let due = fields.get(DEADLINE_FIELD).and_then(parse_deadline)?;
+                Some((id, due))
+            })
+            .collect())
+    }
+
+    /// Reorder ready tasks by urgency: priority lifted toward the top of the scale
+    /// by deadline proximity (see `PrioritiesConfig::urgency`). The sort is stable,
+    /// so tasks of equal urgency keep their order, and without any deadlines the
+    /// order is unchanged.
+    pub fn order_by_urgency(
+        &self,
+        tasks: &mut [Task],
+        priorities: &PrioritiesConfig,
+    ) -> Result<()> {
+        let ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+        let deadlines = self.get_task_deadlines(&ids)?;
+        if deadlines.is_empty() {
+            return Ok(());
+        }
+
+        let now = now_ms();
+        let urgency = |task: &Task| {
+            priorities.urgency(
+                priorities.position(task.priority),
+                deadline_score(deadlines.get(&task.id).copied(), now),
+            )
+        };
+        tasks.sort_by(|a, b| urgency(b).total_cmp(&urgency(a)));
+        Ok(())
+    }
+
+    /// An agent's recent share of high-priority claims, or None when fairness is off.
+    ///
+    /// Counts distinct high-priority tasks each connected agent moved into a timed
+    /// state within the window. The penalty grows from 0 at an equal share
+    /// (1 / agents) to `weight` when the agent made every such claim.
+    pub fn get_fair_share(
+        &self,
+        agent_id: &str,
+        fairness: &FairnessConfig,
+        priorities: &PrioritiesConfig,
+        states_config: &StatesConfig,
+    ) -> Result<Option<FairShare>> {
+        if !fairness.enabled {
+            return Ok(None);
+        }
+        let high_priority = fairness.high_priority_threshold(priorities);
+        let cutoff = now_ms() - fairness.window_seconds * 1000;
+        let timed = serde_json::to_string(&states_config.timed_state_names())?;
+
+        let (counts, agents) = self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT s.worker_id, COUNT(DISTINCT s.task_id) FROM task_sequence s
+                 JOIN tasks t ON t.id = s.task_id
+                 JOIN workers w ON w.id = s.worker_id
+                 WHERE s.timestamp >= ?1
+                   AND s.status IN (SELECT value FROM json_each(?2))
+                   AND CAST(t.priority AS INTEGER) >= ?3
+                 GROUP BY s.worker_id",
+            )?;
+            let counts: HashMap<String, i32> = stmt
+                .query_map(params![cutoff, timed, high_priority], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+            let agents: i32 =
+                conn.query_row("SELECT COUNT(*) FROM workers", [], |row| row.get(0))?;
+            Ok((counts, agents))
+        })?;
+
+        let recent_claims = counts.get(agent_id).copied().unwrap_or(0);
+        let total_claims: i32 = counts.values().sum();
+        let penalty = if agents > 1 && total_claims > 0 {
+            let fair = 1.0 / agents as f64;
+            let share = recent_claims as f64 / total_claims as f64;
+            let overshare = ((share - fair) / (1.0 - fair)).clamp(0.0, 1.0);
+            (fairness.weight.clamp(0.0, 1.0) * overshare * 1000.0).round() / 1000.0
+        } else {
+            0.0
+        };
+
+        Ok(Some(FairShare {
+            recent_claims,
+            total_claims,
+            agents,
+            high_priority,
+            penalty,
+        }))
+    }
+
+    /// Pick the single best ready task for an agent.
+    ///
+    /// Candidates are the agent's ready tasks (readiness, tags, and group already
+    /// qualify). Each is scored on priority, tag match, deadline proximity (the
+    /// `due_at` custom field), and critical-path weight (the longest chain of
+    /// unfinished tasks it unblocks, relative to the other candidates). Ties keep
+    /// the ready order (priority, then newest). A `fair_share` reduces the priority
+    /// score of high-priority tasks, and tags count toward the match by their
+    /// `tags_config` weight. Returns the suggestion, if any, and how many
+    /// tasks were considered.
+    pub fn suggest_next_task(
+        &self,
+        agent_id: &str,
+        states_config: &StatesConfig,
+        deps_config: &DependenciesConfig,
+        priorities: &PrioritiesConfig,
+        tags_config: &TagsConfig,
+        fair_share: Option<&FairShare>,
+    ) -> Result<(Option<TaskSuggestion>, usize)> {
+        let ready =
+            self.get_ready_tasks(Some(agent_id), states_config, deps_config, None, None, None)?;
+        if ready.is_empty() {
+            return Ok((None, 0));
+        }
+
+        let agent_tags = self.get_agent_tags(agent_id)?;
+        let ids: Vec<String> = ready.iter().map(|t| t.id.clone()).collect();
+        let deadlines = self.get_task_deadlines(&ids)?;
+        let edges = self.unfinished_blocking_edges(states_config, deps_config)?;
+
+        let mut memo = HashMap::new();
+        let mut visiting = HashSet::new();
+        let depths: Vec<usize> = ready
+            .iter()
+            .map(|t| downstream_depth(&t.id, &edges, &mut memo, &mut visiting))
+            .collect();
+        let max_depth = depths.iter().copied().max().unwrap_or(0);
+
+        let now = now_ms();
+        let round = |v: f64| (v * 1000.0).round() / 1000.0;
+        let score = |task: &Task, depth: usize| {
+            let due_at = deadlines.get(&task.id).copied();
+            let tag_score = tag_match_score(task, &agent_tags, tags_config);
+            let mut priority_score = priority_scale_score(task, priorities);
+            if let Some(share) = fair_share {
+                priority_score = share.adjust_priority_score(task.priority, priority_score);
+            }
+            let deadline_score = deadline_score(due_at, now);
+            let critical_path_score = if max_depth == 0 {
+                NEUTRAL_SCORE
+            } else {
+                depth as f64 / max_depth as f64
+            };
+            TaskSuggestion {
+                id: task.id.clone(),
+                title: task.title.clone(),
+                priority: task.priority,
+                points: task.points,
+                time_estimate_ms: task.time_estimate_ms,
+                due_at,
+                downstream: depth as i32,
+                score: round(
+                    PRIORITY_WEIGHT * priority_score
+                        + TAG_WEIGHT * tag_score
+                        + DEADLINE_WEIGHT * deadline_score
+                        + CRITICAL_PATH_WEIGHT * critical_path_score,
+                ),
+                priority_score: round(priority_score),
+                tag_score: round(tag_score),
+                deadline_score: round(deadline_score),
+                critical_path_score: round(critical_path_score),
+            }
+        };
+
+        // max_by keeps the last maximum; iterate in reverse so ties keep ready order
+        let best = ready
+            .iter()
+            .zip(depths)
+            .rev()
+            .map(|(task, depth)| score(task, depth))
+            .max_by(|a, b| a.score.total_cmp(&b.score));
+        Ok((best, ready.len()))
+    }
+}
@@ -54,7 +54,7 @@ pub struct DatabaseSchema {
 impl Database {
     /// Get complete schema information for the database.
     pub fn get_schema(&self, include_sql: bool) -> Result<DatabaseSchema> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // Get SQLite version
             let sqlite_version: String =
                 conn.query_row("SELECT sqlite_version()", [], |row| row.get(0))?;
@@ -159,8 +159,13 @@ impl Database {
             // Get columns in this index
             let mut stmt = conn.prepare(&format!("PRAGMA index_info('{}')", index_name))?;
 
+            // Expression columns (e.g. CAST(priority AS INTEGER)) have no name
             let columns: Vec<String> = stmt
-                .query_map([], |row| row.get(2))?
+                .query_map([], |row| {
+                    Ok(row
+                        .get::<_, Option<String>>(2)?
+                        .unwrap_or_else(|| "<expression>".to_string()))
+                })?
                 .collect::<Result<Vec<_>, _>>()?;
 
             indexes.push(IndexInfo {
@@ -199,7 +204,7 @@ impl Database {
 
     /// Get a list of table names only (lightweight).
     pub fn get_table_names(&self) -> Result<Vec<String>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut stmt = conn.prepare(
                 "SELECT name FROM sqlite_master 
                  WHERE type = 'table' 
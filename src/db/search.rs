@@ -1,8 +1,9 @@
 //! Full-text search operations using FTS5.
 
 use super::Database;
+use super::visibility::scope_clause;
+use crate::types::TaskScope;
 use anyhow::Result;
-use rusqlite::params;
 use serde::{Deserialize, Serialize};
 
 /// A search result from full-text search.
@@ -40,6 +41,40 @@ pub struct AttachmentMatch {
     pub content_snippet: String,
 }
 
+/// Filters narrowing a full-text search beyond the query.
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters<'a> {
+    /// Only tasks in this status.
+    pub status: Option<&'a str>,
+    /// Only tasks with this tag.
+    pub tag: Option<&'a str>,
+    /// Only tasks owned by this agent.
+    pub owner: Option<&'a str>,
+}
+
+impl SearchFilters<'_> {
+    /// `AND ...` conditions on tasks `t`, binding their values after `params_vec`.
+    fn clause(&self, params_vec: &mut Vec<Box<dyn rusqlite::ToSql>>) -> String {
+        let mut sql = String::new();
+        if let Some(status) = self.status {
+            params_vec.push(Box::new(status.to_string()));
+            sql.push_str(&format!(" AND t.status = ?{}", params_vec.len()));
+        }
+        if let Some(owner) = self.owner {
+            params_vec.push(Box::new(owner.to_string()));
+            sql.push_str(&format!(" AND t.worker_id = ?{}", params_vec.len()));
+        }
+        if let Some(tag) = self.tag {
+            params_vec.push(Box::new(tag.to_string()));
+            sql.push_str(&format!(
+                " AND EXISTS (SELECT 1 FROM task_tags ft WHERE ft.task_id = t.id AND {})",
+                super::tag_match_sql("ft.tag", &format!("?{}", params_vec.len()))
+            ));
+        }
+        sql
+    }
+}
+
 impl Database {
     /// Search tasks using FTS5 full-text search.
     ///
@@ -62,10 +97,28 @@ impl Database {
         offset: i32,
         include_attachments: bool,
         status_filter: Option<&str>,
+        scope: Option<&TaskScope>,
+    ) -> Result<Vec<SearchResult>> {
+        let filters = SearchFilters {
+            status: status_filter,
+            ..Default::default()
+        };
+        self.search_tasks_filtered(query, limit, offset, include_attachments, &filters, scope)
+    }
+
+    /// Search tasks like `search_tasks`, narrowed by status, tag, and owner.
+    pub fn search_tasks_filtered(
+        &self,
+        query: &str,
+        limit: Option<i32>,
+        offset: i32,
+        include_attachments: bool,
+        filters: &SearchFilters<'_>,
+        scope: Option<&TaskScope>,
     ) -> Result<Vec<SearchResult>> {
         let limit = limit.unwrap_or(20).min(100);
 
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // First, search tasks_fts
             let mut sql = String::from(
                 "SELECT
@@ -84,10 +137,8 @@ impl Database {
             let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
             params_vec.push(Box::new(query.to_string()));
 
-            if let Some(status) = status_filter {
-                sql.push_str(" AND t.status = ?2");
-                params_vec.push(Box::new(status.to_string()));
-            }
+            sql.push_str(&filters.clause(&mut params_vec));
+            sql.push_str(&scope_clause(&mut params_vec, scope)?);
 
             sql.push_str(" ORDER BY score LIMIT ?");
             params_vec.push(Box::new(limit));
@@ -100,7 +151,7 @@ impl Database {
             let params_refs: Vec<&dyn rusqlite::ToSql> =
                 params_vec.iter().map(|b| b.as_ref()).collect();
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
             let mut results: Vec<SearchResult> = stmt
                 .query_map(params_refs.as_slice(), |row| {
                     Ok(SearchResult {
@@ -119,21 +170,29 @@ impl Database {
 
             // If include_attachments, also search attachments_fts
             if include_attachments {
-                // Search attachments
-                let attachment_sql = "SELECT
+                // Search attachments, keeping to the scope's tasks
+                let mut att_params: Vec<Box<dyn rusqlite::ToSql>> =
+                    vec![Box::new(query.to_string()), Box::new(limit * 3)];
+                let attachment_sql = format!(
+                    "SELECT
                     afts.task_id,
                     afts.attachment_type,
                     afts.sequence,
                     afts.name,
                     snippet(attachments_fts, 4, '<mark>', '</mark>', '...', 64) as content_snippet
                 FROM attachments_fts afts
-                WHERE attachments_fts MATCH ?1
+                INNER JOIN tasks t ON t.id = afts.task_id
+                WHERE attachments_fts MATCH ?1{}
                 ORDER BY bm25(attachments_fts)
-                LIMIT ?2";
+                LIMIT ?2",
+                    scope_clause(&mut att_params, scope)?
+                );
+                let att_refs: Vec<&dyn rusqlite::ToSql> =
+                    att_params.iter().map(|b| b.as_ref()).collect();
 
-                let mut att_stmt = conn.prepare(attachment_sql)?;
+                let mut att_stmt = conn.prepare_cached(&attachment_sql)?;
                 let att_matches: Vec<(String, String, i32, String, String)> = att_stmt
-                    .query_map(params![query, limit * 3], |row| {
+                    .query_map(att_refs.as_slice(), |row| {
                         Ok((
                             row.get::<_, String>(0)?,
                             row.get::<_, String>(1)?,
@@ -157,25 +216,21 @@ impl Database {
                         });
                     } else {
                         // Add task to results if not already present (attachment-only match)
-                        // Apply status filter if needed
-                        let task_sql = if status_filter.is_some() {
-                            "SELECT id, title, description, status FROM tasks WHERE id = ?1 AND status = ?2"
-                        } else {
-                            "SELECT id, title, description, status FROM tasks WHERE id = ?1"
-                        };
-
-                        let task_result: Option<(String, String, Option<String>, String)> =
-                            if let Some(status) = status_filter {
-                                conn.query_row(task_sql, params![&task_id, status], |row| {
-                                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-                                })
-                                .ok()
-                            } else {
-                                conn.query_row(task_sql, params![&task_id], |row| {
-                                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-                                })
-                                .ok()
-                            };
+                        // Apply the filters if needed
+                        let mut task_params: Vec<Box<dyn rusqlite::ToSql>> =
+                            vec![Box::new(task_id.clone())];
+                        let task_sql = format!(
+                            "SELECT t.id, t.title, t.description, t.status FROM tasks t WHERE t.id = ?1{}",
+                            filters.clause(&mut task_params)
+                        );
+                        let task_refs: Vec<&dyn rusqlite::ToSql> =
+                            task_params.iter().map(|b| b.as_ref()).collect();
+
+                        let task_result: Option<(String, String, Option<String>, String)> = conn
+                            .query_row(&task_sql, task_refs.as_slice(), |row| {
+                                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                            })
+                            .ok();
 
                         if let Some((id, title, description, status)) = task_result {
                             results.push(SearchResult {
@@ -219,7 +274,7 @@ mod tests {
     #[test]
     fn test_search_empty_db() {
         let db = Database::open_in_memory().unwrap();
-        let results = db.search_tasks("test", None, 0, false, None).unwrap();
+        let results = db.search_tasks("test", None, 0, false, None, None).unwrap();
         assert!(results.is_empty());
     }
 
@@ -247,7 +302,9 @@ mod tests {
             .unwrap();
 
         // Search should find it immediately
-        let results = db.search_tasks("indexing", None, 0, false, None).unwrap();
+        let results = db
+            .search_tasks("indexing", None, 0, false, None, None)
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].task_id, task.id);
     }
@@ -276,7 +333,9 @@ mod tests {
             .unwrap();
 
         // Verify initial content is indexed
-        let results = db.search_tasks("Original", None, 0, false, None).unwrap();
+        let results = db
+            .search_tasks("Original", None, 0, false, None, None)
+            .unwrap();
         assert_eq!(results.len(), 1);
 
         // Update the task - trigger should reindex
@@ -293,12 +352,16 @@ mod tests {
         .unwrap();
 
         // Search should find new content
-        let results = db.search_tasks("newkeyword", None, 0, false, None).unwrap();
+        let results = db
+            .search_tasks("newkeyword", None, 0, false, None, None)
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].task_id, task.id);
 
         // Verify updated title is searchable
-        let results = db.search_tasks("Updated", None, 0, false, None).unwrap();
+        let results = db
+            .search_tasks("Updated", None, 0, false, None, None)
+            .unwrap();
         assert_eq!(results.len(), 1);
     }
 
@@ -326,7 +389,9 @@ mod tests {
             .unwrap();
 
         // Verify it's indexed
-        let results = db.search_tasks("Deletable", None, 0, false, None).unwrap();
+        let results = db
+            .search_tasks("Deletable", None, 0, false, None, None)
+            .unwrap();
         assert_eq!(results.len(), 1);
 
         // Delete the task
@@ -334,7 +399,9 @@ mod tests {
             .unwrap();
 
         // Search should find nothing
-        let results = db.search_tasks("Deletable", None, 0, false, None).unwrap();
+        let results = db
+            .search_tasks("Deletable", None, 0, false, None, None)
+            .unwrap();
         assert!(results.is_empty());
     }
 
@@ -393,7 +460,7 @@ mod tests {
         .unwrap();
 
         // Search for "bug" - higher frequency should rank better
-        let results = db.search_tasks("bug", None, 0, false, None).unwrap();
+        let results = db.search_tasks("bug", None, 0, false, None, None).unwrap();
         assert_eq!(results.len(), 2);
         // The task with more "bug" occurrences should have a better (lower) score
         assert!(results[0].score <= results[1].score);
@@ -434,7 +501,9 @@ mod tests {
         .unwrap();
 
         // Search with include_attachments should find it
-        let results = db.search_tasks("searchable", None, 0, true, None).unwrap();
+        let results = db
+            .search_tasks("searchable", None, 0, true, None, None)
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].task_id, task.id);
         assert_eq!(results[0].attachment_matches.len(), 1);
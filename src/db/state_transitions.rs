@@ -25,13 +25,14 @@ pub(crate) fn record_state_transition(
 
     // Find and close any open transition for this task (status-based)
     let open_transition: Option<(i64, String, i64)> = conn
-        .query_row(
+        .prepare_cached(
             "SELECT id, status, timestamp FROM task_sequence
              WHERE task_id = ?1 AND end_timestamp IS NULL AND status IS NOT NULL
              ORDER BY id DESC LIMIT 1",
-            params![task_id],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
-        )
+        )?
+        .query_row(params![task_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
         .ok();
 
     if let Some((open_id, prev_status, start_timestamp)) = open_transition {
@@ -55,11 +56,11 @@ pub(crate) fn record_state_transition(
     }
 
     // Insert the new transition (snapshot pattern - only new status)
-    conn.execute(
+    conn.prepare_cached(
         "INSERT INTO task_sequence (task_id, worker_id, status, reason, timestamp)
          VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![task_id, worker_id, status, reason, now],
-    )?;
+    )?
+    .execute(params![task_id, worker_id, status, reason, now])?;
 
     Ok(elapsed_added)
 }
@@ -97,11 +98,67 @@ pub struct ProjectStateStats {
     pub time_by_agent_ms: std::collections::HashMap<String, i64>,
 }
 
+/// How `get_project_history_groups` buckets state transitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryGrouping {
+    /// UTC day the transition happened (`YYYY-MM-DD`).
+    Day,
+    /// Agent that made the transition.
+    Agent,
+    /// Status entered.
+    State,
+}
+
+impl HistoryGrouping {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "day" => Some(Self::Day),
+            "agent" => Some(Self::Agent),
+            "state" => Some(Self::State),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Agent => "agent",
+            Self::State => "state",
+        }
+    }
+
+    fn key_sql(&self) -> &'static str {
+        match self {
+            Self::Day => "strftime('%Y-%m-%d', timestamp / 1000, 'unixepoch')",
+            Self::Agent => "worker_id",
+            Self::State => "status",
+        }
+    }
+}
+
+/// Aggregates for one bucket of state transitions. Durations cover only
+/// transitions that have ended; percentiles use the nearest-rank method.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HistoryGroup {
+    /// Bucket key; `None` for transitions made without an agent.
+    pub key: Option<String>,
+    pub transitions: i64,
+    pub tasks: i64,
+    /// Transitions with a duration (the state has been left).
+    pub ended: i64,
+    pub total_ms: i64,
+    pub avg_ms: Option<i64>,
+    pub p50_ms: Option<i64>,
+    pub p90_ms: Option<i64>,
+    pub p99_ms: Option<i64>,
+    pub max_ms: Option<i64>,
+}
+
 impl Database {
     /// Get the unified sequence history for a task (both status and phase changes).
     pub fn get_task_sequence_history(&self, task_id: &str) -> Result<Vec<TaskSequenceEvent>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT id, task_id, worker_id, status, phase, reason, timestamp, end_timestamp
                  FROM task_sequence
                  WHERE task_id = ?1
@@ -129,8 +186,8 @@ impl Database {
 
     /// Get the state transition history for a task (status changes only, for backward compat).
     pub fn get_task_state_history(&self, task_id: &str) -> Result<Vec<TaskSequenceEvent>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT id, task_id, worker_id, status, phase, reason, timestamp, end_timestamp
                  FROM task_sequence
                  WHERE task_id = ?1 AND status IS NOT NULL
@@ -163,15 +220,14 @@ impl Database {
         task_id: &str,
         states_config: &StatesConfig,
     ) -> Result<Option<i64>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let result: Option<(String, i64)> = conn
-                .query_row(
+                .prepare_cached(
                     "SELECT status, timestamp FROM task_sequence
                      WHERE task_id = ?1 AND end_timestamp IS NULL AND status IS NOT NULL
                      ORDER BY id DESC LIMIT 1",
-                    params![task_id],
-                    |row| Ok((row.get(0)?, row.get(1)?)),
-                )
+                )?
+                .query_row(params![task_id], |row| Ok((row.get(0)?, row.get(1)?)))
                 .ok();
 
             match result {
@@ -195,7 +251,7 @@ impl Database {
         state_filter: Option<&[String]>,
         limit: Option<i64>,
     ) -> Result<Vec<TaskSequenceEvent>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // Build query dynamically based on filters
             let mut sql = String::from(
                 "SELECT id, task_id, worker_id, status, phase, reason, timestamp, end_timestamp
@@ -234,7 +290,7 @@ impl Database {
                 param_values.push(Box::new(lim));
             }
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
 
             // Convert Vec<Box<dyn ToSql>> to slice of references
             let param_refs: Vec<&dyn rusqlite::ToSql> =
@@ -266,7 +322,7 @@ impl Database {
         to_timestamp: Option<i64>,
         limit: Option<i64>,
     ) -> Result<Vec<TaskSequenceEvent>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut sql = String::from(
                 "SELECT id, task_id, worker_id, status, phase, reason, timestamp, end_timestamp
                  FROM task_sequence WHERE 1=1",
@@ -290,7 +346,7 @@ impl Database {
                 param_values.push(Box::new(lim));
             }
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
             let param_refs: Vec<&dyn rusqlite::ToSql> =
                 param_values.iter().map(|b| b.as_ref()).collect();
 
@@ -313,6 +369,99 @@ impl Database {
         })
     }
 
+    /// Aggregate project-wide state transitions into buckets, in SQL.
+    ///
+    /// Takes the same filters as [`Self::get_project_state_history`]; buckets
+    /// are ordered by key.
+    pub fn get_project_history_groups(
+        &self,
+        grouping: HistoryGrouping,
+        from_timestamp: Option<i64>,
+        to_timestamp: Option<i64>,
+        state_filter: Option<&[String]>,
+    ) -> Result<Vec<HistoryGroup>> {
+        self.with_read_conn(|conn| {
+            let mut filters = String::new();
+            let mut param_values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+            if let Some(from_ts) = from_timestamp {
+                filters.push_str(&format!(" AND timestamp >= ?{}", param_values.len() + 1));
+                param_values.push(Box::new(from_ts));
+            }
+
+            if let Some(to_ts) = to_timestamp {
+                filters.push_str(&format!(" AND timestamp <= ?{}", param_values.len() + 1));
+                param_values.push(Box::new(to_ts));
+            }
+
+            if let Some(states) = state_filter
+                && !states.is_empty()
+            {
+                let placeholders: Vec<String> = states
+                    .iter()
+                    .enumerate()
+                    .map(|(i, _)| format!("?{}", param_values.len() + i + 1))
+                    .collect();
+                filters.push_str(&format!(" AND status IN ({})", placeholders.join(", ")));
+                for state in states {
+                    param_values.push(Box::new(state.clone()));
+                }
+            }
+
+            // Nearest rank: the smallest duration whose rank reaches n * p
+            let sql = format!(
+                "WITH events AS (
+                     SELECT {key} AS bucket, task_id, end_timestamp - timestamp AS duration
+                     FROM task_sequence WHERE status IS NOT NULL{filters}
+                 ),
+                 ranked AS (
+                     SELECT bucket, duration,
+                            ROW_NUMBER() OVER (PARTITION BY bucket ORDER BY duration) AS rank,
+                            COUNT(*) OVER (PARTITION BY bucket) AS n
+                     FROM events WHERE duration IS NOT NULL
+                 ),
+                 percentiles AS (
+                     SELECT bucket,
+                            MIN(CASE WHEN rank >= n * 0.5 THEN duration END) AS p50,
+                            MIN(CASE WHEN rank >= n * 0.9 THEN duration END) AS p90,
+                            MIN(CASE WHEN rank >= n * 0.99 THEN duration END) AS p99
+                     FROM ranked GROUP BY bucket
+                 )
+                 SELECT e.bucket, COUNT(*), COUNT(DISTINCT e.task_id), COUNT(e.duration),
+                        COALESCE(SUM(e.duration), 0), CAST(AVG(e.duration) AS INTEGER),
+                        p.p50, p.p90, p.p99, MAX(e.duration)
+                 FROM events e LEFT JOIN percentiles p ON p.bucket IS e.bucket
+                 GROUP BY e.bucket
+                 ORDER BY e.bucket",
+                key = grouping.key_sql(),
+                filters = filters,
+            );
+
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let param_refs: Vec<&dyn rusqlite::ToSql> =
+                param_values.iter().map(|b| b.as_ref()).collect();
+
+            let groups = stmt
+                .query_map(param_refs.as_slice(), |row| {
+                    Ok(HistoryGroup {
+                        key: row.get(0)?,
+                        transitions: row.get(1)?,
+                        tasks: row.get(2)?,
+                        ended: row.get(3)?,
+                        total_ms: row.get(4)?,
+                        avg_ms: row.get(5)?,
+                        p50_ms: row.get(6)?,
+                        p90_ms: row.get(7)?,
+                        p99_ms: row.get(8)?,
+                        max_ms: row.get(9)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(groups)
+        })
+    }
+
     /// Get aggregate project statistics for state transitions within a time range.
     /// Returns counts of transitions per state and per agent.
     pub fn get_project_state_stats(
@@ -320,7 +469,7 @@ impl Database {
         from_timestamp: Option<i64>,
         to_timestamp: Option<i64>,
     ) -> Result<ProjectStateStats> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut transitions_by_status = std::collections::HashMap::new();
             let mut time_by_status = std::collections::HashMap::new();
             let mut transitions_by_agent = std::collections::HashMap::new();
@@ -346,7 +495,7 @@ impl Database {
                 param_values.push(Box::new(to_ts));
             }
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
             let param_refs: Vec<&dyn rusqlite::ToSql> =
                 param_values.iter().map(|b| b.as_ref()).collect();
 
@@ -2,9 +2,10 @@
 
 use super::Database;
 use crate::config::StatesConfig;
-use crate::types::Stats;
+use crate::types::{Stats, TagStats, with_tag_ancestors};
 use anyhow::Result;
 use rusqlite::params;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 
 impl Database {
@@ -15,7 +16,7 @@ impl Database {
         task_id: Option<&str>,
         states_config: &StatesConfig,
     ) -> Result<Stats> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             // First, get the base aggregate stats
             let (base_sql, params_vec): (String, Vec<String>) = match (agent_id, task_id) {
                 (Some(aid), None) => (
@@ -39,13 +40,7 @@ impl Database {
                     vec![aid.to_string()],
                 ),
                 (None, Some(tid)) => (
-                    "WITH RECURSIVE descendants AS (
-                        SELECT id FROM tasks WHERE id = ?1
-                        UNION ALL
-                        SELECT dep.to_task_id FROM dependencies dep
-                        INNER JOIN descendants d ON dep.from_task_id = d.id
-                        WHERE dep.dep_type = 'contains'
-                    )
+                    "WITH descendants(id) AS (                        SELECT id FROM tasks WHERE id = ?1                        UNION ALL                        SELECT descendant_id FROM task_closure WHERE ancestor_id = ?1                    )
                     SELECT
                         COUNT(*) as total_tasks,
                         COALESCE(SUM(points), 0) as total_points,
@@ -66,13 +61,7 @@ impl Database {
                     vec![tid.to_string()],
                 ),
                 (Some(aid), Some(tid)) => (
-                    "WITH RECURSIVE descendants AS (
-                        SELECT id FROM tasks WHERE id = ?2
-                        UNION ALL
-                        SELECT dep.to_task_id FROM dependencies dep
-                        INNER JOIN descendants d ON dep.from_task_id = d.id
-                        WHERE dep.dep_type = 'contains'
-                    )
+                    "WITH descendants(id) AS (                        SELECT id FROM tasks WHERE id = ?2                        UNION ALL                        SELECT descendant_id FROM task_closure WHERE ancestor_id = ?2                    )
                     SELECT
                         COUNT(*) as total_tasks,
                         COALESCE(SUM(points), 0) as total_points,
@@ -211,24 +200,12 @@ impl Database {
                     "SELECT status, COUNT(*) as cnt FROM tasks WHERE worker_id = ?1 GROUP BY status"
                 }
                 (None, Some(_tid)) => {
-                    "WITH RECURSIVE descendants AS (
-                        SELECT id FROM tasks WHERE id = ?1
-                        UNION ALL
-                        SELECT dep.to_task_id FROM dependencies dep
-                        INNER JOIN descendants d ON dep.from_task_id = d.id
-                        WHERE dep.dep_type = 'contains'
-                    )
+                    "WITH descendants(id) AS (                        SELECT id FROM tasks WHERE id = ?1                        UNION ALL                        SELECT descendant_id FROM task_closure WHERE ancestor_id = ?1                    )
                     SELECT status, COUNT(*) as cnt FROM tasks
                     WHERE id IN (SELECT id FROM descendants) GROUP BY status"
                 }
                 (Some(_aid), Some(_tid)) => {
-                    "WITH RECURSIVE descendants AS (
-                        SELECT id FROM tasks WHERE id = ?2
-                        UNION ALL
-                        SELECT dep.to_task_id FROM dependencies dep
-                        INNER JOIN descendants d ON dep.from_task_id = d.id
-                        WHERE dep.dep_type = 'contains'
-                    )
+                    "WITH descendants(id) AS (                        SELECT id FROM tasks WHERE id = ?2                        UNION ALL                        SELECT descendant_id FROM task_closure WHERE ancestor_id = ?2                    )
                     SELECT status, COUNT(*) as cnt FROM tasks
                     WHERE id IN (SELECT id FROM descendants) AND worker_id = ?1 GROUP BY status"
                 }
@@ -243,7 +220,7 @@ impl Database {
             }
 
             // Query and fill in actual counts
-            let mut stmt = conn.prepare(count_sql)?;
+            let mut stmt = conn.prepare_cached(count_sql)?;
             let status_counts: Vec<(String, i64)> = if params_vec.is_empty() {
                 stmt.query_map([], |row| {
                     let status: String = row.get(0)?;
@@ -284,25 +261,13 @@ impl Database {
                      WHERE worker_id = ?1 AND status NOT IN (SELECT value FROM json_each(?2))"
                 }
                 (None, Some(_tid)) => {
-                    "WITH RECURSIVE descendants AS (
-                        SELECT id FROM tasks WHERE id = ?1
-                        UNION ALL
-                        SELECT dep.to_task_id FROM dependencies dep
-                        INNER JOIN descendants d ON dep.from_task_id = d.id
-                        WHERE dep.dep_type = 'contains'
-                    )
+                    "WITH descendants(id) AS (                        SELECT id FROM tasks WHERE id = ?1                        UNION ALL                        SELECT descendant_id FROM task_closure WHERE ancestor_id = ?1                    )
                     SELECT COALESCE(SUM(points), 0) FROM tasks
                     WHERE id IN (SELECT id FROM descendants)
                     AND status NOT IN (SELECT value FROM json_each(?2))"
                 }
                 (Some(_aid), Some(_tid)) => {
-                    "WITH RECURSIVE descendants AS (
-                        SELECT id FROM tasks WHERE id = ?2
-                        UNION ALL
-                        SELECT dep.to_task_id FROM dependencies dep
-                        INNER JOIN descendants d ON dep.from_task_id = d.id
-                        WHERE dep.dep_type = 'contains'
-                    )
+                    "WITH descendants(id) AS (                        SELECT id FROM tasks WHERE id = ?2                        UNION ALL                        SELECT descendant_id FROM task_closure WHERE ancestor_id = ?2                    )
                     SELECT COALESCE(SUM(points), 0) FROM tasks
                     WHERE id IN (SELECT id FROM descendants) AND worker_id = ?1
                     AND status NOT IN (SELECT value FROM json_each(?3))"
@@ -350,4 +315,87 @@ impl Database {
             })
         })
     }
+
+    /// Usage of every tag carried by a task, needed by a task, or held by an
+    /// agent, in tag order. Deleted tasks are not counted.
+    pub fn get_tag_stats(&self, states_config: &StatesConfig) -> Result<Vec<TagStats>> {
+        let workers = self.list_workers()?;
+        self.with_read_conn(|conn| {
+            let mut stats: BTreeMap<String, TagStats> = BTreeMap::new();
+            let mut stmt = conn.prepare(
+                "SELECT tt.tag, t.status, COUNT(*), MAX(t.updated_at)
+                 FROM task_tags tt JOIN tasks t ON t.id = tt.task_id
+                 WHERE t.deleted_at IS NULL
+                 GROUP BY tt.tag, t.status",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })?;
+            for row in rows {
+                let (tag, status, count, updated_at) = row?;
+                let s = tag_entry(&mut stats, &tag);
+                s.tasks_by_status.insert(status, count);
+                s.last_used = s.last_used.max(Some(updated_at));
+            }
+
+            let mut stmt = conn.prepare(
+                "SELECT nt.tag, t.status, COUNT(*), MAX(t.updated_at)
+                 FROM task_needed_tags nt JOIN tasks t ON t.id = nt.task_id
+                 WHERE t.deleted_at IS NULL
+                 GROUP BY nt.tag, t.status",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })?;
+            for row in rows {
+                let (tag, status, count, updated_at) = row?;
+                let s = tag_entry(&mut stats, &tag);
+                if states_config.is_blocking_state(&status) {
+                    s.needed_by += count;
+                }
+                s.last_used = s.last_used.max(Some(updated_at));
+            }
+
+            // An agent tagged `lang/rust` meets a need for `lang`
+            let agent_tags: Vec<Vec<String>> = workers
+                .iter()
+                .map(|w| with_tag_ancestors(&w.tags))
+                .collect();
+            for tags in &agent_tags {
+                for tag in tags {
+                    tag_entry(&mut stats, tag);
+                }
+            }
+            for s in stats.values_mut() {
+                s.agents = agent_tags
+                    .iter()
+                    .filter(|tags| tags.contains(&s.tag))
+                    .count() as i64;
+                s.uncovered = s.needed_by > 0 && s.agents == 0;
+            }
+
+            Ok(stats.into_values().collect())
+        })
+    }
+}
+
+fn tag_entry<'a>(stats: &'a mut BTreeMap<String, TagStats>, tag: &str) -> &'a mut TagStats {
+    stats.entry(tag.to_string()).or_insert_with(|| TagStats {
+        tag: tag.to_string(),
+        tasks_by_status: HashMap::new(),
+        needed_by: 0,
+        agents: 0,
+        last_used: None,
+        uncovered: false,
+    })
 }
@@ -1,18 +1,30 @@
 //! Task CRUD and tree operations.
 
+use super::deps::{SEQUENCE_DEP_TYPE, order_siblings};
+use super::fields::set_task_fields_internal;
+use super::filter::{TaskFilter, filter_clause};
+use super::groups::is_group_member;
+use super::projects::project_of;
+use super::reservations::{check_reservation, clear_reservation};
+use super::scheduling::deadline_score;
 use super::state_transitions::record_state_transition;
+use super::visibility::scope_clause;
 use super::{Database, now_ms};
 use crate::config::{
-    AutoAdvanceConfig, DependenciesConfig, IdsConfig, PhasesConfig, StatesConfig, TagsConfig,
+    AutoAdvanceConfig, DependenciesConfig, IdScheme, IdsConfig, PhasesConfig, PrioritiesConfig,
+    StatesConfig, TagsConfig,
 };
-use crate::error::ToolError;
+use crate::error::{ErrorCode, ToolError};
 use crate::types::{
-    PRIORITY_DEFAULT, Priority, Task, TaskTree, TaskTreeInput, Worker, clamp_priority,
-    parse_priority,
+    ClaimBatch, ClaimCandidate, DEFAULT_PROJECT, FairShare, PRIORITY_DEFAULT, Priority, Task,
+    TaskScope, TaskThought, TaskTree, TaskTreeInput, Worker, has_tag, parse_priority,
 };
 use anyhow::{Result, anyhow};
 use petname::{Generator, Petnames};
-use rusqlite::{Connection, Row, params};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use ulid::Ulid;
 
 /// Options for creating a task tree from nested input.
 #[derive(Debug)]
@@ -27,6 +39,403 @@ pub struct CreateTreeOptions<'a> {
     pub ids_config: &'a IdsConfig,
 }
 
+/// Options for splitting a claimed task into new child tasks.
+#[derive(Debug)]
+pub struct SplitTaskOptions<'a> {
+    pub task_id: &'a str,
+    pub worker_id: &'a str,
+    pub titles: Vec<String>,
+    pub sibling_type: Option<String>,
+    /// Divide the remaining time estimate and points evenly across the children.
+    pub distribute_estimate: bool,
+    pub force: bool,
+    pub states_config: &'a StatesConfig,
+    pub phases_config: &'a PhasesConfig,
+    pub tags_config: &'a TagsConfig,
+    pub ids_config: &'a IdsConfig,
+}
+
+/// How a task ID argument was matched to an existing task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskIdMatch {
+    /// The ID is used as given (a live task ID, or unknown).
+    Unchanged,
+    /// The ID is an alias left by a rename; holds the current ID.
+    Alias(String),
+    /// The ID is an unambiguous prefix; holds the full ID.
+    Prefix(String),
+}
+
+/// Maximum number of candidates listed in an AMBIGUOUS_ID error.
+const MAX_PREFIX_CANDIDATES: usize = 20;
+
+/// Weights of the claim match score components (sum to 1.0).
+const TAG_WEIGHT: f64 = 0.5;
+const PRIORITY_WEIGHT: f64 = 0.35;
+const ESTIMATE_WEIGHT: f64 = 0.15;
+
+/// Component score used when there is nothing to compare against.
+pub(crate) const NEUTRAL_SCORE: f64 = 0.5;
+
+/// Thoughts kept per worker in `thought_log`; older entries are pruned on insert.
+pub const THOUGHT_LOG_LIMIT: i64 = 200;
+
+/// Thoughts kept per task in `task_thoughts`; older entries are pruned on insert.
+pub const TASK_THOUGHT_LIMIT: i64 = 1000;
+
+/// Append a thought to a worker's thought log: one row per claimed task it applies
+/// to, or a single row without a task if it applies to none. Each of those tasks
+/// also gets the thought in its journal.
+fn log_thought(
+    conn: &Connection,
+    agent_id: &str,
+    thought: &str,
+    task_ids_json: Option<&str>,
+    now: i64,
+) -> Result<()> {
+    let inserted = conn.execute(
+        "INSERT INTO thought_log (worker_id, task_id, thought, created_at)
+         SELECT ?1, id, ?2, ?3 FROM tasks
+         WHERE worker_id = ?1 AND (?4 IS NULL OR id IN (SELECT value FROM json_each(?4)))",
+        params![agent_id, thought, now, task_ids_json],
+    )?;
+    if inserted == 0 {
+        conn.execute(
+            "INSERT INTO thought_log (worker_id, thought, created_at) VALUES (?1, ?2, ?3)",
+            params![agent_id, thought, now],
+        )?;
+    }
+    conn.execute(
+        "DELETE FROM thought_log WHERE worker_id = ?1 AND id <= (
+            SELECT id FROM thought_log WHERE worker_id = ?1
+            ORDER BY id DESC LIMIT 1 OFFSET ?2)",
+        params![agent_id, THOUGHT_LOG_LIMIT],
+    )?;
+
+    if inserted > 0 {
+        conn.execute(
+            "INSERT INTO task_thoughts (task_id, worker_id, thought, created_at)
+             SELECT id, ?1, ?2, ?3 FROM tasks
+             WHERE worker_id = ?1 AND (?4 IS NULL OR id IN (SELECT value FROM json_each(?4)))",
+            params![agent_id, thought, now, task_ids_json],
+        )?;
+        conn.execute(
+            "DELETE FROM task_thoughts
+             WHERE task_id IN (
+                 SELECT id FROM tasks
+                 WHERE worker_id = ?1 AND (?2 IS NULL OR id IN (SELECT value FROM json_each(?2))))
+               AND id <= (
+                 SELECT t.id FROM task_thoughts t WHERE t.task_id = task_thoughts.task_id
+                 ORDER BY t.id DESC LIMIT 1 OFFSET ?3)",
+            params![agent_id, task_ids_json, TASK_THOUGHT_LIMIT],
+        )?;
+    }
+    Ok(())
+}
+
+/// Parse a `task_thoughts` row (id, task_id, worker_id, thought, created_at).
+pub(crate) fn parse_thought_row(row: &Row) -> rusqlite::Result<TaskThought> {
+    Ok(TaskThought {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        worker_id: row.get(2)?,
+        thought: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+/// Score how well a ready task suits an agent.
+///
+/// Tags: tasks with needed/wanted tags the agent matches beat generic tasks, and
+/// matching more of the wanted tags scores higher. Estimate: a task whose estimate
+/// is within the same order of magnitude as the agent's average completed-task
+/// duration scores higher than one far larger or smaller.
+fn score_claim_candidate(
+    task: &Task,
+    agent_tags: &[String],
+    typical_duration_ms: Option<f64>,
+    priorities: &PrioritiesConfig,
+    tags: &TagsConfig,
+    fair_share: Option<&FairShare>,
+    deadline_score: f64,
+) -> ClaimCandidate {
+    let tag_score = tag_match_score(task, agent_tags, tags);
+    let mut priority_score = priority_scale_score(task, priorities);
+    if let Some(share) = fair_share {
+        priority_score = share.adjust_priority_score(task.priority, priority_score);
+    }
+    let urgency = priorities.urgency(priority_score, deadline_score);
+
+    let estimate_score = match (task.time_estimate_ms, typical_duration_ms) {
+        (Some(estimate), Some(typical)) if estimate > 0 && typical > 0.0 => {
+            (1.0 - (estimate as f64 / typical).log10().abs()).clamp(0.0, 1.0)
+        }
+        _ => NEUTRAL_SCORE,
+    };
+
+    let round = |v: f64| (v * 1000.0).round() / 1000.0;
+    ClaimCandidate {
+        id: task.id.clone(),
+        title: task.title.clone(),
+        priority: task.priority,
+        points: task.points,
+        time_estimate_ms: task.time_estimate_ms,
+        score: round(
+            TAG_WEIGHT * tag_score + PRIORITY_WEIGHT * urgency + ESTIMATE_WEIGHT * estimate_score,
+        ),
+        tag_score: round(tag_score),
+        priority_score: round(priority_score),
+        deadline_score: round(deadline_score),
+        estimate_score: round(estimate_score),
+    }
+}
+
+/// How closely an agent's tags match a task's needed/wanted tags, each tag
+/// counting by its configured weight. Tasks without weighted tag requirements
+/// score neutral.
+pub(crate) fn tag_match_score(task: &Task, agent_tags: &[String], tags: &TagsConfig) -> f64 {
+    let (mut matched, mut total) = (0.0, 0.0);
+    for tag in task.needed_tags.iter().chain(&task.wanted_tags) {
+        let weight = tags.weight(tag);
+        total += weight;
+        if has_tag(agent_tags, tag) {
+            matched += weight;
+        }
+    }
+    if total <= 0.0 {
+        return NEUTRAL_SCORE;
+    }
+    NEUTRAL_SCORE + (1.0 - NEUTRAL_SCORE) * matched / total
+}
+
+/// Task priority normalized to the configured scale (0.0 = lowest, 1.0 = highest).
+pub(crate) fn priority_scale_score(task: &Task, priorities: &PrioritiesConfig) -> f64 {
+    let span = (priorities.max - priorities.min).max(1) as f64;
+    ((task.priority - priorities.min) as f64 / span).clamp(0.0, 1.0)
+}
+
+/// Check that an agent may claim a task: no other worker's reservation,
+/// start-blocking deps satisfied, and no exclusive partner being worked (all
+/// skipped with `force`), same project,
+/// group membership, and tag affinity.
+fn check_claim(
+    conn: &Connection,
+    task: &Task,
+    agent_id: &str,
+    force: bool,
+    states_config: &StatesConfig,
+    deps_config: &DependenciesConfig,
+) -> Result<()> {
+    // Check for unsatisfied blocking dependencies (skip if force)
+    if !force {
+        // Another worker's reservation holds the task
+        check_reservation(conn, &task.id, agent_id)?;
+
+        let unsatisfied_blockers = super::deps::get_unsatisfied_start_blockers_in_tx(
+            conn,
+            &task.id,
+            states_config,
+            deps_config,
+        )?;
+        if !unsatisfied_blockers.is_empty() {
+            // Return structured error with blocking task IDs so clients
+            // can monitor them and retry when they complete
+            return Err(ToolError::deps_not_satisfied(&unsatisfied_blockers).into());
+        }
+
+        // Exclusive (anti-dependency) partners must not be worked concurrently
+        let conflicts =
+            super::deps::get_exclusive_conflicts_in_tx(conn, &task.id, states_config, deps_config)?;
+        if !conflicts.is_empty() {
+            return Err(ToolError::exclusive_conflict(&conflicts).into());
+        }
+    }
+
+    // Get the agent
+    let agent = get_worker_internal(conn, agent_id)?.ok_or_else(|| anyhow!("Agent not found"))?;
+
+    // Check project
+    if agent.project != task.project {
+        return Err(anyhow!(
+            "Agent is in project '{}', but the task is in project '{}'",
+            agent.project,
+            task.project
+        ));
+    }
+
+    // Check group membership
+    if let Some(ref group) = task.needed_group
+        && !is_group_member(conn, agent_id, group)?
+    {
+        return Err(anyhow!("Agent is not in required group: {}", group));
+    }
+
+    // Check tag affinity - needed_tags (AND - must have ALL)
+    if !task.needed_tags.is_empty() {
+        for needed in &task.needed_tags {
+            if !has_tag(&agent.tags, needed) {
+                return Err(anyhow!("Agent missing required tag: {}", needed));
+            }
+        }
+    }
+
+    // Check tag affinity - wanted_tags (OR - must have AT LEAST ONE)
+    if !task.wanted_tags.is_empty() && !task.wanted_optional {
+        let has_any = task
+            .wanted_tags
+            .iter()
+            .any(|wanted| has_tag(&agent.tags, wanted));
+        if !has_any {
+            return Err(anyhow!("Agent has none of the wanted tags"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Compare-and-set guards on a task, checked inside the mutation's transaction.
+#[derive(Debug, Clone, Default)]
+pub struct Preconditions {
+    /// The task's status must be one of these.
+    pub status: Option<Vec<String>>,
+    /// The task's owner must be this worker; `Some(None)` requires it unclaimed.
+    pub owner: Option<Option<String>>,
+}
+
+impl Preconditions {
+    /// Fail with PRECONDITION_FAILED if the task no longer matches.
+    pub fn check(&self, task: &Task) -> Result<()> {
+        if let Some(ref statuses) = self.status
+            && !statuses.contains(&task.status)
+        {
+            return Err(ToolError::precondition_failed(
+                &task.id,
+                "if_status",
+                &statuses.join(" | "),
+                &task.status,
+            )
+            .into());
+        }
+        if let Some(ref owner) = self.owner
+            && *owner != task.worker_id
+        {
+            let describe =
+                |o: &Option<String>| o.clone().unwrap_or_else(|| "unclaimed".to_string());
+            return Err(ToolError::precondition_failed(
+                &task.id,
+                "if_owner",
+                &describe(owner),
+                &describe(&task.worker_id),
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// What `delete` does with the children of the deleted task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeleteCascade {
+    /// Fail if the task has children.
+    #[default]
+    Refuse,
+    /// Delete the children and all their descendants too.
+    DeleteSubtree,
+    /// Detach the children, leaving them as root tasks.
+    Orphan,
+    /// Move the children to the task's parent (roots if it has none).
+    ReparentToGrandparent,
+}
+
+impl DeleteCascade {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeleteCascade::Refuse => "refuse",
+            DeleteCascade::DeleteSubtree => "delete-subtree",
+            DeleteCascade::Orphan => "orphan",
+            DeleteCascade::ReparentToGrandparent => "reparent-to-grandparent",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "refuse" => Some(DeleteCascade::Refuse),
+            "delete-subtree" => Some(DeleteCascade::DeleteSubtree),
+            "orphan" => Some(DeleteCascade::Orphan),
+            "reparent-to-grandparent" => Some(DeleteCascade::ReparentToGrandparent),
+            _ => None,
+        }
+    }
+}
+
+/// Options for [`Database::delete_task_with`].
+#[derive(Debug, Clone, Default)]
+pub struct DeleteOptions {
+    pub cascade: DeleteCascade,
+    pub reason: Option<String>,
+    /// Permanently remove the rows instead of soft deleting.
+    pub obliterate: bool,
+    /// Delete even if claimed by another worker.
+    pub force: bool,
+    /// Report what would change without changing anything.
+    pub dry_run: bool,
+    pub preconditions: Preconditions,
+}
+
+/// Outcome of deleting a task (or what a dry run would do).
+#[derive(Debug, Clone, Default)]
+pub struct DeleteResult {
+    /// Deleted task IDs: the task, then any descendants nearest first.
+    pub deleted: Vec<String>,
+    /// Children left as root tasks.
+    pub orphaned: Vec<String>,
+    /// Children moved to `new_parent`.
+    pub reparented: Vec<String>,
+    /// The deleted task's parent, when its children moved there.
+    pub new_parent: Option<String>,
+}
+
+/// Options for [`Database::bulk_delete_tasks`].
+#[derive(Debug, Clone, Default)]
+pub struct BulkDeleteOptions {
+    pub reason: Option<String>,
+    /// Permanently remove the rows instead of soft deleting.
+    pub obliterate: bool,
+    /// Delete tasks claimed by other workers too.
+    pub force: bool,
+    /// Report what would change without changing anything.
+    pub dry_run: bool,
+    /// Fail unless exactly this many tasks would be deleted.
+    pub expected_count: Option<usize>,
+}
+
+/// Outcome of a bulk operation (or what a dry run would do).
+#[derive(Debug, Clone, Default)]
+pub struct BulkResult {
+    /// Task IDs acted on, in the order given.
+    pub affected: Vec<String>,
+    /// Task IDs left alone, with the reason.
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Fail with PRECONDITION_FAILED unless a bulk operation's count matches the
+/// one its dry run reported.
+pub fn check_expected_count(expected: Option<usize>, actual: usize) -> Result<()> {
+    match expected {
+        Some(expected) if expected == actual => Ok(()),
+        Some(expected) => Err(ToolError::new(
+            ErrorCode::PreconditionFailed,
+            format!(
+                "expected_count is {} but {} tasks now match; run again with dry_run to review them",
+                expected, actual
+            ),
+        )
+        .with_field("expected_count")
+        .into()),
+        None => Err(ToolError::missing_field("expected_count").into()),
+    }
+}
+
 /// Query parameters for listing tasks with optional filters.
 #[derive(Debug, Default)]
 pub struct ListTasksQuery<'a> {
@@ -38,21 +447,97 @@ pub struct ListTasksQuery<'a> {
     pub offset: i32,
     pub sort_by: Option<&'a str>,
     pub sort_order: Option<&'a str>,
+    /// Custom field equality filters as (name, encoded value) pairs; all must match.
+    pub fields: &'a [(String, String)],
+    /// Only tasks flagged with this blocked reason.
+    pub blocked_reason: Option<&'a str>,
+    /// Only tasks restricted to this agent group.
+    pub needed_group: Option<&'a str>,
+    /// Only tasks in this project.
+    pub project: Option<&'a str>,
+    /// Only tasks inside this visibility scope.
+    pub scope: Option<&'a TaskScope>,
+    /// Only tasks matching this `where` expression.
+    pub filter: Option<&'a TaskFilter>,
 }
 
-/// Generate a petname-based task ID using the large wordlist.
-/// Uses the configured number of words and case style.
-fn generate_task_id(ids_config: &IdsConfig) -> String {
-    let words = ids_config.task_id_words;
-    let case = ids_config.id_case;
+/// Generate a task ID for every scheme that needs no database state.
+/// Petname and ULID IDs get the configured project prefix; sequential IDs
+/// need the `id_sequences` counter and must be drawn inside the transaction
+/// that inserts the task (see `generate_task_id`).
+pub fn generate_unsequenced_task_id(ids_config: &IdsConfig) -> Result<String> {
+    let base = match ids_config.task_id_scheme {
+        // Generate with hyphen separator first (petname's default format)
+        IdScheme::Petname => Petnames::medium()
+            .generate_one(ids_config.task_id_words, "-")
+            .unwrap_or_else(|| format!("task-{}", super::now_ms())),
+        IdScheme::Ulid => Ulid::new().to_string(),
+        IdScheme::Sequential => {
+            return Err(anyhow!(
+                "Sequential task IDs are allocated from the database counter"
+            ));
+        }
+    };
+
+    let id = match ids_config.task_id_prefix {
+        Some(ref prefix) => format!("{}-{}", prefix, base),
+        None => base,
+    };
+
+    // Case conversion applies to word-based IDs; ULIDs keep their canonical form
+    Ok(match ids_config.task_id_scheme {
+        IdScheme::Petname => ids_config.id_case.convert(&id),
+        _ => id,
+    })
+}
+
+/// Generate a new task ID using the configured scheme.
+pub(crate) fn generate_task_id(conn: &Connection, ids_config: &IdsConfig) -> Result<String> {
+    match ids_config.task_id_scheme {
+        IdScheme::Sequential => next_sequential_id(conn, ids_config),
+        _ => generate_unsequenced_task_id(ids_config),
+    }
+}
 
-    // Generate with hyphen separator first (petname's default format)
-    let base = Petnames::medium()
-        .generate_one(words, "-")
-        .unwrap_or_else(|| format!("task-{}", super::now_ms()));
+/// Issue the next sequential ID (e.g., T-0142) for the configured prefix.
+/// The counter is seeded from the highest existing ID with that prefix and
+/// skips numbers already taken by explicitly named tasks.
+fn next_sequential_id(conn: &Connection, ids_config: &IdsConfig) -> Result<String> {
+    let prefix = ids_config.sequence_prefix();
+    let width = ids_config.sequence_digits as usize;
+    let stem = format!("{}-", prefix);
+
+    // Seeding scans every task, so only do it the first time a prefix is used
+    let seeded: bool = conn
+        .prepare_cached("SELECT EXISTS(SELECT 1 FROM id_sequences WHERE prefix = ?1)")?
+        .query_row(params![prefix], |row| row.get(0))?;
+    if !seeded {
+        conn.execute(
+            "INSERT INTO id_sequences (prefix, last_value)
+             SELECT ?1, COALESCE(MAX(CAST(substr(id, length(?2) + 1) AS INTEGER)), 0)
+             FROM tasks
+             WHERE substr(id, 1, length(?2)) = ?2
+               AND substr(id, length(?2) + 1) GLOB '[0-9]*'
+               AND substr(id, length(?2) + 1) NOT GLOB '*[^0-9]*'",
+            params![prefix, stem],
+        )?;
+    }
 
-    // Convert to desired case
-    case.convert(&base)
+    loop {
+        let value: i64 = conn
+            .prepare_cached(
+                "UPDATE id_sequences SET last_value = last_value + 1
+             WHERE prefix = ?1 RETURNING last_value",
+            )?
+            .query_row(params![prefix], |row| row.get(0))?;
+        let id = format!("{}{:0width$}", stem, value, width = width);
+        let taken: bool = conn
+            .prepare_cached("SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)")?
+            .query_row(params![id], |row| row.get(0))?;
+        if !taken {
+            return Ok(id);
+        }
+    }
 }
 
 /// Build an ORDER BY clause from sort_by and sort_order parameters.
@@ -83,43 +568,36 @@ fn build_order_clause(sort_by: Option<&str>, sort_order: Option<&str>) -> String
 
 /// Sync task tags to the task_tags junction table.
 /// Replaces all existing tags for the task.
-fn sync_task_tags(conn: &Connection, task_id: &str, tags: &[String]) -> Result<()> {
-    conn.execute("DELETE FROM task_tags WHERE task_id = ?1", params![task_id])?;
+pub(crate) fn sync_task_tags(conn: &Connection, task_id: &str, tags: &[String]) -> Result<()> {
+    conn.prepare_cached("DELETE FROM task_tags WHERE task_id = ?1")?
+        .execute(params![task_id])?;
+    let mut insert = conn.prepare_cached("INSERT INTO task_tags (task_id, tag) VALUES (?1, ?2)")?;
     for tag in tags {
-        conn.execute(
-            "INSERT INTO task_tags (task_id, tag) VALUES (?1, ?2)",
-            params![task_id, tag],
-        )?;
+        insert.execute(params![task_id, tag])?;
     }
     Ok(())
 }
 
 /// Sync needed tags (agent must have ALL) to the task_needed_tags junction table.
-fn sync_needed_tags(conn: &Connection, task_id: &str, tags: &[String]) -> Result<()> {
-    conn.execute(
-        "DELETE FROM task_needed_tags WHERE task_id = ?1",
-        params![task_id],
-    )?;
+pub(crate) fn sync_needed_tags(conn: &Connection, task_id: &str, tags: &[String]) -> Result<()> {
+    conn.prepare_cached("DELETE FROM task_needed_tags WHERE task_id = ?1")?
+        .execute(params![task_id])?;
+    let mut insert =
+        conn.prepare_cached("INSERT INTO task_needed_tags (task_id, tag) VALUES (?1, ?2)")?;
     for tag in tags {
-        conn.execute(
-            "INSERT INTO task_needed_tags (task_id, tag) VALUES (?1, ?2)",
-            params![task_id, tag],
-        )?;
+        insert.execute(params![task_id, tag])?;
     }
     Ok(())
 }
 
 /// Sync wanted tags (agent must have ANY) to the task_wanted_tags junction table.
-fn sync_wanted_tags(conn: &Connection, task_id: &str, tags: &[String]) -> Result<()> {
-    conn.execute(
-        "DELETE FROM task_wanted_tags WHERE task_id = ?1",
-        params![task_id],
-    )?;
+pub(crate) fn sync_wanted_tags(conn: &Connection, task_id: &str, tags: &[String]) -> Result<()> {
+    conn.prepare_cached("DELETE FROM task_wanted_tags WHERE task_id = ?1")?
+        .execute(params![task_id])?;
+    let mut insert =
+        conn.prepare_cached("INSERT INTO task_wanted_tags (task_id, tag) VALUES (?1, ?2)")?;
     for tag in tags {
-        conn.execute(
-            "INSERT INTO task_wanted_tags (task_id, tag) VALUES (?1, ?2)",
-            params![task_id, tag],
-        )?;
+        insert.execute(params![task_id, tag])?;
     }
     Ok(())
 }
@@ -145,6 +623,11 @@ pub fn parse_task_row(row: &Row) -> rusqlite::Result<Task> {
     let completed_at: Option<i64> = row.get("completed_at")?;
 
     let current_thought: Option<String> = row.get("current_thought")?;
+    let blocked_reason: Option<String> = row.get("blocked_reason")?;
+    let blocked_note: Option<String> = row.get("blocked_note")?;
+    let needed_group: Option<String> = row.get("needed_group")?;
+    let wanted_optional: bool = row.get("wanted_optional")?;
+    let required_child_tags_json: Option<String> = row.get("required_child_tags")?;
 
     let cost_usd: f64 = row.get("cost_usd")?;
     let metric_0: i64 = row.get("metric_0")?;
@@ -156,6 +639,7 @@ pub fn parse_task_row(row: &Row) -> rusqlite::Result<Task> {
     let metric_6: i64 = row.get("metric_6")?;
     let metric_7: i64 = row.get("metric_7")?;
 
+    let project: String = row.get("project")?;
     let created_at: i64 = row.get("created_at")?;
     let updated_at: i64 = row.get("updated_at")?;
 
@@ -183,18 +667,26 @@ pub fn parse_task_row(row: &Row) -> rusqlite::Result<Task> {
         started_at,
         completed_at,
         current_thought,
+        blocked_reason,
+        blocked_note,
+        needed_group,
+        wanted_optional,
+        required_child_tags: required_child_tags_json
+            .map(|s| serde_json::from_str(&s).unwrap_or_default())
+            .unwrap_or_default(),
         cost_usd,
         metrics: [
             metric_0, metric_1, metric_2, metric_3, metric_4, metric_5, metric_6, metric_7,
         ],
+        project,
         created_at,
         updated_at,
     })
 }
 
 /// Internal helper to get a task using an existing connection (avoids deadlock).
-fn get_task_internal(conn: &Connection, task_id: &str) -> Result<Option<Task>> {
-    let mut stmt = conn.prepare("SELECT * FROM tasks WHERE id = ?1")?;
+pub(crate) fn get_task_internal(conn: &Connection, task_id: &str) -> Result<Option<Task>> {
+    let mut stmt = conn.prepare_cached("SELECT * FROM tasks WHERE id = ?1")?;
 
     let result = stmt.query_row(params![task_id], parse_task_row);
 
@@ -207,8 +699,8 @@ fn get_task_internal(conn: &Connection, task_id: &str) -> Result<Option<Task>> {
 
 /// Internal helper to get a worker using an existing connection (avoids deadlock).
 fn get_worker_internal(conn: &Connection, worker_id: &str) -> Result<Option<Worker>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, tags, max_claims, registered_at, last_heartbeat, last_status, last_phase, workflow, overlays
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, tags, max_claims, registered_at, last_heartbeat, last_status, last_phase, workflow, overlays, project
          FROM workers WHERE id = ?1",
     )?;
 
@@ -222,6 +714,7 @@ fn get_worker_internal(conn: &Connection, worker_id: &str) -> Result<Option<Work
         let last_phase: Option<String> = row.get(6)?;
         let workflow: Option<String> = row.get(7)?;
         let overlays_json: Option<String> = row.get(8)?;
+        let project: String = row.get(9)?;
 
         Ok((
             id,
@@ -233,6 +726,7 @@ fn get_worker_internal(conn: &Connection, worker_id: &str) -> Result<Option<Work
             last_phase,
             workflow,
             overlays_json,
+            project,
         ))
     });
 
@@ -247,6 +741,7 @@ fn get_worker_internal(conn: &Connection, worker_id: &str) -> Result<Option<Work
             last_phase,
             workflow,
             overlays_json,
+            project,
         )) => {
             let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
             let overlays: Vec<String> = overlays_json
@@ -263,6 +758,7 @@ fn get_worker_internal(conn: &Connection, worker_id: &str) -> Result<Option<Work
                 last_phase,
                 workflow,
                 overlays,
+                project,
             }))
         }
         Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -292,9 +788,46 @@ impl Database {
         states_config: &StatesConfig,
         ids_config: &IdsConfig,
     ) -> Result<Task> {
-        let task_id = id.unwrap_or_else(|| generate_task_id(ids_config));
+        self.create_task_with_fields(
+            id,
+            title,
+            description,
+            parent_id,
+            phase,
+            priority,
+            points,
+            time_estimate_ms,
+            agent_tags_all,
+            agent_tags_any,
+            tags,
+            None,
+            states_config,
+            ids_config,
+        )
+    }
+
+    /// [`Database::create_task`] that also sets custom field values in the
+    /// same transaction, so the task never exists without them.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_task_with_fields(
+        &self,
+        id: Option<String>,
+        title: String,
+        description: Option<String>,
+        parent_id: Option<String>,
+        phase: Option<String>,
+        priority: Option<Priority>,
+        points: Option<i32>,
+        time_estimate_ms: Option<i64>,
+        agent_tags_all: Option<Vec<String>>,
+        agent_tags_any: Option<Vec<String>>,
+        tags: Option<Vec<String>>,
+        fields: Option<&Map<String, Value>>,
+        states_config: &StatesConfig,
+        ids_config: &IdsConfig,
+    ) -> Result<Task> {
         let now = now_ms();
-        let priority = clamp_priority(priority.unwrap_or(PRIORITY_DEFAULT));
+        let priority = priority.unwrap_or(PRIORITY_DEFAULT);
         let initial_status = &states_config.initial;
 
         let needed_tags = agent_tags_all.unwrap_or_default();
@@ -307,11 +840,23 @@ impl Database {
         self.with_conn_mut(|conn| {
             let tx = conn.transaction()?;
 
+            let task_id = match id {
+                Some(id) => id,
+                None => generate_task_id(&tx, ids_config)?,
+            };
+
+            // Children join their parent's project
+            let project = match parent_id {
+                Some(ref pid) => project_of(&tx, pid)?,
+                None => DEFAULT_PROJECT.to_string(),
+            };
+
             tx.execute(
                 "INSERT INTO tasks (
                     id, title, description, status, phase, priority,
-                    needed_tags, wanted_tags, tags, points, time_estimate_ms, created_at, updated_at
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                    needed_tags, wanted_tags, tags, points, time_estimate_ms, created_at, updated_at,
+                    project
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
                 params![
                     &task_id,
                     &title,
@@ -326,6 +871,7 @@ impl Database {
                     time_estimate_ms,
                     now,
                     now,
+                    &project,
                 ],
             )?;
 
@@ -334,6 +880,10 @@ impl Database {
             sync_needed_tags(&tx, &task_id, &needed_tags)?;
             sync_wanted_tags(&tx, &task_id, &wanted_tags)?;
 
+            if let Some(fields) = fields {
+                set_task_fields_internal(&tx, &task_id, fields)?;
+            }
+
             // Create 'contains' dependency if parent_id is provided
             if let Some(ref pid) = parent_id {
                 Database::add_dependency_internal(&tx, pid, &task_id, "contains")?;
@@ -362,8 +912,14 @@ impl Database {
                 started_at: None,
                 completed_at: None,
                 current_thought: None,
+                blocked_reason: None,
+                blocked_note: None,
+                needed_group: None,
+                wanted_optional: false,
+                required_child_tags: Vec::new(),
                 cost_usd: 0.0,
                 metrics: [0; 8],
+                project,
                 created_at: now,
                 updated_at: now,
             })
@@ -432,10 +988,116 @@ impl Database {
         })
     }
 
+    /// Split a claimed task into child tasks.
+    /// The original task becomes the parent (linked via 'contains') and keeps its
+    /// status, owner, and history. Children inherit phase, priority, and tags.
+    /// Returns the new child IDs and any tag warnings.
+    pub fn split_task(&self, opts: SplitTaskOptions<'_>) -> Result<(Vec<String>, Vec<String>)> {
+        if opts.titles.is_empty() {
+            return Err(anyhow!("At least one child title is required"));
+        }
+
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            let task =
+                get_task_internal(&tx, opts.task_id)?.ok_or_else(|| anyhow!("Task not found"))?;
+
+            if !opts.force && task.worker_id.as_deref() != Some(opts.worker_id) {
+                return Err(ToolError::not_owner(opts.task_id, opts.worker_id)
+                    .with_suggestion("Claim the task before splitting it, or use force=true")
+                    .into());
+            }
+
+            let count = opts.titles.len() as i64;
+            let time_shares = match task.time_estimate_ms {
+                Some(estimate) if opts.distribute_estimate => {
+                    let remaining = (estimate - task.time_actual_ms.unwrap_or(0)).max(0);
+                    split_evenly(remaining, count)
+                }
+                _ => vec![None; opts.titles.len()],
+            };
+            let point_shares = match task.points {
+                Some(points) if opts.distribute_estimate => split_evenly(points as i64, count),
+                _ => vec![None; opts.titles.len()],
+            };
+
+            let children = opts
+                .titles
+                .iter()
+                .zip(time_shares.into_iter().zip(point_shares))
+                .map(|(title, (time_estimate_ms, points))| TaskTreeInput {
+                    ref_id: None,
+                    id: None,
+                    title: Some(title.clone()),
+                    description: None,
+                    phase: task.phase.clone(),
+                    priority: Some(task.priority),
+                    points: points.map(|p| p as i32),
+                    time_estimate_ms,
+                    needed_tags: Some(task.needed_tags.clone()),
+                    wanted_tags: Some(task.wanted_tags.clone()),
+                    wanted_optional: Some(task.wanted_optional),
+                    tags: Some(task.tags.clone()),
+                    required_child_tags: None,
+                    children: Vec::new(),
+                })
+                .collect();
+
+            // Reference the original as the tree root so only children are created
+            let input = TaskTreeInput {
+                ref_id: Some(task.id.clone()),
+                id: None,
+                title: None,
+                description: None,
+                phase: None,
+                priority: None,
+                points: None,
+                time_estimate_ms: None,
+                needed_tags: None,
+                wanted_tags: None,
+                wanted_optional: None,
+                tags: None,
+                required_child_tags: None,
+                children,
+            };
+
+            let mut all_ids = Vec::new();
+            let mut phase_warnings = Vec::new();
+            let mut tag_warnings = Vec::new();
+            create_tree_recursive(
+                &tx,
+                &input,
+                None,
+                None,
+                Some("contains"),
+                opts.sibling_type.as_deref(),
+                &mut all_ids,
+                &mut phase_warnings,
+                &mut tag_warnings,
+                opts.states_config,
+                opts.phases_config,
+                opts.tags_config,
+                opts.ids_config,
+            )?;
+
+            tx.execute(
+                "UPDATE tasks SET updated_at = ?1 WHERE id = ?2",
+                params![now_ms(), opts.task_id],
+            )?;
+
+            tx.commit()?;
+
+            // The first ID is the referenced original
+            all_ids.remove(0);
+            Ok((all_ids, tag_warnings))
+        })
+    }
+
     /// Get a task by ID.
     pub fn get_task(&self, task_id: &str) -> Result<Option<Task>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare("SELECT * FROM tasks WHERE id = ?1")?;
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached("SELECT * FROM tasks WHERE id = ?1")?;
 
             let result = stmt.query_row(params![task_id], parse_task_row);
 
@@ -451,7 +1113,7 @@ impl Database {
     ///
     /// Disables foreign key enforcement, updates every table that references
     /// `tasks.id` inside a transaction, then re-enables and verifies FK
-    /// integrity.
+    /// integrity. The old ID is kept as an alias (see [`Self::resolve_task_alias`]).
     pub fn rename_task(&self, old_id: &str, new_id: &str) -> Result<()> {
         // Validate new_id
         if new_id.is_empty() {
@@ -463,21 +1125,17 @@ impl Database {
 
         self.with_conn_mut(|conn| {
             // Pre-check: old_id must exist
-            let exists: bool = conn.query_row(
-                "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
-                params![old_id],
-                |row| row.get(0),
-            )?;
+            let exists: bool = conn
+                .prepare_cached("SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)")?
+                .query_row(params![old_id], |row| row.get(0))?;
             if !exists {
                 return Err(anyhow!("Task '{}' not found", old_id));
             }
 
             // Pre-check: new_id must not already exist
-            let conflict: bool = conn.query_row(
-                "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
-                params![new_id],
-                |row| row.get(0),
-            )?;
+            let conflict: bool = conn
+                .prepare_cached("SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)")?
+                .query_row(params![new_id], |row| row.get(0))?;
             if conflict {
                 return Err(anyhow!("Task '{}' already exists", new_id));
             }
@@ -530,12 +1188,45 @@ impl Database {
                     params![new_id, old_id],
                 )?;
 
+                // Custom fields
+                tx.execute(
+                    "UPDATE task_fields SET task_id = ?1 WHERE task_id = ?2",
+                    params![new_id, old_id],
+                )?;
+
+                // Wait registrations and reservations
+                tx.execute(
+                    "UPDATE task_waiters SET task_id = ?1 WHERE task_id = ?2",
+                    params![new_id, old_id],
+                )?;
+                tx.execute(
+                    "UPDATE task_reservations SET task_id = ?1 WHERE task_id = ?2",
+                    params![new_id, old_id],
+                )?;
+                tx.execute(
+                    "UPDATE deadline_reminders SET task_id = ?1 WHERE task_id = ?2",
+                    params![new_id, old_id],
+                )?;
+
                 // Sequence table
                 tx.execute(
                     "UPDATE task_sequence SET task_id = ?1 WHERE task_id = ?2",
                     params![new_id, old_id],
                 )?;
 
+                // Aliases: repoint earlier aliases, drop any alias shadowed by the
+                // new ID, and keep the old ID as an alias of the new one
+                tx.execute(
+                    "UPDATE task_aliases SET task_id = ?1 WHERE task_id = ?2",
+                    params![new_id, old_id],
+                )?;
+                tx.execute("DELETE FROM task_aliases WHERE alias = ?1", params![new_id])?;
+                tx.execute(
+                    "INSERT OR REPLACE INTO task_aliases (alias, task_id, created_at)
+                     VALUES (?1, ?2, ?3)",
+                    params![old_id, new_id, now_ms()],
+                )?;
+
                 tx.commit()?;
                 Ok(())
             })();
@@ -547,7 +1238,7 @@ impl Database {
             result?;
 
             // Verify FK integrity
-            let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+            let mut stmt = conn.prepare_cached("PRAGMA foreign_key_check")?;
             let violations: Vec<String> = stmt
                 .query_map([], |row| {
                     let table: String = row.get(0)?;
@@ -567,38 +1258,143 @@ impl Database {
         })
     }
 
-    /// Get a task with all its children (tree).
-    pub fn get_task_tree(&self, task_id: &str) -> Result<Option<TaskTree>> {
-        let task = self.get_task(task_id)?;
-        match task {
-            None => Ok(None),
-            Some(task) => {
-                let children = self.get_children_recursive(&task.id)?;
-                Ok(Some(TaskTree { task, children }))
+    /// Resolve a task ID that no longer exists through the alias table.
+    /// Returns the current ID if `id` is an alias left by a rename, or `None`
+    /// if `id` is a live task ID or unknown.
+    pub fn resolve_task_alias(&self, id: &str) -> Result<Option<String>> {
+        self.with_read_conn(|conn| {
+            let result = conn
+                .prepare_cached(
+                    "SELECT a.task_id FROM task_aliases a
+                 WHERE a.alias = ?1
+                   AND NOT EXISTS (SELECT 1 FROM tasks t WHERE t.id = ?1)",
+                )?
+                .query_row(params![id], |row| row.get(0));
+            match result {
+                Ok(task_id) => Ok(Some(task_id)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
+    /// Resolve a task ID argument to an existing task.
+    ///
+    /// Exact IDs win, then rename aliases, then unique prefixes of live
+    /// (non-deleted) task IDs. A prefix matching several tasks fails with an
+    /// `AMBIGUOUS_ID` error listing the candidates. IDs that match nothing
+    /// come back unchanged so the caller reports its usual not-found error.
+    pub fn resolve_task_id(&self, id: &str) -> Result<TaskIdMatch> {
+        if id.is_empty() || self.task_exists(id)? {
+            return Ok(TaskIdMatch::Unchanged);
+        }
+        if let Some(current) = self.resolve_task_alias(id)? {
+            return Ok(TaskIdMatch::Alias(current));
+        }
+
+        let mut candidates = self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id FROM tasks
+                 WHERE substr(id, 1, length(?1)) = ?1 AND deleted_at IS NULL
+                 ORDER BY id",
+            )?;
+            let ids = stmt
+                .query_map(params![id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(ids)
+        })?;
+
+        match candidates.len() {
+            0 => Ok(TaskIdMatch::Unchanged),
+            1 => Ok(TaskIdMatch::Prefix(candidates.remove(0))),
+            n => {
+                candidates.truncate(MAX_PREFIX_CANDIDATES);
+                Err(ToolError::ambiguous_id(id, n, candidates).into())
             }
         }
     }
 
-    /// Get children recursively.
-    fn get_children_recursive(&self, parent_id: &str) -> Result<Vec<TaskTree>> {
-        let children = self.get_children(parent_id)?;
-        let mut result = Vec::new();
+    /// Get all aliases (previous IDs) of a task.
+    pub fn get_task_aliases(&self, task_id: &str) -> Result<Vec<String>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT alias FROM task_aliases WHERE task_id = ?1 ORDER BY created_at, alias",
+            )?;
+            let aliases = stmt
+                .query_map(params![task_id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(aliases)
+        })
+    }
+
+    /// Get a task with all its children (tree).
+    ///
+    /// The whole subtree is read in one query through `task_closure` and
+    /// assembled in memory; children are ordered by their sequence, then by
+    /// creation time.
+    pub fn get_task_tree(&self, task_id: &str) -> Result<Option<TaskTree>> {
+        let Some(task) = self.get_task(task_id)? else {
+            return Ok(None);
+        };
+
+        let (rows, edges) = self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT t.*, d.from_task_id AS parent_id FROM task_closure c
+                 INNER JOIN tasks t ON t.id = c.descendant_id
+                 INNER JOIN dependencies d ON d.to_task_id = t.id AND d.dep_type = 'contains'
+                 WHERE c.ancestor_id = ?1
+                 ORDER BY t.created_at",
+            )?;
+            let rows = stmt
+                .query_map(params![&task.id], |row| {
+                    Ok((row.get("parent_id")?, parse_task_row(row)?))
+                })?
+                .collect::<rusqlite::Result<Vec<(String, Task)>>>()?;
+            let mut stmt = conn.prepare_cached(
+                "SELECT d.from_task_id, d.to_task_id FROM dependencies d
+                 INNER JOIN task_closure a ON a.descendant_id = d.from_task_id AND a.ancestor_id = ?1
+                 INNER JOIN task_closure b ON b.descendant_id = d.to_task_id AND b.ancestor_id = ?1
+                 WHERE d.dep_type = ?2",
+            )?;
+            let edges = stmt
+                .query_map(params![&task.id, SEQUENCE_DEP_TYPE], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+            Ok((rows, edges))
+        })?;
+
+        let mut children: HashMap<String, Vec<Task>> = HashMap::new();
+        for (parent_id, child) in rows {
+            children.entry(parent_id).or_default().push(child);
+        }
 
-        for child in children {
-            let child_children = self.get_children_recursive(&child.id)?;
-            result.push(TaskTree {
-                task: child,
-                children: child_children,
-            });
+        fn build(
+            task: Task,
+            children: &mut HashMap<String, Vec<Task>>,
+            edges: &[(String, String)],
+        ) -> TaskTree {
+            let mut siblings = children.remove(&task.id).unwrap_or_default();
+            let ids: Vec<String> = siblings.iter().map(|t| t.id.clone()).collect();
+            let order = order_siblings(&ids, edges);
+            siblings.sort_by_key(|t| order.iter().position(|id| *id == t.id));
+            let subtrees = siblings
+                .into_iter()
+                .map(|child| build(child, children, edges))
+                .collect();
+            TaskTree {
+                task,
+                children: subtrees,
+            }
         }
 
-        Ok(result)
+        Ok(Some(build(task, &mut children, &edges)))
     }
 
     /// Get direct children of a task (via 'contains' dependency).
     pub fn get_children(&self, parent_id: &str) -> Result<Vec<Task>> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
                 "SELECT t.* FROM tasks t
                  INNER JOIN dependencies d ON t.id = d.to_task_id
                  WHERE d.from_task_id = ?1 AND d.dep_type = 'contains'
@@ -758,6 +1554,56 @@ impl Database {
         states_config: &StatesConfig,
         deps_config: &DependenciesConfig,
         auto_advance: &AutoAdvanceConfig,
+    ) -> Result<(Task, Vec<String>, Vec<String>)> {
+        self.update_task_unified_if(
+            task_id,
+            agent_id,
+            assignee,
+            title,
+            description,
+            status,
+            phase,
+            priority,
+            points,
+            tags,
+            needed_tags,
+            wanted_tags,
+            time_estimate_ms,
+            None,
+            reason,
+            force,
+            states_config,
+            deps_config,
+            auto_advance,
+            &Preconditions::default(),
+        )
+    }
+
+    /// [`Database::update_task_unified`], applied only if the task still
+    /// matches `preconditions` when the transaction reads it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_task_unified_if(
+        &self,
+        task_id: &str,
+        agent_id: &str,
+        assignee: Option<&str>,
+        title: Option<String>,
+        description: Option<Option<String>>,
+        status: Option<String>,
+        phase: Option<String>,
+        priority: Option<Priority>,
+        points: Option<Option<i32>>,
+        tags: Option<Vec<String>>,
+        needed_tags: Option<Vec<String>>,
+        wanted_tags: Option<Vec<String>>,
+        time_estimate_ms: Option<i64>,
+        fields: Option<&Map<String, Value>>,
+        reason: Option<String>,
+        force: bool,
+        states_config: &StatesConfig,
+        deps_config: &DependenciesConfig,
+        auto_advance: &AutoAdvanceConfig,
+        preconditions: &Preconditions,
     ) -> Result<(Task, Vec<String>, Vec<String>)> {
         let now = now_ms();
 
@@ -766,6 +1612,7 @@ impl Database {
 
             let task =
                 get_task_internal(&tx, task_id)?.ok_or_else(|| anyhow!("Task not found"))?;
+            preconditions.check(&task)?;
 
             // Owner-only validation: if task is claimed, only owner can update (unless force)
             if let Some(ref current_owner) = task.worker_id
@@ -838,10 +1685,31 @@ impl Database {
                 let target = get_worker_internal(&tx, target_agent)?
                     .ok_or_else(|| anyhow!("Assignee agent '{}' not found", target_agent))?;
 
+                // The assignee must work in the task's project
+                if target.project != task.project {
+                    return Err(anyhow!(
+                        "Assignee '{}' is in project '{}', but the task is in project '{}'",
+                        target_agent,
+                        target.project,
+                        task.project
+                    ));
+                }
+
+                // The assignee must belong to the task's group
+                if let Some(ref group) = task.needed_group
+                    && !is_group_member(&tx, target_agent, group)?
+                {
+                    return Err(anyhow!(
+                        "Assignee '{}' is not in required group: {}",
+                        target_agent,
+                        group
+                    ));
+                }
+
                 // Check tag affinity for the assignee
                 if !task.needed_tags.is_empty() {
                     for needed in &task.needed_tags {
-                        if !target.tags.contains(needed) {
+                        if !has_tag(&target.tags, needed) {
                             return Err(anyhow!(
                                 "Assignee '{}' missing required tag: {}",
                                 target_agent,
@@ -851,11 +1719,11 @@ impl Database {
                     }
                 }
 
-                if !task.wanted_tags.is_empty() {
+                if !task.wanted_tags.is_empty() && !task.wanted_optional {
                     let has_any = task
                         .wanted_tags
                         .iter()
-                        .any(|wanted| target.tags.contains(wanted));
+                        .any(|wanted| has_tag(&target.tags, wanted));
                     if !has_any {
                         return Err(anyhow!(
                             "Assignee '{}' has none of the wanted tags: {:?}",
@@ -881,44 +1749,8 @@ impl Database {
                     ));
                 }
 
-                // Check for unsatisfied blocking dependencies (skip if force)
-                if !force {
-                    let unsatisfied_blockers = super::deps::get_unsatisfied_start_blockers_in_tx(
-                        &tx,
-                        task_id,
-                        states_config,
-                        deps_config,
-                    )?;
-                    if !unsatisfied_blockers.is_empty() {
-                        // Return structured error with blocking task IDs so clients
-                        // can monitor them and retry when they complete
-                        return Err(ToolError::deps_not_satisfied(&unsatisfied_blockers).into());
-                    }
-                }
-
-                // Get the agent
-                let agent = get_worker_internal(&tx, agent_id)?
-                    .ok_or_else(|| anyhow!("Agent not found"))?;
-
-                // Check tag affinity - needed_tags (AND - must have ALL)
-                if !task.needed_tags.is_empty() {
-                    for needed in &task.needed_tags {
-                        if !agent.tags.contains(needed) {
-                            return Err(anyhow!("Agent missing required tag: {}", needed));
-                        }
-                    }
-                }
-
-                // Check tag affinity - wanted_tags (OR - must have AT LEAST ONE)
-                if !task.wanted_tags.is_empty() {
-                    let has_any = task
-                        .wanted_tags
-                        .iter()
-                        .any(|wanted| agent.tags.contains(wanted));
-                    if !has_any {
-                        return Err(anyhow!("Agent has none of the wanted tags"));
-                    }
-                }
+                check_claim(&tx, &task, agent_id, force, states_config, deps_config)?;
+                clear_reservation(&tx, task_id)?;
 
                 // Set ownership
                 new_owner = Some(agent_id.to_string());
@@ -952,17 +1784,20 @@ impl Database {
                     }
 
                 // Check for incomplete children (via 'contains' dependencies)
-                let incomplete_children: i32 = tx.query_row(
-                    "SELECT COUNT(*) FROM dependencies d
-                     INNER JOIN tasks child ON d.to_task_id = child.id
-                     WHERE d.from_task_id = ?1 AND d.dep_type = 'contains'
-                     AND child.status IN (SELECT value FROM json_each(?2))",
-                    params![
-                        task_id,
-                        serde_json::to_string(&states_config.blocking_states)?
-                    ],
-                    |row| row.get(0),
-                )?;
+                let incomplete_children: i32 = tx
+                    .prepare_cached(
+                        "SELECT COUNT(*) FROM dependencies d
+                         INNER JOIN tasks child ON d.to_task_id = child.id
+                         WHERE d.from_task_id = ?1 AND d.dep_type = 'contains'
+                         AND child.status IN (SELECT value FROM json_each(?2))",
+                    )?
+                    .query_row(
+                        params![
+                            task_id,
+                            serde_json::to_string(&states_config.blocking_states)?
+                        ],
+                        |row| row.get(0),
+                    )?;
 
                 if incomplete_children > 0 {
                     return Err(anyhow!(
@@ -1049,6 +1884,15 @@ impl Database {
                 ],
             )?;
 
+            // A finished (non-blocking) task is no longer blocked on anything
+            let finished = !states_config.is_blocking_state(&new_status);
+            if finished {
+                tx.execute(
+                    "UPDATE tasks SET blocked_reason = NULL, blocked_note = NULL WHERE id = ?1",
+                    params![task_id],
+                )?;
+            }
+
             // Sync tags to junction tables if changed
             if new_tags != task.tags {
                 sync_task_tags(&tx, task_id, &new_tags)?;
@@ -1060,6 +1904,11 @@ impl Database {
                 sync_wanted_tags(&tx, task_id, &new_wanted_tags)?;
             }
 
+            // Custom fields merge with existing values
+            if let Some(fields) = fields {
+                set_task_fields_internal(&tx, task_id, fields)?;
+            }
+
             // Check for unblocked tasks if this task transitioned FROM blocking TO non-blocking
             let (unblocked, auto_advanced) = if status_changed {
                 let was_blocking = states_config.is_blocking_state(&task.status);
@@ -1081,6 +1930,9 @@ impl Database {
                 (vec![], vec![])
             };
 
+            // Flag agents that called wait_for on any newly ready task
+            super::waiters::notify_waiters_in_tx(&tx, &unblocked)?;
+
             tx.commit()?;
 
             Ok((Task {
@@ -1100,112 +1952,266 @@ impl Database {
                 updated_at: now,
                 worker_id: new_owner,
                 claimed_at: new_claimed_at,
+                blocked_reason: if finished { None } else { task.blocked_reason.clone() },
+                blocked_note: if finished { None } else { task.blocked_note.clone() },
                 ..task
             }, unblocked, auto_advanced))
         })
     }
 
-    /// Delete a task (soft delete by default, hard delete with obliterate=true).
-    ///
-    /// - `worker_id`: The worker attempting to delete (required for ownership check)
-    /// - `cascade`: Whether to delete children (default: false)
-    /// - `reason`: Optional reason for deletion
-    /// - `obliterate`: If true, permanently deletes the task; if false (default), soft deletes
-    /// - `force`: If true, allows deletion even if owned by another worker
-    pub fn delete_task(
+    /// Delete a task (soft delete by default, hard delete with obliterate=true).
+    ///
+    /// - `worker_id`: The worker attempting to delete (required for ownership check)
+    /// - `cascade`: Whether to delete children (default: false)
+    /// - `reason`: Optional reason for deletion
+    /// - `obliterate`: If true, permanently deletes the task; if false (default), soft deletes
+    /// - `force`: If true, allows deletion even if owned by another worker
+    pub fn delete_task(
+        &self,
+        task_id: &str,
+        worker_id: &str,
+        cascade: bool,
+        reason: Option<String>,
+        obliterate: bool,
+        force: bool,
+    ) -> Result<()> {
+        self.delete_task_with(
+            task_id,
+            worker_id,
+            &DeleteOptions {
+                cascade: if cascade {
+                    DeleteCascade::DeleteSubtree
+                } else {
+                    DeleteCascade::Refuse
+                },
+                reason,
+                obliterate,
+                force,
+                ..Default::default()
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Delete a task, handling its children as `opts.cascade` says, in one
+    /// transaction. With `opts.dry_run`, nothing changes and the result
+    /// describes what the delete would do.
+    pub fn delete_task_with(
+        &self,
+        task_id: &str,
+        worker_id: &str,
+        opts: &DeleteOptions,
+    ) -> Result<DeleteResult> {
+        let now = now_ms();
+
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            // Get the task to check ownership
+            let task = get_task_internal(&tx, task_id)?
+                .ok_or_else(|| anyhow!("Task not found"))?;
+            opts.preconditions.check(&task)?;
+
+            // Check ownership - reject if claimed by another worker (unless force)
+            if let Some(ref owner) = task.worker_id
+                && owner != worker_id && !opts.force {
+                    return Err(anyhow!(
+                        "Task is claimed by worker '{}'. Use force=true to override.",
+                        owner
+                    ));
+                }
+
+            // Live children; the edges of deleted ones move with them unreported
+            let children: Vec<String> = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT d.to_task_id FROM dependencies d
+                     JOIN tasks c ON c.id = d.to_task_id
+                     WHERE d.from_task_id = ?1 AND d.dep_type = 'contains'
+                       AND c.deleted_at IS NULL
+                     ORDER BY d.to_task_id",
+                )?;
+                stmt.query_map(params![task_id], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?
+            };
+
+            let mut result = DeleteResult::default();
+            match opts.cascade {
+                DeleteCascade::Refuse => {
+                    let child_count: i32 = tx
+                        .prepare_cached(
+                            "SELECT COUNT(*) FROM dependencies WHERE from_task_id = ?1 AND dep_type = 'contains'",
+                        )?
+                        .query_row(params![task_id], |row| row.get(0))?;
+                    if child_count > 0 {
+                        return Err(anyhow!(
+                            "Task has children; use cascade=delete-subtree, orphan, or reparent-to-grandparent"
+                        ));
+                    }
+                    result.deleted = vec![task_id.to_string()];
+                }
+                DeleteCascade::DeleteSubtree => {
+                    // The task first, then its descendants nearest first;
+                    // a soft delete skips those already deleted
+                    let mut stmt = tx.prepare(
+                        "SELECT ?1
+                         UNION ALL
+                         SELECT * FROM (
+                             SELECT c.descendant_id FROM task_closure c
+                             JOIN tasks t ON t.id = c.descendant_id
+                             WHERE c.ancestor_id = ?1 AND c.depth > 0
+                               AND (?2 OR t.deleted_at IS NULL)
+                             ORDER BY c.depth, c.descendant_id
+                         )",
+                    )?;
+                    result.deleted = stmt
+                        .query_map(params![task_id, opts.obliterate], |row| row.get(0))?
+                        .collect::<rusqlite::Result<_>>()?;
+                }
+                DeleteCascade::Orphan => {
+                    result.deleted = vec![task_id.to_string()];
+                    result.orphaned = children;
+                }
+                DeleteCascade::ReparentToGrandparent => {
+                    result.deleted = vec![task_id.to_string()];
+                    result.new_parent = tx
+                        .query_row(
+                            "SELECT from_task_id FROM dependencies
+                             WHERE to_task_id = ?1 AND dep_type = 'contains'",
+                            params![task_id],
+                            |row| row.get(0),
+                        )
+                        .optional()?;
+                    // Without a grandparent the children become roots
+                    if result.new_parent.is_some() {
+                        result.reparented = children;
+                    } else {
+                        result.orphaned = children;
+                    }
+                }
+            }
+
+            if opts.dry_run {
+                return Ok(result);
+            }
+
+            // Move or drop the `contains` edges before the task goes
+            if let Some(ref parent) = result.new_parent {
+                tx.execute(
+                    "INSERT OR IGNORE INTO dependencies (from_task_id, to_task_id, dep_type)
+                     SELECT ?1, to_task_id, 'contains' FROM dependencies
+                     WHERE from_task_id = ?2 AND dep_type = 'contains'",
+                    params![parent, task_id],
+                )?;
+            }
+            if opts.cascade != DeleteCascade::DeleteSubtree {
+                tx.execute(
+                    "DELETE FROM dependencies WHERE from_task_id = ?1 AND dep_type = 'contains'",
+                    params![task_id],
+                )?;
+            }
+
+            let ids = serde_json::to_string(&result.deleted)?;
+            if opts.obliterate {
+                // Hard delete - permanently remove from database
+                tx.execute(
+                    "DELETE FROM tasks WHERE id IN (SELECT value FROM json_each(?1))",
+                    params![ids],
+                )?;
+            } else {
+                // Soft delete - set deleted_at, deleted_by, deleted_reason
+                tx.execute(
+                    "UPDATE tasks SET deleted_at = ?2, deleted_by = ?3, deleted_reason = ?4, updated_at = ?2
+                     WHERE id IN (SELECT value FROM json_each(?1)) AND deleted_at IS NULL",
+                    params![ids, now, worker_id, opts.reason],
+                )?;
+            }
+
+            tx.commit()?;
+            Ok(result)
+        })
+    }
+
+    /// Delete the given tasks in one transaction. Tasks claimed by another
+    /// worker (unless `force`) or with live children outside the set are
+    /// skipped, and so are their ancestors in the set.
+    pub fn bulk_delete_tasks(
         &self,
-        task_id: &str,
+        task_ids: &[String],
         worker_id: &str,
-        cascade: bool,
-        reason: Option<String>,
-        obliterate: bool,
-        force: bool,
-    ) -> Result<()> {
+        opts: &BulkDeleteOptions,
+    ) -> Result<BulkResult> {
         let now = now_ms();
 
         self.with_conn_mut(|conn| {
             let tx = conn.transaction()?;
 
-            // Get the task to check ownership
-            let task = get_task_internal(&tx, task_id)?
-                .ok_or_else(|| anyhow!("Task not found"))?;
-
-            // Check ownership - reject if claimed by another worker (unless force)
-            if let Some(ref owner) = task.worker_id
-                && owner != worker_id && !force {
-                    return Err(anyhow!(
-                        "Task is claimed by worker '{}'. Use force=true to override.",
-                        owner
-                    ));
+            let mut result = BulkResult::default();
+            let mut kept: Vec<String> = Vec::new();
+            for id in task_ids {
+                let Some(task) = get_task_internal(&tx, id)? else {
+                    continue;
+                };
+                match task.worker_id {
+                    Some(ref owner) if owner != worker_id && !opts.force => result
+                        .skipped
+                        .push((id.clone(), format!("claimed by worker '{}'", owner))),
+                    _ => kept.push(id.clone()),
                 }
+            }
 
-            if obliterate {
-                // Hard delete - permanently remove from database
-                if cascade {
-                    // Find all descendants using recursive CTE and delete them
-                    // The CTE finds all tasks reachable via 'contains' dependencies
-                    tx.execute(
-                        "WITH RECURSIVE descendants AS (
-                            SELECT ?1 AS id
-                            UNION ALL
-                            SELECT dep.to_task_id FROM dependencies dep
-                            INNER JOIN descendants d ON dep.from_task_id = d.id
-                            WHERE dep.dep_type = 'contains'
-                        )
-                        DELETE FROM tasks WHERE id IN (SELECT id FROM descendants)",
-                        params![task_id],
-                    )?;
-                } else {
-                    // Check for children via dependencies
-                    let child_count: i32 = tx.query_row(
-                        "SELECT COUNT(*) FROM dependencies WHERE from_task_id = ?1 AND dep_type = 'contains'",
-                        params![task_id],
-                        |row| row.get(0),
-                    )?;
-
-                    if child_count > 0 {
-                        return Err(anyhow!("Task has children; use cascade=true to delete"));
+            // Drop tasks that would leave a live child behind, until none do
+            let mut stmt = tx.prepare_cached(
+                "SELECT d.to_task_id FROM dependencies d
+                 JOIN tasks c ON c.id = d.to_task_id
+                 WHERE d.from_task_id = ?1 AND d.dep_type = 'contains'
+                   AND c.deleted_at IS NULL",
+            )?;
+            loop {
+                let mut next = Vec::with_capacity(kept.len());
+                let mut dropped = false;
+                for id in &kept {
+                    let children: Vec<String> = stmt
+                        .query_map(params![id], |row| row.get(0))?
+                        .collect::<rusqlite::Result<_>>()?;
+                    match children.iter().find(|c| !kept.contains(c)) {
+                        Some(child) => {
+                            result
+                                .skipped
+                                .push((id.clone(), format!("child '{}' is not deleted", child)));
+                            dropped = true;
+                        }
+                        None => next.push(id.clone()),
                     }
-
-                    tx.execute("DELETE FROM tasks WHERE id = ?1", params![task_id])?;
                 }
-            } else {
-                // Soft delete - set deleted_at, deleted_by, deleted_reason
-                if cascade {
-                    // Soft delete all descendants
-                    tx.execute(
-                        "WITH RECURSIVE descendants AS (
-                            SELECT ?1 AS id
-                            UNION ALL
-                            SELECT dep.to_task_id FROM dependencies dep
-                            INNER JOIN descendants d ON dep.from_task_id = d.id
-                            WHERE dep.dep_type = 'contains'
-                        )
-                        UPDATE tasks SET deleted_at = ?2, deleted_by = ?3, deleted_reason = ?4, updated_at = ?2
-                        WHERE id IN (SELECT id FROM descendants) AND deleted_at IS NULL",
-                        params![task_id, now, worker_id, reason],
-                    )?;
-                } else {
-                    // Check for children via dependencies
-                    let child_count: i32 = tx.query_row(
-                        "SELECT COUNT(*) FROM dependencies WHERE from_task_id = ?1 AND dep_type = 'contains'",
-                        params![task_id],
-                        |row| row.get(0),
-                    )?;
+                kept = next;
+                if !dropped {
+                    break;
+                }
+            }
+            drop(stmt);
+            result.affected = kept;
 
-                    if child_count > 0 {
-                        return Err(anyhow!("Task has children; use cascade=true to delete"));
-                    }
+            if opts.dry_run {
+                return Ok(result);
+            }
+            check_expected_count(opts.expected_count, result.affected.len())?;
 
-                    tx.execute(
-                        "UPDATE tasks SET deleted_at = ?1, deleted_by = ?2, deleted_reason = ?3, updated_at = ?1 WHERE id = ?4",
-                        params![now, worker_id, reason, task_id],
-                    )?;
-                }
+            let ids = serde_json::to_string(&result.affected)?;
+            if opts.obliterate {
+                tx.execute(
+                    "DELETE FROM tasks WHERE id IN (SELECT value FROM json_each(?1))",
+                    params![ids],
+                )?;
+            } else {
+                tx.execute(
+                    "UPDATE tasks SET deleted_at = ?2, deleted_by = ?3, deleted_reason = ?4, updated_at = ?2
+                     WHERE id IN (SELECT value FROM json_each(?1)) AND deleted_at IS NULL",
+                    params![ids, now, worker_id, opts.reason],
+                )?;
             }
 
             tx.commit()?;
-            Ok(())
+            Ok(result)
         })
     }
 
@@ -1221,8 +2227,14 @@ impl Database {
             offset,
             sort_by,
             sort_order,
+            fields,
+            blocked_reason,
+            needed_group,
+            project,
+            scope,
+            filter,
         } = query;
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut sql = String::from(
                 "SELECT t.* FROM tasks t WHERE t.deleted_at IS NULL",
             );
@@ -1257,6 +2269,30 @@ impl Database {
                 }
             }
 
+            if let Some(r) = blocked_reason {
+                sql.push_str(" AND t.blocked_reason = ?");
+                params_vec.push(Box::new(r.to_string()));
+            }
+
+            if let Some(g) = needed_group {
+                sql.push_str(" AND t.needed_group = ?");
+                params_vec.push(Box::new(g.to_string()));
+            }
+
+            if let Some(p) = project {
+                sql.push_str(" AND t.project = ?");
+                params_vec.push(Box::new(p.to_string()));
+            }
+
+            for (name, value) in fields {
+                sql.push_str(" AND EXISTS (SELECT 1 FROM task_fields f WHERE f.task_id = t.id AND f.name = ? AND f.value = ?)");
+                params_vec.push(Box::new(name.clone()));
+                params_vec.push(Box::new(value.clone()));
+            }
+
+            sql.push_str(&scope_clause(&mut params_vec, scope)?);
+            sql.push_str(&filter_clause(&mut params_vec, filter));
+
             // Build ORDER BY clause
             let order_clause = build_order_clause(sort_by, sort_order);
             sql.push_str(&format!(" ORDER BY {}", order_clause));
@@ -1272,7 +2308,7 @@ impl Database {
             let params_refs: Vec<&dyn rusqlite::ToSql> =
                 params_vec.iter().map(|b| b.as_ref()).collect();
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare_cached(&sql)?;
             let tasks = stmt
                 .query_map(params_refs.as_slice(), parse_task_row)?
                 .filter_map(|r| r.ok())
@@ -1283,6 +2319,7 @@ impl Database {
     }
 
     /// Set the current thought for tasks owned by an agent.
+    /// A new thought is also appended to the agent's thought log.
     pub fn set_thought(
         &self,
         agent_id: &str,
@@ -1290,6 +2327,7 @@ impl Database {
         task_ids: Option<Vec<String>>,
     ) -> Result<i32> {
         let now = now_ms();
+        let task_ids_json = task_ids.as_ref().map(serde_json::to_string).transpose()?;
 
         self.with_conn(|conn| {
             let updated = if let Some(ids) = task_ids {
@@ -1318,10 +2356,124 @@ impl Database {
                 )?
             };
 
+            if let Some(ref text) = thought {
+                log_thought(conn, agent_id, text, task_ids_json.as_deref(), now)?;
+            }
+
             Ok(updated as i32)
         })
     }
 
+    /// A task's latest `limit` journal thoughts, oldest first.
+    pub fn get_task_thoughts(&self, task_id: &str, limit: i64) -> Result<Vec<TaskThought>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT * FROM (
+                     SELECT id, task_id, worker_id, thought, created_at FROM task_thoughts
+                     WHERE task_id = ?1 ORDER BY id DESC LIMIT ?2)
+                 ORDER BY id",
+            )?;
+            let thoughts = stmt
+                .query_map(params![task_id, limit], parse_thought_row)?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(thoughts)
+        })
+    }
+
+    /// Set or clear the structured blocked reason for a task.
+    /// Returns false if the task does not exist.
+    pub fn set_blocked_reason(
+        &self,
+        task_id: &str,
+        reason: Option<&str>,
+        note: Option<&str>,
+    ) -> Result<bool> {
+        let now = now_ms();
+
+        self.with_conn(|conn| {
+            let updated = conn.execute(
+                "UPDATE tasks SET blocked_reason = ?1, blocked_note = ?2, updated_at = ?3
+                 WHERE id = ?4 AND deleted_at IS NULL",
+                params![reason, note, now, task_id],
+            )?;
+            Ok(updated > 0)
+        })
+    }
+
+    /// Set or clear the agent group required to claim a task.
+    /// Returns false if the task does not exist.
+    pub fn set_needed_group(&self, task_id: &str, group: Option<&str>) -> Result<bool> {
+        let now = now_ms();
+
+        self.with_conn(|conn| {
+            let updated = conn.execute(
+                "UPDATE tasks SET needed_group = ?1, updated_at = ?2
+                 WHERE id = ?3 AND deleted_at IS NULL",
+                params![group, now, task_id],
+            )?;
+            Ok(updated > 0)
+        })
+    }
+
+    /// Set whether a task's wanted tags only raise the match score instead of
+    /// gating the claim. Returns false if the task does not exist.
+    pub fn set_wanted_optional(&self, task_id: &str, optional: bool) -> Result<bool> {
+        let now = now_ms();
+
+        self.with_conn(|conn| {
+            let updated = conn.execute(
+                "UPDATE tasks SET wanted_optional = ?1, updated_at = ?2
+                 WHERE id = ?3 AND deleted_at IS NULL",
+                params![optional, now, task_id],
+            )?;
+            Ok(updated > 0)
+        })
+    }
+
+    /// Set the tag rules every task under this one must meet; an empty list
+    /// clears them. Returns false if the task does not exist.
+    pub fn set_required_child_tags(&self, task_id: &str, rules: &[String]) -> Result<bool> {
+        let now = now_ms();
+        let json = if rules.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(rules)?)
+        };
+
+        self.with_conn(|conn| {
+            let updated = conn.execute(
+                "UPDATE tasks SET required_child_tags = ?1, updated_at = ?2
+                 WHERE id = ?3 AND deleted_at IS NULL",
+                params![json, now, task_id],
+            )?;
+            Ok(updated > 0)
+        })
+    }
+
+    /// The required-tag rules a task placed under `parent_id` must meet, as
+    /// `(rule, task declaring it)`: the parent's and every ancestor's.
+    pub fn get_required_tags_under(&self, parent_id: &str) -> Result<Vec<(String, String)>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, required_child_tags FROM tasks
+                 WHERE (id = ?1 OR id IN (
+                     SELECT ancestor_id FROM task_closure WHERE descendant_id = ?1
+                 ))
+                 AND required_child_tags IS NOT NULL AND deleted_at IS NULL",
+            )?;
+            let rows = stmt.query_map(params![parent_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            let mut rules = Vec::new();
+            for row in rows {
+                let (id, json) = row?;
+                let declared: Vec<String> = serde_json::from_str(&json).unwrap_or_default();
+                rules.extend(declared.into_iter().map(|rule| (rule, id.clone())));
+            }
+            Ok(rules)
+        })
+    }
+
     /// Log time for a task.
     pub fn log_time(&self, task_id: &str, duration_ms: i64) -> Result<i64> {
         let now = now_ms();
@@ -1333,11 +2485,9 @@ impl Database {
                 params![duration_ms, now, task_id],
             )?;
 
-            let total: i64 = conn.query_row(
-                "SELECT COALESCE(time_actual_ms, 0) FROM tasks WHERE id = ?1",
-                params![task_id],
-                |row| row.get(0),
-            )?;
+            let total: i64 = conn
+                .prepare_cached("SELECT COALESCE(time_actual_ms, 0) FROM tasks WHERE id = ?1")?
+                .query_row(params![task_id], |row| row.get(0))?;
 
             Ok(total)
         })
@@ -1422,6 +2572,7 @@ impl Database {
             if task.worker_id.is_some() {
                 return Err(anyhow!("Task is already claimed"));
             }
+            check_reservation(conn, task_id, agent_id)?;
 
             // Validate state transition
             if !states_config.is_valid_transition(&task.status, claim_status) {
@@ -1437,21 +2588,37 @@ impl Database {
             let agent =
                 get_worker_internal(conn, agent_id)?.ok_or_else(|| anyhow!("Agent not found"))?;
 
+            // Check project
+            if agent.project != task.project {
+                return Err(anyhow!(
+                    "Agent is in project '{}', but the task is in project '{}'",
+                    agent.project,
+                    task.project
+                ));
+            }
+
+            // Check group membership
+            if let Some(ref group) = task.needed_group
+                && !is_group_member(conn, agent_id, group)?
+            {
+                return Err(anyhow!("Agent is not in required group: {}", group));
+            }
+
             // Check tag affinity - needed_tags (AND - must have ALL)
             if !task.needed_tags.is_empty() {
                 for needed in &task.needed_tags {
-                    if !agent.tags.contains(needed) {
+                    if !has_tag(&agent.tags, needed) {
                         return Err(anyhow!("Agent missing required tag: {}", needed));
                     }
                 }
             }
 
             // Check tag affinity - wanted_tags (OR - must have AT LEAST ONE)
-            if !task.wanted_tags.is_empty() {
+            if !task.wanted_tags.is_empty() && !task.wanted_optional {
                 let has_any = task
                     .wanted_tags
                     .iter()
-                    .any(|wanted| agent.tags.contains(wanted));
+                    .any(|wanted| has_tag(&agent.tags, wanted));
                 if !has_any {
                     return Err(anyhow!("Agent has none of the wanted tags"));
                 }
@@ -1462,6 +2629,7 @@ impl Database {
                  WHERE id = ?6",
                 params![agent_id, now, claim_status, now, now, task_id,],
             )?;
+            clear_reservation(conn, task_id)?;
 
             // Record state transition (accumulates time if coming from timed state)
             record_state_transition(
@@ -1490,6 +2658,115 @@ impl Database {
         })
     }
 
+    /// Claim several tasks for an agent in one transaction.
+    ///
+    /// Tries `task_ids` in order and claims each one the agent may claim (the
+    /// same checks as a single claim: unclaimed, a valid transition to the first
+    /// timed state, start-blocking deps satisfied, project, group, and tags),
+    /// stopping at `count` claims when given. Claims never exceed the agent's
+    /// `max_claims`; with an explicit list, tasks left over at the limit are
+    /// skipped. The tasks not obtained are returned with the reason.
+    pub fn claim_many(
+        &self,
+        agent_id: &str,
+        task_ids: &[String],
+        count: Option<usize>,
+        states_config: &StatesConfig,
+        deps_config: &DependenciesConfig,
+    ) -> Result<ClaimBatch> {
+        let now = now_ms();
+        let claim_status = states_config
+            .definitions
+            .iter()
+            .find(|(_, def)| def.timed)
+            .map(|(name, _)| name.as_str())
+            .unwrap_or("working");
+
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            let agent =
+                get_worker_internal(&tx, agent_id)?.ok_or_else(|| anyhow!("Agent not found"))?;
+            let load: i64 = tx
+                .prepare_cached("SELECT COUNT(*) FROM tasks WHERE worker_id = ?1")?
+                .query_row(params![agent_id], |row| row.get(0))?;
+            let capacity = (agent.max_claims as i64 - load).max(0) as usize;
+            let wanted = count.unwrap_or(task_ids.len());
+
+            let mut batch = ClaimBatch::default();
+            for task_id in task_ids {
+                if batch.claimed.len() >= wanted {
+                    break;
+                }
+                if batch.claimed.len() >= capacity {
+                    if count.is_some() {
+                        break;
+                    }
+                    batch.skip(
+                        task_id,
+                        format!("Agent is at its claim limit ({})", agent.max_claims),
+                    );
+                    continue;
+                }
+
+                let Some(task) = get_task_internal(&tx, task_id)? else {
+                    batch.skip(task_id, "Task not found");
+                    continue;
+                };
+                if let Some(ref owner) = task.worker_id {
+                    batch.skip(task_id, format!("Task is already claimed by '{}'", owner));
+                    continue;
+                }
+                if !states_config.is_valid_transition(&task.status, claim_status) {
+                    batch.skip(
+                        task_id,
+                        format!("Cannot claim task in state '{}'", task.status),
+                    );
+                    continue;
+                }
+                if let Err(e) = check_claim(&tx, &task, agent_id, false, states_config, deps_config)
+                {
+                    batch.skip(task_id, e.to_string());
+                    continue;
+                }
+
+                let started_at = task.started_at.unwrap_or(now);
+                tx.execute(
+                    "UPDATE tasks SET worker_id = ?1, claimed_at = ?2, status = ?3, started_at = ?4, updated_at = ?5
+                     WHERE id = ?6",
+                    params![agent_id, now, claim_status, started_at, now, task_id],
+                )?;
+                clear_reservation(&tx, task_id)?;
+                record_state_transition(
+                    &tx,
+                    task_id,
+                    claim_status,
+                    Some(agent_id),
+                    None,
+                    states_config,
+                )?;
+
+                batch.claimed.push(Task {
+                    worker_id: Some(agent_id.to_string()),
+                    claimed_at: Some(now),
+                    status: claim_status.to_string(),
+                    started_at: Some(started_at),
+                    updated_at: now,
+                    ..task
+                });
+            }
+
+            if !batch.claimed.is_empty() {
+                tx.execute(
+                    "UPDATE workers SET last_heartbeat = ?1 WHERE id = ?2",
+                    params![now, agent_id],
+                )?;
+            }
+            tx.commit()?;
+            Ok(batch)
+        })
+    }
+
     /// Release a task claim.
     pub fn release_task(
         &self,
@@ -1557,6 +2834,68 @@ impl Database {
         })
     }
 
+    /// Average actual duration of the finished tasks an agent has worked on.
+    /// Returns None when the agent has no timed history.
+    pub fn get_agent_typical_duration(&self, agent_id: &str) -> Result<Option<f64>> {
+        self.with_read_conn(|conn| {
+            let avg: Option<f64> = conn
+                .prepare_cached(
+                    "SELECT AVG(t.time_actual_ms) FROM tasks t
+                 WHERE t.completed_at IS NOT NULL AND t.time_actual_ms > 0
+                 AND t.deleted_at IS NULL
+                 AND EXISTS (SELECT 1 FROM task_sequence s
+                             WHERE s.task_id = t.id AND s.worker_id = ?1)",
+                )?
+                .query_row(params![agent_id], |row| row.get(0))?;
+            Ok(avg)
+        })
+    }
+
+    /// Rank the tasks an agent could claim right now by match score, best first.
+    /// Candidates are the agent's ready tasks (tags and group already qualify);
+    /// ties keep the ready order (priority, then newest). With a `fair_share`, the
+    /// priority score of high-priority tasks is reduced by the agent's penalty.
+    /// Deadlines lift the priority component by `priorities.deadline_weight`;
+    /// tags count toward the match by their `tags` weight.
+    #[allow(clippy::too_many_arguments)]
+    pub fn rank_claim_candidates(
+        &self,
+        agent_id: &str,
+        states_config: &StatesConfig,
+        deps_config: &DependenciesConfig,
+        priorities: &PrioritiesConfig,
+        tags_config: &TagsConfig,
+        fair_share: Option<&FairShare>,
+        limit: usize,
+    ) -> Result<Vec<ClaimCandidate>> {
+        let ready =
+            self.get_ready_tasks(Some(agent_id), states_config, deps_config, None, None, None)?;
+        let agent_tags = self.get_agent_tags(agent_id)?;
+        let typical = self.get_agent_typical_duration(agent_id)?;
+        let ids: Vec<String> = ready.iter().map(|t| t.id.clone()).collect();
+        let deadlines = self.get_task_deadlines(&ids)?;
+        let now = now_ms();
+
+        let mut candidates: Vec<ClaimCandidate> = ready
+            .iter()
+            .map(|task| {
+                let deadline = deadline_score(deadlines.get(&task.id).copied(), now);
+                score_claim_candidate(
+                    task,
+                    &agent_tags,
+                    typical,
+                    priorities,
+                    tags_config,
+                    fair_share,
+                    deadline,
+                )
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        candidates.truncate(limit);
+        Ok(candidates)
+    }
+
     /// Force claim a task even if owned by another agent.
     pub fn force_claim_task(
         &self,
@@ -1583,21 +2922,37 @@ impl Database {
             let agent =
                 get_worker_internal(conn, agent_id)?.ok_or_else(|| anyhow!("Agent not found"))?;
 
+            // Check project
+            if agent.project != task.project {
+                return Err(anyhow!(
+                    "Agent is in project '{}', but the task is in project '{}'",
+                    agent.project,
+                    task.project
+                ));
+            }
+
+            // Check group membership
+            if let Some(ref group) = task.needed_group
+                && !is_group_member(conn, agent_id, group)?
+            {
+                return Err(anyhow!("Agent is not in required group: {}", group));
+            }
+
             // Check tag affinity - needed_tags (AND)
             if !task.needed_tags.is_empty() {
                 for needed in &task.needed_tags {
-                    if !agent.tags.contains(needed) {
+                    if !has_tag(&agent.tags, needed) {
                         return Err(anyhow!("Agent missing required tag: {}", needed));
                     }
                 }
             }
 
             // Check tag affinity - wanted_tags (OR)
-            if !task.wanted_tags.is_empty() {
+            if !task.wanted_tags.is_empty() && !task.wanted_optional {
                 let has_any = task
                     .wanted_tags
                     .iter()
-                    .any(|wanted| agent.tags.contains(wanted));
+                    .any(|wanted| has_tag(&agent.tags, wanted));
                 if !has_any {
                     return Err(anyhow!("Agent has none of the wanted tags"));
                 }
@@ -1743,7 +3098,7 @@ impl Database {
             let tx = conn.transaction()?;
 
             // Get the task
-            let mut stmt = tx.prepare("SELECT * FROM tasks WHERE id = ?1")?;
+            let mut stmt = tx.prepare_cached("SELECT * FROM tasks WHERE id = ?1")?;
             let task = stmt
                 .query_row(params![task_id], parse_task_row)
                 .map_err(|_| anyhow!("Task not found"))?;
@@ -1755,17 +3110,20 @@ impl Database {
             }
 
             // Check for incomplete children (blocking completion)
-            let incomplete_children: i32 = tx.query_row(
-                "SELECT COUNT(*) FROM dependencies d
+            let incomplete_children: i32 = tx
+                .prepare_cached(
+                    "SELECT COUNT(*) FROM dependencies d
                  INNER JOIN tasks child ON d.to_task_id = child.id
                  WHERE d.from_task_id = ?1 AND d.dep_type = 'contains'
                  AND child.status IN (SELECT value FROM json_each(?2))",
-                params![
-                    task_id,
-                    serde_json::to_string(&states_config.blocking_states)?
-                ],
-                |row| row.get(0),
-            )?;
+                )?
+                .query_row(
+                    params![
+                        task_id,
+                        serde_json::to_string(&states_config.blocking_states)?
+                    ],
+                    |row| row.get(0),
+                )?;
 
             if incomplete_children > 0 {
                 return Err(anyhow!(
@@ -1797,7 +3155,7 @@ impl Database {
             // Update task to completed
             tx.execute(
                 "UPDATE tasks SET status = ?1, completed_at = ?2, updated_at = ?3,
-                 worker_id = NULL, claimed_at = NULL
+                 worker_id = NULL, claimed_at = NULL, blocked_reason = NULL, blocked_note = NULL
                  WHERE id = ?4",
                 params![complete_status, now, now, task_id],
             )?;
@@ -1822,16 +3180,35 @@ impl Database {
                 updated_at: now,
                 worker_id: None,
                 claimed_at: None,
+                blocked_reason: None,
+                blocked_note: None,
                 ..task
             })
         })
     }
 
+    /// Get tasks flagged with a blocked reason, oldest update first.
+    /// Excludes soft-deleted tasks.
+    pub fn get_tasks_with_blocked_reason(&self) -> Result<Vec<Task>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT * FROM tasks WHERE blocked_reason IS NOT NULL AND deleted_at IS NULL
+                 ORDER BY blocked_reason, updated_at",
+            )?;
+            let tasks = stmt
+                .query_map([], parse_task_row)?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(tasks)
+        })
+    }
+
     /// Get all tasks. Excludes soft-deleted tasks.
     pub fn get_all_tasks(&self) -> Result<Vec<Task>> {
-        self.with_conn(|conn| {
-            let mut stmt =
-                conn.prepare("SELECT * FROM tasks WHERE deleted_at IS NULL ORDER BY created_at")?;
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT * FROM tasks WHERE deleted_at IS NULL ORDER BY created_at",
+            )?;
             let tasks = stmt
                 .query_map([], parse_task_row)?
                 .filter_map(|r| r.ok())
@@ -1843,9 +3220,9 @@ impl Database {
     /// Get tasks by status.
     #[allow(dead_code)]
     pub fn get_tasks_by_status(&self, status: &str) -> Result<Vec<Task>> {
-        self.with_conn(|conn| {
+        self.with_read_conn(|conn| {
             let mut stmt =
-                conn.prepare("SELECT * FROM tasks WHERE status = ?1 ORDER BY created_at")?;
+                conn.prepare_cached("SELECT * FROM tasks WHERE status = ?1 ORDER BY created_at")?;
             let tasks = stmt
                 .query_map(params![status], parse_task_row)?
                 .filter_map(|r| r.ok())
@@ -1854,29 +3231,48 @@ impl Database {
         })
     }
 
-    /// Get claimed tasks. Excludes soft-deleted tasks.
-    pub fn get_claimed_tasks(&self, agent_id: Option<&str>) -> Result<Vec<Task>> {
-        self.with_conn(|conn| {
-            let tasks = if let Some(aid) = agent_id {
-                let mut stmt = conn
-                    .prepare("SELECT * FROM tasks WHERE worker_id = ?1 AND deleted_at IS NULL ORDER BY claimed_at")?;
-                stmt.query_map(params![aid], parse_task_row)?
-                    .filter_map(|r| r.ok())
-                    .collect()
+    /// Get claimed tasks, optionally within a visibility scope. Excludes soft-deleted tasks.
+    pub fn get_claimed_tasks(
+        &self,
+        agent_id: Option<&str>,
+        scope: Option<&TaskScope>,
+    ) -> Result<Vec<Task>> {
+        self.with_read_conn(|conn| {
+            let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+            let owner_clause = if let Some(aid) = agent_id {
+                params_vec.push(Box::new(aid.to_string()));
+                "t.worker_id = ?1"
             } else {
-                let mut stmt = conn.prepare(
-                    "SELECT * FROM tasks WHERE worker_id IS NOT NULL AND deleted_at IS NULL ORDER BY claimed_at",
-                )?;
-                stmt.query_map([], parse_task_row)?
-                    .filter_map(|r| r.ok())
-                    .collect()
+                "t.worker_id IS NOT NULL"
             };
+            let sql = format!(
+                "SELECT t.* FROM tasks t WHERE {} AND t.deleted_at IS NULL{} ORDER BY t.claimed_at",
+                owner_clause,
+                scope_clause(&mut params_vec, scope)?
+            );
+
+            let params_refs: Vec<&dyn rusqlite::ToSql> =
+                params_vec.iter().map(|b| b.as_ref()).collect();
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let tasks = stmt
+                .query_map(params_refs.as_slice(), parse_task_row)?
+                .filter_map(|r| r.ok())
+                .collect();
 
             Ok(tasks)
         })
     }
 }
 
+/// Divide `total` into `count` near-equal shares, giving the remainder to the first shares.
+fn split_evenly(total: i64, count: i64) -> Vec<Option<i64>> {
+    let base = total / count;
+    let extra = total % count;
+    (0..count)
+        .map(|i| Some(base + if i < extra { 1 } else { 0 }))
+        .collect()
+}
+
 /// Helper function to create task tree recursively within a transaction.
 /// Creates dependencies from parent to children using child_type.
 /// Creates dependencies between siblings using sibling_type.
@@ -1900,23 +3296,21 @@ fn create_tree_recursive(
     // Check if this node references an existing task
     let task_id = if let Some(ref ref_id) = input.ref_id {
         // Verify the referenced task exists
-        let exists: bool = conn.query_row(
-            "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
-            params![ref_id],
-            |row| row.get(0),
-        )?;
+        let exists: bool = conn
+            .prepare_cached("SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)")?
+            .query_row(params![ref_id], |row| row.get(0))?;
         if !exists {
             return Err(anyhow::anyhow!("Referenced task '{}' not found", ref_id));
         }
         ref_id.clone()
     } else {
         // Create a new task
-        let task_id = input
-            .id
-            .clone()
-            .unwrap_or_else(|| generate_task_id(ids_config));
+        let task_id = match input.id {
+            Some(ref id) => id.clone(),
+            None => generate_task_id(conn, ids_config)?,
+        };
         let now = now_ms();
-        let priority = clamp_priority(input.priority.unwrap_or(PRIORITY_DEFAULT));
+        let priority = input.priority.unwrap_or(PRIORITY_DEFAULT);
         let initial_status = &states_config.initial;
 
         // Derive title: use explicit title, or derive from description, or empty
@@ -1935,9 +3329,13 @@ fn create_tree_recursive(
             phase_warnings.push(format!("Task '{}': {}", task_id, warning));
         }
 
-        let needed_tags = input.needed_tags.clone().unwrap_or_default();
-        let wanted_tags = input.wanted_tags.clone().unwrap_or_default();
-        let tags = input.tags.clone().unwrap_or_default();
+        let canonical = |tags: &Option<Vec<String>>| {
+            tags_config.canonical_tags(tags.as_deref().unwrap_or_default())
+        };
+        let needed_tags = canonical(&input.needed_tags);
+        let wanted_tags = canonical(&input.wanted_tags);
+        let tags = canonical(&input.tags);
+        let required_child_tags = canonical(&input.required_child_tags);
 
         // Check tag validity for all tag types
         for warning in tags_config.validate_tags(&tags)? {
@@ -1953,28 +3351,42 @@ fn create_tree_recursive(
         let needed_tags_json = serde_json::to_string(&needed_tags)?;
         let wanted_tags_json = serde_json::to_string(&wanted_tags)?;
         let tags_json = serde_json::to_string(&tags)?;
+        let required_child_tags_json = if required_child_tags.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&required_child_tags)?)
+        };
 
-        conn.execute(
+        let project = match parent_id {
+            Some(pid) => project_of(conn, pid)?,
+            None => DEFAULT_PROJECT.to_string(),
+        };
+
+        conn.prepare_cached(
             "INSERT INTO tasks (
                 id, title, description, status, phase, priority,
-                needed_tags, wanted_tags, tags, points, time_estimate_ms, created_at, updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-            params![
-                &task_id,
-                &title,
-                &input.description,
-                initial_status,
-                &input.phase,
-                priority.to_string(),
-                needed_tags_json,
-                wanted_tags_json,
-                tags_json,
-                input.points,
-                input.time_estimate_ms,
-                now,
-                now,
-            ],
-        )?;
+                needed_tags, wanted_tags, tags, points, time_estimate_ms, created_at, updated_at,
+                project, required_child_tags, wanted_optional
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        )?
+        .execute(params![
+            &task_id,
+            &title,
+            &input.description,
+            initial_status,
+            &input.phase,
+            priority.to_string(),
+            needed_tags_json,
+            wanted_tags_json,
+            tags_json,
+            input.points,
+            input.time_estimate_ms,
+            now,
+            now,
+            &project,
+            required_child_tags_json,
+            input.wanted_optional.unwrap_or(false),
+        ])?;
 
         // Record initial state transition
         record_state_transition(conn, &task_id, initial_status, None, None, states_config)?;
@@ -30,7 +30,7 @@ use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use super::Database;
-use super::import::{ImportMode, ImportOptions, ImportResult, remap_snapshot};
+use super::import::{ImportMode, ImportOptions, ImportResult, remap_snapshot_with};
 
 /// Metadata about a template, extracted during analysis.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -263,7 +263,7 @@ pub fn analyze_template(
 /// modifying task properties. Returns the prepared snapshot and ID mapping.
 ///
 /// This is the core transformation step:
-/// 1. Remap all task IDs to fresh petname IDs
+/// 1. Remap all task IDs to fresh IDs produced by `next_id`
 /// 2. Optionally reset task statuses to initial state
 /// 3. Optionally prefix task titles
 /// 4. Optionally add extra tags
@@ -271,12 +271,12 @@ pub fn analyze_template(
 /// 6. Clear runtime fields (worker_id, claimed_at, thoughts, etc.)
 fn prepare_snapshot(
     snapshot: &Snapshot,
-    ids_config: &IdsConfig,
     options: &InstantiateOptions,
+    next_id: impl FnMut() -> Result<String>,
 ) -> Result<(Snapshot, HashMap<String, String>)> {
-    // Phase 1: Remap all IDs using the existing remap_snapshot function
+    // Phase 1: Remap all IDs using the existing remap_snapshot_with function
     let (mut prepared, id_map) =
-        remap_snapshot(snapshot, ids_config).context("Failed to remap template IDs")?;
+        remap_snapshot_with(snapshot, next_id).context("Failed to remap template IDs")?;
 
     let now_ms = chrono::Utc::now().timestamp_millis();
 
@@ -304,6 +304,8 @@ fn prepare_snapshot(
                 obj.insert("worker_id".to_string(), Value::Null);
                 obj.insert("claimed_at".to_string(), Value::Null);
                 obj.insert("current_thought".to_string(), Value::Null);
+                obj.insert("blocked_reason".to_string(), Value::Null);
+                obj.insert("blocked_note".to_string(), Value::Null);
                 obj.insert("started_at".to_string(), Value::Null);
                 obj.insert("completed_at".to_string(), Value::Null);
                 obj.insert("time_actual_ms".to_string(), Value::Null);
@@ -312,6 +314,10 @@ fn prepare_snapshot(
                     "metrics".to_string(),
                     serde_json::json!([0, 0, 0, 0, 0, 0, 0, 0]),
                 );
+                // Raw database rows (e.g. from snapshot_subtree) carry per-column metrics
+                for i in 0..8 {
+                    obj.insert(format!("metric_{}", i), serde_json::json!(0));
+                }
 
                 // Update timestamps to now
                 obj.insert("created_at".to_string(), serde_json::json!(now_ms));
@@ -348,6 +354,22 @@ fn prepare_snapshot(
                 }));
             }
         }
+
+        // Keep the denormalized tags column in sync for raw database rows,
+        // where it is stored as a JSON-encoded string
+        if let Some(tasks) = prepared.tables.get_mut("tasks") {
+            for task_row in tasks.iter_mut() {
+                if let Some(tags_json) = task_row.get("tags").and_then(|v| v.as_str()) {
+                    let mut tags: Vec<String> = serde_json::from_str(tags_json).unwrap_or_default();
+                    for tag in &options.extra_tags {
+                        if !tags.contains(tag) {
+                            tags.push(tag.clone());
+                        }
+                    }
+                    task_row["tags"] = Value::String(serde_json::to_string(&tags)?);
+                }
+            }
+        }
     }
 
     // Phase 4: Clear task_sequence (state history is not relevant for instantiated templates)
@@ -410,7 +432,127 @@ pub fn list_templates(templates_dir: &Path) -> Result<Vec<TemplateMetadata>> {
     Ok(templates)
 }
 
+/// Tables copied alongside the tasks of a cloned subtree (all keyed by `task_id`).
+const SUBTREE_TABLES: &[&str] = &[
+    "attachments",
+    "task_tags",
+    "task_needed_tags",
+    "task_wanted_tags",
+    "task_fields",
+];
+
+/// Convert a SQLite row into a JSON object keyed by column name.
+fn row_to_json(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    use rusqlite::types::ValueRef;
+
+    let mut obj = serde_json::Map::new();
+    for (i, name) in row.as_ref().column_names().iter().enumerate() {
+        let value = match row.get_ref(i)? {
+            ValueRef::Null => Value::Null,
+            ValueRef::Integer(n) => serde_json::json!(n),
+            ValueRef::Real(f) => serde_json::json!(f),
+            ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).into_owned()),
+            ValueRef::Blob(b) => Value::String(String::from_utf8_lossy(b).into_owned()),
+        };
+        obj.insert(name.to_string(), value);
+    }
+    Ok(Value::Object(obj))
+}
+
 impl Database {
+    /// Capture a task and its descendants as a Snapshot.
+    ///
+    /// The subtree is found by following `contains` dependencies from the root,
+    /// skipping soft-deleted tasks. Rows are captured in raw column form so the
+    /// snapshot can be fed straight back through the import path. Only
+    /// dependencies with both endpoints inside the subtree are included; state
+    /// history is omitted.
+    pub fn snapshot_subtree(&self, root_id: &str) -> Result<Snapshot> {
+        self.with_read_conn(|conn| {
+            let root_live: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1 AND deleted_at IS NULL)",
+                rusqlite::params![root_id],
+                |row| row.get(0),
+            )?;
+            if !root_live {
+                return Err(anyhow!("Task '{}' not found", root_id));
+            }
+
+            let mut stmt = conn.prepare_cached(
+                "WITH RECURSIVE subtree(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT d.to_task_id FROM dependencies d
+                    INNER JOIN subtree s ON d.from_task_id = s.id
+                    INNER JOIN tasks t ON t.id = d.to_task_id
+                    WHERE d.dep_type = 'contains' AND t.deleted_at IS NULL
+                )
+                SELECT id FROM subtree",
+            )?;
+            let ids: Vec<String> = stmt
+                .query_map(rusqlite::params![root_id], |row| row.get(0))?
+                .filter_map(|r| r.ok())
+                .collect();
+            let ids_json = serde_json::to_string(&ids)?;
+
+            let mut snapshot = Snapshot::new();
+
+            let mut stmt = conn.prepare_cached(
+                "SELECT * FROM tasks WHERE id IN (SELECT value FROM json_each(?1))
+                 ORDER BY created_at, rowid",
+            )?;
+            let rows = stmt
+                .query_map(rusqlite::params![ids_json], row_to_json)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            snapshot.tables.insert("tasks".to_string(), rows);
+
+            let mut stmt = conn.prepare_cached(
+                "SELECT from_task_id, to_task_id, dep_type, lag_ms, any_of FROM dependencies
+                 WHERE from_task_id IN (SELECT value FROM json_each(?1))
+                   AND to_task_id IN (SELECT value FROM json_each(?1))
+                 ORDER BY from_task_id, to_task_id, dep_type",
+            )?;
+            let rows = stmt
+                .query_map(rusqlite::params![ids_json], row_to_json)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            snapshot.tables.insert("dependencies".to_string(), rows);
+
+            for table in SUBTREE_TABLES {
+                let mut stmt = conn.prepare_cached(&format!(
+                    "SELECT * FROM {} WHERE task_id IN (SELECT value FROM json_each(?1)) {}",
+                    table,
+                    crate::export::get_table_ordering(table)
+                ))?;
+                let rows = stmt
+                    .query_map(rusqlite::params![ids_json], row_to_json)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                snapshot.tables.insert(table.to_string(), rows);
+            }
+
+            Ok(snapshot)
+        })
+    }
+
+    /// Deep-copy a task subtree under fresh IDs.
+    ///
+    /// Snapshots the subtree rooted at `root_id`, then instantiates it like a
+    /// template: IDs are remapped, runtime fields are cleared, tags and
+    /// attachments are copied, and internal dependencies are rewired to the
+    /// new IDs. The copied root is attached to `options.parent_task_id` if set.
+    ///
+    /// Attachment rows are copied as they are, so copies of media-dir files
+    /// still point at the original's file; `clone_tree` gives them their own
+    /// (see `tools::attachments::copy_cloned_media`).
+    pub fn clone_subtree(
+        &self,
+        root_id: &str,
+        ids_config: &IdsConfig,
+        options: &InstantiateOptions,
+    ) -> Result<InstantiateResult> {
+        let snapshot = self.snapshot_subtree(root_id)?;
+        self.instantiate_template(&snapshot, root_id, None, ids_config, options)
+    }
+
     /// Instantiate a template from a Snapshot into the database.
     ///
     /// This is the main entry point for template instantiation:
@@ -451,8 +593,20 @@ impl Database {
             ));
         }
 
-        // Step 3: Prepare the snapshot (remap IDs, apply transformations)
-        let (prepared_snapshot, id_map) = prepare_snapshot(snapshot, ids_config, options)?;
+        // Step 3: Prepare the snapshot (remap IDs, apply transformations) and
+        // import it in merge mode. The fresh IDs are generated inside the
+        // import transaction, so a failed import consumes no sequence numbers.
+        let import_options = ImportOptions {
+            mode: ImportMode::Merge,
+            remap_ids: true,
+            parent_id: None,
+        };
+        let mut import_result = self
+            .import_snapshot_remapped(snapshot, &import_options, ids_config, |s, next| {
+                prepare_snapshot(s, options, next)
+            })
+            .context("Failed to import instantiated template")?;
+        let id_map = import_result.id_remap.take().unwrap_or_default();
 
         // Step 4: Map entry/exit points to new IDs
         let entry_point_ids: Vec<String> = metadata
@@ -467,21 +621,16 @@ impl Database {
             .filter_map(|old_id| id_map.get(old_id).cloned())
             .collect();
 
-        // Step 5: Import the prepared snapshot using merge mode
-        let import_options = ImportOptions {
-            mode: ImportMode::Merge,
-            remap_ids: false,
-            parent_id: None,
-        };
-        let import_result = self
-            .import_snapshot(&prepared_snapshot, &import_options)
-            .context("Failed to import instantiated template")?;
-
         let import_stats = ImportStats::from(&import_result);
 
-        // Step 6: Attach entry points to parent task if specified
+        // Step 5: Attach entry points to parent task if specified; the copy
+        // joins the parent's project
         if let Some(ref parent_id) = options.parent_task_id {
             self.attach_template_to_parent(parent_id, &entry_point_ids, &options.attach_dep_type)?;
+            if let Some(project) = self.get_task_project(parent_id)? {
+                let new_ids: Vec<String> = id_map.values().cloned().collect();
+                self.set_tasks_project(&new_ids, &project)?;
+            }
         }
 
         Ok(InstantiateResult {
@@ -548,6 +697,7 @@ impl Database {
 mod tests {
     use super::*;
     use crate::config::IdsConfig;
+    use crate::db::tasks::generate_unsequenced_task_id;
     use crate::export::{CURRENT_SCHEMA_VERSION, EXPORT_VERSION, Snapshot};
     use std::collections::BTreeMap;
 
@@ -690,7 +840,10 @@ mod tests {
         let ids_config = IdsConfig::default();
         let options = InstantiateOptions::new();
 
-        let (prepared, id_map) = prepare_snapshot(&snapshot, &ids_config, &options).unwrap();
+        let (prepared, id_map) = prepare_snapshot(&snapshot, &options, || {
+            generate_unsequenced_task_id(&ids_config)
+        })
+        .unwrap();
 
         // All original IDs should be remapped
         assert!(id_map.contains_key("tpl-root"));
@@ -730,7 +883,10 @@ mod tests {
         let ids_config = IdsConfig::default();
         let options = InstantiateOptions::new(); // reset_status = true by default
 
-        let (prepared, _) = prepare_snapshot(&snapshot, &ids_config, &options).unwrap();
+        let (prepared, _) = prepare_snapshot(&snapshot, &options, || {
+            generate_unsequenced_task_id(&ids_config)
+        })
+        .unwrap();
 
         // All tasks should be reset to pending
         let tasks = prepared.tables.get("tasks").unwrap();
@@ -748,7 +904,10 @@ mod tests {
         let ids_config = IdsConfig::default();
         let options = InstantiateOptions::new().with_title_prefix("Sprint-1");
 
-        let (prepared, _) = prepare_snapshot(&snapshot, &ids_config, &options).unwrap();
+        let (prepared, _) = prepare_snapshot(&snapshot, &options, || {
+            generate_unsequenced_task_id(&ids_config)
+        })
+        .unwrap();
 
         let tasks = prepared.tables.get("tasks").unwrap();
         for task in tasks {
@@ -768,7 +927,10 @@ mod tests {
         let options =
             InstantiateOptions::new().with_extra_tags(vec!["sprint-1".into(), "team-a".into()]);
 
-        let (prepared, _) = prepare_snapshot(&snapshot, &ids_config, &options).unwrap();
+        let (prepared, _) = prepare_snapshot(&snapshot, &options, || {
+            generate_unsequenced_task_id(&ids_config)
+        })
+        .unwrap();
 
         // Should have original tags + extra tags for each task
         let tags = prepared.tables.get("task_tags").unwrap();
@@ -802,7 +964,10 @@ mod tests {
         let ids_config = IdsConfig::default();
         let options = InstantiateOptions::new();
 
-        let (prepared, _) = prepare_snapshot(&snapshot, &ids_config, &options).unwrap();
+        let (prepared, _) = prepare_snapshot(&snapshot, &options, || {
+            generate_unsequenced_task_id(&ids_config)
+        })
+        .unwrap();
 
         // State history should be cleared
         let sequence = prepared.tables.get("task_sequence").unwrap();
@@ -0,0 +1,221 @@
+//! Reconstructing tasks as they stood at a past time.
+//!
+//! A task is rewound from its current row: recorded field changes after the
+//! target time are undone newest first, then status, phase, ownership, and
+//! time tracking are replayed from `task_sequence` up to that time. Values that
+//! only other tools change (cost, metrics) keep their current values, live
+//! thoughts are dropped, and dependencies are not versioned.
+
+use super::Database;
+use super::changes::{CUSTOM_FIELD_PREFIX, parse_change_row};
+use super::tasks::parse_task_row;
+use crate::config::StatesConfig;
+use crate::types::{Task, TaskChange};
+use anyhow::Result;
+use rusqlite::params;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// What `task_sequence` and `task_changes` say about a task up to the target time.
+#[derive(Default)]
+struct Timeline {
+    /// Changes after the target time, newest first.
+    later_changes: Vec<TaskChange>,
+    /// Latest status event: (status, worker, timestamp).
+    status: Option<(String, Option<String>, i64)>,
+    /// Latest phase event.
+    phase: Option<String>,
+    /// Latest recorded owner change: (new owner, timestamp).
+    owner_change: Option<(Option<String>, i64)>,
+    /// Time spent in timed states.
+    time_in_timed_ms: i64,
+    /// Latest event of any kind.
+    last_event: Option<i64>,
+}
+
+/// Set a field (as named in `task_changes`) to a recorded value.
+fn apply_value(task: &mut Task, fields: &mut Map<String, Value>, field: &str, value: &Value) {
+    fn from<T: serde::de::DeserializeOwned + Default>(value: &Value) -> T {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+    match field {
+        "title" => task.title = from(value),
+        "description" => task.description = from(value),
+        "status" => task.status = from(value),
+        "phase" => task.phase = from(value),
+        "priority" => task.priority = from(value),
+        "worker_id" => task.worker_id = from(value),
+        "tags" => task.tags = from(value),
+        "needed_tags" => task.needed_tags = from(value),
+        "wanted_tags" => task.wanted_tags = from(value),
+        "wanted_optional" => task.wanted_optional = from(value),
+        "needed_group" => task.needed_group = from(value),
+        "required_child_tags" => task.required_child_tags = from(value),
+        "points" => task.points = from(value),
+        "time_estimate_ms" => task.time_estimate_ms = from(value),
+        "blocked_reason" => task.blocked_reason = from(value),
+        "blocked_note" => task.blocked_note = from(value),
+        _ => {
+            if let Some(name) = field.strip_prefix(CUSTOM_FIELD_PREFIX) {
+                if value.is_null() {
+                    fields.remove(name);
+                } else {
+                    fields.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Rewind a task (and its custom fields) to `at` using its timeline.
+fn rewind(
+    task: &mut Task,
+    fields: &mut Map<String, Value>,
+    timeline: &Timeline,
+    at: i64,
+    states_config: &StatesConfig,
+) {
+    for change in &timeline.later_changes {
+        apply_value(task, fields, &change.field, &change.old_value);
+    }
+
+    if let Some((status, worker, since)) = &timeline.status {
+        task.status = status.clone();
+        // Timed states are owned by the agent that entered them; otherwise only
+        // an assignment recorded since the transition keeps an owner
+        if states_config.is_timed_state(status) {
+            task.worker_id = worker.clone();
+            task.claimed_at = Some(*since);
+        } else {
+            task.worker_id = timeline
+                .owner_change
+                .as_ref()
+                .filter(|(_, ts)| ts >= since)
+                .and_then(|(owner, _)| owner.clone());
+            task.claimed_at = None;
+        }
+        task.time_actual_ms = Some(timeline.time_in_timed_ms).filter(|ms| *ms > 0);
+    }
+    if let Some(phase) = &timeline.phase {
+        task.phase = Some(phase.clone());
+    }
+
+    task.started_at = task.started_at.filter(|ts| *ts <= at);
+    task.completed_at = task.completed_at.filter(|ts| *ts <= at);
+    task.current_thought = None;
+    if task.updated_at > at {
+        task.updated_at = timeline
+            .last_event
+            .unwrap_or(task.created_at)
+            .max(task.created_at);
+    }
+}
+
+impl Database {
+    /// A task as it stood at `at` (epoch ms), with its custom fields. None if the
+    /// task did not exist then (created later, or already deleted).
+    pub fn get_task_as_of(
+        &self,
+        task_id: &str,
+        at: i64,
+        states_config: &StatesConfig,
+    ) -> Result<Option<(Task, Map<String, Value>)>> {
+        Ok(self
+            .rewind_tasks(at, Some(task_id), states_config)?
+            .into_iter()
+            .next())
+    }
+
+    /// All tasks that existed at `at` (epoch ms), as they stood then, ordered by ID.
+    pub fn get_tasks_as_of(
+        &self,
+        at: i64,
+        states_config: &StatesConfig,
+    ) -> Result<Vec<(Task, Map<String, Value>)>> {
+        self.rewind_tasks(at, None, states_config)
+    }
+
+    fn rewind_tasks(
+        &self,
+        at: i64,
+        task_id: Option<&str>,
+        states_config: &StatesConfig,
+    ) -> Result<Vec<(Task, Map<String, Value>)>> {
+        let (tasks, timelines) = self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT * FROM tasks
+                 WHERE created_at <= ?1 AND (deleted_at IS NULL OR deleted_at > ?1)
+                   AND (?2 IS NULL OR id = ?2)
+                 ORDER BY id",
+            )?;
+            let tasks: Vec<Task> = stmt
+                .query_map(params![at, task_id], parse_task_row)?
+                .collect::<rusqlite::Result<_>>()?;
+
+            let mut timelines: HashMap<String, Timeline> = HashMap::new();
+
+            let mut stmt = conn.prepare(
+                "SELECT * FROM task_changes WHERE (?1 IS NULL OR task_id = ?1) ORDER BY id DESC",
+            )?;
+            let changes = stmt.query_map(params![task_id], parse_change_row)?;
+            for change in changes {
+                let change = change?;
+                let timeline = timelines.entry(change.task_id.clone()).or_default();
+                if change.timestamp > at {
+                    timeline.later_changes.push(change);
+                    continue;
+                }
+                // Newest first, so the first change seen at or before `at` is the latest
+                timeline.last_event.get_or_insert(change.timestamp);
+                if change.field == "worker_id" && timeline.owner_change.is_none() {
+                    let owner = change.new_value.as_str().map(String::from);
+                    timeline.owner_change = Some((owner, change.timestamp));
+                }
+            }
+
+            let mut stmt = conn.prepare(
+                "SELECT task_id, worker_id, status, phase, timestamp, end_timestamp
+                 FROM task_sequence
+                 WHERE timestamp <= ?1 AND (?2 IS NULL OR task_id = ?2)
+                 ORDER BY id",
+            )?;
+            let mut rows = stmt.query(params![at, task_id])?;
+            while let Some(row) = rows.next()? {
+                let id: String = row.get(0)?;
+                let worker: Option<String> = row.get(1)?;
+                let status: Option<String> = row.get(2)?;
+                let phase: Option<String> = row.get(3)?;
+                let start: i64 = row.get(4)?;
+                let end: Option<i64> = row.get(5)?;
+
+                let timeline = timelines.entry(id).or_default();
+                if let Some(status) = status {
+                    if states_config.is_timed_state(&status) {
+                        timeline.time_in_timed_ms += end.unwrap_or(at).min(at) - start;
+                    }
+                    timeline.status = Some((status, worker, start));
+                }
+                if phase.is_some() {
+                    timeline.phase = phase;
+                }
+                timeline.last_event = timeline.last_event.max(Some(start));
+            }
+
+            Ok((tasks, timelines))
+        })?;
+
+        let ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+        let mut fields_by_task = self.get_task_fields_batch(&ids)?;
+        let empty = Timeline::default();
+
+        Ok(tasks
+            .into_iter()
+            .map(|mut task| {
+                let mut fields = fields_by_task.remove(&task.id).unwrap_or_default();
+                let timeline = timelines.get(&task.id).unwrap_or(&empty);
+                rewind(&mut task, &mut fields, timeline, at, states_config);
+                (task, fields)
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,268 @@
+//! Soft-deleted tasks: listing, restore, and permanent purge.
+
+use super::{Database, now_ms};
+use crate::error::{ErrorCode, ToolError};
+use crate::types::DeletedTask;
+use anyhow::Result;
+use rusqlite::{OptionalExtension, params};
+use std::collections::BTreeMap;
+
+/// Tables holding rows of a task, as (table, task ID column) pairs.
+/// All cascade when the task row is deleted.
+const TASK_ROW_TABLES: &[(&str, &str)] = &[
+    ("dependencies", "from_task_id"),
+    ("attachments", "task_id"),
+    ("attachment_uploads", "task_id"),
+    ("task_tags", "task_id"),
+    ("task_needed_tags", "task_id"),
+    ("task_wanted_tags", "task_id"),
+    ("task_fields", "task_id"),
+    ("task_sequence", "task_id"),
+    ("task_changes", "task_id"),
+    ("task_thoughts", "task_id"),
+    ("task_aliases", "task_id"),
+    ("task_waiters", "task_id"),
+    ("task_reservations", "task_id"),
+    ("deadline_reminders", "task_id"),
+    ("thought_log", "task_id"),
+];
+
+/// Outcome of purging soft-deleted tasks (or what a dry run would remove).
+#[derive(Debug, Clone, Default)]
+pub struct PurgeResult {
+    /// Purged task IDs, oldest deletion first.
+    pub task_ids: Vec<String>,
+    /// Rows removed per table, including `tasks`. Tables with none are omitted.
+    pub rows: BTreeMap<String, usize>,
+    /// Paths of removed non-blob attachment files, for the caller to delete.
+    pub file_paths: Vec<String>,
+}
+
+/// Outcome of restoring a soft-deleted task.
+#[derive(Debug, Clone, Default)]
+pub struct RestoreResult {
+    /// Restored task IDs: the task, then any restored descendants.
+    pub task_ids: Vec<String>,
+    /// Dependency edges between restored tasks and live tasks, now active again.
+    pub dependencies: usize,
+    /// Deleted parent the task was detached from, if any.
+    pub detached_from: Option<String>,
+}
+
+impl Database {
+    /// List soft-deleted tasks, most recently deleted first.
+    pub fn list_deleted_tasks(
+        &self,
+        deleted_by: Option<&str>,
+        limit: i32,
+        offset: i32,
+    ) -> Result<Vec<DeletedTask>> {
+        let now = now_ms();
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT t.id, t.title, t.status, p.id, p.deleted_at IS NOT NULL,
+                        t.deleted_at, t.deleted_by, t.deleted_reason
+                 FROM tasks t
+                 LEFT JOIN dependencies d ON d.to_task_id = t.id AND d.dep_type = 'contains'
+                 LEFT JOIN tasks p ON p.id = d.from_task_id
+                 WHERE t.deleted_at IS NOT NULL AND (?1 IS NULL OR t.deleted_by = ?1)
+                 ORDER BY t.deleted_at DESC, t.id
+                 LIMIT ?2 OFFSET ?3",
+            )?;
+            let tasks = stmt
+                .query_map(params![deleted_by, limit, offset], |row| {
+                    let deleted_at: i64 = row.get(5)?;
+                    Ok(DeletedTask {
+                        id: row.get(0)?,
+                        title: row.get(1)?,
+                        status: row.get(2)?,
+                        parent: row.get(3)?,
+                        parent_deleted: row.get::<_, Option<bool>>(4)?.unwrap_or(false),
+                        deleted_at,
+                        deleted_by: row.get(6)?,
+                        deleted_reason: row.get(7)?,
+                        age_ms: now - deleted_at,
+                    })
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(tasks)
+        })
+    }
+
+    /// Restore a soft-deleted task, and with `cascade` its soft-deleted
+    /// descendants. Dependency edges survive soft deletion, so they become
+    /// active again with the task.
+    ///
+    /// A task whose parent is still deleted cannot be restored on its own:
+    /// restore the parent first, or pass `detach` to drop the `contains` edge
+    /// and restore the task as a root.
+    pub fn restore_task(
+        &self,
+        task_id: &str,
+        cascade: bool,
+        detach: bool,
+    ) -> Result<RestoreResult> {
+        let now = now_ms();
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            let deleted_at: Option<i64> = tx
+                .query_row(
+                    "SELECT deleted_at FROM tasks WHERE id = ?1",
+                    params![task_id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or_else(|| ToolError::task_not_found(task_id))?;
+            if deleted_at.is_none() {
+                return Err(ToolError::new(
+                    ErrorCode::InvalidState,
+                    format!("Task '{}' is not deleted", task_id),
+                )
+                .into());
+            }
+
+            let mut result = RestoreResult::default();
+            let deleted_parent: Option<String> = tx
+                .query_row(
+                    "SELECT p.id FROM dependencies d JOIN tasks p ON p.id = d.from_task_id
+                     WHERE d.to_task_id = ?1 AND d.dep_type = 'contains'
+                       AND p.deleted_at IS NOT NULL",
+                    params![task_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(parent) = deleted_parent {
+                if !detach {
+                    return Err(ToolError::new(
+                        ErrorCode::InvalidState,
+                        format!(
+                            "Parent task '{}' is deleted. Restore it first, or use detach=true to restore '{}' as a root task.",
+                            parent, task_id
+                        ),
+                    )
+                    .into());
+                }
+                tx.execute(
+                    "DELETE FROM dependencies
+                     WHERE from_task_id = ?1 AND to_task_id = ?2 AND dep_type = 'contains'",
+                    params![parent, task_id],
+                )?;
+                result.detached_from = Some(parent);
+            }
+
+            // Descend only through deleted children; live ones were never removed
+            let sql = if cascade {
+                "WITH RECURSIVE restored(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT d.to_task_id FROM dependencies d
+                    JOIN restored r ON d.from_task_id = r.id
+                    JOIN tasks c ON c.id = d.to_task_id
+                    WHERE d.dep_type = 'contains' AND c.deleted_at IS NOT NULL
+                 )
+                 SELECT id FROM restored"
+            } else {
+                "SELECT ?1"
+            };
+            result.task_ids = {
+                let mut stmt = tx.prepare(sql)?;
+                stmt.query_map(params![task_id], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?
+            };
+            let ids = serde_json::to_string(&result.task_ids)?;
+
+            tx.execute(
+                "UPDATE tasks SET deleted_at = NULL, deleted_by = NULL, deleted_reason = NULL,
+                        updated_at = ?1
+                 WHERE id IN (SELECT value FROM json_each(?2))",
+                params![now, ids],
+            )?;
+            result.dependencies = tx.query_row(
+                "SELECT COUNT(*) FROM dependencies d
+                 JOIN tasks f ON f.id = d.from_task_id
+                 JOIN tasks t ON t.id = d.to_task_id
+                 WHERE (d.from_task_id IN (SELECT value FROM json_each(?1))
+                        OR d.to_task_id IN (SELECT value FROM json_each(?1)))
+                   AND f.deleted_at IS NULL AND t.deleted_at IS NULL",
+                params![ids],
+                |row| row.get(0),
+            )?;
+
+            tx.commit()?;
+            Ok(result)
+        })
+    }
+
+    /// Permanently remove tasks soft-deleted at or before `deleted_before`
+    /// (epoch ms), with their dependencies, attachments, tags, fields, and
+    /// history. File marks naming a purged task keep the mark and lose the task.
+    /// With `dry_run`, nothing changes and the result describes what would go.
+    pub fn purge_deleted(&self, deleted_before: i64, dry_run: bool) -> Result<PurgeResult> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            let task_ids: Vec<String> = {
+                let mut stmt = tx.prepare(
+                    "SELECT id FROM tasks
+                     WHERE deleted_at IS NOT NULL AND deleted_at <= ?1
+                     ORDER BY deleted_at, id",
+                )?;
+                stmt.query_map(params![deleted_before], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?
+            };
+            let mut result = PurgeResult::default();
+            if task_ids.is_empty() {
+                return Ok(result);
+            }
+            let ids = serde_json::to_string(&task_ids)?;
+
+            for (table, column) in TASK_ROW_TABLES {
+                // Edges from surviving tasks into the purged set go too
+                let extra = if *table == "dependencies" {
+                    " OR to_task_id IN (SELECT value FROM json_each(?1))"
+                } else {
+                    ""
+                };
+                let count: usize = tx.query_row(
+                    &format!(
+                        "SELECT COUNT(*) FROM {} WHERE {} IN (SELECT value FROM json_each(?1)){}",
+                        table, column, extra
+                    ),
+                    params![ids],
+                    |row| row.get(0),
+                )?;
+                if count > 0 {
+                    result.rows.insert(table.to_string(), count);
+                }
+            }
+            result.rows.insert("tasks".to_string(), task_ids.len());
+
+            result.file_paths = {
+                let mut stmt = tx.prepare(
+                    "SELECT file_path FROM attachments
+                     WHERE task_id IN (SELECT value FROM json_each(?1))
+                       AND file_path IS NOT NULL AND content_hash IS NULL",
+                )?;
+                stmt.query_map(params![ids], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?
+            };
+            result.task_ids = task_ids;
+
+            if !dry_run {
+                // file_locks.task_id has no ON DELETE action
+                tx.execute(
+                    "UPDATE file_locks SET task_id = NULL
+                     WHERE task_id IN (SELECT value FROM json_each(?1))",
+                    params![ids],
+                )?;
+                tx.execute(
+                    "DELETE FROM tasks WHERE id IN (SELECT value FROM json_each(?1))",
+                    params![ids],
+                )?;
+                tx.commit()?;
+            }
+            Ok(result)
+        })
+    }
+}
@@ -0,0 +1,148 @@
+//! Visibility scopes: limiting what restricted agents can see of the task graph.
+
+use super::Database;
+use crate::config::VisibilityConfig;
+use crate::types::{Task, TaskScope, TaskTree};
+use anyhow::Result;
+use rusqlite::ToSql;
+use std::collections::HashSet;
+
+/// SQL condition keeping only tasks `t` inside a scope. Binds the scope's tags
+/// and subtree roots as JSON arrays at `?{first}` and `?{first + 1}`.
+fn scope_condition(first: usize) -> String {
+    format!(
+        "(EXISTS (SELECT 1 FROM task_tags vt
+                  WHERE vt.task_id = t.id AND vt.tag IN (SELECT value FROM json_each(?{0})))
+          OR t.id IN (SELECT value FROM json_each(?{1}))
+          OR t.id IN (
+              SELECT descendant_id FROM task_closure
+              WHERE ancestor_id IN (SELECT value FROM json_each(?{1}))
+          ))",
+        first,
+        first + 1
+    )
+}
+
+/// `AND <scope condition>` for a query whose parameters so far are exactly
+/// `params_vec`, binding the scope after them. Empty without a scope.
+pub(crate) fn scope_clause(
+    params_vec: &mut Vec<Box<dyn ToSql>>,
+    scope: Option<&TaskScope>,
+) -> Result<String> {
+    let Some(scope) = scope else {
+        return Ok(String::new());
+    };
+    let condition = scope_condition(params_vec.len() + 1);
+    params_vec.push(Box::new(serde_json::to_string(&scope.tags)?));
+    params_vec.push(Box::new(serde_json::to_string(&scope.subtrees)?));
+    Ok(format!(" AND {}", condition))
+}
+
+impl Database {
+    /// Visibility scope of an agent under the configured rules, or None when
+    /// the agent is unrestricted.
+    pub fn task_scope(
+        &self,
+        agent_id: &str,
+        visibility: &VisibilityConfig,
+    ) -> Result<Option<TaskScope>> {
+        if visibility.rules.is_empty() {
+            return Ok(None);
+        }
+        let groups = self.get_worker_groups(agent_id)?;
+        let agent_tags = self.get_agent_tags(agent_id)?;
+        Ok(visibility.scope_for(agent_id, &groups, &agent_tags))
+    }
+
+    /// Visibility scope of a caller. An identified agent gets its `task_scope`.
+    /// While any rule is configured, a caller that did not say who it is gets
+    /// an empty scope, which hides every task.
+    pub fn caller_scope(
+        &self,
+        caller: Option<&str>,
+        visibility: &VisibilityConfig,
+    ) -> Result<Option<TaskScope>> {
+        match caller {
+            Some(agent_id) => self.task_scope(agent_id, visibility),
+            None if visibility.rules.is_empty() => Ok(None),
+            None => Ok(Some(TaskScope::default())),
+        }
+    }
+
+    /// Whether a task is inside a scope.
+    pub fn is_visible(&self, task_id: &str, scope: Option<&TaskScope>) -> Result<bool> {
+        let mut ids = vec![task_id.to_string()];
+        self.retain_visible_ids(&mut ids, scope)?;
+        Ok(!ids.is_empty())
+    }
+
+    /// Keep only the tasks inside a scope, evaluated in the database.
+    /// For task lists not produced by a single query (e.g. descendant walks).
+    pub fn retain_visible(&self, tasks: &mut Vec<Task>, scope: Option<&TaskScope>) -> Result<()> {
+        let mut ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+        self.retain_visible_ids(&mut ids, scope)?;
+        let visible: HashSet<String> = ids.into_iter().collect();
+        tasks.retain(|t| visible.contains(&t.id));
+        Ok(())
+    }
+
+    /// Prune a task tree to a scope. A hidden task is dropped with everything
+    /// under it, so the tree never shows a visible task under a hidden one.
+    pub fn retain_visible_tree(
+        &self,
+        tree: &mut TaskTree,
+        scope: Option<&TaskScope>,
+    ) -> Result<()> {
+        if scope.is_none() {
+            return Ok(());
+        }
+        fn collect(tree: &TaskTree, ids: &mut Vec<String>) {
+            for child in &tree.children {
+                ids.push(child.task.id.clone());
+                collect(child, ids);
+            }
+        }
+        fn prune(tree: &mut TaskTree, visible: &HashSet<String>) {
+            tree.children.retain(|c| visible.contains(&c.task.id));
+            for child in &mut tree.children {
+                prune(child, visible);
+            }
+        }
+        let mut ids = Vec::new();
+        collect(tree, &mut ids);
+        self.retain_visible_ids(&mut ids, scope)?;
+        prune(tree, &ids.into_iter().collect());
+        Ok(())
+    }
+
+    /// Keep only the task IDs inside a scope.
+    pub fn retain_visible_ids(
+        &self,
+        ids: &mut Vec<String>,
+        scope: Option<&TaskScope>,
+    ) -> Result<()> {
+        if scope.is_none() || ids.is_empty() {
+            return Ok(());
+        }
+        let ids_json = serde_json::to_string(ids)?;
+
+        let visible: HashSet<String> = self.with_read_conn(|conn| {
+            let mut params_vec: Vec<Box<dyn ToSql>> = vec![Box::new(ids_json)];
+            let sql = format!(
+                "SELECT t.id FROM tasks t WHERE t.id IN (SELECT value FROM json_each(?1)){}",
+                scope_clause(&mut params_vec, scope)?
+            );
+
+            let params_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| b.as_ref()).collect();
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let visible = stmt
+                .query_map(params_refs.as_slice(), |row| row.get(0))?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(visible)
+        })?;
+
+        ids.retain(|id| visible.contains(id));
+        Ok(())
+    }
+}
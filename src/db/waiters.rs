@@ -0,0 +1,89 @@
+//! Wait registrations for agents interested in tasks becoming ready.
+
+use super::{Database, now_ms};
+use anyhow::Result;
+use rusqlite::{Connection, params};
+
+/// Mark waiters of newly unblocked tasks as notified.
+/// Called inside the transaction that completed the last blocker.
+pub(crate) fn notify_waiters_in_tx(conn: &Connection, task_ids: &[String]) -> Result<()> {
+    if task_ids.is_empty() {
+        return Ok(());
+    }
+    conn.execute(
+        "UPDATE task_waiters SET notified_at = ?1
+         WHERE notified_at IS NULL AND task_id IN (SELECT value FROM json_each(?2))",
+        params![now_ms(), serde_json::to_string(task_ids)?],
+    )?;
+    Ok(())
+}
+
+impl Database {
+    /// Register a worker's interest in tasks. Existing registrations are reset to waiting.
+    pub fn add_waiters(&self, worker_id: &str, task_ids: &[String]) -> Result<()> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let now = now_ms();
+            for task_id in task_ids {
+                tx.execute(
+                    "INSERT INTO task_waiters (task_id, worker_id, registered_at) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(task_id, worker_id) DO UPDATE
+                     SET registered_at = excluded.registered_at, notified_at = NULL",
+                    params![task_id, worker_id, now],
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Remove a worker's registrations for the given tasks.
+    pub fn remove_waiters(&self, worker_id: &str, task_ids: &[String]) -> Result<()> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "DELETE FROM task_waiters
+                 WHERE worker_id = ?1 AND task_id IN (SELECT value FROM json_each(?2))",
+                params![worker_id, serde_json::to_string(task_ids)?],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Get the tasks a worker is still waiting on (not yet notified).
+    pub fn get_waiting_tasks(&self, worker_id: &str) -> Result<Vec<String>> {
+        self.with_read_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT task_id FROM task_waiters
+                 WHERE worker_id = ?1 AND notified_at IS NULL
+                 ORDER BY registered_at, task_id",
+            )?;
+            let ids = stmt
+                .query_map(params![worker_id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(ids)
+        })
+    }
+
+    /// Take pending unblock notifications for a worker.
+    /// Returns the task IDs that became ready and removes their registrations.
+    pub fn take_unblock_notifications(&self, worker_id: &str) -> Result<Vec<String>> {
+        self.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            let ids = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT task_id FROM task_waiters
+                     WHERE worker_id = ?1 AND notified_at IS NOT NULL
+                     ORDER BY notified_at, task_id",
+                )?;
+                stmt.query_map(params![worker_id], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?
+            };
+            tx.execute(
+                "DELETE FROM task_waiters WHERE worker_id = ?1 AND notified_at IS NOT NULL",
+                params![worker_id],
+            )?;
+            tx.commit()?;
+            Ok(ids)
+        })
+    }
+}
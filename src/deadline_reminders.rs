@@ -0,0 +1,65 @@
+//! Deadline reminder scheduler.
+//!
+//! When `deadline_reminders.enabled` is set, the server sweeps tasks with a
+//! `due_at` custom field every `interval_secs`. Reminders that have come due
+//! for claimed tasks are POSTed to the configured webhooks, and the server
+//! notifies subscribers of `query://deadlines` so agents get a push signal
+//! instead of polling. The sweep also keeps the overdue tag in step with
+//! deadlines.
+
+use crate::config::{DeadlineRemindersConfig, StatesConfig};
+use crate::db::Database;
+use crate::db::deadlines::DeadlineSweep;
+use crate::db::now_ms;
+use crate::types::DeadlineReminder;
+use anyhow::Result;
+use serde_json::json;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Run one sweep and deliver its reminders to the webhooks.
+pub fn run_sweep(
+    db: &Database,
+    config: &DeadlineRemindersConfig,
+    states_config: &StatesConfig,
+) -> Result<DeadlineSweep> {
+    let sweep = db.sweep_deadlines(config, states_config, now_ms())?;
+    if !sweep.reminders.is_empty() {
+        info!(count = sweep.reminders.len(), "Deadline reminders due");
+        post_webhooks(config, &sweep.reminders);
+    }
+    Ok(sweep)
+}
+
+/// Webhook body for a reminder.
+pub fn webhook_payload(reminder: &DeadlineReminder) -> serde_json::Value {
+    json!({
+        "event": "deadline_reminder",
+        "task_id": reminder.task_id,
+        "title": reminder.title,
+        "worker_id": reminder.worker_id,
+        "due_at": reminder.due_at,
+        "offset_ms": reminder.offset_ms,
+        "overdue": reminder.overdue,
+    })
+}
+
+/// POST each reminder to every webhook. Delivery is at most once: failures
+/// are logged and not retried, since the reminder is already recorded as sent.
+fn post_webhooks(config: &DeadlineRemindersConfig, reminders: &[DeadlineReminder]) {
+    if config.webhooks.is_empty() {
+        return;
+    }
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_millis(config.webhook_timeout_ms))
+        .build();
+    for reminder in reminders {
+        let payload = webhook_payload(reminder).to_string();
+        for url in &config.webhooks {
+            let request = agent.post(url).set("Content-Type", "application/json");
+            if let Err(e) = request.send_string(&payload) {
+                warn!(url = %url, task = %reminder.task_id, "Deadline webhook failed: {}", e);
+            }
+        }
+    }
+}
@@ -4,38 +4,164 @@ use serde::Serialize;
 use std::fmt;
 
 /// Error codes for programmatic error handling.
+///
+/// Every code has a default recovery hint ([`ErrorCode::suggestion`]) and,
+/// for transient conflicts, a retry delay ([`ErrorCode::retry_after_ms`]).
+/// The full list is documented in `docs/ERRORS.md`.
 #[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ErrorCode {
     // Validation errors (4xx-like)
+    /// A required argument was not given.
     MissingRequiredField,
+    /// An argument has the wrong type or an unaccepted value.
     InvalidFieldValue,
+    /// Unknown state, or a transition the state machine does not allow.
     InvalidState,
+    /// A file path is malformed or escapes the sandbox root.
     InvalidPath,
+    /// A path prefix is unknown or not lowercase.
     InvalidPrefix,
+    /// A task ID prefix matches more than one task.
+    AmbiguousId,
 
     // Not found errors
+    /// The worker ID is not registered.
     AgentNotFound,
+    /// No task has this ID (or alias, or prefix).
     TaskNotFound,
+    /// A file to attach or read does not exist.
     FileNotFound,
+    /// The task has no attachment of this type or sequence.
     AttachmentNotFound,
 
     // Conflict errors
+    /// The task is claimed by another agent.
     AlreadyClaimed,
+    /// An entity with this ID or name already exists.
     AlreadyExists,
+    /// An exclusive lock is held by another agent.
     LockConflict,
+    /// Files are marked by another agent.
+    MarkConflict,
+    /// A dependency would create a cycle.
     DependencyCycle,
+    /// The agent lacks tags the task requires.
     TagMismatch,
+    /// The agent does not own the task.
     NotOwner,
+    /// Start-blocking dependencies are not satisfied.
     DependencyNotSatisfied,
+    /// A task it may not run alongside is in a working state.
+    ExclusiveConflict,
+    /// Gates on leaving the current state are not satisfied.
     GatesNotSatisfied,
+    /// Attachment storage quota would be exceeded.
+    QuotaExceeded,
+    /// The task's status or owner differs from an `if_status` / `if_owner` guard.
+    PreconditionFailed,
+    /// Visibility rules keep this read from the caller.
+    NotVisible,
 
     // Internal errors
+    /// SQLite reported an error (often a transient busy database).
     DatabaseError,
+    /// Unexpected server-side failure.
     InternalError,
+    /// No tool has this name.
     UnknownTool,
 }
 
+impl ErrorCode {
+    /// Default recovery hint for this code. Constructors that know more
+    /// (e.g. which task blocks) replace it with a more specific one.
+    pub fn suggestion(self) -> &'static str {
+        match self {
+            ErrorCode::MissingRequiredField => {
+                "Add the argument named in `field` and retry the call"
+            }
+            ErrorCode::InvalidFieldValue => {
+                "Fix the argument named in `field` (the message says what is accepted) and retry"
+            }
+            ErrorCode::InvalidState => {
+                "Use a state from config://states; each state lists the transitions it allows"
+            }
+            ErrorCode::InvalidPath => {
+                "Use a path inside the workspace, either relative or with a configured prefix"
+            }
+            ErrorCode::InvalidPrefix => {
+                "Use a lowercase path prefix defined under `paths` in the configuration"
+            }
+            ErrorCode::AmbiguousId => "Use a longer prefix or the full task ID",
+            ErrorCode::AgentNotFound => {
+                "Call connect to register, or connect(worker_id=<id>, resume=true) to restore an earlier session"
+            }
+            ErrorCode::TaskNotFound => {
+                "Check the ID with list_tasks or search; soft-deleted tasks are listed by list_deleted"
+            }
+            ErrorCode::FileNotFound => {
+                "Check the path; relative paths resolve against the server's working directory"
+            }
+            ErrorCode::AttachmentNotFound => {
+                "List the task's attachments with attachments(task=<id>)"
+            }
+            ErrorCode::AlreadyClaimed => "Call list_tasks(ready=true) and claim another task",
+            ErrorCode::AlreadyExists => "Use a different ID or name, or update the existing entry",
+            ErrorCode::LockConflict => {
+                "Wait for the lock to be released, or coordinate with the holding agent"
+            }
+            ErrorCode::MarkConflict => {
+                "Coordinate with the holding agent, or retry with wait=true to be told via mark_updates when the marks are released"
+            }
+            ErrorCode::DependencyCycle => {
+                "Inspect the chain with scan(task=<id>, direction=\"before\"), then link the other way or drop an edge"
+            }
+            ErrorCode::TagMismatch => {
+                "Call list_tasks(ready=true) for tasks matching your tags, or reconnect with the required tags"
+            }
+            ErrorCode::NotOwner => {
+                "Claim the task first, or coordinate with its owner (get(task=<id>) shows who)"
+            }
+            ErrorCode::DependencyNotSatisfied => {
+                "Call wait_for(task=<id>) to be told when the task is ready, or list_tasks(ready=true) to find unblocked work"
+            }
+            ErrorCode::ExclusiveConflict => {
+                "Wait for the conflicting tasks to leave their working state, or call list_tasks(ready=true) to find other work"
+            }
+            ErrorCode::GatesNotSatisfied => {
+                "Satisfy the gates check_gates lists (attach an artifact, meet the condition, or make the command pass), then retry the transition"
+            }
+            ErrorCode::QuotaExceeded => {
+                "Free space with detach or prune_attachments, or raise attachments.max_task_bytes / max_project_bytes"
+            }
+            ErrorCode::PreconditionFailed => {
+                "Re-read the task with get(task=<id>) and decide again from its current status and owner"
+            }
+            ErrorCode::NotVisible => {
+                "Pass worker_id; agents restricted by visibility rules read tasks through list_tasks, search, get, and query://visible/{agent_id}/{view}"
+            }
+            ErrorCode::DatabaseError => {
+                "Retry after retry_after_ms; if it persists, the database may need repair (see the repair skill)"
+            }
+            ErrorCode::InternalError => {
+                "Check the arguments against the tool's schema and retry once; get_logs shows recent failures"
+            }
+            ErrorCode::UnknownTool => "Check the tool name against the server's tool list",
+        }
+    }
+
+    /// How long to wait before retrying, for errors that usually clear up on
+    /// their own. None means retrying unchanged will not help.
+    pub fn retry_after_ms(self) -> Option<u64> {
+        match self {
+            ErrorCode::DatabaseError => Some(1_000),
+            ErrorCode::LockConflict | ErrorCode::MarkConflict => Some(5_000),
+            ErrorCode::ExclusiveConflict => Some(30_000),
+            _ => None,
+        }
+    }
+}
+
 /// Warning codes for non-fatal issues.
 #[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -138,6 +264,19 @@ impl fmt::Display for ToolWarning {
     }
 }
 
+/// An advisory file mark held by another agent that overlaps a requested mark.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarkConflict {
+    /// Path the caller asked to mark.
+    pub file: String,
+    /// Path of the existing mark (same file, or an enclosing/enclosed directory).
+    pub marked_path: String,
+    pub held_by: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    pub mark_age_ms: i64,
+}
+
 /// Structured error for tool responses.
 #[derive(Debug, Serialize)]
 pub struct ToolError {
@@ -151,6 +290,13 @@ pub struct ToolError {
     pub blocked_by: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub suggestion: Option<String>,
+    /// Suggested delay before retrying, for transient conflicts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidates: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflicts: Option<Vec<MarkConflict>>,
 }
 
 impl ToolError {
@@ -161,7 +307,10 @@ impl ToolError {
             field: None,
             details: None,
             blocked_by: None,
-            suggestion: None,
+            suggestion: Some(code.suggestion().to_string()),
+            retry_after_ms: code.retry_after_ms(),
+            candidates: None,
+            conflicts: None,
         }
     }
 
@@ -185,6 +334,21 @@ impl ToolError {
         self
     }
 
+    pub fn with_retry_after_ms(mut self, retry_after_ms: u64) -> Self {
+        self.retry_after_ms = Some(retry_after_ms);
+        self
+    }
+
+    pub fn with_candidates(mut self, candidates: Vec<String>) -> Self {
+        self.candidates = Some(candidates);
+        self
+    }
+
+    pub fn with_conflicts(mut self, conflicts: Vec<MarkConflict>) -> Self {
+        self.conflicts = Some(conflicts);
+        self
+    }
+
     // Convenience constructors
 
     pub fn missing_field(field: &str) -> Self {
@@ -223,9 +387,6 @@ impl ToolError {
         )
         .with_field("file")
         .with_details(format!("held_by: {}", held_by))
-        .with_suggestion(
-            "Wait for the lock to be released, or coordinate with the holding agent".to_string(),
-        )
     }
 
     pub fn already_claimed(task_id: &str, owner: &str) -> Self {
@@ -269,40 +430,95 @@ impl ToolError {
         )
         .with_blocked_by(blockers.to_vec())
         .with_suggestion(
-            "Wait for blocking tasks to complete. Meanwhile: (1) call list_tasks(ready=true) to find unblocked work, (2) use scan(task=<id>, direction=\"before\") to inspect the dependency chain, (3) call thinking() regularly to maintain heartbeat while waiting."
+            "Wait for blocking tasks to complete. Meanwhile: (1) call wait_for(task=<id>) to be told when it becomes ready, or list_tasks(ready=true) to find unblocked work, (2) use scan(task=<id>, direction=\"before\") to inspect the dependency chain, (3) call thinking() regularly to maintain heartbeat while waiting."
                 .to_string(),
         )
     }
 
-    pub fn gates_not_satisfied(status: &str, gates: &[String]) -> Self {
-        let gate_list = gates.join(", ");
-        let how_to_fix: Vec<String> = gates
+    pub fn mark_conflict(conflicts: Vec<MarkConflict>) -> Self {
+        let holders: Vec<String> = conflicts
             .iter()
-            .map(|g| {
-                // Extract the gate type from "gate_type (description)" format
-                let gate_type = g.split(" (").next().unwrap_or(g);
-                format!(
-                    "  - Satisfy '{}': attach(task=<id>, type=\"{}\", content=\"...\")",
-                    gate_type, gate_type
-                )
-            })
+            .map(|c| format!("'{}' (held by '{}')", c.marked_path, c.held_by))
             .collect();
+        Self::new(
+            ErrorCode::MarkConflict,
+            format!(
+                "Files are already marked by other agents: {}",
+                holders.join(", ")
+            ),
+        )
+        .with_field("file")
+        .with_conflicts(conflicts)
+    }
+
+    pub fn exclusive_conflict(conflicts: &[String]) -> Self {
+        Self::new(
+            ErrorCode::ExclusiveConflict,
+            format!(
+                "Task cannot run concurrently with: {}",
+                conflicts.join(", ")
+            ),
+        )
+        .with_blocked_by(conflicts.to_vec())
+    }
+
+    pub fn gates_not_satisfied(
+        status: &str,
+        gates: &[String],
+        how_to_fix: &[String],
+        suggestion: String,
+    ) -> Self {
         Self::new(
             ErrorCode::GatesNotSatisfied,
             format!(
                 "Cannot exit '{}': unsatisfied gates: {}",
-                status, gate_list
+                status,
+                gates.join(", ")
             ),
         )
         .with_details(format!(
-            "How to satisfy:\n{}\n\nOr use force=true with a reason to skip warn-level gates.",
+            "These are reject-level gates and cannot be skipped. Satisfy them:\n{}",
             how_to_fix.join("\n")
         ))
-        .with_suggestion(
-            "Attach the required artifacts, then retry the transition. For warn-level gates, you can use update(..., force=true, reason=\"...\") to proceed.".to_string(),
+        .with_suggestion(suggestion)
+    }
+
+    pub fn quota_exceeded(scope: &str, used: i64, incoming: i64, limit: i64) -> Self {
+        Self::new(
+            ErrorCode::QuotaExceeded,
+            format!(
+                "Attachment quota exceeded for {}: {} bytes used + {} bytes new > {} byte limit",
+                scope, used, incoming, limit
+            ),
+        )
+    }
+
+    pub fn precondition_failed(task_id: &str, field: &str, expected: &str, actual: &str) -> Self {
+        Self::new(
+            ErrorCode::PreconditionFailed,
+            format!(
+                "Precondition failed on task {}: {} expected {}, found {}",
+                task_id, field, expected, actual
+            ),
+        )
+        .with_field(field)
+    }
+
+    pub fn not_visible(what: &str) -> Self {
+        Self::new(
+            ErrorCode::NotVisible,
+            format!("{} is not available under the visibility rules", what),
         )
     }
 
+    pub fn ambiguous_id(prefix: &str, match_count: usize, candidates: Vec<String>) -> Self {
+        Self::new(
+            ErrorCode::AmbiguousId,
+            format!("Task ID prefix '{}' matches {} tasks", prefix, match_count),
+        )
+        .with_candidates(candidates)
+    }
+
     pub fn invalid_path(path: &str, reason: &str) -> Self {
         Self::new(
             ErrorCode::InvalidPath,
@@ -365,3 +581,38 @@ impl From<anyhow::Error> for ToolError {
 
 /// Result type for tool operations.
 pub type ToolResult<T> = std::result::Result<T, ToolError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_errors_carry_default_suggestion() {
+        let err = serde_json::to_value(ToolError::already_claimed("t1", "w2")).unwrap();
+        assert_eq!(err["code"], json!("ALREADY_CLAIMED"));
+        assert_eq!(
+            err["suggestion"],
+            json!("Call list_tasks(ready=true) and claim another task")
+        );
+        assert!(err.get("retry_after_ms").is_none());
+    }
+
+    #[test]
+    fn test_specific_suggestion_overrides_default() {
+        let err = ToolError::deps_not_satisfied(&["a".to_string()]);
+        assert_ne!(
+            err.suggestion.as_deref(),
+            Some(ErrorCode::DependencyNotSatisfied.suggestion())
+        );
+    }
+
+    #[test]
+    fn test_transient_conflicts_suggest_retry_delay() {
+        let err = serde_json::to_value(ToolError::lock_conflict("lock:db", "w2")).unwrap();
+        assert_eq!(err["retry_after_ms"], json!(5_000));
+
+        let err = ToolError::database("database is locked").with_retry_after_ms(250);
+        assert_eq!(err.retry_after_ms, Some(250));
+    }
+}
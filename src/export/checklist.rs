@@ -0,0 +1,489 @@
+//! Markdown checklists: import snapshots and GitHub task-list exports.
+//!
+//! Planning files like `PLAN.md` often keep work as task lists:
+//!
+//! ```text
+//! # Release 1.2
+//! ## Docs
+//! - [x] Changelog
+//! - [ ] Upgrade guide #docs
+//!   - [ ] Screenshots
+//!   - check the Windows steps
+//! ```
+//!
+//! Every checkbox item becomes a task, completed if checked. Headings and plain
+//! list items become the parents of the checkbox items under them, completed
+//! when everything below is. Anything without a checkbox item below it (notes,
+//! prose, a `## Background` section) goes into the description of the task
+//! above it, or is dropped at the top level. Items take the outline syntax of
+//! [`crate::outline`], so trailing `#tag` and `!priority` words apply.
+//!
+//! Task IDs in the snapshot are placeholders (`checklist-1`, ...) meant to be
+//! remapped to fresh IDs on import. Items ending in an ID comment
+//! (`<!-- id: gentle-turtle -->`), as written by [`render_checklist`], keep
+//! that ID instead, so [`skip_existing`] can leave the tasks a database
+//! already has alone and an exported list can be edited and imported back.
+
+use super::Snapshot;
+use crate::config::{PrioritiesConfig, StatesConfig};
+use crate::db::now_ms;
+use crate::outline::{OutlineItem, parse_markdown};
+use crate::types::{PRIORITY_DEFAULT, TaskTree};
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Value, json};
+use std::collections::HashSet;
+
+/// Prefix of the placeholder task IDs in a checklist snapshot.
+pub const PLACEHOLDER_PREFIX: &str = "checklist-";
+
+/// Key of the HTML comment carrying an item's task ID.
+const ID_COMMENT_KEY: &str = "id:";
+
+/// A kept checklist node: a task and its subtasks.
+struct Node {
+    item: OutlineItem,
+    /// Task ID from the item's ID comment.
+    id: Option<String>,
+    completed: bool,
+    children: Vec<Node>,
+}
+
+/// Whether an item or anything below it has a checkbox.
+fn has_checkbox(item: &OutlineItem) -> bool {
+    item.checked.is_some() || item.children.iter().any(has_checkbox)
+}
+
+/// Split a trailing `<!-- id: ... -->` comment off a title.
+fn split_task_id(title: &str) -> (&str, Option<&str>) {
+    let parsed = title.strip_suffix("-->").and_then(|rest| {
+        let (before, comment) = rest.rsplit_once("<!--")?;
+        let id = comment.trim().strip_prefix(ID_COMMENT_KEY)?.trim();
+        (!id.is_empty() && !id.contains(char::is_whitespace)).then_some((before.trim_end(), id))
+    });
+    match parsed {
+        Some((title, id)) => (title, Some(id)),
+        None => (title, None),
+    }
+}
+
+/// Markdown lines for an item kept only as a note, indented by depth.
+fn note_lines(item: &OutlineItem, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    let mut line = format!("{}- {}", indent, item.title);
+    for tag in &item.tags {
+        line.push_str(&format!(" #{}", tag));
+    }
+    if let Some(ref priority) = item.priority {
+        line.push_str(&format!(" !{}", priority));
+    }
+    lines.push(line);
+    if let Some(ref description) = item.description {
+        for text in description.lines() {
+            lines.push(format!("{}  {}", indent, text));
+        }
+    }
+    for child in &item.children {
+        note_lines(child, depth + 1, lines);
+    }
+}
+
+/// Keep an item that has a checkbox at or below it; fold the rest of its
+/// children into its description.
+fn prune(mut item: OutlineItem) -> Node {
+    let mut notes = Vec::new();
+    let mut children = Vec::new();
+    for child in std::mem::take(&mut item.children) {
+        if has_checkbox(&child) {
+            children.push(prune(child));
+        } else {
+            note_lines(&child, 0, &mut notes);
+        }
+    }
+    if !notes.is_empty() {
+        let notes = notes.join("\n");
+        item.description = Some(match item.description.take() {
+            Some(description) => format!("{}\n{}", description, notes),
+            None => notes,
+        });
+    }
+    let completed = match item.checked {
+        Some(checked) => checked,
+        None => children.iter().all(|c: &Node| c.completed),
+    };
+    let (title, id) = split_task_id(&item.title);
+    let (title, id) = (title.to_string(), id.map(String::from));
+    item.title = title;
+    Node {
+        item,
+        id,
+        completed,
+        children,
+    }
+}
+
+/// The state checked items are imported in: `completed` if defined, otherwise
+/// the first terminal state by name.
+fn completed_state(states: &StatesConfig) -> Result<&str> {
+    if states.definitions.contains_key("completed") {
+        return Ok("completed");
+    }
+    let mut terminal: Vec<&str> = states
+        .definitions
+        .iter()
+        .filter(|(_, def)| def.exits.is_empty())
+        .map(|(name, _)| name.as_str())
+        .collect();
+    terminal.sort();
+    terminal
+        .first()
+        .copied()
+        .ok_or_else(|| anyhow!("No terminal state to import checked items in"))
+}
+
+struct Builder<'a> {
+    initial: &'a str,
+    completed: &'a str,
+    priorities: &'a PrioritiesConfig,
+    now: i64,
+    snapshot: Snapshot,
+    count: usize,
+}
+
+impl Builder<'_> {
+    fn push(&mut self, table: &str, row: Value) {
+        self.snapshot
+            .tables
+            .entry(table.to_string())
+            .or_default()
+            .push(row);
+    }
+
+    /// Add a node and its subtree; returns the node's placeholder ID.
+    fn add(&mut self, node: &Node, parent: Option<&str>) -> Result<String> {
+        self.count += 1;
+        let id = match node.id {
+            Some(ref id) => id.clone(),
+            None => format!("{}{}", PLACEHOLDER_PREFIX, self.count),
+        };
+        let item = &node.item;
+        let priority = match item.priority {
+            Some(ref p) => self
+                .priorities
+                .parse(&json!(p))
+                .map_err(|e| anyhow!("'{}': {}", item.title, e))?,
+            None => PRIORITY_DEFAULT,
+        };
+        // One millisecond apart, so children keep their checklist order
+        let created_at = self.now + self.count as i64;
+        let status = if node.completed {
+            self.completed
+        } else {
+            self.initial
+        };
+
+        self.push(
+            "tasks",
+            json!({
+                "id": id,
+                "title": item.title,
+                "description": item.description,
+                "status": status,
+                "priority": priority.to_string(),
+                "needed_tags": "[]",
+                "wanted_tags": "[]",
+                "tags": serde_json::to_string(&item.tags)?,
+                "completed_at": node.completed.then_some(created_at),
+                "created_at": created_at,
+                "updated_at": created_at,
+            }),
+        );
+        for tag in &item.tags {
+            self.push("task_tags", json!({ "task_id": id, "tag": tag }));
+        }
+        if let Some(parent) = parent {
+            self.push(
+                "dependencies",
+                json!({ "from_task_id": parent, "to_task_id": id, "dep_type": "contains" }),
+            );
+        }
+        for child in &node.children {
+            self.add(child, Some(&id))?;
+        }
+        Ok(id)
+    }
+}
+
+/// Build an import snapshot from a markdown checklist.
+pub fn checklist_snapshot(
+    markdown: &str,
+    states: &StatesConfig,
+    priorities: &PrioritiesConfig,
+) -> Result<Snapshot> {
+    let roots: Vec<Node> = parse_markdown(markdown)?
+        .into_iter()
+        .filter(has_checkbox)
+        .map(prune)
+        .collect();
+    if roots.is_empty() {
+        bail!("No checklist items (- [ ] or - [x]) found");
+    }
+
+    let mut builder = Builder {
+        initial: &states.initial,
+        completed: completed_state(states)?,
+        priorities,
+        now: now_ms(),
+        snapshot: Snapshot::new(),
+        count: 0,
+    };
+    for root in &roots {
+        builder.add(root, None)?;
+    }
+    Ok(builder.snapshot)
+}
+
+/// Drop the tasks of a checklist snapshot that `exists` says are already in
+/// the database, with their tags and links to their parents; links from them
+/// to new subtasks are kept. Returns the dropped task IDs.
+///
+/// Only tasks named by an ID comment are checked, not placeholders.
+pub fn skip_existing(
+    snapshot: &mut Snapshot,
+    mut exists: impl FnMut(&str) -> Result<bool>,
+) -> Result<Vec<String>> {
+    let mut existing = Vec::new();
+    for row in snapshot.get_table("tasks").into_iter().flatten() {
+        if let Some(id) = row["id"].as_str()
+            && !id.starts_with(PLACEHOLDER_PREFIX)
+            && exists(id)?
+        {
+            existing.push(id.to_string());
+        }
+    }
+    let skipped: HashSet<&str> = existing.iter().map(String::as_str).collect();
+    let keep =
+        |row: &Value, field: &str| !row[field].as_str().is_some_and(|id| skipped.contains(id));
+
+    for (table, field) in [
+        ("tasks", "id"),
+        ("task_tags", "task_id"),
+        ("dependencies", "to_task_id"),
+    ] {
+        if let Some(rows) = snapshot.tables.get_mut(table) {
+            rows.retain(|row| keep(row, field));
+        }
+    }
+    Ok(existing)
+}
+
+/// Render a task subtree as a nested GitHub task list.
+///
+/// Completed tasks are checked. Every item ends with its task ID in an HTML
+/// comment, which GitHub does not display and [`checklist_snapshot`] reads back.
+pub fn render_checklist(tree: &TaskTree, states: &StatesConfig) -> String {
+    fn render(tree: &TaskTree, depth: usize, completed: Option<&str>, md: &mut String) {
+        let checked = Some(tree.task.status.as_str()) == completed;
+        md.push_str(&format!(
+            "{}- [{}] {} <!-- {} {} -->\n",
+            "  ".repeat(depth),
+            if checked { 'x' } else { ' ' },
+            tree.task.title,
+            ID_COMMENT_KEY,
+            tree.task.id
+        ));
+        for child in &tree.children {
+            render(child, depth + 1, completed, md);
+        }
+    }
+
+    let mut md = String::new();
+    render(tree, 0, completed_state(states).ok(), &mut md);
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows<'a>(snapshot: &'a Snapshot, table: &str) -> &'a [Value] {
+        snapshot.get_table(table).map_or(&[], |t| t.as_slice())
+    }
+
+    fn task<'a>(snapshot: &'a Snapshot, title: &str) -> &'a Value {
+        rows(snapshot, "tasks")
+            .iter()
+            .find(|t| t["title"] == title)
+            .unwrap()
+    }
+
+    #[test]
+    fn checkboxes_and_headings_become_tasks() {
+        let md = "# Release\n\
+                  Ship it.\n\
+                  ## Docs\n\
+                  - [x] Changelog\n\
+                  - [ ] Upgrade guide #docs\n\
+                  \x20 - [x] Screenshots\n\
+                  \x20 - check the Windows steps\n\
+                  ## Background\n\
+                  Some prose.\n\
+                  - a plain note\n";
+        let snapshot =
+            checklist_snapshot(md, &StatesConfig::default(), &PrioritiesConfig::default()).unwrap();
+
+        let titles: Vec<&str> = rows(&snapshot, "tasks")
+            .iter()
+            .map(|t| t["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Release",
+                "Docs",
+                "Changelog",
+                "Upgrade guide",
+                "Screenshots"
+            ]
+        );
+        assert_eq!(task(&snapshot, "Changelog")["status"], "completed");
+        assert!(task(&snapshot, "Changelog")["completed_at"].is_i64());
+        assert_eq!(task(&snapshot, "Screenshots")["status"], "completed");
+        assert_eq!(task(&snapshot, "Upgrade guide")["status"], "pending");
+        assert_eq!(task(&snapshot, "Docs")["status"], "pending");
+        assert_eq!(
+            task(&snapshot, "Upgrade guide")["description"],
+            "- check the Windows steps"
+        );
+        assert_eq!(
+            task(&snapshot, "Release")["description"],
+            "Ship it.\n- Background\n  Some prose.\n  - a plain note"
+        );
+        assert_eq!(task(&snapshot, "Upgrade guide")["tags"], "[\"docs\"]");
+        assert_eq!(rows(&snapshot, "task_tags").len(), 1);
+        assert_eq!(rows(&snapshot, "dependencies").len(), 4);
+    }
+
+    #[test]
+    fn groups_complete_when_everything_below_is() {
+        let md = "## Done\n- [x] a\n- [x] b\n## Open\n- [x] c\n- [ ] d\n";
+        let snapshot =
+            checklist_snapshot(md, &StatesConfig::default(), &PrioritiesConfig::default()).unwrap();
+        assert_eq!(task(&snapshot, "Done")["status"], "completed");
+        assert_eq!(task(&snapshot, "Open")["status"], "pending");
+        assert!(task(&snapshot, "Open")["completed_at"].is_null());
+    }
+
+    #[test]
+    fn priority_words_and_errors() {
+        let snapshot = checklist_snapshot(
+            "- [ ] Urgent !9\n",
+            &StatesConfig::default(),
+            &PrioritiesConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(task(&snapshot, "Urgent")["priority"], "9");
+
+        let err = checklist_snapshot(
+            "# Notes\n- no boxes\n",
+            &StatesConfig::default(),
+            &PrioritiesConfig::default(),
+        );
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn id_comments_keep_ids_and_existing_tasks_are_skipped() {
+        let md = "- [ ] Release <!-- id: known -->\n\
+                  \x20 - [x] Changelog <!-- id: known-child -->\n\
+                  \x20 - [ ] Upgrade guide #docs\n\
+                  - [ ] Odd <!-- not an id -->\n";
+        let mut snapshot =
+            checklist_snapshot(md, &StatesConfig::default(), &PrioritiesConfig::default()).unwrap();
+        assert_eq!(task(&snapshot, "Release")["id"], "known");
+        assert_eq!(task(&snapshot, "Upgrade guide")["tags"], "[\"docs\"]");
+        assert_eq!(
+            task(&snapshot, "Odd <!-- not an id -->")["id"],
+            "checklist-4"
+        );
+
+        let skipped = skip_existing(&mut snapshot, |id| Ok(id.starts_with("known"))).unwrap();
+        assert_eq!(skipped, vec!["known", "known-child"]);
+        let ids: Vec<&str> = rows(&snapshot, "tasks")
+            .iter()
+            .map(|t| t["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["checklist-3", "checklist-4"]);
+        // The new subtask still hangs off the existing task
+        let deps = rows(&snapshot, "dependencies");
+        assert_eq!(deps.len(), 1);
+        assert_eq!(deps[0]["from_task_id"], "known");
+        assert_eq!(rows(&snapshot, "task_tags").len(), 1);
+    }
+
+    #[test]
+    fn rendered_checklists_read_back() {
+        use crate::types::{DEFAULT_PROJECT, Task};
+
+        let node = |id: &str, title: &str, status: &str, children| TaskTree {
+            task: Task {
+                id: id.to_string(),
+                title: title.to_string(),
+                description: None,
+                status: status.to_string(),
+                phase: None,
+                priority: PRIORITY_DEFAULT,
+                worker_id: None,
+                claimed_at: None,
+                needed_tags: vec![],
+                wanted_tags: vec![],
+                tags: vec![],
+                points: None,
+                time_estimate_ms: None,
+                time_actual_ms: None,
+                started_at: None,
+                completed_at: None,
+                current_thought: None,
+                blocked_reason: None,
+                blocked_note: None,
+                needed_group: None,
+                wanted_optional: false,
+                required_child_tags: Vec::new(),
+                cost_usd: 0.0,
+                metrics: [0; 8],
+                project: DEFAULT_PROJECT.to_string(),
+                created_at: 0,
+                updated_at: 0,
+            },
+            children,
+        };
+        let tree = node(
+            "root",
+            "Release",
+            "working",
+            vec![
+                node("a", "Changelog", "completed", vec![]),
+                node(
+                    "b",
+                    "Docs",
+                    "pending",
+                    vec![node("c", "Guide", "cancelled", vec![])],
+                ),
+            ],
+        );
+        let md = render_checklist(&tree, &StatesConfig::default());
+        assert_eq!(
+            md,
+            "- [ ] Release <!-- id: root -->\n\
+             \x20 - [x] Changelog <!-- id: a -->\n\
+             \x20 - [ ] Docs <!-- id: b -->\n\
+             \x20   - [ ] Guide <!-- id: c -->\n"
+        );
+
+        let snapshot =
+            checklist_snapshot(&md, &StatesConfig::default(), &PrioritiesConfig::default())
+                .unwrap();
+        assert_eq!(task(&snapshot, "Changelog")["id"], "a");
+        assert_eq!(task(&snapshot, "Changelog")["status"], "completed");
+        assert_eq!(task(&snapshot, "Guide")["id"], "c");
+    }
+}
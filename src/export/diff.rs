@@ -380,7 +380,7 @@ fn query_table_as_json(db: &Database, table_name: &str) -> Result<Vec<Value>> {
     let ordering = get_table_ordering(table_name);
     let query = format!("SELECT * FROM {} {}", table_name, ordering);
 
-    db.with_conn(|conn| {
+    db.with_read_conn(|conn| {
         let mut stmt = conn.prepare(&query)?;
         let column_names: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
 
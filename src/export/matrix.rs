@@ -0,0 +1,122 @@
+//! CSV exports of the blocking graph.
+//!
+//! A [`DependencyMatrix`] renders in two layouts:
+//!
+//! - `matrix`: one row per task with its status and blocker counts, then one
+//!   column per task holding the dependency type where the column task
+//!   blocks the row task.
+//! - `edges`: one `from,to,type` row per blocking edge.
+//!
+//! Both open in a spreadsheet for checking a plan's structure by eye.
+
+use crate::db::DependencyMatrix;
+
+/// Layout of a matrix export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatrixLayout {
+    /// Adjacency matrix, rows blocked by columns.
+    #[default]
+    Matrix,
+    /// Edge list.
+    Edges,
+}
+
+impl MatrixLayout {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "matrix" => Some(Self::Matrix),
+            "edges" => Some(Self::Edges),
+            _ => None,
+        }
+    }
+}
+
+/// Quote a CSV field if it holds a comma, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let fields: Vec<String> = fields.into_iter().map(csv_field).collect();
+    format!("{}\n", fields.join(","))
+}
+
+/// Render a dependency matrix as CSV in the given layout.
+pub fn render_matrix_csv(matrix: &DependencyMatrix, layout: MatrixLayout) -> String {
+    match layout {
+        MatrixLayout::Edges => {
+            let mut out = csv_row(["from", "to", "type"]);
+            for (from, to, dep_type) in &matrix.edges {
+                out.push_str(&csv_row([from.as_str(), to.as_str(), dep_type.as_str()]));
+            }
+            out
+        }
+        MatrixLayout::Matrix => {
+            let mut header = vec!["id", "title", "status", "blockers", "transitive_blockers"];
+            header.extend(matrix.tasks.iter().map(|t| t.id.as_str()));
+            let mut out = csv_row(header);
+            for task in &matrix.tasks {
+                let (blockers, transitive) = (
+                    task.blockers.to_string(),
+                    task.transitive_blockers.to_string(),
+                );
+                let mut row = vec![
+                    task.id.as_str(),
+                    task.title.as_str(),
+                    task.status.as_str(),
+                    blockers.as_str(),
+                    transitive.as_str(),
+                ];
+                row.extend(matrix.tasks.iter().map(|column| {
+                    matrix
+                        .edges
+                        .iter()
+                        .find(|(from, to, _)| *from == column.id && *to == task.id)
+                        .map_or("", |(_, _, dep_type)| dep_type.as_str())
+                }));
+                out.push_str(&csv_row(row));
+            }
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MatrixTask;
+
+    fn task(id: &str, title: &str, blockers: usize) -> MatrixTask {
+        MatrixTask {
+            id: id.to_string(),
+            title: title.to_string(),
+            status: "pending".to_string(),
+            blockers,
+            transitive_blockers: blockers,
+        }
+    }
+
+    #[test]
+    fn test_render_matrix_layouts() {
+        let matrix = DependencyMatrix {
+            root: None,
+            tasks: vec![task("a", "Design, draft", 0), task("b", "Say \"hi\"", 1)],
+            edges: vec![("a".to_string(), "b".to_string(), "blocks".to_string())],
+        };
+
+        assert_eq!(
+            render_matrix_csv(&matrix, MatrixLayout::Matrix),
+            "id,title,status,blockers,transitive_blockers,a,b\n\
+             a,\"Design, draft\",pending,0,0,,\n\
+             b,\"Say \"\"hi\"\"\",pending,1,1,blocks,\n"
+        );
+        assert_eq!(
+            render_matrix_csv(&matrix, MatrixLayout::Edges),
+            "from,to,type\na,b,blocks\n"
+        );
+    }
+}
@@ -6,8 +6,11 @@
 //! - Migration between schema versions
 //! - Human-readable diffs in git
 
+pub mod checklist;
 pub mod diff;
+pub mod matrix;
 
+use crate::types::ExportTables;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::BTreeMap;
@@ -27,7 +30,11 @@ pub const EXPORTED_TABLES: &[&str] = &[
     "task_tags",
     "task_needed_tags",
     "task_wanted_tags",
+    "task_fields",
     "task_sequence",
+    "task_changes",
+    "task_thoughts",
+    "audit_log",
 ];
 
 /// Tables excluded from export (ephemeral/runtime).
@@ -81,35 +88,47 @@ impl Snapshot {
         serde_json::from_str(json)
     }
 
+    /// Build a snapshot from exported database tables.
+    pub fn from_tables(tables: ExportTables) -> Result<Self, serde_json::Error> {
+        let mut snapshot = Self::new();
+        // ExportTables serializes as table name -> rows, leaving out unexported tables
+        if let Value::Object(map) = serde_json::to_value(tables)? {
+            for (name, rows) in map {
+                if let Value::Array(rows) = rows {
+                    snapshot.tables.insert(name, rows);
+                }
+            }
+        }
+        Ok(snapshot)
+    }
+
     /// Load a snapshot from a file (supports both plain JSON and gzip).
     pub fn from_file(path: &std::path::Path) -> anyhow::Result<Self> {
-        use std::fs::File;
-        use std::io::{BufReader, Read};
-
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-
-        // Check for gzip magic bytes
-        let mut magic = [0u8; 2];
-        reader.read_exact(&mut magic)?;
-
-        // Reset to start
-        drop(reader);
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
+        Self::from_bytes(&std::fs::read(path)?)
+    }
 
-        if magic == [0x1f, 0x8b] {
+    /// Load a snapshot from plain or gzipped JSON bytes.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
             // Gzip compressed
-            let decoder = flate2::read::GzDecoder::new(reader);
-            let snapshot: Snapshot = serde_json::from_reader(decoder)?;
-            Ok(snapshot)
+            let decoder = flate2::read::GzDecoder::new(bytes);
+            Ok(serde_json::from_reader(decoder)?)
         } else {
-            // Plain JSON
-            let snapshot: Snapshot = serde_json::from_reader(reader)?;
-            Ok(snapshot)
+            Ok(serde_json::from_slice(bytes)?)
         }
     }
 
+    /// Serialize to gzipped pretty JSON.
+    pub fn to_json_gzip(&self) -> anyhow::Result<Vec<u8>> {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(self.to_json_pretty()?.as_bytes())?;
+        Ok(encoder.finish()?)
+    }
+
     /// Serialize to JSON with pretty formatting.
     pub fn to_json_pretty(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string_pretty(self)
@@ -147,7 +166,11 @@ pub fn get_table_ordering(table: &str) -> &'static str {
         "task_tags" => "ORDER BY task_id, tag",
         "task_needed_tags" => "ORDER BY task_id, tag",
         "task_wanted_tags" => "ORDER BY task_id, tag",
+        "task_fields" => "ORDER BY task_id, name",
         "task_sequence" => "ORDER BY task_id, id",
+        "task_changes" => "ORDER BY task_id, id",
+        "task_thoughts" => "ORDER BY task_id, id",
+        "audit_log" => "ORDER BY id",
         _ => "ORDER BY rowid",
     }
 }
@@ -162,7 +185,11 @@ pub fn get_table_primary_key(table: &str) -> &'static [&'static str] {
         "task_tags" => &["task_id", "tag"],
         "task_needed_tags" => &["task_id", "tag"],
         "task_wanted_tags" => &["task_id", "tag"],
+        "task_fields" => &["task_id", "name"],
         "task_sequence" => &["id"],
+        "task_changes" => &["id"],
+        "task_thoughts" => &["id"],
+        "audit_log" => &["id"],
         _ => &["rowid"],
     }
 }
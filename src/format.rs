@@ -1,17 +1,30 @@
-//! Output formatting utilities for markdown and JSON.
+//! Output formatting utilities for markdown, JSON, and compact text.
 
-use crate::config::StatesConfig;
-use crate::types::{PRIORITY_DEFAULT, ScanResult, Task, TaskTree, WorkerInfo};
+use crate::config::{PrioritiesConfig, StatesConfig};
+use crate::types::{DEFAULT_PROJECT, PRIORITY_DEFAULT, ScanResult, Task, TaskTree, WorkerInfo};
 use serde_json::Value;
 use std::collections::HashMap;
 
 /// Output format for query results.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
     #[default]
     Json,
     Markdown,
+    /// Tab-separated rows with short column keys, for list-style tools.
+    /// Other tools answer in JSON.
+    Compact,
 }
 
 impl OutputFormat {
@@ -19,38 +32,158 @@ impl OutputFormat {
         match s.to_lowercase().as_str() {
             "json" => Some(OutputFormat::Json),
             "markdown" | "md" => Some(OutputFormat::Markdown),
+            "compact" => Some(OutputFormat::Compact),
             _ => None,
         }
     }
 }
 
-/// Format a single task as markdown.
-pub fn format_task_markdown(task: &Task, blocked_by: &[String]) -> String {
+/// Task fields a caller asked for, from a `select` argument: field names to
+/// include, and/or names prefixed with `-` to omit (e.g. `["-description"]`).
+/// Names are the keys of a task's JSON output. `id` is always kept.
+#[derive(Debug, Clone, Default)]
+pub struct FieldSelection {
+    include: Option<Vec<String>>,
+    exclude: Vec<String>,
+}
+
+impl FieldSelection {
+    pub fn new(names: &[String]) -> Self {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for name in names {
+            match name.strip_prefix('-') {
+                Some(omitted) => exclude.push(omitted.to_string()),
+                None => include.push(name.clone()),
+            }
+        }
+        Self {
+            include: (!include.is_empty()).then_some(include),
+            exclude,
+        }
+    }
+
+    /// Whether a field should be returned.
+    pub fn includes(&self, field: &str) -> bool {
+        field == "id"
+            || (!self.exclude.iter().any(|f| f == field)
+                && self
+                    .include
+                    .as_ref()
+                    .is_none_or(|include| include.iter().any(|f| f == field)))
+    }
+
+    /// Whether a field was named explicitly, for fields left out by default.
+    pub fn names(&self, field: &str) -> bool {
+        self.include
+            .as_ref()
+            .is_some_and(|include| include.iter().any(|f| f == field))
+    }
+
+    /// Drop the keys of a JSON object that are not selected.
+    pub fn apply(&self, value: &mut Value) {
+        if let Some(obj) = value.as_object_mut() {
+            obj.retain(|key, _| self.includes(key));
+        }
+    }
+
+    /// Columns for compact output: `id` and the included fields in the order
+    /// given, or the default columns minus omitted ones.
+    fn columns(&self, defaults: &[&str]) -> Vec<String> {
+        match &self.include {
+            Some(include) => std::iter::once("id")
+                .chain(include.iter().map(String::as_str).filter(|f| *f != "id"))
+                .filter(|f| self.includes(f))
+                .map(String::from)
+                .collect(),
+            None => defaults
+                .iter()
+                .filter(|f| self.includes(f))
+                .map(|f| f.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Format a single task as markdown, showing only the selected fields.
+pub fn format_task_markdown(
+    task: &Task,
+    blocked_by: &[String],
+    priorities: &PrioritiesConfig,
+    select: &FieldSelection,
+) -> String {
     let mut md = String::new();
 
-    md.push_str(&format!("## Task: {}\n", task.title));
+    if select.includes("title") {
+        md.push_str(&format!("## Task: {}\n", task.title));
+    } else {
+        md.push_str(&format!("## Task: `{}`\n", task.id));
+    }
     md.push_str(&format!("- **id**: `{}`\n", task.id));
-    md.push_str(&format!("- **status**: {}\n", task.status));
-    md.push_str(&format!("- **priority**: {}\n", task.priority));
+    if select.includes("status") {
+        md.push_str(&format!("- **status**: {}\n", task.status));
+    }
+    if select.includes("priority") {
+        md.push_str(&format!(
+            "- **priority**: {}\n",
+            priorities.display(task.priority)
+        ));
+    }
 
-    if let Some(ref owner) = task.worker_id {
+    if let Some(ref owner) = task.worker_id
+        && select.includes("worker_id")
+    {
         md.push_str(&format!("- **owner**: {}\n", owner));
     }
 
-    if !blocked_by.is_empty() {
+    if let Some(ref group) = task.needed_group
+        && select.includes("needed_group")
+    {
+        md.push_str(&format!("- **needed_group**: {}\n", group));
+    }
+
+    if !task.required_child_tags.is_empty() && select.includes("required_child_tags") {
+        md.push_str(&format!(
+            "- **required_child_tags**: {}\n",
+            task.required_child_tags.join(", ")
+        ));
+    }
+
+    if task.project != DEFAULT_PROJECT && select.includes("project") {
+        md.push_str(&format!("- **project**: {}\n", task.project));
+    }
+
+    if !blocked_by.is_empty() && select.includes("blocked_by") {
         let blockers: Vec<String> = blocked_by.iter().map(|id| format!("`{}`", id)).collect();
         md.push_str(&format!("- **blocked_by**: {}\n", blockers.join(", ")));
     }
 
-    if let Some(points) = task.points {
+    if let Some(ref reason) = task.blocked_reason
+        && select.includes("blocked_reason")
+    {
+        match task.blocked_note {
+            Some(ref note) => {
+                md.push_str(&format!("- **blocked_reason**: {} ({})\n", reason, note))
+            }
+            None => md.push_str(&format!("- **blocked_reason**: {}\n", reason)),
+        }
+    }
+
+    if let Some(points) = task.points
+        && select.includes("points")
+    {
         md.push_str(&format!("- **points**: {}\n", points));
     }
 
-    if let Some(ref thought) = task.current_thought {
+    if let Some(ref thought) = task.current_thought
+        && select.includes("current_thought")
+    {
         md.push_str(&format!("- **thought**: {}\n", thought));
     }
 
-    if let Some(ref desc) = task.description {
+    if let Some(ref desc) = task.description
+        && select.includes("description")
+    {
         md.push_str("\n### Description\n");
         md.push_str(desc);
         md.push('\n');
@@ -59,74 +192,165 @@ pub fn format_task_markdown(task: &Task, blocked_by: &[String]) -> String {
     md
 }
 
+/// Format tasks related to another (children, blockers, ...) as a markdown
+/// section of one line each.
+pub fn format_related_tasks_markdown(heading: &str, tasks: &[Task]) -> String {
+    let mut md = format!("\n### {}\n", heading);
+    if tasks.is_empty() {
+        md.push_str("None.\n");
+    }
+    for task in tasks {
+        let owner = task
+            .worker_id
+            .as_ref()
+            .map(|o| format!(" @{}", o))
+            .unwrap_or_default();
+        md.push_str(&format!(
+            "- `{}` {} [{}]{}\n",
+            task.id,
+            truncate_title(&task.title),
+            task.status,
+            owner
+        ));
+    }
+    md
+}
+
 /// Format a list of tasks as markdown.
 /// Groups tasks by their state dynamically based on the states config.
 pub fn format_tasks_markdown(
     tasks: &[(Task, Vec<String>)],
     states_config: &StatesConfig,
+    priorities: &PrioritiesConfig,
+    select: &FieldSelection,
 ) -> String {
     let mut md = String::new();
 
     md.push_str(&format!("# Tasks ({})\n\n", tasks.len()));
 
     // Group tasks by status
-    let mut by_status: HashMap<String, Vec<&(Task, Vec<String>)>> = HashMap::new();
-    for state in states_config.state_names() {
-        by_status.insert(state.to_string(), Vec::new());
-    }
+    let mut by_status: HashMap<&str, Vec<&(Task, Vec<String>)>> = HashMap::new();
     for task_entry in tasks {
         by_status
-            .entry(task_entry.0.status.clone())
+            .entry(task_entry.0.status.as_str())
             .or_default()
             .push(task_entry);
     }
 
-    // Output blocking states first (in-progress tasks), then initial state, then others
-    // This provides a sensible default ordering
-
-    // First, output blocking states (excluding initial state)
-    for state in &states_config.blocking_states {
-        if state != &states_config.initial
-            && let Some(state_tasks) = by_status.get(state)
-            && !state_tasks.is_empty()
-        {
+    for state in state_display_order(states_config) {
+        if let Some(state_tasks) = by_status.get(state) {
             md.push_str(&format!("## {}\n\n", format_state_name(state)));
             for (task, blocked_by) in state_tasks {
-                md.push_str(&format_task_short(task, blocked_by));
+                md.push_str(&format_task_short(task, blocked_by, priorities, select));
             }
             md.push('\n');
         }
     }
 
-    // Then initial state
-    if let Some(state_tasks) = by_status.get(&states_config.initial)
-        && !state_tasks.is_empty()
-    {
-        md.push_str(&format!(
-            "## {}\n\n",
-            format_state_name(&states_config.initial)
-        ));
-        for (task, blocked_by) in state_tasks {
-            md.push_str(&format_task_short(task, blocked_by));
+    md
+}
+
+/// States in the order task lists show them: blocking states (in-progress
+/// work) first, then the initial state, then the rest (terminal states like
+/// completed, failed, cancelled).
+pub fn state_display_order(states_config: &StatesConfig) -> Vec<&str> {
+    let initial = states_config.initial.as_str();
+    let mut order: Vec<&str> = states_config
+        .blocking_states
+        .iter()
+        .map(String::as_str)
+        .filter(|s| *s != initial)
+        .collect();
+    order.push(initial);
+    for state in states_config.state_names() {
+        if !states_config.is_blocking_state(state) && state != initial {
+            order.push(state);
+        }
+    }
+    order
+}
+
+/// What `list_tasks` groups its results by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Status,
+    /// The owning agent.
+    Agent,
+    /// Each tag; a task with several tags is in several groups.
+    Tag,
+    /// The containing task.
+    Parent,
+}
+
+impl GroupBy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "status" => Some(Self::Status),
+            "agent" | "owner" => Some(Self::Agent),
+            "tag" | "tags" => Some(Self::Tag),
+            "parent" => Some(Self::Parent),
+            _ => None,
         }
-        md.push('\n');
     }
 
-    // Then non-blocking states (terminal states like completed, failed, cancelled)
-    for state in states_config.state_names() {
-        if !states_config.is_blocking_state(state)
-            && state != states_config.initial
-            && let Some(state_tasks) = by_status.get(state)
-            && !state_tasks.is_empty()
-        {
-            md.push_str(&format!("## {}\n\n", format_state_name(state)));
-            for (task, blocked_by) in state_tasks {
-                md.push_str(&format_task_short(task, blocked_by));
-            }
-            md.push('\n');
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Status => "status",
+            Self::Agent => "agent",
+            Self::Tag => "tag",
+            Self::Parent => "parent",
         }
     }
+}
+
+/// One bucket of grouped tasks. `key` is None for tasks without a value
+/// (unowned, untagged, or top-level); `title` names a parent task.
+#[derive(Debug, Clone)]
+pub struct TaskGroup {
+    pub key: Option<String>,
+    pub title: Option<String>,
+    /// Indexes into the grouped task list.
+    pub tasks: Vec<usize>,
+}
+
+/// Section heading of a task group.
+fn group_heading(group_by: GroupBy, group: &TaskGroup) -> String {
+    match (group_by, &group.key) {
+        (GroupBy::Status, Some(status)) => format_state_name(status),
+        (GroupBy::Agent, Some(agent)) => format!("@{}", agent),
+        (GroupBy::Agent, None) => "Unassigned".to_string(),
+        (GroupBy::Tag, Some(tag)) => format!("#{}", tag),
+        (GroupBy::Tag, None) => "Untagged".to_string(),
+        (GroupBy::Parent, Some(id)) => match &group.title {
+            Some(title) => format!("{} `{}`", truncate_title(title), id),
+            None => format!("`{}`", id),
+        },
+        (GroupBy::Parent, None) => "Top level".to_string(),
+        (GroupBy::Status, None) => "No status".to_string(),
+    }
+}
 
+/// Format grouped tasks as markdown, one section per group with its count.
+pub fn format_task_groups_markdown(
+    tasks: &[(Task, Vec<String>)],
+    group_by: GroupBy,
+    groups: &[TaskGroup],
+    priorities: &PrioritiesConfig,
+    select: &FieldSelection,
+) -> String {
+    let mut md = format!("# Tasks ({}) by {}\n\n", tasks.len(), group_by.as_str());
+    for group in groups {
+        md.push_str(&format!(
+            "## {} ({})\n\n",
+            group_heading(group_by, group),
+            group.tasks.len()
+        ));
+        for &i in &group.tasks {
+            let (task, blocked_by) = &tasks[i];
+            md.push_str(&format_task_short(task, blocked_by, priorities, select));
+        }
+        md.push('\n');
+    }
     md
 }
 
@@ -148,6 +372,20 @@ pub fn truncate_title(title: &str) -> std::borrow::Cow<'_, str> {
     }
 }
 
+/// Cut a description to its first `max_chars` characters for a preview,
+/// noting the full length. Returns `None` if it already fits (or `max_chars` is 0).
+pub fn preview_description(description: &str, max_chars: usize) -> Option<String> {
+    if max_chars == 0 {
+        return None;
+    }
+    let (cut, _) = description.char_indices().nth(max_chars)?;
+    Some(format!(
+        "{}... [truncated: {} characters in full; pass full=true for the whole description]",
+        description[..cut].trim_end(),
+        description.chars().count()
+    ))
+}
+
 /// Format a state name for display (capitalize, replace underscores with spaces).
 fn format_state_name(state: &str) -> String {
     state
@@ -163,21 +401,20 @@ fn format_state_name(state: &str) -> String {
         .join(" ")
 }
 
-/// Priority marker for list/scan output. Only shows for above-default priorities.
-fn priority_marker(priority: i32) -> &'static str {
-    match priority {
-        10 => "!!! ",
-        8..=9 => "!! ",
-        6..=7 => "! ",
-        _ => "",
-    }
-}
-
-/// Format a task in short form for lists.
-fn format_task_short(task: &Task, blocked_by: &[String]) -> String {
-    let priority_marker = priority_marker(task.priority);
+/// Format a task in short form for lists, showing only the selected fields.
+fn format_task_short(
+    task: &Task,
+    blocked_by: &[String],
+    priorities: &PrioritiesConfig,
+    select: &FieldSelection,
+) -> String {
+    let priority_marker = if select.includes("priority") {
+        priorities.marker(task.priority)
+    } else {
+        ""
+    };
 
-    let blocked = if blocked_by.is_empty() {
+    let blocked = if blocked_by.is_empty() || !select.includes("blocked_by") {
         String::new()
     } else {
         format!(" [blocked by {}]", blocked_by.len())
@@ -186,19 +423,27 @@ fn format_task_short(task: &Task, blocked_by: &[String]) -> String {
     let owner = task
         .worker_id
         .as_ref()
+        .filter(|_| select.includes("worker_id"))
         .map(|o| format!(" @{}", o))
         .unwrap_or_default();
 
     let thought = task
         .current_thought
         .as_ref()
+        .filter(|_| select.includes("current_thought"))
         .map(|t| format!(" - _{}_", t))
         .unwrap_or_default();
 
+    let title = if select.includes("title") {
+        truncate_title(&task.title)
+    } else {
+        "".into()
+    };
+
     format!(
         "- {}{} `{}`{}{}{}\n",
         priority_marker,
-        truncate_title(&task.title),
+        title,
         &task.id[..8.min(task.id.len())],
         owner,
         blocked,
@@ -220,6 +465,10 @@ pub fn format_workers_markdown(workers: &[WorkerInfo]) -> String {
             md.push_str(&format!("- **tags**: {}\n", worker.tags.join(", ")));
         }
 
+        if !worker.groups.is_empty() {
+            md.push_str(&format!("- **groups**: {}\n", worker.groups.join(", ")));
+        }
+
         if let Some(ref workflow) = worker.workflow {
             md.push_str(&format!("- **workflow**: {}\n", workflow));
         }
@@ -266,11 +515,17 @@ pub fn format_attachments_markdown(attachments: &[crate::types::AttachmentMeta])
         if !attachment.name.is_empty() {
             md.push_str(&format!("- **name**: {}\n", attachment.name));
         }
+        if attachment.version > 1 {
+            md.push_str(&format!("- **version**: {}\n", attachment.version));
+        }
         md.push_str(&format!("- **mime**: {}\n", attachment.mime_type));
 
         if let Some(ref fp) = attachment.file_path {
             md.push_str(&format!("- **file**: `{}`\n", fp));
         }
+        if let (Some(hash), Some(size)) = (&attachment.content_hash, attachment.size_bytes) {
+            md.push_str(&format!("- **blob**: sha256 `{}` ({} bytes)\n", hash, size));
+        }
 
         // Format created_at as relative time if possible
         let created_secs = attachment.created_at / 1000;
@@ -290,6 +545,124 @@ pub fn markdown_to_json(md: String) -> Value {
     })
 }
 
+/// Wrap compact text for tools that return JSON. [`ToolResult::from_value`]
+/// unwraps it so the text goes out as-is rather than as an escaped string.
+pub fn compact_to_json(text: String) -> Value {
+    serde_json::json!({
+        "format": "compact",
+        "content": text
+    })
+}
+
+/// Format rows as compact text: a header line of short keys, then one
+/// tab-separated line per row. Missing values are empty cells; tabs and
+/// newlines inside values become spaces.
+pub fn format_compact_rows(keys: &[&str], rows: &[Vec<String>]) -> String {
+    let mut out = keys.join("\t");
+    out.push('\n');
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|cell| cell.replace(['\t', '\n', '\r'], " "))
+            .collect();
+        out.push_str(&cells.join("\t"));
+        out.push('\n');
+    }
+    out
+}
+
+/// Task columns in compact output unless a selection names others.
+const COMPACT_TASK_COLUMNS: &[&str] = &[
+    "id",
+    "status",
+    "priority",
+    "worker_id",
+    "blocked_by",
+    "title",
+];
+
+/// Short key of a task field in compact output.
+fn compact_key(field: &str) -> &str {
+    match field {
+        "status" => "st",
+        "priority" => "pri",
+        "worker_id" => "own",
+        "blocked_by" => "blk",
+        other => other,
+    }
+}
+
+/// A JSON value as a compact cell: arrays comma-joined, null empty.
+fn compact_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(compact_cell).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+/// Format tasks (as their JSON objects) as compact rows. By default the
+/// columns are `id`, `st` (status), `pri` (priority), `own` (owner), `blk`
+/// (blockers), and `title`; a selection picks others.
+pub fn format_tasks_compact(
+    tasks: &[Value],
+    select: &FieldSelection,
+    next_offset: Option<i32>,
+) -> String {
+    let columns = select.columns(COMPACT_TASK_COLUMNS);
+    let keys: Vec<&str> = columns.iter().map(|c| compact_key(c)).collect();
+    let rows: Vec<Vec<String>> = tasks
+        .iter()
+        .map(|task| compact_task_row(task, &columns))
+        .collect();
+    let mut out = format_compact_rows(&keys, &rows);
+    if let Some(offset) = next_offset {
+        out.push_str(&format!("more\toffset={}\n", offset));
+    }
+    out
+}
+
+/// Cells of one task row for the given columns.
+fn compact_task_row(task: &Value, columns: &[String]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|c| match task.get(c) {
+            Some(value) => compact_cell(value),
+            // Task JSON leaves out the default priority
+            None if c == "priority" => PRIORITY_DEFAULT.to_string(),
+            None => String::new(),
+        })
+        .collect()
+}
+
+/// Format grouped tasks as compact rows, each led by a `group` column holding
+/// the group key (empty for tasks without one).
+pub fn format_task_groups_compact(
+    tasks: &[Value],
+    groups: &[TaskGroup],
+    select: &FieldSelection,
+    next_offset: Option<i32>,
+) -> String {
+    let columns = select.columns(COMPACT_TASK_COLUMNS);
+    let keys: Vec<&str> = std::iter::once("group")
+        .chain(columns.iter().map(|c| compact_key(c)))
+        .collect();
+    let mut rows = Vec::new();
+    for group in groups {
+        for &i in &group.tasks {
+            let mut row = vec![group.key.clone().unwrap_or_default()];
+            row.extend(compact_task_row(&tasks[i], &columns));
+            rows.push(row);
+        }
+    }
+    let mut out = format_compact_rows(&keys, &rows);
+    if let Some(offset) = next_offset {
+        out.push_str(&format!("more\toffset={}\n", offset));
+    }
+    out
+}
+
 /// Result type for tool handlers - allows returning either JSON or raw text.
 #[derive(Debug)]
 pub enum ToolResult {
@@ -310,6 +683,16 @@ impl ToolResult {
         ToolResult::Raw(text)
     }
 
+    /// Wrap a tool's JSON response, unwrapping compact text to raw output
+    pub fn from_value(value: Value) -> Self {
+        if value.get("format").and_then(Value::as_str) == Some("compact")
+            && let Some(Value::String(text)) = value.get("content")
+        {
+            return ToolResult::Raw(text.clone());
+        }
+        ToolResult::Json(value)
+    }
+
     /// Convert to the appropriate string representation
     pub fn into_string(self) -> String {
         match self {
@@ -317,10 +700,18 @@ impl ToolResult {
             ToolResult::Raw(s) => s,
         }
     }
+
+    /// Length in bytes of the string representation
+    pub fn byte_len(&self) -> usize {
+        match self {
+            ToolResult::Json(v) => serde_json::to_string_pretty(v).map_or(0, |s| s.len()),
+            ToolResult::Raw(s) => s.len(),
+        }
+    }
 }
 
 /// Format a task tree as markdown with visual tree structure.
-pub fn format_task_tree_markdown(tree: &TaskTree) -> String {
+pub fn format_task_tree_markdown(tree: &TaskTree, priorities: &PrioritiesConfig) -> String {
     let mut md = String::new();
 
     // Format root task as heading
@@ -329,8 +720,8 @@ pub fn format_task_tree_markdown(tree: &TaskTree) -> String {
     // Add root task metadata
     let mut meta_parts = Vec::new();
     meta_parts.push(tree.task.status.to_uppercase());
-    if tree.task.priority != PRIORITY_DEFAULT {
-        meta_parts.push(format!("P{}", tree.task.priority));
+    if tree.task.priority != priorities.default {
+        meta_parts.push(tree_priority(tree.task.priority, priorities));
     }
     if let Some(points) = tree.task.points {
         meta_parts.push(format!("{} pts", points));
@@ -350,14 +741,27 @@ pub fn format_task_tree_markdown(tree: &TaskTree) -> String {
     // Format children with tree characters
     if !tree.children.is_empty() {
         md.push('\n');
-        format_tree_children(&tree.children, "", &mut md);
+        format_tree_children(&tree.children, "", &mut md, priorities);
     }
 
     md
 }
 
+/// Priority tag for tree output: the configured label, or "P" and the number.
+fn tree_priority(priority: i32, priorities: &PrioritiesConfig) -> String {
+    priorities
+        .label(priority)
+        .map(String::from)
+        .unwrap_or_else(|| format!("P{}", priority))
+}
+
 /// Recursively format children with tree structure characters.
-fn format_tree_children(children: &[TaskTree], prefix: &str, md: &mut String) {
+fn format_tree_children(
+    children: &[TaskTree],
+    prefix: &str,
+    md: &mut String,
+    priorities: &PrioritiesConfig,
+) {
     let count = children.len();
 
     for (i, child) in children.iter().enumerate() {
@@ -368,8 +772,8 @@ fn format_tree_children(children: &[TaskTree], prefix: &str, md: &mut String) {
         // Build the task line with metadata
         let mut meta_parts = Vec::new();
         meta_parts.push(child.task.status.clone());
-        if child.task.priority != PRIORITY_DEFAULT {
-            meta_parts.push(format!("P{}", child.task.priority));
+        if child.task.priority != priorities.default {
+            meta_parts.push(tree_priority(child.task.priority, priorities));
         }
         if let Some(points) = child.task.points {
             meta_parts.push(format!("{} pts", points));
@@ -391,20 +795,28 @@ fn format_tree_children(children: &[TaskTree], prefix: &str, md: &mut String) {
 
         // Recursively format grandchildren
         if !child.children.is_empty() {
-            format_tree_children(&child.children, &format!("{}{}", prefix, child_prefix), md);
+            format_tree_children(
+                &child.children,
+                &format!("{}{}", prefix, child_prefix),
+                md,
+                priorities,
+            );
         }
     }
 }
 
 /// Format a scan result as markdown.
-pub fn format_scan_result_markdown(result: &ScanResult) -> String {
+pub fn format_scan_result_markdown(result: &ScanResult, priorities: &PrioritiesConfig) -> String {
     let mut md = String::new();
 
     // Root task header
     md.push_str(&format!("# Scan: {}\\n", result.root.title));
     md.push_str(&format!("- **id**: `{}`\\n", result.root.id));
     md.push_str(&format!("- **status**: {}\\n", result.root.status));
-    md.push_str(&format!("- **priority**: {}\\n", result.root.priority));
+    md.push_str(&format!(
+        "- **priority**: {}\\n",
+        priorities.display(result.root.priority)
+    ));
 
     if let Some(ref owner) = result.root.worker_id {
         md.push_str(&format!("- **owner**: {}\\n", owner));
@@ -419,7 +831,7 @@ pub fn format_scan_result_markdown(result: &ScanResult) -> String {
         md.push_str(&format!("\\n## Before ({} tasks)\\n", result.before.len()));
         md.push_str("_Tasks that block this task via blocks/follows dependencies_\\n\\n");
         for task in &result.before {
-            md.push_str(&format_scan_task_short(task));
+            md.push_str(&format_scan_task_short(task, priorities));
         }
     }
 
@@ -428,7 +840,7 @@ pub fn format_scan_result_markdown(result: &ScanResult) -> String {
         md.push_str(&format!("\\n## After ({} tasks)\\n", result.after.len()));
         md.push_str("_Tasks that this task blocks via blocks/follows dependencies_\\n\\n");
         for task in &result.after {
-            md.push_str(&format_scan_task_short(task));
+            md.push_str(&format_scan_task_short(task, priorities));
         }
     }
 
@@ -437,7 +849,7 @@ pub fn format_scan_result_markdown(result: &ScanResult) -> String {
         md.push_str(&format!("\\n## Above ({} tasks)\\n", result.above.len()));
         md.push_str("_Parent chain via contains dependency_\\n\\n");
         for task in &result.above {
-            md.push_str(&format_scan_task_short(task));
+            md.push_str(&format_scan_task_short(task, priorities));
         }
     }
 
@@ -446,7 +858,7 @@ pub fn format_scan_result_markdown(result: &ScanResult) -> String {
         md.push_str(&format!("\\n## Below ({} tasks)\\n", result.below.len()));
         md.push_str("_Descendants via contains dependency_\\n\\n");
         for task in &result.below {
-            md.push_str(&format_scan_task_short(task));
+            md.push_str(&format_scan_task_short(task, priorities));
         }
     }
 
@@ -458,8 +870,8 @@ pub fn format_scan_result_markdown(result: &ScanResult) -> String {
 }
 
 /// Format a task in short form for scan results.
-fn format_scan_task_short(task: &Task) -> String {
-    let priority_marker = priority_marker(task.priority);
+fn format_scan_task_short(task: &Task, priorities: &PrioritiesConfig) -> String {
+    let priority_marker = priorities.marker(task.priority);
 
     let owner = task
         .worker_id
@@ -486,7 +898,8 @@ fn format_scan_task_short(task: &Task) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{PRIORITY_DEFAULT, Priority, Task, TaskTree};
+    use crate::types::{Priority, Task, TaskTree};
+    use serde_json::json;
 
     fn make_test_task(
         id: &str,
@@ -513,8 +926,14 @@ mod tests {
             started_at: None,
             completed_at: None,
             current_thought: None,
+            blocked_reason: None,
+            blocked_note: None,
+            needed_group: None,
+            wanted_optional: false,
+            required_child_tags: Vec::new(),
             cost_usd: 0.0,
             metrics: [0; 8],
+            project: DEFAULT_PROJECT.to_string(),
             created_at: 0,
             updated_at: 0,
         }
@@ -527,7 +946,7 @@ mod tests {
             children: vec![],
         };
 
-        let result = format_task_tree_markdown(&tree);
+        let result = format_task_tree_markdown(&tree, &PrioritiesConfig::default());
         assert!(result.contains("# Root Task"));
         assert!(result.contains("PENDING"));
         assert!(result.contains("P8"));
@@ -577,7 +996,7 @@ mod tests {
             ],
         };
 
-        let result = format_task_tree_markdown(&tree);
+        let result = format_task_tree_markdown(&tree, &PrioritiesConfig::default());
 
         // Check root formatting
         assert!(result.contains("# API Refactoring Sprint"));
@@ -608,7 +1027,7 @@ mod tests {
             }],
         };
 
-        let result = format_task_tree_markdown(&tree);
+        let result = format_task_tree_markdown(&tree, &PrioritiesConfig::default());
 
         // Check deep nesting with proper prefix
         assert!(result.contains("└── Level 1"));
@@ -650,4 +1069,103 @@ mod tests {
         assert!(result.ends_with("..."));
         assert!(result.len() <= MAX_TITLE_DISPLAY_LEN + 3);
     }
+
+    #[test]
+    fn test_preview_description() {
+        assert_eq!(preview_description("short", 10), None);
+        assert_eq!(preview_description("exactly ten", 11), None);
+        assert_eq!(preview_description("anything", 0), None);
+        let preview = preview_description("héllo wörld", 5).unwrap();
+        assert!(
+            preview.starts_with("héllo... [truncated: 11 characters"),
+            "{}",
+            preview
+        );
+    }
+
+    #[test]
+    fn test_format_tasks_compact() {
+        let mut task = make_test_task("t1", "Fix\tthe\nparser", "working", 7, None);
+        task.worker_id = Some("w1".to_string());
+        let idle = make_test_task("t2", "Docs", "pending", 5, None);
+        let mut task = serde_json::to_value(&task).unwrap();
+        task["blocked_by"] = json!(["a", "b"]);
+        let mut idle = serde_json::to_value(&idle).unwrap();
+        idle["blocked_by"] = json!([]);
+        let out = format_tasks_compact(&[task, idle], &FieldSelection::default(), Some(2));
+        assert_eq!(
+            out,
+            "id\tst\tpri\town\tblk\ttitle\n\
+             t1\tworking\t7\tw1\ta,b\tFix the parser\n\
+             t2\tpending\t5\t\t\tDocs\n\
+             more\toffset=2\n"
+        );
+    }
+
+    #[test]
+    fn test_field_selection_includes() {
+        let only = FieldSelection::new(&["title".to_string(), "status".to_string()]);
+        assert!(only.includes("id"));
+        assert!(only.includes("title"));
+        assert!(!only.includes("description"));
+
+        let omit = FieldSelection::new(&["-description".to_string(), "-id".to_string()]);
+        assert!(omit.includes("id"));
+        assert!(omit.includes("title"));
+        assert!(!omit.includes("description"));
+        assert!(!omit.names("title"));
+    }
+
+    #[test]
+    fn test_field_selection_apply() {
+        let select = FieldSelection::new(&["title".to_string()]);
+        let mut value = json!({"id": "t1", "title": "Fix", "description": "Long"});
+        select.apply(&mut value);
+        assert_eq!(value, json!({"id": "t1", "title": "Fix"}));
+    }
+
+    #[test]
+    fn test_format_tasks_compact_selected_columns() {
+        let task = make_test_task("t1", "Fix", "working", 7, None);
+        let mut task = serde_json::to_value(&task).unwrap();
+        task["fields"] = json!({"estimate": 3});
+        let select = FieldSelection::new(&["status".to_string(), "fields".to_string()]);
+        assert_eq!(
+            format_tasks_compact(&[task.clone()], &select, None),
+            "id\tst\tfields\nt1\tworking\t{\"estimate\":3}\n"
+        );
+
+        let select = FieldSelection::new(&["-title".to_string(), "-worker_id".to_string()]);
+        assert_eq!(
+            format_tasks_compact(&[task], &select, None),
+            "id\tst\tpri\tblk\nt1\tworking\t7\t\n"
+        );
+    }
+
+    #[test]
+    fn test_format_task_markdown_omits_unselected() {
+        let mut task = make_test_task("t1", "Fix", "working", 7, None);
+        task.description = Some("A very long description".to_string());
+        let priorities = PrioritiesConfig::default();
+        let full = format_task_markdown(&task, &[], &priorities, &FieldSelection::default());
+        assert!(full.contains("A very long description"));
+
+        let select = FieldSelection::new(&["-description".to_string()]);
+        let md = format_task_markdown(&task, &[], &priorities, &select);
+        assert!(md.contains("## Task: Fix"));
+        assert!(!md.contains("A very long description"));
+    }
+
+    #[test]
+    fn test_compact_results_are_sent_raw() {
+        match ToolResult::from_value(compact_to_json("id\n".to_string())) {
+            ToolResult::Raw(text) => assert_eq!(text, "id\n"),
+            other => panic!("expected raw text, got {:?}", other),
+        }
+        let markdown = markdown_to_json("# Tasks".to_string());
+        assert!(matches!(
+            ToolResult::from_value(markdown),
+            ToolResult::Json(_)
+        ));
+    }
 }
@@ -2,13 +2,65 @@
 //!
 //! Gates are checklist items that must be satisfied before transitioning out of
 //! a status or phase. A gate is satisfied when the task has an attachment with
-//! a matching type (e.g., "gate/tests", "gate/commit").
+//! a matching type (e.g., "gate/tests", "gate/commit"). A gate with `sql` is
+//! instead a read-only predicate over the database, evaluated on every check,
+//! so conditions like "no open child tasks" need no attachment at all:
+//!
+//! ```yaml
+//! gates:
+//!   status:working:
+//!     - type: "gate/children-done"
+//!       enforcement: reject
+//!       description: "Finish or cancel all child tasks"
+//!       sql: >
+//!         SELECT NOT EXISTS (
+//!           SELECT 1 FROM dependencies d JOIN tasks c ON c.id = d.to_task_id
+//!           WHERE d.from_task_id = :task_id AND d.dep_type = 'contains'
+//!             AND c.status NOT IN ('completed', 'cancelled') AND c.deleted_at IS NULL)
+//! ```
+//!
+//! A gate with `command` runs a shell command on every transition attempt and
+//! is satisfied when it exits 0, so "CI is green" can guard `completed`. The
+//! command gets the task in `TASK_GRAPH_TASK_ID`, `TASK_GRAPH_TASK_TITLE`,
+//! `TASK_GRAPH_TASK_STATUS`, `TASK_GRAPH_TASK_PHASE` and the gate type in
+//! `TASK_GRAPH_GATE`; it is killed after `timeout_seconds`. Each run's output
+//! is attached to the task under the gate's type. Pre-flight checks
+//! ([`CommandGates::LastRun`]) report the last run instead of running again.
 
-use crate::config::{GateDefinition, GateEnforcement};
+use crate::config::{AttachmentsConfig, GateDefinition, GateEnforcement};
 use crate::db::Database;
-use anyhow::Result;
+use crate::tools::attachments::check_quotas;
+use crate::tools::query::validate_readonly_sql;
+use crate::types::{AttachmentMeta, Task};
+use anyhow::{Result, anyhow, bail};
+use rusqlite::types::ValueRef;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::fs::{self, File};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use ulid::Ulid;
+
+/// Seconds a gate command may run when the gate sets no `timeout_seconds`.
+pub const DEFAULT_GATE_COMMAND_TIMEOUT_SECS: u64 = 300;
+
+/// Command output kept in the attachment; longer output keeps its end.
+const MAX_GATE_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// Name of the attachment holding a command gate's output.
+const GATE_OUTPUT_NAME: &str = "output";
+
+/// How command gates are evaluated.
+#[derive(Debug, Clone, Copy)]
+pub enum CommandGates<'a> {
+    /// Run each command and attach its output in place of the previous
+    /// run's, within these attachment quotas. Used by transitions.
+    Run(&'a AttachmentsConfig),
+    /// Report how each command's last run ended, without running anything,
+    /// so pre-flight checks have no side effects.
+    LastRun,
+}
 
 /// Result of evaluating a single gate.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +73,62 @@ pub struct GateResult {
     pub description: String,
     /// Whether the gate is satisfied (always false in unsatisfied_gates list).
     pub satisfied: bool,
+    /// Whether the gate is a SQL predicate rather than an attachment.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub predicate: bool,
+    /// Whether the gate is an external command.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub command: bool,
+    /// How the command last ended (e.g. "exit 1", "timed out after 300s").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl GateResult {
+    /// `type (description)`, as gates are named in errors and warnings.
+    pub fn label(&self) -> String {
+        match self.detail {
+            Some(ref detail) => format!("{} ({}; {})", self.gate_type, self.description, detail),
+            None => format!("{} ({})", self.gate_type, self.description),
+        }
+    }
+
+    /// What to do to satisfy the gate, as a list item for error details.
+    pub fn fix_hint(&self, task_id: &str) -> String {
+        format!("  - {}", self.fix_step(task_id))
+    }
+
+    /// What to do to satisfy the gate, depending on its kind.
+    fn fix_step(&self, task_id: &str) -> String {
+        if self.command {
+            format!(
+                "Make the '{}' command pass; its output is attached as type \"{}\"",
+                self.gate_type, self.gate_type
+            )
+        } else if self.predicate {
+            format!(
+                "Satisfy '{}': {}",
+                self.gate_type,
+                if self.description.is_empty() {
+                    "its condition is checked automatically"
+                } else {
+                    &self.description
+                }
+            )
+        } else {
+            format!(
+                "attach(task=\"{}\", type=\"{}\", content=\"...\")",
+                task_id, self.gate_type
+            )
+        }
+    }
+}
+
+/// Suggestion for a transition refused by `gates`: each gate's fix step in
+/// order, followed by `then` (e.g. "retry the transition").
+pub fn fix_suggestion(gates: &[&GateResult], task_id: &str, then: &str) -> String {
+    let steps: Vec<String> = gates.iter().map(|g| g.fix_step(task_id)).collect();
+    format!("{}. Then {}", steps.join("; "), then)
 }
 
 /// Aggregated result of evaluating all gates for a transition.
@@ -32,13 +140,15 @@ pub struct GateCheckResult {
     pub unsatisfied_gates: Vec<GateResult>,
 }
 
-/// Evaluate gates for a task against its attachments.
-/// Returns only unsatisfied gates.
+/// Evaluate gates for a task against its attachments, running the SQL
+/// predicates of predicate gates and evaluating command gates as `commands`
+/// says. Returns only unsatisfied gates.
 ///
 /// # Arguments
 /// * `db` - Database handle for fetching attachments
 /// * `task_id` - The task ID to check gates for
 /// * `gates` - List of gate definitions to evaluate
+/// * `commands` - Whether command gates run or report their last run
 ///
 /// # Returns
 /// A `GateCheckResult` with:
@@ -49,6 +159,7 @@ pub fn evaluate_gates(
     db: &Database,
     task_id: &str,
     gates: &[GateDefinition],
+    commands: CommandGates<'_>,
 ) -> Result<GateCheckResult> {
     // Get all attachment types for this task
     let attachments = db.get_attachments(task_id)?;
@@ -57,12 +168,66 @@ pub fn evaluate_gates(
         .map(|a| a.attachment_type.clone())
         .collect();
 
+    // Command gates that run pass the task to the command's environment
+    let runner = match commands {
+        CommandGates::Run(limits) if gates.iter().any(|g| g.command.is_some()) => Some((
+            limits,
+            db.get_task(task_id)?
+                .ok_or_else(|| anyhow!("Task not found: {}", task_id))?,
+        )),
+        _ => None,
+    };
+
     let mut unsatisfied_gates = Vec::new();
     let mut has_reject = false;
     let mut has_warn = false;
 
     for gate in gates {
-        let satisfied = attachment_types.contains(&gate.gate_type);
+        let mut detail = None;
+        let satisfied = match (&gate.sql, &gate.command, &runner) {
+            (Some(_), Some(_), _) => {
+                bail!("Gate '{}' sets both sql and command", gate.gate_type)
+            }
+            (Some(sql), None, _) => predicate_holds(db, task_id, sql)
+                .map_err(|e| anyhow!("Gate '{}' SQL failed: {}", gate.gate_type, e))?,
+            (None, Some(command), Some((limits, task))) => {
+                let prior: Vec<AttachmentMeta> = attachments
+                    .iter()
+                    .filter(|a| a.attachment_type == gate.gate_type && a.name == GATE_OUTPUT_NAME)
+                    .cloned()
+                    .collect();
+                let (passed, ended) = run_gate_command(db, limits, task, gate, command, &prior)
+                    .map_err(|e| {
+                        anyhow!("Gate '{}' command could not run: {}", gate.gate_type, e)
+                    })?;
+                if !passed {
+                    detail = Some(ended);
+                }
+                passed
+            }
+            (None, Some(command), None) => {
+                let last = attachments
+                    .iter()
+                    .filter(|a| a.attachment_type == gate.gate_type && a.name == GATE_OUTPUT_NAME)
+                    .max_by_key(|a| a.sequence)
+                    .map(|a| db.get_attachment(task_id, &a.attachment_type, a.sequence))
+                    .transpose()?
+                    .flatten()
+                    .and_then(|a| last_run_ending(&a.content, command).map(String::from));
+                match last {
+                    Some(ended) if ended == "exit 0" => true,
+                    Some(ended) => {
+                        detail = Some(format!("last run: {}", ended));
+                        false
+                    }
+                    None => {
+                        detail = Some("not run yet; runs on the transition".to_string());
+                        false
+                    }
+                }
+            }
+            _ => attachment_types.contains(&gate.gate_type),
+        };
 
         if !satisfied {
             match gate.enforcement {
@@ -76,6 +241,9 @@ pub fn evaluate_gates(
                 enforcement: gate.enforcement,
                 description: gate.description.clone(),
                 satisfied: false,
+                predicate: gate.sql.is_some(),
+                command: gate.command.is_some(),
+                detail,
             });
         }
         // Satisfied gates are omitted from results per spec
@@ -95,6 +263,175 @@ pub fn evaluate_gates(
     })
 }
 
+/// Run a gate's SQL predicate for a task: true when the first column of the
+/// first row is a non-zero number or non-empty text.
+fn predicate_holds(db: &Database, task_id: &str, sql: &str) -> Result<bool> {
+    validate_readonly_sql(sql)?;
+    db.with_read_conn(|conn| {
+        let mut stmt = conn.prepare(sql)?;
+        // Readers are query-only, but in-memory databases read on the writer
+        if !stmt.readonly() {
+            bail!("gate SQL must be read-only");
+        }
+        let mut rows = match stmt.parameter_count() {
+            0 => stmt.query([])?,
+            1 if stmt.parameter_index(":task_id")?.is_some() => {
+                stmt.query(rusqlite::named_params! { ":task_id": task_id })?
+            }
+            _ => bail!("gate SQL may only use the :task_id parameter"),
+        };
+        let Some(row) = rows.next()? else {
+            return Ok(false);
+        };
+        Ok(match row.get_ref(0)? {
+            ValueRef::Null => false,
+            ValueRef::Integer(i) => i != 0,
+            ValueRef::Real(f) => f != 0.0,
+            ValueRef::Text(t) | ValueRef::Blob(t) => !t.is_empty(),
+        })
+    })
+}
+
+/// Run a gate's command for a task and attach its output under the gate's
+/// type in place of the `prior` run's. Returns whether it exited 0 and how it
+/// ended.
+fn run_gate_command(
+    db: &Database,
+    limits: &AttachmentsConfig,
+    task: &Task,
+    gate: &GateDefinition,
+    command: &str,
+    prior: &[AttachmentMeta],
+) -> Result<(bool, String)> {
+    // Output goes to a file rather than pipes, so a killed command's
+    // children cannot hold the read open
+    let log_path = std::env::temp_dir().join(format!("task-graph-gate-{}.log", Ulid::new()));
+    let log = File::create(&log_path)?;
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell
+        .arg(command)
+        .env("TASK_GRAPH_TASK_ID", &task.id)
+        .env("TASK_GRAPH_TASK_TITLE", &task.title)
+        .env("TASK_GRAPH_TASK_STATUS", &task.status)
+        .env("TASK_GRAPH_TASK_PHASE", task.phase.as_deref().unwrap_or(""))
+        .env("TASK_GRAPH_GATE", &gate.gate_type)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+
+    let timeout = gate
+        .timeout_seconds
+        .unwrap_or(DEFAULT_GATE_COMMAND_TIMEOUT_SECS);
+    let started = Instant::now();
+    let exit = match shell.spawn() {
+        Ok(child) => wait_with_timeout(child, Duration::from_secs(timeout)),
+        Err(e) => Err(e.into()),
+    };
+    let output = fs::read(&log_path);
+    let _ = fs::remove_file(&log_path);
+    let exit = exit?;
+    let output = String::from_utf8_lossy(&output?).into_owned();
+
+    let (passed, ended) = match exit {
+        Some(status) => (status.success(), exit_label(status)),
+        None => (false, format!("timed out after {}s", timeout)),
+    };
+    let content = format!(
+        "$ {}\n{} in {:.1}s\n\n{}",
+        command,
+        ended,
+        started.elapsed().as_secs_f64(),
+        tail(&output, MAX_GATE_OUTPUT_BYTES)
+    );
+
+    // Only the latest output is kept, and it counts against the quotas like
+    // any attachment. Output that does not fit is logged, not attached; the
+    // gate's outcome stands either way.
+    db.delete_attachments(prior)?;
+    match check_quotas(
+        db,
+        limits,
+        std::slice::from_ref(&task.id),
+        &gate.gate_type,
+        false,
+        content.len() as i64,
+    ) {
+        Ok(()) => {
+            db.add_attachment(
+                &task.id,
+                gate.gate_type.clone(),
+                GATE_OUTPUT_NAME.to_string(),
+                content,
+                Some("text/plain".to_string()),
+                None,
+            )?;
+        }
+        Err(e) => warn!(
+            task_id = %task.id,
+            gate = %gate.gate_type,
+            error = %e,
+            "Gate command output not attached"
+        ),
+    }
+    Ok((passed, ended))
+}
+
+/// How the run recorded in a gate output attachment ended (e.g. "exit 0"),
+/// or `None` if the output is from a different command.
+fn last_run_ending<'a>(content: &'a str, command: &str) -> Option<&'a str> {
+    let rest = content
+        .strip_prefix("$ ")?
+        .strip_prefix(command)?
+        .strip_prefix('\n')?;
+    Some(rest.lines().next()?.rsplit_once(" in ")?.0)
+}
+
+/// Wait for `child`, killing it once `timeout` passes. `None` means it was killed.
+fn wait_with_timeout(
+    mut child: std::process::Child,
+    timeout: Duration,
+) -> Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn exit_label(status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exit {}", code),
+        None => "killed by signal".to_string(),
+    }
+}
+
+/// The last `max` bytes of `text`, cut at a character boundary.
+fn tail(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut start = text.len() - max;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,6 +453,9 @@ mod tests {
             enforcement: GateEnforcement::Warn,
             description: "Tests must pass".to_string(),
             satisfied: false,
+            predicate: false,
+            command: false,
+            detail: None,
         };
 
         let json = serde_json::to_string(&gate).unwrap();
@@ -2,18 +2,24 @@
 //!
 //! This module exports the core components for testing and integration.
 
+pub mod blob_store;
 pub mod cli;
 pub mod config;
 pub mod dashboard;
 pub mod db;
+pub mod deadline_reminders;
 pub mod error;
 pub mod export;
 pub mod format;
 pub mod gates;
 pub mod logging;
+pub mod mark_watch;
+pub mod outline;
 pub mod paths;
 pub mod prompts;
 pub mod resources;
+pub mod source_scan;
+pub mod source_watch;
 pub mod subscriptions;
 pub mod tools;
 pub mod types;
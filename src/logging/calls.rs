@@ -0,0 +1,331 @@
+//! Structured per-tool call log.
+//!
+//! Every tool call appends one JSON line to `tool-calls.jsonl` in the log
+//! directory, independent of the tracing level. The file is rotated to
+//! `tool-calls.1.jsonl` (shifting older files up) once it would exceed the
+//! configured size or has been written for longer than the configured age.
+
+use crate::config::ToolLogConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// File name of the active call log.
+pub const CALL_LOG_FILE: &str = "tool-calls.jsonl";
+
+/// One tool call, as written to the call log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    /// When the call completed (epoch ms).
+    pub timestamp: i64,
+    /// Agent that made the call, when it identified itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_id: Option<String>,
+    pub tool: String,
+    pub duration_ms: u64,
+    /// Size of the response text in bytes (0 for failed calls).
+    pub result_bytes: usize,
+    /// `ok`, or the error code of a failed call.
+    pub outcome: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Filters for reading the call log. Records come back newest first.
+#[derive(Debug, Default)]
+pub struct CallLogQuery<'a> {
+    pub worker_id: Option<&'a str>,
+    pub tool: Option<&'a str>,
+    /// `ok`, `error` (any failure), or a specific error code.
+    pub outcome: Option<&'a str>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+impl CallLogQuery<'_> {
+    fn matches(&self, record: &ToolCallRecord) -> bool {
+        self.worker_id
+            .is_none_or(|w| record.worker_id.as_deref() == Some(w))
+            && self.tool.is_none_or(|t| record.tool == t)
+            && match self.outcome {
+                Some("error") => record.outcome != "ok",
+                Some(o) => record.outcome == o,
+                None => true,
+            }
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp <= until)
+    }
+}
+
+/// Read records matching all given filters from the active and rotated call
+/// log files under `dir`, newest first. Lines that do not parse are skipped.
+pub fn read_call_log(dir: &Path, query: &CallLogQuery<'_>) -> Result<Vec<ToolCallRecord>> {
+    let mut matched = Vec::new();
+    let wanted = query.limit.map(|l| l + query.offset);
+    let mut n = 0;
+    loop {
+        let path = call_log_path(dir, n);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => break,
+            Err(e) => return Err(e.into()),
+        };
+        for line in content.lines().rev() {
+            let Ok(record) = serde_json::from_str::<ToolCallRecord>(line) else {
+                continue;
+            };
+            if query.matches(&record) {
+                matched.push(record);
+            }
+        }
+        if wanted.is_some_and(|w| matched.len() >= w) {
+            break;
+        }
+        n += 1;
+    }
+    Ok(matched
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+/// The file currently being appended to.
+struct ActiveFile {
+    file: File,
+    size: u64,
+    /// Timestamp of the file's first record.
+    started_at: Option<i64>,
+}
+
+/// Appends [`ToolCallRecord`]s to the call log, rotating as configured.
+pub struct ToolCallLog {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    max_age_ms: Option<i64>,
+    max_files: usize,
+    active: Mutex<Option<ActiveFile>>,
+}
+
+/// Path of the `n`th rotated file (0 is the active file).
+pub fn call_log_path(dir: &Path, n: usize) -> PathBuf {
+    if n == 0 {
+        dir.join(CALL_LOG_FILE)
+    } else {
+        dir.join(format!("tool-calls.{}.jsonl", n))
+    }
+}
+
+impl ToolCallLog {
+    /// Create a call log writing under `dir`. Files are opened on first write.
+    pub fn new(dir: impl Into<PathBuf>, config: &ToolLogConfig) -> Self {
+        Self {
+            dir: dir.into(),
+            max_file_bytes: config.max_file_bytes,
+            max_age_ms: (config.max_age_hours > 0).then(|| config.max_age_hours as i64 * 3_600_000),
+            max_files: config.max_files,
+            active: Mutex::new(None),
+        }
+    }
+
+    /// Append a record, rotating first if it is due.
+    pub fn write(&self, record: &ToolCallRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let mut active = self.active.lock().unwrap();
+        if active.is_none() {
+            *active = Some(self.open()?);
+        }
+        let current = active.as_ref().unwrap();
+        let too_big = current.size > 0 && current.size + line.len() as u64 > self.max_file_bytes;
+        let too_old = match (self.max_age_ms, current.started_at) {
+            (Some(max_age), Some(started)) => record.timestamp - started >= max_age,
+            _ => false,
+        };
+        if too_big || too_old {
+            *active = None;
+            self.rotate()?;
+            *active = Some(self.open()?);
+        }
+
+        let current = active.as_mut().unwrap();
+        current.file.write_all(line.as_bytes())?;
+        current.size += line.len() as u64;
+        current.started_at.get_or_insert(record.timestamp);
+        Ok(())
+    }
+
+    /// Open the active file for appending, picking up where an earlier run left off.
+    fn open(&self) -> Result<ActiveFile> {
+        fs::create_dir_all(&self.dir)?;
+        let path = call_log_path(&self.dir, 0);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        let started_at = if size > 0 {
+            let mut first = String::new();
+            BufReader::new(File::open(&path)?).read_line(&mut first)?;
+            serde_json::from_str::<ToolCallRecord>(&first)
+                .ok()
+                .map(|r| r.timestamp)
+        } else {
+            None
+        };
+        Ok(ActiveFile {
+            file,
+            size,
+            started_at,
+        })
+    }
+
+    /// Shift rotated files up by one, dropping the oldest, and move the active
+    /// file to `tool-calls.1.jsonl`.
+    fn rotate(&self) -> Result<()> {
+        if self.max_files == 0 {
+            fs::remove_file(call_log_path(&self.dir, 0))?;
+            return Ok(());
+        }
+        let oldest = call_log_path(&self.dir, self.max_files);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for n in (0..self.max_files).rev() {
+            let from = call_log_path(&self.dir, n);
+            if from.exists() {
+                fs::rename(from, call_log_path(&self.dir, n + 1))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: i64, tool: &str) -> ToolCallRecord {
+        ToolCallRecord {
+            timestamp,
+            worker_id: Some("w1".to_string()),
+            tool: tool.to_string(),
+            duration_ms: 3,
+            result_bytes: 120,
+            outcome: "ok".to_string(),
+            error: None,
+        }
+    }
+
+    fn read(path: &Path) -> Vec<ToolCallRecord> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_rotates_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let line_len = serde_json::to_string(&record(0, "get")).unwrap().len() as u64 + 1;
+        let config = ToolLogConfig {
+            max_file_bytes: line_len * 2,
+            max_files: 2,
+            ..Default::default()
+        };
+        let log = ToolCallLog::new(dir.path(), &config);
+        for i in 0..7 {
+            log.write(&record(i, "get")).unwrap();
+        }
+
+        // 7 records, 2 per file: the oldest file beyond max_files is dropped
+        let times = |n| {
+            read(&call_log_path(dir.path(), n))
+                .iter()
+                .map(|r| r.timestamp)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(times(0), vec![6]);
+        assert_eq!(times(1), vec![4, 5]);
+        assert_eq!(times(2), vec![2, 3]);
+        assert!(!call_log_path(dir.path(), 3).exists());
+    }
+
+    #[test]
+    fn test_read_spans_rotated_files_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let line_len = serde_json::to_string(&record(0, "get")).unwrap().len() as u64 + 1;
+        let config = ToolLogConfig {
+            max_file_bytes: line_len * 2,
+            ..Default::default()
+        };
+        let log = ToolCallLog::new(dir.path(), &config);
+        for i in 0..5 {
+            let mut r = record(i, if i % 2 == 0 { "get" } else { "claim" });
+            if i == 3 {
+                r.outcome = "TASK_NOT_FOUND".to_string();
+            }
+            log.write(&r).unwrap();
+        }
+
+        let times = |query: CallLogQuery| {
+            read_call_log(dir.path(), &query)
+                .unwrap()
+                .iter()
+                .map(|r| r.timestamp)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(times(CallLogQuery::default()), vec![4, 3, 2, 1, 0]);
+        assert_eq!(
+            times(CallLogQuery {
+                tool: Some("get"),
+                since: Some(1),
+                ..Default::default()
+            }),
+            vec![4, 2]
+        );
+        assert_eq!(
+            times(CallLogQuery {
+                outcome: Some("error"),
+                ..Default::default()
+            }),
+            vec![3]
+        );
+        assert_eq!(
+            times(CallLogQuery {
+                limit: Some(2),
+                offset: 1,
+                ..Default::default()
+            }),
+            vec![3, 2]
+        );
+    }
+
+    #[test]
+    fn test_rotates_by_age_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ToolLogConfig {
+            max_age_hours: 1,
+            ..Default::default()
+        };
+        ToolCallLog::new(dir.path(), &config)
+            .write(&record(0, "create"))
+            .unwrap();
+
+        // A new writer picks up the existing file's start time
+        let log = ToolCallLog::new(dir.path(), &config);
+        log.write(&record(1_000, "update")).unwrap();
+        assert_eq!(read(&call_log_path(dir.path(), 0)).len(), 2);
+
+        log.write(&record(3_600_000, "claim")).unwrap();
+        assert_eq!(
+            read(&call_log_path(dir.path(), 0)),
+            vec![record(3_600_000, "claim")]
+        );
+        assert_eq!(read(&call_log_path(dir.path(), 1)).len(), 2);
+    }
+}
@@ -4,7 +4,10 @@
 //! - CLI/stderr/file via tracing
 //! - MCP client via `notify_logging_message`
 //!
-//! Uses MCP LoggingLevel as the canonical level type.
+//! Uses MCP LoggingLevel as the canonical level type. Tool calls are also
+//! recorded separately as JSONL under the log directory (see [`calls`]).
+
+pub mod calls;
 
 use rmcp::{
     RoleServer,
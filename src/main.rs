@@ -3,11 +3,11 @@
 //! A Rust MCP server providing atomic, token-efficient task management
 //! for multi-agent coordination.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use arc_swap::ArcSwap;
 use clap::Parser;
 use rmcp::{
-    ErrorData, RoleServer, ServerHandler, ServiceExt,
+    ErrorData, Peer, RoleServer, ServerHandler, ServiceExt,
     model::{
         CallToolRequestParams, CallToolResult, Content, InitializeResult,
         ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, PaginatedRequestParams,
@@ -22,17 +22,24 @@ use serde_json::{Value, json};
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::sync::Arc;
+use task_graph_mcp::cli::config::{ConfigArgs, ConfigCommand};
 use task_graph_mcp::cli::diff::DiffArgs;
 use task_graph_mcp::cli::diff::DiffFormat;
+use task_graph_mcp::cli::doctor::DoctorArgs;
 use task_graph_mcp::cli::export::ExportArgs;
 use task_graph_mcp::cli::import::ImportArgs;
+use task_graph_mcp::cli::purge::PurgeArgs;
 use task_graph_mcp::cli::{Cli, Command, UiMode as CliUiMode, migrate};
+use task_graph_mcp::config::effective::EffectiveConfig;
+use task_graph_mcp::config::schema::ConfigFileKind;
+use task_graph_mcp::config::status::{ConfigStatus, PendingRestart};
 use task_graph_mcp::config::{
-    AppConfig, Config, ConfigLoader, PhasesConfig, Prompts, ServerPaths, StatesConfig, UiMode,
+    AppConfig, Config, ConfigLoader, DeadlineRemindersConfig, PhasesConfig, Prompts, ServerPaths,
+    StatesConfig, UiMode,
     watcher::{WatchPaths, WatcherConfig, start_config_watcher},
     workflows::WorkflowsConfig,
 };
-use task_graph_mcp::dashboard;
+use task_graph_mcp::dashboard::{self, templates::TemplateOverrides};
 use task_graph_mcp::db::Database;
 use task_graph_mcp::db::export::ExportOptions;
 use task_graph_mcp::db::import::ImportMode;
@@ -40,8 +47,11 @@ use task_graph_mcp::error::ToolError;
 use task_graph_mcp::export::diff::{diff_snapshot_vs_database, diff_snapshots};
 use task_graph_mcp::export::{CURRENT_SCHEMA_VERSION, Snapshot};
 use task_graph_mcp::format::OutputFormat;
+use task_graph_mcp::logging::calls::ToolCallLog;
 use task_graph_mcp::logging::{LogLevelFilter, Logger};
+use task_graph_mcp::mark_watch::start_mark_watcher;
 use task_graph_mcp::resources::ResourceHandler;
+use task_graph_mcp::source_watch::start_source_watcher;
 use task_graph_mcp::subscriptions::{MutationKind, SubscriptionManager};
 use task_graph_mcp::tools::{ToolContext, ToolHandler};
 use tracing::{Level, debug, info, warn};
@@ -75,6 +85,9 @@ struct TaskGraphServer {
     /// Tracks which resource URIs the client has subscribed to for update
     /// notifications, enabling interrupt-style coordination instead of polling.
     subscriptions: Arc<SubscriptionManager>,
+    /// Peer of the client that last subscribed, used to send notifications from
+    /// background tasks (e.g., the mark watcher) outside of any request.
+    subscriber_peer: Arc<std::sync::Mutex<Option<Peer<RoleServer>>>>,
 }
 
 impl TaskGraphServer {
@@ -90,8 +103,11 @@ impl TaskGraphServer {
         default_page_size: i32,
         path_mapper: Arc<task_graph_mcp::paths::PathMapper>,
         level_filter: Arc<LogLevelFilter>,
+        call_log: Option<Arc<ToolCallLog>>,
+        effective_config: Arc<EffectiveConfig>,
+        config_status: Arc<ConfigStatus>,
     ) -> Self {
-        let tool_handler = Arc::new(ToolHandler::new(
+        let mut tool_handler = ToolHandler::new(
             Arc::clone(&db),
             media_dir,
             skills_dir.clone(),
@@ -101,10 +117,17 @@ impl TaskGraphServer {
             default_format,
             default_page_size,
             path_mapper,
-        ));
+        );
+        if let Some(call_log) = call_log {
+            tool_handler = tool_handler.with_call_log(call_log);
+        }
+        let tool_handler = Arc::new(tool_handler);
         // Auto-discover docs directory
         let docs_dir = discover_docs_dir();
-        let mut resource_handler = ResourceHandler::new(db, app_config).with_skills_dir(skills_dir);
+        let mut resource_handler = ResourceHandler::new(db, app_config)
+            .with_skills_dir(skills_dir)
+            .with_effective_config(effective_config)
+            .with_config_status(config_status);
         if let Some(ref dir) = docs_dir {
             resource_handler = resource_handler.with_docs_dir(dir.clone());
         }
@@ -116,7 +139,31 @@ impl TaskGraphServer {
             prompts: Arc::new(ArcSwap::from(prompts)),
             level_filter,
             subscriptions: Arc::new(SubscriptionManager::new()),
+            subscriber_peer: Arc::new(std::sync::Mutex::new(None)),
+        }
+    }
+
+    /// Notify subscribed resources about a mutation that happened outside a tool call.
+    fn notify_background_mutation(&self, mutation: MutationKind) {
+        self.tool_handler
+            .load()
+            .db
+            .aggregates()
+            .invalidate(&[mutation]);
+        let affected = self.subscriptions.affected_subscriptions(&[mutation]);
+        if affected.is_empty() {
+            return;
         }
+        let Some(peer) = self.subscriber_peer.lock().ok().and_then(|p| p.clone()) else {
+            return;
+        };
+        tokio::spawn(async move {
+            for uri in affected {
+                debug!(uri = %uri, "Sending background resource updated notification");
+                let param = ResourceUpdatedNotificationParam { uri };
+                let _ = peer.notify_resource_updated(param).await;
+            }
+        });
     }
 }
 
@@ -166,9 +213,12 @@ impl ServerHandler for TaskGraphServer {
     async fn subscribe(
         &self,
         request: SubscribeRequestParams,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> std::result::Result<(), ErrorData> {
         let uri = request.uri;
+        if let Ok(mut peer) = self.subscriber_peer.lock() {
+            *peer = Some(context.peer.clone());
+        }
         let is_new = self.subscriptions.subscribe(&uri);
         if is_new {
             info!(uri = %uri, "Client subscribed to resource");
@@ -221,30 +271,40 @@ impl ServerHandler for TaskGraphServer {
             .with_name(format!("tool:{}", tool_name));
         let tool_ctx = ToolContext::new(logger);
 
-        let handler = self.tool_handler.load();
+        let handler = self.tool_handler.load_full();
+        let db = Arc::clone(&handler.db);
+        // Mutations are keyed by the unprefixed name
+        let internal_name = handler
+            .config
+            .tools
+            .internal_name(&tool_name)
+            .unwrap_or(&tool_name)
+            .to_string();
         let args = Value::Object(request.arguments.unwrap_or_default());
-        match handler.call_tool(&tool_name, args, &tool_ctx).await {
+        let name = tool_name.clone();
+        let call = async move { handler.call_tool(&name, args, &tool_ctx).await };
+        match run_blocking(call).await {
             Ok(result) => {
                 let elapsed = start.elapsed();
                 debug!(tool = %tool_name, duration_ms = elapsed.as_millis() as u64, "Tool call succeeded");
 
+                let mutations = mutations_for_tool(&internal_name);
+                db.aggregates().invalidate(&mutations);
+
                 // Notify subscribed resources about mutations from this tool call.
                 // Only send notifications if the client has any active subscriptions
                 // to avoid unnecessary work.
-                if self.subscriptions.has_subscriptions() {
-                    let mutations = mutations_for_tool(&tool_name);
-                    if !mutations.is_empty() {
-                        let affected = self.subscriptions.affected_subscriptions(&mutations);
-                        if !affected.is_empty() {
-                            let peer = context.peer.clone();
-                            tokio::spawn(async move {
-                                for uri in affected {
-                                    debug!(uri = %uri, tool = %tool_name, "Sending resource updated notification");
-                                    let param = ResourceUpdatedNotificationParam { uri };
-                                    let _ = peer.notify_resource_updated(param).await;
-                                }
-                            });
-                        }
+                if self.subscriptions.has_subscriptions() && !mutations.is_empty() {
+                    let affected = self.subscriptions.affected_subscriptions(&mutations);
+                    if !affected.is_empty() {
+                        let peer = context.peer.clone();
+                        tokio::spawn(async move {
+                            for uri in affected {
+                                debug!(uri = %uri, tool = %tool_name, "Sending resource updated notification");
+                                let param = ResourceUpdatedNotificationParam { uri };
+                                let _ = peer.notify_resource_updated(param).await;
+                            }
+                        });
                     }
                 }
 
@@ -278,11 +338,8 @@ impl ServerHandler for TaskGraphServer {
                             duration_ms = elapsed.as_millis() as u64,
                             "Tool call failed with internal error"
                         );
-                        json!({
-                            "code": "INTERNAL_ERROR",
-                            "message": e.to_string()
-                        })
-                        .to_string()
+                        serde_json::to_string(&ToolError::internal(&e))
+                            .unwrap_or_else(|_| json!({ "error": e.to_string() }).to_string())
                     }
                 };
                 Ok(CallToolResult {
@@ -326,9 +383,10 @@ impl ServerHandler for TaskGraphServer {
         request: ReadResourceRequestParams,
         _context: RequestContext<RoleServer>,
     ) -> std::result::Result<ReadResourceResult, ErrorData> {
-        let handler = self.resource_handler.load();
+        let handler = self.resource_handler.load_full();
         let uri_string = request.uri.to_string();
-        match handler.read_resource(&uri_string).await {
+        let uri = uri_string.clone();
+        match run_blocking(async move { handler.read_resource(&uri).await }).await {
             Ok(result) => Ok(ReadResourceResult {
                 contents: vec![ResourceContents::text(
                     serde_json::to_string_pretty(&result).unwrap_or_default(),
@@ -350,35 +408,67 @@ impl ServerHandler for TaskGraphServer {
     }
 }
 
+/// Run a tool call or resource read on the blocking thread pool.
+///
+/// Handlers call rusqlite directly, so a slow query (an FTS rebuild, a large
+/// tree read) would otherwise hold a runtime worker and stall notification
+/// delivery and other requests. The future is driven on a blocking thread;
+/// its own awaits (client logging, long polls) still run on the runtime.
+async fn run_blocking<F, T>(future: F) -> Result<T>
+where
+    F: std::future::Future<Output = Result<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || runtime.block_on(future))
+        .await
+        .map_err(|e| anyhow::anyhow!("Handler task failed: {}", e))?
+}
+
 /// Map a tool name to the mutation categories it causes.
 /// Used to determine which subscribed resource URIs need notifications
 /// after a successful tool call.
 fn mutations_for_tool(tool_name: &str) -> Vec<MutationKind> {
     match tool_name {
         // Task mutations
-        "create" | "create_tree" | "delete" | "rename" | "scan" => {
+        "create" | "create_tree" | "clone_tree" | "split" | "delete" | "rename" | "scan"
+        | "restore" | "purge" | "bulk_delete" | "bulk_archive" | "scan_source" => {
             vec![MutationKind::TaskChanged]
         }
         // Update can change status, which affects claimed/ready/blocked views
         "update" => vec![MutationKind::TaskChanged],
         // Claiming changes task status and agent claims
-        "claim" => vec![MutationKind::TaskChanged, MutationKind::AgentChanged],
+        "claim" | "claim_many" | "reserve" => {
+            vec![MutationKind::TaskChanged, MutationKind::AgentChanged]
+        }
         // Dependency mutations affect ready/blocked status
-        "link" | "unlink" | "relink" => {
+        "link" | "unlink" | "relink" | "sequence" => {
             vec![MutationKind::DependencyChanged, MutationKind::TaskChanged]
         }
         // File coordination
         "mark_file" | "unmark_file" => vec![MutationKind::FileMarkChanged],
         // Agent lifecycle
-        "connect" | "disconnect" | "cleanup_stale" => vec![MutationKind::AgentChanged],
+        "connect" | "disconnect" | "cleanup_stale" | "register_group" => {
+            vec![MutationKind::AgentChanged]
+        }
         // Attachments
-        "attach" | "detach" => vec![MutationKind::AttachmentChanged],
+        "attach" | "attach_commit" | "detach" | "prune_attachments" => {
+            vec![MutationKind::AttachmentChanged]
+        }
+        // Staged chunked uploads are not visible in any resource until committed
+        "attach_begin" | "attach_chunk" => vec![],
+        // Unreferenced blobs are not visible in any resource
+        "gc_blobs" => vec![],
         // Tracking tools update agent state
         "thinking" | "log_metrics" => vec![MutationKind::AgentChanged],
         // Read-only tools cause no mutations
-        "get" | "list_tasks" | "list_agents" | "list_marks" | "mark_updates" | "attachments"
-        | "get_schema" | "search" | "query" | "check_gates" | "task_history" | "get_metrics"
-        | "project_history" | "list_workflows" | "give_feedback" | "list_feedback" => vec![],
+        "get" | "list_tasks" | "list_agents" | "list_marks" | "mark_history" | "mark_updates"
+        | "attachments" | "get_attachment" | "get_schema" | "search" | "query" | "check_gates"
+        | "task_history" | "get_metrics" | "project_history" | "get_thoughts" | "audit"
+        | "get_logs" | "list_workflows" | "give_feedback" | "list_feedback" | "wait_for"
+        | "suggest_next_task" | "list_deleted" => {
+            vec![]
+        }
         // Skills tools are read-only
         name if name.starts_with("get_skill") || name.starts_with("list_skills") => vec![],
         // Unknown tools -- conservatively notify nothing
@@ -437,6 +527,13 @@ async fn main() -> Result<()> {
         }
     }
 
+    // The schema needs no configuration, so print it even when the config is invalid
+    if let Some(Command::Schema(args)) = &cli.command {
+        let schema = ConfigFileKind::from(args.kind).schema();
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(());
+    }
+
     // Load configuration using the new ConfigLoader with tier merging
     // If explicit config path given, set it as env var for ConfigLoader to pick up
     // SAFETY: This is safe at program startup before any other threads are spawned
@@ -446,6 +543,13 @@ async fn main() -> Result<()> {
             std::env::set_var("TASK_GRAPH_CONFIG_PATH", config_path);
         }
     }
+    // Likewise for the profile, so config reloads select the same one
+    if let Some(profile) = &cli.profile {
+        // SAFETY: still at startup, before any other threads are spawned
+        unsafe {
+            std::env::set_var("TASK_GRAPH_PROFILE", profile);
+        }
+    }
     let mut loader = ConfigLoader::load()?;
 
     // Track if using deprecated paths
@@ -456,6 +560,10 @@ async fn main() -> Result<()> {
         );
     }
 
+    if let Some(profile) = loader.profile() {
+        info!("Using config profile '{}'", profile);
+    }
+
     let config_path_used = loader
         .config_path()
         .map(|p| p.to_string_lossy().to_string());
@@ -493,20 +601,43 @@ async fn main() -> Result<()> {
         Some(Command::Diff(args)) => {
             run_diff(config, args)?;
         }
+        Some(Command::Purge(args)) => {
+            run_purge(config, args)?;
+        }
+        Some(Command::Doctor(args)) => {
+            run_doctor(config, args)?;
+        }
+        Some(Command::Schema(_)) => unreachable!("handled before loading configuration"),
+        Some(Command::Config(args)) => {
+            run_config(&loader, args)?;
+        }
         Some(Command::Migrate(args)) => {
             // Run migration command
             migrate::run_migrate(&args)?;
         }
         Some(Command::Serve) | None => {
             // Load prompts using the loader (before consuming it)
-            let prompts = loader.load_prompts();
+            let prompts = loader.load_prompts()?;
             // Load workflows configuration (contains states, phases, and transition prompts)
             // Also pre-loads named workflow configs (workflow-*.yaml) for per-worker selection
-            let workflows = load_workflows_with_cache(&loader);
+            let workflows = load_workflows_with_cache(&loader)?;
+            // Dashboard template overrides, searched first-found-wins like skills
+            let dashboard_templates =
+                TemplateOverrides::new(loader.tier_dirs(dashboard::templates::OVERRIDE_DIR));
+            // Record value sources now that CLI overrides are applied
+            let effective_config = loader.effective_config()?;
             // Get the final config
             let config = loader.into_config();
             // Default: run MCP server
-            run_server(config, prompts, workflows, config_path_used).await?;
+            run_server(
+                config,
+                prompts,
+                workflows,
+                config_path_used,
+                dashboard_templates,
+                effective_config,
+            )
+            .await?;
         }
     }
 
@@ -515,7 +646,7 @@ async fn main() -> Result<()> {
 
 /// Load workflows config and pre-load named workflow configs into cache.
 /// If default_workflow is configured, that workflow becomes the base config.
-fn load_workflows_with_cache(loader: &ConfigLoader) -> WorkflowsConfig {
+fn load_workflows_with_cache(loader: &ConfigLoader) -> Result<WorkflowsConfig> {
     let default_workflow_name = loader.config().server.default_workflow.clone();
 
     // If a default workflow is configured, load it as the base
@@ -531,11 +662,11 @@ fn load_workflows_with_cache(loader: &ConfigLoader) -> WorkflowsConfig {
                     name,
                     e
                 );
-                loader.load_workflows()
+                loader.load_workflows()?
             }
         }
     } else {
-        loader.load_workflows()
+        loader.load_workflows()?
     };
 
     // List all available named workflows and load them into cache
@@ -593,58 +724,76 @@ fn load_workflows_with_cache(loader: &ConfigLoader) -> WorkflowsConfig {
         );
     }
 
-    workflows
+    Ok(workflows)
 }
 
 /// Rebuild config from disk using a fresh ConfigLoader, then rebuild the
 /// ToolHandler and ResourceHandler and swap them into the server atomically.
 ///
 /// Immutable state (db, server_paths, path_mapper, level_filter, media/skills dirs)
-/// is carried over from the original server construction since those are not
-/// expected to change via config file edits.
+/// is carried over from the original server construction. Edits to those
+/// settings are logged and reported at `config://status` as pending a restart.
 fn reload_config(server: &TaskGraphServer, reload_ctx: &ReloadContext) {
     info!("Reloading configuration from disk...");
 
-    // Re-load configuration from disk using a fresh ConfigLoader
-    let loader = match ConfigLoader::load() {
-        Ok(loader) => loader,
+    match apply_reload(server, reload_ctx) {
+        Ok(pending) => {
+            for p in &pending {
+                warn!(
+                    "{} changed to {} but the server keeps using {} until restarted",
+                    p.key, p.configured, p.running
+                );
+            }
+            info!("Configuration reloaded successfully");
+        }
         Err(e) => {
-            warn!(
-                "Config reload failed during load: {}. Keeping current config.",
-                e
-            );
-            return;
+            warn!("Config reload failed: {:#}. Keeping current config.", e);
+            reload_ctx.status.record_failure(&format!("{:#}", e));
         }
-    };
+    }
+}
 
-    // Reload prompts
-    let prompts = loader.load_prompts();
+/// Load, validate, and swap in the configuration on disk. Returns the
+/// startup-only settings that changed and wait for a restart.
+fn apply_reload(
+    server: &TaskGraphServer,
+    reload_ctx: &ReloadContext,
+) -> Result<Vec<PendingRestart>> {
+    // Re-load configuration from disk using a fresh ConfigLoader
+    let loader = ConfigLoader::load().context("loading config")?;
+    let effective_config = loader
+        .effective_config()
+        .context("recording value sources")?;
 
-    // Reload workflows with cache
-    let workflows = load_workflows_with_cache(&loader);
+    // Reload prompts and workflows (with cache)
+    let prompts = loader.load_prompts().context("loading prompts")?;
+    let workflows = load_workflows_with_cache(&loader).context("loading workflows")?;
 
     // Re-derive states and phases from the new workflows
     let states_config: StatesConfig = (&workflows).into();
     let phases_config: PhasesConfig = (&workflows).into();
 
     // Validate
-    if let Err(e) = states_config.validate() {
-        warn!(
-            "Config reload failed validation (states): {}. Keeping current config.",
-            e
-        );
-        return;
-    }
+    states_config.validate().context("validating states")?;
 
     // Re-load the base config for dependencies, auto_advance, etc.
     let new_config = loader.into_config();
-    if let Err(e) = new_config.dependencies.validate() {
-        warn!(
-            "Config reload failed validation (dependencies): {}. Keeping current config.",
-            e
-        );
-        return;
-    }
+    new_config
+        .dependencies
+        .validate()
+        .context("validating dependencies")?;
+    new_config
+        .stale_claims
+        .validate(&states_config)
+        .context("validating stale_claims")?;
+    new_config
+        .visibility
+        .validate()
+        .context("validating visibility")?;
+    new_config
+        .source_scan
+        .validate()
+        .context("validating source_scan")?;
 
     // Wrap in Arc and build consolidated AppConfig
     let prompts = Arc::new(prompts);
@@ -658,8 +807,18 @@ fn reload_config(server: &TaskGraphServer, reload_ctx: &ReloadContext) {
     tags_config.register_workflow_tags(&workflows.all_role_tags());
     let tags_config = Arc::new(tags_config);
     let ids_config = Arc::new(new_config.ids.clone());
+    let custom_fields_config = Arc::new(new_config.custom_fields.clone());
+    let priorities_config = Arc::new(new_config.priorities.clone());
 
     let feedback_config = Arc::new(new_config.feedback.clone());
+    let groups_config = Arc::new(new_config.groups.clone());
+    let stale_claims_config = Arc::new(new_config.stale_claims.clone());
+    let claiming_config = Arc::new(new_config.claiming.clone());
+    let visibility_config = Arc::new(new_config.visibility.clone());
+    let source_scan_config = Arc::new(new_config.source_scan.clone());
+    let tools_config = Arc::new(new_config.tools.clone());
+    let skills_config = Arc::new(new_config.skills.clone());
+    let limits_config = Arc::new(new_config.limits.clone());
     let app_config = AppConfig::new(
         Arc::clone(&states_config),
         Arc::clone(&phases_config),
@@ -668,12 +827,22 @@ fn reload_config(server: &TaskGraphServer, reload_ctx: &ReloadContext) {
         Arc::clone(&attachments_config),
         Arc::clone(&tags_config),
         ids_config,
+        custom_fields_config,
+        priorities_config,
         Arc::clone(&workflows),
         feedback_config,
+        groups_config,
+        stale_claims_config,
+        claiming_config,
+        visibility_config,
+        source_scan_config,
+        tools_config,
+        skills_config,
+        limits_config,
     );
 
     // Build new ToolHandler
-    let new_tool_handler = Arc::new(ToolHandler::new(
+    let mut new_tool_handler = ToolHandler::new(
         Arc::clone(&reload_ctx.db),
         reload_ctx.media_dir.clone(),
         reload_ctx.skills_dir.clone(),
@@ -683,23 +852,36 @@ fn reload_config(server: &TaskGraphServer, reload_ctx: &ReloadContext) {
         reload_ctx.default_format,
         reload_ctx.default_page_size,
         Arc::clone(&reload_ctx.path_mapper),
-    ));
+    );
+    if let Some(ref call_log) = reload_ctx.call_log {
+        new_tool_handler = new_tool_handler.with_call_log(Arc::clone(call_log));
+    }
+    new_config
+        .tools
+        .validate(&new_tool_handler.tool_names())
+        .context("validating tools")?;
+    let new_tool_handler = Arc::new(new_tool_handler);
 
     // Build new ResourceHandler
     let docs_dir = discover_docs_dir();
     let mut new_resource_handler = ResourceHandler::new(Arc::clone(&reload_ctx.db), app_config)
-        .with_skills_dir(reload_ctx.skills_dir.clone());
+        .with_skills_dir(reload_ctx.skills_dir.clone())
+        .with_effective_config(Arc::new(effective_config))
+        .with_config_status(Arc::clone(&reload_ctx.status));
     if let Some(ref dir) = docs_dir {
         new_resource_handler = new_resource_handler.with_docs_dir(dir.clone());
     }
     let new_resource_handler = Arc::new(new_resource_handler);
 
+    // Cached aggregates were computed with the old configuration
+    reload_ctx.db.aggregates().clear();
+
     // Atomically swap in the new handlers
     server.tool_handler.store(new_tool_handler);
     server.resource_handler.store(new_resource_handler);
     server.prompts.store(prompts);
 
-    info!("Configuration reloaded successfully");
+    Ok(reload_ctx.status.record_reload(&new_config))
 }
 
 /// Immutable context needed by the reload path -- values that do not change
@@ -713,6 +895,8 @@ struct ReloadContext {
     path_mapper: Arc<task_graph_mcp::paths::PathMapper>,
     default_format: OutputFormat,
     default_page_size: i32,
+    call_log: Option<Arc<ToolCallLog>>,
+    status: Arc<ConfigStatus>,
 }
 
 /// Run the MCP server
@@ -721,6 +905,8 @@ async fn run_server(
     prompts: Prompts,
     workflows: WorkflowsConfig,
     config_path_used: Option<String>,
+    dashboard_templates: TemplateOverrides,
+    effective_config: EffectiveConfig,
 ) -> Result<()> {
     // Ensure directories exist
     config.ensure_db_dir()?;
@@ -734,6 +920,10 @@ async fn run_server(
     // Validate configuration
     states_config.validate()?;
     config.dependencies.validate()?;
+    config.stale_claims.validate(&states_config)?;
+    config.visibility.validate()?;
+    config.source_scan.validate()?;
+    config.deadline_reminders.validate()?;
 
     // Wrap in Arc
     let prompts = Arc::new(prompts);
@@ -775,7 +965,17 @@ async fn run_server(
     tags_config.register_workflow_tags(&workflows.all_role_tags());
     let tags_config = Arc::new(tags_config);
     let ids_config = Arc::new(config.ids.clone());
+    let custom_fields_config = Arc::new(config.custom_fields.clone());
+    let priorities_config = Arc::new(config.priorities.clone());
     let feedback_config = Arc::new(config.feedback.clone());
+    let groups_config = Arc::new(config.groups.clone());
+    let stale_claims_config = Arc::new(config.stale_claims.clone());
+    let claiming_config = Arc::new(config.claiming.clone());
+    let visibility_config = Arc::new(config.visibility.clone());
+    let source_scan_config = Arc::new(config.source_scan.clone());
+    let tools_config = Arc::new(config.tools.clone());
+    let skills_config = Arc::new(config.skills.clone());
+    let limits_config = Arc::new(config.limits.clone());
 
     let app_config = AppConfig::new(
         Arc::clone(&states_config),
@@ -785,8 +985,18 @@ async fn run_server(
         attachments_config,
         tags_config,
         ids_config,
+        custom_fields_config,
+        priorities_config,
         Arc::clone(&workflows),
         feedback_config,
+        groups_config,
+        stale_claims_config,
+        claiming_config,
+        visibility_config,
+        source_scan_config,
+        tools_config,
+        skills_config,
+        limits_config,
     );
 
     // Create path mapper from config
@@ -798,18 +1008,33 @@ async fn run_server(
     // Create level filter for unified logging (defaults to Debug - logs everything)
     let level_filter = Arc::new(LogLevelFilter::default());
 
+    // Structured per-tool call log, independent of the tracing level
+    let call_log = config.server.tool_log.enabled.then(|| {
+        Arc::new(ToolCallLog::new(
+            &config.server.log_dir,
+            &config.server.tool_log,
+        ))
+    });
+
+    let config_status = Arc::new(ConfigStatus::new(&config, &effective_config));
     let server = TaskGraphServer::new(
         Arc::clone(&db),
         config.server.media_dir.clone(),
         config.server.skills_dir.clone(),
         Arc::clone(&server_paths),
         prompts,
-        app_config,
+        app_config.clone(),
         config.server.default_format,
         config.server.default_page_size,
         Arc::clone(&path_mapper),
         level_filter,
+        call_log.clone(),
+        Arc::new(effective_config),
+        Arc::clone(&config_status),
     );
+    config
+        .tools
+        .validate(&server.tool_handler.load().tool_names())?;
 
     // Build the reload context with immutable state needed for config hot-reload
     let reload_ctx = ReloadContext {
@@ -820,11 +1045,66 @@ async fn run_server(
         path_mapper,
         default_format: config.server.default_format,
         default_page_size: config.server.default_page_size,
+        call_log,
+        status: config_status,
     };
 
     // Start config file watcher for hot-reload
     start_config_file_watcher(&server, reload_ctx, &config);
 
+    // Start the optional mark watcher (records files modified without a mark)
+    let _mark_watch_handle = if config.server.mark_watch.enabled {
+        let notify_server = server.clone();
+        let runtime = tokio::runtime::Handle::current();
+        match start_mark_watcher(
+            Arc::clone(&db),
+            &config.server.mark_watch,
+            move |_violations| {
+                let _guard = runtime.enter();
+                notify_server.notify_background_mutation(MutationKind::FileMarkChanged);
+            },
+        ) {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                warn!("Failed to start mark watcher: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Start the optional source watcher (turns TODO comment edits into task changes)
+    let _source_watch_handle = if config.source_scan.watch {
+        let notify_server = server.clone();
+        let runtime = tokio::runtime::Handle::current();
+        match start_source_watcher(Arc::clone(&db), app_config.clone(), move |_sync| {
+            let _guard = runtime.enter();
+            notify_server.notify_background_mutation(MutationKind::TaskChanged);
+        }) {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                warn!("Failed to start source watcher: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Release file marks whose lease (mark_file ttl_ms) has expired
+    start_mark_reaper(&server, Arc::clone(&db));
+
+    // Fire reminders before due_at deadlines and tag overdue tasks
+    if config.deadline_reminders.enabled {
+        start_deadline_reminders(
+            &server,
+            Arc::clone(&db),
+            config.deadline_reminders.clone(),
+            Arc::clone(&app_config.states),
+        );
+    }
+
     // Start the HTTP dashboard server only when UI mode is explicitly set to Web.
     // When mode is "none", skip the dashboard entirely (bug fix: dashboard was
     // previously starting regardless of the ui.mode config setting).
@@ -834,7 +1114,9 @@ async fn run_server(
             Some(dashboard::start_server_with_retry(
                 Arc::clone(&db),
                 &config.server.ui,
-                Arc::clone(&states_config),
+                app_config.clone(),
+                config.server.media_dir.clone(),
+                dashboard_templates,
             ))
         }
         UiMode::None => {
@@ -852,6 +1134,75 @@ async fn run_server(
     Ok(())
 }
 
+/// Interval between sweeps for expired file mark leases.
+const MARK_REAPER_INTERVAL_SECS: u64 = 10;
+
+/// Spawn a background task that periodically releases expired file marks and
+/// notifies subscribers of `query://files/marks` when any were released.
+fn start_mark_reaper(server: &TaskGraphServer, db: Arc<Database>) {
+    let server = server.clone();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(MARK_REAPER_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let db = Arc::clone(&db);
+            match tokio::task::spawn_blocking(move || db.reap_expired_marks()).await {
+                Ok(Ok(released)) if !released.is_empty() => {
+                    info!(count = released.len(), "Released expired file marks");
+                    server.notify_background_mutation(MutationKind::FileMarkChanged);
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => warn!("Failed to reap expired file marks: {}", e),
+                Err(e) => warn!("Mark reaper task failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Spawn a background task that sweeps deadlines every
+/// `deadline_reminders.interval_secs`, delivering due reminders to webhooks and
+/// notifying subscribers of `query://deadlines` (and of the task views when
+/// the overdue tag changed).
+fn start_deadline_reminders(
+    server: &TaskGraphServer,
+    db: Arc<Database>,
+    config: DeadlineRemindersConfig,
+    states_config: Arc<StatesConfig>,
+) {
+    let server = server.clone();
+    let config = Arc::new(config);
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(config.interval_secs));
+        loop {
+            interval.tick().await;
+            let (db, config, states_config) = (
+                Arc::clone(&db),
+                Arc::clone(&config),
+                Arc::clone(&states_config),
+            );
+            let sweep = tokio::task::spawn_blocking(move || {
+                task_graph_mcp::deadline_reminders::run_sweep(&db, &config, &states_config)
+            })
+            .await;
+            match sweep {
+                Ok(Ok(sweep)) => {
+                    if !sweep.retagged.is_empty() {
+                        info!(count = sweep.retagged.len(), "Updated overdue tags");
+                        server.notify_background_mutation(MutationKind::TaskChanged);
+                    }
+                    if !sweep.reminders.is_empty() {
+                        server.notify_background_mutation(MutationKind::DeadlineReminder);
+                    }
+                }
+                Ok(Err(e)) => warn!("Failed to sweep deadlines: {}", e),
+                Err(e) => warn!("Deadline reminder task failed: {}", e),
+            }
+        }
+    });
+}
+
 /// Start the config file watcher and spawn a background task that listens for
 /// change events and triggers a config reload.
 ///
@@ -932,78 +1283,33 @@ fn run_export(config: &Config, args: ExportArgs) -> Result<()> {
     // Open database
     let db = Database::open(&config.server.db_path)?;
 
+    // A subtree as a GitHub task list
+    if let Some(ref task_id) = args.markdown {
+        let Some(tree) = db.get_task_tree(task_id)? else {
+            anyhow::bail!("Task not found: {}", task_id);
+        };
+        let markdown = task_graph_mcp::export::checklist::render_checklist(&tree, &config.states);
+        if let Some(ref path) = args.output {
+            std::fs::write(path, &markdown)?;
+            eprintln!("Exported to {}", path.display());
+        } else {
+            print!("{}", markdown);
+        }
+        return Ok(());
+    }
+
     // Build export options from CLI args
     let options = ExportOptions {
         exclude_deleted: args.exclude_deleted,
         tables: args.tables_to_export(),
+        snapshot_at: args.snapshot_at_ms()?,
+        states_config: config.states.clone(),
     };
 
     // Export tables
     let export_tables = db.export_tables(&options)?;
 
-    // Build snapshot
-    let mut snapshot = Snapshot::new();
-
-    // Convert ExportTables to Snapshot tables format
-    if let Some(tasks) = export_tables.tasks {
-        snapshot.tables.insert(
-            "tasks".to_string(),
-            tasks
-                .into_iter()
-                .map(|t| serde_json::to_value(t).unwrap())
-                .collect(),
-        );
-    }
-    if let Some(deps) = export_tables.dependencies {
-        snapshot.tables.insert(
-            "dependencies".to_string(),
-            deps.into_iter()
-                .map(|d| serde_json::to_value(d).unwrap())
-                .collect(),
-        );
-    }
-    if let Some(attachments) = export_tables.attachments {
-        snapshot.tables.insert(
-            "attachments".to_string(),
-            attachments
-                .into_iter()
-                .map(|a| serde_json::to_value(a).unwrap())
-                .collect(),
-        );
-    }
-    if let Some(tags) = export_tables.task_tags {
-        snapshot.tables.insert(
-            "task_tags".to_string(),
-            tags.into_iter()
-                .map(|t| serde_json::to_value(t).unwrap())
-                .collect(),
-        );
-    }
-    if let Some(tags) = export_tables.task_needed_tags {
-        snapshot.tables.insert(
-            "task_needed_tags".to_string(),
-            tags.into_iter()
-                .map(|t| serde_json::to_value(t).unwrap())
-                .collect(),
-        );
-    }
-    if let Some(tags) = export_tables.task_wanted_tags {
-        snapshot.tables.insert(
-            "task_wanted_tags".to_string(),
-            tags.into_iter()
-                .map(|t| serde_json::to_value(t).unwrap())
-                .collect(),
-        );
-    }
-    if let Some(sequence) = export_tables.task_sequence {
-        snapshot.tables.insert(
-            "task_sequence".to_string(),
-            sequence
-                .into_iter()
-                .map(|s| serde_json::to_value(s).unwrap())
-                .collect(),
-        );
-    }
+    let snapshot = Snapshot::from_tables(export_tables)?;
 
     // Serialize to JSON
     let json_output = snapshot.to_json_pretty()?;
@@ -1049,10 +1355,21 @@ fn run_export(config: &Config, args: ExportArgs) -> Result<()> {
 
 /// Run the import command
 fn run_import(config: &Config, args: ImportArgs) -> Result<()> {
-    use task_graph_mcp::db::import::{ImportOptions, remap_snapshot};
+    use task_graph_mcp::db::import::{ImportOptions, remap_snapshot_with};
+    use task_graph_mcp::export::checklist::{checklist_snapshot, skip_existing};
+
+    // Checklists must not replace existing data, so they are always merged
+    // with fresh IDs; items naming a task the database has are left out
+    let checklist = args.is_checklist();
+    let remap_ids = args.remap_ids || checklist;
 
     // Load snapshot from file
-    let mut snapshot = Snapshot::from_file(&args.file)?;
+    let mut snapshot = if checklist {
+        let markdown = std::fs::read_to_string(&args.file)?;
+        checklist_snapshot(&markdown, &config.states, &config.priorities)?
+    } else {
+        Snapshot::from_file(&args.file)?
+    };
 
     // Check schema compatibility
     if !snapshot.is_schema_compatible() {
@@ -1062,27 +1379,26 @@ fn run_import(config: &Config, args: ImportArgs) -> Result<()> {
         );
     }
 
-    // Apply ID remapping if requested
-    let remap_result = if args.remap_ids {
-        let ids_config = config.ids.clone();
-        let (remapped, id_map) = remap_snapshot(&snapshot, &ids_config)?;
-        snapshot = remapped;
-        eprintln!("Remapped {} task IDs to fresh IDs", id_map.len());
-        Some(id_map)
-    } else {
-        None
-    };
-
     // Open database
     let db = Database::open(&config.server.db_path)?;
 
+    if checklist {
+        let existing = skip_existing(&mut snapshot, |id| db.task_exists(id))?;
+        if !existing.is_empty() {
+            eprintln!(
+                "Skipping {} task(s) already in the database",
+                existing.len()
+            );
+        }
+    }
+
     // Determine import options
-    let mut options = if args.merge {
+    let mut options = if args.merge || checklist {
         ImportOptions::merge()
     } else {
         ImportOptions::replace()
     };
-    if args.remap_ids {
+    if remap_ids {
         options.remap_ids = true;
     }
     if let Some(ref parent) = args.parent {
@@ -1090,11 +1406,22 @@ fn run_import(config: &Config, args: ImportArgs) -> Result<()> {
     }
 
     if args.dry_run {
+        // Preview the ID remapping without consuming sequence numbers
+        let remap_result = if remap_ids {
+            let (remapped, id_map) = db.preview_remap(&snapshot, &config.ids, |s, next| {
+                remap_snapshot_with(s, next)
+            })?;
+            snapshot = remapped;
+            Some(id_map)
+        } else {
+            None
+        };
+
         // Dry run - just validate and report
         let result = db.preview_import(&snapshot, &options);
         println!("Dry run results:");
         println!("  Mode: {:?}", result.mode);
-        if args.remap_ids {
+        if remap_ids {
             println!("  ID remapping: enabled");
         }
         println!("  Database is empty: {}", result.database_is_empty);
@@ -1138,7 +1465,7 @@ fn run_import(config: &Config, args: ImportArgs) -> Result<()> {
     // Check if database has existing data and we're in replace mode without force
     // When remap_ids is active, the IDs are fresh so merge is the natural mode,
     // but if they chose replace mode, still check.
-    if options.mode == ImportMode::Replace && !args.force && !args.remap_ids {
+    if options.mode == ImportMode::Replace && !args.force && !remap_ids {
         let preview = db.preview_import(&snapshot, &options);
         if !preview.database_is_empty {
             anyhow::bail!(
@@ -1147,17 +1474,21 @@ fn run_import(config: &Config, args: ImportArgs) -> Result<()> {
         }
     }
 
-    // Perform import
-    let mut result = db.import_snapshot(&snapshot, &options)?;
-
-    // Attach the remap table to the result
-    if let Some(id_map) = remap_result {
-        result.id_remap = Some(id_map);
+    // Perform import; remapped IDs are generated inside the import transaction
+    let result = if remap_ids {
+        db.import_snapshot_remapped(&snapshot, &options, &config.ids, |s, next| {
+            remap_snapshot_with(s, next)
+        })?
+    } else {
+        db.import_snapshot(&snapshot, &options)?
+    };
+    if let Some(ref id_map) = result.id_remap {
+        eprintln!("Remapped {} task IDs to fresh IDs", id_map.len());
     }
 
     println!("Import complete:");
     println!("  Mode: {:?}", options.mode);
-    if args.remap_ids {
+    if remap_ids {
         println!("  ID remapping: enabled");
     }
     println!("  Rows imported:");
@@ -1176,7 +1507,7 @@ fn run_import(config: &Config, args: ImportArgs) -> Result<()> {
             println!("    {}: {}", table, count);
         }
     }
-    println!("  FTS indexes rebuilt: {}", result.fts_rebuilt);
+    println!("  FTS re-indexed: {} tasks", result.fts_tasks_synced);
     if !result.warnings.is_empty() {
         println!("  Warnings:");
         for warning in &result.warnings {
@@ -1206,6 +1537,100 @@ fn run_import(config: &Config, args: ImportArgs) -> Result<()> {
 }
 
 /// Run the diff command
+fn run_purge(config: &Config, args: PurgeArgs) -> Result<()> {
+    use task_graph_mcp::tools::trash::remove_purged_media;
+
+    if args.older_than_days < 0 {
+        anyhow::bail!("--older-than-days must not be negative");
+    }
+
+    let db = Database::open(&config.server.db_path)?;
+    let cutoff = task_graph_mcp::db::now_ms() - args.older_than_days * 24 * 60 * 60 * 1000;
+    let result = db.purge_deleted(cutoff, args.dry_run)?;
+
+    if args.dry_run {
+        println!("Dry run results:");
+        println!(
+            "  Would purge {} task(s) deleted over {} day(s) ago",
+            result.task_ids.len(),
+            args.older_than_days
+        );
+    } else {
+        println!("Purge complete:");
+        println!(
+            "  Purged {} task(s) deleted over {} day(s) ago",
+            result.task_ids.len(),
+            args.older_than_days
+        );
+    }
+    for id in &result.task_ids {
+        println!("    {}", id);
+    }
+    if !result.rows.is_empty() {
+        println!(
+            "  Rows {}:",
+            if args.dry_run { "to delete" } else { "deleted" }
+        );
+        for (table, count) in &result.rows {
+            println!("    {}: {}", table, count);
+        }
+    }
+
+    if !args.dry_run && !result.task_ids.is_empty() {
+        let files_deleted = remove_purged_media(&db, &config.server.media_dir, &result)?;
+        println!("  Media files deleted: {}", files_deleted);
+    }
+
+    Ok(())
+}
+
+fn run_config(loader: &ConfigLoader, args: ConfigArgs) -> Result<()> {
+    match args.command {
+        ConfigCommand::Show(args) => {
+            let mut effective = loader.effective_config()?;
+            if let Some(ref key) = args.key {
+                effective.retain_prefix(key);
+                if effective.values.is_empty() {
+                    anyhow::bail!("No configuration values under '{}'", key);
+                }
+            }
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&effective)?);
+            } else {
+                print!("{}", effective.to_text());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_doctor(config: &Config, args: DoctorArgs) -> Result<()> {
+    let db = Database::open(&config.server.db_path)?;
+    let reports = db.audit_query_plans()?;
+
+    println!("Query plans ({}):", config.server.db_path.display());
+    for report in &reports {
+        let verdict = if report.is_ok() { "ok" } else { "FULL SCAN" };
+        println!("  {:<24} {}", report.name, verdict);
+        if args.plans || !report.is_ok() {
+            for step in &report.plan {
+                println!("      {}", step);
+            }
+        }
+    }
+
+    let failing = reports.iter().filter(|r| !r.is_ok()).count();
+    if failing > 0 {
+        anyhow::bail!(
+            "{} of {} queries scan whole tables; check for missing indexes",
+            failing,
+            reports.len()
+        );
+    }
+    println!("All {} queries use indexes.", reports.len());
+    Ok(())
+}
+
 fn run_diff(config: &Config, args: DiffArgs) -> Result<()> {
     // Load source snapshot
     let source = Snapshot::from_file(&args.source)?;
@@ -0,0 +1,166 @@
+//! Filesystem watcher that verifies advisory file marks.
+//!
+//! Marks are advisory, so nothing stops an agent (or a human) from editing a file
+//! nobody marked. When `server.mark_watch.enabled` is set, this watcher observes the
+//! workspace with the same debounced `notify` watcher used for config hot-reload and
+//! records every modified file that no mark covers in the `mark_violations` table.
+//! The server then notifies subscribers of `query://files/marks`.
+//!
+//! The watcher cannot tell who changed a file, so a change to a marked file is
+//! assumed to come from the mark holder.
+
+use crate::config::MarkWatchConfig;
+use crate::db::Database;
+use notify_debouncer_mini::{DebouncedEvent, DebouncedEventKind, new_debouncer};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, mpsc};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// Handle to the running mark watcher (dropping it does not stop the watcher thread,
+/// which lives as long as the process).
+pub struct MarkWatchHandle {
+    /// Absolute workspace root being watched.
+    pub root: PathBuf,
+    _task_handle: tokio::task::JoinHandle<()>,
+}
+
+/// Resolve the configured root to an absolute path without following symlinks,
+/// matching how `mark_file` normalizes paths.
+pub fn resolve_root(root: Option<&Path>) -> std::io::Result<PathBuf> {
+    let cwd = std::env::current_dir()?;
+    let joined = match root {
+        Some(r) if r.is_absolute() => r.to_path_buf(),
+        Some(r) => cwd.join(r),
+        None => cwd,
+    };
+    Ok(joined
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect())
+}
+
+/// Convert debounced events into the forward-slash file paths that marks use,
+/// skipping directories and anything under an ignored path component.
+pub fn changed_files(events: &[DebouncedEvent], root: &Path, ignore: &[String]) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for event in events {
+        if !matches!(
+            event.kind,
+            DebouncedEventKind::Any | DebouncedEventKind::AnyContinuous
+        ) {
+            continue;
+        }
+        let Ok(relative) = event.path.strip_prefix(root) else {
+            continue;
+        };
+        let ignored = relative.components().any(|c| match c {
+            Component::Normal(name) => ignore.iter().any(|i| name == i.as_str()),
+            _ => false,
+        });
+        if ignored || event.path.is_dir() {
+            continue;
+        }
+        let path = event.path.to_string_lossy().replace('\\', "/");
+        if !files.contains(&path) {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Start watching the workspace for unmarked modifications.
+///
+/// `on_violation` is called with the newly recorded paths after each batch that
+/// produced at least one violation.
+pub fn start_mark_watcher<F>(
+    db: Arc<Database>,
+    config: &MarkWatchConfig,
+    on_violation: F,
+) -> Result<MarkWatchHandle, notify::Error>
+where
+    F: Fn(Vec<String>) + Send + 'static,
+{
+    let root = resolve_root(config.root.as_deref()).map_err(notify::Error::io)?;
+    let (notify_tx, notify_rx) = mpsc::channel();
+
+    let mut debouncer = new_debouncer(Duration::from_millis(config.debounce_ms), notify_tx)?;
+    debouncer
+        .watcher()
+        .watch(&root, notify::RecursiveMode::Recursive)?;
+    info!(
+        "Watching workspace for unmarked changes: {}",
+        root.display()
+    );
+
+    let ignore = config.ignore.clone();
+    let watch_root = root.clone();
+    let task_handle = tokio::task::spawn_blocking(move || {
+        // Keep the debouncer alive
+        let _debouncer = debouncer;
+
+        loop {
+            match notify_rx.recv() {
+                Ok(Ok(events)) => {
+                    let files = changed_files(&events, &watch_root, &ignore);
+                    if files.is_empty() {
+                        continue;
+                    }
+                    match db.record_mark_violations(&files) {
+                        Ok(violations) if !violations.is_empty() => {
+                            warn!("Files modified without a mark: {}", violations.join(", "));
+                            on_violation(violations);
+                        }
+                        Ok(_) => {}
+                        Err(e) => error!("Failed to record mark violations: {}", e),
+                    }
+                }
+                Ok(Err(e)) => error!("Mark watcher error: {}", e),
+                Err(_) => {
+                    info!("Mark watcher channel closed, stopping");
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(MarkWatchHandle {
+        root,
+        _task_handle: task_handle,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(path: &str) -> DebouncedEvent {
+        DebouncedEvent {
+            path: PathBuf::from(path),
+            kind: DebouncedEventKind::Any,
+        }
+    }
+
+    #[test]
+    fn test_changed_files_skips_ignored_and_outside_paths() {
+        let ignore = vec![".git".to_string(), "target".to_string()];
+        let events = vec![
+            event("/proj/src/main.rs"),
+            event("/proj/.git/index"),
+            event("/proj/target/debug/app"),
+            event("/other/file.rs"),
+            event("/proj/src/main.rs"),
+        ];
+
+        let files = changed_files(&events, Path::new("/proj"), &ignore);
+        assert_eq!(files, vec!["/proj/src/main.rs".to_string()]);
+    }
+
+    #[test]
+    fn test_resolve_root_strips_cur_dir() {
+        let root = resolve_root(Some(Path::new("./src"))).unwrap();
+        assert!(root.is_absolute());
+        assert!(root.ends_with("src"));
+        assert!(!root.components().any(|c| matches!(c, Component::CurDir)));
+    }
+}
@@ -0,0 +1,370 @@
+//! Task outlines: nested markdown lists and YAML, read into `create_tree` input.
+//!
+//! Markdown outlines nest by indentation. Headings nest by level and contain
+//! the items under them, and other indented text becomes the description of
+//! the item above it:
+//!
+//! ```text
+//! # Auth rewrite
+//! - Login form #frontend
+//!   - [ ] Validation !high
+//! - Token refresh #backend !8
+//!   Rotate refresh tokens on every use.
+//! ```
+//!
+//! Trailing `#tag` words add tags and a trailing `!priority` word (a number or
+//! configured label) sets the priority. YAML outlines are a node or a list of
+//! nodes, where a node is a title string (with the same trailing words, quoted
+//! since `#` starts a YAML comment), a mapping of task fields with `children`,
+//! or `Title: [children]`.
+
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Map, Value, json};
+
+/// Syntax of an outline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutlineFormat {
+    #[default]
+    Markdown,
+    Yaml,
+}
+
+impl OutlineFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Some(Self::Markdown),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// One outline entry: a task to create, with its subtasks.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutlineItem {
+    pub title: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    /// Priority as written: a number or a configured label.
+    pub priority: Option<String>,
+    /// Checkbox state of a markdown task-list item (`[ ]` or `[x]`).
+    pub checked: Option<bool>,
+    /// Other task fields given in YAML, passed through to the tree input.
+    pub fields: Map<String, Value>,
+    pub children: Vec<OutlineItem>,
+}
+
+impl OutlineItem {
+    /// An item from a title line, taking trailing `#tag` and `!priority` words.
+    fn from_line(line: &str) -> Self {
+        let mut words: Vec<&str> = line.split_whitespace().collect();
+        let mut item = OutlineItem::default();
+        while let Some(word) = words.last() {
+            if let Some(tag) = word.strip_prefix('#').filter(|t| is_token(t)) {
+                item.tags.insert(0, tag.to_string());
+            } else if let Some(priority) = word.strip_prefix('!').filter(|p| is_token(p))
+                && item.priority.is_none()
+            {
+                item.priority = Some(priority.to_string());
+            } else {
+                break;
+            }
+            words.pop();
+        }
+        item.title = words.join(" ");
+        item
+    }
+
+    /// The `create_tree` node for this item and its children.
+    pub fn to_tree(&self) -> Value {
+        let mut node = self.fields.clone();
+        node.insert("title".to_string(), json!(self.title));
+        if let Some(ref description) = self.description {
+            node.insert("description".to_string(), json!(description));
+        }
+        if !self.tags.is_empty() {
+            node.insert("tags".to_string(), json!(self.tags));
+        }
+        if let Some(ref priority) = self.priority {
+            node.insert("priority".to_string(), json!(priority));
+        }
+        if !self.children.is_empty() {
+            let children: Vec<Value> = self.children.iter().map(OutlineItem::to_tree).collect();
+            node.insert("children".to_string(), json!(children));
+        }
+        Value::Object(node)
+    }
+}
+
+/// Whether a `#tag` or `!priority` word is a plain token (not e.g. `C#` or `!`).
+fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '/' | '.'))
+}
+
+/// Parse an outline into its top-level items.
+pub fn parse_outline(text: &str, format: OutlineFormat) -> Result<Vec<OutlineItem>> {
+    let items = match format {
+        OutlineFormat::Markdown => parse_markdown(text)?,
+        OutlineFormat::Yaml => parse_yaml(text)?,
+    };
+    if items.is_empty() {
+        bail!("Outline has no items");
+    }
+    Ok(items)
+}
+
+/// A list item line: (indent, text after the marker), or None for other lines.
+fn list_item(line: &str) -> Option<(usize, &str)> {
+    let indent: usize = line
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+    let rest = line.trim_start();
+    let after_marker = if let Some(r) = rest
+        .strip_prefix("- ")
+        .or_else(|| rest.strip_prefix("* "))
+        .or_else(|| rest.strip_prefix("+ "))
+    {
+        r
+    } else {
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        let r = &rest[digits..];
+        if digits == 0 {
+            return None;
+        }
+        r.strip_prefix(". ").or_else(|| r.strip_prefix(") "))?
+    };
+    Some((indent, after_marker.trim()))
+}
+
+/// A heading line: (level, text).
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    line[level..]
+        .strip_prefix(' ')
+        .map(|text| (level, text.trim()))
+}
+
+/// Take a leading `[ ]` / `[x]` checkbox off list item text.
+fn checkbox(text: &str) -> (Option<bool>, &str) {
+    for (mark, checked) in [("[ ]", false), ("[x]", true), ("[X]", true)] {
+        if let Some(rest) = text.strip_prefix(mark) {
+            return (Some(checked), rest.trim_start());
+        }
+    }
+    (None, text)
+}
+
+/// Parse a markdown outline. Nesting keys: headings sort before any list
+/// indentation, so list items always nest under the heading above them.
+pub fn parse_markdown(text: &str) -> Result<Vec<OutlineItem>> {
+    let mut roots: Vec<OutlineItem> = Vec::new();
+    let mut stack: Vec<(i64, OutlineItem)> = Vec::new();
+
+    fn close(stack: &mut Vec<(i64, OutlineItem)>, roots: &mut Vec<OutlineItem>, key: i64) {
+        while stack.last().is_some_and(|(k, _)| *k >= key) {
+            let (_, item) = stack.pop().unwrap();
+            match stack.last_mut() {
+                Some((_, parent)) => parent.children.push(item),
+                None => roots.push(item),
+            }
+        }
+    }
+
+    let mut in_fence = false;
+    for (n, line) in text.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if line.trim().is_empty() || in_fence {
+            continue;
+        }
+        let (key, item) = if let Some((level, text)) = heading(line) {
+            (level as i64 - 10, OutlineItem::from_line(text))
+        } else if let Some((indent, text)) = list_item(line) {
+            let (checked, text) = checkbox(text);
+            let mut item = OutlineItem::from_line(text);
+            item.checked = checked;
+            (indent as i64, item)
+        } else {
+            // Other text describes the item above it
+            if let Some((_, item)) = stack.last_mut() {
+                let text = line.trim();
+                match item.description {
+                    Some(ref mut d) => {
+                        d.push('\n');
+                        d.push_str(text);
+                    }
+                    None => item.description = Some(text.to_string()),
+                }
+            }
+            continue;
+        };
+        if item.title.is_empty() {
+            bail!("Line {} has no title", n + 1);
+        }
+        close(&mut stack, &mut roots, key);
+        stack.push((key, item));
+    }
+    close(&mut stack, &mut roots, i64::MIN);
+    Ok(roots)
+}
+
+/// Parse a YAML outline.
+pub fn parse_yaml(text: &str) -> Result<Vec<OutlineItem>> {
+    let value: Value =
+        serde_yaml::from_str(text).map_err(|e| anyhow!("Invalid YAML outline: {}", e))?;
+    match value {
+        Value::Array(nodes) => nodes.into_iter().map(yaml_node).collect(),
+        Value::Null => Ok(Vec::new()),
+        node => Ok(vec![yaml_node(node)?]),
+    }
+}
+
+fn yaml_children(value: Value) -> Result<Vec<OutlineItem>> {
+    match value {
+        Value::Array(nodes) => nodes.into_iter().map(yaml_node).collect(),
+        Value::Null => Ok(Vec::new()),
+        other => bail!("children must be a list, got {}", other),
+    }
+}
+
+fn yaml_node(value: Value) -> Result<OutlineItem> {
+    let mut map = match value {
+        Value::String(line) => return Ok(OutlineItem::from_line(&line)),
+        Value::Object(map) => map,
+        other => bail!("Outline nodes must be titles or mappings, got {}", other),
+    };
+
+    // `Title: [children]` shorthand
+    if !map.contains_key("title") && map.len() == 1 {
+        let (title, children) = map.into_iter().next().unwrap();
+        let mut item = OutlineItem::from_line(&title);
+        item.children = yaml_children(children)?;
+        return Ok(item);
+    }
+
+    let title = match map.remove("title") {
+        Some(Value::String(title)) => title,
+        _ => bail!("Outline node needs a title: {}", Value::Object(map)),
+    };
+    let mut item = OutlineItem::from_line(&title);
+    if let Some(Value::String(d)) = map.remove("description") {
+        item.description = Some(d);
+    }
+    match map.remove("tags") {
+        Some(Value::Array(tags)) => {
+            item.tags
+                .extend(tags.iter().filter_map(|t| t.as_str().map(String::from)));
+        }
+        Some(Value::String(tags)) => {
+            item.tags
+                .extend(tags.split(',').map(|t| t.trim().to_string()));
+        }
+        _ => {}
+    }
+    match map.remove("priority") {
+        Some(Value::String(p)) => item.priority = Some(p),
+        Some(Value::Number(p)) => item.priority = Some(p.to_string()),
+        _ => {}
+    }
+    item.children = yaml_children(map.remove("children").unwrap_or(Value::Null))?;
+    item.fields = map;
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(items: &[OutlineItem]) -> Vec<&str> {
+        items.iter().map(|i| i.title.as_str()).collect()
+    }
+
+    #[test]
+    fn markdown_nests_by_indent_and_heading() {
+        let items = parse_markdown(
+            "# Auth rewrite\n\
+             intro text\n\
+             - Login form #frontend\n\
+             \x20 - [ ] Validation !high\n\
+             \x20 - [x] Layout\n\
+             - Token refresh #backend #api !8\n\
+             \x20 Rotate refresh tokens.\n\
+             ## Later\n\
+             1. Audit\n",
+        )
+        .unwrap();
+        assert_eq!(titles(&items), vec!["Auth rewrite"]);
+        let root = &items[0];
+        assert_eq!(root.description.as_deref(), Some("intro text"));
+        assert_eq!(
+            titles(&root.children),
+            vec!["Login form", "Token refresh", "Later"]
+        );
+
+        let login = &root.children[0];
+        assert_eq!(login.tags, vec!["frontend"]);
+        assert_eq!(titles(&login.children), vec!["Validation", "Layout"]);
+        assert_eq!(login.children[0].priority.as_deref(), Some("high"));
+        assert_eq!(login.children[0].checked, Some(false));
+        assert_eq!(login.children[1].checked, Some(true));
+
+        let refresh = &root.children[1];
+        assert_eq!(refresh.tags, vec!["backend", "api"]);
+        assert_eq!(refresh.priority.as_deref(), Some("8"));
+        assert_eq!(
+            refresh.description.as_deref(),
+            Some("Rotate refresh tokens.")
+        );
+        assert_eq!(titles(&root.children[2].children), vec!["Audit"]);
+    }
+
+    #[test]
+    fn markdown_keeps_hashes_inside_titles() {
+        let items = parse_markdown("- Port C# client to #rust\n- Ship it !\n").unwrap();
+        assert_eq!(items[0].title, "Port C# client to");
+        assert_eq!(items[0].tags, vec!["rust"]);
+        assert_eq!(items[1].title, "Ship it !");
+    }
+
+    #[test]
+    fn yaml_accepts_strings_mappings_and_shorthand() {
+        let items = parse_yaml(
+            "title: Release\n\
+             tags: [ops]\n\
+             points: 3\n\
+             children:\n\
+             \x20 - \"Changelog #docs\"\n\
+             \x20 - Packaging:\n\
+             \x20     - title: Build\n\
+             \x20       priority: 9\n",
+        )
+        .unwrap();
+        let root = &items[0];
+        assert_eq!(root.tags, vec!["ops"]);
+        assert_eq!(root.fields.get("points"), Some(&json!(3)));
+        assert_eq!(titles(&root.children), vec!["Changelog", "Packaging"]);
+        assert_eq!(root.children[0].tags, vec!["docs"]);
+        assert_eq!(root.children[1].children[0].priority.as_deref(), Some("9"));
+
+        let tree = root.to_tree();
+        assert_eq!(tree["points"], json!(3));
+        assert_eq!(tree["children"][1]["children"][0]["title"], json!("Build"));
+    }
+
+    #[test]
+    fn rejects_empty_and_malformed_outlines() {
+        assert!(parse_outline("just prose\n", OutlineFormat::Markdown).is_err());
+        assert!(parse_outline("- #tag\n", OutlineFormat::Markdown).is_err());
+        assert!(parse_outline("- description: no title\n", OutlineFormat::Yaml).is_err());
+        assert!(parse_outline("[unclosed\n", OutlineFormat::Yaml).is_err());
+    }
+}
@@ -23,6 +23,7 @@
 
 use crate::config::workflows::WorkflowsConfig;
 use crate::config::{PhasesConfig, StatesConfig};
+use crate::types::Task;
 
 /// Context for expanding template variables in prompts.
 ///
@@ -53,6 +54,8 @@ pub struct PromptContext<'a> {
     pub agent_role: Option<&'a str>,
     /// Agent's tags (if available)
     pub agent_tags: Option<&'a [String]>,
+    /// Tasks the agent has claimed (if available)
+    pub agent_claims: Option<&'a [Task]>,
 }
 
 impl<'a> PromptContext<'a> {
@@ -79,6 +82,7 @@ impl<'a> PromptContext<'a> {
             agent_id: None,
             agent_role: None,
             agent_tags: None,
+            agent_claims: None,
         }
     }
 
@@ -109,6 +113,12 @@ impl<'a> PromptContext<'a> {
         self.agent_tags = Some(tags);
         self
     }
+
+    /// Add the tasks the agent has claimed to the prompt context.
+    pub fn with_claims(mut self, claims: &'a [Task]) -> Self {
+        self.agent_claims = Some(claims);
+        self
+    }
 }
 
 /// Load a prompt by trigger name from WorkflowsConfig.
@@ -138,6 +148,7 @@ pub fn load_prompt(trigger: &str, workflows: &WorkflowsConfig) -> Option<String>
 /// - `{{agent_id}}` - agent/worker identifier
 /// - `{{agent_role}}` - matched role name or "(none)"
 /// - `{{agent_tags}}` - comma-separated agent tags
+/// - `{{agent_claims}}` - markdown list of the agent's claimed tasks
 pub fn expand_prompt(content: &str, ctx: &PromptContext) -> String {
     let mut result = content.to_string();
 
@@ -241,6 +252,18 @@ pub fn expand_prompt(content: &str, ctx: &PromptContext) -> String {
         result = result.replace("{{agent_tags}}", &val);
     }
 
+    if result.contains("{{agent_claims}}") {
+        let val = match ctx.agent_claims {
+            Some(claims) if !claims.is_empty() => claims
+                .iter()
+                .map(|t| format!("- `{}` {} ({})", t.id, t.title, t.status))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => "_(none)_".to_string(),
+        };
+        result = result.replace("{{agent_claims}}", &val);
+    }
+
     result
 }
 
@@ -598,6 +621,18 @@ mod tests {
         assert_eq!(result, "_unknown_ / _(none)_ / _(none)_");
     }
 
+    #[test]
+    fn test_expand_prompt_agent_claims() {
+        let states_config = StatesConfig::default();
+        let phases_config = PhasesConfig::default();
+        let ctx = PromptContext::new("working", None, &states_config, &phases_config);
+        assert_eq!(expand_prompt("{{agent_claims}}", &ctx), "_(none)_");
+
+        let claims: Vec<Task> = vec![];
+        let ctx = ctx.with_claims(&claims);
+        assert_eq!(expand_prompt("{{agent_claims}}", &ctx), "_(none)_");
+    }
+
     #[test]
     fn test_expand_prompt_combined_context() {
         let states_config = StatesConfig::default();
@@ -1,5 +1,6 @@
 //! Worker resource handlers.
 
+use crate::config::AppConfig;
 use crate::db::Database;
 use anyhow::Result;
 use serde_json::{Value, json};
@@ -21,3 +22,23 @@ pub fn get_all_workers(db: &Database) -> Result<Value> {
         }).collect::<Vec<_>>()
     }))
 }
+
+/// Per-group membership and workload, including configured groups nobody has joined.
+pub fn get_group_stats(db: &Database, config: &AppConfig) -> Result<Value> {
+    let stats = db.get_group_stats(&config.groups.group_names(), &config.states)?;
+
+    Ok(json!({
+        "groups": stats.iter().map(|g| {
+            let mut entry = json!(g);
+            if let Some(desc) = config
+                .groups
+                .definitions
+                .get(&g.group)
+                .and_then(|d| d.description.as_ref())
+            {
+                entry["description"] = json!(desc);
+            }
+            entry
+        }).collect::<Vec<_>>()
+    }))
+}
@@ -0,0 +1,41 @@
+//! Change feed resource handlers.
+
+use crate::db::Database;
+use anyhow::{Result, anyhow};
+use serde_json::{Value, json};
+
+/// Maximum number of entries returned per read.
+const PAGE_SIZE: usize = 500;
+
+/// Feed entries after `cursor`. Read `changes://since/{next_cursor}` for the
+/// next page; when `has_more` is false the client is caught up. A cursor
+/// beyond the head (e.g. after the database was replaced) comes back with
+/// `reset: true` and the current head to resume from.
+pub fn get_changes_since(db: &Database, cursor: &str) -> Result<Value> {
+    let cursor: i64 = cursor
+        .parse()
+        .ok()
+        .filter(|c| *c >= 0)
+        .ok_or_else(|| anyhow!("Invalid change feed cursor: {}", cursor))?;
+
+    let head = db.get_feed_head()?;
+    if cursor > head {
+        return Ok(json!({
+            "cursor": cursor,
+            "next_cursor": head,
+            "has_more": false,
+            "reset": true,
+            "changes": []
+        }));
+    }
+
+    let changes = db.get_changes_since(cursor, PAGE_SIZE)?;
+    let next_cursor = changes.last().map_or(cursor, |c| c.id);
+
+    Ok(json!({
+        "cursor": cursor,
+        "next_cursor": next_cursor,
+        "has_more": next_cursor < head,
+        "changes": changes
+    }))
+}
@@ -62,6 +62,7 @@ pub fn get_dependencies_config(deps_config: &DependenciesConfig) -> Result<Value
                 "name": name,
                 "display": format!("{:?}", def.display).to_lowercase(),
                 "blocks": format!("{:?}", def.blocks).to_lowercase(),
+                "semantics": format!("{:?}", def.semantics).to_lowercase(),
             })
         })
         .collect();
@@ -70,6 +71,8 @@ pub fn get_dependencies_config(deps_config: &DependenciesConfig) -> Result<Value
         "dependency_types": dep_types,
         "start_blocking_types": deps_config.start_blocking_types(),
         "completion_blocking_types": deps_config.completion_blocking_types(),
+        "soft_start_types": deps_config.soft_start_types(),
+        "exclusive_types": deps_config.exclusive_types(),
         "count": deps_config.definitions.len(),
     }))
 }
@@ -1,17 +1,46 @@
 //! File lock resource handlers.
 
 use crate::db::Database;
+use crate::tools::files::{claim_event_json, normalize_file_path, summarize_history_agents};
 use anyhow::Result;
 use serde_json::{Value, json};
 
+/// Number of recent mark violations included in the marks resource.
+const RECENT_VIOLATIONS: i32 = 50;
+
+/// Number of claim events included in the mark history resource.
+const RECENT_HISTORY: usize = 100;
+
 pub fn get_all_file_locks(db: &Database) -> Result<Value> {
     let locks = db.get_all_file_locks()?;
+    let violations = db.get_mark_violations(RECENT_VIOLATIONS)?;
 
     Ok(json!({
         "locks": locks.iter().map(|l| json!({
             "file_path": l.file_path,
             "worker_id": l.worker_id.to_string(),
-            "locked_at": l.locked_at
-        })).collect::<Vec<_>>()
+            "locked_at": l.locked_at,
+            "expires_at": l.expires_at
+        })).collect::<Vec<_>>(),
+        "violations": violations
+    }))
+}
+
+/// Recent mark/release events from the claim audit trail, optionally limited to
+/// events overlapping `file` (relative paths resolve against the server's cwd).
+pub fn get_mark_history(db: &Database, file: Option<&str>) -> Result<Value> {
+    let path = file.map(|f| {
+        if f.starts_with("lock:") {
+            f.to_string()
+        } else {
+            normalize_file_path(f)
+        }
+    });
+    let events = db.get_claim_history(path.as_deref(), None, RECENT_HISTORY)?;
+
+    Ok(json!({
+        "file": path,
+        "agents": summarize_history_agents(&events),
+        "events": events.iter().map(claim_event_json).collect::<Vec<_>>()
     }))
 }
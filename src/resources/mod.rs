@@ -1,6 +1,7 @@
 //! MCP resource implementations.
 
 pub mod agents;
+pub mod changes;
 pub mod config;
 pub mod docs;
 pub mod files;
@@ -10,7 +11,12 @@ pub mod tasks;
 pub mod workflows;
 
 use crate::config::AppConfig;
+use crate::config::effective::EffectiveConfig;
+use crate::config::status::ConfigStatus;
 use crate::db::Database;
+use crate::error::ToolError;
+use crate::subscriptions::MutationKind;
+use crate::types::TaskScope;
 use anyhow::Result;
 use rmcp::model::{Annotated, RawResource, RawResourceTemplate, Resource, ResourceTemplate};
 use serde_json::Value;
@@ -25,6 +31,10 @@ pub struct ResourceHandler {
     pub skills_dir: Option<std::path::PathBuf>,
     /// Directory containing documentation markdown files (e.g., `docs/`)
     pub docs_dir: Option<std::path::PathBuf>,
+    /// Merged configuration with value sources, captured when config was loaded
+    pub effective_config: Option<Arc<EffectiveConfig>>,
+    /// Hot-reload status, shared with the reload path
+    pub config_status: Option<Arc<ConfigStatus>>,
 }
 
 impl ResourceHandler {
@@ -34,6 +44,8 @@ impl ResourceHandler {
             config,
             skills_dir: None,
             docs_dir: None,
+            effective_config: None,
+            config_status: None,
         }
     }
 
@@ -49,6 +61,18 @@ impl ResourceHandler {
         self
     }
 
+    /// Set the effective configuration report served at `config://effective`.
+    pub fn with_effective_config(mut self, effective: Arc<EffectiveConfig>) -> Self {
+        self.effective_config = Some(effective);
+        self
+    }
+
+    /// Set the hot-reload status served at `config://status`.
+    pub fn with_config_status(mut self, status: Arc<ConfigStatus>) -> Self {
+        self.config_status = Some(status);
+        self
+    }
+
     /// Get all available resource templates.
     pub fn get_resource_templates(&self) -> Vec<ResourceTemplate> {
         vec![
@@ -80,7 +104,7 @@ impl ResourceHandler {
                     uri_template: "query://tasks/blocked".into(),
                     name: "Blocked Tasks".into(),
                     title: None,
-                    description: Some("Tasks blocked by dependencies".into()),
+                    description: Some("Tasks blocked by dependencies or flagged with a blocked reason".into()),
                     mime_type: Some("application/json".into()),
                     icons: None,
                 },
@@ -119,6 +143,32 @@ impl ResourceHandler {
                 },
                 None,
             ),
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "query://projects/{project}/{view}".into(),
+                    name: "Project Tasks".into(),
+                    title: None,
+                    description: Some(
+                        "Tasks of one project; view is all, ready, blocked, or claimed".into(),
+                    ),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "query://visible/{agent_id}/{view}".into(),
+                    name: "Visible Tasks".into(),
+                    title: None,
+                    description: Some(
+                        "Tasks an agent may see under the visibility rules; view is all, ready, blocked, or claimed".into(),
+                    ),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
             Annotated::new(
                 RawResourceTemplate {
                     uri_template: "query://files/marks".into(),
@@ -130,6 +180,42 @@ impl ResourceHandler {
                 },
                 None,
             ),
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "query://files/history/{path}".into(),
+                    name: "File Mark History".into(),
+                    title: None,
+                    description: Some("Recent mark/release events touching a path".into()),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "query://deadlines".into(),
+                    name: "Deadlines".into(),
+                    title: None,
+                    description: Some(
+                        "Claimed tasks with a due_at deadline, soonest first, with reminders sent"
+                            .into(),
+                    ),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "query://projects".into(),
+                    name: "Projects".into(),
+                    title: None,
+                    description: Some("Projects with task counts and connected agents".into()),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
             Annotated::new(
                 RawResourceTemplate {
                     uri_template: "query://agents/all".into(),
@@ -141,6 +227,17 @@ impl ResourceHandler {
                 },
                 None,
             ),
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "query://agents/groups".into(),
+                    name: "Agent Groups".into(),
+                    title: None,
+                    description: Some("Agent groups with members and per-group workload".into()),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
             Annotated::new(
                 RawResourceTemplate {
                     uri_template: "query://stats/summary".into(),
@@ -152,6 +249,44 @@ impl ResourceHandler {
                 },
                 None,
             ),
+            // Tag usage
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "tags://stats".into(),
+                    name: "Tag Statistics".into(),
+                    title: None,
+                    description: Some("Each tag with task counts by status, agents carrying it, and last use; flags tags open tasks need that no agent has".into()),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
+            // Blocking graph
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "deps://matrix/{task_id}".into(),
+                    name: "Dependency Matrix".into(),
+                    title: None,
+                    description: Some("Blocking edges within a subtree, with direct and transitive blocker counts per task".into()),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
+            // Change feed
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "changes://since/{cursor}".into(),
+                    name: "Change Feed".into(),
+                    title: None,
+                    description: Some(
+                        "Mutations after a cursor, oldest first; start from 0 and follow next_cursor".into(),
+                    ),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
             // Config resources
             Annotated::new(
                 RawResourceTemplate {
@@ -208,6 +343,28 @@ impl ResourceHandler {
                 },
                 None,
             ),
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "config://effective".into(),
+                    name: "Effective Configuration".into(),
+                    title: None,
+                    description: Some("Merged config.yaml values with the tier or file each came from, plus the prompts, workflow, and overlay files in use".into()),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
+            Annotated::new(
+                RawResourceTemplate {
+                    uri_template: "config://status".into(),
+                    name: "Config Reload Status".into(),
+                    title: None,
+                    description: Some("Outcome of the last config hot-reload, and edits to startup-only settings (database, directories, dashboard) that wait for a restart".into()),
+                    mime_type: Some("application/json".into()),
+                    icons: None,
+                },
+                None,
+            ),
             // Docs resources (reference content: docs, skills, workflows)
             Annotated::new(
                 RawResourceTemplate {
@@ -330,7 +487,9 @@ impl ResourceHandler {
                     uri: "query://tasks/blocked".into(),
                     name: "Blocked Tasks".into(),
                     title: None,
-                    description: Some("Tasks blocked by dependencies".into()),
+                    description: Some(
+                        "Tasks blocked by dependencies or flagged with a blocked reason".into(),
+                    ),
                     mime_type: Some("application/json".into()),
                     size: None,
                     icons: None,
@@ -364,6 +523,48 @@ impl ResourceHandler {
                 },
                 None,
             ),
+            Annotated::new(
+                RawResource {
+                    uri: "query://files/history".into(),
+                    name: "File Mark History".into(),
+                    title: None,
+                    description: Some("Recent mark/release events across all files".into()),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                    icons: None,
+                    meta: None,
+                },
+                None,
+            ),
+            Annotated::new(
+                RawResource {
+                    uri: "query://deadlines".into(),
+                    name: "Deadlines".into(),
+                    title: None,
+                    description: Some(
+                        "Claimed tasks with a due_at deadline, soonest first, with reminders sent"
+                            .into(),
+                    ),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                    icons: None,
+                    meta: None,
+                },
+                None,
+            ),
+            Annotated::new(
+                RawResource {
+                    uri: "query://projects".into(),
+                    name: "Projects".into(),
+                    title: None,
+                    description: Some("Projects with task counts and connected agents".into()),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                    icons: None,
+                    meta: None,
+                },
+                None,
+            ),
             Annotated::new(
                 RawResource {
                     uri: "query://agents/all".into(),
@@ -377,6 +578,19 @@ impl ResourceHandler {
                 },
                 None,
             ),
+            Annotated::new(
+                RawResource {
+                    uri: "query://agents/groups".into(),
+                    name: "Agent Groups".into(),
+                    title: None,
+                    description: Some("Agent groups with members and per-group workload".into()),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                    icons: None,
+                    meta: None,
+                },
+                None,
+            ),
             Annotated::new(
                 RawResource {
                     uri: "query://stats/summary".into(),
@@ -390,6 +604,38 @@ impl ResourceHandler {
                 },
                 None,
             ),
+            Annotated::new(
+                RawResource {
+                    uri: "tags://stats".into(),
+                    name: "Tag Statistics".into(),
+                    title: None,
+                    description: Some(
+                        "Each tag with task counts by status, agents carrying it, and last use; flags tags open tasks need that no agent has"
+                            .into(),
+                    ),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                    icons: None,
+                    meta: None,
+                },
+                None,
+            ),
+            Annotated::new(
+                RawResource {
+                    uri: "deps://matrix".into(),
+                    name: "Dependency Matrix".into(),
+                    title: None,
+                    description: Some(
+                        "Blocking edges between all live tasks, with direct and transitive blocker counts per task"
+                            .into(),
+                    ),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                    icons: None,
+                    meta: None,
+                },
+                None,
+            ),
             // Config resources
             Annotated::new(
                 RawResource {
@@ -459,6 +705,38 @@ impl ResourceHandler {
                 },
                 None,
             ),
+            Annotated::new(
+                RawResource {
+                    uri: "config://effective".into(),
+                    name: "Effective Configuration".into(),
+                    title: None,
+                    description: Some(
+                        "Merged config.yaml values with the tier or file each came from, plus the prompts, workflow, and overlay files in use"
+                            .into(),
+                    ),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                    icons: None,
+                    meta: None,
+                },
+                None,
+            ),
+            Annotated::new(
+                RawResource {
+                    uri: "config://status".into(),
+                    name: "Config Reload Status".into(),
+                    title: None,
+                    description: Some(
+                        "Outcome of the last config hot-reload, and edits to startup-only settings (database, directories, dashboard) that wait for a restart"
+                            .into(),
+                    ),
+                    mime_type: Some("application/json".into()),
+                    size: None,
+                    icons: None,
+                    meta: None,
+                },
+                None,
+            ),
             // Docs resources (reference content: docs, skills, workflows)
             Annotated::new(
                 RawResource {
@@ -506,8 +784,22 @@ impl ResourceHandler {
 
     /// Read a resource by URI.
     pub async fn read_resource(&self, uri: &str) -> Result<Value> {
+        // Resource reads do not say who the caller is, so under visibility
+        // rules the unscoped task listings are refused
+        if !self.config.visibility.rules.is_empty() && lists_tasks(uri) {
+            return Err(ToolError::not_visible(&format!("Resource {}", uri)).into());
+        }
+
         if uri.starts_with("query://") {
             self.read_query_resource(uri).await
+        } else if let Some(cursor) = uri.strip_prefix("changes://since/") {
+            changes::get_changes_since(&self.db, cursor)
+        } else if uri == "deps://matrix" {
+            tasks::get_dependency_matrix(&self.db, None, &self.config.deps)
+        } else if let Some(task_id) = uri.strip_prefix("deps://matrix/") {
+            tasks::get_dependency_matrix(&self.db, Some(task_id), &self.config.deps)
+        } else if uri == "tags://stats" {
+            stats::get_tag_stats(&self.db, &self.config.states)
         } else if uri.starts_with("config://") {
             self.read_config_resource(uri).await
         } else if uri.starts_with("docs://") {
@@ -520,19 +812,31 @@ impl ResourceHandler {
     async fn read_query_resource(&self, uri: &str) -> Result<Value> {
         let path = uri.strip_prefix("query://").unwrap_or("");
 
+        // Project-scoped task views: projects/{project}/{all|ready|blocked|claimed}
+        if let Some(rest) = path.strip_prefix("projects/") {
+            let (project, view) = rest.split_once('/').unwrap_or((rest, "all"));
+            return self.read_task_view(view, Some(project), None);
+        }
+
+        // Task views limited by an agent's visibility rules: visible/{agent_id}/{view}
+        if let Some(rest) = path.strip_prefix("visible/") {
+            let (agent_id, view) = rest.split_once('/').unwrap_or((rest, "all"));
+            let scope = self.db.task_scope(agent_id, &self.config.visibility)?;
+            return self.read_task_view(view, None, scope.as_ref());
+        }
+
         match path {
             // Tasks
-            "tasks/all" => tasks::get_all_tasks(&self.db),
-            "tasks/ready" => {
-                tasks::get_ready_tasks(&self.db, &self.config.states, &self.config.deps)
-            }
-            "tasks/blocked" => {
-                tasks::get_blocked_tasks(&self.db, &self.config.states, &self.config.deps)
+            // Polled aggregates are cached until a mutation that affects them
+            "tasks/blocked" => self.db.cached(uri, &MutationKind::affecting(uri), || {
+                self.read_task_view("blocked", None, None)
+            }),
+            "tasks/all" | "tasks/ready" | "tasks/claimed" => {
+                self.read_task_view(&path["tasks/".len()..], None, None)
             }
-            "tasks/claimed" => tasks::get_claimed_tasks(&self.db, None),
             _ if path.starts_with("tasks/agent/") => {
                 let agent_id = path.strip_prefix("tasks/agent/").unwrap();
-                tasks::get_claimed_tasks(&self.db, Some(agent_id))
+                tasks::get_claimed_tasks(&self.db, Some(agent_id), None, None)
             }
             _ if path.starts_with("tasks/tree/") => {
                 let task_id = path.strip_prefix("tasks/tree/").unwrap();
@@ -540,14 +844,43 @@ impl ResourceHandler {
             }
             // Files
             "files/marks" => files::get_all_file_locks(&self.db),
+            "files/history" => files::get_mark_history(&self.db, None),
+            _ if path.starts_with("files/history/") => {
+                let file = path.strip_prefix("files/history/").unwrap();
+                files::get_mark_history(&self.db, Some(file))
+            }
+            // Deadlines
+            "deadlines" => tasks::get_deadlines(&self.db, &self.config.states),
+            // Projects
+            "projects" => tasks::get_projects(&self.db),
             // Agents
             "agents/all" => agents::get_all_workers(&self.db),
+            "agents/groups" => agents::get_group_stats(&self.db, &self.config),
             // Stats
-            "stats/summary" => stats::get_stats_summary(&self.db, &self.config.states),
+            "stats/summary" => self.db.cached(uri, &MutationKind::affecting(uri), || {
+                stats::get_stats_summary(&self.db, &self.config.states)
+            }),
             _ => Err(anyhow::anyhow!("Unknown query resource: {}", path)),
         }
     }
 
+    /// Read one of the task views, optionally limited to a project or visibility scope.
+    fn read_task_view(
+        &self,
+        view: &str,
+        project: Option<&str>,
+        scope: Option<&TaskScope>,
+    ) -> Result<Value> {
+        let (states, deps) = (&self.config.states, &self.config.deps);
+        match view {
+            "all" => tasks::get_all_tasks(&self.db, project, scope),
+            "ready" => tasks::get_ready_tasks(&self.db, states, deps, project, scope),
+            "blocked" => tasks::get_blocked_tasks(&self.db, states, deps, project, scope),
+            "claimed" => tasks::get_claimed_tasks(&self.db, None, project, scope),
+            _ => Err(anyhow::anyhow!("Unknown task view: {}", view)),
+        }
+    }
+
     async fn read_config_resource(&self, uri: &str) -> Result<Value> {
         let path = uri.strip_prefix("config://").unwrap_or("");
 
@@ -570,6 +903,18 @@ impl ResourceHandler {
             "phases" => config::get_phases_config(&self.config.phases),
             "dependencies" => config::get_dependencies_config(&self.config.deps),
             "tags" => config::get_tags_config(&self.config.tags),
+            "effective" => match self.effective_config {
+                Some(ref effective) => Ok(serde_json::to_value(effective.as_ref())?),
+                None => Err(anyhow::anyhow!(
+                    "Effective configuration is not available in this server"
+                )),
+            },
+            "status" => match self.config_status {
+                Some(ref status) => Ok(serde_json::to_value(status.report())?),
+                None => Err(anyhow::anyhow!(
+                    "Config reload status is not available in this server"
+                )),
+            },
             _ => Err(anyhow::anyhow!("Unknown config resource: {}", path)),
         }
     }
@@ -581,7 +926,7 @@ impl ResourceHandler {
 
         match path {
             // Skills
-            "skills/list" => skills::list_skills(skills_dir),
+            "skills/list" => skills::list_skills(skills_dir, &skills::SkillFilter::default()),
             _ if path.starts_with("skills/") => {
                 let name = path.strip_prefix("skills/").unwrap();
                 skills::get_skill_resource(skills_dir, name)
@@ -607,3 +952,17 @@ impl ResourceHandler {
         }
     }
 }
+
+/// Resources that list tasks (or their changes) without a visibility scope.
+/// `query://visible/{agent_id}/{view}` serves the scoped views instead.
+fn lists_tasks(uri: &str) -> bool {
+    [
+        "query://tasks/",
+        "query://projects/",
+        "query://deadlines",
+        "changes://",
+        "deps://",
+    ]
+    .iter()
+    .any(|prefix| uri.starts_with(prefix))
+}
@@ -11,6 +11,7 @@
 //! 3. Embedded default (compiled into binary from `config/skills/`)
 
 use anyhow::Result;
+use serde::Serialize;
 use serde_json::{Value, json};
 use std::path::{Path, PathBuf};
 
@@ -71,9 +72,24 @@ fn get_embedded_skill(name: &str) -> Option<&'static str> {
     }
 }
 
-/// Extract the `description` field from YAML frontmatter in a SKILL.md file.
-/// Returns None if frontmatter is missing or has no description.
-fn parse_frontmatter_description(content: &str) -> Option<String> {
+/// Metadata from the YAML frontmatter of a SKILL.md file.
+///
+/// Fields may sit at the top level or under `metadata:`. `category` falls back
+/// to `metadata.role`, which the embedded skills use. `tags` and `audience`
+/// accept a single string or a list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SkillFrontmatter {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    /// Agent roles the skill is written for; empty means any agent.
+    pub audience: Vec<String>,
+}
+
+/// Parse the YAML frontmatter of a SKILL.md file.
+/// Returns None if frontmatter is missing or is not a YAML mapping.
+pub fn parse_frontmatter(content: &str) -> Option<SkillFrontmatter> {
     let content = content.trim_start();
     if !content.starts_with("---") {
         return None;
@@ -82,10 +98,32 @@ fn parse_frontmatter_description(content: &str) -> Option<String> {
     let close = after_open.find("\n---")?;
     let yaml_block = &after_open[..close];
     let mapping: serde_yaml::Value = serde_yaml::from_str(yaml_block).ok()?;
-    mapping
-        .get("description")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
+    if !mapping.is_mapping() {
+        return None;
+    }
+
+    let field = |key: &str| {
+        mapping
+            .get(key)
+            .or_else(|| mapping.get("metadata").and_then(|m| m.get(key)))
+    };
+    let string = |key: &str| field(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let list = |key: &str| match field(key) {
+        Some(serde_yaml::Value::String(s)) => vec![s.clone()],
+        Some(serde_yaml::Value::Sequence(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => vec![],
+    };
+
+    Some(SkillFrontmatter {
+        title: string("title"),
+        description: string("description"),
+        category: string("category").or_else(|| string("role")),
+        tags: list("tags"),
+        audience: list("audience"),
+    })
 }
 
 /// Normalize skill name (strip "task-graph-" prefix if present).
@@ -184,25 +222,87 @@ fn is_overridden(skills_dir: Option<&Path>, name: &str) -> bool {
     }
 }
 
-/// List all skills as JSON, indicating which are overridden.
-pub fn list_skills(skills_dir: Option<&Path>) -> Result<Value> {
-    let mut skills_list: Vec<Value> = SKILLS
+/// Filter for `list_skills`; unset fields match every skill.
+#[derive(Debug, Clone, Default)]
+pub struct SkillFilter {
+    pub category: Option<String>,
+    pub tag: Option<String>,
+}
+
+impl SkillFilter {
+    fn matches(&self, entry: &SkillEntry) -> bool {
+        let category = self
+            .category
+            .as_ref()
+            .is_none_or(|c| entry.category.eq_ignore_ascii_case(c));
+        let tag = self
+            .tag
+            .as_ref()
+            .is_none_or(|t| entry.tags.iter().any(|x| x.eq_ignore_ascii_case(t)));
+        category && tag
+    }
+}
+
+/// A skill's listing: its name, frontmatter metadata, and where it comes from.
+#[derive(Debug, Clone, Serialize)]
+struct SkillEntry {
+    name: String,
+    full_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    description: String,
+    category: String,
+    /// Same as `category`; kept for clients reading the original field.
+    role: String,
+    tags: Vec<String>,
+    audience: Vec<String>,
+    uri: String,
+    overridden: bool,
+    source: &'static str,
+}
+
+impl SkillEntry {
+    /// Build the listing for `name` from its content, filling gaps from the
+    /// built-in metadata, if any.
+    fn new(name: &str, full_name: &str, content: &str, overridden: bool, builtin: bool) -> Self {
+        let info = SKILLS.iter().find(|s| s.name == name);
+        let meta = parse_frontmatter(content).unwrap_or_default();
+        let category = meta
+            .category
+            .or_else(|| info.map(|i| i.role.to_string()))
+            .unwrap_or_else(|| "custom".to_string());
+        Self {
+            name: name.to_string(),
+            full_name: full_name.to_string(),
+            title: meta.title,
+            description: meta
+                .description
+                .or_else(|| info.map(|i| i.description.to_string()))
+                .unwrap_or_else(|| "Custom skill".to_string()),
+            role: category.clone(),
+            category,
+            tags: meta.tags,
+            audience: meta.audience,
+            uri: format!("skills://{}", name),
+            overridden,
+            source: if builtin && !overridden {
+                "embedded"
+            } else {
+                "local"
+            },
+        }
+    }
+}
+
+/// Listings of every skill: built-in skills first, then custom skills in the
+/// override directory.
+fn all_skills(skills_dir: Option<&Path>) -> Vec<SkillEntry> {
+    let mut skills: Vec<SkillEntry> = SKILLS
         .iter()
         .map(|s| {
             let overridden = is_overridden(skills_dir, s.name);
-            let description = get_skill(skills_dir, s.name)
-                .ok()
-                .and_then(|content| parse_frontmatter_description(&content))
-                .unwrap_or_else(|| s.description.to_string());
-            json!({
-                "name": s.name,
-                "full_name": s.full_name,
-                "description": description,
-                "role": s.role,
-                "uri": format!("skills://{}", s.name),
-                "overridden": overridden,
-                "source": if overridden { "local" } else { "embedded" },
-            })
+            let content = get_skill(skills_dir, s.name).unwrap_or_default();
+            SkillEntry::new(s.name, s.full_name, &content, overridden, true)
         })
         .collect();
 
@@ -211,40 +311,40 @@ pub fn list_skills(skills_dir: Option<&Path>) -> Result<Value> {
         && dir.exists()
         && let Ok(entries) = std::fs::read_dir(dir)
     {
+        let mut custom: Vec<SkillEntry> = Vec::new();
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.is_dir() {
-                let skill_md = path.join("SKILL.md");
-                if skill_md.exists() {
-                    let name = path.file_name().unwrap().to_string_lossy().to_string();
-                    let normalized = normalize_name(&name);
-
-                    // Skip if it's an override of a known skill
-                    if SKILLS
-                        .iter()
-                        .any(|s| s.name == normalized || s.full_name == name)
-                    {
-                        continue;
-                    }
-
-                    let description = std::fs::read_to_string(&skill_md)
-                        .ok()
-                        .and_then(|content| parse_frontmatter_description(&content))
-                        .unwrap_or_else(|| "Custom skill".to_string());
-
-                    skills_list.push(json!({
-                        "name": normalized,
-                        "full_name": name,
-                        "description": description,
-                        "role": "custom",
-                        "uri": format!("skills://{}", normalized),
-                        "overridden": false,
-                        "source": "local",
-                    }));
-                }
+            let skill_md = path.join("SKILL.md");
+            if !path.is_dir() || !skill_md.exists() {
+                continue;
+            }
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let normalized = normalize_name(&name);
+
+            // Skip if it's an override of a known skill
+            if SKILLS
+                .iter()
+                .any(|s| s.name == normalized || s.full_name == name)
+            {
+                continue;
             }
+
+            let content = std::fs::read_to_string(&skill_md).unwrap_or_default();
+            custom.push(SkillEntry::new(normalized, &name, &content, false, false));
         }
+        custom.sort_by(|a, b| a.name.cmp(&b.name));
+        skills.extend(custom);
     }
+    skills
+}
+
+/// List skills matching `filter` as JSON, with their frontmatter metadata and
+/// whether each is overridden.
+pub fn list_skills(skills_dir: Option<&Path>, filter: &SkillFilter) -> Result<Value> {
+    let skills_list: Vec<SkillEntry> = all_skills(skills_dir)
+        .into_iter()
+        .filter(|s| filter.matches(s))
+        .collect();
 
     Ok(json!({
         "skills": skills_list,
@@ -261,18 +361,39 @@ pub fn get_skill_resource(skills_dir: Option<&Path>, name: &str) -> Result<Value
     let is_builtin = is_builtin_skill(name);
     let overridden = is_overridden(skills_dir, name);
 
-    let info = SKILLS.iter().find(|s| s.name == normalized);
+    let full_name = SKILLS
+        .iter()
+        .find(|s| s.name == normalized)
+        .map(|i| i.full_name)
+        .unwrap_or(name);
     let content = get_skill(skills_dir, name)?;
+    let entry = SkillEntry::new(normalized, full_name, &content, overridden, is_builtin);
+
+    let mut value = serde_json::to_value(entry)?;
+    value["content"] = json!(content);
+    value["mime_type"] = json!("text/markdown");
+    Ok(value)
+}
+
+/// Get the content of every skill in `category` as JSON.
+pub fn get_skills_by_category(skills_dir: Option<&Path>, category: &str) -> Result<Value> {
+    let filter = SkillFilter {
+        category: Some(category.to_string()),
+        tag: None,
+    };
+    let skills = all_skills(skills_dir)
+        .into_iter()
+        .filter(|s| filter.matches(s))
+        .map(|s| get_skill_resource(skills_dir, &s.full_name))
+        .collect::<Result<Vec<_>>>()?;
+    if skills.is_empty() {
+        return Err(anyhow::anyhow!("No skills in category: {}", category));
+    }
 
     Ok(json!({
-        "name": info.map(|i| i.name).unwrap_or(normalized),
-        "full_name": info.map(|i| i.full_name).unwrap_or(name),
-        "role": info.map(|i| i.role).unwrap_or("custom"),
-        "description": info.map(|i| i.description).unwrap_or("Custom skill"),
-        "content": content,
-        "mime_type": "text/markdown",
-        "overridden": overridden,
-        "source": if is_builtin && !overridden { "embedded" } else { "local" },
+        "category": category,
+        "skills": skills,
+        "count": skills.len(),
     }))
 }
 
@@ -304,32 +425,85 @@ mod tests {
     #[test]
     fn test_parse_frontmatter_description() {
         let md = "---\nname: foo\ndescription: A great skill\n---\n# Heading\n";
-        assert_eq!(
-            parse_frontmatter_description(md),
-            Some("A great skill".to_string())
-        );
+        let meta = parse_frontmatter(md).unwrap();
+        assert_eq!(meta.description, Some("A great skill".to_string()));
+        assert_eq!(meta.category, None);
     }
 
     #[test]
-    fn test_parse_frontmatter_no_description() {
-        let md = "---\nname: foo\n---\n# Heading\n";
-        assert_eq!(parse_frontmatter_description(md), None);
+    fn test_parse_frontmatter_metadata() {
+        let md = "---\ntitle: Deploys\ncategory: ops\ntags: [release, ci]\naudience: worker\n---\n";
+        let meta = parse_frontmatter(md).unwrap();
+        assert_eq!(meta.title, Some("Deploys".to_string()));
+        assert_eq!(meta.category, Some("ops".to_string()));
+        assert_eq!(meta.tags, vec!["release", "ci"]);
+        assert_eq!(meta.audience, vec!["worker"]);
+
+        // Nested under `metadata:`, with `role` as the category
+        let md = "---\nmetadata:\n  role: repair\n  tags: [fix]\n---\n";
+        let meta = parse_frontmatter(md).unwrap();
+        assert_eq!(meta.category, Some("repair".to_string()));
+        assert_eq!(meta.tags, vec!["fix"]);
     }
 
     #[test]
     fn test_parse_frontmatter_missing() {
-        assert_eq!(parse_frontmatter_description("# No frontmatter"), None);
+        assert_eq!(parse_frontmatter("# No frontmatter"), None);
+    }
+
+    #[test]
+    fn test_custom_skill_metadata_and_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let skill_dir = dir.path().join("deploy");
+        std::fs::create_dir_all(&skill_dir).unwrap();
+        std::fs::write(
+            skill_dir.join("SKILL.md"),
+            "---\ndescription: How we deploy\ncategory: ops\ntags: [release]\naudience: [worker]\n---\n# Deploy\n",
+        )
+        .unwrap();
+
+        let all = list_skills(Some(dir.path()), &SkillFilter::default()).unwrap();
+        assert_eq!(all["count"], 5);
+
+        let filter = SkillFilter {
+            category: Some("ops".into()),
+            tag: None,
+        };
+        let ops = list_skills(Some(dir.path()), &filter).unwrap();
+        assert_eq!(ops["count"], 1);
+        let skill = &ops["skills"][0];
+        assert_eq!(skill["name"], "deploy");
+        assert_eq!(skill["description"], "How we deploy");
+        assert_eq!(skill["tags"], json!(["release"]));
+        assert_eq!(skill["audience"], json!(["worker"]));
+        assert_eq!(skill["source"], "local");
+
+        let filter = SkillFilter {
+            category: None,
+            tag: Some("release".into()),
+        };
+        assert_eq!(list_skills(Some(dir.path()), &filter).unwrap()["count"], 1);
+
+        let by_category = get_skills_by_category(Some(dir.path()), "ops").unwrap();
+        assert_eq!(by_category["count"], 1);
+        assert!(
+            by_category["skills"][0]["content"]
+                .as_str()
+                .unwrap()
+                .contains("# Deploy")
+        );
+        assert!(get_skills_by_category(Some(dir.path()), "nope").is_err());
     }
 
     #[test]
     fn test_list_skills() {
-        let result = list_skills(None).unwrap();
+        let result = list_skills(None, &SkillFilter::default()).unwrap();
         assert_eq!(result["count"], 4);
     }
 
     #[test]
     fn test_list_skills_has_frontmatter_descriptions() {
-        let result = list_skills(None).unwrap();
+        let result = list_skills(None, &SkillFilter::default()).unwrap();
         let skills = result["skills"].as_array().unwrap();
         for skill in skills {
             let desc = skill["description"].as_str().unwrap();
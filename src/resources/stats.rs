@@ -25,19 +25,42 @@ pub fn get_stats_summary(db: &Database, states_config: &StatesConfig) -> Result<
     }))
 }
 
+/// Tag usage, with the tags open tasks need that no registered agent has.
+pub fn get_tag_stats(db: &Database, states_config: &StatesConfig) -> Result<Value> {
+    let tags = db.get_tag_stats(states_config)?;
+    let uncovered: Vec<&str> = tags
+        .iter()
+        .filter(|t| t.uncovered)
+        .map(|t| t.tag.as_str())
+        .collect();
+
+    Ok(json!({
+        "tags": tags,
+        "uncovered": uncovered
+    }))
+}
+
 /// Export tasks in ACP (Agent Coordination Protocol) compatible format.
 pub fn get_acp_plan(db: &Database) -> Result<Value> {
     let tasks = db.get_all_tasks()?;
     let deps = db.get_all_dependencies()?;
 
-    // Build dependency map
+    // Build dependency map (and per-blocker lag, where set)
     let mut blockers_map: std::collections::HashMap<String, Vec<String>> =
         std::collections::HashMap::new();
+    let mut lag_map: std::collections::HashMap<String, serde_json::Map<String, Value>> =
+        std::collections::HashMap::new();
     for dep in &deps {
         blockers_map
             .entry(dep.to_task_id.to_string())
             .or_default()
             .push(dep.from_task_id.to_string());
+        if let Some(lag) = dep.lag_ms {
+            lag_map
+                .entry(dep.to_task_id.to_string())
+                .or_default()
+                .insert(dep.from_task_id.to_string(), json!(lag));
+        }
     }
 
     // Convert tasks to ACP format
@@ -57,7 +80,7 @@ pub fn get_acp_plan(db: &Database) -> Result<Value> {
                 _ => &t.status, // Pass through other states
             };
 
-            json!({
+            let mut acp_task = json!({
                 "id": t.id.to_string(),
                 "title": t.title,
                 "description": t.description,
@@ -74,7 +97,12 @@ pub fn get_acp_plan(db: &Database) -> Result<Value> {
                         "usd": t.cost_usd
                     }
                 }
-            })
+            });
+            // Lag (ms after each blocker completes) lets planners offset start times
+            if let Some(lags) = lag_map.get(&t.id) {
+                acp_task["metadata"]["blockerLagMs"] = Value::Object(lags.clone());
+            }
+            acp_task
         })
         .collect();
 
@@ -2,12 +2,42 @@
 
 use crate::config::{DependenciesConfig, StatesConfig};
 use crate::db::Database;
+use crate::db::tasks::ListTasksQuery;
+use crate::types::{Task, TaskScope};
 use anyhow::Result;
 use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashSet};
 
-pub fn get_all_tasks(db: &Database) -> Result<Value> {
-    let tasks = db.get_all_tasks()?;
-    let deps = db.get_all_dependencies()?;
+/// Keep only the tasks of `project`, if given.
+fn in_project(tasks: &mut Vec<Task>, project: Option<&str>) {
+    if let Some(p) = project {
+        tasks.retain(|t| t.project == p);
+    }
+}
+
+pub fn get_all_tasks(
+    db: &Database,
+    project: Option<&str>,
+    scope: Option<&TaskScope>,
+) -> Result<Value> {
+    let mut tasks = match scope {
+        Some(_) => db.list_tasks(ListTasksQuery {
+            sort_by: Some("created_at"),
+            sort_order: Some("asc"),
+            scope,
+            ..Default::default()
+        })?,
+        None => db.get_all_tasks()?,
+    };
+    let mut deps = db.get_all_dependencies()?;
+    if project.is_some() || scope.is_some() {
+        in_project(&mut tasks, project);
+        // Links never cross projects; within a scope, keep only links between visible tasks
+        let ids: HashSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+        deps.retain(|d| {
+            ids.contains(d.from_task_id.as_str()) && ids.contains(d.to_task_id.as_str())
+        });
+    }
 
     Ok(json!({
         "tasks": tasks.iter().map(|t| json!({
@@ -39,8 +69,11 @@ pub fn get_ready_tasks(
     db: &Database,
     states_config: &StatesConfig,
     deps_config: &DependenciesConfig,
+    project: Option<&str>,
+    scope: Option<&TaskScope>,
 ) -> Result<Value> {
-    let tasks = db.get_ready_tasks(None, states_config, deps_config, None, None)?;
+    let mut tasks = db.get_ready_tasks(None, states_config, deps_config, None, None, scope)?;
+    in_project(&mut tasks, project);
 
     Ok(json!({
         "tasks": tasks.iter().map(|t| json!({
@@ -59,8 +92,27 @@ pub fn get_blocked_tasks(
     db: &Database,
     states_config: &StatesConfig,
     deps_config: &DependenciesConfig,
+    project: Option<&str>,
+    scope: Option<&TaskScope>,
 ) -> Result<Value> {
-    let tasks = db.get_blocked_tasks(states_config, deps_config, None, None)?;
+    let mut tasks = db.get_blocked_tasks(states_config, deps_config, None, None, scope)?;
+
+    // Tasks flagged with a blocked reason count as blocked even without dependencies
+    let mut flagged = db.get_tasks_with_blocked_reason()?;
+    db.retain_visible(&mut flagged, scope)?;
+    for t in flagged {
+        if !tasks.iter().any(|b| b.id == t.id) {
+            tasks.push(t);
+        }
+    }
+    in_project(&mut tasks, project);
+
+    let mut by_reason: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for t in &tasks {
+        if let Some(ref reason) = t.blocked_reason {
+            by_reason.entry(reason).or_default().push(&t.id);
+        }
+    }
 
     Ok(json!({
         "tasks": tasks.iter().map(|t| {
@@ -69,14 +121,23 @@ pub fn get_blocked_tasks(
                 "id": &t.id,
                 "title": t.title,
                 "priority": t.priority,
-                "blocked_by": &blockers
+                "blocked_by": &blockers,
+                "blocked_reason": t.blocked_reason,
+                "blocked_note": t.blocked_note
             })
-        }).collect::<Vec<_>>()
+        }).collect::<Vec<_>>(),
+        "by_reason": by_reason
     }))
 }
 
-pub fn get_claimed_tasks(db: &Database, agent_id: Option<&str>) -> Result<Value> {
-    let tasks = db.get_claimed_tasks(agent_id)?;
+pub fn get_claimed_tasks(
+    db: &Database,
+    agent_id: Option<&str>,
+    project: Option<&str>,
+    scope: Option<&TaskScope>,
+) -> Result<Value> {
+    let mut tasks = db.get_claimed_tasks(agent_id, scope)?;
+    in_project(&mut tasks, project);
 
     Ok(json!({
         "tasks": tasks.iter().map(|t| json!({
@@ -91,6 +152,16 @@ pub fn get_claimed_tasks(db: &Database, agent_id: Option<&str>) -> Result<Value>
     }))
 }
 
+/// Deadlines of claimed, unfinished tasks with the reminders fired for each.
+pub fn get_deadlines(db: &Database, states_config: &StatesConfig) -> Result<Value> {
+    Ok(json!({ "deadlines": db.get_claimed_deadlines(states_config)? }))
+}
+
+/// Projects with their task counts by status and connected agents.
+pub fn get_projects(db: &Database) -> Result<Value> {
+    Ok(json!({ "projects": db.list_projects()? }))
+}
+
 pub fn get_task_tree(db: &Database, task_id: &str) -> Result<Value> {
     let tree = db
         .get_task_tree(task_id)?
@@ -98,3 +169,36 @@ pub fn get_task_tree(db: &Database, task_id: &str) -> Result<Value> {
 
     Ok(serde_json::to_value(tree)?)
 }
+
+/// The blocking graph of a subtree, or of every live task, as JSON.
+pub fn get_dependency_matrix(
+    db: &Database,
+    root: Option<&str>,
+    deps_config: &DependenciesConfig,
+) -> Result<Value> {
+    let matrix = db.dependency_matrix(root, deps_config, None)?;
+    let tasks: Vec<Value> = matrix
+        .tasks
+        .iter()
+        .map(|t| {
+            json!({
+                "id": t.id,
+                "title": t.title,
+                "status": t.status,
+                "blockers": t.blockers,
+                "transitive_blockers": t.transitive_blockers
+            })
+        })
+        .collect();
+    let edges: Vec<Value> = matrix
+        .edges
+        .iter()
+        .map(|(from, to, dep_type)| json!({"from": from, "to": to, "type": dep_type}))
+        .collect();
+
+    Ok(json!({
+        "root": matrix.root,
+        "tasks": tasks,
+        "edges": edges
+    }))
+}
@@ -0,0 +1,398 @@
+//! Finding TODO-style comments in source files.
+//!
+//! `scan_source` walks the configured directories and turns every comment
+//! marker (TODO, FIXME, and HACK by default) into a [`CodeTodo`]. A marker only
+//! counts inside a comment: `//`, `/*`, `#`, or `<!--` must come before it on
+//! the line, or the line must start with `*`, `;`, `--`, or `%`. An optional
+//! `(author)` and `:` after the marker are dropped from the text.
+//!
+//! Each TODO gets a fingerprint from its file, marker, and text (plus an
+//! occurrence number for repeats within a file). Moving a TODO to another line
+//! keeps its fingerprint; editing its text or moving it to another file does not.
+
+use crate::config::SourceScanConfig;
+use anyhow::{Result, anyhow};
+use regex_lite::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Lines of surrounding code kept on each side of a TODO.
+pub const CONTEXT_LINES: usize = 2;
+
+/// Longest task title generated from a TODO, in characters.
+const MAX_TITLE_CHARS: usize = 100;
+
+/// Comment leaders that may appear anywhere before the marker.
+const INLINE_LEADERS: &[&str] = &["//", "/*", "#", "<!--"];
+
+/// Comment leaders that must start the line.
+const LINE_LEADERS: &[&str] = &["*", ";", "--", "%"];
+
+/// Files are treated as binary when this prefix contains a NUL byte.
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+/// A TODO-style comment found in a source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeTodo {
+    /// The marker that matched, e.g. `FIXME`.
+    pub marker: String,
+    /// Comment text after the marker (may be empty).
+    pub text: String,
+    /// File path relative to the scan root, with forward slashes.
+    pub file: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// Up to [`CONTEXT_LINES`] lines either side of the TODO, including it.
+    pub context: Vec<String>,
+    /// Stable identity used to match the TODO to its task.
+    pub fingerprint: String,
+}
+
+impl CodeTodo {
+    /// `file:line`, as stored on the task.
+    pub fn location(&self) -> String {
+        format!("{}:{}", self.file, self.line)
+    }
+
+    /// Task title: the marker and its text, shortened if needed.
+    pub fn title(&self) -> String {
+        let title = if self.text.is_empty() {
+            format!("{} in {}", self.marker, self.file)
+        } else {
+            format!("{}: {}", self.marker, self.text)
+        };
+        if title.chars().count() <= MAX_TITLE_CHARS {
+            return title;
+        }
+        let mut short: String = title.chars().take(MAX_TITLE_CHARS - 1).collect();
+        short.push('…');
+        short
+    }
+
+    /// Task description: the location and the surrounding code.
+    pub fn description(&self) -> String {
+        let lang = Path::new(&self.file)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("");
+        format!(
+            "{} at `{}`:\n\n```{}\n{}\n```",
+            self.marker,
+            self.location(),
+            lang,
+            self.context.join("\n")
+        )
+    }
+}
+
+/// TODOs found by a scan.
+#[derive(Debug, Default)]
+pub struct SourceScan {
+    pub todos: Vec<CodeTodo>,
+    /// Number of text files read.
+    pub files_scanned: usize,
+}
+
+/// Extracts TODOs from files under a root, following a [`SourceScanConfig`].
+pub struct SourceScanner<'a> {
+    config: &'a SourceScanConfig,
+    root: PathBuf,
+    pattern: Regex,
+}
+
+impl<'a> SourceScanner<'a> {
+    /// Build a scanner for `root`, which should be absolute.
+    pub fn new(config: &'a SourceScanConfig, root: PathBuf) -> Result<Self> {
+        let markers: Vec<String> = config
+            .markers
+            .iter()
+            .map(|m| m.trim())
+            .filter(|m| !m.is_empty())
+            .map(regex_lite::escape)
+            .collect();
+        if markers.is_empty() {
+            return Err(anyhow!("source_scan.markers: needs at least one marker"));
+        }
+        let pattern = Regex::new(&format!(
+            r"(?:^|[^A-Za-z0-9_])({})(?:\([^)]*\))?(?::|\s|$)(.*)",
+            markers.join("|")
+        ))?;
+        Ok(Self {
+            config,
+            root,
+            pattern,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Absolute path of a directory (or file) given relative to the root.
+    pub fn resolve(&self, dir: &Path) -> PathBuf {
+        self.root.join(dir).components().collect()
+    }
+
+    /// Scan each of `dirs` (relative to the root) recursively.
+    pub fn scan_dirs(&self, dirs: &[PathBuf]) -> Result<SourceScan> {
+        let mut files = Vec::new();
+        for dir in dirs {
+            let path = self.resolve(dir);
+            if path.is_file() {
+                files.push(path);
+            } else if path.is_dir() {
+                self.collect_files(&path, &mut files)?;
+            } else {
+                return Err(anyhow!("Source directory not found: {}", dir.display()));
+            }
+        }
+        files.sort();
+        files.dedup();
+
+        let mut scan = SourceScan::default();
+        for path in &files {
+            if let Some(todos) = self.scan_file(path)? {
+                scan.files_scanned += 1;
+                scan.todos.extend(todos);
+            }
+        }
+        Ok(scan)
+    }
+
+    /// TODOs in one file, or None if the file is skipped (filtered out, too
+    /// large, binary, not UTF-8, or gone).
+    pub fn scan_file(&self, path: &Path) -> Result<Option<Vec<CodeTodo>>> {
+        if !self.wants_file(path) {
+            return Ok(None);
+        }
+        let Ok(meta) = fs::metadata(path) else {
+            return Ok(None);
+        };
+        if !meta.is_file() || meta.len() > self.config.max_file_bytes {
+            return Ok(None);
+        }
+        let bytes = fs::read(path)?;
+        if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+            return Ok(None);
+        }
+        let Ok(text) = String::from_utf8(bytes) else {
+            return Ok(None);
+        };
+        Ok(Some(self.scan_text(&self.relative(path), &text)))
+    }
+
+    /// TODOs in file contents; `file` is the path recorded on each TODO.
+    pub fn scan_text(&self, file: &str, text: &str) -> Vec<CodeTodo> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut seen: HashMap<(String, String), usize> = HashMap::new();
+        let mut todos = Vec::new();
+
+        for (index, line) in lines.iter().enumerate() {
+            let Some((marker, body)) = self.find_marker(line) else {
+                continue;
+            };
+            let occurrence = seen.entry((marker.clone(), body.clone())).or_insert(0);
+            let fingerprint = fingerprint(file, &marker, &body, *occurrence);
+            *occurrence += 1;
+
+            let first = index.saturating_sub(CONTEXT_LINES);
+            let last = (index + CONTEXT_LINES + 1).min(lines.len());
+            todos.push(CodeTodo {
+                marker,
+                text: body,
+                file: file.to_string(),
+                line: index + 1,
+                context: lines[first..last].iter().map(|l| l.to_string()).collect(),
+                fingerprint,
+            });
+        }
+        todos
+    }
+
+    /// The first marker on a line that sits inside a comment, with its text.
+    fn find_marker(&self, line: &str) -> Option<(String, String)> {
+        let trimmed = line.trim_start();
+        let line_comment = LINE_LEADERS.iter().any(|l| trimmed.starts_with(l));
+
+        for caps in self.pattern.captures_iter(line) {
+            let marker = caps.get(1)?;
+            let before = &line[..marker.start()];
+            if !line_comment && !INLINE_LEADERS.iter().any(|l| before.contains(l)) {
+                continue;
+            }
+            let body = caps.get(2).map_or("", |m| m.as_str());
+            let body = body
+                .trim()
+                .trim_end_matches("-->")
+                .trim_end_matches("*/")
+                .trim()
+                .trim_start_matches([':', '-'])
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            return Some((marker.as_str().to_string(), body));
+        }
+        None
+    }
+
+    /// Whether a file passes the ignore and extension filters.
+    pub fn wants_file(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let ignored = relative.components().any(|c| match c {
+            Component::Normal(name) => self.config.ignore.iter().any(|i| name == i.as_str()),
+            _ => false,
+        });
+        if ignored {
+            return false;
+        }
+        self.config.extensions.is_empty()
+            || path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|ext| {
+                    self.config
+                        .extensions
+                        .iter()
+                        .any(|want| want.trim_start_matches('.').eq_ignore_ascii_case(ext))
+                })
+    }
+
+    /// Path relative to the root with forward slashes (absolute if outside it).
+    pub fn relative(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                Component::RootDir => Some(String::new()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn collect_files(&self, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if self.config.ignore.iter().any(|i| name == i.as_str()) {
+                continue;
+            }
+            // Symlinks are skipped so that link cycles cannot recurse forever
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                self.collect_files(&entry.path(), files)?;
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hex digest identifying a TODO independently of its line number.
+fn fingerprint(file: &str, marker: &str, text: &str, occurrence: usize) -> String {
+    let mut hasher = Sha256::new();
+    for part in [file, marker, text, &occurrence.to_string()] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.finalize()[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scanner(config: &SourceScanConfig) -> SourceScanner<'_> {
+        SourceScanner::new(config, PathBuf::from("/repo")).unwrap()
+    }
+
+    #[test]
+    fn finds_markers_in_comments_only() {
+        let config = SourceScanConfig::default();
+        let text = "fn main() {\n    // TODO: handle errors\n    let s = \"TODO: not a comment\";\n    # FIXME(ana) - flaky\n    /* HACK */\n    // TODOS are plural\n}\n";
+        let todos = scanner(&config).scan_text("src/main.rs", text);
+
+        let found: Vec<(&str, &str, usize)> = todos
+            .iter()
+            .map(|t| (t.marker.as_str(), t.text.as_str(), t.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("TODO", "handle errors", 2),
+                ("FIXME", "flaky", 4),
+                ("HACK", "", 5)
+            ]
+        );
+        assert_eq!(todos[0].location(), "src/main.rs:2");
+        assert_eq!(todos[0].title(), "TODO: handle errors");
+        assert_eq!(todos[2].title(), "HACK in src/main.rs");
+        assert_eq!(todos[0].context.len(), 4);
+    }
+
+    #[test]
+    fn line_leaders_must_start_the_line() {
+        let config = SourceScanConfig::default();
+        let text =
+            "-- TODO: index this\nSELECT 1; -- TODO: inline\nx = y; TODO: bare\n * TODO: doc\n";
+        let todos = scanner(&config).scan_text("q.sql", text);
+        let texts: Vec<&str> = todos.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, vec!["index this", "doc"]);
+    }
+
+    #[test]
+    fn fingerprint_ignores_line_but_not_text() {
+        let config = SourceScanConfig::default();
+        let s = scanner(&config);
+        let before = s.scan_text("a.rs", "// TODO: one\n");
+        let moved = s.scan_text("a.rs", "\n\n// TODO:   one\n");
+        let edited = s.scan_text("a.rs", "// TODO: two\n");
+        let other_file = s.scan_text("b.rs", "// TODO: one\n");
+
+        assert_eq!(before[0].fingerprint, moved[0].fingerprint);
+        assert_ne!(before[0].fingerprint, edited[0].fingerprint);
+        assert_ne!(before[0].fingerprint, other_file[0].fingerprint);
+    }
+
+    #[test]
+    fn repeated_todos_get_distinct_fingerprints() {
+        let config = SourceScanConfig::default();
+        let todos = scanner(&config).scan_text("a.rs", "// TODO: same\n// TODO: same\n");
+        assert_eq!(todos.len(), 2);
+        assert_ne!(todos[0].fingerprint, todos[1].fingerprint);
+    }
+
+    #[test]
+    fn custom_markers_and_extension_filter() {
+        let config = SourceScanConfig {
+            markers: vec!["XXX".to_string()],
+            extensions: vec!["py".to_string()],
+            ..Default::default()
+        };
+        let s = scanner(&config);
+        let todos = s.scan_text("a.py", "# XXX: fix\n# TODO: ignored\n");
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].marker, "XXX");
+
+        assert!(s.wants_file(Path::new("/repo/src/a.py")));
+        assert!(!s.wants_file(Path::new("/repo/src/a.rs")));
+        assert!(!s.wants_file(Path::new("/repo/target/a.py")));
+    }
+
+    #[test]
+    fn long_titles_are_shortened() {
+        let config = SourceScanConfig::default();
+        let text = format!("// TODO: {}\n", "word ".repeat(40));
+        let todos = scanner(&config).scan_text("a.rs", &text);
+        let title = todos[0].title();
+        assert_eq!(title.chars().count(), MAX_TITLE_CHARS);
+        assert!(title.ends_with('…'));
+    }
+}
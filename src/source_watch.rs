@@ -0,0 +1,153 @@
+//! Filesystem watcher that keeps `from-code` tasks in step with source edits.
+//!
+//! When `source_scan.watch` is set, files changed under the configured `dirs`
+//! are rescanned with the same debounced `notify` watcher used for config
+//! hot-reload and mark watching. Only the changed files are read: new TODOs
+//! become tasks, moved ones get their location updated, and open tasks whose
+//! TODO disappeared are resolved, as `scan_source` does with `resolve=true`.
+//! TODOs that predate the watcher are only picked up by a `scan_source` call.
+
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::mark_watch::{changed_files, resolve_root};
+use crate::source_scan::SourceScanner;
+use crate::tools::source::{SourceSync, SyncOptions, sync_todos};
+use anyhow::Result;
+use notify_debouncer_mini::new_debouncer;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, mpsc};
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Handle to the running source watcher (dropping it does not stop the watcher
+/// thread, which lives as long as the process).
+pub struct SourceWatchHandle {
+    /// Absolute root the scanned directories are relative to.
+    pub root: PathBuf,
+    _task_handle: tokio::task::JoinHandle<()>,
+}
+
+/// Rescan changed files (absolute paths) and sync their TODOs with tasks.
+///
+/// Files outside `dirs` (absolute) or excluded by the scan filters are
+/// ignored; files that are gone count as having no TODOs.
+pub fn rescan_files(
+    db: &Database,
+    config: &AppConfig,
+    scanner: &SourceScanner<'_>,
+    dirs: &[PathBuf],
+    files: &[PathBuf],
+) -> Result<SourceSync> {
+    let mut scope: HashSet<String> = HashSet::new();
+    let mut todos = Vec::new();
+    for path in files {
+        if !dirs.iter().any(|dir| path.starts_with(dir)) || !scanner.wants_file(path) {
+            continue;
+        }
+        scope.insert(scanner.relative(path));
+        if let Some(found) = scanner.scan_file(path)? {
+            todos.extend(found);
+        }
+    }
+    if scope.is_empty() {
+        return Ok(SourceSync::default());
+    }
+
+    sync_todos(
+        db,
+        config,
+        &todos,
+        |file| scope.contains(file),
+        SyncOptions {
+            parent: config.source_scan.parent.as_deref(),
+            resolve: true,
+            dry_run: false,
+        },
+    )
+}
+
+/// Start rescanning source files as they change.
+///
+/// `on_change` is called after each batch that created, updated, or resolved
+/// at least one task.
+pub fn start_source_watcher<F>(
+    db: Arc<Database>,
+    config: AppConfig,
+    on_change: F,
+) -> Result<SourceWatchHandle, notify::Error>
+where
+    F: Fn(&SourceSync) + Send + 'static,
+{
+    let scan_config = Arc::clone(&config.source_scan);
+    let root = resolve_root(scan_config.root.as_deref()).map_err(notify::Error::io)?;
+    // Fail now rather than on the first change if the markers do not compile
+    SourceScanner::new(&scan_config, root.clone())
+        .map_err(|e| notify::Error::generic(&e.to_string()))?;
+
+    let (notify_tx, notify_rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(scan_config.debounce_ms), notify_tx)?;
+    let mut dirs = Vec::new();
+    for dir in &scan_config.dirs {
+        let path: PathBuf = root.join(dir).components().collect();
+        debouncer
+            .watcher()
+            .watch(&path, notify::RecursiveMode::Recursive)?;
+        dirs.push(path);
+    }
+    info!("Watching source for TODO changes: {}", display_dirs(&dirs));
+
+    let watch_root = root.clone();
+    let task_handle = tokio::task::spawn_blocking(move || {
+        // Keep the debouncer alive
+        let _debouncer = debouncer;
+        let Ok(scanner) = SourceScanner::new(&scan_config, watch_root.clone()) else {
+            return;
+        };
+
+        loop {
+            match notify_rx.recv() {
+                Ok(Ok(events)) => {
+                    let files: Vec<PathBuf> =
+                        changed_files(&events, &watch_root, &scan_config.ignore)
+                            .into_iter()
+                            .map(PathBuf::from)
+                            .collect();
+                    if files.is_empty() {
+                        continue;
+                    }
+                    match rescan_files(&db, &config, &scanner, &dirs, &files) {
+                        Ok(sync) if sync.changed() => {
+                            info!(
+                                "Source TODOs: {} created, {} updated, {} resolved",
+                                sync.created.len(),
+                                sync.updated.len(),
+                                sync.resolved.len()
+                            );
+                            on_change(&sync);
+                        }
+                        Ok(_) => {}
+                        Err(e) => error!("Failed to sync source TODOs: {}", e),
+                    }
+                }
+                Ok(Err(e)) => error!("Source watcher error: {}", e),
+                Err(_) => {
+                    info!("Source watcher channel closed, stopping");
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(SourceWatchHandle {
+        root,
+        _task_handle: task_handle,
+    })
+}
+
+fn display_dirs(dirs: &[PathBuf]) -> String {
+    dirs.iter()
+        .map(|d| d.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
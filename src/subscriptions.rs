@@ -28,6 +28,8 @@ pub enum MutationKind {
     AgentChanged,
     /// An attachment was added or removed.
     AttachmentChanged,
+    /// Deadline reminders came due for claimed tasks.
+    DeadlineReminder,
 }
 
 impl MutationKind {
@@ -40,25 +42,69 @@ impl MutationKind {
                 "query://tasks/ready",
                 "query://tasks/blocked",
                 "query://tasks/claimed",
+                "query://agents/groups",
                 "query://stats/summary",
+                "tags://stats",
+                "deps://matrix",
+                "query://deadlines",
             ],
             MutationKind::DependencyChanged => &[
                 "query://tasks/all",
                 "query://tasks/ready",
                 "query://tasks/blocked",
                 "query://stats/summary",
+                "deps://matrix",
             ],
-            MutationKind::FileMarkChanged => &["query://files/marks"],
+            MutationKind::FileMarkChanged => &["query://files/marks", "query://files/history"],
             MutationKind::AgentChanged => &[
                 "query://agents/all",
+                "query://agents/groups",
                 "query://tasks/claimed",
                 "query://stats/summary",
+                "tags://stats",
             ],
             MutationKind::AttachmentChanged => &["query://tasks/all", "query://stats/summary"],
+            MutationKind::DeadlineReminder => &["query://deadlines"],
         }
     }
 }
 
+impl MutationKind {
+    /// Every mutation kind.
+    pub const ALL: [MutationKind; 6] = [
+        MutationKind::TaskChanged,
+        MutationKind::DependencyChanged,
+        MutationKind::FileMarkChanged,
+        MutationKind::AgentChanged,
+        MutationKind::AttachmentChanged,
+        MutationKind::DeadlineReminder,
+    ];
+
+    /// The mutation kinds that can change the resource at `uri`.
+    pub fn affecting(uri: &str) -> Vec<MutationKind> {
+        Self::ALL
+            .into_iter()
+            .filter(|kind| kind.affected_uris().contains(&uri))
+            .collect()
+    }
+}
+
+/// URI prefix of the change feed. Feed URIs embed a cursor, so subscriptions
+/// to them are matched by prefix rather than exactly.
+pub const CHANGE_FEED_PREFIX: &str = "changes://";
+
+impl MutationKind {
+    /// Whether this kind of mutation appends to the change feed.
+    pub fn feeds_changes(&self) -> bool {
+        matches!(
+            self,
+            MutationKind::TaskChanged
+                | MutationKind::DependencyChanged
+                | MutationKind::AttachmentChanged
+        )
+    }
+}
+
 /// Manages resource subscriptions for the connected MCP client.
 ///
 /// Thread-safe: uses an internal `Mutex` so it can be shared across async
@@ -110,6 +156,11 @@ impl SubscriptionManager {
                 }
             }
         }
+        if mutations.iter().any(MutationKind::feeds_changes) {
+            for uri in set.iter().filter(|u| u.starts_with(CHANGE_FEED_PREFIX)) {
+                result.insert(uri.clone());
+            }
+        }
         result.into_iter().collect()
     }
 }
@@ -167,6 +218,37 @@ mod tests {
         assert!(affected.contains(&"query://files/marks".to_string()));
     }
 
+    #[test]
+    fn test_change_feed_subscriptions_match_by_prefix() {
+        let mgr = SubscriptionManager::new();
+        mgr.subscribe("changes://since/42");
+
+        let affected = mgr.affected_subscriptions(&[MutationKind::DependencyChanged]);
+        assert_eq!(affected, vec!["changes://since/42".to_string()]);
+
+        // File marks and agents are not part of the feed
+        let affected = mgr
+            .affected_subscriptions(&[MutationKind::FileMarkChanged, MutationKind::AgentChanged]);
+        assert!(affected.is_empty());
+    }
+
+    #[test]
+    fn test_affecting_inverts_affected_uris() {
+        assert_eq!(
+            MutationKind::affecting("query://tasks/blocked"),
+            vec![MutationKind::TaskChanged, MutationKind::DependencyChanged]
+        );
+        assert_eq!(
+            MutationKind::affecting("query://files/marks"),
+            vec![MutationKind::FileMarkChanged]
+        );
+        assert_eq!(
+            MutationKind::affecting("query://deadlines"),
+            vec![MutationKind::TaskChanged, MutationKind::DeadlineReminder]
+        );
+        assert!(MutationKind::affecting("query://unknown").is_empty());
+    }
+
     #[test]
     fn test_no_subscriptions_returns_empty() {
         let mgr = SubscriptionManager::new();
@@ -1,14 +1,19 @@
 //! Worker connection and management tools.
 
-use super::{get_bool, get_i32, get_string, get_string_array, make_tool_with_prompts};
+use super::{get_bool, get_i32, get_project, get_string, get_string_array, make_tool_with_prompts};
 use crate::config::workflows::WorkflowsConfig;
-use crate::config::{AppConfig, Prompts, ServerPaths, StatesConfig};
+use crate::config::{AppConfig, Prompts, ServerPaths, StaleAction, StalePolicy, StatesConfig};
 use crate::db::Database;
 use crate::error::ToolError;
-use crate::format::{OutputFormat, ToolResult, format_workers_markdown};
+use crate::format::{OutputFormat, ToolResult, format_compact_rows, format_workers_markdown};
+use crate::types::{CleanupSummary, Worker};
 use anyhow::Result;
 use rmcp::model::Tool;
 use serde_json::{Value, json};
+use std::path::Path;
+
+/// Default seconds since the last heartbeat within which `connect(resume=true)` restores a session.
+const DEFAULT_RESUME_GRACE_SECONDS: i64 = 300;
 
 /// Options for connecting a worker to the task graph.
 pub struct ConnectOptions<'a> {
@@ -17,13 +22,15 @@ pub struct ConnectOptions<'a> {
     pub config: &'a AppConfig,
     /// Per-connect workflow (may differ from config.workflows for named workflows).
     pub workflows: &'a WorkflowsConfig,
+    /// Skill override directory, for skills delivered on connect.
+    pub skills_dir: Option<&'a Path>,
 }
 
 pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
     vec![
         make_tool_with_prompts(
             "connect",
-            "Connect as a worker. Call this FIRST before using other tools. Returns worker_id (save it for all subsequent calls). Tags enable task affinity matching.",
+            "Connect as a worker. Call this FIRST before using other tools. Returns worker_id (save it for all subsequent calls). Tags enable task affinity matching. After a crash or restart, reconnect with the same worker_id and resume=true to get your claims back.",
             json!({
                 "worker_id": {
                     "type": "string",
@@ -38,6 +45,14 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
                     "type": "boolean",
                     "description": "Force reconnection if worker ID already exists (default: false). Use for stuck worker recovery."
                 },
+                "resume": {
+                    "type": "boolean",
+                    "description": "Resume the session of an existing worker_id (default: false). Within the grace window, claims, marks, and wait registrations are kept and returned; after it, the stale registration is evicted and a fresh one created. Requires worker_id."
+                },
+                "grace_seconds": {
+                    "type": "integer",
+                    "description": "Resume grace window: seconds since the worker's last heartbeat within which its session can be resumed. Default: 300 (5 minutes)."
+                },
                 "db_path": {
                     "type": "string",
                     "description": "Override database file path (same as TASK_GRAPH_DB_PATH env var). Note: Can only be set before server starts."
@@ -62,6 +77,10 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
                     "type": "array",
                     "items": { "type": "string" },
                     "description": "Overlay names to apply on top of the workflow, in order (e.g., ['git', 'user-request']). Use list_workflows to see available overlays."
+                },
+                "project": {
+                    "type": "string",
+                    "description": "Project to work in (default: 'default'). Agents only see, claim, and assign tasks of their own project. A resumed session keeps its project."
                 }
             }),
             vec![],
@@ -105,9 +124,22 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
                     "type": "integer",
                     "description": "Task relationship depth (-3 to 3). Negative: ancestors, positive: descendants. Used with 'task' filter."
                 },
+                "group": {
+                    "type": "string",
+                    "description": "Filter workers that belong to this agent group"
+                },
+                "project": {
+                    "type": "string",
+                    "description": "Filter workers connected to this project"
+                },
                 "stale_timeout": {
                     "type": "integer",
                     "description": "Seconds without heartbeat before a worker is considered stale and evicted. Set to 0 to disable auto-cleanup. Default: 300 (5 minutes)."
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["json", "markdown", "compact"],
+                    "description": "Output format (default: server setting). compact: a header of short keys, then one tab-separated row per worker (id, claims, hb_age_ms, project, thought)"
                 }
             }),
             vec![],
@@ -115,7 +147,7 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
         ),
         make_tool_with_prompts(
             "cleanup_stale",
-            "Evict stale workers that haven't sent a heartbeat within the timeout period. Releases their task claims and file locks.",
+            "Evict stale workers that haven't sent a heartbeat within the timeout period. Releases their file locks and applies the configured stale-claim policy (requeue, fail, escalate, or reassign) to their task claims.",
             json!({
                 "timeout": {
                     "type": "integer",
@@ -124,7 +156,7 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
                 "final_status": {
                     "type": "string",
                     "enum": ["pending", "completed", "cancelled", "failed"],
-                    "description": "Status to set released tasks to (default: config disconnect_status, typically 'pending'). Must be an untimed status."
+                    "description": "Override the configured stale-claim policies and release every claim with this status. Must be an untimed status."
                 }
             }),
             vec![],
@@ -162,6 +194,31 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
             vec!["worker_id", "overlay"],
             prompts,
         ),
+        make_tool_with_prompts(
+            "register_group",
+            "Add workers to an agent group (team), or remove them with leave=true. Tasks with needed_group can only be claimed or assigned by group members. Members of groups defined in config join automatically on connect.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "The worker joining (or leaving) the group"
+                },
+                "group": {
+                    "type": "string",
+                    "description": "Group name (e.g., 'frontend', 'reviewers')"
+                },
+                "members": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Additional worker IDs to add or remove along with worker_id (e.g., a coordinator forming a team)"
+                },
+                "leave": {
+                    "type": "boolean",
+                    "description": "Remove the workers from the group instead of adding them (default: false)"
+                }
+            }),
+            vec!["worker_id", "group"],
+            prompts,
+        ),
     ]
 }
 
@@ -171,6 +228,7 @@ pub fn connect(opts: ConnectOptions<'_>, args: Value) -> Result<Value> {
         server_paths,
         config,
         workflows,
+        skills_dir,
     } = opts;
 
     let states_config = &config.states;
@@ -180,10 +238,23 @@ pub fn connect(opts: ConnectOptions<'_>, args: Value) -> Result<Value> {
     let ids_config = &config.ids;
 
     let worker_id = get_string(&args, "worker_id");
-    let tags = get_string_array(&args, "tags").unwrap_or_default();
+    let requested_tags = get_string_array(&args, "tags").map(|t| tags_config.canonical_tags(&t));
+    let tags = requested_tags.clone().unwrap_or_default();
     let force = get_bool(&args, "force").unwrap_or(false);
+    let resume = get_bool(&args, "resume").unwrap_or(false);
+    let grace_seconds = get_i32(&args, "grace_seconds")
+        .map(i64::from)
+        .unwrap_or(DEFAULT_RESUME_GRACE_SECONDS);
     let workflow = get_string(&args, "workflow");
 
+    if resume && worker_id.is_none() {
+        return Err(ToolError::invalid_value(
+            "resume",
+            "requires the worker_id of the session to resume",
+        )
+        .into());
+    }
+
     // Validate tags if provided
     let tag_warnings = tags_config.validate_tags(&tags)?;
 
@@ -234,8 +305,56 @@ pub fn connect(opts: ConnectOptions<'_>, args: Value) -> Result<Value> {
         }
     }
 
-    let overlays = get_string_array(&args, "overlays").unwrap_or_default();
-    let worker = db.register_worker(worker_id, tags, force, ids_config, workflow, overlays)?;
+    let requested_overlays = get_string_array(&args, "overlays");
+    let overlays = requested_overlays.clone().unwrap_or_default();
+    let project = get_project(&args)?;
+
+    let mut session = None;
+    let mut expired_session = None;
+    if resume && let Some(ref id) = worker_id {
+        // Claims and marks belong to the session's project, so a resume cannot switch it
+        if let (Some(requested), Some(existing)) = (&project, db.get_worker(id)?)
+            && *requested != existing.project
+        {
+            return Err(ToolError::invalid_value(
+                "project",
+                &format!(
+                    "Worker '{}' is in project '{}'. Reconnect with force=true to switch projects.",
+                    id, existing.project
+                ),
+            )
+            .into());
+        }
+        session = db.resume_worker(
+            id,
+            requested_tags,
+            workflow.clone(),
+            requested_overlays,
+            grace_seconds,
+        )?;
+        if session.is_none() && db.get_worker(id)?.is_some() {
+            // Outside the grace window: evict the stale registration before starting fresh
+            let _ = db.release_worker_locks(id);
+            expired_session = Some(db.unregister_worker(id, &states_config.disconnect_state)?);
+        }
+    }
+
+    let worker = match session {
+        Some((ref worker, _)) => worker.clone(),
+        None => {
+            let mut worker =
+                db.register_worker(worker_id, tags, force, ids_config, workflow, overlays)?;
+            if let Some(project) = project {
+                db.set_worker_project(&worker.id, &project)?;
+                worker.project = project;
+            }
+            worker
+        }
+    };
+
+    // Join the configured groups that list this worker
+    db.join_groups(&worker.id, &config.groups.groups_for(&worker.id))?;
+    let groups = db.get_worker_groups(&worker.id)?;
 
     // Build config summary for the response
     let timed_states: Vec<&str> = states_config
@@ -259,6 +378,7 @@ pub fn connect(opts: ConnectOptions<'_>, args: Value) -> Result<Value> {
         "max_claims": worker.max_claims,
         "registered_at": worker.registered_at,
         "workflow": worker.workflow,
+        "project": worker.project,
         "paths": {
             "db_path": server_paths.db_path.to_string_lossy(),
             "media_dir": server_paths.media_dir.to_string_lossy(),
@@ -313,6 +433,24 @@ pub fn connect(opts: ConnectOptions<'_>, args: Value) -> Result<Value> {
         }
     }
 
+    // Deliver the skills configured for this agent's role and tags
+    let role = workflows.match_role(&worker.tags);
+    let (skills, skill_warnings) = super::skills::deliver_on_connect(
+        db,
+        config,
+        workflows,
+        skills_dir,
+        &worker.id,
+        role.as_deref(),
+        &worker.tags,
+    )?;
+    if !skills.is_empty() {
+        response["skills"] = json!(skills);
+    }
+    if !skill_warnings.is_empty() {
+        response["skill_warnings"] = json!(skill_warnings);
+    }
+
     // Include workflow description if available
     if let Some(ref desc) = workflows.description {
         response["workflow_description"] = json!(desc);
@@ -323,6 +461,20 @@ pub fn connect(opts: ConnectOptions<'_>, args: Value) -> Result<Value> {
         response["overlays"] = json!(worker.overlays);
     }
 
+    if !groups.is_empty() {
+        response["groups"] = json!(groups);
+    }
+
+    if resume {
+        response["resumed"] = json!(session.is_some());
+        if let Some((_, session)) = session {
+            response["session"] = json!(session);
+        }
+        if let Some(summary) = expired_session {
+            response["expired_session"] = json!(summary);
+        }
+    }
+
     Ok(response)
 }
 
@@ -363,29 +515,40 @@ pub fn disconnect(db: &Database, states_config: &StatesConfig, args: Value) -> R
 
 pub fn list_agents(
     db: &Database,
-    states_config: &StatesConfig,
+    config: &AppConfig,
     format: OutputFormat,
     args: Value,
 ) -> Result<ToolResult> {
     // Extract filter parameters
-    let tags = get_string_array(&args, "tags");
+    let tags = get_string_array(&args, "tags").map(|t| config.tags.canonical_tags(&t));
     let file = get_string(&args, "file");
     let task = get_string(&args, "task");
     let depth = get_i32(&args, "depth").unwrap_or(0).clamp(-3, 3);
+    let group = get_string(&args, "group");
+    let project = get_project(&args)?;
+    let format = get_string(&args, "format")
+        .and_then(|s| OutputFormat::parse(&s))
+        .unwrap_or(format);
 
     // Auto-cleanup stale workers (default 5 minutes, 0 to disable)
     let stale_timeout = get_i32(&args, "stale_timeout").unwrap_or(300);
     let cleanup_summary = if stale_timeout > 0 {
-        let final_status = states_config.disconnect_state.clone();
-        db.cleanup_stale_workers(stale_timeout as i64, &final_status)
-            .ok()
+        evict_stale_workers(db, config, stale_timeout as i64, None).ok()
     } else {
         None
     };
 
     // Get workers with filters
-    let workers =
-        db.list_workers_filtered(tags.as_ref(), file.as_deref(), task.as_deref(), depth)?;
+    let mut workers = db.list_workers_filtered(
+        tags.as_ref(),
+        file.as_deref(),
+        task.as_deref(),
+        depth,
+        group.as_deref(),
+    )?;
+    if let Some(ref p) = project {
+        workers.retain(|w| w.project == *p);
+    }
 
     // Get current time for heartbeat age calculation
     let now = std::time::SystemTime::now()
@@ -394,16 +557,35 @@ pub fn list_agents(
         .unwrap_or(0);
 
     match format {
+        OutputFormat::Compact => {
+            let rows: Vec<Vec<String>> = workers
+                .iter()
+                .map(|w| {
+                    vec![
+                        w.id.clone(),
+                        w.claim_count.to_string(),
+                        (now - w.last_heartbeat).to_string(),
+                        w.project.clone(),
+                        w.current_thought.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            Ok(ToolResult::Raw(format_compact_rows(
+                &["id", "claims", "hb_age_ms", "project", "thought"],
+                &rows,
+            )))
+        }
         OutputFormat::Markdown => {
             let mut output = String::new();
             if let Some(ref summary) = cleanup_summary
                 && summary.workers_evicted > 0
             {
                 output.push_str(&format!(
-                    "**Evicted {} stale worker(s)**: {} (released {} task(s), {} file(s))\n\n",
+                    "**Evicted {} stale worker(s)**: {} (released {} task(s), reassigned {}, {} file(s))\n\n",
                     summary.workers_evicted,
                     summary.evicted_worker_ids.join(", "),
                     summary.tasks_released,
+                    summary.tasks_reassigned,
                     summary.files_released
                 ));
             }
@@ -421,7 +603,8 @@ pub fn list_agents(
                     "registered_at": w.registered_at,
                     "last_heartbeat": w.last_heartbeat,
                     "heartbeat_age_ms": now - w.last_heartbeat,
-                    "workflow": w.workflow
+                    "workflow": w.workflow,
+                    "groups": w.groups
                 })).collect::<Vec<_>>()
             });
 
@@ -432,7 +615,9 @@ pub fn list_agents(
                     "workers_evicted": summary.workers_evicted,
                     "evicted_worker_ids": summary.evicted_worker_ids,
                     "tasks_released": summary.tasks_released,
-                    "files_released": summary.files_released
+                    "tasks_reassigned": summary.tasks_reassigned,
+                    "files_released": summary.files_released,
+                    "evictions": summary.evictions
                 });
             }
 
@@ -441,38 +626,154 @@ pub fn list_agents(
     }
 }
 
-pub fn cleanup_stale(db: &Database, states_config: &StatesConfig, args: Value) -> Result<Value> {
+pub fn cleanup_stale(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
+    let states_config = &config.states;
+
     // Default timeout: 5 minutes
     let timeout = get_i32(&args, "timeout").unwrap_or(300) as i64;
 
-    // Get final_status from args or fall back to config
-    let final_status =
-        get_string(&args, "final_status").unwrap_or_else(|| states_config.disconnect_state.clone());
+    // An explicit final_status overrides the configured stale-claim policies
+    let final_status = get_string(&args, "final_status");
 
     // Validate final_status is untimed
-    if states_config.is_timed_state(&final_status) {
+    if let Some(ref status) = final_status
+        && (!states_config.is_valid_state(status) || states_config.is_timed_state(status))
+    {
         return Err(ToolError::invalid_value(
             "final_status",
             &format!(
                 "must be an untimed status, got '{}'. Valid statuses: {:?}",
-                final_status,
+                status,
                 states_config.untimed_state_names()
             ),
         )
         .into());
     }
 
-    let summary = db.cleanup_stale_workers(timeout, &final_status)?;
+    let summary = evict_stale_workers(db, config, timeout, final_status.as_deref())?;
 
     Ok(json!({
         "workers_evicted": summary.workers_evicted,
         "evicted_worker_ids": summary.evicted_worker_ids,
         "tasks_released": summary.tasks_released,
+        "tasks_reassigned": summary.tasks_reassigned,
         "files_released": summary.files_released,
-        "final_status": summary.final_status
+        "final_status": summary.final_status,
+        "evictions": summary.evictions
     }))
 }
 
+/// Evict workers without a heartbeat for `timeout_seconds`, applying the stale-claim
+/// policy of each worker's workflow (`stale_claims` in config.yaml).
+///
+/// `final_status` overrides the policies: every claim is requeued with that status.
+/// A worker that fails to evict is skipped and left for the next sweep.
+pub fn evict_stale_workers(
+    db: &Database,
+    config: &AppConfig,
+    timeout_seconds: i64,
+    final_status: Option<&str>,
+) -> Result<CleanupSummary> {
+    let stale_workers = db.get_stale_workers(timeout_seconds)?;
+    let override_policy = final_status.map(|status| StalePolicy {
+        action: StaleAction::Requeue,
+        status: Some(status.to_string()),
+        ..Default::default()
+    });
+
+    let stale_ids: Vec<&str> = stale_workers.iter().map(|w| w.id.as_str()).collect();
+    let live_workers: Vec<Worker> = if stale_workers.is_empty() {
+        Vec::new()
+    } else {
+        db.list_workers()?
+            .into_iter()
+            .filter(|w| !stale_ids.contains(&w.id.as_str()))
+            .collect()
+    };
+
+    let mut summary = CleanupSummary {
+        workers_evicted: 0,
+        tasks_released: 0,
+        files_released: 0,
+        final_status: final_status
+            .unwrap_or(config.states.disconnect_state.as_str())
+            .to_string(),
+        evicted_worker_ids: Vec::new(),
+        tasks_reassigned: 0,
+        evictions: Vec::new(),
+    };
+
+    for worker in &stale_workers {
+        let policy = override_policy
+            .as_ref()
+            .unwrap_or_else(|| config.stale_claims.policy_for(worker.workflow.as_deref()));
+
+        // Live workers holding the policy's role, in the stale worker's workflow
+        let reassign_to: Vec<String> = match (&policy.action, &policy.role) {
+            (StaleAction::Reassign, Some(role)) => {
+                let workflow = resolve_base_workflow(worker, config);
+                let role_tags = workflow
+                    .roles
+                    .get(role)
+                    .or_else(|| config.workflows.roles.get(role))
+                    .map(|r| r.tags.clone())
+                    .unwrap_or_default();
+                live_workers
+                    .iter()
+                    .filter(|w| role_tags.iter().any(|t| w.tags.contains(t)))
+                    .map(|w| w.id.clone())
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let Ok(mut eviction) =
+            db.evict_worker_with_policy(&worker.id, policy, &reassign_to, &config.states)
+        else {
+            continue;
+        };
+
+        if policy.action == StaleAction::Escalate
+            && !eviction.tasks_released.is_empty()
+            && let Some(ref coordinator) = policy.coordinator_task
+        {
+            let note = format!(
+                "Worker `{}` stopped sending heartbeats and was evicted.\n\nReleased as `{}`:\n{}",
+                worker.id,
+                eviction.status,
+                eviction
+                    .tasks_released
+                    .iter()
+                    .map(|id| format!("- {}", id))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+            if db
+                .add_attachment(
+                    coordinator,
+                    "escalation".to_string(),
+                    format!("Stale worker {}", worker.id),
+                    note,
+                    Some("text/markdown".to_string()),
+                    None,
+                )
+                .is_ok()
+            {
+                eviction.escalated_to = Some(coordinator.clone());
+            }
+        }
+
+        summary.workers_evicted += 1;
+        summary.tasks_released += eviction.tasks_released.len() as i32;
+        summary.tasks_reassigned += eviction.reassigned.len() as i32;
+        summary.files_released += eviction.files_released;
+        summary.evicted_worker_ids.push(worker.id.clone());
+        summary.evictions.push(eviction);
+    }
+
+    Ok(summary)
+}
+
 pub fn add_overlay(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
     let worker_id =
         get_string(&args, "worker_id").ok_or_else(|| ToolError::missing_field("worker_id"))?;
@@ -611,6 +912,50 @@ pub fn remove_overlay(db: &Database, config: &AppConfig, args: Value) -> Result<
     Ok(response)
 }
 
+/// Add workers to a group, or remove them with `leave`.
+pub fn register_group(db: &Database, args: Value) -> Result<Value> {
+    let worker_id =
+        get_string(&args, "worker_id").ok_or_else(|| ToolError::missing_field("worker_id"))?;
+    let group = get_string(&args, "group").ok_or_else(|| ToolError::missing_field("group"))?;
+    let leave = get_bool(&args, "leave").unwrap_or(false);
+
+    if group.trim().is_empty() {
+        return Err(ToolError::invalid_value("group", "Group name cannot be empty").into());
+    }
+
+    let mut worker_ids = vec![worker_id];
+    for member in get_string_array(&args, "members").unwrap_or_default() {
+        if !worker_ids.contains(&member) {
+            worker_ids.push(member);
+        }
+    }
+    for id in &worker_ids {
+        if db.get_worker(id)?.is_none() {
+            return Err(ToolError::agent_not_found(id).into());
+        }
+    }
+
+    let groups = std::slice::from_ref(&group);
+    let mut changed = Vec::new();
+    for id in &worker_ids {
+        let done = if leave {
+            db.leave_groups(id, groups)?
+        } else {
+            db.join_groups(id, groups)?
+        };
+        if !done.is_empty() {
+            changed.push(id.clone());
+        }
+    }
+
+    let mut response = json!({
+        "group": &group,
+        "members": db.get_group_members(&group)?
+    });
+    response[if leave { "left" } else { "joined" }] = json!(changed);
+    Ok(response)
+}
+
 /// Resolve the base workflow for a worker (before overlays).
 fn resolve_base_workflow(
     worker: &crate::types::Worker,
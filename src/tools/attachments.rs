@@ -1,14 +1,21 @@
 //! Attachment management tools.
 
-use super::{get_bool, get_string, get_string_or_array, make_tool_with_prompts};
+use super::{get_bool, get_i32, get_i64, get_string, get_string_or_array, make_tool_with_prompts};
+use crate::blob_store::{BlobStore, open_blob_store, sha256_hex};
 use crate::config::{AttachmentsConfig, Prompts, UnknownKeyBehavior};
 use crate::db::Database;
 use crate::error::{ErrorCode, ToolError};
 use crate::format::{OutputFormat, format_attachments_markdown, markdown_to_json};
+use crate::types::{Attachment, AttachmentMeta};
 use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use rmcp::model::Tool;
 use serde_json::{Value, json};
-use std::path::Path;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
 
 pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
     vec![
@@ -18,7 +25,10 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
              Attachments are indexed by (task_id, type, sequence). Each type auto-increments its own sequence.\n\n\
              For inline content: provide 'content' directly.\n\
              For file reference: provide 'file' path (existing file, will be referenced).\n\
-             For media storage: provide 'content' + 'store_as_file'=true (saves to .task-graph/media/).",
+             For media storage: provide 'content' + 'store_as_file'=true (saves to .task-graph/media/).\n\
+             For binary content (screenshots, build outputs): provide base64 'content' with 'encoding'='base64', \
+             or 'file' + 'store_as_file'=true to copy a file. Binary content is stored once per SHA-256 digest \
+             in .task-graph/media/blobs/ and only metadata is kept in the database.",
             json!({
                 "agent": {
                     "type": "string",
@@ -37,12 +47,17 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
                 },
                 "name": {
                     "type": "string",
-                    "description": "Optional label/name for the attachment (arbitrary string, not used for indexing)"
+                    "description": "Optional label/name for the attachment. Required with mode 'version', where it identifies the versioned attachment."
                 },
                 "content": {
                     "type": "string",
                     "description": "Content (text or base64). Optional if 'file' is provided."
                 },
+                "encoding": {
+                    "type": "string",
+                    "enum": ["utf8", "base64"],
+                    "description": "Encoding of 'content': 'utf8' (default) stores it as given, 'base64' decodes it and stores the bytes as a content-addressed blob"
+                },
                 "mime": {
                     "type": "string",
                     "description": "MIME type (default: text/plain)"
@@ -53,21 +68,104 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
                 },
                 "store_as_file": {
                     "type": "boolean",
-                    "description": "If true, store content in .task-graph/media/ instead of database"
+                    "description": "If true, store content in .task-graph/media/ instead of database. With 'file', copy the file into the blob store instead of referencing it."
                 },
                 "mode": {
                     "type": "string",
-                    "enum": ["append", "replace"],
-                    "description": "How to handle existing attachments of the same type: 'append' (default) adds new, 'replace' deletes all existing of this type first"
+                    "enum": ["append", "replace", "version"],
+                    "description": "How to handle existing attachments of the same type: 'append' (default) adds new, 'replace' deletes all existing of this type first, 'version' adds a new version of the attachment with the same name (older versions beyond the configured max_versions are deleted)"
                 }
             }),
             vec!["task", "type"],
             prompts,
         ),
+        make_tool_with_prompts(
+            "attach_begin",
+            "Start a chunked upload for a large attachment. Send the content with attach_chunk \
+             (base64, in order from index 0) and finish with attach_commit, which verifies the size \
+             and SHA-256 digest and stores the data as a content-addressed blob. Uploads idle for an \
+             hour are discarded.",
+            json!({
+                "agent": {
+                    "type": "string",
+                    "description": "Agent ID"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "Task ID"
+                },
+                "type": {
+                    "type": "string",
+                    "description": "Attachment type/category (e.g., 'screenshot', 'build')"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Optional label/name for the attachment"
+                },
+                "mime": {
+                    "type": "string",
+                    "description": "MIME type (default: from attachment type config)"
+                },
+                "mode": {
+                    "type": "string",
+                    "enum": ["append", "replace", "version"],
+                    "description": "Applied on commit: 'append' adds, 'replace' deletes existing attachments of this type first, 'version' adds a new version of the attachment with the same name"
+                },
+                "size": {
+                    "type": "integer",
+                    "description": "Total size in bytes; chunks beyond it are rejected and commit verifies it"
+                },
+                "sha256": {
+                    "type": "string",
+                    "description": "Expected SHA-256 (hex) of the full content, verified on commit"
+                }
+            }),
+            vec!["task", "type"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "attach_chunk",
+            "Append one base64-encoded chunk to an upload started with attach_begin. Chunks must be sent in order; \
+             'index' must equal the upload's next chunk index (returned by each call).",
+            json!({
+                "upload_id": {
+                    "type": "string",
+                    "description": "Upload ID from attach_begin"
+                },
+                "index": {
+                    "type": "integer",
+                    "description": "Zero-based chunk index"
+                },
+                "data": {
+                    "type": "string",
+                    "description": "Base64-encoded chunk content"
+                }
+            }),
+            vec!["upload_id", "index", "data"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "attach_commit",
+            "Finish a chunked upload: verify size and SHA-256 (from attach_begin or given here) and attach the \
+             assembled content to the task. On mismatch the upload is discarded.",
+            json!({
+                "upload_id": {
+                    "type": "string",
+                    "description": "Upload ID from attach_begin"
+                },
+                "sha256": {
+                    "type": "string",
+                    "description": "Expected SHA-256 (hex) of the full content, if not given to attach_begin"
+                }
+            }),
+            vec!["upload_id"],
+            prompts,
+        ),
         make_tool_with_prompts(
             "attachments",
-            "Get attachments for a task. Returns metadata only.\n\n\
-             To retrieve attachment content, use the `get_attachment` API (not yet available via MCP).",
+            "Get attachments for a task. Returns metadata only; blob attachments include content_hash and size_bytes, and 'missing' if the blob file is gone.\n\n\
+             Only the latest version of each named attachment is listed unless include_versions is true. \
+             To retrieve attachment content, use get_attachment.",
             json!({
                 "task": {
                     "type": "string",
@@ -80,14 +178,82 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
                 "mime": {
                     "type": "string",
                     "description": "Filter by MIME type prefix (e.g., 'image/' matches image/png, image/jpeg)"
+                },
+                "include_versions": {
+                    "type": "boolean",
+                    "description": "If true, list every stored version of named attachments (default: false, latest version only)"
                 }
             }),
             vec!["task"],
             prompts,
         ),
+        make_tool_with_prompts(
+            "get_attachment",
+            "Get one attachment with its content. Select it by 'sequence', or by 'name' (latest version, \
+             or 'version'). Text content is returned as-is; binary file or blob content is returned base64-encoded \
+             with 'encoding'='base64'.",
+            json!({
+                "task": {
+                    "type": "string",
+                    "description": "Task ID"
+                },
+                "type": {
+                    "type": "string",
+                    "description": "Attachment type"
+                },
+                "sequence": {
+                    "type": "integer",
+                    "description": "Attachment sequence within the type"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Attachment name (alternative to sequence)"
+                },
+                "version": {
+                    "type": "integer",
+                    "description": "Version of the named attachment (default: latest)"
+                }
+            }),
+            vec!["task", "type"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "prune_attachments",
+            "Delete attachments outside a retention policy. By default applies the keep_last / max_age_seconds \
+             policies configured in attachments.definitions; pass 'type' with 'keep_last' and/or 'max_age_seconds' \
+             to prune one type with an explicit policy. Blobs are left in the store until gc_blobs runs.",
+            json!({
+                "agent": {
+                    "type": "string",
+                    "description": "Agent ID"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "Only prune this task's attachments (default: all tasks)"
+                },
+                "type": {
+                    "type": "string",
+                    "description": "Only prune this attachment type (required with keep_last or max_age_seconds)"
+                },
+                "keep_last": {
+                    "type": "integer",
+                    "description": "Keep only the newest N attachments of the type per task"
+                },
+                "max_age_seconds": {
+                    "type": "integer",
+                    "description": "Delete attachments of the type older than this many seconds"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "If true, list what would be deleted without deleting (default: false)"
+                }
+            }),
+            vec![],
+            prompts,
+        ),
         make_tool_with_prompts(
             "detach",
-            "Delete attachments by task and type. Deletes all attachments of the specified type.",
+            "Delete attachments by task and type. Deletes all attachments of the specified type. Content-addressed blobs stay in the store until gc_blobs runs.",
             json!({
                 "agent": {
                     "type": "string",
@@ -109,6 +275,24 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
             vec!["agent", "task", "type"],
             prompts,
         ),
+        make_tool_with_prompts(
+            "gc_blobs",
+            "Delete content-addressed blobs that no attachment references any more. Detaching, replacing, \
+             pruning and purging leave blobs in the store; run this as maintenance to reclaim the space. \
+             Only run it against a blob store this server does not share with another.",
+            json!({
+                "agent": {
+                    "type": "string",
+                    "description": "Agent ID"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "If true, list the blobs that would be deleted without deleting (default: false)"
+                }
+            }),
+            vec![],
+            prompts,
+        ),
     ]
 }
 
@@ -225,8 +409,243 @@ fn generate_media_filename(task_id: &str, attachment_type: &str, mime_type: &str
     format!("{}_{}_{}.{}", task_id, safe_type, timestamp_str, ext)
 }
 
+/// Store bytes in the blob store. Returns (location, hex digest, size).
+fn store_blob(store: &dyn BlobStore, bytes: &[u8]) -> Result<(String, String, i64)> {
+    let hash = sha256_hex(bytes);
+    store.put(&hash, bytes)?;
+    Ok((store.location(&hash), hash, bytes.len() as i64))
+}
+
+/// SHA-256 digest (hex) and size of a file, read in a streaming fashion.
+fn hash_file(path: &Path) -> Result<(String, i64)> {
+    let mut hasher = Sha256::new();
+    let mut file = std::fs::File::open(path)?;
+    let size = std::io::copy(&mut file, &mut hasher)?;
+    Ok((
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+        size as i64,
+    ))
+}
+
+/// Subdirectory of the media dir holding partial chunked uploads.
+const UPLOAD_DIR: &str = "uploads";
+
+/// Uploads with no chunk received for this long are discarded.
+const UPLOAD_IDLE_TIMEOUT_MS: i64 = 60 * 60 * 1000;
+
+/// Path of the partial file for a chunked upload.
+fn upload_part_path(media_dir: &Path, upload_id: &str) -> PathBuf {
+    media_dir
+        .join(UPLOAD_DIR)
+        .join(format!("{}.part", upload_id))
+}
+
+/// Discard idle uploads and partial files whose upload no longer exists
+/// (e.g. because the task was deleted).
+pub(crate) fn reap_uploads(db: &Database, media_dir: &Path) -> Result<()> {
+    for id in db.reap_stale_attachment_uploads(crate::db::now_ms() - UPLOAD_IDLE_TIMEOUT_MS)? {
+        let _ = std::fs::remove_file(upload_part_path(media_dir, &id));
+    }
+    if let Ok(entries) = std::fs::read_dir(media_dir.join(UPLOAD_DIR)) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if let Some(id) = name.strip_suffix(".part")
+                && db.get_attachment_upload(id)?.is_none()
+            {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Give cloned tasks their own copies of attachment files in the media dir,
+/// so deleting a clone's attachment never removes the original's file. Blobs
+/// and files outside the media dir stay shared. Returns the number copied.
+pub fn copy_cloned_media(db: &Database, media_dir: &Path, task_ids: &[String]) -> Result<usize> {
+    let mut copied = 0;
+    for task_id in task_ids {
+        for a in db.get_attachments(task_id)? {
+            let Some(fp) = a.file_path.filter(|_| a.content_hash.is_none()) else {
+                continue;
+            };
+            if !is_in_media_dir(&fp, media_dir) || !Path::new(&fp).exists() {
+                continue;
+            }
+            let filename = generate_media_filename(task_id, &a.attachment_type, &a.mime_type);
+            let dest = unused_media_path(media_dir, &filename);
+            std::fs::copy(&fp, &dest)?;
+            db.set_attachment_file_path(
+                task_id,
+                &a.attachment_type,
+                a.sequence,
+                &dest.to_string_lossy(),
+            )?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// `media_dir/filename`, numbered (`name-1.ext`, ...) if that file already exists.
+fn unused_media_path(media_dir: &Path, filename: &str) -> PathBuf {
+    let path = media_dir.join(filename);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, ext) = filename.rsplit_once('.').unwrap_or((filename, ""));
+    (1..)
+        .map(|n| match ext {
+            "" => media_dir.join(format!("{}-{}", stem, n)),
+            _ => media_dir.join(format!("{}-{}.{}", stem, n, ext)),
+        })
+        .find(|p| !p.exists())
+        .expect("unbounded range")
+}
+
+/// Look up an upload or fail with NOT_FOUND.
+fn require_upload(db: &Database, upload_id: &str) -> Result<crate::types::AttachmentUpload> {
+    db.get_attachment_upload(upload_id)?.ok_or_else(|| {
+        ToolError::new(
+            ErrorCode::AttachmentNotFound,
+            format!(
+                "Upload not found (expired or already committed): {}",
+                upload_id
+            ),
+        )
+        .into()
+    })
+}
+
+/// Blobs are stored before the attachment rows that reference them. Writers
+/// hold this lock for reading across both steps and `collect_blobs` holds it
+/// for writing, so a collection never deletes a blob whose row is still to come.
+static BLOB_LOCK: RwLock<()> = RwLock::new(());
+
+/// Delete blobs that no attachment references any more (or, with `dry_run`,
+/// only find them). Returns their digests.
+///
+/// Every blob in the store that this database does not reference is
+/// unreferenced, so the store must not be shared with another server.
+pub fn collect_blobs(db: &Database, store: &dyn BlobStore, dry_run: bool) -> Result<Vec<String>> {
+    let _gc = BLOB_LOCK.write().unwrap_or_else(PoisonError::into_inner);
+    let referenced = db.get_referenced_blob_hashes()?;
+    let mut unreferenced = Vec::new();
+    for hash in store.list()? {
+        if !referenced.contains(&hash) {
+            if !dry_run {
+                store.delete(&hash)?;
+            }
+            unreferenced.push(hash);
+        }
+    }
+    Ok(unreferenced)
+}
+
+/// Delete versions of a named attachment beyond the configured retention limit,
+/// removing their media files. Blobs are left to `gc_blobs`.
+fn prune_versions(
+    db: &Database,
+    media_dir: &Path,
+    attachments_config: &AttachmentsConfig,
+    task_id: &str,
+    attachment_type: &str,
+    name: &str,
+) -> Result<()> {
+    if let Some(keep) = attachments_config.get_max_versions(attachment_type) {
+        // The version just added is always kept
+        let keep = keep.max(1);
+        for old_fp in db.prune_attachment_versions(task_id, attachment_type, name, keep)? {
+            if is_in_media_dir(&old_fp, media_dir) {
+                let _ = std::fs::remove_file(&old_fp);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reject attaching `incoming` bytes to each of `task_ids` if that would push a task
+/// or the project over its configured quota. In replace mode the type's existing
+/// attachments on those tasks are about to be deleted and do not count.
+pub(crate) fn check_quotas(
+    db: &Database,
+    attachments_config: &AttachmentsConfig,
+    task_ids: &[String],
+    attachment_type: &str,
+    replace: bool,
+    incoming: i64,
+) -> Result<()> {
+    if attachments_config.max_task_bytes.is_none() && attachments_config.max_project_bytes.is_none()
+    {
+        return Ok(());
+    }
+
+    let mut replaced_total = 0;
+    for task_id in task_ids {
+        let replaced = if replace {
+            db.get_attachment_usage(Some(task_id), Some(attachment_type))?
+        } else {
+            0
+        };
+        replaced_total += replaced;
+        if let Some(limit) = attachments_config.max_task_bytes {
+            let used = db.get_attachment_usage(Some(task_id), None)? - replaced;
+            if used + incoming > limit {
+                return Err(ToolError::quota_exceeded(
+                    &format!("task {}", task_id),
+                    used,
+                    incoming,
+                    limit,
+                )
+                .into());
+            }
+        }
+    }
+    if let Some(limit) = attachments_config.max_project_bytes {
+        let used = db.get_attachment_usage(None, None)? - replaced_total;
+        let incoming = incoming * task_ids.len() as i64;
+        if used + incoming > limit {
+            return Err(ToolError::quota_exceeded("the project", used, incoming, limit).into());
+        }
+    }
+    Ok(())
+}
+
+/// Delete attachments of `attachment_type` outside a retention policy and remove
+/// their media files. Blobs are left to `gc_blobs`. Returns the expired attachments
+/// (not deleted when `dry_run`).
+fn apply_retention(
+    db: &Database,
+    media_dir: &Path,
+    task_id: Option<&str>,
+    attachment_type: &str,
+    keep_last: Option<usize>,
+    max_age_seconds: Option<i64>,
+    dry_run: bool,
+) -> Result<Vec<AttachmentMeta>> {
+    let created_before = max_age_seconds.map(|age| crate::db::now_ms() - age * 1000);
+    let expired =
+        db.get_expired_attachments(task_id, attachment_type, keep_last, created_before)?;
+    if !dry_run && !expired.is_empty() {
+        db.delete_attachments(&expired)?;
+        for a in &expired {
+            if let Some(ref fp) = a.file_path
+                && a.content_hash.is_none()
+                && is_in_media_dir(fp, media_dir)
+            {
+                let _ = std::fs::remove_file(fp);
+            }
+        }
+    }
+    Ok(expired)
+}
+
 /// Check if a file path is within the media directory.
-fn is_in_media_dir(file_path: &str, media_dir: &Path) -> bool {
+pub(crate) fn is_in_media_dir(file_path: &str, media_dir: &Path) -> bool {
     let file_path = Path::new(file_path);
 
     // Try to canonicalize both paths for comparison
@@ -238,35 +657,15 @@ fn is_in_media_dir(file_path: &str, media_dir: &Path) -> bool {
     }
 }
 
-pub fn attach(
-    db: &Database,
-    media_dir: &Path,
+/// Resolve MIME type and mode for an attachment type from args and config defaults,
+/// applying the `unknown_key` policy. Returns (mime_type, mode, warning).
+fn resolve_attachment_options(
     attachments_config: &AttachmentsConfig,
-    args: Value,
-) -> Result<Value> {
-    // Agent parameter is optional - for tracking/audit purposes
-    let _agent_id = get_string(&args, "agent");
-
-    let task_ids =
-        get_string_or_array(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
-
-    if task_ids.is_empty() {
-        return Err(ToolError::new(
-            ErrorCode::InvalidFieldValue,
-            "At least one task ID must be provided",
-        )
-        .into());
-    }
-
-    let attachment_type =
-        get_string(&args, "type").ok_or_else(|| ToolError::missing_field("type"))?;
-    let name = get_string(&args, "name").unwrap_or_default();
-    let content = get_string(&args, "content");
-    let file_path = get_string(&args, "file");
-    let store_as_file = get_bool(&args, "store_as_file").unwrap_or(false);
-
+    attachment_type: &str,
+    args: &Value,
+) -> Result<(String, String, Option<String>)> {
     // Check if this is a known key and handle unknown_key behavior
-    let is_known = attachments_config.is_known_key(&attachment_type);
+    let is_known = attachments_config.is_known_key(attachment_type);
     let warning: Option<String> = if !is_known {
         match attachments_config.unknown_key {
             UnknownKeyBehavior::Reject => {
@@ -285,24 +684,69 @@ pub fn attach(
     };
 
     // Use config defaults for mime/mode, but allow explicit overrides from args
-    let mime_type = get_string(&args, "mime").unwrap_or_else(|| {
+    let mime_type = get_string(args, "mime").unwrap_or_else(|| {
         attachments_config
-            .get_mime_default(&attachment_type)
+            .get_mime_default(attachment_type)
             .to_string()
     });
     validate_mime_type(&mime_type)?;
 
-    let mode = get_string(&args, "mode").unwrap_or_else(|| {
+    let mode = get_string(args, "mode").unwrap_or_else(|| {
         attachments_config
-            .get_mode_default(&attachment_type)
+            .get_mode_default(attachment_type)
             .to_string()
     });
 
     // Validate mode
-    if mode != "append" && mode != "replace" {
+    if mode != "append" && mode != "replace" && mode != "version" {
+        return Err(ToolError::new(
+            ErrorCode::InvalidFieldValue,
+            "mode must be 'append', 'replace' or 'version'",
+        )
+        .into());
+    }
+    if mode == "version" && get_string(args, "name").unwrap_or_default().is_empty() {
+        return Err(ToolError::invalid_value("name", "is required with mode 'version'").into());
+    }
+
+    Ok((mime_type, mode, warning))
+}
+
+pub fn attach(
+    db: &Database,
+    media_dir: &Path,
+    attachments_config: &AttachmentsConfig,
+    args: Value,
+) -> Result<Value> {
+    // Agent parameter is optional - for tracking/audit purposes
+    let _agent_id = get_string(&args, "agent");
+
+    let task_ids =
+        get_string_or_array(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+
+    if task_ids.is_empty() {
         return Err(ToolError::new(
             ErrorCode::InvalidFieldValue,
-            "mode must be 'append' or 'replace'",
+            "At least one task ID must be provided",
+        )
+        .into());
+    }
+
+    let attachment_type =
+        get_string(&args, "type").ok_or_else(|| ToolError::missing_field("type"))?;
+    let name = get_string(&args, "name").unwrap_or_default();
+    let content = get_string(&args, "content");
+    let file_path = get_string(&args, "file");
+    let store_as_file = get_bool(&args, "store_as_file").unwrap_or(false);
+    let encoding = get_string(&args, "encoding").unwrap_or_else(|| "utf8".to_string());
+
+    let (mime_type, mode, warning) =
+        resolve_attachment_options(attachments_config, &attachment_type, &args)?;
+
+    if encoding != "utf8" && encoding != "base64" {
+        return Err(ToolError::new(
+            ErrorCode::InvalidFieldValue,
+            "encoding must be 'utf8' or 'base64'",
         )
         .into());
     }
@@ -316,6 +760,51 @@ pub fn attach(
         .into());
     }
 
+    // Binary content (base64 or a copied file) goes to the content-addressed blob store
+    let blob_bytes: Option<Vec<u8>> = if let Some(ref fp) = file_path
+        && store_as_file
+    {
+        Some(std::fs::read(fp).map_err(|_| {
+            ToolError::new(ErrorCode::FileNotFound, format!("File not found: {}", fp))
+        })?)
+    } else if encoding == "base64" && file_path.is_none() {
+        Some(
+            BASE64
+                .decode(content.as_deref().unwrap_or_default().trim())
+                .map_err(|e| {
+                    ToolError::new(
+                        ErrorCode::InvalidFieldValue,
+                        format!("content is not valid base64: {}", e),
+                    )
+                })?,
+        )
+    } else {
+        None
+    };
+
+    // Quotas count blobs and inline content, so check before storing anything
+    let incoming = match (&blob_bytes, &file_path) {
+        (Some(bytes), _) => bytes.len() as i64,
+        (None, None) if !store_as_file => content.as_deref().map_or(0, |c| c.len() as i64),
+        _ => 0,
+    };
+    check_quotas(
+        db,
+        attachments_config,
+        &task_ids,
+        &attachment_type,
+        mode == "replace",
+        incoming,
+    )?;
+
+    let store = open_blob_store(&attachments_config.storage, media_dir)?;
+    let storing = BLOB_LOCK.read().unwrap_or_else(PoisonError::into_inner);
+    let blob: Option<(String, String, i64)> = blob_bytes
+        .map(|bytes| store_blob(store.as_ref(), &bytes))
+        .transpose()?;
+    let retention = attachments_config.get_retention(&attachment_type);
+    let mut expired = 0;
+
     // Handle different attachment modes - prepare content/file once for all tasks
     let (base_content, base_file_path): (String, Option<String>) = if let Some(ref fp) = file_path {
         // File reference mode: verify file exists
@@ -326,6 +815,9 @@ pub fn attach(
             );
         }
         (String::new(), Some(fp.clone()))
+    } else if blob.is_some() {
+        // Blob content is shared by all tasks
+        (String::new(), None)
     } else if store_as_file {
         // For store_as_file with multiple tasks, we'll create per-task files
         (content.clone().unwrap(), None)
@@ -367,31 +859,96 @@ pub fn attach(
                 (base_content.clone(), base_file_path.clone())
             };
 
-        let sequence = db.add_attachment(
-            task_id,
-            attachment_type.clone(),
-            name.clone(),
-            final_content,
-            Some(mime_type.clone()),
-            final_file_path.clone(),
-        )?;
+        let (sequence, version) = if mode == "version" {
+            let versioned = match blob {
+                Some((ref path, ref hash, size)) => db.add_attachment_version(
+                    task_id,
+                    attachment_type.clone(),
+                    name.clone(),
+                    String::new(),
+                    Some(mime_type.clone()),
+                    Some(path.clone()),
+                    Some((hash.clone(), size)),
+                )?,
+                None => db.add_attachment_version(
+                    task_id,
+                    attachment_type.clone(),
+                    name.clone(),
+                    final_content,
+                    Some(mime_type.clone()),
+                    final_file_path.clone(),
+                    None,
+                )?,
+            };
+            prune_versions(
+                db,
+                media_dir,
+                attachments_config,
+                task_id,
+                &attachment_type,
+                &name,
+            )?;
+            versioned
+        } else {
+            let sequence = match blob {
+                Some((ref path, ref hash, size)) => db.add_blob_attachment(
+                    task_id,
+                    attachment_type.clone(),
+                    name.clone(),
+                    Some(mime_type.clone()),
+                    path.clone(),
+                    hash.clone(),
+                    size,
+                )?,
+                None => db.add_attachment(
+                    task_id,
+                    attachment_type.clone(),
+                    name.clone(),
+                    final_content,
+                    Some(mime_type.clone()),
+                    final_file_path.clone(),
+                )?,
+            };
+            (sequence, 1)
+        };
 
         let mut result = json!({
             "task_id": task_id,
             "type": &attachment_type,
             "sequence": sequence
         });
+        if mode == "version" {
+            result["version"] = json!(version);
+        }
+
+        if let Some((keep_last, max_age_seconds)) = retention {
+            expired += apply_retention(
+                db,
+                media_dir,
+                Some(task_id),
+                &attachment_type,
+                keep_last,
+                max_age_seconds,
+                false,
+            )?
+            .len();
+        }
 
         if !name.is_empty() {
             result["name"] = json!(&name);
         }
 
-        if let Some(fp) = final_file_path {
+        if let Some((ref path, ref hash, size)) = blob {
+            result["file_path"] = json!(path);
+            result["content_hash"] = json!(hash);
+            result["size_bytes"] = json!(size);
+        } else if let Some(fp) = final_file_path {
             result["file_path"] = json!(fp);
         }
 
         results.push(result);
     }
+    drop(storing);
 
     // Return single result for single task, array for bulk
     let mut response = if results.len() == 1 {
@@ -399,6 +956,9 @@ pub fn attach(
     } else {
         json!({ "attachments": results })
     };
+    if expired > 0 {
+        response["pruned"] = json!(expired);
+    }
 
     // Add warning if unknown key behavior is "warn"
     if let Some(warn_msg) = warning {
@@ -408,35 +968,302 @@ pub fn attach(
     Ok(response)
 }
 
+pub fn attach_begin(
+    db: &Database,
+    media_dir: &Path,
+    attachments_config: &AttachmentsConfig,
+    args: Value,
+) -> Result<Value> {
+    // Agent parameter is optional - for tracking/audit purposes
+    let _agent_id = get_string(&args, "agent");
+
+    let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    let attachment_type =
+        get_string(&args, "type").ok_or_else(|| ToolError::missing_field("type"))?;
+    let name = get_string(&args, "name").unwrap_or_default();
+    let size = get_i64(&args, "size");
+    let sha256 = get_string(&args, "sha256").map(|h| h.to_ascii_lowercase());
+
+    let (mime_type, mode, warning) =
+        resolve_attachment_options(attachments_config, &attachment_type, &args)?;
+
+    if size.is_some_and(|s| s < 0) {
+        return Err(ToolError::invalid_value("size", "must not be negative").into());
+    }
+    if let Some(ref h) = sha256
+        && (h.len() != 64 || !h.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        return Err(ToolError::invalid_value("sha256", "must be 64 hex characters").into());
+    }
+
+    if db.get_task(&task_id)?.is_none() {
+        return Err(ToolError::task_not_found(&task_id).into());
+    }
+    if let Some(size) = size {
+        check_quotas(
+            db,
+            attachments_config,
+            std::slice::from_ref(&task_id),
+            &attachment_type,
+            mode == "replace",
+            size,
+        )?;
+    }
+
+    reap_uploads(db, media_dir)?;
+    let upload = db.create_attachment_upload(
+        &task_id,
+        &attachment_type,
+        &name,
+        &mime_type,
+        &mode,
+        size,
+        sha256.as_deref(),
+    )?;
+
+    let part = upload_part_path(media_dir, &upload.id);
+    if let Some(dir) = part.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::File::create(&part)?;
+
+    let mut response = json!({
+        "upload_id": upload.id,
+        "task_id": task_id,
+        "type": attachment_type,
+        "next_chunk": 0
+    });
+    if let Some(warn_msg) = warning {
+        response["warning"] = json!(warn_msg);
+    }
+    Ok(response)
+}
+
+pub fn attach_chunk(db: &Database, media_dir: &Path, args: Value) -> Result<Value> {
+    let upload_id =
+        get_string(&args, "upload_id").ok_or_else(|| ToolError::missing_field("upload_id"))?;
+    let index = get_i64(&args, "index").ok_or_else(|| ToolError::missing_field("index"))?;
+    let data = get_string(&args, "data").ok_or_else(|| ToolError::missing_field("data"))?;
+
+    let bytes = BASE64.decode(data.trim()).map_err(|e| {
+        ToolError::new(
+            ErrorCode::InvalidFieldValue,
+            format!("data is not valid base64: {}", e),
+        )
+    })?;
+
+    let upload = require_upload(db, &upload_id)?;
+    if index != upload.next_chunk {
+        return Err(ToolError::invalid_value(
+            "index",
+            &format!("expected chunk {}, got {}", upload.next_chunk, index),
+        )
+        .into());
+    }
+    if let Some(expected) = upload.expected_size
+        && upload.received_bytes + bytes.len() as i64 > expected
+    {
+        return Err(ToolError::invalid_value(
+            "data",
+            &format!("chunk would exceed the declared size of {} bytes", expected),
+        )
+        .into());
+    }
+
+    // Claim the chunk index before writing so a concurrent duplicate cannot append twice
+    if !db.advance_attachment_upload(&upload_id, index, bytes.len() as i64)? {
+        return Err(ToolError::invalid_value(
+            "index",
+            &format!("chunk {} was already received", index),
+        )
+        .into());
+    }
+    let mut part = std::fs::OpenOptions::new()
+        .append(true)
+        .open(upload_part_path(media_dir, &upload_id))?;
+    std::io::Write::write_all(&mut part, &bytes)?;
+
+    Ok(json!({
+        "upload_id": upload_id,
+        "received_bytes": upload.received_bytes + bytes.len() as i64,
+        "next_chunk": index + 1
+    }))
+}
+
+pub fn attach_commit(
+    db: &Database,
+    media_dir: &Path,
+    attachments_config: &AttachmentsConfig,
+    args: Value,
+) -> Result<Value> {
+    let upload_id =
+        get_string(&args, "upload_id").ok_or_else(|| ToolError::missing_field("upload_id"))?;
+    let sha256 = get_string(&args, "sha256").map(|h| h.to_ascii_lowercase());
+
+    let upload = require_upload(db, &upload_id)?;
+    let part = upload_part_path(media_dir, &upload_id);
+    let (hash, size) = hash_file(&part)?;
+
+    // Verify integrity; a corrupt upload cannot be repaired, so it is discarded
+    let mismatch = if upload.expected_size.is_some_and(|s| s != size) {
+        Some(format!(
+            "size mismatch: expected {} bytes, received {}",
+            upload.expected_size.unwrap_or_default(),
+            size
+        ))
+    } else {
+        [upload.expected_hash.as_deref(), sha256.as_deref()]
+            .into_iter()
+            .flatten()
+            .find(|expected| *expected != hash)
+            .map(|expected| format!("sha256 mismatch: expected {}, got {}", expected, hash))
+    };
+    if let Some(message) = mismatch {
+        db.delete_attachment_upload(&upload_id)?;
+        let _ = std::fs::remove_file(&part);
+        return Err(ToolError::new(ErrorCode::InvalidFieldValue, message).into());
+    }
+
+    // The upload is kept on a quota failure so it can be committed after freeing space
+    check_quotas(
+        db,
+        attachments_config,
+        std::slice::from_ref(&upload.task_id),
+        &upload.attachment_type,
+        upload.mode == "replace",
+        size,
+    )?;
+
+    if upload.mode == "replace" {
+        for old_fp in db.delete_attachments_by_type(&upload.task_id, &upload.attachment_type)? {
+            if is_in_media_dir(&old_fp, media_dir) {
+                let _ = std::fs::remove_file(&old_fp);
+            }
+        }
+    }
+
+    let store = open_blob_store(&attachments_config.storage, media_dir)?;
+    let storing = BLOB_LOCK.read().unwrap_or_else(PoisonError::into_inner);
+    store.put_file(&hash, &part)?;
+    let blob_path = store.location(&hash);
+    let (sequence, version) = if upload.mode == "version" {
+        let versioned = db.add_attachment_version(
+            &upload.task_id,
+            upload.attachment_type.clone(),
+            upload.name.clone(),
+            String::new(),
+            Some(upload.mime_type.clone()),
+            Some(blob_path.clone()),
+            Some((hash.clone(), size)),
+        )?;
+        prune_versions(
+            db,
+            media_dir,
+            attachments_config,
+            &upload.task_id,
+            &upload.attachment_type,
+            &upload.name,
+        )?;
+        versioned
+    } else {
+        let sequence = db.add_blob_attachment(
+            &upload.task_id,
+            upload.attachment_type.clone(),
+            upload.name.clone(),
+            Some(upload.mime_type.clone()),
+            blob_path.clone(),
+            hash.clone(),
+            size,
+        )?;
+        (sequence, 1)
+    };
+    drop(storing);
+    db.delete_attachment_upload(&upload_id)?;
+    let expired = match attachments_config.get_retention(&upload.attachment_type) {
+        Some((keep_last, max_age_seconds)) => apply_retention(
+            db,
+            media_dir,
+            Some(&upload.task_id),
+            &upload.attachment_type,
+            keep_last,
+            max_age_seconds,
+            false,
+        )?
+        .len(),
+        None => 0,
+    };
+
+    let mut result = json!({
+        "task_id": upload.task_id,
+        "type": upload.attachment_type,
+        "sequence": sequence,
+        "file_path": blob_path,
+        "content_hash": hash,
+        "size_bytes": size
+    });
+    if !upload.name.is_empty() {
+        result["name"] = json!(upload.name);
+    }
+    if upload.mode == "version" {
+        result["version"] = json!(version);
+    }
+    if expired > 0 {
+        result["pruned"] = json!(expired);
+    }
+    Ok(result)
+}
+
 pub fn attachments(
     db: &Database,
-    _media_dir: &Path,
+    media_dir: &Path,
+    attachments_config: &AttachmentsConfig,
     default_format: OutputFormat,
     args: Value,
 ) -> Result<Value> {
     let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
     let type_pattern = get_string(&args, "type");
     let mime_pattern = get_string(&args, "mime");
+    let include_versions = get_bool(&args, "include_versions").unwrap_or(false);
     let format = get_string(&args, "format")
         .and_then(|s| OutputFormat::parse(&s))
         .unwrap_or(default_format);
 
     // Get filtered attachments (metadata only)
-    let attachments =
+    let mut attachments =
         db.get_attachments_filtered(&task_id, type_pattern.as_deref(), mime_pattern.as_deref())?;
 
+    // Older versions of a named attachment are hidden unless asked for
+    if !include_versions {
+        let latest: HashMap<(String, String), i32> = attachments
+            .iter()
+            .filter(|a| !a.name.is_empty())
+            .fold(HashMap::new(), |mut latest, a| {
+                let entry = latest
+                    .entry((a.attachment_type.clone(), a.name.clone()))
+                    .or_insert(a.version);
+                *entry = (*entry).max(a.version);
+                latest
+            });
+        attachments.retain(|a| {
+            a.name.is_empty()
+                || latest.get(&(a.attachment_type.clone(), a.name.clone())) == Some(&a.version)
+        });
+    }
+
     match format {
         OutputFormat::Markdown => Ok(markdown_to_json(format_attachments_markdown(&attachments))),
-        OutputFormat::Json => {
+        OutputFormat::Json | OutputFormat::Compact => {
+            let store = open_blob_store(&attachments_config.storage, media_dir)?;
             let results: Vec<Value> = attachments
                 .iter()
-                .map(|a| {
+                .map(|a| -> Result<Value> {
                     let mut obj = json!({
                         "task_id": &a.task_id,
                         "type": &a.attachment_type,
                         "sequence": a.sequence,
                         "name": &a.name,
                         "mime_type": &a.mime_type,
+                        "version": a.version,
                         "created_at": a.created_at
                     });
 
@@ -444,15 +1271,202 @@ pub fn attachments(
                         obj["file_path"] = json!(fp);
                     }
 
-                    obj
+                    if let Some(ref hash) = a.content_hash {
+                        obj["content_hash"] = json!(hash);
+                        obj["size_bytes"] = json!(a.size_bytes);
+                        if !store.exists(hash)? {
+                            obj["missing"] = json!(true);
+                        }
+                    }
+
+                    Ok(obj)
                 })
-                .collect();
+                .collect::<Result<_>>()?;
 
             Ok(json!({ "attachments": results }))
         }
     }
 }
 
+/// Read the bytes of a blob or file attachment: blobs from the blob store,
+/// files from disk. Returns `None` for inline attachments.
+pub fn read_stored_content(
+    attachment: &Attachment,
+    store: &dyn BlobStore,
+) -> Result<Option<Vec<u8>>> {
+    if let Some(ref hash) = attachment.content_hash {
+        let bytes = store.get(hash)?.ok_or_else(|| {
+            ToolError::new(
+                ErrorCode::FileNotFound,
+                format!("Blob is missing from storage: {}", hash),
+            )
+        })?;
+        Ok(Some(bytes))
+    } else if let Some(ref fp) = attachment.file_path {
+        let bytes = std::fs::read(fp).map_err(|_| {
+            ToolError::new(ErrorCode::FileNotFound, format!("File not found: {}", fp))
+        })?;
+        Ok(Some(bytes))
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn get_attachment(
+    db: &Database,
+    media_dir: &Path,
+    attachments_config: &AttachmentsConfig,
+    args: Value,
+) -> Result<Value> {
+    let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    let attachment_type =
+        get_string(&args, "type").ok_or_else(|| ToolError::missing_field("type"))?;
+    let sequence = get_i32(&args, "sequence");
+    let name = get_string(&args, "name");
+    let version = get_i32(&args, "version");
+
+    let attachment = match (sequence, name) {
+        (Some(sequence), _) => db.get_attachment(&task_id, &attachment_type, sequence)?,
+        (None, Some(name)) => {
+            db.get_attachment_version(&task_id, &attachment_type, &name, version)?
+        }
+        (None, None) => {
+            return Err(ToolError::new(
+                ErrorCode::InvalidFieldValue,
+                "Either 'sequence' or 'name' must be provided",
+            )
+            .into());
+        }
+    };
+    let attachment = attachment.ok_or_else(|| {
+        ToolError::new(
+            ErrorCode::AttachmentNotFound,
+            format!("Attachment not found: {} {}", task_id, attachment_type),
+        )
+    })?;
+
+    let store = open_blob_store(&attachments_config.storage, media_dir)?;
+    let (content, encoding) = match read_stored_content(&attachment, store.as_ref())? {
+        Some(bytes) => match String::from_utf8(bytes) {
+            Ok(text) => (text, "utf8"),
+            Err(e) => (BASE64.encode(e.into_bytes()), "base64"),
+        },
+        None => (attachment.content.clone(), "utf8"),
+    };
+
+    let mut result = json!({
+        "task_id": attachment.task_id,
+        "type": attachment.attachment_type,
+        "sequence": attachment.sequence,
+        "name": attachment.name,
+        "mime_type": attachment.mime_type,
+        "version": attachment.version,
+        "created_at": attachment.created_at,
+        "content": content,
+        "encoding": encoding
+    });
+    if let Some(fp) = attachment.file_path {
+        result["file_path"] = json!(fp);
+    }
+    if let Some(hash) = attachment.content_hash {
+        result["content_hash"] = json!(hash);
+        result["size_bytes"] = json!(attachment.size_bytes);
+    }
+    Ok(result)
+}
+
+pub fn prune_attachments(
+    db: &Database,
+    media_dir: &Path,
+    attachments_config: &AttachmentsConfig,
+    args: Value,
+) -> Result<Value> {
+    // Agent parameter is optional - for tracking/audit purposes
+    let _agent_id = get_string(&args, "agent");
+
+    let task_id = get_string(&args, "task");
+    let attachment_type = get_string(&args, "type");
+    let keep_last = get_i64(&args, "keep_last");
+    let max_age_seconds = get_i64(&args, "max_age_seconds");
+    let dry_run = get_bool(&args, "dry_run").unwrap_or(false);
+
+    if keep_last.is_some_and(|k| k < 0) {
+        return Err(ToolError::invalid_value("keep_last", "must not be negative").into());
+    }
+    if max_age_seconds.is_some_and(|a| a < 0) {
+        return Err(ToolError::invalid_value("max_age_seconds", "must not be negative").into());
+    }
+
+    // An explicit policy applies to one type; otherwise use the configured policies
+    let policies: Vec<(String, Option<usize>, Option<i64>)> =
+        if keep_last.is_some() || max_age_seconds.is_some() {
+            let attachment_type = attachment_type.ok_or_else(|| {
+                ToolError::invalid_value("type", "is required with keep_last or max_age_seconds")
+            })?;
+            vec![(
+                attachment_type,
+                keep_last.map(|k| k as usize),
+                max_age_seconds,
+            )]
+        } else {
+            let mut policies: Vec<_> = attachments_config
+                .definitions
+                .keys()
+                .filter(|key| attachment_type.as_ref().is_none_or(|t| t == *key))
+                .filter_map(|key| {
+                    attachments_config
+                        .get_retention(key)
+                        .map(|(keep_last, max_age)| (key.clone(), keep_last, max_age))
+                })
+                .collect();
+            policies.sort();
+            policies
+        };
+
+    let used_before = db.get_attachment_usage(task_id.as_deref(), None)?;
+    let mut expired: Vec<AttachmentMeta> = Vec::new();
+    for (attachment_type, keep_last, max_age_seconds) in &policies {
+        expired.extend(apply_retention(
+            db,
+            media_dir,
+            task_id.as_deref(),
+            attachment_type,
+            *keep_last,
+            *max_age_seconds,
+            dry_run,
+        )?);
+    }
+
+    let mut by_type: HashMap<&str, usize> = HashMap::new();
+    for a in &expired {
+        *by_type.entry(a.attachment_type.as_str()).or_default() += 1;
+    }
+    let mut response = json!({
+        "deleted_count": if dry_run { 0 } else { expired.len() },
+        "by_type": by_type,
+        "dry_run": dry_run
+    });
+
+    if dry_run {
+        response["would_delete"] = json!(
+            expired
+                .iter()
+                .map(|a| json!({
+                    "task_id": &a.task_id,
+                    "type": &a.attachment_type,
+                    "sequence": a.sequence,
+                    "name": &a.name,
+                    "created_at": a.created_at
+                }))
+                .collect::<Vec<_>>()
+        );
+    } else {
+        response["bytes_freed"] =
+            json!(used_before - db.get_attachment_usage(task_id.as_deref(), None)?);
+    }
+    Ok(response)
+}
+
 pub fn detach(db: &Database, media_dir: &Path, args: Value) -> Result<Value> {
     // Agent parameter is optional - for tracking/audit purposes
     let _agent_id = get_string(&args, "agent");
@@ -485,6 +1499,29 @@ pub fn detach(db: &Database, media_dir: &Path, args: Value) -> Result<Value> {
     }))
 }
 
+pub fn gc_blobs(
+    db: &Database,
+    media_dir: &Path,
+    attachments_config: &AttachmentsConfig,
+    args: Value,
+) -> Result<Value> {
+    // Agent parameter is optional - for tracking/audit purposes
+    let _agent_id = get_string(&args, "agent");
+    let dry_run = get_bool(&args, "dry_run").unwrap_or(false);
+
+    let store = open_blob_store(&attachments_config.storage, media_dir)?;
+    let hashes = collect_blobs(db, store.as_ref(), dry_run)?;
+
+    let mut response = json!({
+        "deleted_count": if dry_run { 0 } else { hashes.len() },
+        "dry_run": dry_run
+    });
+    if dry_run {
+        response["would_delete"] = json!(hashes);
+    }
+    Ok(response)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
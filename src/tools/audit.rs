@@ -0,0 +1,87 @@
+//! Audit log query tool.
+
+use super::tracking::parse_timestamp;
+use super::{get_i32, get_string, make_tool_with_prompts};
+use crate::config::Prompts;
+use crate::db::Database;
+use crate::db::audit::AuditQuery;
+use anyhow::Result;
+use rmcp::model::Tool;
+use serde_json::{Value, json};
+
+pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
+    vec![make_tool_with_prompts(
+        "audit",
+        "Read the audit log of mutating tool calls, newest first. Each entry records the calling agent, tool, a SHA-256 hash of the arguments, affected task IDs, outcome, and timestamp. Covers deletes, relinks, attachments, and tag edits that leave no trace in task history.",
+        json!({
+            "agent": {
+                "type": "string",
+                "description": "Only calls made by this agent"
+            },
+            "tool": {
+                "type": "string",
+                "description": "Only calls to this tool (e.g., 'delete', 'relink')"
+            },
+            "task": {
+                "type": "string",
+                "description": "Only calls that named or created this task"
+            },
+            "outcome": {
+                "type": "string",
+                "description": "'ok', 'error' (any failure), or a specific error code (e.g., 'TASK_NOT_FOUND')"
+            },
+            "since": {
+                "type": "string",
+                "description": "Start of time range (ISO 8601 datetime or milliseconds since epoch)"
+            },
+            "until": {
+                "type": "string",
+                "description": "End of time range (ISO 8601 datetime or milliseconds since epoch)"
+            },
+            "limit": {
+                "type": "integer",
+                "description": "Maximum number of entries to return (default: 100, max: 1000)"
+            },
+            "offset": {
+                "type": "integer",
+                "description": "Number of entries to skip for pagination (default: 0)"
+            }
+        }),
+        vec![],
+        prompts,
+    )]
+}
+
+pub fn audit(db: &Database, args: Value) -> Result<Value> {
+    let agent = get_string(&args, "agent");
+    let tool = get_string(&args, "tool");
+    let task = get_string(&args, "task");
+    let outcome = get_string(&args, "outcome");
+    let since = get_string(&args, "since").and_then(|s| parse_timestamp(&s));
+    let until = get_string(&args, "until").and_then(|s| parse_timestamp(&s));
+    let limit = get_i32(&args, "limit").unwrap_or(100).clamp(1, 1000);
+    let offset = get_i32(&args, "offset").unwrap_or(0).max(0);
+
+    // Fetch limit+1 to detect if there are more entries
+    let mut entries = db.get_audit_log(AuditQuery {
+        worker_id: agent.as_deref(),
+        tool: tool.as_deref(),
+        task_id: task.as_deref(),
+        outcome: outcome.as_deref(),
+        since,
+        until,
+        limit: Some(limit + 1),
+        offset,
+    })?;
+
+    let has_more = entries.len() > limit as usize;
+    entries.truncate(limit as usize);
+
+    Ok(json!({
+        "entry_count": entries.len(),
+        "has_more": has_more,
+        "offset": offset,
+        "limit": limit,
+        "entries": entries
+    }))
+}
@@ -2,38 +2,177 @@
 //!
 //! The `claim` tool is a convenience wrapper around `update` that transitions
 //! a task to the first timed state. For releasing tasks, use `update` with
-//! a non-timed state (ownership clears automatically).
+//! a non-timed state (ownership clears automatically). Called without a task,
+//! it ranks the worker's ready tasks by match score instead of claiming one,
+//! or, given a filter (`tags`, `parent`, `min_priority`), atomically claims the
+//! best-ranked ready task that matches.
+//!
+//! The `reserve` tool places a short-lived hold on a task so a worker can look
+//! it over before committing to a claim, without claim/release churn.
+//!
+//! The `claim_many` tool claims several tasks in one transaction, for batch
+//! workers that would otherwise race each other with sequential claims.
+//!
+//! The `suggest_next_task` tool picks the single best ready task for a worker,
+//! replacing the list/filter/choose loop.
+//!
+//! The `wait_for` tool lets a worker register interest in blocked tasks so it
+//! is told when they become ready, instead of polling `list_tasks(ready=true)`.
 
-use super::{get_bool, get_string, make_tool_with_prompts};
-use crate::config::{AppConfig, Prompts, StatesConfig};
+use super::{
+    get_bool, get_i64, get_string, get_string_array, get_string_or_array, make_tool_with_prompts,
+};
+use crate::config::{AppConfig, DependenciesConfig, Prompts, StatesConfig};
 use crate::db::Database;
 use crate::error::ToolError;
 use crate::prompts::PromptContext;
+use crate::types::{Task, has_tag};
 use anyhow::Result;
 use rmcp::model::Tool;
 use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Longest a single `wait_for` call may block.
+const MAX_WAIT_MS: i64 = 300_000;
+
+/// How often a blocking `wait_for` re-checks readiness.
+const WAIT_POLL_INTERVAL_MS: u64 = 250;
+
+/// Reservation length when `reserve` is not given `ttl_ms`.
+const DEFAULT_RESERVATION_MS: i64 = 60_000;
+
+/// Longest a single reservation may last.
+const MAX_RESERVATION_MS: i64 = 600_000;
+
+/// Candidates returned by `claim` without a task, unless `limit` says otherwise.
+const DEFAULT_CANDIDATES: i64 = 5;
 
 pub fn get_tools(prompts: &Prompts, _states_config: &StatesConfig) -> Vec<Tool> {
-    vec![make_tool_with_prompts(
-        "claim",
-        "Commit to working on a task (like adding to a changelist). Fails if: already claimed, deps unsatisfied, or worker lacks required tags. Sets status to timed (working) status.",
-        json!({
-            "worker_id": {
-                "type": "string",
-                "description": "Worker ID claiming the task"
-            },
-            "task": {
-                "type": "string",
-                "description": "Task ID to claim"
-            },
-            "force": {
-                "type": "boolean",
-                "description": "Force claim even if owned by another agent (default: false)"
-            }
-        }),
-        vec!["worker_id", "task"],
-        prompts,
-    )]
+    vec![
+        make_tool_with_prompts(
+            "claim",
+            "Commit to working on a task (like adding to a changelist). Fails if: already claimed, deps unsatisfied, an exclusive partner is being worked, or worker lacks required tags. Unsatisfied soft deps are returned as dependency_warnings. Sets status to timed (working) status. Without a task, claims nothing and returns ready candidates ranked by match score (tags, priority, estimate vs the worker's history); with a filter (tags, parent, min_priority) instead, atomically claims the best-ranked ready task that matches.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Worker ID claiming the task"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "Task ID to claim. Omit to get ranked candidates instead."
+                },
+                "force": {
+                    "type": "boolean",
+                    "description": "Force claim even if owned by another agent (default: false)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Maximum candidates to return when task is omitted (default: 5)"
+                },
+                "tags": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Without task: claim the best ready task carrying ALL of these tags (child tags match)"
+                },
+                "parent": {
+                    "type": "string",
+                    "description": "Without task: claim the best ready task under this subtree root"
+                },
+                "min_priority": {
+                    "oneOf": [{ "type": "integer" }, { "type": "string" }],
+                    "description": "Without task: claim the best ready task at or above this priority"
+                }
+            }),
+            vec!["worker_id"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "reserve",
+            "Place a short-lived hold on an unclaimed task while gathering context before claiming it. While reserved, other workers cannot claim it (without force) and it drops out of their ready lists; list_tasks shows reserved_by. Reserving again extends the hold; claiming it ends the hold. Holds expire on their own; ttl_ms=0 drops one early.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Worker ID placing the hold"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "Task ID to reserve"
+                },
+                "ttl_ms": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": MAX_RESERVATION_MS,
+                    "description": "How long the hold lasts (default: 60000; 0 releases the worker's hold)"
+                }
+            }),
+            vec!["worker_id", "task"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "claim_many",
+            "Claim several tasks atomically. Give count to claim up to that many of the worker's best-ranked ready tasks, or tasks to claim an explicit list. Each task gets the same checks as claim (deps, exclusive partners, project, group, tags; no force), and claims stop at the worker's max_claims. Returns exactly which tasks were claimed and why the others were skipped.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Worker ID claiming the tasks"
+                },
+                "count": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Claim up to this many ready tasks, best match first"
+                },
+                "tasks": {
+                    "oneOf": [
+                        { "type": "string" },
+                        { "type": "array", "items": { "type": "string" } }
+                    ],
+                    "description": "Task ID(s) to claim, in order"
+                }
+            }),
+            vec!["worker_id"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "wait_for",
+            "Register interest in tasks becoming ready instead of polling list_tasks(ready=true). Returns ready tasks immediately; otherwise registers the worker so later unblocks are reported here and in thinking() responses. With timeout_ms, blocks until a task becomes ready or the timeout elapses.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Worker ID that is waiting"
+                },
+                "task": {
+                    "oneOf": [
+                        { "type": "string" },
+                        { "type": "array", "items": { "type": "string" } }
+                    ],
+                    "description": "Task ID(s) to wait for"
+                },
+                "timeout_ms": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": MAX_WAIT_MS,
+                    "description": "How long to block waiting for a task to become ready (default: 0 = register and return)"
+                }
+            }),
+            vec!["worker_id", "task"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "suggest_next_task",
+            "Suggest the single best task for a worker to claim next, weighing priority, tag match, deadline proximity (the due_at custom field), and critical-path weight (how long a chain of unfinished tasks it unblocks). Only ready tasks the worker qualifies for are considered. Returns no task when the worker is at its claim limit. Claims nothing.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Worker ID to suggest a task for"
+                }
+            }),
+            vec!["worker_id"],
+            prompts,
+        ),
+    ]
 }
 
 pub fn claim(
@@ -45,12 +184,19 @@ pub fn claim(
     // Derive states from the per-worker workflow so overlay-added states are recognized
     let states_config_owned: StatesConfig = workflows.into();
     let states_config = &states_config_owned;
-    let phases_config = &config.phases;
     let deps_config = &config.deps;
     let auto_advance = &config.auto_advance;
     let worker_id =
         get_string(&args, "worker_id").ok_or_else(|| ToolError::missing_field("worker_id"))?;
-    let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    let Some(task_id) = get_string(&args, "task") else {
+        if ["tags", "parent", "min_priority"]
+            .iter()
+            .any(|key| args.get(key).is_some())
+        {
+            return claim_matching(db, config, workflows, states_config, &worker_id, &args);
+        }
+        return claim_candidates(db, config, states_config, &worker_id, &args);
+    };
     let force = get_bool(&args, "force").unwrap_or(false);
 
     // Find the first timed state to use for claiming
@@ -100,8 +246,24 @@ pub fn claim(
         }
     };
 
+    claimed_response(db, config, workflows, states_config, &worker_id, &task)
+}
+
+/// The response to a successful claim: the task, soft dependency warnings, and
+/// the transition and role prompts for the worker.
+fn claimed_response(
+    db: &Database,
+    config: &AppConfig,
+    workflows: &crate::config::workflows::WorkflowsConfig,
+    states_config: &StatesConfig,
+    worker_id: &str,
+    task: &Task,
+) -> Result<Value> {
+    let phases_config = &config.phases;
+    let dependency_warnings = soft_dependency_warnings(db, &task.id, states_config, &config.deps)?;
+
     // Pre-fetch worker info for context-sensitive prompts (must outlive ctx)
-    let worker_info = db.get_worker(&worker_id).ok().flatten();
+    let worker_info = db.get_worker(worker_id).ok().flatten();
     let worker_role = worker_info
         .as_ref()
         .map(|w| workflows.match_role(&w.tags))
@@ -109,7 +271,7 @@ pub fn claim(
 
     // Get transition prompts for claiming (with context-sensitive template expansion)
     let mut transition_prompt_list: Vec<String> = {
-        match db.update_worker_state(&worker_id, Some(&task.status), task.phase.as_deref()) {
+        match db.update_worker_state(worker_id, Some(&task.status), task.phase.as_deref()) {
             Ok((old_status, old_phase)) => {
                 // Create context with task and agent info for rich template expansion
                 let mut ctx = PromptContext::new(
@@ -122,7 +284,7 @@ pub fn claim(
 
                 // Add agent context if worker info is available
                 if let Some(ref worker) = worker_info {
-                    ctx = ctx.with_agent(&worker_id, worker_role.as_deref(), &worker.tags);
+                    ctx = ctx.with_agent(worker_id, worker_role.as_deref(), &worker.tags);
                 }
 
                 crate::prompts::get_transition_prompts_with_context(
@@ -149,6 +311,11 @@ pub fn claim(
         }
     });
 
+    // Soft dependencies never block the claim, but the agent should know about them
+    if !dependency_warnings.is_empty() {
+        response["dependency_warnings"] = json!(dependency_warnings);
+    }
+
     // Add role-specific prompts: both "claiming" guidance and "reporting" guidance
     // This gives the agent full context on how to work and communicate from the start
     if let Some(ref role_name) = worker_role {
@@ -171,3 +338,394 @@ pub fn claim(
 
     Ok(response)
 }
+
+/// Reserve a task for a worker, or with `ttl_ms: 0` drop its reservation.
+pub fn reserve(db: &Database, args: Value) -> Result<Value> {
+    let worker_id =
+        get_string(&args, "worker_id").ok_or_else(|| ToolError::missing_field("worker_id"))?;
+    let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    if db.get_worker(&worker_id)?.is_none() {
+        return Err(ToolError::agent_not_found(&worker_id).into());
+    }
+    let ttl_ms = get_i64(&args, "ttl_ms").unwrap_or(DEFAULT_RESERVATION_MS);
+    if !(0..=MAX_RESERVATION_MS).contains(&ttl_ms) {
+        return Err(ToolError::invalid_value(
+            "ttl_ms",
+            &format!("Must be between 0 and {}", MAX_RESERVATION_MS),
+        )
+        .into());
+    }
+
+    if ttl_ms == 0 {
+        let released = db.release_reservation(&task_id, &worker_id)?;
+        return Ok(json!({
+            "success": true,
+            "task": task_id,
+            "released": released,
+        }));
+    }
+
+    let reservation = db.reserve_task(&task_id, &worker_id, ttl_ms)?;
+    Ok(json!({
+        "success": true,
+        "task": task_id,
+        "reserved_by": reservation.worker_id,
+        "expires_at": reservation.expires_at,
+    }))
+}
+
+/// Claim several tasks in one transaction: `count` of the worker's best-ranked
+/// ready tasks, or an explicit `tasks` list.
+pub fn claim_many(
+    db: &Database,
+    config: &AppConfig,
+    workflows: &crate::config::workflows::WorkflowsConfig,
+    args: Value,
+) -> Result<Value> {
+    // Derive states from the per-worker workflow so overlay-added states are recognized
+    let states_config: StatesConfig = workflows.into();
+    let worker_id =
+        get_string(&args, "worker_id").ok_or_else(|| ToolError::missing_field("worker_id"))?;
+    if db.get_worker(&worker_id)?.is_none() {
+        return Err(ToolError::agent_not_found(&worker_id).into());
+    }
+
+    let count = get_i64(&args, "count");
+    let (task_ids, count) = match (get_string_or_array(&args, "tasks"), count) {
+        (Some(_), Some(_)) => {
+            return Err(
+                ToolError::invalid_value("count", "Give either count or tasks, not both").into(),
+            );
+        }
+        (Some(ids), None) => (ids, None),
+        (None, Some(n)) if n < 1 => {
+            return Err(ToolError::invalid_value("count", "count must be at least 1").into());
+        }
+        (None, Some(n)) => {
+            let fair_share = db.get_fair_share(
+                &worker_id,
+                &config.claiming.fairness,
+                &config.priorities,
+                &states_config,
+            )?;
+            let ranked = db.rank_claim_candidates(
+                &worker_id,
+                &states_config,
+                &config.deps,
+                &config.priorities,
+                &config.tags,
+                fair_share.as_ref(),
+                usize::MAX,
+            )?;
+            (ranked.into_iter().map(|c| c.id).collect(), Some(n as usize))
+        }
+        (None, None) => return Err(ToolError::missing_field("count or tasks").into()),
+    };
+
+    let batch = db.claim_many(&worker_id, &task_ids, count, &states_config, &config.deps)?;
+    let claimed: Vec<Value> = batch
+        .claimed
+        .iter()
+        .map(|task| {
+            json!({
+                "id": &task.id,
+                "title": task.title,
+                "status": task.status,
+                "worker_id": task.worker_id,
+                "claimed_at": task.claimed_at
+            })
+        })
+        .collect();
+
+    let mut response = json!({
+        "success": !claimed.is_empty(),
+        "claimed": claimed,
+        "skipped": batch.skipped,
+    });
+    if let Some(n) = count {
+        response["requested"] = json!(n);
+    }
+    Ok(response)
+}
+
+/// Claim the best-ranked ready task matching `tags` (all of them), `parent`
+/// (a subtree root), and `min_priority`, for `claim` with a filter instead of
+/// a task. Selection and claim happen in one transaction, so two workers
+/// filtering at once never pick the same task.
+fn claim_matching(
+    db: &Database,
+    config: &AppConfig,
+    workflows: &crate::config::workflows::WorkflowsConfig,
+    states_config: &StatesConfig,
+    worker_id: &str,
+    args: &Value,
+) -> Result<Value> {
+    if db.get_worker(worker_id)?.is_none() {
+        return Err(ToolError::agent_not_found(worker_id).into());
+    }
+    let tags = get_string_array(args, "tags")
+        .map(|tags| config.tags.canonical_tags(&tags))
+        .unwrap_or_default();
+    let min_priority = match args.get("min_priority") {
+        Some(v) => Some(
+            config
+                .priorities
+                .parse(v)
+                .map_err(|e| ToolError::invalid_value("min_priority", &e.to_string()))?,
+        ),
+        None => None,
+    };
+    let subtree: Option<HashSet<String>> = match get_string(args, "parent") {
+        Some(root) => Some(
+            db.get_descendants(&root, -1)?
+                .into_iter()
+                .map(|t| t.id)
+                .collect(),
+        ),
+        None => None,
+    };
+
+    let matching: HashSet<String> = db
+        .get_ready_tasks(
+            Some(worker_id),
+            states_config,
+            &config.deps,
+            None,
+            None,
+            None,
+        )?
+        .into_iter()
+        .filter(|t| tags.iter().all(|tag| has_tag(&t.tags, tag)))
+        .filter(|t| min_priority.is_none_or(|p| t.priority >= p))
+        .filter(|t| subtree.as_ref().is_none_or(|ids| ids.contains(&t.id)))
+        .map(|t| t.id)
+        .collect();
+    let fair_share = db.get_fair_share(
+        worker_id,
+        &config.claiming.fairness,
+        &config.priorities,
+        states_config,
+    )?;
+    let ranked: Vec<String> = db
+        .rank_claim_candidates(
+            worker_id,
+            states_config,
+            &config.deps,
+            &config.priorities,
+            &config.tags,
+            fair_share.as_ref(),
+            usize::MAX,
+        )?
+        .into_iter()
+        .map(|c| c.id)
+        .filter(|id| matching.contains(id))
+        .collect();
+
+    let batch = db.claim_many(worker_id, &ranked, Some(1), states_config, &config.deps)?;
+    match batch.claimed.first() {
+        Some(task) => claimed_response(db, config, workflows, states_config, worker_id, task),
+        None => Ok(json!({
+            "success": false,
+            "message": "No ready task matching the filter could be claimed",
+            "task": null,
+            "considered": ranked.len(),
+        })),
+    }
+}
+
+/// List the ready tasks best suited to a worker, for `claim` without a task.
+fn claim_candidates(
+    db: &Database,
+    config: &AppConfig,
+    states_config: &StatesConfig,
+    worker_id: &str,
+    args: &Value,
+) -> Result<Value> {
+    if db.get_worker(worker_id)?.is_none() {
+        return Err(ToolError::agent_not_found(worker_id).into());
+    }
+    let limit = get_i64(args, "limit").unwrap_or(DEFAULT_CANDIDATES).max(1) as usize;
+
+    let fair_share = db.get_fair_share(
+        worker_id,
+        &config.claiming.fairness,
+        &config.priorities,
+        states_config,
+    )?;
+    let candidates = db.rank_claim_candidates(
+        worker_id,
+        states_config,
+        &config.deps,
+        &config.priorities,
+        &config.tags,
+        fair_share.as_ref(),
+        limit,
+    )?;
+
+    let mut response = json!({
+        "message": if candidates.is_empty() {
+            "No ready tasks match this worker"
+        } else {
+            "No task given; claim one of the candidates"
+        },
+        "candidates": candidates,
+    });
+    if let Some(share) = fair_share {
+        response["fair_share"] = json!(share);
+    }
+    Ok(response)
+}
+
+/// Suggest the single best ready task for a worker, given its tags and current load.
+pub fn suggest_next_task(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
+    let worker_id =
+        get_string(&args, "worker_id").ok_or_else(|| ToolError::missing_field("worker_id"))?;
+    let worker = db
+        .get_worker(&worker_id)?
+        .ok_or_else(|| ToolError::agent_not_found(&worker_id))?;
+    let load = db.get_claimed_tasks(Some(&worker_id), None)?.len();
+
+    if load >= worker.max_claims.max(0) as usize {
+        return Ok(json!({
+            "message": "Worker is at its claim limit; finish or release a task first",
+            "task": null,
+            "load": load,
+            "considered": 0,
+        }));
+    }
+
+    let fair_share = db.get_fair_share(
+        &worker_id,
+        &config.claiming.fairness,
+        &config.priorities,
+        &config.states,
+    )?;
+    let (suggestion, considered) = db.suggest_next_task(
+        &worker_id,
+        &config.states,
+        &config.deps,
+        &config.priorities,
+        &config.tags,
+        fair_share.as_ref(),
+    )?;
+
+    let mut response = json!({
+        "message": if suggestion.is_some() {
+            "Claim this task next"
+        } else {
+            "No ready tasks match this worker"
+        },
+        "task": suggestion,
+        "load": load,
+        "considered": considered,
+    });
+    if let Some(share) = fair_share {
+        response["fair_share"] = json!(share);
+    }
+    Ok(response)
+}
+
+/// Describe soft start dependencies of a task that are not yet satisfied.
+pub(crate) fn soft_dependency_warnings(
+    db: &Database,
+    task_id: &str,
+    states_config: &StatesConfig,
+    deps_config: &DependenciesConfig,
+) -> Result<Vec<String>> {
+    Ok(db
+        .get_unsatisfied_soft_blockers(task_id, states_config, deps_config)?
+        .into_iter()
+        .map(|blocker| format!("Soft dependency on '{}' is not yet satisfied", blocker))
+        .collect())
+}
+
+/// Check the requested tasks once, registering the worker for those that are not ready.
+///
+/// Returns tasks that are ready now (including earlier registrations whose last
+/// blocker has since completed), tasks still being waited on, and requested tasks
+/// that can no longer become ready because they have left the initial state.
+pub fn wait_for(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
+    let worker_id =
+        get_string(&args, "worker_id").ok_or_else(|| ToolError::missing_field("worker_id"))?;
+    let task_ids =
+        get_string_or_array(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    if db.get_worker(&worker_id)?.is_none() {
+        return Err(ToolError::agent_not_found(&worker_id).into());
+    }
+
+    let mut closed = Vec::new();
+    let mut candidates = Vec::new();
+    for task_id in &task_ids {
+        let task = db
+            .get_task(task_id)?
+            .ok_or_else(|| ToolError::task_not_found(task_id))?;
+        if task.status == config.states.initial && task.worker_id.is_none() {
+            candidates.push(task_id.clone());
+        } else {
+            closed.push(task_id.clone());
+        }
+    }
+
+    let ready_now: HashSet<String> = db
+        .get_ready_tasks(None, &config.states, &config.deps, None, None, None)?
+        .into_iter()
+        .map(|t| t.id)
+        .collect();
+
+    // Earlier registrations that were unblocked count too, if still claimable
+    let mut ready: Vec<String> = db
+        .take_unblock_notifications(&worker_id)?
+        .into_iter()
+        .filter(|id| ready_now.contains(id) && !candidates.contains(id))
+        .collect();
+    let mut waiting = Vec::new();
+    for task_id in candidates {
+        if ready_now.contains(&task_id) {
+            ready.push(task_id);
+        } else {
+            waiting.push(task_id);
+        }
+    }
+
+    db.remove_waiters(&worker_id, &ready)?;
+    db.remove_waiters(&worker_id, &closed)?;
+    db.add_waiters(&worker_id, &waiting)?;
+
+    let mut response = json!({
+        "ready": ready,
+        "waiting": waiting,
+    });
+    if !closed.is_empty() {
+        response["closed"] = json!(closed);
+    }
+    Ok(response)
+}
+
+/// Blocking version of `wait_for`: re-checks until a task is ready or `timeout_ms` elapses.
+pub async fn wait_for_async(db: Arc<Database>, config: AppConfig, args: Value) -> Result<Value> {
+    let timeout_ms = get_i64(&args, "timeout_ms")
+        .unwrap_or(0)
+        .clamp(0, MAX_WAIT_MS);
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms as u64);
+
+    loop {
+        let (db, config_ref, args_ref) = (Arc::clone(&db), config.clone(), args.clone());
+        // Run on blocking thread pool since db operations are synchronous
+        let mut result = tokio::task::spawn_blocking(move || wait_for(&db, &config_ref, args_ref))
+            .await
+            .map_err(|e| anyhow::anyhow!("Task join error: {}", e))??;
+
+        let has_ready = result["ready"].as_array().is_some_and(|r| !r.is_empty());
+        let has_waiting = result["waiting"].as_array().is_some_and(|w| !w.is_empty());
+        let now = Instant::now();
+        if has_ready || !has_waiting || now >= deadline {
+            if timeout_ms > 0 {
+                result["timed_out"] = json!(!has_ready && has_waiting);
+            }
+            return Ok(result);
+        }
+
+        let remaining = deadline - now;
+        tokio::time::sleep(remaining.min(Duration::from_millis(WAIT_POLL_INTERVAL_MS))).await;
+    }
+}
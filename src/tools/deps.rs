@@ -1,12 +1,13 @@
 //! Dependency management tools.
 
 use super::{
-    IdList, get_string, get_string_or_array, get_string_or_array_or_wildcard,
-    make_tool_with_prompts,
+    IdList, ToolResult, get_string, get_string_or_array, get_string_or_array_or_wildcard,
+    make_tool_with_prompts, require_visible,
 };
-use crate::config::{DependenciesConfig, Prompts};
-use crate::db::{AddDependencyResult, Database};
+use crate::config::{BlockTarget, DependenciesConfig, Prompts, VisibilityConfig};
+use crate::db::Database;
 use crate::error::{ToolError, ToolWarning};
+use crate::export::matrix::{MatrixLayout, render_matrix_csv};
 use anyhow::Result;
 use rmcp::model::Tool;
 use serde_json::{Value, json};
@@ -22,7 +23,7 @@ pub fn get_tools(prompts: &Prompts, deps_config: &DependenciesConfig) -> Vec<Too
     vec![
         make_tool_with_prompts(
             "link",
-            "Create dependency links between tasks. Supports bulk: from and to accept string or array. Example: link(from=['A','B'], to='C', type='blocks') creates A->C and B->C dependencies.",
+            "Create dependency links between tasks in one transaction. Supports bulk: from and to accept string or array. Example: link(from=['A','B'], to='C', type='blocks') creates A->C and B->C dependencies; link(chain=['A','B','C']) creates A->B and B->C. If any link fails (cycle, second parent, cross-project), none are created.",
             json!({
                 "agent": {
                     "type": "string",
@@ -42,13 +43,27 @@ pub fn get_tools(prompts: &Prompts, deps_config: &DependenciesConfig) -> Vec<Too
                     ],
                     "description": "Target task ID(s) - the task(s) that are blocked/follow"
                 },
+                "chain": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Task IDs in order; links each to the next. Use instead of from/to."
+                },
                 "type": {
                     "type": "string",
                     "enum": dep_types,
                     "description": "Dependency type (default: 'blocks')"
+                },
+                "lag_ms": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Delay in milliseconds after the source task completes before the target becomes ready (start-blocking types only). Re-linking an existing dependency updates its lag."
+                },
+                "any_of": {
+                    "type": "string",
+                    "description": "Any-of group name (start-blocking types only). Edges into the same task with the same type and group are alternatives: the target is ready once any one is satisfied. Re-linking an existing dependency moves it to this group."
                 }
             }),
-            vec!["from", "to"],
+            vec![],
             prompts,
         ),
         make_tool_with_prompts(
@@ -127,6 +142,48 @@ pub fn get_tools(prompts: &Prompts, deps_config: &DependenciesConfig) -> Vec<Too
             vec!["prev_from", "prev_to", "from", "to"],
             prompts,
         ),
+        make_tool_with_prompts(
+            "sequence",
+            "Set the order of sibling tasks. Replaces the blocks edges between a parent's children with one from each listed task to the next, so inserting, reordering, or removing a task is one call with the new list. Children left out run unordered; an empty list clears the order.",
+            json!({
+                "agent": {
+                    "type": "string",
+                    "description": "Agent ID setting the order"
+                },
+                "tasks": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Sibling task IDs in execution order"
+                },
+                "parent": {
+                    "type": "string",
+                    "description": "Parent of the tasks (default: the first task's parent; required for an empty list)"
+                }
+            }),
+            vec!["tasks"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "export_matrix",
+            "Export the blocking graph of a subtree as CSV for auditing a plan's structure: an adjacency matrix (one row per task with its direct and transitive blocker counts, a column per task marking what blocks it) or an edge list. Only start-blocking dependency types between tasks in the subtree are included.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Calling agent ID; tasks outside its visibility rules are left out"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "Root task ID of the subtree (default: all live tasks)"
+                },
+                "layout": {
+                    "type": "string",
+                    "enum": ["matrix", "edges"],
+                    "description": "matrix (default) or edges (from,to,type rows)"
+                }
+            }),
+            vec![],
+            prompts,
+        ),
     ]
 }
 
@@ -134,68 +191,170 @@ pub fn link(db: &Database, deps_config: &DependenciesConfig, args: Value) -> Res
     // Agent parameter is optional - for tracking/audit purposes
     let _agent_id = get_string(&args, "agent");
 
-    let from_ids =
-        get_string_or_array(&args, "from").ok_or_else(|| ToolError::missing_field("from"))?;
-    let to_ids = get_string_or_array(&args, "to").ok_or_else(|| ToolError::missing_field("to"))?;
+    let chain = get_string_or_array(&args, "chain");
+    let edges: Vec<(String, String)> = match chain {
+        Some(chain) => {
+            if args.get("from").is_some() || args.get("to").is_some() {
+                return Err(ToolError::invalid_value(
+                    "chain",
+                    "chain cannot be combined with from or to",
+                )
+                .into());
+            }
+            if chain.len() < 2 {
+                return Err(
+                    ToolError::invalid_value("chain", "chain needs at least two task IDs").into(),
+                );
+            }
+            chain
+                .windows(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect()
+        }
+        None => {
+            let from_ids = get_string_or_array(&args, "from")
+                .ok_or_else(|| ToolError::missing_field("from"))?;
+            let to_ids =
+                get_string_or_array(&args, "to").ok_or_else(|| ToolError::missing_field("to"))?;
 
-    if from_ids.is_empty() {
-        return Err(ToolError::new(
-            crate::error::ErrorCode::InvalidFieldValue,
-            "At least one 'from' task ID must be provided",
-        )
-        .into());
-    }
-    if to_ids.is_empty() {
-        return Err(ToolError::new(
-            crate::error::ErrorCode::InvalidFieldValue,
-            "At least one 'to' task ID must be provided",
-        )
-        .into());
-    }
+            if from_ids.is_empty() {
+                return Err(ToolError::new(
+                    crate::error::ErrorCode::InvalidFieldValue,
+                    "At least one 'from' task ID must be provided",
+                )
+                .into());
+            }
+            if to_ids.is_empty() {
+                return Err(ToolError::new(
+                    crate::error::ErrorCode::InvalidFieldValue,
+                    "At least one 'to' task ID must be provided",
+                )
+                .into());
+            }
 
-    let dep_type = get_string(&args, "type").unwrap_or_else(|| "blocks".to_string());
+            // Create all combinations of from x to
+            from_ids
+                .iter()
+                .flat_map(|from_id| {
+                    to_ids
+                        .iter()
+                        .map(move |to_id| (from_id.clone(), to_id.clone()))
+                })
+                .collect()
+        }
+    };
 
-    let mut created = Vec::new();
-    let mut warnings: Vec<ToolWarning> = Vec::new();
-    let mut errors = Vec::new();
+    let dep_type = get_string(&args, "type").unwrap_or_else(|| "blocks".to_string());
 
-    // Create all combinations of from x to
-    for from_id in &from_ids {
-        for to_id in &to_ids {
-            match db.add_dependency_soft(from_id, to_id, &dep_type, deps_config) {
-                Ok(AddDependencyResult::Created) => created.push(json!({
-                    "from": from_id,
-                    "to": to_id,
-                    "type": &dep_type
-                })),
-                Ok(AddDependencyResult::AlreadyExists) => {
-                    warnings.push(ToolWarning::duplicate(&format!(
-                        "dependency {} -> {}",
-                        from_id, to_id
-                    )));
-                }
-                Ok(AddDependencyResult::FromTaskNotFound) => {
-                    warnings.push(ToolWarning::task_not_found(from_id).with_field("from"));
-                }
-                Ok(AddDependencyResult::ToTaskNotFound) => {
-                    warnings.push(ToolWarning::dependency_not_found(to_id, "to"));
-                }
-                Err(e) => errors.push(json!({
-                    "from": from_id,
-                    "to": to_id,
-                    "error": e.to_string()
-                })),
+    let lag_ms = match args.get("lag_ms") {
+        None | Some(Value::Null) => None,
+        Some(v) => {
+            let lag = v.as_i64().filter(|lag| *lag >= 0).ok_or_else(|| {
+                ToolError::invalid_value("lag_ms", "lag_ms must be a non-negative integer")
+            })?;
+            let starts = deps_config
+                .get_definition(&dep_type)
+                .is_some_and(|def| def.blocks == BlockTarget::Start);
+            if !starts {
+                return Err(ToolError::invalid_value(
+                    "lag_ms",
+                    &format!(
+                        "lag_ms only applies to dependency types with blocks: start, not '{}'",
+                        dep_type
+                    ),
+                )
+                .into());
             }
+            Some(lag)
+        }
+    };
+
+    let any_of = get_string(&args, "any_of");
+    if let Some(ref group) = any_of {
+        if group.trim().is_empty() {
+            return Err(ToolError::invalid_value("any_of", "any_of must not be empty").into());
+        }
+        let starts = deps_config
+            .get_definition(&dep_type)
+            .is_some_and(|def| def.blocks == BlockTarget::Start);
+        if !starts {
+            return Err(ToolError::invalid_value(
+                "any_of",
+                &format!(
+                    "any_of only applies to dependency types with blocks: start, not '{}'",
+                    dep_type
+                ),
+            )
+            .into());
         }
     }
 
-    Ok(json!({
+    // All edges go in one transaction; any error rolls the batch back
+    let result = db.link_many(&edges, &dep_type, lag_ms, any_of.as_deref(), deps_config)?;
+    // Created edges without a named group take the type's default one
+    let default_any_of = deps_config.default_any_of(&dep_type);
+
+    let edge_json = |(from_id, to_id): &(String, String), group: Option<&str>| {
+        let mut entry = json!({
+            "from": from_id,
+            "to": to_id,
+            "type": &dep_type
+        });
+        if lag_ms.is_some() {
+            entry["lag_ms"] = json!(lag_ms);
+        }
+        if group.is_some() {
+            entry["any_of"] = json!(group);
+        }
+        entry
+    };
+    let created: Vec<Value> = result
+        .created
+        .iter()
+        .map(|edge| edge_json(edge, any_of.as_deref().or(default_any_of)))
+        .collect();
+    let updated: Vec<Value> = result
+        .updated
+        .iter()
+        .map(|edge| edge_json(edge, any_of.as_deref()))
+        .collect();
+
+    let mut warnings: Vec<ToolWarning> = Vec::new();
+    for (from_id, to_id) in &result.existing {
+        warnings.push(ToolWarning::duplicate(&format!(
+            "dependency {} -> {}",
+            from_id, to_id
+        )));
+    }
+    for from_id in &result.missing_from {
+        warnings.push(ToolWarning::task_not_found(from_id).with_field("from"));
+    }
+    for to_id in &result.missing_to {
+        warnings.push(ToolWarning::dependency_not_found(to_id, "to"));
+    }
+    let errors: Vec<Value> = result
+        .errors
+        .iter()
+        .map(|(from_id, to_id, error)| {
+            json!({
+                "from": from_id,
+                "to": to_id,
+                "error": error
+            })
+        })
+        .collect();
+
+    let mut response = json!({
         "success": errors.is_empty(),
         "created": created,
         "warnings": warnings,
         "errors": errors,
         "type": dep_type
-    }))
+    });
+    if !updated.is_empty() {
+        response["updated"] = json!(updated);
+    }
+    Ok(response)
 }
 
 pub fn unlink(db: &Database, args: Value) -> Result<Value> {
@@ -399,3 +558,69 @@ pub fn relink(db: &Database, deps_config: &DependenciesConfig, args: Value) -> R
         })),
     }
 }
+
+pub fn sequence(db: &Database, deps_config: &DependenciesConfig, args: Value) -> Result<Value> {
+    // Agent parameter is optional - for tracking/audit purposes
+    let _agent_id = get_string(&args, "agent");
+
+    let order =
+        get_string_or_array(&args, "tasks").ok_or_else(|| ToolError::missing_field("tasks"))?;
+    let parent = match get_string(&args, "parent") {
+        Some(parent) => parent,
+        None => {
+            let first = order.first().ok_or_else(|| {
+                ToolError::invalid_value("parent", "parent is required to clear a sequence")
+            })?;
+            db.get_parent(first)?.ok_or_else(|| {
+                ToolError::invalid_value(
+                    "tasks",
+                    &format!(
+                        "Task {} has no parent; only siblings can be sequenced",
+                        first
+                    ),
+                )
+            })?
+        }
+    };
+
+    let result = db.set_sequence(&parent, &order, deps_config)?;
+    let edges = |edges: &[(String, String)]| -> Vec<Value> {
+        edges
+            .iter()
+            .map(|(from, to)| json!({"from": from, "to": to}))
+            .collect()
+    };
+
+    Ok(json!({
+        "parent": result.parent,
+        "sequence": db.get_sequence(&parent)?,
+        "linked": edges(&result.linked),
+        "unlinked": edges(&result.unlinked)
+    }))
+}
+
+pub fn export_matrix(
+    db: &Database,
+    deps_config: &DependenciesConfig,
+    visibility: &VisibilityConfig,
+    args: Value,
+) -> Result<ToolResult> {
+    let task_id = get_string(&args, "task");
+    let scope = db.caller_scope(get_string(&args, "worker_id").as_deref(), visibility)?;
+    let scope = scope.as_ref();
+    let layout = match get_string(&args, "layout") {
+        Some(layout) => MatrixLayout::parse(&layout).ok_or_else(|| {
+            ToolError::invalid_value("layout", "layout must be 'matrix' or 'edges'")
+        })?,
+        None => MatrixLayout::default(),
+    };
+    if let Some(ref id) = task_id {
+        if db.get_task(id)?.is_none() {
+            return Err(ToolError::task_not_found(id).into());
+        }
+        require_visible(db, id, scope)?;
+    }
+
+    let matrix = db.dependency_matrix(task_id.as_deref(), deps_config, scope)?;
+    Ok(ToolResult::Raw(render_matrix_csv(&matrix, layout)))
+}
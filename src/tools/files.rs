@@ -1,7 +1,10 @@
 //! File coordination tools (advisory marks and exclusive locks).
 //!
 //! Supports two modes:
-//! - **Advisory marks** (default): warns if another agent holds a file mark.
+//! - **Advisory marks** (default): rejects with `MARK_CONFLICT` if another agent holds an
+//!   overlapping mark (same file, or an enclosing/enclosed directory). With `wait=true`
+//!   the free files are marked and the conflicting ones are reported by `mark_updates`
+//!   once released.
 //! - **Exclusive locks** (`lock:` prefix): rejects with error if another agent holds the lock.
 //!
 //! The `lock:` namespace uses the same `file_locks` table but enforces mutual exclusion.
@@ -9,14 +12,14 @@
 //! resource "git-commit". Another agent attempting the same lock will receive an error.
 
 use super::{
-    IdList, get_string, get_string_or_array, get_string_or_array_or_wildcard,
-    make_tool_with_prompts,
+    IdList, get_bool, get_i64, get_project, get_string, get_string_or_array,
+    get_string_or_array_or_wildcard, make_tool_with_prompts,
 };
 use crate::config::Prompts;
 use crate::db::Database;
 use crate::db::locks::ExclusiveLockResult;
-use crate::error::ToolError;
-use crate::format::{OutputFormat, markdown_to_json};
+use crate::error::{MarkConflict, ToolError};
+use crate::format::{OutputFormat, compact_to_json, format_compact_rows, markdown_to_json};
 use anyhow::Result;
 use rmcp::model::Tool;
 use serde_json::{Value, json};
@@ -25,6 +28,9 @@ use std::path::{Component, Path, PathBuf};
 /// The prefix that triggers exclusive lock semantics.
 const LOCK_PREFIX: &str = "lock:";
 
+/// Leased marks expiring within this window are reported by `mark_updates`.
+const MARK_EXPIRY_WARNING_MS: i64 = 60_000;
+
 /// Normalize a file path to an absolute, canonical form.
 ///
 /// This function:
@@ -37,7 +43,7 @@ const LOCK_PREFIX: &str = "lock:";
 /// - `src/main.rs` -> `/project/src/main.rs`
 /// - `./src/../src/main.rs` -> `/project/src/main.rs`
 /// - `/absolute/path.rs` -> `/absolute/path.rs`
-fn normalize_file_path(path: &str) -> String {
+pub(crate) fn normalize_file_path(path: &str) -> String {
     let path = Path::new(path);
 
     // Get absolute path
@@ -134,7 +140,7 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
     vec![
         make_tool_with_prompts(
             "mark_file",
-            "Mark a file to signal intent to work on it (advisory, non-blocking). Returns warning if another agent has marked the file. Track changes via mark_updates.\n\nUse the `lock:` prefix for exclusive locks: `lock:resource-name` will reject (not just warn) if another agent holds the lock. Example: `mark_file(file=\"lock:git-commit\")` acquires a mutual-exclusion lock on the resource \"git-commit\".",
+            "Mark a file to signal intent to work on it (advisory). Fails with MARK_CONFLICT, naming the holder, their task, and the mark age, if another agent has marked the same file or an enclosing/enclosed directory; pass wait=true to mark the free files and be told via mark_updates when the conflicting marks are released. Track changes via mark_updates.\n\nUse the `lock:` prefix for exclusive locks: `lock:resource-name` will reject (not just warn) if another agent holds the lock. Example: `mark_file(file=\"lock:git-commit\")` acquires a mutual-exclusion lock on the resource \"git-commit\".",
             json!({
                 "agent": {
                     "type": "string",
@@ -154,6 +160,14 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
                 "reason": {
                     "type": "string",
                     "description": "Optional reason for marking (visible to other agents)"
+                },
+                "ttl_ms": {
+                    "type": "integer",
+                    "description": "Lease duration in milliseconds; the mark is released automatically once it expires. Re-mark to renew. Omit for a mark that never expires."
+                },
+                "wait": {
+                    "type": "boolean",
+                    "description": "On conflict, mark the free files and wait for the conflicting ones instead of failing; released files are listed under 'available' in mark_updates (default: false)"
                 }
             }),
             vec!["agent", "file"],
@@ -188,7 +202,7 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
         ),
         make_tool_with_prompts(
             "list_marks",
-            "Get current file marks. Requires at least one filter: agent, task, or files.",
+            "Get current file marks. Requires at least one filter: agent, task, files, or project.",
             json!({
                 "files": {
                     "type": "array",
@@ -202,14 +216,48 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
                 "task": {
                     "type": "string",
                     "description": "Filter by task ID"
+                },
+                "project": {
+                    "type": "string",
+                    "description": "Filter by the project of the marking agent"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["json", "markdown", "compact"],
+                    "description": "Output format (default: server setting). compact: a header of short keys, then one tab-separated row per mark (file, own, task, age_ms)"
                 }
             }),
             vec![],
             prompts,
         ),
+        make_tool_with_prompts(
+            "mark_history",
+            "Show recent mark and release events for a path from the claim audit trail, newest first, with a per-agent summary. Directory paths include events on files inside them (and enclosing directories). Use before planning work in an area to see who has been touching it.",
+            json!({
+                "file": {
+                    "type": "string",
+                    "description": "File or directory path (or lock:resource) to inspect"
+                },
+                "agent": {
+                    "type": "string",
+                    "description": "Only include events by this agent"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum events to return (default: 50)"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["json", "markdown"],
+                    "description": "Output format (default: server setting)"
+                }
+            }),
+            vec!["file"],
+            prompts,
+        ),
         make_tool_with_prompts(
             "mark_updates",
-            "Poll for file mark changes since last call. Returns new marks and removals. Use for coordination between agents.",
+            "Poll for file mark changes since last call. Returns new marks and removals (including expired leases), 'available': files this agent waited on (mark_file wait=true) that are no longer marked by others, and 'expiring': this agent's leased marks that expire within a minute. Use for coordination between agents.",
             json!({
                 "agent": {
                     "type": "string",
@@ -228,6 +276,14 @@ pub fn mark_file(db: &Database, args: Value) -> Result<Value> {
         get_string_or_array(&args, "file").ok_or_else(|| ToolError::missing_field("file"))?;
     let task_id = get_string(&args, "task");
     let reason = get_string(&args, "reason");
+    let wait = get_bool(&args, "wait").unwrap_or(false);
+    let ttl_ms = get_i64(&args, "ttl_ms");
+    if ttl_ms.is_some_and(|t| t <= 0) {
+        return Err(ToolError::invalid_value("ttl_ms", "ttl_ms must be a positive integer").into());
+    }
+
+    // Release expired leases first so they neither conflict nor linger
+    db.reap_expired_marks()?;
 
     // Separate lock: prefixed paths from regular file paths
     let mut lock_paths: Vec<String> = Vec::new();
@@ -245,6 +301,30 @@ pub fn mark_file(db: &Database, args: Value) -> Result<Value> {
     // Normalize regular file paths to absolute canonical form
     let normalized_regular = normalize_file_paths(regular_paths);
 
+    // Detect overlapping advisory marks held by other agents before marking anything
+    let now = crate::db::now_ms();
+    let expires_at = ttl_ms.map(|t| now + t);
+    let conflicts: Vec<MarkConflict> = db
+        .find_mark_conflicts(&normalized_regular, &worker_id)?
+        .into_iter()
+        .map(|(file, mark)| MarkConflict {
+            file,
+            marked_path: mark.file_path,
+            held_by: mark.worker_id,
+            task_id: mark.task_id,
+            mark_age_ms: now - mark.locked_at,
+        })
+        .collect();
+    if !conflicts.is_empty() && !wait {
+        return Err(ToolError::mark_conflict(conflicts).into());
+    }
+    let mut waiting: Vec<String> = conflicts.iter().map(|c| c.file.clone()).collect();
+    waiting.dedup();
+    let normalized_regular: Vec<String> = normalized_regular
+        .into_iter()
+        .filter(|p| !waiting.contains(p))
+        .collect();
+
     let mut results = Vec::new();
     let mut warnings = Vec::new();
     let mut locks_acquired = Vec::new();
@@ -263,10 +343,8 @@ pub fn mark_file(db: &Database, args: Value) -> Result<Value> {
                 // Exclusive lock conflict - return error immediately
                 return Err(ToolError::lock_conflict(lock_path, &other_agent).into());
             }
-            ExclusiveLockResult::Acquired => {
-                locks_acquired.push(lock_path.clone());
-            }
-            ExclusiveLockResult::AlreadyHeldBySelf => {
+            ExclusiveLockResult::Acquired | ExclusiveLockResult::AlreadyHeldBySelf => {
+                db.set_mark_expiry(lock_path, &worker_id, expires_at)?;
                 locks_acquired.push(lock_path.clone());
             }
         }
@@ -286,6 +364,8 @@ pub fn mark_file(db: &Database, args: Value) -> Result<Value> {
                 "file": file_path,
                 "marked_by": other_agent
             }));
+        } else {
+            db.set_mark_expiry(file_path, &worker_id, expires_at)?;
         }
         results.push(file_path.clone());
     }
@@ -299,10 +379,20 @@ pub fn mark_file(db: &Database, args: Value) -> Result<Value> {
         response["locks_acquired"] = json!(locks_acquired);
     }
 
+    if let Some(expires_at) = expires_at {
+        response["expires_at"] = json!(expires_at);
+    }
+
     if !warnings.is_empty() {
         response["warnings"] = json!(warnings);
     }
 
+    if !waiting.is_empty() {
+        db.add_mark_waiters(&worker_id, &waiting)?;
+        response["conflicts"] = json!(conflicts);
+        response["waiting"] = json!(waiting);
+    }
+
     Ok(response)
 }
 
@@ -372,15 +462,16 @@ pub fn list_marks(db: &Database, default_format: OutputFormat, args: Value) -> R
     let files = get_string_or_array(&args, "files");
     let worker_id = get_string(&args, "agent");
     let task_id = get_string(&args, "task");
+    let project = get_project(&args)?;
     let format = get_string(&args, "format")
         .and_then(|s| OutputFormat::parse(&s))
         .unwrap_or(default_format);
 
     // Require at least one filter
-    if files.is_none() && worker_id.is_none() && task_id.is_none() {
+    if files.is_none() && worker_id.is_none() && task_id.is_none() && project.is_none() {
         return Err(ToolError::invalid_value(
             "filter",
-            "At least one filter required: agent, task, or files",
+            "At least one filter required: agent, task, files, or project",
         )
         .into());
     }
@@ -399,17 +490,43 @@ pub fn list_marks(db: &Database, default_format: OutputFormat, args: Value) -> R
             .collect()
     });
 
-    let marks = db.get_file_locks(normalized_files, worker_id.as_deref(), task_id.as_deref())?;
+    db.reap_expired_marks()?;
+    let mut marks = match project {
+        Some(ref p) if normalized_files.is_none() && worker_id.is_none() && task_id.is_none() => {
+            db.get_project_file_locks(p)?
+        }
+        _ => db.get_file_locks(normalized_files, worker_id.as_deref(), task_id.as_deref())?,
+    };
+    if let Some(ref p) = project {
+        marks.retain(|_, mark| mark.project == *p);
+    }
     let now = crate::db::now_ms();
 
     match format {
+        OutputFormat::Compact => {
+            let rows: Vec<Vec<String>> = marks
+                .iter()
+                .map(|(path, mark)| {
+                    vec![
+                        path.clone(),
+                        mark.worker_id.clone(),
+                        mark.task_id.clone().unwrap_or_default(),
+                        (now - mark.locked_at).to_string(),
+                    ]
+                })
+                .collect();
+            Ok(compact_to_json(format_compact_rows(
+                &["file", "own", "task", "age_ms"],
+                &rows,
+            )))
+        }
         OutputFormat::Markdown => {
             let mut md = String::from("# File Marks\n\n");
             if marks.is_empty() {
                 md.push_str("No marks found.\n");
             } else {
-                md.push_str("| File | Type | Agent | Task | Reason | Age |\n");
-                md.push_str("|------|------|-------|------|--------|-----|\n");
+                md.push_str("| File | Type | Agent | Task | Reason | Age | Expires |\n");
+                md.push_str("|------|------|-------|------|--------|-----|---------|\n");
                 for (path, mark) in &marks {
                     let age_ms = now - mark.locked_at;
                     let age_str = format_duration(age_ms);
@@ -418,14 +535,19 @@ pub fn list_marks(db: &Database, default_format: OutputFormat, args: Value) -> R
                     } else {
                         "advisory"
                     };
+                    let expires_str = mark
+                        .expires_at
+                        .map(|t| format!("in {}", format_duration((t - now).max(0))))
+                        .unwrap_or_else(|| "-".to_string());
                     md.push_str(&format!(
-                        "| {} | {} | {} | {} | {} | {} |\n",
+                        "| {} | {} | {} | {} | {} | {} | {} |\n",
                         path,
                         lock_type,
                         mark.worker_id,
                         mark.task_id.as_deref().unwrap_or("-"),
                         mark.reason.as_deref().unwrap_or("-"),
-                        age_str
+                        age_str,
+                        expires_str
                     ));
                 }
             }
@@ -444,7 +566,8 @@ pub fn list_marks(db: &Database, default_format: OutputFormat, args: Value) -> R
                         "task_id": mark.task_id,
                         "reason": mark.reason,
                         "marked_at": mark.locked_at,
-                        "mark_age_ms": age_ms
+                        "mark_age_ms": age_ms,
+                        "expires_at": mark.expires_at
                     })
                 })
                 .collect();
@@ -454,37 +577,130 @@ pub fn list_marks(db: &Database, default_format: OutputFormat, args: Value) -> R
     }
 }
 
+/// Default number of events returned by `mark_history`.
+const DEFAULT_HISTORY_LIMIT: i64 = 50;
+
+pub fn mark_history(db: &Database, default_format: OutputFormat, args: Value) -> Result<Value> {
+    let file = get_string(&args, "file").ok_or_else(|| ToolError::missing_field("file"))?;
+    let worker_id = get_string(&args, "agent");
+    let limit = get_i64(&args, "limit").unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let format = get_string(&args, "format")
+        .and_then(|s| OutputFormat::parse(&s))
+        .unwrap_or(default_format);
+
+    if limit <= 0 {
+        return Err(ToolError::invalid_value("limit", "must be a positive number").into());
+    }
+
+    let path = if file.starts_with(LOCK_PREFIX) {
+        file
+    } else {
+        normalize_file_path(&file)
+    };
+
+    db.reap_expired_marks()?;
+    let events = db.get_claim_history(Some(&path), worker_id.as_deref(), limit as usize)?;
+    let agents = summarize_history_agents(&events);
+    let now = crate::db::now_ms();
+
+    match format {
+        OutputFormat::Markdown => {
+            let mut md = format!("# Mark History: {}\n\n", path);
+            if events.is_empty() {
+                md.push_str("No mark activity recorded.\n");
+            } else {
+                md.push_str("## Agents\n\n");
+                for a in &agents {
+                    md.push_str(&format!(
+                        "- **{}**: {} events, last {} ago\n",
+                        a["agent"].as_str().unwrap_or("-"),
+                        a["events"],
+                        format_duration(now - a["last_seen"].as_i64().unwrap_or(now))
+                    ));
+                }
+                md.push_str("\n## Events\n\n");
+                md.push_str("| File | Event | Agent | Reason | When |\n");
+                md.push_str("|------|-------|-------|--------|------|\n");
+                for e in &events {
+                    md.push_str(&format!(
+                        "| {} | {} | {} | {} | {} ago |\n",
+                        e.file_path,
+                        e.event.as_str(),
+                        e.worker_id,
+                        e.reason.as_deref().unwrap_or("-"),
+                        format_duration(now - e.timestamp)
+                    ));
+                }
+            }
+            Ok(markdown_to_json(md))
+        }
+        OutputFormat::Json | OutputFormat::Compact => Ok(json!({
+            "file": path,
+            "agents": agents,
+            "events": events.iter().map(claim_event_json).collect::<Vec<_>>()
+        })),
+    }
+}
+
+/// JSON view of a claim audit event, including how long a claim was (or has been) held.
+pub(crate) fn claim_event_json(e: &crate::types::ClaimEvent) -> Value {
+    let held_ms = match e.event {
+        crate::types::ClaimEventType::Claimed => {
+            Some(e.end_timestamp.unwrap_or_else(crate::db::now_ms) - e.timestamp)
+        }
+        crate::types::ClaimEventType::Released => None,
+    };
+    json!({
+        "id": e.id,
+        "file": e.file_path,
+        "agent": e.worker_id,
+        "event": e.event.as_str(),
+        "reason": e.reason,
+        "timestamp": e.timestamp,
+        "end_timestamp": e.end_timestamp,
+        "held_ms": held_ms
+    })
+}
+
+/// Per-agent event counts for a history, most recently active agent first.
+pub(crate) fn summarize_history_agents(events: &[crate::types::ClaimEvent]) -> Vec<Value> {
+    let mut agents: Vec<(String, usize, i64)> = Vec::new();
+    for e in events {
+        match agents.iter_mut().find(|(a, _, _)| *a == e.worker_id) {
+            Some(entry) => {
+                entry.1 += 1;
+                entry.2 = entry.2.max(e.timestamp);
+            }
+            None => agents.push((e.worker_id.clone(), 1, e.timestamp)),
+        }
+    }
+    agents.sort_by_key(|a| std::cmp::Reverse(a.2));
+    agents
+        .into_iter()
+        .map(|(agent, count, last_seen)| {
+            json!({ "agent": agent, "events": count, "last_seen": last_seen })
+        })
+        .collect()
+}
+
 /// Async version of mark_updates.
 pub async fn mark_updates_async(db: std::sync::Arc<Database>, args: Value) -> Result<Value> {
-    let worker_id = get_string(&args, "agent").ok_or_else(|| ToolError::missing_field("agent"))?;
-
     // Run on blocking thread pool since db operations are synchronous
-    let updates = tokio::task::spawn_blocking(move || db.claim_updates(&worker_id))
+    tokio::task::spawn_blocking(move || mark_updates(&db, args))
         .await
-        .map_err(|e| anyhow::anyhow!("Task join error: {}", e))??;
-
-    Ok(json!({
-        "new_marks": updates.new_claims.iter().map(|e| json!({
-            "file": e.file_path,
-            "agent": e.worker_id,
-            "reason": e.reason,
-            "marked_at": e.timestamp
-        })).collect::<Vec<_>>(),
-        "removed_marks": updates.dropped_claims.iter().map(|e| json!({
-            "file": e.file_path,
-            "agent": e.worker_id,
-            "reason": e.reason,
-            "removed_at": e.timestamp
-        })).collect::<Vec<_>>(),
-        "sequence": updates.sequence
-    }))
+        .map_err(|e| anyhow::anyhow!("Task join error: {}", e))?
 }
 
 /// Synchronous version of mark_updates.
 pub fn mark_updates(db: &Database, args: Value) -> Result<Value> {
     let worker_id = get_string(&args, "agent").ok_or_else(|| ToolError::missing_field("agent"))?;
 
+    // Expired leases show up as removals in this poll
+    db.reap_expired_marks()?;
     let updates = db.claim_updates(&worker_id)?;
+    let available = db.take_available_mark_waits(&worker_id)?;
+    let expiring = db.get_expiring_marks(&worker_id, MARK_EXPIRY_WARNING_MS)?;
+    let now = crate::db::now_ms();
 
     Ok(json!({
         "new_marks": updates.new_claims.iter().map(|e| json!({
@@ -499,6 +715,12 @@ pub fn mark_updates(db: &Database, args: Value) -> Result<Value> {
             "reason": e.reason,
             "removed_at": e.timestamp
         })).collect::<Vec<_>>(),
+        "available": available,
+        "expiring": expiring.iter().map(|m| json!({
+            "file": m.file_path,
+            "expires_at": m.expires_at,
+            "expires_in_ms": m.expires_at.map(|t| (t - now).max(0))
+        })).collect::<Vec<_>>(),
         "sequence": updates.sequence
     }))
 }
@@ -7,7 +7,7 @@ use crate::config::Prompts;
 use crate::config::workflows::WorkflowsConfig;
 use crate::db::Database;
 use crate::error::ToolError;
-use crate::gates::{GateResult, evaluate_gates};
+use crate::gates::{CommandGates, GateResult, evaluate_gates};
 use anyhow::Result;
 use rmcp::model::Tool;
 use serde_json::{Value, json};
@@ -16,7 +16,7 @@ use serde_json::{Value, json};
 pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
     vec![make_tool_with_prompts(
         "check_gates",
-        "Check gate requirements for a task before attempting a status/phase transition. Returns unsatisfied gates with overall status (pass/warn/fail).",
+        "Check gate requirements for a task before attempting a status/phase transition. Returns unsatisfied gates with overall status (pass/warn/fail). Attachment gates need an attachment of the gate's type; predicate gates (marked predicate: true) run a configured SQL check against the task; command gates (marked command: true) are not run here: they report how the command's last run (on a transition attempt) ended in detail, or that it has not run yet.",
         json!({
             "task": {
                 "type": "string",
@@ -74,7 +74,8 @@ pub fn check_gates(db: &Database, workflows: &WorkflowsConfig, args: Value) -> R
     }
 
     // Evaluate gates
-    let result = evaluate_gates(db, &task_id, &all_gates)?;
+    // Command gates report their last run; only transitions run them
+    let result = evaluate_gates(db, &task_id, &all_gates, CommandGates::LastRun)?;
 
     // Build response in the required format
     let gates: Vec<Value> = result
@@ -91,12 +92,22 @@ pub fn check_gates(db: &Database, workflows: &WorkflowsConfig, args: Value) -> R
 
 /// Convert a GateResult to the response JSON format.
 fn gate_result_to_json(gate: &GateResult) -> Value {
-    json!({
+    let mut entry = json!({
         "type": gate.gate_type,
         "enforcement": gate.enforcement,
         "description": gate.description,
         "satisfied": gate.satisfied
-    })
+    });
+    if gate.predicate {
+        entry["predicate"] = json!(true);
+    }
+    if gate.command {
+        entry["command"] = json!(true);
+    }
+    if let Some(ref detail) = gate.detail {
+        entry["detail"] = json!(detail);
+    }
+    entry
 }
 
 #[cfg(test)]
@@ -112,6 +123,9 @@ mod tests {
             enforcement: GateEnforcement::Reject,
             description: "Attach test results".to_string(),
             satisfied: false,
+            predicate: false,
+            command: false,
+            detail: None,
         };
 
         let json = gate_result_to_json(&gate);
@@ -0,0 +1,85 @@
+//! Tool call log query tool.
+
+use super::tracking::parse_timestamp;
+use super::{get_i32, get_string, make_tool_with_prompts};
+use crate::config::Prompts;
+use crate::logging::calls::{CallLogQuery, read_call_log};
+use anyhow::Result;
+use rmcp::model::Tool;
+use serde_json::{Value, json};
+use std::path::Path;
+
+pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
+    vec![make_tool_with_prompts(
+        "get_logs",
+        "Read the server's tool call log, newest first. Each entry records the calling agent, tool, duration, response size, outcome (ok or error code), and error message. Covers every tool call, read-only ones included; use it to see why another agent's calls are failing.",
+        json!({
+            "agent": {
+                "type": "string",
+                "description": "Only calls made by this agent"
+            },
+            "tool": {
+                "type": "string",
+                "description": "Only calls to this tool (e.g., 'claim')"
+            },
+            "outcome": {
+                "type": "string",
+                "description": "'ok', 'error' (any failure), or a specific error code (e.g., 'TASK_NOT_FOUND')"
+            },
+            "since": {
+                "type": "string",
+                "description": "Start of time range (ISO 8601 datetime or milliseconds since epoch)"
+            },
+            "until": {
+                "type": "string",
+                "description": "End of time range (ISO 8601 datetime or milliseconds since epoch)"
+            },
+            "limit": {
+                "type": "integer",
+                "description": "Maximum number of entries to return (default: 100, max: 1000)"
+            },
+            "offset": {
+                "type": "integer",
+                "description": "Number of entries to skip for pagination (default: 0)"
+            }
+        }),
+        vec![],
+        prompts,
+    )]
+}
+
+pub fn get_logs(log_dir: &Path, enabled: bool, args: Value) -> Result<Value> {
+    let agent = get_string(&args, "agent");
+    let tool = get_string(&args, "tool");
+    let outcome = get_string(&args, "outcome");
+    let since = get_string(&args, "since").and_then(|s| parse_timestamp(&s));
+    let until = get_string(&args, "until").and_then(|s| parse_timestamp(&s));
+    let limit = get_i32(&args, "limit").unwrap_or(100).clamp(1, 1000) as usize;
+    let offset = get_i32(&args, "offset").unwrap_or(0).max(0) as usize;
+
+    // Fetch limit+1 to detect if there are more entries
+    let mut entries = read_call_log(
+        log_dir,
+        &CallLogQuery {
+            worker_id: agent.as_deref(),
+            tool: tool.as_deref(),
+            outcome: outcome.as_deref(),
+            since,
+            until,
+            limit: Some(limit + 1),
+            offset,
+        },
+    )?;
+
+    let has_more = entries.len() > limit;
+    entries.truncate(limit);
+
+    Ok(json!({
+        "enabled": enabled,
+        "entry_count": entries.len(),
+        "has_more": has_more,
+        "offset": offset,
+        "limit": limit,
+        "entries": entries
+    }))
+}
@@ -2,31 +2,147 @@
 
 pub mod agents;
 pub mod attachments;
+pub mod audit;
 pub mod claiming;
 pub mod context;
 pub mod deps;
 pub mod feedback;
 pub mod files;
 pub mod gates;
+pub mod logs;
 pub mod query;
 pub mod schema;
 pub mod search;
 pub mod skills;
+pub mod source;
 pub mod tasks;
 pub mod tracking;
+pub mod trash;
 pub mod workflows;
 
 pub use context::ToolContext;
 
 use crate::config::{AppConfig, Prompts, ServerPaths, workflows::WorkflowsConfig};
 use crate::db::Database;
+use crate::db::tasks::TaskIdMatch;
 use crate::error::ToolError;
 use crate::format::{OutputFormat, ToolResult};
+use crate::logging::calls::{ToolCallLog, ToolCallRecord};
+use crate::types::TaskScope;
 use anyhow::Result;
 use rmcp::model::Tool;
 use serde_json::Value;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
+
+/// Arguments of each tool that carry task IDs (single string or array of
+/// strings). Only these are resolved from aliases and prefixes, so arguments
+/// that merely share a name (e.g. `project_history`'s timestamp `from`/`to`)
+/// are passed through untouched.
+fn task_id_args(tool: &str) -> &'static [&'static str] {
+    match tool {
+        "link" => &["from", "to", "chain"],
+        "unlink" => &["from", "to"],
+        "relink" => &["from", "to", "prev_from", "prev_to"],
+        "sequence" => &["parent", "tasks"],
+        "claim" | "clone_tree" => &["task", "parent"],
+        "claim_many" | "thinking" => &["tasks"],
+        "give_feedback" => &["task_id"],
+        "create" | "create_tree" | "list_tasks" | "scan_source" => &["parent"],
+        "list_agents" | "audit" | "reserve" | "wait_for" | "export_matrix" | "mark_file"
+        | "unmark_file" | "list_marks" | "attach" | "attach_begin" | "attachments"
+        | "get_attachment" | "prune_attachments" | "detach" | "check_gates" | "export_markdown"
+        | "split" | "get" | "update" | "delete" | "rename" | "scan" | "task_history"
+        | "log_metrics" | "get_metrics" | "get_thoughts" | "restore" => &["task"],
+        _ => &[],
+    }
+}
+
+/// Tools that never change the database, and so are left out of the audit log.
+/// Skill tools are read-only too (see `skills::is_skill_tool`).
+const READ_ONLY_TOOLS: &[&str] = &[
+    "get",
+    "export_markdown",
+    "export_matrix",
+    "list_tasks",
+    "list_agents",
+    "list_marks",
+    "mark_history",
+    "mark_updates",
+    "attachments",
+    "get_attachment",
+    "get_schema",
+    "search",
+    "query",
+    "check_gates",
+    "task_history",
+    "get_metrics",
+    "project_history",
+    "get_thoughts",
+    "audit",
+    "get_logs",
+    "list_workflows",
+    "give_feedback",
+    "list_feedback",
+    "wait_for",
+    "suggest_next_task",
+    "list_deleted",
+];
+
+/// Whether calls to a tool are recorded in the audit log.
+fn is_audited(name: &str) -> bool {
+    !READ_ONLY_TOOLS.contains(&name) && !skills::is_skill_tool(name)
+}
+
+/// Task IDs a tool response reports as created or renamed.
+fn response_task_ids(response: &Value) -> Vec<String> {
+    let mut ids = Vec::new();
+    for key in ["id", "new_id"] {
+        if let Some(id) = response.get(key).and_then(|v| v.as_str()) {
+            ids.push(id.to_string());
+        }
+    }
+    match response.get("root") {
+        Some(Value::String(id)) => ids.push(id.clone()),
+        Some(root) => ids.extend(get_string(root, "id")),
+        None => {}
+    }
+    for key in ["all_ids", "children"] {
+        if let Some(Value::Array(items)) = response.get(key) {
+            ids.extend(items.iter().filter_map(|item| {
+                item.as_str()
+                    .map(String::from)
+                    .or_else(|| get_string(item, "id"))
+            }));
+        }
+    }
+    ids
+}
+
+/// `ok` or the error code of a call's result, with the error message.
+fn call_outcome(result: &Result<ToolResult>) -> (String, Option<String>) {
+    match result {
+        Ok(_) => ("ok".to_string(), None),
+        Err(e) => match e.downcast_ref::<ToolError>() {
+            Some(err) => (
+                serde_json::to_value(err.code)
+                    .ok()
+                    .and_then(|v| v.as_str().map(String::from))
+                    .unwrap_or_else(|| "INTERNAL_ERROR".to_string()),
+                Some(err.message.clone()),
+            ),
+            None => ("INTERNAL_ERROR".to_string(), Some(e.to_string())),
+        },
+    }
+}
+
+/// Task ID arguments rewritten before dispatch, as (given, resolved) pairs.
+#[derive(Default)]
+struct ResolvedTaskIds {
+    aliases: Vec<(String, String)>,
+    prefixes: Vec<(String, String)>,
+}
 
 /// Tool handler that processes MCP tool calls.
 pub struct ToolHandler {
@@ -40,6 +156,8 @@ pub struct ToolHandler {
     pub default_format: OutputFormat,
     pub default_page_size: i32,
     pub path_mapper: Arc<crate::paths::PathMapper>,
+    /// Structured log of every tool call, if enabled.
+    pub call_log: Option<Arc<ToolCallLog>>,
 }
 
 impl ToolHandler {
@@ -65,9 +183,16 @@ impl ToolHandler {
             default_format,
             default_page_size,
             path_mapper,
+            call_log: None,
         }
     }
 
+    /// Write every tool call to the given call log.
+    pub fn with_call_log(mut self, call_log: Arc<ToolCallLog>) -> Self {
+        self.call_log = Some(call_log);
+        self
+    }
+
     /// Get the workflow config for a worker.
     /// Looks up the worker's workflow name and returns the corresponding config,
     /// or falls back to the configured default workflow, or the base config.
@@ -120,19 +245,53 @@ impl ToolHandler {
         }
     }
 
-    /// Get all available tools.
+    /// Get all available tools, less those switched off by the `tools` config,
+    /// under their exposed (prefixed) names.
     pub fn get_tools(&self) -> Vec<Tool> {
+        let mut tools = self.all_tools();
+        tools.retain(|tool| self.config.tools.is_enabled(&tool.name));
+        if !self.config.tools.prefix.is_empty() {
+            for tool in &mut tools {
+                tool.name = self.config.tools.exposed_name(&tool.name).into();
+            }
+        }
+        tools
+    }
+
+    /// Names of every tool this server implements, whether or not enabled.
+    pub fn tool_names(&self) -> Vec<String> {
+        let mut tools = self.all_tools();
+        if !self.config.feedback.enabled {
+            tools.extend(feedback::get_tools());
+        }
+        tools
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect()
+    }
+
+    fn all_tools(&self) -> Vec<Tool> {
         let mut tools = Vec::new();
 
         // Worker tools
         tools.extend(agents::get_tools(&self.prompts));
 
         // Task tools (with dynamic state schema)
-        tools.extend(tasks::get_tools(&self.prompts, &self.config.states));
+        tools.extend(tasks::get_tools(
+            &self.prompts,
+            &self.config.states,
+            &self.config.priorities,
+        ));
 
         // Tracking tools
         tools.extend(tracking::get_tools(&self.prompts, &self.config.states));
 
+        // Audit log tools
+        tools.extend(audit::get_tools(&self.prompts));
+
+        // Tool call log tools
+        tools.extend(logs::get_tools(&self.prompts));
+
         // Dependency tools
         tools.extend(deps::get_tools(&self.prompts, &self.config.deps));
 
@@ -145,6 +304,12 @@ impl ToolHandler {
         // Attachment tools
         tools.extend(attachments::get_tools(&self.prompts));
 
+        // Soft-deleted task tools
+        tools.extend(trash::get_tools(&self.prompts));
+
+        // Source TODO scanning
+        tools.extend(source::get_tools(&self.prompts));
+
         // Skill tools (no prompts needed, always available)
         tools.extend(skills::get_tools());
 
@@ -172,15 +337,177 @@ impl ToolHandler {
     }
 
     /// Call a tool by name.
-    #[allow(unused_variables)]
+    ///
+    /// Task ID arguments are resolved first: aliases of renamed tasks map to
+    /// the current ID and unambiguous prefixes expand to the full ID. JSON
+    /// responses then carry `resolved_aliases` / `resolved_prefixes` maps so
+    /// the caller can update its references.
+    ///
+    /// Calls to mutating tools are recorded in the audit log, whether they
+    /// succeed or fail. Every call is written to the call log, if one is set.
     pub async fn call_tool(
         &self,
         name: &str,
         arguments: Value,
         ctx: &ToolContext,
+    ) -> Result<ToolResult> {
+        // Exposed names carry the configured prefix; everything below uses the bare name
+        let Some(name) = self.config.tools.internal_name(name) else {
+            return Err(ToolError::unknown_tool(name).into());
+        };
+        let Some(call_log) = &self.call_log else {
+            return self.run_tool(name, arguments, ctx).await;
+        };
+        let started = Instant::now();
+        let worker_id = get_string(&arguments, "worker_id");
+        let result = self.run_tool(name, arguments, ctx).await;
+
+        let (outcome, error) = call_outcome(&result);
+        let record = ToolCallRecord {
+            timestamp: crate::db::now_ms(),
+            worker_id: worker_id.or_else(|| match &result {
+                Ok(ToolResult::Json(v)) => get_string(v, "worker_id"),
+                _ => None,
+            }),
+            tool: name.to_string(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            result_bytes: result.as_ref().map_or(0, ToolResult::byte_len),
+            outcome,
+            error,
+        };
+        if let Err(e) = call_log.write(&record) {
+            tracing::warn!(tool = %name, error = %e, "Failed to write call log");
+        }
+        result
+    }
+
+    async fn run_tool(
+        &self,
+        name: &str,
+        mut arguments: Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult> {
+        if !self.config.tools.is_enabled(name) {
+            return Err(ToolError::unknown_tool(name).into());
+        }
+        let resolved = self.resolve_task_ids(name, &mut arguments)?;
+        let audit_args = is_audited(name).then(|| arguments.clone());
+        let result = self.dispatch_tool(name, arguments, ctx).await;
+        if let Some(args) = audit_args {
+            self.record_audit(name, &args, &result);
+        }
+        let mut result = result?;
+
+        if let ToolResult::Json(Value::Object(ref mut obj)) = result {
+            for (key, pairs) in [
+                ("resolved_aliases", resolved.aliases),
+                ("resolved_prefixes", resolved.prefixes),
+            ] {
+                if !pairs.is_empty() {
+                    obj.insert(
+                        key.to_string(),
+                        Value::Object(
+                            pairs
+                                .into_iter()
+                                .map(|(given, id)| (given, Value::String(id)))
+                                .collect(),
+                        ),
+                    );
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Append an audit log entry for a mutating call. A failed write is logged
+    /// rather than returned, since the call itself has already taken effect.
+    fn record_audit(&self, name: &str, args: &Value, result: &Result<ToolResult>) {
+        let response = match result {
+            Ok(ToolResult::Json(v)) => Some(v),
+            _ => None,
+        };
+        // connect reports the (possibly generated) ID only in its response
+        let worker_id = get_string(args, "worker_id")
+            .or_else(|| response.and_then(|v| get_string(v, "worker_id")));
+
+        let mut task_ids: Vec<String> = Vec::new();
+        for key in task_id_args(name) {
+            let given: Vec<&str> = match args.get(*key) {
+                Some(Value::String(id)) => vec![id.as_str()],
+                Some(Value::Array(items)) => items.iter().filter_map(|v| v.as_str()).collect(),
+                _ => continue,
+            };
+            task_ids.extend(given.into_iter().filter(|id| *id != "*").map(String::from));
+        }
+        if let Some(response) = response {
+            task_ids.extend(response_task_ids(response));
+        }
+        let mut seen = std::collections::HashSet::new();
+        task_ids.retain(|id| seen.insert(id.clone()));
+
+        let (outcome, error) = call_outcome(result);
+
+        let args_hash = crate::blob_store::sha256_hex(args.to_string().as_bytes());
+        if let Err(e) = self.db.record_audit(
+            worker_id.as_deref(),
+            name,
+            &args_hash,
+            &task_ids,
+            &outcome,
+            error.as_deref(),
+        ) {
+            tracing::warn!(tool = %name, error = %e, "Failed to record audit entry");
+        }
+    }
+
+    /// Rewrite the tool's task ID arguments that are aliases or unique prefixes.
+    fn resolve_task_ids(&self, tool: &str, arguments: &mut Value) -> Result<ResolvedTaskIds> {
+        let mut resolved = ResolvedTaskIds::default();
+        let Some(obj) = arguments.as_object_mut() else {
+            return Ok(resolved);
+        };
+
+        for key in task_id_args(tool) {
+            let Some(value) = obj.get_mut(*key) else {
+                continue;
+            };
+            let ids: Vec<&mut Value> = match value {
+                Value::Array(items) => items.iter_mut().collect(),
+                other => vec![other],
+            };
+            for id in ids {
+                let Some(given) = id.as_str().filter(|s| *s != "*") else {
+                    continue;
+                };
+                let given = given.to_string();
+                let current = match self.db.resolve_task_id(&given)? {
+                    TaskIdMatch::Unchanged => continue,
+                    TaskIdMatch::Alias(current) => {
+                        resolved.aliases.push((given, current.clone()));
+                        current
+                    }
+                    TaskIdMatch::Prefix(current) => {
+                        resolved.prefixes.push((given, current.clone()));
+                        current
+                    }
+                };
+                *id = Value::String(current);
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    #[allow(unused_variables)]
+    async fn dispatch_tool(
+        &self,
+        name: &str,
+        arguments: Value,
+        ctx: &ToolContext,
     ) -> Result<ToolResult> {
         // Helper to wrap JSON results
-        let json = |r: Result<Value>| r.map(ToolResult::Json);
+        let json = |r: Result<Value>| r.map(ToolResult::from_value);
 
         match name {
             // Worker tools
@@ -224,33 +551,47 @@ impl ToolHandler {
                         server_paths: &self.server_paths,
                         config: &self.config,
                         workflows: &workflow,
+                        skills_dir: Some(&self.skills_dir),
                     },
                     arguments,
                 ))
             }
             "disconnect" => json(agents::disconnect(&self.db, &self.config.states, arguments)),
-            "list_agents" => agents::list_agents(
+            "list_agents" => {
+                agents::list_agents(&self.db, &self.config, self.default_format, arguments)
+            }
+            "cleanup_stale" => json(agents::cleanup_stale(&self.db, &self.config, arguments)),
+            "add_overlay" => json(agents::add_overlay(&self.db, &self.config, arguments)),
+            "remove_overlay" => json(agents::remove_overlay(&self.db, &self.config, arguments)),
+            "register_group" => json(agents::register_group(&self.db, arguments)),
+
+            // Task tools
+            "create" => json(tasks::create(&self.db, &self.config, arguments)),
+            "create_tree" => json(tasks::create_tree(&self.db, &self.config, arguments)),
+            "clone_tree" => json(tasks::clone_tree(
+                &self.db,
+                &self.config,
+                &self.media_dir,
+                arguments,
+            )),
+            "export_markdown" => tasks::export_markdown(
                 &self.db,
                 &self.config.states,
-                self.default_format,
+                &self.config.visibility,
                 arguments,
             ),
-            "cleanup_stale" => json(agents::cleanup_stale(
+            "split" => json(tasks::split(&self.db, &self.config, arguments)),
+            "get" => json(tasks::get(
                 &self.db,
                 &self.config.states,
+                &self.config.priorities,
+                &self.config.visibility,
+                self.default_format,
                 arguments,
             )),
-            "add_overlay" => json(agents::add_overlay(&self.db, &self.config, arguments)),
-            "remove_overlay" => json(agents::remove_overlay(&self.db, &self.config, arguments)),
-
-            // Task tools
-            "create" => json(tasks::create(&self.db, &self.config, arguments)),
-            "create_tree" => json(tasks::create_tree(&self.db, &self.config, arguments)),
-            "get" => json(tasks::get(&self.db, self.default_format, arguments)),
             "list_tasks" => json(tasks::list_tasks(
                 &self.db,
-                &self.config.states,
-                &self.config.deps,
+                &self.config,
                 self.default_format,
                 arguments,
             )),
@@ -272,10 +613,25 @@ impl ToolHandler {
             }
             "delete" => json(tasks::delete(&self.db, arguments)),
             "rename" => json(tasks::rename(&self.db, arguments)),
-            "scan" => json(tasks::scan(&self.db, self.default_format, arguments)),
+            "scan" => json(tasks::scan(
+                &self.db,
+                &self.config.priorities,
+                &self.config.visibility,
+                self.default_format,
+                arguments,
+            )),
 
             // Tracking tools
-            "thinking" => json(tracking::thinking(&self.db, arguments)),
+            "thinking" => {
+                let agent = get_string(&arguments, "agent").unwrap_or_default();
+                let workflow = self.get_workflow_for_worker(&agent);
+                json(tracking::thinking(
+                    &self.db,
+                    &self.config,
+                    &workflow,
+                    arguments,
+                ))
+            }
             "task_history" => json(tracking::task_history(
                 &self.db,
                 &self.config.states,
@@ -284,16 +640,32 @@ impl ToolHandler {
             )),
             "log_metrics" => json(tracking::log_metrics(&self.db, arguments)),
             "get_metrics" => json(tracking::get_metrics(&self.db, arguments)),
+            "get_thoughts" => json(tracking::get_thoughts(&self.db, arguments)),
             "project_history" => json(tracking::project_history(
                 &self.db,
                 self.default_format,
                 arguments,
             )),
 
+            // Audit log tools
+            "audit" => json(audit::audit(&self.db, arguments)),
+            "get_logs" => json(logs::get_logs(
+                &self.server_paths.log_dir,
+                self.call_log.is_some(),
+                arguments,
+            )),
+
             // Dependency tools
             "link" => json(deps::link(&self.db, &self.config.deps, arguments)),
             "unlink" => json(deps::unlink(&self.db, arguments)),
             "relink" => json(deps::relink(&self.db, &self.config.deps, arguments)),
+            "sequence" => json(deps::sequence(&self.db, &self.config.deps, arguments)),
+            "export_matrix" => deps::export_matrix(
+                &self.db,
+                &self.config.deps,
+                &self.config.visibility,
+                arguments,
+            ),
 
             // Claiming tools
             "claim" => {
@@ -311,10 +683,42 @@ impl ToolHandler {
                 ))
             }
 
+            "reserve" => json(claiming::reserve(&self.db, arguments)),
+
+            "claim_many" => {
+                let worker_id = arguments
+                    .get("worker_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let workflow = self.get_workflow_for_worker(worker_id);
+                json(claiming::claim_many(
+                    &self.db,
+                    &self.config,
+                    &workflow,
+                    arguments,
+                ))
+            }
+
+            "suggest_next_task" => json(claiming::suggest_next_task(
+                &self.db,
+                &self.config,
+                arguments,
+            )),
+
+            "wait_for" => json(
+                claiming::wait_for_async(Arc::clone(&self.db), self.config.clone(), arguments)
+                    .await,
+            ),
+
             // File coordination tools
             "mark_file" => json(files::mark_file(&self.db, arguments)),
             "unmark_file" => json(files::unmark_file(&self.db, arguments)),
             "list_marks" => json(files::list_marks(&self.db, self.default_format, arguments)),
+            "mark_history" => json(files::mark_history(
+                &self.db,
+                self.default_format,
+                arguments,
+            )),
             "mark_updates" => {
                 json(files::mark_updates_async(std::sync::Arc::clone(&self.db), arguments).await)
             }
@@ -329,24 +733,89 @@ impl ToolHandler {
             "attachments" => json(attachments::attachments(
                 &self.db,
                 &self.media_dir,
+                &self.config.attachments,
                 self.default_format,
                 arguments,
             )),
+            "attach_begin" => json(attachments::attach_begin(
+                &self.db,
+                &self.media_dir,
+                &self.config.attachments,
+                arguments,
+            )),
+            "attach_chunk" => json(attachments::attach_chunk(
+                &self.db,
+                &self.media_dir,
+                arguments,
+            )),
+            "attach_commit" => json(attachments::attach_commit(
+                &self.db,
+                &self.media_dir,
+                &self.config.attachments,
+                arguments,
+            )),
+            "get_attachment" => json(attachments::get_attachment(
+                &self.db,
+                &self.media_dir,
+                &self.config.attachments,
+                arguments,
+            )),
+            "prune_attachments" => json(attachments::prune_attachments(
+                &self.db,
+                &self.media_dir,
+                &self.config.attachments,
+                arguments,
+            )),
             "detach" => json(attachments::detach(&self.db, &self.media_dir, arguments)),
+            "gc_blobs" => json(attachments::gc_blobs(
+                &self.db,
+                &self.media_dir,
+                &self.config.attachments,
+                arguments,
+            )),
+
+            // Soft-deleted task tools
+            "list_deleted" => json(trash::list_deleted(&self.db, arguments)),
+            "restore" => json(trash::restore(&self.db, arguments)),
+            "purge" => json(trash::purge(&self.db, &self.media_dir, arguments)),
+            "bulk_delete" => json(trash::bulk_delete(&self.db, &self.config, arguments)),
+            "bulk_archive" => json(trash::bulk_archive(&self.db, &self.config, arguments)),
+
+            // Source TODO scanning
+            "scan_source" => json(source::scan_source(&self.db, &self.config, arguments)),
 
             // Skill tools
             name if skills::is_skill_tool(name) => {
-                json(skills::call_tool(&self.skills_dir, name, &arguments))
+                let worker_id = get_string(&arguments, "worker_id").unwrap_or_default();
+                let workflow = self.get_workflow_for_worker(&worker_id);
+                json(skills::call_tool(
+                    &self.db,
+                    &self.config,
+                    &workflow,
+                    &self.skills_dir,
+                    name,
+                    &arguments,
+                ))
             }
 
             // Schema introspection tools
             "get_schema" => json(schema::get_schema(&self.db, arguments)),
 
             // Search tools
-            "search" => json(search::search(&self.db, self.default_page_size, arguments)),
+            "search" => json(search::search(
+                &self.db,
+                &self.config.visibility,
+                self.default_page_size,
+                arguments,
+            )),
 
             // Query tools (read-only SQL)
-            "query" => query::query(&self.db, self.default_format, arguments),
+            "query" => query::query(
+                &self.db,
+                &self.config.visibility,
+                self.default_format,
+                arguments,
+            ),
 
             // Gate checking tools
             "check_gates" => {
@@ -482,3 +951,34 @@ pub fn get_string_or_array_or_wildcard(args: &Value, key: &str) -> Option<IdList
         Some(IdList::Ids(vals))
     }
 }
+
+/// Helper to get a project name from arguments.
+/// Project names appear in resource URIs, so they are limited to letters,
+/// digits, '-', '_' and '.'.
+pub fn get_project(args: &Value) -> Result<Option<String>> {
+    let Some(project) = get_string(args, "project") else {
+        return Ok(None);
+    };
+    let valid = !project.is_empty()
+        && project
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(ToolError::invalid_value(
+            "project",
+            "Project names must be non-empty and use only letters, digits, '-', '_' and '.'",
+        )
+        .into());
+    }
+    Ok(Some(project))
+}
+
+/// Fail with TASK_NOT_FOUND when a task is outside the caller's visibility
+/// scope, so a restricted agent cannot tell a hidden task from a missing one.
+pub fn require_visible(db: &Database, task_id: &str, scope: Option<&TaskScope>) -> Result<()> {
+    if db.is_visible(task_id, scope)? {
+        Ok(())
+    } else {
+        Err(ToolError::task_not_found(task_id).into())
+    }
+}
@@ -8,6 +8,7 @@
 //! are rejected.
 
 use super::{get_i32, get_string, get_string_array, make_tool};
+use crate::config::VisibilityConfig;
 use crate::db::Database;
 use crate::error::{ErrorCode, ToolError};
 use crate::format::{OutputFormat, ToolResult};
@@ -56,6 +57,10 @@ pub fn get_tools() -> Vec<Tool> {
                 "type": "string",
                 "description": "SQL SELECT query to execute. Only SELECT statements are allowed."
             },
+            "worker_id": {
+                "type": "string",
+                "description": "Calling agent ID. Required when visibility rules are configured; agents they restrict cannot use query."
+            },
             "params": {
                 "type": "array",
                 "items": { "type": "string" },
@@ -90,7 +95,7 @@ pub fn get_tools() -> Vec<Tool> {
 
 /// Validate that a SQL query is read-only (SELECT only).
 #[allow(clippy::result_large_err)]
-fn validate_readonly_sql(sql: &str) -> Result<(), ToolError> {
+pub(crate) fn validate_readonly_sql(sql: &str) -> Result<(), ToolError> {
     // Normalize whitespace and convert to uppercase for checking
     let normalized = sql.trim().to_uppercase();
 
@@ -167,9 +172,22 @@ fn validate_readonly_sql(sql: &str) -> Result<(), ToolError> {
 }
 
 /// Execute a read-only SQL query.
-pub fn query(db: &Database, default_format: OutputFormat, args: Value) -> Result<ToolResult> {
+pub fn query(
+    db: &Database,
+    visibility: &VisibilityConfig,
+    default_format: OutputFormat,
+    args: Value,
+) -> Result<ToolResult> {
     let sql = get_string(&args, "sql").ok_or_else(|| ToolError::missing_field("sql"))?;
 
+    // Raw SQL cannot be held to a visibility scope, so only unrestricted callers get it
+    if db
+        .caller_scope(get_string(&args, "worker_id").as_deref(), visibility)?
+        .is_some()
+    {
+        return Err(ToolError::not_visible("query").into());
+    }
+
     let params = get_string_array(&args, "params").unwrap_or_default();
 
     let limit = get_i32(&args, "limit")
@@ -182,13 +200,14 @@ pub fn query(db: &Database, default_format: OutputFormat, args: Value) -> Result
         .unwrap_or(match default_format {
             OutputFormat::Json => QueryFormat::Json,
             OutputFormat::Markdown => QueryFormat::Markdown,
+            OutputFormat::Compact => QueryFormat::Csv,
         });
 
     // Validate the query is read-only
     validate_readonly_sql(&sql)?;
 
     // Execute the query with timeout
-    let result = db.with_conn(|conn| {
+    let result = db.with_read_conn(|conn| {
         // Set a busy timeout for this connection
         conn.busy_timeout(Duration::from_secs(QUERY_TIMEOUT_SECS))?;
 
@@ -1,7 +1,7 @@
 //! Full-text search tool.
 
 use super::{get_bool, get_i32, get_string, make_tool_with_prompts};
-use crate::config::Prompts;
+use crate::config::{Prompts, VisibilityConfig};
 use crate::db::Database;
 use crate::error::ToolError;
 use anyhow::Result;
@@ -32,6 +32,10 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
             "status_filter": {
                 "type": "string",
                 "description": "Optional status to filter results by (e.g., 'pending', 'working')"
+            },
+            "worker_id": {
+                "type": "string",
+                "description": "Calling agent ID; visibility rules for this agent limit the results"
             }
         }),
         vec!["query"],
@@ -39,7 +43,12 @@ pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
     )]
 }
 
-pub fn search(db: &Database, default_page_size: i32, args: Value) -> Result<Value> {
+pub fn search(
+    db: &Database,
+    visibility: &VisibilityConfig,
+    default_page_size: i32,
+    args: Value,
+) -> Result<Value> {
     let query = get_string(&args, "query").ok_or_else(|| ToolError::missing_field("query"))?;
     let limit = get_i32(&args, "limit")
         .unwrap_or(default_page_size.min(20))
@@ -47,6 +56,7 @@ pub fn search(db: &Database, default_page_size: i32, args: Value) -> Result<Valu
     let offset = get_i32(&args, "offset").unwrap_or(0).max(0);
     let include_attachments = get_bool(&args, "include_attachments").unwrap_or(false);
     let status_filter = get_string(&args, "status_filter");
+    let scope = db.caller_scope(get_string(&args, "worker_id").as_deref(), visibility)?;
 
     // Fetch limit+1 to detect if there are more results
     let fetch_limit = limit + 1;
@@ -56,6 +66,7 @@ pub fn search(db: &Database, default_page_size: i32, args: Value) -> Result<Valu
         offset,
         include_attachments,
         status_filter.as_deref(),
+        scope.as_ref(),
     )?;
 
     let has_more = results.len() > limit as usize;
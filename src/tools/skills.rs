@@ -1,7 +1,18 @@
 //! Skill management tools - list and get skills.
+//!
+//! `get_skill` with a `worker_id` expands the same `{{...}}` template
+//! variables as transition prompts in the skill content, from that agent's
+//! role, tags, and claims. Its most recent claim is the current task.
 
-use super::make_tool;
-use crate::resources::skills::{get_skill_resource, list_skills};
+use super::{get_string, make_tool};
+use crate::config::AppConfig;
+use crate::config::workflows::WorkflowsConfig;
+use crate::db::Database;
+use crate::error::ToolError;
+use crate::prompts::{PromptContext, expand_prompt};
+use crate::resources::skills::{
+    SkillFilter, get_skill_resource, get_skills_by_category, list_skills,
+};
 use anyhow::Result;
 use rmcp::model::Tool;
 use serde_json::{Value, json};
@@ -12,42 +23,153 @@ pub fn get_tools() -> Vec<Tool> {
     vec![
         make_tool(
             "list_skills",
-            "List all available skills. Shows built-in and custom skills with their source information.",
-            json!({}),
+            "List all available skills with their frontmatter metadata (title, description, category, tags, audience) and source information.",
+            json!({
+                "category": {
+                    "type": "string",
+                    "description": "Only skills in this category (e.g., 'foundation', 'repair', or a custom skill's category)"
+                },
+                "tag": {
+                    "type": "string",
+                    "description": "Only skills with this tag"
+                }
+            }),
             vec![],
         ),
         make_tool(
             "get_skill",
-            "Get a skill's full content by name.",
+            "Get a skill's full content by name, or the content of every skill in a category.",
             json!({
                 "name": {
                     "type": "string",
                     "description": "The skill name (e.g., 'basics', 'reporting', 'migration', 'repair')"
+                },
+                "category": {
+                    "type": "string",
+                    "description": "Get all skills in this category instead of one by name"
+                },
+                "worker_id": {
+                    "type": "string",
+                    "description": "Your worker ID; expands {{agent_role}}, {{task_title}}, {{agent_claims}}, and other template variables in the content for you"
                 }
             }),
-            vec!["name"],
+            vec![],
         ),
     ]
 }
 
-/// Handle skill tool calls.
-pub fn call_tool(skills_dir: &Path, name: &str, args: &Value) -> Result<Value> {
+/// Handle skill tool calls. `workflows` is the calling worker's workflow.
+pub fn call_tool(
+    db: &Database,
+    config: &AppConfig,
+    workflows: &WorkflowsConfig,
+    skills_dir: &Path,
+    name: &str,
+    args: &Value,
+) -> Result<Value> {
     match name {
-        "list_skills" => list_skills(Some(skills_dir)),
+        "list_skills" => {
+            let filter = SkillFilter {
+                category: get_string(args, "category"),
+                tag: get_string(args, "tag"),
+            };
+            list_skills(Some(skills_dir), &filter)
+        }
 
         "get_skill" => {
-            let skill_name = args
-                .get("name")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| anyhow::anyhow!("Missing required parameter: name"))?;
-
-            get_skill_resource(Some(skills_dir), skill_name)
+            let mut result = match (get_string(args, "name"), get_string(args, "category")) {
+                (Some(skill_name), _) => get_skill_resource(Some(skills_dir), &skill_name)?,
+                (None, Some(category)) => get_skills_by_category(Some(skills_dir), &category)?,
+                (None, None) => {
+                    return Err(anyhow::anyhow!(
+                        "Missing required parameter: name (or category)"
+                    ));
+                }
+            };
+            if let Some(worker_id) = get_string(args, "worker_id") {
+                expand_for_worker(db, config, workflows, &worker_id, &mut result)?;
+            }
+            Ok(result)
         }
 
         _ => Err(anyhow::anyhow!("Unknown skill tool: {}", name)),
     }
 }
 
+/// Skills configured in `skills.on_connect` for a connecting worker, with
+/// templates expanded for it (or without content, per `include_content`).
+/// Skills that cannot be loaded are returned as warnings rather than failing
+/// the connect.
+pub fn deliver_on_connect(
+    db: &Database,
+    config: &AppConfig,
+    workflows: &WorkflowsConfig,
+    skills_dir: Option<&Path>,
+    worker_id: &str,
+    role: Option<&str>,
+    tags: &[String],
+) -> Result<(Vec<Value>, Vec<String>)> {
+    let mut skills = Vec::new();
+    let mut warnings = Vec::new();
+    for name in config.skills.skills_for(role, tags) {
+        match get_skill_resource(skills_dir, &name) {
+            Ok(mut skill) => {
+                if config.skills.include_content {
+                    expand_for_worker(db, config, workflows, worker_id, &mut skill)?;
+                } else if let Some(fields) = skill.as_object_mut() {
+                    fields.remove("content");
+                    fields.remove("mime_type");
+                }
+                skills.push(skill);
+            }
+            Err(e) => warnings.push(format!("skill '{}': {}", name, e)),
+        }
+    }
+    Ok((skills, warnings))
+}
+
+/// Expand template variables in the `content` of the skill(s) in `result`
+/// with the worker's context.
+fn expand_for_worker(
+    db: &Database,
+    config: &AppConfig,
+    workflows: &WorkflowsConfig,
+    worker_id: &str,
+    result: &mut Value,
+) -> Result<()> {
+    let worker = db
+        .get_worker(worker_id)?
+        .ok_or_else(|| ToolError::agent_not_found(worker_id))?;
+    let role = workflows.match_role(&worker.tags);
+    let claims = db.get_claimed_tasks(Some(worker_id), None)?;
+
+    // Claims are ordered by claim time; the latest is the task at hand
+    let mut ctx = match claims.last() {
+        Some(task) => PromptContext::new(
+            &task.status,
+            task.phase.as_deref(),
+            &config.states,
+            &config.phases,
+        )
+        .with_task(&task.id, &task.title, task.priority, &task.tags),
+        None => PromptContext::new("", None, &config.states, &config.phases),
+    };
+    ctx = ctx
+        .with_agent(worker_id, role.as_deref(), &worker.tags)
+        .with_claims(&claims);
+
+    let mut expand = |skill: &mut Value| {
+        if let Some(content) = skill.get("content").and_then(|c| c.as_str()) {
+            skill["content"] = json!(expand_prompt(content, &ctx));
+        }
+    };
+    match result.get_mut("skills").and_then(|s| s.as_array_mut()) {
+        Some(skills) => skills.iter_mut().for_each(&mut expand),
+        None => expand(result),
+    }
+    Ok(())
+}
+
 /// Check if a tool name is a skill tool.
 pub fn is_skill_tool(name: &str) -> bool {
     matches!(name, "list_skills" | "get_skill")
@@ -0,0 +1,306 @@
+//! Tool for turning TODO-style source comments into tasks.
+
+use super::{get_bool, get_string, get_string_or_array, make_tool_with_prompts};
+use crate::config::{AppConfig, Prompts, StatesConfig};
+use crate::db::Database;
+use crate::error::ToolError;
+use crate::mark_watch::resolve_root;
+use crate::source_scan::{CodeTodo, SourceScanner};
+use crate::types::Task;
+use anyhow::Result;
+use rmcp::model::Tool;
+use serde_json::{Map, Value, json};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Custom field holding the fingerprint of the TODO a task was created from.
+pub const FINGERPRINT_FIELD: &str = "code_fingerprint";
+
+/// Custom field holding the TODO's `file:line`.
+pub const LOCATION_FIELD: &str = "code_location";
+
+pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
+    vec![make_tool_with_prompts(
+        "scan_source",
+        "Scan source files for TODO/FIXME/HACK comments and keep one task per comment. New comments become tasks tagged from-code (see source_scan in config) with the file, line, and surrounding code; tasks whose comment moved get their location updated. Comments are matched to tasks by a fingerprint of file, marker, and text, stored in the code_fingerprint field. With resolve=true, unclaimed open tasks whose comment is gone from the scanned directories are closed: completed where the workflow allows it, otherwise moved to the first non-blocking state they can exit to (cancelled for pending tasks by default).",
+        json!({
+            "worker_id": {
+                "type": "string",
+                "description": "Calling agent ID (recorded in the audit log)"
+            },
+            "dirs": {
+                "oneOf": [
+                    {"type": "string"},
+                    {"type": "array", "items": {"type": "string"}}
+                ],
+                "description": "Directories or files to scan, relative to source_scan.root (default: source_scan.dirs)"
+            },
+            "parent": {
+                "type": "string",
+                "description": "Task to create new tasks under (default: source_scan.parent)"
+            },
+            "resolve": {
+                "type": "boolean",
+                "description": "Close unclaimed open tasks whose comment no longer exists (default: false)"
+            },
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report what would change without changing anything (default: false)"
+            }
+        }),
+        vec![],
+        prompts,
+    )]
+}
+
+/// The state a task in `from` moves to when its comment disappears:
+/// `completed` if that transition is allowed, otherwise the first
+/// non-blocking state it can exit to.
+fn resolved_state<'a>(states: &'a StatesConfig, from: &str) -> Option<&'a str> {
+    let exits = states.definitions.get(from)?.exits.as_slice();
+    if exits.iter().any(|e| e == "completed") {
+        return Some("completed");
+    }
+    exits
+        .iter()
+        .find(|e| !states.is_blocking_state(e))
+        .map(String::as_str)
+}
+
+/// File part of a stored `file:line` location.
+fn location_file(location: &str) -> &str {
+    location.rsplit_once(':').map_or(location, |(file, _)| file)
+}
+
+fn todo_summary(todo: &CodeTodo) -> Value {
+    json!({
+        "title": todo.title(),
+        "location": todo.location(),
+        "fingerprint": todo.fingerprint,
+    })
+}
+
+/// What syncing TODOs with their tasks did (or, in a dry run, would do).
+#[derive(Debug, Default)]
+pub struct SourceSync {
+    pub created: Vec<Value>,
+    pub updated: Vec<Value>,
+    pub unchanged: usize,
+    /// Tasks already finished whose comment is still in the code.
+    pub closed: Vec<Value>,
+    pub resolved: Vec<Value>,
+    /// Open tasks whose comment is gone but that were left open.
+    pub missing: Vec<Value>,
+}
+
+impl SourceSync {
+    /// Whether any task was created, updated, or resolved.
+    pub fn changed(&self) -> bool {
+        !self.created.is_empty() || !self.updated.is_empty() || !self.resolved.is_empty()
+    }
+}
+
+/// Options for [`sync_todos`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyncOptions<'a> {
+    /// Task new tasks are created under.
+    pub parent: Option<&'a str>,
+    /// Close open tasks whose comment is gone.
+    pub resolve: bool,
+    pub dry_run: bool,
+}
+
+/// Bring `from-code` tasks in line with the TODOs found by a scan.
+///
+/// `in_scope` says whether a file (relative path) was covered by the scan;
+/// only tasks whose comment was in such a file count as gone when no TODO
+/// matches them.
+pub fn sync_todos(
+    db: &Database,
+    config: &AppConfig,
+    todos: &[CodeTodo],
+    in_scope: impl Fn(&str) -> bool,
+    opts: SyncOptions<'_>,
+) -> Result<SourceSync> {
+    let mut existing: HashMap<String, Task> = HashMap::new();
+    let mut locations: HashMap<String, String> = HashMap::new();
+    for (task, fingerprint) in db.get_tasks_with_field(FINGERPRINT_FIELD)? {
+        if let Some(fp) = fingerprint.as_str() {
+            existing.insert(fp.to_string(), task);
+        }
+    }
+    for (task, location) in db.get_tasks_with_field(LOCATION_FIELD)? {
+        if let Some(loc) = location.as_str() {
+            locations.insert(task.id, loc.to_string());
+        }
+    }
+
+    let states = &config.states;
+    let mut sync = SourceSync::default();
+    let mut seen: HashSet<&str> = HashSet::new();
+
+    for todo in todos {
+        seen.insert(&todo.fingerprint);
+        let Some(task) = existing.get(&todo.fingerprint) else {
+            if opts.dry_run {
+                sync.created.push(todo_summary(todo));
+                continue;
+            }
+            let task = db.create_task(
+                None,
+                todo.title(),
+                Some(todo.description()),
+                opts.parent.map(String::from),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(vec![config.source_scan.tag.clone()]),
+                states,
+                &config.ids,
+            )?;
+            let mut fields = Map::new();
+            fields.insert(FINGERPRINT_FIELD.to_string(), json!(todo.fingerprint));
+            fields.insert(LOCATION_FIELD.to_string(), json!(todo.location()));
+            db.set_task_fields(&task.id, &fields)?;
+
+            let mut summary = todo_summary(todo);
+            summary["id"] = json!(task.id);
+            sync.created.push(summary);
+            continue;
+        };
+
+        // Tasks someone already finished stay finished
+        if !states.is_blocking_state(&task.status) {
+            sync.closed.push(json!(task.id));
+            continue;
+        }
+        let location = todo.location();
+        if locations.get(&task.id) == Some(&location) {
+            sync.unchanged += 1;
+            continue;
+        }
+        if !opts.dry_run {
+            db.update_task(
+                &task.id,
+                None,
+                Some(Some(todo.description())),
+                None,
+                None,
+                None,
+                None,
+                states,
+            )?;
+            let mut fields = Map::new();
+            fields.insert(LOCATION_FIELD.to_string(), json!(location));
+            db.set_task_fields(&task.id, &fields)?;
+        }
+        sync.updated
+            .push(json!({ "id": task.id, "location": location }));
+    }
+
+    // Open tasks whose comment was in a scanned file but is gone now
+    let mut gone: Vec<&Task> = existing
+        .iter()
+        .filter(|(fp, task)| !seen.contains(fp.as_str()) && states.is_blocking_state(&task.status))
+        .map(|(_, task)| task)
+        .filter(|task| {
+            locations
+                .get(&task.id)
+                .is_some_and(|loc| in_scope(location_file(loc)))
+        })
+        .collect();
+    gone.sort_by(|a, b| a.id.cmp(&b.id));
+
+    for task in gone {
+        let target = resolved_state(states, &task.status).filter(|_| opts.resolve);
+        if target.is_none() || task.worker_id.is_some() {
+            sync.missing.push(json!(task.id));
+            continue;
+        }
+        if !opts.dry_run {
+            db.update_task(
+                &task.id,
+                None,
+                None,
+                target.map(String::from),
+                None,
+                None,
+                None,
+                states,
+            )?;
+        }
+        sync.resolved
+            .push(json!({ "id": task.id, "status": target }));
+    }
+
+    Ok(sync)
+}
+
+pub fn scan_source(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
+    let scan_config = &config.source_scan;
+    let dirs: Vec<PathBuf> = match get_string_or_array(&args, "dirs") {
+        Some(dirs) => dirs.into_iter().map(PathBuf::from).collect(),
+        None => scan_config.dirs.clone(),
+    };
+    let parent = get_string(&args, "parent").or_else(|| scan_config.parent.clone());
+    let resolve = get_bool(&args, "resolve").unwrap_or(false);
+    let dry_run = get_bool(&args, "dry_run").unwrap_or(false);
+
+    let root = resolve_root(scan_config.root.as_deref())?;
+    let scanner = SourceScanner::new(scan_config, root)?;
+    let scan_paths: Vec<PathBuf> = dirs.iter().map(|d| scanner.resolve(d)).collect();
+    for (dir, path) in dirs.iter().zip(&scan_paths) {
+        if !path.exists() {
+            return Err(ToolError::invalid_value(
+                "dirs",
+                &format!(
+                    "'{}' does not exist under {}",
+                    dir.display(),
+                    scanner.root().display()
+                ),
+            )
+            .into());
+        }
+    }
+    if let Some(ref pid) = parent
+        && db.get_task(pid)?.is_none()
+    {
+        return Err(ToolError::new(
+            crate::error::ErrorCode::TaskNotFound,
+            format!("Parent task not found: {}", pid),
+        )
+        .into());
+    }
+
+    let scan = scanner.scan_dirs(&dirs)?;
+    let sync = sync_todos(
+        db,
+        config,
+        &scan.todos,
+        |file| {
+            let path = scanner.root().join(file);
+            scan_paths.iter().any(|dir| path.starts_with(dir))
+        },
+        SyncOptions {
+            parent: parent.as_deref(),
+            resolve,
+            dry_run,
+        },
+    )?;
+
+    Ok(json!({
+        "root": scanner.root().display().to_string(),
+        "files_scanned": scan.files_scanned,
+        "found": scan.todos.len(),
+        "created": sync.created,
+        "updated": sync.updated,
+        "unchanged": sync.unchanged,
+        "closed": sync.closed,
+        "resolved": sync.resolved,
+        "missing": sync.missing,
+        "dry_run": dry_run,
+    }))
+}
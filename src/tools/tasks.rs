@@ -1,25 +1,43 @@
 //! Task CRUD tools.
 
+use super::attachments::copy_cloned_media;
 use super::{
-    get_bool, get_i32, get_i64, get_string, get_string_array, get_string_or_array,
-    make_tool_with_prompts,
+    get_bool, get_i32, get_i64, get_project, get_string, get_string_array, get_string_or_array,
+    make_tool_with_prompts, require_visible,
 };
 use crate::config::{
-    AppConfig, DependenciesConfig, GateEnforcement, Prompts, StatesConfig, UnknownKeyBehavior,
+    AppConfig, GateEnforcement, LimitsConfig, PrioritiesConfig, Prompts, StatesConfig, TagsConfig,
+    UnknownKeyBehavior, VisibilityConfig,
 };
 use crate::db::Database;
-use crate::db::tasks::{CreateTreeOptions, ListTasksQuery};
+use crate::db::fields::encode_field_value;
+use crate::db::filter::TaskFilter;
+use crate::db::patch::{TagList, TaskOp};
+use crate::db::tasks::{
+    CreateTreeOptions, DeleteCascade, DeleteOptions, ListTasksQuery, Preconditions,
+    SplitTaskOptions, tag_match_score,
+};
+use crate::db::template::InstantiateOptions;
 use crate::error::ToolError;
+use crate::export::checklist::render_checklist;
 use crate::format::{
-    OutputFormat, format_scan_result_markdown, format_task_markdown, format_tasks_markdown,
-    markdown_to_json,
+    FieldSelection, GroupBy, OutputFormat, TaskGroup, ToolResult, compact_to_json,
+    format_related_tasks_markdown, format_scan_result_markdown, format_task_groups_compact,
+    format_task_groups_markdown, format_task_markdown, format_tasks_compact, format_tasks_markdown,
+    markdown_to_json, state_display_order,
 };
-use crate::gates::evaluate_gates;
+use crate::gates::{CommandGates, evaluate_gates, fix_suggestion};
+use crate::outline::{OutlineFormat, parse_outline};
 use crate::prompts::PromptContext;
-use crate::types::{ScanResult, TaskTreeInput, parse_priority};
+use crate::types::{
+    Attachment, BLOCKED_REASONS, DEFAULT_PROJECT, Priority, ScanResult, Task, TaskChange,
+    TaskScope, TaskSequenceEvent, TaskTreeInput, has_tag, meets_required_tag,
+};
 use anyhow::Result;
 use rmcp::model::Tool;
-use serde_json::{Value, json};
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
 use tracing::warn;
 
 /// Options for the task update tool, grouping config references.
@@ -30,10 +48,15 @@ pub struct UpdateOptions<'a> {
     pub workflows: &'a crate::config::workflows::WorkflowsConfig,
 }
 
-pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
+pub fn get_tools(
+    prompts: &Prompts,
+    states_config: &StatesConfig,
+    priorities: &PrioritiesConfig,
+) -> Vec<Tool> {
     // Generate state enum from config
     let state_names: Vec<&str> = states_config.state_names();
     let state_enum: Vec<Value> = state_names.iter().map(|s| json!(s)).collect();
+    let priority_scale = priorities.describe();
 
     vec![
         make_tool_with_prompts(
@@ -57,8 +80,8 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                     "description": "Parent task ID for nesting"
                 },
                 "priority": {
-                    "type": "integer",
-                    "description": "Task priority 0-10 (higher = more important, default 5)"
+                    "oneOf": [{ "type": "integer" }, { "type": "string" }],
+                    "description": format!("Task priority {}", priority_scale)
                 },
                 "points": {
                     "type": "integer",
@@ -72,6 +95,27 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                     "type": "array",
                     "items": { "type": "string" },
                     "description": "Categorization/discovery tags (what the task IS, for querying)"
+                },
+                "needed_group": {
+                    "type": "string",
+                    "description": "Agent group whose members alone may claim or be assigned this task"
+                },
+                "wanted_optional": {
+                    "type": "boolean",
+                    "description": "Wanted tags only raise the match score instead of gating who may claim (default: false)"
+                },
+                "required_child_tags": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Tag rules every task created or retagged under this one must meet: 'component' (the tag or a child tag) or 'component/*' (a child tag)"
+                },
+                "project": {
+                    "type": "string",
+                    "description": "Project for the task (default: the parent's project, or 'default'). Must match the parent's project."
+                },
+                "fields": {
+                    "type": "object",
+                    "description": "Custom field values keyed by field name (see custom_fields config)"
                 }
             }),
             vec![],
@@ -79,7 +123,7 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
         ),
         make_tool_with_prompts(
             "create_tree",
-            "Create a task tree from nested structure. child_type (default 'contains') links parent→children, sibling_type ('follows' or null) links siblings. Use 'ref' in nodes to include existing tasks.",
+            "Create a task tree from nested structure, or from a markdown or YAML outline. child_type (default 'contains') links parent→children, sibling_type ('follows' or null) links siblings. Use 'ref' in nodes to include existing tasks.",
             json!({
                 "tree": {
                     "type": "object",
@@ -89,15 +133,26 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                         "id": { "type": "string", "description": "Custom task ID (optional, petname ID generated if not provided)" },
                         "title": { "type": "string", "description": "Task title (required for new tasks)" },
                         "description": { "type": "string", "description": "Task description" },
-                        "priority": { "type": "integer", "description": "Task priority 0-10 (default 5)" },
+                        "priority": { "oneOf": [{ "type": "integer" }, { "type": "string" }], "description": format!("Task priority {}", priority_scale) },
                         "points": { "type": "integer", "description": "Story points / complexity estimate" },
                         "time_estimate_ms": { "type": "integer", "description": "Estimated duration in milliseconds" },
                         "tags": { "type": "array", "items": { "type": "string" }, "description": "Categorization/discovery tags" },
                         "needed_tags": { "type": "array", "items": { "type": "string" }, "description": "Tags agent must have ALL of to claim (AND)" },
                         "wanted_tags": { "type": "array", "items": { "type": "string" }, "description": "Tags agent must have AT LEAST ONE of to claim (OR)" },
+                        "wanted_optional": { "type": "boolean", "description": "Wanted tags only raise the match score instead of gating claims" },
+                        "required_child_tags": { "type": "array", "items": { "type": "string" }, "description": "Tag rules every task under this node must meet ('component' or 'component/*')" },
                         "children": { "type": "array", "description": "Child nodes (same structure, recursive)" }
                     }
                 },
+                "outline": {
+                    "type": "string",
+                    "description": "Tree as an outline instead of 'tree'. Markdown: a nested list (indentation nests; headings contain the items below them; other indented text becomes the item's description). YAML: a node or list of nodes, each a title string, a mapping of task fields with children, or 'Title: [children]'. Trailing '#tag' words add tags and a trailing '!priority' (number or label) sets priority. Must have a single root."
+                },
+                "outline_format": {
+                    "type": "string",
+                    "enum": ["markdown", "yaml"],
+                    "description": "Syntax of 'outline' (default: markdown)"
+                },
                 "parent": {
                     "type": "string",
                     "description": "Optional parent task ID for the tree root"
@@ -109,18 +164,121 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                 "sibling_type": {
                     "type": "string",
                     "description": "Dependency type between consecutive siblings (default: null/parallel). Use 'follows' for sequential."
+                },
+                "project": {
+                    "type": "string",
+                    "description": "Project for the new tasks (default: the parent's project, or 'default'). Must match the parent's project."
                 }
             }),
-            vec!["tree"],
+            vec![],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "clone_tree",
+            "Deep-copy a task and its descendants under fresh IDs. Tags and attachments are copied (media files too, so the copies never share a file with the original), dependencies inside the subtree are rewired to the copies, and runtime state (owner, timers, metrics, history) is reset.",
+            json!({
+                "task": {
+                    "type": "string",
+                    "description": "Root task ID of the subtree to clone"
+                },
+                "parent": {
+                    "type": "string",
+                    "description": "Parent task ID for the copy (default: same parent as the original). Use 'null' to create the copy as a root task."
+                },
+                "title_prefix": {
+                    "type": "string",
+                    "description": "Optional prefix for every copied title (e.g., 'Sprint 12' gives 'Sprint 12: <title>')"
+                },
+                "tags": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Extra tags to add to every copied task"
+                }
+            }),
+            vec!["task"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "export_markdown",
+            "Render a task and its descendants as a nested GitHub task list for pasting into a PR or issue body. Completed tasks are checked, and each item ends with its task ID in an HTML comment (<!-- id: ... -->) that GitHub hides; `task-graph import` of the edited list adds only the items without a known ID.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Calling agent ID; tasks outside its visibility rules are not returned"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "Root task ID of the subtree to render"
+                }
+            }),
+            vec!["task"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "split",
+            "Split a claimed task that turned out too big into child tasks. The original becomes their parent and keeps its status, owner, and history. Children inherit phase, priority, and tags.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Worker ID (must own the task unless force=true)"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "Task ID to split"
+                },
+                "titles": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Titles for the new child tasks, in order"
+                },
+                "distribute_estimate": {
+                    "type": "boolean",
+                    "description": "Divide the remaining time estimate (estimate minus time already logged) and points evenly across the children (default: false)"
+                },
+                "sibling_type": {
+                    "type": "string",
+                    "description": "Dependency type between consecutive children (default: null/parallel). Use 'follows' for sequential."
+                },
+                "force": {
+                    "type": "boolean",
+                    "description": "Split even if the task is not claimed by this worker (default: false)"
+                }
+            }),
+            vec!["worker_id", "task", "titles"],
             prompts,
         ),
         make_tool_with_prompts(
             "get",
-            "Get a single task by ID. Returns detailed task with attachment metadata list and counts by type.",
+            "Get a single task by ID. Returns detailed task with attachment metadata list and counts by type. Use include to expand its graph neighborhood (children, parents, blockers, blocking, attachments, recent_history) in the same call. With as_of, returns the task as it stood at that time.",
             json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Calling agent ID; tasks outside its visibility rules are not returned"
+                },
                 "task": {
                     "type": "string",
                     "description": "Task ID"
+                },
+                "as_of": {
+                    "type": "string",
+                    "description": "Show the task as it stood at this time (ISO 8601 or epoch ms), rebuilt from its status history and recorded field changes"
+                },
+                "select": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Fields to return, by their JSON names (e.g. [\"title\", \"status\"]); prefix a name with - to omit it instead (e.g. [\"-description\", \"-attachments\"]). id is always returned."
+                },
+                "include": {
+                    "type": "array",
+                    "items": {
+                        "type": "string",
+                        "enum": ["children", "parents", "blockers", "blocking", "attachments", "recent_history"]
+                    },
+                    "description": "Related entities to return with the task in one call: children, parents (chain up to the root, nearest first), blockers, and blocking as {id, title, status, worker_id} summaries; attachments with their inline content; recent_history as the latest status/phase events and field changes. Not combinable with as_of."
+                },
+                "preview_chars": {
+                    "type": "integer",
+                    "description": "Cut the description to this many characters, noting its full length (default: the whole description)"
                 }
             }),
             vec!["task"],
@@ -139,12 +297,25 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                 },
                 "ready": {
                     "type": "boolean",
-                    "description": "Filter for claimable tasks: in initial status, unclaimed, all start-blocking deps satisfied. When combined with 'agent', also filters by agent's tag qualifications."
+                    "description": "Filter for claimable tasks: in initial status, unclaimed, all start-blocking deps satisfied. When combined with 'agent', also filters by agent's tag qualifications and adds each task's weighted tag match_score."
                 },
                 "blocked": {
                     "type": "boolean",
                     "description": "Filter for blocked tasks: have unsatisfied start-blocking dependencies"
                 },
+                "blocked_reason": {
+                    "type": "string",
+                    "enum": BLOCKED_REASONS,
+                    "description": "Filter tasks flagged with this blocked reason (set via update)"
+                },
+                "needed_group": {
+                    "type": "string",
+                    "description": "Filter tasks restricted to this agent group"
+                },
+                "project": {
+                    "type": "string",
+                    "description": "Filter tasks in this project"
+                },
                 "claimed": {
                     "type": "boolean",
                     "description": "Filter for claimed tasks: currently owned by any agent (owner_agent IS NOT NULL)"
@@ -163,7 +334,11 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                 },
                 "agent": {
                     "type": "string",
-                    "description": "Agent ID for filtering. With ready=true, filters tasks the agent is qualified to claim based on agent_tags_all/agent_tags_any requirements."
+                    "description": "Agent ID for filtering. With ready=true, filters tasks the agent is qualified to claim based on agent_tags_all/agent_tags_any requirements and needed_group membership."
+                },
+                "worker_id": {
+                    "type": "string",
+                    "description": "Calling agent ID. Visibility rules for this agent (or for 'agent' when omitted) limit the tasks returned."
                 },
                 "tags_any": {
                     "type": "array",
@@ -175,10 +350,14 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                     "items": { "type": "string" },
                     "description": "Filter tasks that have ALL of these tags (AND)"
                 },
+                "fields": {
+                    "type": "object",
+                    "description": "Filter tasks whose custom fields equal ALL of these values"
+                },
                 "sort_by": {
                     "type": "string",
                     "enum": ["priority", "created_at", "updated_at"],
-                    "description": "Field to sort by (default: created_at for general queries, priority then created_at for ready queries)"
+                    "description": "Field to sort by (default: created_at for general queries, priority weighed against due_at deadlines, then created_at, for ready queries)"
                 },
                 "sort_order": {
                     "type": "string",
@@ -192,6 +371,37 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                 "offset": {
                     "type": "integer",
                     "description": "Number of tasks to skip for pagination (default: 0)"
+                },
+                "preview_chars": {
+                    "type": "integer",
+                    "description": "Cut descriptions to this many characters, noting their full length (default: server setting limits.description_preview_chars; 0 returns them whole)"
+                },
+                "full": {
+                    "type": "boolean",
+                    "description": "Return whole descriptions, ignoring preview_chars (default: false)"
+                },
+                "as_of": {
+                    "type": "string",
+                    "description": "List tasks as they stood at this time (ISO 8601 or epoch ms): tasks created later or deleted by then are left out, and filters apply to the past state. Not combinable with ready or blocked."
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["json", "markdown", "compact"],
+                    "description": "Output format (default: server setting). compact: a header of short keys, then one tab-separated row per task (id, st, pri, own, blk, title)"
+                },
+                "select": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Task fields to return, by their JSON names (e.g. [\"title\", \"status\"]); prefix a name with - to omit it instead (e.g. [\"-description\"]). id is always returned. In compact output the selected fields become the columns."
+                },
+                "group_by": {
+                    "type": "string",
+                    "enum": ["status", "agent", "tag", "parent"],
+                    "description": "Group the results into buckets with counts: JSON returns groups of {key, count, tasks} (key null for unowned, untagged, or top-level tasks; parent groups add the parent's title), markdown a section per group, compact a leading group column. A task with several tags appears in each tag's group. Groups cover the returned page."
+                },
+                "where": {
+                    "type": "string",
+                    "description": "Filter expression combining comparisons with AND, OR, NOT, and parentheses, e.g. 'status=working AND tag:backend AND priority>=7'. Operators: = != < <= > >= and ~ (substring). Fields: id, title, description, status, phase, owner, priority, points, project, needed_group, blocked_reason, parent, created_at, updated_at, and fields.<name> for custom fields. tag:name matches a tag; null matches a missing value; quote values with spaces. Applies on top of the other filters; not combinable with as_of."
                 }
             }),
             vec![],
@@ -227,8 +437,8 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                     "description": "New description"
                 },
                 "priority": {
-                    "type": "integer",
-                    "description": "New priority 0-10 (higher = more important)"
+                    "oneOf": [{ "type": "integer" }, { "type": "string" }],
+                    "description": format!("New priority {}", priority_scale)
                 },
                 "points": {
                     "type": "integer",
@@ -249,10 +459,48 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                     "items": { "type": "string" },
                     "description": "Tags agent must have AT LEAST ONE of to claim (OR)"
                 },
+                "needed_group": {
+                    "type": ["string", "null"],
+                    "description": "Agent group whose members alone may claim this task (null clears)"
+                },
+                "wanted_optional": {
+                    "type": "boolean",
+                    "description": "Wanted tags only raise the match score instead of gating who may claim"
+                },
+                "required_child_tags": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Tag rules every task created or retagged under this one must meet (empty clears). Existing descendants are not rechecked."
+                },
+                "fields": {
+                    "type": "object",
+                    "description": "Custom field values to set, merged with existing values (null removes a field)"
+                },
+                "ops": {
+                    "type": "array",
+                    "description": "Partial edits applied atomically to the current task, RFC 6902 style: {op, path, value}. add/remove on /tags, /needed_tags, /wanted_tags (value: tag or tags); add (append a line) or replace on /description; replace on /title; add/replace/remove on /fields/<name>. An invalid op fails the whole update, naming its index. Cannot be combined with a parameter for the same target.",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "op": { "type": "string", "enum": ["add", "remove", "replace"] },
+                            "path": { "type": "string" },
+                            "value": {}
+                        },
+                        "required": ["op", "path"]
+                    }
+                },
                 "time_estimate_ms": {
                     "type": "integer",
                     "description": "Estimated duration in milliseconds"
                 },
+                "blocked_reason": {
+                    "type": ["string", "null"],
+                    "description": format!("Why the task is blocked: one of {} (null clears). Cleared automatically when the task leaves the blocking states (e.g., completed).", BLOCKED_REASONS.join(", "))
+                },
+                "blocked_note": {
+                    "type": ["string", "null"],
+                    "description": "Free-text detail for the blocked reason (null clears)"
+                },
                 "reason": {
                     "type": "string",
                     "description": "Reason for the update (stored in audit trail for state transitions)"
@@ -261,6 +509,15 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                     "type": "boolean",
                     "description": "Force ownership changes even if owned by another worker (default: false)"
                 },
+                "if_status": {
+                    "type": ["string", "array"],
+                    "items": { "type": "string" },
+                    "description": "Only update if the task's status is this (or one of these); otherwise fail with PRECONDITION_FAILED"
+                },
+                "if_owner": {
+                    "type": ["string", "null"],
+                    "description": "Only update if the task is claimed by this worker (null: unclaimed); otherwise fail with PRECONDITION_FAILED"
+                },
                 "attachments": {
                     "type": "array",
                     "description": "List of attachments to add to the task (e.g., commit hashes, changelists, notes)",
@@ -309,8 +566,8 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                     "description": "Task ID"
                 },
                 "cascade": {
-                    "type": "boolean",
-                    "description": "Whether to delete children (default: false)"
+                    "type": ["string", "boolean"],
+                    "description": "What to do with children: 'delete-subtree' deletes all descendants (true), 'orphan' leaves children as root tasks, 'reparent-to-grandparent' moves them to this task's parent. Default fails if the task has children."
                 },
                 "reason": {
                     "type": "string",
@@ -323,6 +580,19 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                 "force": {
                     "type": "boolean",
                     "description": "Force deletion even if claimed by another worker (default: false)"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Report the tasks that would be deleted, orphaned, or reparented without changing anything (default: false)"
+                },
+                "if_status": {
+                    "type": ["string", "array"],
+                    "items": { "type": "string" },
+                    "description": "Only delete if the task's status is this (or one of these); otherwise fail with PRECONDITION_FAILED"
+                },
+                "if_owner": {
+                    "type": ["string", "null"],
+                    "description": "Only delete if the task is claimed by this worker (null: unclaimed); otherwise fail with PRECONDITION_FAILED"
                 }
             }),
             vec!["worker_id", "task"],
@@ -352,6 +622,10 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
             "scan",
             "Scan the task graph from a starting task in multiple directions. Returns related tasks organized by direction: before (predecessors via blocks/follows), after (successors), above (ancestors via contains), below (descendants). Each direction has depth control: 0=none, N=levels, -1=all.",
             json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Calling agent ID; tasks outside its visibility rules are not returned"
+                },
                 "task": {
                     "type": "string",
                     "description": "Task ID to scan from"
@@ -384,6 +658,15 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
     ]
 }
 
+/// Extract the optional `fields` object argument.
+fn get_fields(args: &Value) -> Result<Option<serde_json::Map<String, Value>>> {
+    match args.get("fields") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Object(map)) => Ok(Some(map.clone())),
+        Some(_) => Err(ToolError::invalid_value("fields", "fields must be an object").into()),
+    }
+}
+
 pub fn create(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
     let states_config = &config.states;
     let phases_config = &config.phases;
@@ -394,20 +677,44 @@ pub fn create(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
     let description = get_string(&args, "description");
     let parent_id = get_string(&args, "parent");
     let phase = get_string(&args, "phase");
-    // Support both integer and string priority
-    let priority = get_i32(&args, "priority")
-        .or_else(|| get_string(&args, "priority").map(|s| parse_priority(&s)));
+    // Accept integers, numeric strings, and configured labels; default from the scale
+    let priority = match args.get("priority").filter(|v| !v.is_null()) {
+        Some(v) => parse_priority_arg(&config.priorities, v)?,
+        None => config.priorities.default,
+    };
     let points = get_i32(&args, "points");
     let time_estimate_ms = get_i64(&args, "time_estimate_ms");
-    let tags = get_string_array(&args, "tags");
-    let needed_tags = get_string_array(&args, "needed_tags");
-    let wanted_tags = get_string_array(&args, "wanted_tags");
+    let canonical =
+        |name: &str| get_string_array(&args, name).map(|tags| config.tags.canonical_tags(&tags));
+    let tags = canonical("tags");
+    let needed_tags = canonical("needed_tags");
+    let wanted_tags = canonical("wanted_tags");
+    let needed_group = get_string(&args, "needed_group");
+    let wanted_optional = get_bool(&args, "wanted_optional").unwrap_or(false);
+    let required_child_tags = canonical("required_child_tags");
+    let fields = get_fields(&args)?;
+    let project = check_parent_project(db, parent_id.as_deref(), get_project(&args)?)?;
 
     // Require at least one of title or description
     if title.is_none() && description.is_none() {
         return Err(ToolError::missing_field("title or description").into());
     }
 
+    if let Some(ref t) = title {
+        config.limits.check_title(t)?;
+    }
+    if let Some(ref d) = description {
+        config.limits.check_description(d)?;
+    }
+
+    check_required_tags(
+        db,
+        config,
+        &config.workflows.required_tags,
+        parent_id.as_deref(),
+        tags.as_deref().unwrap_or_default(),
+    )?;
+
     // Derive effective title: explicit title, or truncated description
     let effective_title = title.unwrap_or_else(|| {
         crate::format::truncate_title(description.as_deref().unwrap_or("")).into_owned()
@@ -432,22 +739,45 @@ pub fn create(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
         tag_warnings.extend(tags_config.validate_tags(t)?);
     }
 
-    let task = db.create_task(
+    // Check custom field values
+    let field_warnings = match fields {
+        Some(ref f) => config.custom_fields.validate_fields(f)?,
+        None => Vec::new(),
+    };
+
+    let task = db.create_task_with_fields(
         id,
         effective_title,
         description,
         parent_id,
         phase,
-        priority,
+        Some(priority),
         points,
         time_estimate_ms,
         needed_tags,
         wanted_tags,
         tags,
+        fields.as_ref(),
         states_config,
         ids_config,
     )?;
 
+    if let Some(ref g) = needed_group {
+        db.set_needed_group(&task.id, Some(g))?;
+    }
+
+    if wanted_optional {
+        db.set_wanted_optional(&task.id, true)?;
+    }
+
+    if let Some(ref rules) = required_child_tags {
+        db.set_required_child_tags(&task.id, rules)?;
+    }
+
+    if let Some(ref p) = project {
+        db.set_tasks_project(std::slice::from_ref(&task.id), p)?;
+    }
+
     let mut response = json!({
         "id": &task.id,
         "title": task.title,
@@ -466,6 +796,30 @@ pub fn create(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
         response["tag_warnings"] = json!(tag_warnings);
     }
 
+    if let Some(f) = fields.filter(|f| !f.is_empty()) {
+        response["fields"] = json!(f);
+    }
+
+    if let Some(g) = needed_group {
+        response["needed_group"] = json!(g);
+    }
+
+    if wanted_optional {
+        response["wanted_optional"] = json!(true);
+    }
+
+    if let Some(rules) = required_child_tags.filter(|r| !r.is_empty()) {
+        response["required_child_tags"] = json!(rules);
+    }
+
+    if let Some(p) = project.filter(|p| p != DEFAULT_PROJECT) {
+        response["project"] = json!(p);
+    }
+
+    if !field_warnings.is_empty() {
+        response["field_warnings"] = json!(field_warnings);
+    }
+
     // Warn if title is too long for scannable list output
     if task.title.len() > crate::format::MAX_TITLE_DISPLAY_LEN || task.title.contains('\n') {
         response["title_warning"] = json!(
@@ -476,19 +830,175 @@ pub fn create(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
     Ok(response)
 }
 
+/// Parse `update`'s `ops`, canonicalizing tags. Every invalid operation is
+/// reported at once, as is an operation on a parameter also given directly.
+fn parse_ops(args: &Value, tags_config: &TagsConfig) -> Result<Option<Vec<TaskOp>>> {
+    let Some(items) = args.get("ops") else {
+        return Ok(None);
+    };
+    let items = items
+        .as_array()
+        .ok_or_else(|| ToolError::invalid_value("ops", "Expected an array of operations"))?;
+
+    let mut ops = Vec::with_capacity(items.len());
+    let mut errors = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        match TaskOp::parse(item) {
+            Ok(op) if args.get(op.target()).is_some() => errors.push(format!(
+                "ops[{}]: '{}' is also given as a parameter",
+                i,
+                op.target()
+            )),
+            Ok(TaskOp::AddTags(list, tags)) => {
+                ops.push(TaskOp::AddTags(list, tags_config.canonical_tags(&tags)))
+            }
+            Ok(TaskOp::RemoveTags(list, tags)) => {
+                ops.push(TaskOp::RemoveTags(list, tags_config.canonical_tags(&tags)))
+            }
+            Ok(op) => ops.push(op),
+            Err(e) => errors.push(format!("ops[{}]: {}", i, e)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ToolError::invalid_value("ops", &errors.join("; ")).into());
+    }
+    Ok(Some(ops))
+}
+
+/// The task tags `ops` would leave, starting from `tags`.
+fn ops_tag_result(mut tags: Vec<String>, ops: &[TaskOp]) -> Vec<String> {
+    for op in ops {
+        match op {
+            TaskOp::AddTags(TagList::Tags, added) => {
+                for tag in added {
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
+                    }
+                }
+            }
+            TaskOp::RemoveTags(TagList::Tags, removed) => tags.retain(|t| !removed.contains(t)),
+            _ => {}
+        }
+    }
+    tags
+}
+
+/// Parse `if_status` (string or array) and `if_owner` (worker ID, or null
+/// for unclaimed) into compare-and-set guards.
+fn parse_preconditions(args: &Value) -> Result<Preconditions> {
+    let status = get_string_or_array(args, "if_status");
+    if status.as_ref().is_some_and(|s| s.is_empty()) {
+        return Err(ToolError::invalid_value(
+            "if_status",
+            "if_status must name at least one state",
+        )
+        .into());
+    }
+    let owner = match args.get("if_owner") {
+        None => None,
+        Some(Value::Null) => Some(None),
+        Some(Value::String(o)) => Some(Some(o.clone())),
+        Some(_) => {
+            return Err(ToolError::invalid_value(
+                "if_owner",
+                "if_owner must be a worker ID, or null for an unclaimed task",
+            )
+            .into());
+        }
+    };
+    Ok(Preconditions { status, owner })
+}
+
+/// Parse a priority argument against the configured scale.
+fn parse_priority_arg(priorities: &PrioritiesConfig, value: &Value) -> Result<Priority> {
+    priorities
+        .parse(value)
+        .map_err(|e| ToolError::invalid_value("priority", &e.to_string()).into())
+}
+
+/// The create_tree node for a markdown or YAML outline with a single root.
+fn outline_tree(outline: &str, format: Option<String>) -> Result<Value> {
+    let format = match format {
+        Some(f) => OutlineFormat::parse(&f).ok_or_else(|| {
+            ToolError::invalid_value("outline_format", "Expected markdown or yaml")
+        })?,
+        None => OutlineFormat::default(),
+    };
+    let items = parse_outline(outline, format)
+        .map_err(|e| ToolError::invalid_value("outline", &e.to_string()))?;
+    match items.as_slice() {
+        [root] => Ok(root.to_tree()),
+        _ => Err(ToolError::invalid_value(
+            "outline",
+            &format!(
+                "Outline has {} top-level items; it needs one root (e.g. a heading above the list)",
+                items.len()
+            ),
+        )
+        .into()),
+    }
+}
+
+/// Replace priority labels in a create_tree node (and its children) with values on
+/// the configured scale, filling in the default for new nodes that omit one.
+fn resolve_tree_priorities(node: &mut Value, priorities: &PrioritiesConfig) -> Result<()> {
+    let Some(obj) = node.as_object_mut() else {
+        return Ok(());
+    };
+
+    if !obj.contains_key("ref") {
+        let priority = match obj.get("priority").filter(|v| !v.is_null()) {
+            Some(v) => parse_priority_arg(priorities, v)?,
+            None => priorities.default,
+        };
+        obj.insert("priority".to_string(), json!(priority));
+    }
+
+    if let Some(Value::Array(children)) = obj.get_mut("children") {
+        for child in children {
+            resolve_tree_priorities(child, priorities)?;
+        }
+    }
+    Ok(())
+}
+
 pub fn create_tree(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
     let states_config = &config.states;
     let phases_config = &config.phases;
     let tags_config = &config.tags;
     let ids_config = &config.ids;
-    let tree: TaskTreeInput = serde_json::from_value(
-        args.get("tree")
-            .cloned()
-            .ok_or_else(|| ToolError::missing_field("tree"))?,
-    )?;
+    let mut tree_value = match (
+        args.get("tree").filter(|v| !v.is_null()),
+        get_string(&args, "outline"),
+    ) {
+        (Some(_), Some(_)) => {
+            return Err(ToolError::invalid_value(
+                "outline",
+                "Pass either tree or outline, not both",
+            )
+            .into());
+        }
+        (Some(tree), None) => tree.clone(),
+        (None, Some(outline)) => outline_tree(&outline, get_string(&args, "outline_format"))?,
+        (None, None) => return Err(ToolError::missing_field("tree").into()),
+    };
+    resolve_tree_priorities(&mut tree_value, &config.priorities)?;
+    let tree: TaskTreeInput = serde_json::from_value(tree_value)?;
+    check_tree_limits(&tree, &config.limits)?;
     let parent_id = get_string(&args, "parent");
     let child_type = get_string(&args, "child_type");
     let sibling_type = get_string(&args, "sibling_type");
+    let project = check_parent_project(db, parent_id.as_deref(), get_project(&args)?)?;
+    let mut referenced = Vec::new();
+    collect_tree_refs(&tree, &mut referenced);
+
+    let rules = required_rules(
+        db,
+        config,
+        &config.workflows.required_tags,
+        parent_id.as_deref(),
+    )?;
+    check_tree_required_tags(db, config, &tree, &rules)?;
 
     let (root_id, all_ids, phase_warnings, tag_warnings) =
         db.create_task_tree(CreateTreeOptions {
@@ -502,6 +1012,16 @@ pub fn create_tree(db: &Database, config: &AppConfig, args: Value) -> Result<Val
             ids_config,
         })?;
 
+    // Referenced tasks keep their project
+    if let Some(ref p) = project {
+        let new_ids: Vec<String> = all_ids
+            .iter()
+            .filter(|id| !referenced.contains(id))
+            .cloned()
+            .collect();
+        db.set_tasks_project(&new_ids, p)?;
+    }
+
     // Fetch the root task to return full details
     let root_task = db.get_task(&root_id)?.ok_or_else(|| {
         ToolError::new(
@@ -535,20 +1055,550 @@ pub fn create_tree(db: &Database, config: &AppConfig, args: Value) -> Result<Val
     Ok(response)
 }
 
-pub fn get(db: &Database, default_format: OutputFormat, args: Value) -> Result<Value> {
+/// Check the titles and descriptions of a tree input against the size limits.
+fn check_tree_limits(input: &TaskTreeInput, limits: &LimitsConfig) -> Result<()> {
+    if let Some(ref title) = input.title {
+        limits.check_title(title)?;
+    }
+    if let Some(ref description) = input.description {
+        limits.check_description(description)?;
+    }
+    input
+        .children
+        .iter()
+        .try_for_each(|child| check_tree_limits(child, limits))
+}
+
+/// The required-tag rules a task under `parent` must meet, as `(rule, source)`:
+/// `workflow_rules`, then those declared by the parent and its ancestors.
+fn required_rules(
+    db: &Database,
+    config: &AppConfig,
+    workflow_rules: &[String],
+    parent: Option<&str>,
+) -> Result<Vec<(String, String)>> {
+    let mut rules: Vec<(String, String)> = config
+        .tags
+        .canonical_tags(workflow_rules)
+        .into_iter()
+        .map(|rule| (rule, "the workflow".to_string()))
+        .collect();
+    if let Some(parent) = parent {
+        for (rule, id) in db.get_required_tags_under(parent)? {
+            rules.push((rule, format!("task '{}'", id)));
+        }
+    }
+    Ok(rules)
+}
+
+/// Reject `tags` that do not meet every rule, naming each rule missed.
+fn check_rules(rules: &[(String, String)], tags: &[String], task: Option<&str>) -> Result<()> {
+    let missing: Vec<String> = rules
+        .iter()
+        .filter(|(rule, _)| !meets_required_tag(tags, rule))
+        .map(|(rule, source)| format!("'{}' (required by {})", rule, source))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let subject = match task {
+        Some(title) => format!("Task '{}' is", title),
+        None => "Task is".to_string(),
+    };
+    Err(ToolError::invalid_value(
+        "tags",
+        &format!("{} missing required tags: {}", subject, missing.join(", ")),
+    )
+    .into())
+}
+
+/// Check a task's tags against the workflow's required tags and the rules
+/// declared by `parent` and its ancestors.
+fn check_required_tags(
+    db: &Database,
+    config: &AppConfig,
+    workflow_rules: &[String],
+    parent: Option<&str>,
+    tags: &[String],
+) -> Result<()> {
+    let rules = required_rules(db, config, workflow_rules, parent)?;
+    check_rules(&rules, tags, None)
+}
+
+/// Check every new node of a tree input against `rules` plus the rules its
+/// ancestors in the tree declare. Referenced tasks are not checked, but the
+/// rules they declare apply to the nodes below them.
+fn check_tree_required_tags(
+    db: &Database,
+    config: &AppConfig,
+    input: &TaskTreeInput,
+    rules: &[(String, String)],
+) -> Result<()> {
+    let declared = match input.ref_id {
+        Some(ref id) => required_rules(db, config, &[], Some(id))?,
+        None => {
+            let tags = config
+                .tags
+                .canonical_tags(input.tags.as_deref().unwrap_or_default());
+            let title = input.title.as_deref().or(input.id.as_deref());
+            check_rules(rules, &tags, title)?;
+            let source = match title {
+                Some(title) => format!("task '{}'", title),
+                None => "a parent in the tree".to_string(),
+            };
+            config
+                .tags
+                .canonical_tags(input.required_child_tags.as_deref().unwrap_or_default())
+                .into_iter()
+                .map(|rule| (rule, source.clone()))
+                .collect()
+        }
+    };
+    let mut below = rules.to_vec();
+    for rule in declared {
+        if !below.contains(&rule) {
+            below.push(rule);
+        }
+    }
+    input
+        .children
+        .iter()
+        .try_for_each(|child| check_tree_required_tags(db, config, child, &below))
+}
+
+/// Collect the existing task IDs a tree input references.
+fn collect_tree_refs(input: &TaskTreeInput, refs: &mut Vec<String>) {
+    if let Some(ref id) = input.ref_id {
+        refs.push(id.clone());
+    }
+    for child in &input.children {
+        collect_tree_refs(child, refs);
+    }
+}
+
+/// Check a requested project against the parent's. Returns the project to apply
+/// after creation, or None to keep the inherited one.
+fn check_parent_project(
+    db: &Database,
+    parent_id: Option<&str>,
+    project: Option<String>,
+) -> Result<Option<String>> {
+    let (Some(parent), Some(requested)) = (parent_id, project.as_ref()) else {
+        return Ok(project);
+    };
+    match db.get_task_project(parent)? {
+        Some(parent_project) if parent_project != *requested => Err(ToolError::invalid_value(
+            "project",
+            &format!(
+                "Parent task '{}' is in project '{}', not '{}'",
+                parent, parent_project, requested
+            ),
+        )
+        .into()),
+        _ => Ok(None),
+    }
+}
+
+pub fn clone_tree(
+    db: &Database,
+    config: &AppConfig,
+    media_dir: &Path,
+    args: Value,
+) -> Result<Value> {
+    let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    let title_prefix = get_string(&args, "title_prefix");
+    let extra_tags = config
+        .tags
+        .canonical_tags(&get_string_array(&args, "tags").unwrap_or_default());
+
+    if db.get_task(&task_id)?.is_none() {
+        return Err(ToolError::task_not_found(&task_id).into());
+    }
+
+    // Default to placing the copy alongside the original
+    let parent_id = match get_string(&args, "parent") {
+        Some(p) if p == "null" => None,
+        Some(p) => Some(p),
+        None => db.get_parent(&task_id)?,
+    };
+
+    let tag_warnings = config.tags.validate_tags(&extra_tags)?;
+
+    let mut options = InstantiateOptions::new().with_extra_tags(extra_tags);
+    options.initial_status = Some(config.states.initial.clone());
+    if let Some(ref pid) = parent_id {
+        options = options.with_parent(pid);
+    }
+    if let Some(ref prefix) = title_prefix {
+        options = options.with_title_prefix(prefix);
+    }
+
+    let result = db.clone_subtree(&task_id, &config.ids, &options)?;
+    let root_id = result.id_map.get(&task_id).cloned().unwrap_or_default();
+    let new_ids: Vec<String> = result.id_map.values().cloned().collect();
+    copy_cloned_media(db, media_dir, &new_ids)?;
+
+    let mut response = json!({
+        "root": root_id,
+        "source": task_id,
+        "parent": parent_id,
+        "id_map": result.id_map,
+        "count": result.import_stats.tasks_imported,
+        "dependencies_copied": result.import_stats.dependencies_imported
+    });
+
+    if !tag_warnings.is_empty() {
+        response["tag_warnings"] = json!(tag_warnings);
+    }
+
+    Ok(response)
+}
+
+pub fn export_markdown(
+    db: &Database,
+    states: &StatesConfig,
+    visibility: &VisibilityConfig,
+    args: Value,
+) -> Result<ToolResult> {
+    let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    let scope = db.caller_scope(get_string(&args, "worker_id").as_deref(), visibility)?;
+    let scope = scope.as_ref();
+    let mut tree = db
+        .get_task_tree(&task_id)?
+        .ok_or_else(|| ToolError::new(crate::error::ErrorCode::TaskNotFound, "Task not found"))?;
+    require_visible(db, &task_id, scope)?;
+    db.retain_visible_tree(&mut tree, scope)?;
+    Ok(ToolResult::Raw(render_checklist(&tree, states)))
+}
+
+pub fn split(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
+    let worker_id =
+        get_string(&args, "worker_id").ok_or_else(|| ToolError::missing_field("worker_id"))?;
+    let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    let titles =
+        get_string_array(&args, "titles").ok_or_else(|| ToolError::missing_field("titles"))?;
+    if titles.is_empty() {
+        return Err(ToolError::invalid_value("titles", "must contain at least one title").into());
+    }
+    for title in &titles {
+        config.limits.check_title(title)?;
+    }
+    let distribute_estimate = get_bool(&args, "distribute_estimate").unwrap_or(false);
+    let sibling_type = get_string(&args, "sibling_type");
+    let force = get_bool(&args, "force").unwrap_or(false);
+
+    if db.get_task(&task_id)?.is_none() {
+        return Err(ToolError::task_not_found(&task_id).into());
+    }
+
+    let (child_ids, tag_warnings) = db.split_task(SplitTaskOptions {
+        task_id: &task_id,
+        worker_id: &worker_id,
+        titles,
+        sibling_type,
+        distribute_estimate,
+        force,
+        states_config: &config.states,
+        phases_config: &config.phases,
+        tags_config: &config.tags,
+        ids_config: &config.ids,
+    })?;
+
+    let children: Vec<Value> = child_ids
+        .iter()
+        .filter_map(|id| db.get_task(id).ok().flatten())
+        .map(|t| {
+            json!({
+                "id": t.id,
+                "title": t.title,
+                "status": t.status,
+                "points": t.points,
+                "time_estimate_ms": t.time_estimate_ms
+            })
+        })
+        .collect();
+
+    let mut response = json!({
+        "parent": task_id,
+        "children": children,
+        "count": child_ids.len()
+    });
+
+    if !tag_warnings.is_empty() {
+        response["tag_warnings"] = json!(tag_warnings);
+    }
+
+    Ok(response)
+}
+
+/// Parse the `as_of` time of a time-travel query (ISO 8601 or epoch ms).
+fn get_as_of(args: &Value) -> Result<Option<i64>> {
+    match get_string(args, "as_of") {
+        Some(s) => match super::tracking::parse_timestamp(&s) {
+            Some(at) => Ok(Some(at)),
+            None => Err(ToolError::invalid_value(
+                "as_of",
+                "Expected an ISO 8601 time or epoch milliseconds",
+            )
+            .into()),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Related entities `get` can expand with `include`.
+const GET_INCLUDES: &[&str] = &[
+    "children",
+    "parents",
+    "blockers",
+    "blocking",
+    "attachments",
+    "recent_history",
+];
+
+/// Entries of each kind (status/phase events, field changes) in `recent_history`.
+const RECENT_HISTORY_LEN: usize = 10;
+
+/// The neighborhood of a task that `get` expands on request.
+#[derive(Default)]
+struct Related {
+    /// Parent chain, nearest first.
+    parents: Option<Vec<Task>>,
+    children: Option<Vec<Task>>,
+    blockers: Option<Vec<Task>>,
+    blocking: Option<Vec<Task>>,
+    /// Attachments with their inline content.
+    attachments: Option<Vec<Attachment>>,
+    /// Latest sequence events and field changes, newest first.
+    history: Option<(Vec<TaskSequenceEvent>, Vec<TaskChange>)>,
+}
+
+impl Related {
+    fn load(
+        db: &Database,
+        task_id: &str,
+        blocked_by: &[String],
+        include: &[String],
+        scope: Option<&TaskScope>,
+    ) -> Result<Self> {
+        let tasks = |mut ids: Vec<String>| -> Result<Vec<Task>> {
+            db.retain_visible_ids(&mut ids, scope)?;
+            let mut tasks = Vec::with_capacity(ids.len());
+            for id in ids {
+                tasks.extend(db.get_task(&id)?);
+            }
+            Ok(tasks)
+        };
+        let wants = |name: &str| include.iter().any(|i| i == name);
+
+        let mut related = Related::default();
+        if wants("parents") {
+            let mut parents = db.get_ancestors(task_id, -1)?;
+            db.retain_visible(&mut parents, scope)?;
+            related.parents = Some(parents);
+        }
+        if wants("children") {
+            related.children = Some(tasks(db.get_ordered_child_ids(task_id)?)?);
+        }
+        if wants("blockers") {
+            related.blockers = Some(tasks(blocked_by.to_vec())?);
+        }
+        if wants("blocking") {
+            related.blocking = Some(tasks(db.get_blocking(task_id)?)?);
+        }
+        if wants("attachments") {
+            related.attachments = Some(db.get_attachments_full(task_id, true)?);
+        }
+        if wants("recent_history") {
+            let mut events = db.get_task_sequence_history(task_id)?;
+            events.reverse();
+            events.truncate(RECENT_HISTORY_LEN);
+            let mut changes = db.get_task_changes(task_id)?;
+            changes.reverse();
+            changes.truncate(RECENT_HISTORY_LEN);
+            related.history = Some((events, changes));
+        }
+        Ok(related)
+    }
+
+    /// Add the expansions to a task's JSON object.
+    fn insert_json(self, obj: &mut Map<String, Value>) -> Result<()> {
+        let lists = [
+            ("parents", self.parents),
+            ("children", self.children),
+            ("blockers", self.blockers),
+            ("blocking", self.blocking),
+        ];
+        for (key, tasks) in lists {
+            if let Some(tasks) = tasks {
+                let summaries: Vec<Value> = tasks.iter().map(related_summary).collect();
+                obj.insert(key.to_string(), json!(summaries));
+            }
+        }
+        if let Some(attachments) = self.attachments {
+            obj.insert(
+                "attachments".to_string(),
+                serde_json::to_value(attachments)?,
+            );
+        }
+        if let Some((events, changes)) = self.history {
+            obj.insert(
+                "recent_history".to_string(),
+                json!({ "events": events, "changes": changes }),
+            );
+        }
+        Ok(())
+    }
+
+    /// Markdown sections for the expansions (attachment content is rendered
+    /// with the attachment list).
+    fn markdown(&self) -> String {
+        let mut md = String::new();
+        let lists = [
+            ("Parents", &self.parents),
+            ("Children", &self.children),
+            ("Blockers", &self.blockers),
+            ("Blocking", &self.blocking),
+        ];
+        for (heading, tasks) in lists {
+            if let Some(tasks) = tasks {
+                md.push_str(&format_related_tasks_markdown(heading, tasks));
+            }
+        }
+        if let Some((events, changes)) = &self.history {
+            md.push_str("\n### Recent History\n");
+            if events.is_empty() && changes.is_empty() {
+                md.push_str("No history recorded.\n");
+            }
+            for event in events {
+                let what = match (&event.status, &event.phase) {
+                    (Some(status), Some(phase)) => format!("{} ({})", status, phase),
+                    (Some(status), None) => status.clone(),
+                    (None, Some(phase)) => format!("phase {}", phase),
+                    (None, None) => "event".to_string(),
+                };
+                let by = event
+                    .worker_id
+                    .as_ref()
+                    .map(|w| format!(" by {}", w))
+                    .unwrap_or_default();
+                md.push_str(&format!(
+                    "- {} → {}{}\n",
+                    format_as_of(event.timestamp),
+                    what,
+                    by
+                ));
+            }
+            for change in changes {
+                md.push_str(&format!(
+                    "- {} `{}`: {} → {}\n",
+                    format_as_of(change.timestamp),
+                    change.field,
+                    change.old_value,
+                    change.new_value
+                ));
+            }
+        }
+        md
+    }
+}
+
+/// A related task in `get` expansions: enough to decide whether to fetch it.
+fn related_summary(task: &Task) -> Value {
+    let mut summary = json!({
+        "id": task.id,
+        "title": task.title,
+        "status": task.status,
+    });
+    if let Some(ref owner) = task.worker_id {
+        summary["worker_id"] = json!(owner);
+    }
+    summary
+}
+
+/// Render an `as_of` time for Markdown output.
+fn format_as_of(at: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(at)
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_else(|| at.to_string())
+}
+
+pub fn get(
+    db: &Database,
+    states_config: &StatesConfig,
+    priorities: &PrioritiesConfig,
+    visibility: &VisibilityConfig,
+    default_format: OutputFormat,
+    args: Value,
+) -> Result<Value> {
     let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    let scope = db.caller_scope(get_string(&args, "worker_id").as_deref(), visibility)?;
+    let scope = scope.as_ref();
     let format = get_string(&args, "format")
         .and_then(|s| OutputFormat::parse(&s))
         .unwrap_or(default_format);
+    let as_of = get_as_of(&args)?;
+    let preview_chars = get_i32(&args, "preview_chars").map(|n| n.max(0) as usize);
+    let select = FieldSelection::new(&get_string_array(&args, "select").unwrap_or_default());
+    let include = get_string_array(&args, "include").unwrap_or_default();
+    if let Some(unknown) = include.iter().find(|i| !GET_INCLUDES.contains(&i.as_str())) {
+        return Err(ToolError::invalid_value(
+            "include",
+            &format!(
+                "Unknown expansion '{}'. Expected {}",
+                unknown,
+                GET_INCLUDES.join(", ")
+            ),
+        )
+        .into());
+    }
+    if as_of.is_some() && !include.is_empty() {
+        return Err(
+            ToolError::invalid_value("include", "include cannot be combined with as_of").into(),
+        );
+    }
 
-    let task = db
-        .get_task(&task_id)?
-        .ok_or_else(|| ToolError::new(crate::error::ErrorCode::TaskNotFound, "Task not found"))?;
+    // A past state comes with the custom fields it had then
+    let (mut task, past_fields) = match as_of {
+        Some(at) => {
+            let (task, fields) =
+                db.get_task_as_of(&task_id, at, states_config)?
+                    .ok_or_else(|| {
+                        ToolError::new(
+                            crate::error::ErrorCode::TaskNotFound,
+                            "Task did not exist at as_of",
+                        )
+                    })?;
+            (task, Some(fields))
+        }
+        None => {
+            let task = db.get_task(&task_id)?.ok_or_else(|| {
+                ToolError::new(crate::error::ErrorCode::TaskNotFound, "Task not found")
+            })?;
+            (task, None)
+        }
+    };
+    require_visible(db, &task_id, scope)?;
+
+    if let Some(max_chars) = preview_chars {
+        preview_task_description(&mut task, max_chars);
+    }
 
-    let blocked_by = db.get_blockers(&task_id)?;
+    let mut blocked_by = db.get_blockers(&task_id)?;
+    db.retain_visible_ids(&mut blocked_by, scope)?;
+
+    let related = Related::load(db, &task_id, &blocked_by, &include, scope)?;
 
     // Get attachment metadata
-    let attachments = db.get_attachments(&task_id)?;
+    let mut attachments = if select.includes("attachments") || select.includes("attachment_counts")
+    {
+        db.get_attachments(&task_id)?
+    } else {
+        Vec::new()
+    };
+    if let Some(at) = as_of {
+        attachments.retain(|a| a.created_at <= at);
+    }
 
     // Calculate attachment counts by MIME type
     let mut attachment_counts: std::collections::HashMap<String, i32> =
@@ -559,10 +1609,15 @@ pub fn get(db: &Database, default_format: OutputFormat, args: Value) -> Result<V
 
     match format {
         OutputFormat::Markdown => {
-            let mut md = format_task_markdown(&task, &blocked_by);
+            let mut md = format_task_markdown(&task, &blocked_by, priorities, &select);
+            if let Some(at) = as_of {
+                md.push_str(&format!("\n*As of {}*\n", format_as_of(at)));
+            }
+
+            md.push_str(&related.markdown());
 
             // Add attachment section if there are attachments
-            if !attachments.is_empty() {
+            if !attachments.is_empty() && select.includes("attachments") {
                 md.push_str("\n### Attachments\n");
                 for att in &attachments {
                     let file_indicator = if att.file_path.is_some() {
@@ -576,6 +1631,16 @@ pub fn get(db: &Database, default_format: OutputFormat, args: Value) -> Result<V
                     ));
                 }
 
+                // Inline content of expanded attachments
+                for att in related.attachments.iter().flatten() {
+                    if !att.content.is_empty() {
+                        md.push_str(&format!(
+                            "\n#### {}\n\n```\n{}\n```\n",
+                            att.name, att.content
+                        ));
+                    }
+                }
+
                 // Add counts by type
                 md.push_str("\n**Counts by type:**\n");
                 for (mime_type, count) in &attachment_counts {
@@ -585,7 +1650,7 @@ pub fn get(db: &Database, default_format: OutputFormat, args: Value) -> Result<V
 
             Ok(markdown_to_json(md))
         }
-        OutputFormat::Json => {
+        OutputFormat::Json | OutputFormat::Compact => {
             let mut task_json = serde_json::to_value(&task)?;
             if let Some(obj) = task_json.as_object_mut() {
                 obj.insert("blocked_by".to_string(), json!(blocked_by));
@@ -597,6 +1662,53 @@ pub fn get(db: &Database, default_format: OutputFormat, args: Value) -> Result<V
                     "attachment_counts".to_string(),
                     serde_json::to_value(&attachment_counts)?,
                 );
+                if select.includes("aliases") {
+                    let aliases = db.get_task_aliases(&task_id)?;
+                    if !aliases.is_empty() {
+                        obj.insert("aliases".to_string(), json!(aliases));
+                    }
+                }
+                if select.includes("sequence")
+                    && as_of.is_none()
+                    && let Some(seq) = db.get_sequence_position(&task_id)?
+                {
+                    obj.insert(
+                        "sequence".to_string(),
+                        json!({
+                            "parent": seq.parent,
+                            "position": seq.position,
+                            "length": seq.length,
+                            "previous": seq.previous,
+                            "next": seq.next
+                        }),
+                    );
+                }
+                if select.includes("fields") {
+                    let fields = match past_fields {
+                        Some(fields) => fields,
+                        None => db.get_task_fields(&task_id)?,
+                    };
+                    if !fields.is_empty() {
+                        obj.insert("fields".to_string(), json!(fields));
+                    }
+                }
+            }
+            select.apply(&mut task_json);
+            // Expansions were asked for explicitly, so they bypass the selection
+            if let Some(obj) = task_json.as_object_mut() {
+                related.insert_json(obj)?;
+            }
+
+            if format == OutputFormat::Compact {
+                return Ok(compact_to_json(format_tasks_compact(
+                    &[task_json],
+                    &select,
+                    None,
+                )));
+            }
+            // The as_of time is metadata, kept whatever was selected
+            if let (Some(at), Some(obj)) = (as_of, task_json.as_object_mut()) {
+                obj.insert("as_of".to_string(), json!(at));
             }
             Ok(task_json)
         }
@@ -605,11 +1717,15 @@ pub fn get(db: &Database, default_format: OutputFormat, args: Value) -> Result<V
 
 pub fn list_tasks(
     db: &Database,
-    states_config: &StatesConfig,
-    deps_config: &DependenciesConfig,
+    config: &AppConfig,
     default_format: OutputFormat,
     args: Value,
 ) -> Result<Value> {
+    let states_config = &config.states;
+    let deps_config = &config.deps;
+    let priorities = &config.priorities;
+    let visibility = &config.visibility;
+    let limits = &config.limits;
     let format = get_string(&args, "format")
         .and_then(|s| OutputFormat::parse(&s))
         .unwrap_or(default_format);
@@ -621,15 +1737,68 @@ pub fn list_tasks(
     let limit = get_i32(&args, "limit");
     let offset = get_i32(&args, "offset").unwrap_or(0).max(0);
     let fetch_limit = limit.map(|l| l + 1);
+    let preview_chars = if get_bool(&args, "full").unwrap_or(false) {
+        0
+    } else {
+        get_i32(&args, "preview_chars")
+            .map(|n| n.max(0) as usize)
+            .unwrap_or(limits.description_preview_chars)
+    };
+    let select = FieldSelection::new(&get_string_array(&args, "select").unwrap_or_default());
+    let group_by = match get_string(&args, "group_by") {
+        Some(s) => Some(GroupBy::parse(&s).ok_or_else(|| {
+            ToolError::invalid_value("group_by", "Expected status, agent, tag, or parent")
+        })?),
+        None => None,
+    };
     let phase = get_string(&args, "phase");
+    let blocked_reason = get_string(&args, "blocked_reason");
+    let needed_group = get_string(&args, "needed_group");
+    let project = get_project(&args)?;
+    let as_of = get_as_of(&args)?;
+    if as_of.is_some() && (ready || blocked) {
+        return Err(ToolError::invalid_value(
+            "as_of",
+            "as_of cannot be combined with ready or blocked",
+        )
+        .into());
+    }
+    let filter = match get_string(&args, "where") {
+        Some(expr) => {
+            let mut filter = TaskFilter::parse(&expr, priorities)
+                .map_err(|e| ToolError::invalid_value("where", &e.to_string()))?;
+            filter.map_tags(&|tag| config.tags.canonical_tag(tag));
+            Some(filter)
+        }
+        None => None,
+    };
+    if as_of.is_some() && filter.is_some() {
+        return Err(
+            ToolError::invalid_value("where", "where cannot be combined with as_of").into(),
+        );
+    }
 
-    // Extract tag filtering parameters
-    let tags_any = get_string_array(&args, "tags_any");
-    let tags_all = get_string_array(&args, "tags_all");
+    // Extract tag filtering parameters, spelled as tags are stored
+    let tags_any = get_string_array(&args, "tags_any").map(|t| config.tags.canonical_tags(&t));
+    let tags_all = get_string_array(&args, "tags_all").map(|t| config.tags.canonical_tags(&t));
+
+    // Custom field equality filters, encoded the way they are stored
+    let field_filters: Vec<(String, String)> = get_fields(&args)?
+        .map(|f| {
+            f.iter()
+                .map(|(name, value)| (name.clone(), encode_field_value(value)))
+                .collect()
+        })
+        .unwrap_or_default();
 
     // 'agent' replaces both 'worker_id' and 'qualified_for' - single param for agent-related filtering
     let agent_id = get_string(&args, "agent");
 
+    // Visibility rules follow the caller, else the agent being filtered for
+    let viewer = get_string(&args, "worker_id").or_else(|| agent_id.clone());
+    let scope = db.caller_scope(viewer.as_deref(), visibility)?;
+    let scope = scope.as_ref();
+
     // Sorting parameters
     let sort_by = get_string(&args, "sort_by");
     let sort_order = get_string(&args, "sort_order");
@@ -637,106 +1806,241 @@ pub fn list_tasks(
     // Get tasks based on filters
     let parent_id_str = get_string(&args, "parent");
 
-    let mut tasks =
-        if recursive && parent_id_str.is_some() && parent_id_str.as_deref() != Some("null") {
-            // Recursive descent: get all descendants of the parent via contains dependencies
-            let pid = parent_id_str.as_deref().unwrap();
-            let mut descendants = db.get_descendants(pid, -1)?;
-
-            // Apply status filter in memory
-            if let Some(status_set) = get_string_or_array(&args, "status")
-                && !status_set.is_empty()
-            {
-                descendants.retain(|t| status_set.contains(&t.status));
+    // Custom fields of past states (as_of); current fields are read from the database
+    let mut past_fields: Option<HashMap<String, Map<String, Value>>> = None;
+    // Set when list_tasks applied every filter in SQL, so none is repeated below
+    let mut filtered_in_sql = false;
+
+    let mut tasks = if let Some(at) = as_of {
+        // Time travel: rebuild every task that existed then and filter in memory
+        let (mut tasks, fields): (Vec<Task>, HashMap<_, _>) = db
+            .get_tasks_as_of(at, states_config)?
+            .into_iter()
+            .map(|(task, fields)| {
+                let id = task.id.clone();
+                (task, (id, fields))
+            })
+            .unzip();
+        past_fields = Some(fields);
+
+        if let Some(status_set) = get_string_or_array(&args, "status")
+            && !status_set.is_empty()
+        {
+            tasks.retain(|t| status_set.contains(&t.status));
+        }
+        if let Some(ref owner) = get_string(&args, "owner") {
+            tasks.retain(|t| t.worker_id.as_deref() == Some(owner.as_str()));
+        }
+        if claimed {
+            tasks.retain(|t| t.worker_id.is_some());
+        }
+        match parent_id_str.as_deref() {
+            Some("null") => {
+                let mut roots = Vec::with_capacity(tasks.len());
+                for task in tasks {
+                    if db.get_parent(&task.id)?.is_none() {
+                        roots.push(task);
+                    }
+                }
+                tasks = roots;
             }
-
-            // Apply owner filter in memory
-            if let Some(ref owner) = get_string(&args, "owner") {
-                descendants.retain(|t| t.worker_id.as_deref() == Some(owner.as_str()));
+            Some(pid) => {
+                let children: HashSet<String> = if recursive {
+                    db.get_descendants(pid, -1)?
+                        .into_iter()
+                        .map(|t| t.id)
+                        .collect()
+                } else {
+                    db.get_children_ids(pid)?.into_iter().collect()
+                };
+                tasks.retain(|t| children.contains(&t.id));
             }
+            None => {}
+        }
+        if let Some(ref any) = tags_any {
+            tasks.retain(|t| any.iter().any(|tag| has_tag(&t.tags, tag)));
+        }
+        if let Some(ref all) = tags_all {
+            tasks.retain(|t| all.iter().all(|tag| has_tag(&t.tags, tag)));
+        }
 
-            descendants
-        } else if ready {
-            // Ready tasks: in initial state, unclaimed, all deps satisfied
-            // If agent is provided, also filter by agent's tag qualifications
-            db.get_ready_tasks(
-                agent_id.as_deref(),
-                states_config,
-                deps_config,
-                sort_by.as_deref(),
-                sort_order.as_deref(),
-            )?
-        } else if blocked {
-            // Blocked tasks: have unsatisfied deps
-            db.get_blocked_tasks(
-                states_config,
-                deps_config,
-                sort_by.as_deref(),
-                sort_order.as_deref(),
-            )?
-        } else if claimed {
-            // Claimed tasks: currently owned by any agent
-            db.get_claimed_tasks(None)?
-        } else {
-            // General query with filters
-            let status_vec = get_string_or_array(&args, "status");
-            let owner = get_string(&args, "owner");
-            let parent_id: Option<Option<&str>> = match &parent_id_str {
-                Some(pid_str) if pid_str == "null" => Some(None), // Root tasks
-                Some(pid_str) => Some(Some(pid_str.as_str())),
-                None => None,
-            };
+        let descending = sort_order.as_deref() != Some("asc");
+        match sort_by.as_deref() {
+            Some("priority") => tasks.sort_by(|a, b| {
+                b.priority
+                    .cmp(&a.priority)
+                    .then(b.created_at.cmp(&a.created_at))
+            }),
+            Some("updated_at") => tasks.sort_by_key(|t| t.updated_at),
+            _ => tasks.sort_by_key(|t| t.created_at),
+        }
+        if descending && sort_by.as_deref() != Some("priority") {
+            tasks.reverse();
+        }
 
-            // Check if tag filtering or agent qualification filtering is needed
-            let has_tag_filters = tags_any.is_some() || tags_all.is_some() || agent_id.is_some();
+        db.retain_visible(&mut tasks, scope)?;
+        tasks
+    } else if recursive && parent_id_str.is_some() && parent_id_str.as_deref() != Some("null") {
+        // Recursive descent: get all descendants of the parent via contains dependencies
+        let pid = parent_id_str.as_deref().unwrap();
+        let mut descendants = db.get_descendants(pid, -1)?;
 
-            if has_tag_filters {
-                // Use the tag-filtered query
-                // When agent is provided without ready=true, filter by agent's qualification
-                let qualified_agent_tags = if let Some(aid) = &agent_id {
-                    Some(db.get_agent_tags(aid)?)
-                } else {
-                    None
-                };
+        // Apply status filter in memory
+        if let Some(status_set) = get_string_or_array(&args, "status")
+            && !status_set.is_empty()
+        {
+            descendants.retain(|t| status_set.contains(&t.status));
+        }
 
-                db.list_tasks_with_tag_filters(
-                    status_vec,
-                    owner.as_deref(),
-                    parent_id,
-                    tags_any,
-                    tags_all,
-                    qualified_agent_tags,
-                    fetch_limit,
-                    offset,
-                    sort_by.as_deref(),
-                    sort_order.as_deref(),
-                )?
+        // Apply owner filter in memory
+        if let Some(ref owner) = get_string(&args, "owner") {
+            descendants.retain(|t| t.worker_id.as_deref() == Some(owner.as_str()));
+        }
+
+        db.retain_visible(&mut descendants, scope)?;
+        descendants
+    } else if ready {
+        // Ready tasks: in initial state, unclaimed, all deps satisfied
+        // If agent is provided, also filter by agent's tag qualifications
+        let mut tasks = db.get_ready_tasks(
+            agent_id.as_deref(),
+            states_config,
+            deps_config,
+            sort_by.as_deref(),
+            sort_order.as_deref(),
+            scope,
+        )?;
+        // Default ready ordering weighs deadlines against priority
+        if sort_by.is_none() {
+            db.order_by_urgency(&mut tasks, priorities)?;
+        }
+        tasks
+    } else if blocked {
+        // Blocked tasks: have unsatisfied deps
+        db.get_blocked_tasks(
+            states_config,
+            deps_config,
+            sort_by.as_deref(),
+            sort_order.as_deref(),
+            scope,
+        )?
+    } else if claimed {
+        // Claimed tasks: currently owned by any agent
+        db.get_claimed_tasks(None, scope)?
+    } else {
+        // General query with filters
+        let status_vec = get_string_or_array(&args, "status");
+        let owner = get_string(&args, "owner");
+        let parent_id: Option<Option<&str>> = match &parent_id_str {
+            Some(pid_str) if pid_str == "null" => Some(None), // Root tasks
+            Some(pid_str) => Some(Some(pid_str.as_str())),
+            None => None,
+        };
+
+        // Check if tag filtering or agent qualification filtering is needed
+        let has_tag_filters = tags_any.is_some() || tags_all.is_some() || agent_id.is_some();
+
+        if has_tag_filters {
+            // Use the tag-filtered query
+            // When agent is provided without ready=true, filter by agent's qualification
+            let qualified_agent_tags = if let Some(aid) = &agent_id {
+                Some(db.get_agent_tags(aid)?)
             } else {
-                // Use list_tasks which returns full Task objects (only supports single status)
-                let status = status_vec
-                    .as_ref()
-                    .and_then(|v| v.first().map(|s| s.as_str()));
-                db.list_tasks(ListTasksQuery {
-                    status,
-                    phase: phase.as_deref(),
-                    owner: owner.as_deref(),
-                    parent_id,
-                    limit: fetch_limit,
-                    offset,
-                    sort_by: sort_by.as_deref(),
-                    sort_order: sort_order.as_deref(),
-                })?
+                None
+            };
+
+            let mut tasks = db.list_tasks_with_tag_filters(
+                status_vec,
+                owner.as_deref(),
+                parent_id,
+                tags_any,
+                tags_all,
+                qualified_agent_tags,
+                fetch_limit,
+                offset,
+                sort_by.as_deref(),
+                sort_order.as_deref(),
+                scope,
+                filter.as_ref(),
+            )?;
+
+            // Qualification also covers needed_group membership
+            if let Some(aid) = &agent_id {
+                let groups = db.get_worker_groups(aid)?;
+                tasks.retain(|t| t.needed_group.as_ref().is_none_or(|g| groups.contains(g)));
             }
-        };
+            tasks
+        } else {
+            // Use list_tasks which returns full Task objects (only supports single status)
+            let status = status_vec
+                .as_ref()
+                .and_then(|v| v.first().map(|s| s.as_str()));
+            filtered_in_sql = true;
+            db.list_tasks(ListTasksQuery {
+                status,
+                phase: phase.as_deref(),
+                owner: owner.as_deref(),
+                parent_id,
+                limit: fetch_limit,
+                offset,
+                sort_by: sort_by.as_deref(),
+                sort_order: sort_order.as_deref(),
+                fields: &field_filters,
+                blocked_reason: blocked_reason.as_deref(),
+                needed_group: needed_group.as_deref(),
+                project: project.as_deref(),
+                scope,
+                filter: filter.as_ref(),
+            })?
+        }
+    };
+
+    // The general query paths apply the filter in SQL; the others fetch
+    // everything matching and are filtered here
+    if let Some(ref filter) = filter
+        && (recursive || ready || blocked || claimed)
+    {
+        db.retain_matching(&mut tasks, filter)?;
+    }
+
+    // The other paths fetch without these filters and apply them here
+    if !filtered_in_sql {
+        if let Some(ref p) = phase {
+            tasks.retain(|t| t.phase.as_deref() == Some(p.as_str()));
+        }
+        if let Some(ref r) = blocked_reason {
+            tasks.retain(|t| t.blocked_reason.as_deref() == Some(r.as_str()));
+        }
+        if let Some(ref g) = needed_group {
+            tasks.retain(|t| t.needed_group.as_deref() == Some(g.as_str()));
+        }
+        if let Some(ref p) = project {
+            tasks.retain(|t| t.project == *p);
+        }
 
-    // Apply phase filter for ready/blocked/claimed paths (list_tasks handles it internally)
-    if let Some(ref p) = phase {
-        tasks.retain(|t| t.phase.as_deref() == Some(p.as_str()));
+        if !field_filters.is_empty() {
+            let ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+            let current_fields;
+            let task_fields = match past_fields {
+                Some(ref fields) => fields,
+                None => {
+                    current_fields = db.get_task_fields_batch(&ids)?;
+                    &current_fields
+                }
+            };
+            tasks.retain(|t| {
+                task_fields.get(&t.id).is_some_and(|f| {
+                    field_filters.iter().all(|(name, value)| {
+                        f.get(name).is_some_and(|v| &encode_field_value(v) == value)
+                    })
+                })
+            });
+        }
     }
 
     // Apply offset for paths that don't go through paginated DB queries
-    // (ready, blocked, claimed, recursive paths fetch all matching tasks)
-    if offset > 0 && (ready || blocked || claimed || recursive) {
+    // (as_of, ready, blocked, claimed, recursive paths fetch all matching tasks)
+    if offset > 0 && (as_of.is_some() || ready || blocked || claimed || recursive) {
         if (offset as usize) < tasks.len() {
             tasks = tasks.split_off(offset as usize);
         } else {
@@ -749,6 +2053,25 @@ pub fn list_tasks(
     if let Some(l) = limit {
         tasks.truncate(l as usize);
     }
+    for task in &mut tasks {
+        preview_task_description(task, preview_chars);
+    }
+
+    // Custom fields are shown in JSON unless omitted, and in compact rows only
+    // when selected by name
+    let wants_fields = match format {
+        OutputFormat::Json => select.includes("fields"),
+        OutputFormat::Compact => select.names("fields"),
+        OutputFormat::Markdown => false,
+    };
+    let fields_by_task = if !wants_fields {
+        Default::default()
+    } else if let Some(fields) = past_fields {
+        fields
+    } else {
+        let ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+        db.get_task_fields_batch(&ids)?
+    };
 
     // Get blockers for each task
     let tasks_with_blockers: Vec<_> = tasks
@@ -759,9 +2082,112 @@ pub fn list_tasks(
         })
         .collect();
 
+    // Reserved tasks show who holds them
+    let reservations = db.get_active_reservations()?;
+
+    // Ready tasks for an agent carry its weighted tag match score
+    let match_tags = match (&agent_id, ready) {
+        (Some(aid), true) => Some(db.get_agent_tags(aid)?),
+        _ => None,
+    };
+
+    let task_values = || -> Vec<Value> {
+        tasks_with_blockers
+            .iter()
+            .map(|(task, blockers)| {
+                let mut task_json = serde_json::to_value(task).unwrap();
+                if let Some(obj) = task_json.as_object_mut() {
+                    obj.insert("blocked_by".to_string(), json!(blockers));
+                    if let Some(r) = reservations.get(&task.id) {
+                        obj.insert("reserved_by".to_string(), json!(r.worker_id));
+                        obj.insert("reserved_until".to_string(), json!(r.expires_at));
+                    }
+                    if let Some(ref agent_tags) = match_tags {
+                        let score = tag_match_score(task, agent_tags, &config.tags);
+                        obj.insert(
+                            "match_score".to_string(),
+                            json!((score * 1000.0).round() / 1000.0),
+                        );
+                    }
+                    if let Some(fields) = fields_by_task.get(&task.id) {
+                        obj.insert("fields".to_string(), json!(fields));
+                    }
+                }
+                select.apply(&mut task_json);
+                task_json
+            })
+            .collect()
+    };
+
+    if let Some(group_by) = group_by {
+        let groups = group_tasks(db, &tasks_with_blockers, group_by, states_config)?;
+        return Ok(match format {
+            OutputFormat::Compact => {
+                let next_offset = has_more.then(|| offset + limit.unwrap_or(0));
+                compact_to_json(format_task_groups_compact(
+                    &task_values(),
+                    &groups,
+                    &select,
+                    next_offset,
+                ))
+            }
+            OutputFormat::Markdown => {
+                let mut md = format_task_groups_markdown(
+                    &tasks_with_blockers,
+                    group_by,
+                    &groups,
+                    priorities,
+                    &select,
+                );
+                if has_more {
+                    let next_offset = offset + limit.unwrap_or(0);
+                    md.push_str(&format!(
+                        "\n*More results available. Use offset={} to see next page.*",
+                        next_offset
+                    ));
+                }
+                markdown_to_json(md)
+            }
+            OutputFormat::Json => {
+                let values = task_values();
+                let groups: Vec<Value> = groups
+                    .iter()
+                    .map(|group| {
+                        let mut obj = json!({
+                            "key": group.key,
+                            "count": group.tasks.len(),
+                            "tasks": group.tasks.iter().map(|&i| &values[i]).collect::<Vec<_>>(),
+                        });
+                        if let Some(title) = &group.title {
+                            obj["title"] = json!(title);
+                        }
+                        obj
+                    })
+                    .collect();
+                json!({
+                    "group_by": group_by.as_str(),
+                    "total": values.len(),
+                    "groups": groups,
+                    "has_more": has_more,
+                    "offset": offset,
+                    "limit": limit,
+                })
+            }
+        });
+    }
+
     match format {
+        OutputFormat::Compact => {
+            let next_offset = has_more.then(|| offset + limit.unwrap_or(0));
+            Ok(compact_to_json(format_tasks_compact(
+                &task_values(),
+                &select,
+                next_offset,
+            )))
+        }
         OutputFormat::Markdown => {
-            let mut md = format_tasks_markdown(&tasks_with_blockers, states_config);
+            let mut md =
+                format_tasks_markdown(&tasks_with_blockers, states_config, priorities, &select);
             if has_more {
                 let next_offset = offset + limit.unwrap_or(0);
                 md.push_str(&format!(
@@ -772,13 +2198,7 @@ pub fn list_tasks(
             Ok(markdown_to_json(md))
         }
         OutputFormat::Json => Ok(json!({
-            "tasks": tasks_with_blockers.iter().map(|(task, blockers)| {
-                let mut task_json = serde_json::to_value(task).unwrap();
-                if let Some(obj) = task_json.as_object_mut() {
-                    obj.insert("blocked_by".to_string(), json!(blockers));
-                }
-                task_json
-            }).collect::<Vec<_>>(),
+            "tasks": task_values(),
             "has_more": has_more,
             "offset": offset,
             "limit": limit,
@@ -786,6 +2206,75 @@ pub fn list_tasks(
     }
 }
 
+/// Replace a task's description with its preview if it is longer than `max_chars`.
+fn preview_task_description(task: &mut Task, max_chars: usize) {
+    if let Some(preview) = task
+        .description
+        .as_deref()
+        .and_then(|d| crate::format::preview_description(d, max_chars))
+    {
+        task.description = Some(preview);
+    }
+}
+
+/// Bucket listed tasks for `group_by`. Statuses follow the display order of
+/// task lists; other keys are sorted, with the keyless bucket last.
+fn group_tasks(
+    db: &Database,
+    tasks: &[(Task, Vec<String>)],
+    group_by: GroupBy,
+    states_config: &StatesConfig,
+) -> Result<Vec<TaskGroup>> {
+    let mut buckets: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    let mut keyless = Vec::new();
+    for (i, (task, _)) in tasks.iter().enumerate() {
+        let keys = match group_by {
+            GroupBy::Status => vec![task.status.clone()],
+            GroupBy::Agent => task.worker_id.iter().cloned().collect(),
+            GroupBy::Tag => task.tags.clone(),
+            GroupBy::Parent => db.get_parent(&task.id)?.into_iter().collect(),
+        };
+        if keys.is_empty() {
+            keyless.push(i);
+        }
+        for key in keys {
+            buckets.entry(key).or_default().push(i);
+        }
+    }
+
+    let mut groups = Vec::with_capacity(buckets.len() + 1);
+    if group_by == GroupBy::Status {
+        for state in state_display_order(states_config) {
+            if let Some(indexes) = buckets.remove(state) {
+                groups.push(TaskGroup {
+                    key: Some(state.to_string()),
+                    title: None,
+                    tasks: indexes,
+                });
+            }
+        }
+    }
+    for (key, indexes) in buckets {
+        let title = match group_by {
+            GroupBy::Parent => db.get_task(&key)?.map(|t| t.title),
+            _ => None,
+        };
+        groups.push(TaskGroup {
+            key: Some(key),
+            title,
+            tasks: indexes,
+        });
+    }
+    if !keyless.is_empty() {
+        groups.push(TaskGroup {
+            key: None,
+            title: None,
+            tasks: keyless,
+        });
+    }
+    Ok(groups)
+}
+
 pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
     let UpdateOptions {
         db,
@@ -812,35 +2301,97 @@ pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
     } else {
         None
     };
+    if let Some(ref t) = title {
+        config.limits.check_title(t)?;
+    }
+    if let Some(Some(ref d)) = description {
+        config.limits.check_description(d)?;
+    }
     let status = get_string(&args, "status");
     let phase = get_string(&args, "phase");
-    // Support both integer and string priority
-    let priority = get_i32(&args, "priority")
-        .or_else(|| get_string(&args, "priority").map(|s| parse_priority(&s)));
+    let priority = args
+        .get("priority")
+        .filter(|v| !v.is_null())
+        .map(|v| parse_priority_arg(&config.priorities, v))
+        .transpose()?;
     let points = if args.get("points").is_some() {
         Some(get_i32(&args, "points"))
     } else {
         None
     };
-    let tags = if args.get("tags").is_some() {
-        Some(get_string_array(&args, "tags").unwrap_or_default())
-    } else {
-        None
-    };
-    let needed_tags = if args.get("needed_tags").is_some() {
-        Some(get_string_array(&args, "needed_tags").unwrap_or_default())
-    } else {
-        None
-    };
-    let wanted_tags = if args.get("wanted_tags").is_some() {
-        Some(get_string_array(&args, "wanted_tags").unwrap_or_default())
-    } else {
-        None
+    let canonical = |name: &str| {
+        args.get(name)
+            .is_some()
+            .then(|| tags_config.canonical_tags(&get_string_array(&args, name).unwrap_or_default()))
     };
+    let tags = canonical("tags");
+    let needed_tags = canonical("needed_tags");
+    let wanted_tags = canonical("wanted_tags");
     let time_estimate_ms = get_i64(&args, "time_estimate_ms");
     let reason = get_string(&args, "reason");
     let force = get_bool(&args, "force").unwrap_or(false);
 
+    // Blocked reason: absent = unchanged, null = clear, string = one of BLOCKED_REASONS
+    let blocked_reason: Option<Option<String>> = match args.get("blocked_reason") {
+        None => None,
+        Some(Value::Null) => Some(None),
+        Some(Value::String(r)) if BLOCKED_REASONS.contains(&r.as_str()) => Some(Some(r.clone())),
+        Some(_) => {
+            return Err(ToolError::invalid_value(
+                "blocked_reason",
+                &format!(
+                    "blocked_reason must be one of: {}",
+                    BLOCKED_REASONS.join(", ")
+                ),
+            )
+            .into());
+        }
+    };
+    let blocked_note: Option<Option<String>> = args
+        .get("blocked_note")
+        .map(|v| v.as_str().map(String::from));
+    // Needed group: absent = unchanged, null = clear
+    let needed_group: Option<Option<String>> = args
+        .get("needed_group")
+        .map(|v| v.as_str().map(String::from));
+    let wanted_optional = get_bool(&args, "wanted_optional");
+    let required_child_tags = canonical("required_child_tags");
+    let ops = parse_ops(&args, tags_config)?;
+    // Checked up front so a stale caller hears about it before any other
+    // validation, then again inside the update's transaction
+    let preconditions = parse_preconditions(&args)?;
+    if preconditions.status.is_some() || preconditions.owner.is_some() {
+        let current = db
+            .get_task(&task_id)?
+            .ok_or_else(|| ToolError::task_not_found(&task_id))?;
+        preconditions.check(&current)?;
+    }
+    // Tag ops are checked against the tags they would leave on the task
+    let ops_tags = match ops {
+        Some(ref ops) if ops.iter().any(|op| op.target() == "tags") => {
+            let current = db
+                .get_task(&task_id)?
+                .ok_or_else(|| ToolError::task_not_found(&task_id))?;
+            Some(ops_tag_result(current.tags, ops))
+        }
+        _ => None,
+    };
+    if let Some(t) = tags.as_ref().or(ops_tags.as_ref()) {
+        let parent = db.get_parent(&task_id)?;
+        check_required_tags(db, config, &workflows.required_tags, parent.as_deref(), t)?;
+    }
+    if matches!(blocked_reason, Some(Some(_)))
+        && status
+            .as_deref()
+            .is_some_and(|s| !states_config.is_blocking_state(s))
+    {
+        return Err(ToolError::invalid_value(
+            "blocked_reason",
+            "Cannot set a blocked reason while finishing the task",
+        )
+        .into());
+    }
+
     // Process attachments first (before the update)
     let mut attachment_results: Vec<Value> = Vec::new();
     let mut attachment_warnings: Vec<String> = Vec::new();
@@ -959,6 +2510,24 @@ pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
     if let Some(ref t) = wanted_tags {
         tag_warnings.extend(tags_config.validate_tags(t)?);
     }
+    let mut op_fields = serde_json::Map::new();
+    for op in ops.iter().flatten() {
+        match op {
+            TaskOp::AddTags(_, t) => tag_warnings.extend(tags_config.validate_tags(t)?),
+            TaskOp::SetField(name, value) => {
+                op_fields.insert(name.clone(), value.clone());
+            }
+            _ => {}
+        }
+    }
+
+    // Check custom field values
+    let fields = get_fields(&args)?;
+    let mut field_warnings = match fields {
+        Some(ref f) => config.custom_fields.validate_fields(f)?,
+        None => Vec::new(),
+    };
+    field_warnings.extend(config.custom_fields.validate_fields(&op_fields)?);
 
     // Check exit gates for status transitions
     let mut gate_warnings: Vec<String> = Vec::new();
@@ -979,44 +2548,46 @@ pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
                 // Convert references to owned GateDefinitions for evaluate_gates
                 let gates_owned: Vec<crate::config::GateDefinition> =
                     exit_gates.iter().map(|g| (*g).clone()).collect();
-                let gate_result = evaluate_gates(db, &task_id, &gates_owned)?;
+                let gate_result = evaluate_gates(
+                    db,
+                    &task_id,
+                    &gates_owned,
+                    CommandGates::Run(&config.attachments),
+                )?;
 
                 match gate_result.status.as_str() {
                     "fail" => {
                         // Reject-level gates unsatisfied - cannot proceed
-                        let gate_names: Vec<String> = gate_result
+                        let failing: Vec<_> = gate_result
                             .unsatisfied_gates
                             .iter()
                             .filter(|g| g.enforcement == GateEnforcement::Reject)
-                            .map(|g| format!("{} ({})", g.gate_type, g.description))
                             .collect();
+                        let gate_names: Vec<String> = failing.iter().map(|g| g.label()).collect();
+                        let how_to_fix: Vec<String> =
+                            failing.iter().map(|g| g.fix_hint(&task_id)).collect();
                         return Err(ToolError::gates_not_satisfied(
                             &current_task.status,
                             &gate_names,
+                            &how_to_fix,
+                            fix_suggestion(&failing, &task_id, "retry the transition."),
                         )
                         .into());
                     }
                     "warn" => {
                         // Warn-level gates unsatisfied
-                        let warn_gates: Vec<String> = gate_result
+                        let warn_results: Vec<_> = gate_result
                             .unsatisfied_gates
                             .iter()
                             .filter(|g| g.enforcement == GateEnforcement::Warn)
-                            .map(|g| format!("{} ({})", g.gate_type, g.description))
                             .collect();
+                        let warn_gates: Vec<String> =
+                            warn_results.iter().map(|g| g.label()).collect();
 
                         if !force {
                             // Cannot proceed without force flag - include actionable guidance
-                            let how_to_fix: Vec<String> = warn_gates
-                                .iter()
-                                .map(|g| {
-                                    let gate_type = g.split(" (").next().unwrap_or(g);
-                                    format!(
-                                        "  - attach(task=\"{}\", type=\"{}\", content=\"...\")",
-                                        task_id, gate_type
-                                    )
-                                })
-                                .collect();
+                            let how_to_fix: Vec<String> =
+                                warn_results.iter().map(|g| g.fix_hint(&task_id)).collect();
                             return Err(ToolError::new(
                                 crate::error::ErrorCode::GatesNotSatisfied,
                                 format!(
@@ -1026,12 +2597,14 @@ pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
                                 ),
                             )
                             .with_details(format!(
-                                "Satisfy these gates by attaching the required artifacts:\n{}\n\nOr pass force=true with a reason to skip warn-level gates.",
+                                "Satisfy these gates:\n{}\n\nOr pass force=true with a reason to skip warn-level gates.",
                                 how_to_fix.join("\n")
                             ))
-                            .with_suggestion(
-                                "Attach the required gate artifacts and retry, or use update(..., force=true, reason=\"why skipping\") to proceed.".to_string(),
-                            )
+                            .with_suggestion(fix_suggestion(
+                                &warn_results,
+                                &task_id,
+                                "retry, or use update(..., force=true, reason=\"why skipping\") to proceed.",
+                            ))
                             .into());
                         }
                         // force=true: proceed but include warning and log for audit
@@ -1091,27 +2664,24 @@ pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
                 // Convert references to owned GateDefinitions for evaluate_gates
                 let gates_owned: Vec<crate::config::GateDefinition> =
                     exit_gates.iter().map(|g| (*g).clone()).collect();
-                let gate_result = evaluate_gates(db, &task_id, &gates_owned)?;
+                let gate_result = evaluate_gates(
+                    db,
+                    &task_id,
+                    &gates_owned,
+                    CommandGates::Run(&config.attachments),
+                )?;
 
                 match gate_result.status.as_str() {
                     "fail" => {
                         // Reject-level gates unsatisfied - cannot proceed
-                        let gate_names: Vec<String> = gate_result
+                        let failing: Vec<_> = gate_result
                             .unsatisfied_gates
                             .iter()
                             .filter(|g| g.enforcement == GateEnforcement::Reject)
-                            .map(|g| format!("{} ({})", g.gate_type, g.description))
-                            .collect();
-                        let how_to_fix: Vec<String> = gate_names
-                            .iter()
-                            .map(|g| {
-                                let gate_type = g.split(" (").next().unwrap_or(g);
-                                format!(
-                                    "  - attach(task=\"{}\", type=\"{}\", content=\"...\")",
-                                    task_id, gate_type
-                                )
-                            })
                             .collect();
+                        let gate_names: Vec<String> = failing.iter().map(|g| g.label()).collect();
+                        let how_to_fix: Vec<String> =
+                            failing.iter().map(|g| g.fix_hint(&task_id)).collect();
                         return Err(ToolError::new(
                             crate::error::ErrorCode::GatesNotSatisfied,
                             format!(
@@ -1124,33 +2694,27 @@ pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
                             "These are reject-level gates and cannot be skipped. Satisfy them:\n{}",
                             how_to_fix.join("\n")
                         ))
-                        .with_suggestion(
-                            "Attach the required gate artifacts, then retry the phase transition."
-                                .to_string(),
-                        )
+                        .with_suggestion(fix_suggestion(
+                            &failing,
+                            &task_id,
+                            "retry the phase transition.",
+                        ))
                         .into());
                     }
                     "warn" => {
                         // Warn-level gates unsatisfied
-                        let warn_gates: Vec<String> = gate_result
+                        let warn_results: Vec<_> = gate_result
                             .unsatisfied_gates
                             .iter()
                             .filter(|g| g.enforcement == GateEnforcement::Warn)
-                            .map(|g| format!("{} ({})", g.gate_type, g.description))
                             .collect();
+                        let warn_gates: Vec<String> =
+                            warn_results.iter().map(|g| g.label()).collect();
 
                         if !force {
                             // Cannot proceed without force flag - include actionable guidance
-                            let how_to_fix: Vec<String> = warn_gates
-                                .iter()
-                                .map(|g| {
-                                    let gate_type = g.split(" (").next().unwrap_or(g);
-                                    format!(
-                                        "  - attach(task=\"{}\", type=\"{}\", content=\"...\")",
-                                        task_id, gate_type
-                                    )
-                                })
-                                .collect();
+                            let how_to_fix: Vec<String> =
+                                warn_results.iter().map(|g| g.fix_hint(&task_id)).collect();
                             return Err(ToolError::new(
                                     crate::error::ErrorCode::GatesNotSatisfied,
                                     format!(
@@ -1160,12 +2724,14 @@ pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
                                     ),
                                 )
                                 .with_details(format!(
-                                    "Satisfy these gates by attaching the required artifacts:\n{}\n\nOr pass force=true with a reason to skip warn-level gates.",
+                                    "Satisfy these gates:\n{}\n\nOr pass force=true with a reason to skip warn-level gates.",
                                     how_to_fix.join("\n")
                                 ))
-                                .with_suggestion(
-                                    "Attach the required gate artifacts and retry, or use update(..., force=true, reason=\"why skipping\") to proceed.".to_string(),
-                                )
+                                .with_suggestion(fix_suggestion(
+                                    &warn_results,
+                                    &task_id,
+                                    "retry, or use update(..., force=true, reason=\"why skipping\") to proceed.",
+                                ))
                                 .into());
                         }
                         // force=true: proceed but include warning and log for audit
@@ -1236,8 +2802,17 @@ pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
         }
     };
 
+    // Soft dependencies are reported (not enforced) when entering a timed state
+    let entering_timed = status
+        .as_deref()
+        .is_some_and(|s| states_config.is_timed_state(s));
+
+    // Snapshot for the field-level change history
+    let before = db.get_task(&task_id)?;
+    let fields_before = db.get_task_fields(&task_id)?;
+
     // Perform the task update
-    let (task, unblocked, auto_advanced) = db.update_task_unified(
+    let (task, unblocked, auto_advanced) = db.update_task_unified_if(
         &task_id,
         &worker_id,
         assignee.as_deref(),
@@ -1251,13 +2826,97 @@ pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
         needed_tags,
         wanted_tags,
         time_estimate_ms,
+        fields.as_ref(),
         audit_reason,
         force,
         states_config,
         deps_config,
         auto_advance,
+        &preconditions,
     )?;
 
+    // Partial edits apply to the task as it now stands, all or nothing
+    let ops_edit_fields = ops.iter().flatten().any(|op| op.target() == "fields");
+    let task = match ops {
+        Some(ref ops) if !ops.is_empty() => {
+            db.apply_task_ops(&task_id, &worker_id, force, ops, &config.limits)?
+        }
+        _ => task,
+    };
+
+    // Apply blocked reason after ownership checks; a bare note keeps the current reason
+    let task = if blocked_reason.is_some() || blocked_note.is_some() {
+        let (new_reason, new_note) = match blocked_reason {
+            Some(None) => (None, None),
+            Some(Some(r)) => (
+                Some(r),
+                blocked_note.unwrap_or_else(|| task.blocked_note.clone()),
+            ),
+            None => (task.blocked_reason.clone(), blocked_note.flatten()),
+        };
+        db.set_blocked_reason(&task_id, new_reason.as_deref(), new_note.as_deref())?;
+        crate::types::Task {
+            blocked_reason: new_reason,
+            blocked_note: new_note,
+            ..task
+        }
+    } else {
+        task
+    };
+
+    let task = if let Some(group) = needed_group {
+        db.set_needed_group(&task_id, group.as_deref())?;
+        crate::types::Task {
+            needed_group: group,
+            ..task
+        }
+    } else {
+        task
+    };
+
+    let task = if let Some(optional) = wanted_optional {
+        db.set_wanted_optional(&task_id, optional)?;
+        crate::types::Task {
+            wanted_optional: optional,
+            ..task
+        }
+    } else {
+        task
+    };
+
+    let task = if let Some(rules) = required_child_tags {
+        db.set_required_child_tags(&task_id, &rules)?;
+        crate::types::Task {
+            required_child_tags: rules,
+            ..task
+        }
+    } else {
+        task
+    };
+
+    if let Some(ref before) = before {
+        let fields_after = if fields.is_some() || ops_edit_fields {
+            db.get_task_fields(&task_id)?
+        } else {
+            fields_before.clone()
+        };
+        if let Err(e) = db.record_task_changes(
+            Some(&worker_id),
+            before,
+            &fields_before,
+            &task,
+            &fields_after,
+        ) {
+            warn!(task = %task_id, error = %e, "Failed to record task changes");
+        }
+    }
+
+    let dependency_warnings = if entering_timed {
+        super::claiming::soft_dependency_warnings(db, &task_id, states_config, deps_config)?
+    } else {
+        Vec::new()
+    };
+
     // Pre-fetch worker info for context-sensitive prompts (must outlive ctx)
     let worker_info_for_prompts = db.get_worker(&worker_id).ok().flatten();
     let worker_role_for_prompts = worker_info_for_prompts
@@ -1333,10 +2992,24 @@ pub fn update(opts: UpdateOptions<'_>, args: Value) -> Result<Value> {
         if !tag_warnings.is_empty() {
             map.insert("tag_warnings".to_string(), json!(tag_warnings));
         }
+        // Include the resulting custom fields and any warnings if fields were updated
+        if fields.is_some() {
+            map.insert("fields".to_string(), json!(db.get_task_fields(&task_id)?));
+        }
+        if !field_warnings.is_empty() {
+            map.insert("field_warnings".to_string(), json!(field_warnings));
+        }
         // Include gate warnings if any
         if !gate_warnings.is_empty() {
             map.insert("gate_warnings".to_string(), json!(gate_warnings));
         }
+        // Include unsatisfied soft dependencies if any
+        if !dependency_warnings.is_empty() {
+            map.insert(
+                "dependency_warnings".to_string(),
+                json!(dependency_warnings),
+            );
+        }
         // Add role-specific prompt based on the new status
         // Uses pre-fetched worker info to avoid redundant DB lookups
         if let Some(ref role_name) = worker_role_for_prompts {
@@ -1365,17 +3038,54 @@ pub fn delete(db: &Database, args: Value) -> Result<Value> {
     let worker_id =
         get_string(&args, "worker_id").ok_or_else(|| ToolError::missing_field("worker_id"))?;
     let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
-    let cascade = get_bool(&args, "cascade").unwrap_or(false);
-    let reason = get_string(&args, "reason");
+    let cascade = match args.get("cascade") {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => DeleteCascade::Refuse,
+        Some(Value::Bool(true)) => DeleteCascade::DeleteSubtree,
+        Some(Value::String(mode)) => DeleteCascade::parse(mode).ok_or_else(|| {
+            ToolError::invalid_value(
+                "cascade",
+                "cascade must be delete-subtree, orphan, or reparent-to-grandparent",
+            )
+        })?,
+        Some(_) => {
+            return Err(ToolError::invalid_value(
+                "cascade",
+                "cascade must be a boolean or a cascade mode",
+            )
+            .into());
+        }
+    };
     let obliterate = get_bool(&args, "obliterate").unwrap_or(false);
-    let force = get_bool(&args, "force").unwrap_or(false);
+    let dry_run = get_bool(&args, "dry_run").unwrap_or(false);
+    let opts = DeleteOptions {
+        cascade,
+        reason: get_string(&args, "reason"),
+        obliterate,
+        force: get_bool(&args, "force").unwrap_or(false),
+        dry_run,
+        preconditions: parse_preconditions(&args)?,
+    };
 
-    db.delete_task(&task_id, &worker_id, cascade, reason, obliterate, force)?;
+    let result = db.delete_task_with(&task_id, &worker_id, &opts)?;
 
-    Ok(json!({
+    let mut response = json!({
         "success": true,
-        "soft_deleted": !obliterate
-    }))
+        "soft_deleted": !obliterate,
+        "cascade": cascade.as_str(),
+        "deleted_count": result.deleted.len(),
+        "deleted": result.deleted
+    });
+    if dry_run {
+        response["dry_run"] = json!(true);
+    }
+    if !result.orphaned.is_empty() {
+        response["orphaned"] = json!(result.orphaned);
+    }
+    if !result.reparented.is_empty() {
+        response["reparented"] = json!(result.reparented);
+        response["new_parent"] = json!(result.new_parent);
+    }
+    Ok(response)
 }
 
 pub fn rename(db: &Database, args: Value) -> Result<Value> {
@@ -1393,8 +3103,16 @@ pub fn rename(db: &Database, args: Value) -> Result<Value> {
     }))
 }
 
-pub fn scan(db: &Database, default_format: OutputFormat, args: Value) -> Result<Value> {
+pub fn scan(
+    db: &Database,
+    priorities: &PrioritiesConfig,
+    visibility: &VisibilityConfig,
+    default_format: OutputFormat,
+    args: Value,
+) -> Result<Value> {
     let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    let scope = db.caller_scope(get_string(&args, "worker_id").as_deref(), visibility)?;
+    let scope = scope.as_ref();
     let format = get_string(&args, "format")
         .and_then(|s| OutputFormat::parse(&s))
         .unwrap_or(default_format);
@@ -1409,12 +3127,16 @@ pub fn scan(db: &Database, default_format: OutputFormat, args: Value) -> Result<
     let root_task = db
         .get_task(&task_id)?
         .ok_or_else(|| ToolError::new(crate::error::ErrorCode::TaskNotFound, "Task not found"))?;
+    require_visible(db, &task_id, scope)?;
 
     // Traverse in each direction
-    let before = db.get_predecessors(&task_id, before_depth)?;
-    let after = db.get_successors(&task_id, after_depth)?;
-    let above = db.get_ancestors(&task_id, above_depth)?;
-    let below = db.get_descendants(&task_id, below_depth)?;
+    let mut before = db.get_predecessors(&task_id, before_depth)?;
+    let mut after = db.get_successors(&task_id, after_depth)?;
+    let mut above = db.get_ancestors(&task_id, above_depth)?;
+    let mut below = db.get_descendants(&task_id, below_depth)?;
+    for tasks in [&mut before, &mut after, &mut above, &mut below] {
+        db.retain_visible(tasks, scope)?;
+    }
 
     let result = ScanResult {
         root: root_task,
@@ -1425,7 +3147,9 @@ pub fn scan(db: &Database, default_format: OutputFormat, args: Value) -> Result<
     };
 
     match format {
-        OutputFormat::Markdown => Ok(markdown_to_json(format_scan_result_markdown(&result))),
-        OutputFormat::Json => Ok(serde_json::to_value(&result)?),
+        OutputFormat::Markdown => Ok(markdown_to_json(format_scan_result_markdown(
+            &result, priorities,
+        ))),
+        OutputFormat::Json | OutputFormat::Compact => Ok(serde_json::to_value(&result)?),
     }
 }
@@ -1,12 +1,17 @@
 //! Live status and tracking tools.
 
 use super::{
-    get_f64, get_i64, get_string, get_string_array, get_string_or_array, make_tool_with_prompts,
+    get_bool, get_f64, get_i64, get_string, get_string_array, get_string_or_array,
+    make_tool_with_prompts,
 };
-use crate::config::{Prompts, StatesConfig};
-use crate::db::Database;
+use crate::config::workflows::WorkflowsConfig;
+use crate::config::{AppConfig, Prompts, StatesConfig};
+use crate::db::state_transitions::{HistoryGroup, HistoryGrouping};
+use crate::db::{Database, now_ms};
 use crate::error::ToolError;
 use crate::format::{OutputFormat, markdown_to_json};
+use crate::prompts::{PromptContext, expand_prompt};
+use crate::types::{TaskChange, TaskSequenceEvent};
 use anyhow::Result;
 use rmcp::model::Tool;
 use serde_json::{Value, json};
@@ -45,7 +50,7 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
     vec![
         make_tool_with_prompts(
             "thinking",
-            "Broadcast real-time status updates (what you're doing right now). Also refreshes heartbeat. Call frequently during work to show live progress.",
+            "Broadcast real-time status updates (what you're doing right now). Also refreshes heartbeat. Call frequently during work to show live progress. Each thought is also kept in the claimed task's thought journal (see get_thoughts).",
             json!({
                 "agent": {
                     "type": "string",
@@ -64,9 +69,25 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
             vec!["agent", "thought"],
             prompts,
         ),
+        make_tool_with_prompts(
+            "get_thoughts",
+            "Read a task's thought journal: the thoughts agents broadcast with thinking while working on it, oldest first. Use when resuming a task to pick up prior reasoning.",
+            json!({
+                "task": {
+                    "type": "string",
+                    "description": "Task ID"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Return the latest N thoughts (default: 50)"
+                }
+            }),
+            vec!["task"],
+            prompts,
+        ),
         make_tool_with_prompts(
             "task_history",
-            "Get the status transition history for a task, including automatic time tracking data and aggregate statistics.",
+            "Get the status transition history for a task, including automatic time tracking data and aggregate statistics. With show_diffs, also lists every field an update changed, by whom, with before/after values. With include, interleaves attachment, dependency and tag events with the status transitions in one chronological timeline.",
             json!({
                 "task": {
                     "type": "string",
@@ -76,6 +97,15 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                     "type": "array",
                     "items": { "type": "string", "enum": state_enum },
                     "description": "Filter to only show transitions involving these statuses"
+                },
+                "show_diffs": {
+                    "type": "boolean",
+                    "description": "Include field-level changes with before/after values and line diffs of multi-line text (default: false)"
+                },
+                "include": {
+                    "type": "array",
+                    "items": { "type": "string", "enum": ["attachments", "dependencies", "tags"] },
+                    "description": "Event classes to interleave with status transitions in a chronological timeline: attachment adds/removes, dependency links/unlinks (either end), tag changes"
                 }
             }),
             vec!["task"],
@@ -108,7 +138,7 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
         ),
         make_tool_with_prompts(
             "project_history",
-            "Get project-wide status transition history and aggregate statistics. Like task_history but across all tasks with date/time range filters.",
+            "Get project-wide status transition history and aggregate statistics. Like task_history but across all tasks with date/time range filters. With group_by, returns per-day, per-agent or per-state aggregates (counts and duration percentiles, computed in the database) instead of raw transitions.",
             json!({
                 "from": {
                     "type": "string",
@@ -126,6 +156,11 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
                 "limit": {
                     "type": "integer",
                     "description": "Maximum number of transitions to return (default: 100)"
+                },
+                "group_by": {
+                    "type": "string",
+                    "enum": ["day", "agent", "state"],
+                    "description": "Aggregate transitions per UTC day, agent or status entered: count, tasks, total/avg/p50/p90/p99/max time in the status. Replaces the transition list."
                 }
             }),
             vec![],
@@ -149,7 +184,12 @@ pub fn get_tools(prompts: &Prompts, states_config: &StatesConfig) -> Vec<Tool> {
     ]
 }
 
-pub fn thinking(db: &Database, args: Value) -> Result<Value> {
+pub fn thinking(
+    db: &Database,
+    config: &AppConfig,
+    workflows: &WorkflowsConfig,
+    args: Value,
+) -> Result<Value> {
     let agent_id = get_string(&args, "agent").ok_or_else(|| ToolError::missing_field("agent"))?;
     let thought =
         get_string(&args, "thought").ok_or_else(|| ToolError::missing_field("thought"))?;
@@ -160,12 +200,288 @@ pub fn thinking(db: &Database, args: Value) -> Result<Value> {
 
     let updated = db.set_thought(&agent_id, Some(thought), task_ids)?;
 
-    Ok(json!({
+    let mut response = json!({
         "success": true,
         "updated_count": updated
+    });
+
+    // Deliver tasks registered via wait_for that have become ready since the last call
+    let unblocked = db.take_unblock_notifications(&agent_id)?;
+    if !unblocked.is_empty() {
+        response["unblocked"] = json!(unblocked);
+    }
+
+    let reminders = due_reminders(db, config, workflows, &agent_id)?;
+    if !reminders.is_empty() {
+        response["reminders"] = json!(reminders);
+    }
+
+    Ok(response)
+}
+
+/// Thoughts returned by `get_thoughts` without a limit.
+const DEFAULT_THOUGHT_LIMIT: i64 = 50;
+
+pub fn get_thoughts(db: &Database, args: Value) -> Result<Value> {
+    let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    let limit = get_i64(&args, "limit").unwrap_or(DEFAULT_THOUGHT_LIMIT);
+    if limit < 1 {
+        return Err(ToolError::invalid_value("limit", "limit must be at least 1").into());
+    }
+    if db.get_task(&task_id)?.is_none() {
+        return Err(ToolError::task_not_found(&task_id).into());
+    }
+
+    let thoughts = db.get_task_thoughts(&task_id, limit)?;
+    Ok(json!({
+        "task": task_id,
+        "count": thoughts.len(),
+        "thoughts": thoughts
     }))
 }
 
+/// Reminders due for a worker, expanded for its claims, recorded as delivered.
+///
+/// A reminder is due once the worker has held a claim in one of its states for
+/// `every_minutes`, counting from the earliest such claim or the last delivery,
+/// whichever is later.
+fn due_reminders(
+    db: &Database,
+    config: &AppConfig,
+    workflows: &WorkflowsConfig,
+    worker_id: &str,
+) -> Result<Vec<Value>> {
+    if workflows.reminders.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(worker) = db.get_worker(worker_id)? else {
+        return Ok(Vec::new());
+    };
+    let claims = db.get_claimed_tasks(Some(worker_id), None)?;
+    if claims.is_empty() {
+        return Ok(Vec::new());
+    }
+    let delivered = db.get_reminder_deliveries(worker_id)?;
+    let role = workflows.match_role(&worker.tags);
+    let now = now_ms();
+
+    let mut due = Vec::new();
+    let mut names = Vec::new();
+    for reminder in &workflows.reminders {
+        let matching: Vec<_> = claims
+            .iter()
+            .filter(|t| reminder.states.is_empty() || reminder.states.contains(&t.status))
+            .collect();
+        // Claims are ordered by claim time; the latest is the task at hand
+        let (Some(first), Some(task)) = (matching.first(), matching.last()) else {
+            continue;
+        };
+        let anchor = first
+            .claimed_at
+            .unwrap_or(now)
+            .max(delivered.get(&reminder.name).copied().unwrap_or(0));
+        if now - anchor < i64::from(reminder.every_minutes) * 60_000 {
+            continue;
+        }
+
+        let ctx = PromptContext::new(
+            &task.status,
+            task.phase.as_deref(),
+            &config.states,
+            &config.phases,
+        )
+        .with_task(&task.id, &task.title, task.priority, &task.tags)
+        .with_agent(worker_id, role.as_deref(), &worker.tags)
+        .with_claims(&claims);
+        due.push(json!({
+            "name": reminder.name,
+            "prompt": expand_prompt(&reminder.prompt, &ctx),
+        }));
+        names.push(reminder.name.clone());
+    }
+
+    db.record_reminder_deliveries(worker_id, &names)?;
+    Ok(due)
+}
+
+/// Line diff of two texts: unchanged lines prefixed with two spaces, removed
+/// lines with `- ` and added lines with `+ `.
+fn line_diff(old: &str, new: &str) -> Vec<String> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = length of the longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push(format!("  {}", a[i]));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push(format!("- {}", a[i]));
+            i += 1;
+        } else {
+            out.push(format!("+ {}", b[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+/// Line diff of a change, when either side is multi-line text.
+fn change_diff(change: &TaskChange) -> Option<Vec<String>> {
+    let old = change.old_value.as_str();
+    let new = change.new_value.as_str();
+    let multi_line = |s: Option<&str>| s.is_some_and(|s| s.contains('\n'));
+    if !multi_line(old) && !multi_line(new) {
+        return None;
+    }
+    Some(line_diff(old.unwrap_or(""), new.unwrap_or("")))
+}
+
+/// Render a changed value for a Markdown table cell.
+fn change_cell(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) if s.contains('\n') => "(multi-line)".to_string(),
+        Value::String(s) => s.replace('|', "\\|"),
+        other => other.to_string().replace('|', "\\|"),
+    }
+}
+
+/// Event classes `task_history` can interleave with status transitions.
+const TIMELINE_CLASSES: [&str; 3] = ["attachments", "dependencies", "tags"];
+
+/// Task columns whose changes count as tag changes.
+const TAG_FIELDS: [&str; 3] = ["tags", "needed_tags", "wanted_tags"];
+
+/// One event of a task's timeline.
+struct TimelineEntry {
+    timestamp: i64,
+    worker_id: Option<String>,
+    /// One-line description for Markdown.
+    summary: String,
+    event: Value,
+}
+
+/// Status transitions and the requested event classes of a task, oldest
+/// first. Events at the same millisecond keep status transitions first.
+fn task_timeline(
+    db: &Database,
+    task_id: &str,
+    history: &[TaskSequenceEvent],
+    include: &[String],
+) -> Result<Vec<TimelineEntry>> {
+    let mut timeline: Vec<TimelineEntry> = history
+        .iter()
+        .map(|e| {
+            let status = e.status.as_deref().unwrap_or("-");
+            TimelineEntry {
+                timestamp: e.timestamp,
+                worker_id: e.worker_id.clone(),
+                summary: format!("status → {}", status),
+                event: json!({
+                    "kind": "status",
+                    "timestamp": e.timestamp,
+                    "status": status,
+                    "worker_id": e.worker_id,
+                    "reason": e.reason,
+                }),
+            }
+        })
+        .collect();
+
+    let included = |class: &str| include.iter().any(|c| c == class);
+    let mut entities = Vec::new();
+    if included("attachments") {
+        entities.push("attachment");
+    }
+    if included("dependencies") {
+        entities.push("dependency");
+    }
+    for entry in db.get_task_feed(task_id, &entities)? {
+        let (summary, event) = if entry.entity == "dependency" {
+            // The feed keys dependency rows by the dependent (to) task
+            let (from, to) = (entry.related_id.unwrap_or_default(), entry.task_id);
+            let dep_type = entry.detail.unwrap_or_default();
+            (
+                format!(
+                    "dependency {}: {} {} → {}",
+                    entry.action,
+                    from,
+                    dep_type,
+                    to.as_deref().unwrap_or("-")
+                ),
+                json!({
+                    "kind": "dependency",
+                    "timestamp": entry.timestamp,
+                    "action": entry.action,
+                    "dep_type": dep_type,
+                    "from": from,
+                    "to": to,
+                }),
+            )
+        } else {
+            let name = entry.detail.unwrap_or_default();
+            (
+                format!("attachment {}: {}", entry.action, name),
+                json!({
+                    "kind": "attachment",
+                    "timestamp": entry.timestamp,
+                    "action": entry.action,
+                    "name": name,
+                }),
+            )
+        };
+        timeline.push(TimelineEntry {
+            timestamp: entry.timestamp,
+            worker_id: None,
+            summary,
+            event,
+        });
+    }
+
+    if included("tags") {
+        for change in db.get_task_changes(task_id)? {
+            if !TAG_FIELDS.contains(&change.field.as_str()) {
+                continue;
+            }
+            timeline.push(TimelineEntry {
+                timestamp: change.timestamp,
+                worker_id: change.worker_id.clone(),
+                summary: format!(
+                    "{}: {} → {}",
+                    change.field, change.old_value, change.new_value
+                ),
+                event: json!({
+                    "kind": "tags",
+                    "timestamp": change.timestamp,
+                    "field": change.field,
+                    "worker_id": change.worker_id,
+                    "old_value": change.old_value,
+                    "new_value": change.new_value,
+                }),
+            });
+        }
+    }
+
+    // Stable: status transitions stay ahead of same-millisecond events
+    timeline.sort_by_key(|e| e.timestamp);
+    Ok(timeline)
+}
+
 pub fn task_history(
     db: &Database,
     states_config: &StatesConfig,
@@ -174,11 +490,32 @@ pub fn task_history(
 ) -> Result<Value> {
     let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
     let state_filter = get_string_array(&args, "states");
+    let show_diffs = get_bool(&args, "show_diffs").unwrap_or(false);
+    let include = get_string_array(&args, "include").unwrap_or_default();
+    if let Some(bad) = include
+        .iter()
+        .find(|c| !TIMELINE_CLASSES.contains(&c.as_str()))
+    {
+        return Err(ToolError::invalid_value(
+            "include",
+            &format!(
+                "Unknown event class '{}'; expected: {}",
+                bad,
+                TIMELINE_CLASSES.join(", ")
+            ),
+        )
+        .into());
+    }
     let format = get_string(&args, "format")
         .and_then(|s| OutputFormat::parse(&s))
         .unwrap_or(default_format);
 
     let history = db.get_task_state_history(&task_id)?;
+    let changes = if show_diffs {
+        Some(db.get_task_changes(&task_id)?)
+    } else {
+        None
+    };
     let current_duration = db.get_current_state_duration(&task_id, states_config)?;
 
     // Filter history by statuses if specified
@@ -191,6 +528,12 @@ pub fn task_history(
         history
     };
 
+    let timeline = if include.is_empty() {
+        None
+    } else {
+        Some(task_timeline(db, &task_id, &filtered_history, &include)?)
+    };
+
     // Calculate aggregate stats
     let mut time_per_status: HashMap<String, i64> = HashMap::new();
     let mut time_per_agent: HashMap<String, i64> = HashMap::new();
@@ -282,14 +625,81 @@ pub fn task_history(
                 }
             }
 
+            if let Some(ref timeline) = timeline {
+                md.push_str("\n## Timeline\n\n");
+                md.push_str("| # | Timestamp | Event | Agent |\n");
+                md.push_str("|---|-----------|-------|-------|\n");
+                for (i, entry) in timeline.iter().enumerate() {
+                    md.push_str(&format!(
+                        "| {} | {} | {} | {} |\n",
+                        i + 1,
+                        format_timestamp(entry.timestamp),
+                        entry.summary.replace('|', "\\|"),
+                        entry.worker_id.as_deref().unwrap_or("-")
+                    ));
+                }
+            }
+
+            if let Some(ref changes) = changes {
+                md.push_str("\n## Field Changes\n\n");
+                if changes.is_empty() {
+                    md.push_str("No field changes recorded.\n");
+                } else {
+                    md.push_str("| # | Field | Agent | Timestamp | Before | After |\n");
+                    md.push_str("|---|-------|-------|-----------|--------|-------|\n");
+                    for (i, change) in changes.iter().enumerate() {
+                        md.push_str(&format!(
+                            "| {} | {} | {} | {} | {} | {} |\n",
+                            i + 1,
+                            change.field,
+                            change.worker_id.as_deref().unwrap_or("-"),
+                            format_timestamp(change.timestamp),
+                            change_cell(&change.old_value),
+                            change_cell(&change.new_value)
+                        ));
+                    }
+                    for (i, change) in changes.iter().enumerate() {
+                        if let Some(diff) = change_diff(change) {
+                            md.push_str(&format!(
+                                "\n### #{} {}\n\n```diff\n{}\n```\n",
+                                i + 1,
+                                change.field,
+                                diff.join("\n")
+                            ));
+                        }
+                    }
+                }
+            }
+
             Ok(markdown_to_json(md))
         }
-        OutputFormat::Json => Ok(json!({
-            "history": filtered_history,
-            "current_duration_ms": current_duration,
-            "time_per_status_ms": time_per_status,
-            "time_per_agent_ms": time_per_agent
-        })),
+        OutputFormat::Json | OutputFormat::Compact => {
+            let mut response = json!({
+                "history": filtered_history,
+                "current_duration_ms": current_duration,
+                "time_per_status_ms": time_per_status,
+                "time_per_agent_ms": time_per_agent
+            });
+            if let Some(timeline) = timeline {
+                response["timeline"] =
+                    json!(timeline.into_iter().map(|e| e.event).collect::<Vec<_>>());
+            }
+            if let Some(changes) = changes {
+                let changes: Vec<Value> = changes
+                    .into_iter()
+                    .map(|change| {
+                        let diff = change_diff(&change);
+                        let mut value = json!(change);
+                        if let Some(diff) = diff {
+                            value["diff"] = json!(diff);
+                        }
+                        value
+                    })
+                    .collect();
+                response["changes"] = json!(changes);
+            }
+            Ok(response)
+        }
     }
 }
 
@@ -315,7 +725,7 @@ pub fn log_metrics(db: &Database, args: Value) -> Result<Value> {
 }
 
 /// Parse a timestamp from either ISO 8601 string or milliseconds.
-fn parse_timestamp(s: &str) -> Option<i64> {
+pub(crate) fn parse_timestamp(s: &str) -> Option<i64> {
     // Try parsing as milliseconds first
     if let Ok(ms) = s.parse::<i64>() {
         return Some(ms);
@@ -352,6 +762,25 @@ pub fn project_history(db: &Database, default_format: OutputFormat, args: Value)
         .and_then(|s| OutputFormat::parse(&s))
         .unwrap_or(default_format);
 
+    if let Some(group_by) = get_string(&args, "group_by") {
+        let grouping = HistoryGrouping::parse(&group_by).ok_or_else(|| {
+            ToolError::invalid_value("group_by", "group_by must be one of: day, agent, state")
+        })?;
+        let groups = db.get_project_history_groups(
+            grouping,
+            from_timestamp,
+            to_timestamp,
+            state_filter.as_deref(),
+        )?;
+        return Ok(project_history_groups(
+            grouping,
+            &groups,
+            from_timestamp,
+            to_timestamp,
+            format,
+        ));
+    }
+
     // Get transitions
     let history =
         db.get_project_state_history(from_timestamp, to_timestamp, state_filter.as_deref(), limit)?;
@@ -459,7 +888,7 @@ pub fn project_history(db: &Database, default_format: OutputFormat, args: Value)
 
             Ok(markdown_to_json(md))
         }
-        OutputFormat::Json => Ok(json!({
+        OutputFormat::Json | OutputFormat::Compact => Ok(json!({
             "time_range": {
                 "from_ms": from_timestamp,
                 "to_ms": to_timestamp
@@ -478,6 +907,63 @@ pub fn project_history(db: &Database, default_format: OutputFormat, args: Value)
     }
 }
 
+/// Render grouped project history.
+fn project_history_groups(
+    grouping: HistoryGrouping,
+    groups: &[HistoryGroup],
+    from_timestamp: Option<i64>,
+    to_timestamp: Option<i64>,
+    format: OutputFormat,
+) -> Value {
+    match format {
+        OutputFormat::Markdown => {
+            let mut md = String::from("# Project History\n\n");
+            let from_str = from_timestamp
+                .map(format_timestamp)
+                .unwrap_or_else(|| "beginning".to_string());
+            let to_str = to_timestamp
+                .map(format_timestamp)
+                .unwrap_or_else(|| "now".to_string());
+            md.push_str(&format!("**From:** {} **To:** {}\n\n", from_str, to_str));
+
+            md.push_str(&format!("## By {}\n\n", grouping.as_str()));
+            if groups.is_empty() {
+                md.push_str("No status transitions found.\n");
+            } else {
+                let duration = |ms: Option<i64>| ms.map_or("-".to_string(), format_duration_ms);
+                md.push_str(&format!(
+                    "| {} | Transitions | Tasks | Total Time | Avg | p50 | p90 | p99 | Max |\n",
+                    grouping.as_str()
+                ));
+                md.push_str("|---|---|---|---|---|---|---|---|---|\n");
+                for group in groups {
+                    md.push_str(&format!(
+                        "| {} | {} | {} | {} | {} | {} | {} | {} | {} |\n",
+                        group.key.as_deref().unwrap_or("-"),
+                        group.transitions,
+                        group.tasks,
+                        format_duration_ms(group.total_ms),
+                        duration(group.avg_ms),
+                        duration(group.p50_ms),
+                        duration(group.p90_ms),
+                        duration(group.p99_ms),
+                        duration(group.max_ms)
+                    ));
+                }
+            }
+            markdown_to_json(md)
+        }
+        OutputFormat::Json | OutputFormat::Compact => json!({
+            "time_range": {
+                "from_ms": from_timestamp,
+                "to_ms": to_timestamp
+            },
+            "group_by": grouping.as_str(),
+            "groups": groups
+        }),
+    }
+}
+
 pub fn get_metrics(db: &Database, args: Value) -> Result<Value> {
     use super::get_string_or_array;
 
@@ -0,0 +1,371 @@
+//! Tools for bulk cleanup and soft-deleted tasks.
+
+use super::attachments::{is_in_media_dir, reap_uploads};
+use super::{get_bool, get_i32, get_i64, get_string, make_tool_with_prompts};
+use crate::config::{AppConfig, Prompts};
+use crate::db::filter::TaskFilter;
+use crate::db::tasks::{BulkDeleteOptions, BulkResult, ListTasksQuery, check_expected_count};
+use crate::db::trash::PurgeResult;
+use crate::db::{Database, now_ms};
+use crate::error::ToolError;
+use anyhow::Result;
+use rmcp::model::Tool;
+use serde_json::{Value, json};
+use std::path::Path;
+
+/// Default minimum age of a soft deletion before `purge` removes it.
+pub const DEFAULT_PURGE_AGE_DAYS: i64 = 30;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// State `bulk_archive` moves tasks to unless told otherwise.
+const DEFAULT_ARCHIVE_STATUS: &str = "cancelled";
+
+pub fn get_tools(prompts: &Prompts) -> Vec<Tool> {
+    vec![
+        make_tool_with_prompts(
+            "list_deleted",
+            "List soft-deleted tasks, most recently deleted first, with who deleted them, why, and how long ago. Tasks whose parent is also deleted are flagged with parent_deleted.",
+            json!({
+                "deleted_by": {
+                    "type": "string",
+                    "description": "Only tasks deleted by this worker"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of tasks to return (default: 50, max: 500)"
+                },
+                "offset": {
+                    "type": "integer",
+                    "description": "Number of tasks to skip for pagination (default: 0)"
+                }
+            }),
+            vec![],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "restore",
+            "Restore a soft-deleted task. Its dependency edges become active again. With cascade (default), soft-deleted descendants are restored too. Fails if the parent is still deleted unless detach=true, which restores the task as a root.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Calling agent ID (recorded in the audit log)"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "Deleted task to restore"
+                },
+                "cascade": {
+                    "type": "boolean",
+                    "description": "Also restore soft-deleted descendants (default: true)"
+                },
+                "detach": {
+                    "type": "boolean",
+                    "description": "If the parent is deleted, drop the parent link and restore as a root task (default: false)"
+                }
+            }),
+            vec!["task"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "purge",
+            "Permanently remove tasks soft-deleted more than N days ago, with their dependencies, attachments (and media files), tags, fields, and history. Cannot be undone; use dry_run to preview.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Calling agent ID (recorded in the audit log)"
+                },
+                "older_than_days": {
+                    "type": "integer",
+                    "description": "Only purge tasks deleted at least this many days ago (default: 30; 0 purges every soft-deleted task)"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Report what would be removed without changing anything (default: false)"
+                }
+            }),
+            vec![],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "bulk_delete",
+            "Delete every task matching a where expression (the list_tasks filter syntax). Runs as a dry run listing the affected IDs unless dry_run=false, which also requires expected_count from that dry run. Tasks claimed by others (unless force) or with children outside the match are skipped.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Worker ID doing the deletion"
+                },
+                "where": {
+                    "type": "string",
+                    "description": "Filter expression, e.g. 'status=cancelled AND tag:scanner'"
+                },
+                "reason": {
+                    "type": "string",
+                    "description": "Reason recorded on each deleted task"
+                },
+                "obliterate": {
+                    "type": "boolean",
+                    "description": "Permanently remove the tasks instead of soft deleting (default: false)"
+                },
+                "force": {
+                    "type": "boolean",
+                    "description": "Also delete tasks claimed by other workers (default: false)"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Report the tasks that would be deleted without changing anything (default: true)"
+                },
+                "expected_count": {
+                    "type": "integer",
+                    "description": "Count reported by the dry run; required with dry_run=false, which fails if the match has changed"
+                }
+            }),
+            vec!["worker_id", "where"],
+            prompts,
+        ),
+        make_tool_with_prompts(
+            "bulk_archive",
+            "Close every unfinished task matching a where expression by moving it to a terminal state (default: cancelled), keeping it and its history. Runs as a dry run listing the affected IDs unless dry_run=false, which also requires expected_count from that dry run.",
+            json!({
+                "worker_id": {
+                    "type": "string",
+                    "description": "Worker ID doing the archiving"
+                },
+                "where": {
+                    "type": "string",
+                    "description": "Filter expression, e.g. 'status=pending AND tag:scanner'"
+                },
+                "status": {
+                    "type": "string",
+                    "description": "Terminal state to move the tasks to (default: cancelled)"
+                },
+                "reason": {
+                    "type": "string",
+                    "description": "Reason recorded on each state change"
+                },
+                "force": {
+                    "type": "boolean",
+                    "description": "Also archive tasks claimed by other workers (default: false)"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Report the tasks that would be archived without changing anything (default: true)"
+                },
+                "expected_count": {
+                    "type": "integer",
+                    "description": "Count reported by the dry run; required with dry_run=false, which fails if the match has changed"
+                }
+            }),
+            vec!["worker_id", "where"],
+            prompts,
+        ),
+    ]
+}
+
+/// Delete the media left behind by a purge: non-blob attachment files in the
+/// media dir and partial uploads. Blobs are left to `gc_blobs`.
+/// Returns the number of files deleted.
+pub fn remove_purged_media(db: &Database, media_dir: &Path, result: &PurgeResult) -> Result<usize> {
+    let mut files_deleted = 0;
+    for fp in &result.file_paths {
+        if is_in_media_dir(fp, media_dir) && std::fs::remove_file(fp).is_ok() {
+            files_deleted += 1;
+        }
+    }
+    reap_uploads(db, media_dir)?;
+    Ok(files_deleted)
+}
+
+pub fn list_deleted(db: &Database, args: Value) -> Result<Value> {
+    let deleted_by = get_string(&args, "deleted_by");
+    let limit = get_i32(&args, "limit").unwrap_or(50).clamp(1, 500);
+    let offset = get_i32(&args, "offset").unwrap_or(0).max(0);
+
+    // Fetch limit+1 to detect if there are more tasks
+    let mut tasks = db.list_deleted_tasks(deleted_by.as_deref(), limit + 1, offset)?;
+    let has_more = tasks.len() > limit as usize;
+    tasks.truncate(limit as usize);
+
+    Ok(json!({
+        "task_count": tasks.len(),
+        "has_more": has_more,
+        "offset": offset,
+        "tasks": tasks
+    }))
+}
+
+pub fn restore(db: &Database, args: Value) -> Result<Value> {
+    let task_id = get_string(&args, "task").ok_or_else(|| ToolError::missing_field("task"))?;
+    let cascade = get_bool(&args, "cascade").unwrap_or(true);
+    let detach = get_bool(&args, "detach").unwrap_or(false);
+
+    let result = db.restore_task(&task_id, cascade, detach)?;
+
+    let mut response = json!({
+        "task": task_id,
+        "restored": result.task_ids,
+        "dependencies": result.dependencies
+    });
+    if let Some(parent) = result.detached_from {
+        response["detached_from"] = json!(parent);
+    }
+    Ok(response)
+}
+
+pub fn purge(db: &Database, media_dir: &Path, args: Value) -> Result<Value> {
+    let older_than_days = get_i64(&args, "older_than_days").unwrap_or(DEFAULT_PURGE_AGE_DAYS);
+    if older_than_days < 0 {
+        return Err(ToolError::invalid_value("older_than_days", "must not be negative").into());
+    }
+    let dry_run = get_bool(&args, "dry_run").unwrap_or(false);
+
+    let result = db.purge_deleted(now_ms() - older_than_days * DAY_MS, dry_run)?;
+
+    let mut response = json!({
+        "dry_run": dry_run,
+        "older_than_days": older_than_days,
+        "task_count": result.task_ids.len(),
+        "tasks": &result.task_ids,
+        "rows": &result.rows
+    });
+    if !dry_run && !result.task_ids.is_empty() {
+        response["files_deleted"] = json!(remove_purged_media(db, media_dir, &result)?);
+    }
+    Ok(response)
+}
+
+/// IDs of the live tasks matching the required `where` argument, within the
+/// caller's visibility scope.
+fn matching_task_ids(db: &Database, config: &AppConfig, args: &Value) -> Result<Vec<String>> {
+    let worker_id =
+        get_string(args, "worker_id").ok_or_else(|| ToolError::missing_field("worker_id"))?;
+    let expr = get_string(args, "where").ok_or_else(|| ToolError::missing_field("where"))?;
+    let mut filter = TaskFilter::parse(&expr, &config.priorities)
+        .map_err(|e| ToolError::invalid_value("where", &e.to_string()))?;
+    filter.map_tags(&|tag| config.tags.canonical_tag(tag));
+    let scope = db.task_scope(&worker_id, &config.visibility)?;
+
+    let tasks = db.list_tasks(ListTasksQuery {
+        scope: scope.as_ref(),
+        filter: Some(&filter),
+        ..Default::default()
+    })?;
+    let mut ids: Vec<String> = tasks.into_iter().map(|t| t.id).collect();
+    ids.sort();
+    Ok(ids)
+}
+
+/// Read `expected_count`, rejecting negative values.
+fn get_expected_count(args: &Value) -> Result<Option<usize>> {
+    match get_i64(args, "expected_count") {
+        Some(n) if n < 0 => {
+            Err(ToolError::invalid_value("expected_count", "must not be negative").into())
+        }
+        n => Ok(n.map(|n| n as usize)),
+    }
+}
+
+fn bulk_response(result: BulkResult, dry_run: bool) -> Value {
+    let skipped: Vec<Value> = result
+        .skipped
+        .iter()
+        .map(|(id, reason)| json!({"id": id, "reason": reason}))
+        .collect();
+    json!({
+        "dry_run": dry_run,
+        "count": result.affected.len(),
+        "tasks": result.affected,
+        "skipped": skipped
+    })
+}
+
+pub fn bulk_delete(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
+    let task_ids = matching_task_ids(db, config, &args)?;
+    let worker_id = get_string(&args, "worker_id").unwrap_or_default();
+    let dry_run = get_bool(&args, "dry_run").unwrap_or(true);
+
+    let opts = BulkDeleteOptions {
+        reason: get_string(&args, "reason"),
+        obliterate: get_bool(&args, "obliterate").unwrap_or(false),
+        force: get_bool(&args, "force").unwrap_or(false),
+        dry_run,
+        expected_count: get_expected_count(&args)?,
+    };
+    let result = db.bulk_delete_tasks(&task_ids, &worker_id, &opts)?;
+
+    let mut response = bulk_response(result, dry_run);
+    response["soft_deleted"] = json!(!opts.obliterate);
+    Ok(response)
+}
+
+pub fn bulk_archive(db: &Database, config: &AppConfig, args: Value) -> Result<Value> {
+    let task_ids = matching_task_ids(db, config, &args)?;
+    let worker_id = get_string(&args, "worker_id").unwrap_or_default();
+    let dry_run = get_bool(&args, "dry_run").unwrap_or(true);
+    let force = get_bool(&args, "force").unwrap_or(false);
+    let reason = get_string(&args, "reason");
+    let status = get_string(&args, "status").unwrap_or_else(|| DEFAULT_ARCHIVE_STATUS.to_string());
+    if !config.states.is_terminal_state(&status) {
+        return Err(ToolError::invalid_value(
+            "status",
+            &format!("'{}' is not a terminal state", status),
+        )
+        .into());
+    }
+
+    // Finished tasks are already archived; claimed ones need force
+    let mut result = BulkResult::default();
+    for id in task_ids {
+        let Some(task) = db.get_task(&id)? else {
+            continue;
+        };
+        if config.states.is_terminal_state(&task.status) {
+            continue;
+        }
+        match task.worker_id {
+            Some(ref owner) if *owner != worker_id && !force => result
+                .skipped
+                .push((id, format!("claimed by worker '{}'", owner))),
+            _ => result.affected.push(id),
+        }
+    }
+    if dry_run {
+        return Ok(bulk_response(result, true));
+    }
+    check_expected_count(get_expected_count(&args)?, result.affected.len())?;
+
+    // Each task changes state through the normal update path, so transitions,
+    // unblocking, and auto-advance behave as for a single update
+    let mut archived = BulkResult {
+        affected: Vec::new(),
+        skipped: result.skipped,
+    };
+    for id in result.affected {
+        match db.update_task_unified(
+            &id,
+            &worker_id,
+            None,
+            None,
+            None,
+            Some(status.clone()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            reason.clone(),
+            force,
+            &config.states,
+            &config.deps,
+            &config.auto_advance,
+        ) {
+            Ok(_) => archived.affected.push(id),
+            Err(e) => archived.skipped.push((id, e.to_string())),
+        }
+    }
+    let mut response = bulk_response(archived, false);
+    response["status"] = json!(status);
+    Ok(response)
+}
@@ -1,7 +1,7 @@
 //! Core types for the Task Graph MCP Server.
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 // Skip-if helpers (serde requires function paths, not closures)
 fn is_zero<T: Default + PartialEq>(v: &T) -> bool {
@@ -12,6 +12,14 @@ fn is_default_priority(p: &Priority) -> bool {
     *p == PRIORITY_DEFAULT
 }
 
+fn is_default_project(p: &str) -> bool {
+    p == DEFAULT_PROJECT
+}
+
+fn default_project() -> String {
+    DEFAULT_PROJECT.to_string()
+}
+
 /// Metrics array - serializes with trailing zeros trimmed, deserializes back to [i64; 8]
 mod metrics_serde {
     use super::*;
@@ -61,6 +69,12 @@ pub struct Worker {
     /// Overlay names applied on top of the workflow (e.g., ["git", "user-request"])
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub overlays: Vec<String>,
+    /// Project namespace the worker claims and marks within
+    #[serde(
+        default = "default_project",
+        skip_serializing_if = "is_default_project"
+    )]
+    pub project: String,
 }
 
 /// Worker info with additional runtime details for list_workers.
@@ -88,23 +102,86 @@ pub struct WorkerInfo {
     /// Overlay names applied on top of the workflow (e.g., ["git", "user-request"])
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub overlays: Vec<String>,
+    /// Agent groups (teams) the worker belongs to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    /// Project namespace the worker claims and marks within
+    #[serde(
+        default = "default_project",
+        skip_serializing_if = "is_default_project"
+    )]
+    pub project: String,
 }
 
 /// Task priority as an integer (higher = more important).
-/// Range: 0-10, where 10 is highest priority. Default is 5.
+/// The valid range is set by the `priorities` config (0-10 by default).
 pub type Priority = i32;
 
-/// Default priority (middle of 0-10 range).
+/// Default priority (middle of the default 0-10 range).
 pub const PRIORITY_DEFAULT: Priority = 5;
 
-/// Parse a priority value, clamping to 0-10 range.
-pub fn parse_priority(s: &str) -> Priority {
-    s.parse().unwrap_or(PRIORITY_DEFAULT).clamp(0, 10)
+/// Project that tasks, workers, and marks belong to unless one is named.
+pub const DEFAULT_PROJECT: &str = "default";
+
+/// Known values for a task's `blocked_reason`.
+pub const BLOCKED_REASONS: &[&str] = &[
+    "waiting-on-human",
+    "external-dependency",
+    "needs-decision",
+    "needs-info",
+    "other",
+];
+
+/// Separator of tag hierarchy levels: `lang/rust` is a child of `lang`.
+pub const TAG_SEPARATOR: char = '/';
+
+/// Whether `tag` is `filter` or lies under it in the tag hierarchy:
+/// `lang` matches `lang` and `lang/rust`, but not `language`.
+pub fn tag_matches(filter: &str, tag: &str) -> bool {
+    tag.strip_prefix(filter)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(TAG_SEPARATOR))
 }
 
-/// Clamp priority to valid range.
-pub fn clamp_priority(p: Priority) -> Priority {
-    p.clamp(0, 10)
+/// Whether any of `tags` matches `filter` (see [`tag_matches`]).
+pub fn has_tag(tags: &[String], filter: &str) -> bool {
+    tags.iter().any(|tag| tag_matches(filter, tag))
+}
+
+/// The parent tags of `tag`, nearest first: `lang/rust/async` yields
+/// `lang/rust`, then `lang`.
+pub fn tag_ancestors(tag: &str) -> impl Iterator<Item = &str> {
+    tag.rmatch_indices(TAG_SEPARATOR).map(|(i, _)| &tag[..i])
+}
+
+/// `tags` and all their ancestors, without duplicates. An agent tagged
+/// `lang/rust` has these tags when matched against a requirement for `lang`.
+pub fn with_tag_ancestors(tags: &[String]) -> Vec<String> {
+    let mut all: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        for t in std::iter::once(tag.as_str()).chain(tag_ancestors(tag)) {
+            if !all.iter().any(|a| a == t) {
+                all.push(t.to_string());
+            }
+        }
+    }
+    all
+}
+
+/// Whether `tags` meet a required-tag rule: `component` is met by `component`
+/// or any child tag, `component/*` only by a child tag.
+pub fn meets_required_tag(tags: &[String], rule: &str) -> bool {
+    match rule.strip_suffix("/*") {
+        Some(parent) => tags
+            .iter()
+            .any(|tag| tag.len() > parent.len() && tag_matches(parent, tag)),
+        None => has_tag(tags, rule),
+    }
+}
+
+/// Parse a stored priority value, falling back to the default.
+/// Range checks happen at the tool layer against the configured scale.
+pub fn parse_priority(s: &str) -> Priority {
+    s.parse().unwrap_or(PRIORITY_DEFAULT)
 }
 
 /// A task in the task graph.
@@ -129,6 +206,15 @@ pub struct Task {
     pub needed_tags: Vec<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub wanted_tags: Vec<String>,
+    /// Wanted tags only raise the match score instead of gating the claim
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub wanted_optional: bool,
+    /// Agent group whose members alone may claim the task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub needed_group: Option<String>,
+    /// Tag rules every task under this one must meet (see [`meets_required_tag`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_child_tags: Vec<String>,
 
     // Categorization/discovery tags
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -150,6 +236,12 @@ pub struct Task {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub current_thought: Option<String>,
 
+    // Why the task is stuck (one of BLOCKED_REASONS) and free-text detail
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_note: Option<String>,
+
     // Cost accounting
     #[serde(skip_serializing_if = "is_zero")]
     pub cost_usd: f64,
@@ -161,6 +253,13 @@ pub struct Task {
     )]
     pub metrics: [i64; 8],
 
+    /// Project namespace; tasks only interact with tasks and agents of the same project
+    #[serde(
+        default = "default_project",
+        skip_serializing_if = "is_default_project"
+    )]
+    pub project: String,
+
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -211,9 +310,17 @@ pub struct TaskTreeInput {
     /// Tags that claiming agent must have AT LEAST ONE of (OR logic).
     pub wanted_tags: Option<Vec<String>>,
 
+    /// Whether wanted tags only raise the match score instead of gating the claim.
+    #[serde(default)]
+    pub wanted_optional: Option<bool>,
+
     /// Categorization/discovery tags for the task.
     pub tags: Option<Vec<String>>,
 
+    /// Tag rules every task under this one must meet.
+    #[serde(default)]
+    pub required_child_tags: Option<Vec<String>>,
+
     /// Child nodes in the tree.
     #[serde(default)]
     pub children: Vec<TaskTreeInput>,
@@ -227,6 +334,21 @@ pub struct Dependency {
     pub to_task_id: String,
     /// Dependency type: "blocks", "follows", "contains", or custom types.
     pub dep_type: String,
+    /// Delay after the blocker completes before the dependency is satisfied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lag_ms: Option<i64>,
+    /// Any-of group: edges into the same task with the same type and group
+    /// are satisfied once any one of them is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub any_of: Option<String>,
+}
+
+/// A file modified on disk while no agent held a mark covering it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkViolation {
+    pub id: i64,
+    pub file_path: String,
+    pub detected_at: i64,
 }
 
 /// An advisory file lock.
@@ -239,6 +361,15 @@ pub struct FileLock {
     pub locked_at: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub task_id: Option<String>,
+    /// Lease expiry (ms); the mark is released by the reaper after this time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// Project of the agent that placed the mark
+    #[serde(
+        default = "default_project",
+        skip_serializing_if = "is_default_project"
+    )]
+    pub project: String,
 }
 
 /// A claim event for file coordination tracking.
@@ -278,6 +409,95 @@ pub struct TaskSequenceEvent {
     pub end_timestamp: Option<i64>,
 }
 
+/// One mutating tool call recorded in the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Agent that made the call, when it identified itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worker_id: Option<String>,
+    pub tool: String,
+    /// SHA-256 of the call's arguments as JSON.
+    pub args_hash: String,
+    /// Tasks the call named or created.
+    #[serde(default)]
+    pub task_ids: Vec<String>,
+    /// `ok`, or the error code of a failed call.
+    pub outcome: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub timestamp: i64,
+}
+
+/// One entry of the change feed. The ID is the feed cursor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeFeedEntry {
+    pub id: i64,
+    /// `task`, `dependency`, or `attachment`.
+    pub entity: String,
+    /// `created`, `updated`, `renamed`, `deleted`, `restored`, `purged`,
+    /// `added`, or `removed`.
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    /// Other task of a dependency, or the previous ID of a renamed task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related_id: Option<String>,
+    /// Dependency type, attachment name, or `fields.<name>` for custom fields.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub timestamp: i64,
+}
+
+/// A soft-deleted task, from `list_deleted`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedTask {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    /// Parent via a `contains` edge, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// Whether the parent is deleted too (restoring this task alone needs `detach`).
+    #[serde(default)]
+    pub parent_deleted: bool,
+    pub deleted_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_reason: Option<String>,
+    /// Milliseconds since the deletion.
+    pub age_ms: i64,
+}
+
+/// One field changed by a task update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskChange {
+    pub id: i64,
+    pub task_id: String,
+    /// Agent that made the update.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worker_id: Option<String>,
+    /// Task column (`description`, `priority`, ...) or `fields.<name>` for a custom field.
+    pub field: String,
+    /// Value before the update; null when unset.
+    pub old_value: serde_json::Value,
+    /// Value after the update; null when cleared.
+    pub new_value: serde_json::Value,
+    pub timestamp: i64,
+}
+
+/// A thought recorded against a task by `thinking`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskThought {
+    pub id: i64,
+    pub task_id: String,
+    /// Agent that had the thought.
+    pub worker_id: String,
+    pub thought: String,
+    pub created_at: i64,
+}
+
 /// Legacy alias for backward compatibility in exports.
 /// A task state transition event for time tracking.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -340,6 +560,15 @@ pub struct Attachment {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_path: Option<String>,
     pub created_at: i64,
+    /// SHA-256 digest (hex) of a content-addressed blob in the media dir.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Size of the blob in bytes (blob attachments only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<i64>,
+    /// Version within (task_id, attachment_type, name); 1 unless added with mode "version".
+    #[serde(default = "default_attachment_version")]
+    pub version: i32,
 }
 
 /// Attachment metadata (without content).
@@ -355,6 +584,38 @@ pub struct AttachmentMeta {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_path: Option<String>,
     pub created_at: i64,
+    /// SHA-256 digest (hex) of a content-addressed blob in the media dir.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Size of the blob in bytes (blob attachments only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<i64>,
+    /// Version within (task_id, attachment_type, name).
+    pub version: i32,
+}
+
+fn default_attachment_version() -> i32 {
+    1
+}
+
+/// An in-progress chunked attachment upload (`attach_begin` .. `attach_commit`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentUpload {
+    pub id: String,
+    pub task_id: String,
+    pub attachment_type: String,
+    pub name: String,
+    pub mime_type: String,
+    pub mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_size: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_hash: Option<String>,
+    pub received_bytes: i64,
+    /// Index of the next chunk `attach_chunk` accepts.
+    pub next_chunk: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
 }
 
 /// Aggregate statistics.
@@ -382,6 +643,23 @@ pub struct Stats {
     pub total_metrics: [i64; 8],
 }
 
+/// Usage of one tag across tasks and agents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagStats {
+    pub tag: String,
+    /// Counts of tasks carrying the tag, by state.
+    pub tasks_by_status: HashMap<String, i64>,
+    /// Open tasks (in a blocking state) that need the tag to be claimed.
+    pub needed_by: i64,
+    /// Registered agents that meet the tag, directly or through a child tag.
+    pub agents: i64,
+    /// Latest update to a task carrying or needing the tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<i64>,
+    /// Whether open tasks need the tag but no registered agent has it.
+    pub uncovered: bool,
+}
+
 /// Compact task representation for list views.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskSummary {
@@ -429,6 +707,212 @@ pub struct DisconnectSummary {
     pub final_status: String,
 }
 
+/// A task still claimed by an agent that resumed its session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumedClaim {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_thought: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claimed_at: Option<i64>,
+}
+
+/// Session state handed back to an agent that reconnected within the grace window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumedSession {
+    /// Milliseconds between the agent's last heartbeat and the reconnect.
+    pub offline_ms: i64,
+    /// Tasks the agent still holds, with their current thoughts.
+    pub claims: Vec<ResumedClaim>,
+    /// File paths the agent still has marked.
+    pub marks: Vec<String>,
+    /// Tasks registered with `wait_for` that have not become ready yet.
+    pub waiting_for: Vec<String>,
+    /// File paths the agent is waiting on other agents to unmark.
+    pub mark_waits: Vec<String>,
+}
+
+/// A ready task ranked for an agent by how well it suits them.
+///
+/// Each component is in 0.0..=1.0; `score` is their weighted sum.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimCandidate {
+    pub id: String,
+    pub title: String,
+    pub priority: Priority,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub points: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_estimate_ms: Option<i64>,
+    pub score: f64,
+    /// How closely the agent's tags match the task's needed/wanted tags (0.5 = no requirements).
+    pub tag_score: f64,
+    /// Task priority normalized to the configured scale.
+    pub priority_score: f64,
+    /// Deadline proximity from the `due_at` field (0 = no deadline); lifts the
+    /// priority component by `priorities.deadline_weight`.
+    pub deadline_score: f64,
+    /// How close the estimate is to the agent's typical task duration (0.5 = unknown).
+    pub estimate_score: f64,
+}
+
+/// A short-lived hold on a task, placed by `reserve` before a claim.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reservation {
+    pub task_id: String,
+    pub worker_id: String,
+    pub reserved_at: i64,
+    pub expires_at: i64,
+}
+
+/// A task a batch claim passed over, and why.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimSkip {
+    pub id: String,
+    pub reason: String,
+}
+
+/// Outcome of claiming several tasks at once.
+#[derive(Debug, Clone, Default)]
+pub struct ClaimBatch {
+    /// Tasks now owned by the agent, in the order tried.
+    pub claimed: Vec<Task>,
+    /// Tasks tried but not obtained.
+    pub skipped: Vec<ClaimSkip>,
+}
+
+impl ClaimBatch {
+    pub fn skip(&mut self, id: &str, reason: impl Into<String>) {
+        self.skipped.push(ClaimSkip {
+            id: id.to_string(),
+            reason: reason.into(),
+        });
+    }
+}
+
+/// An agent's recent share of high-priority claims, for fair-share ranking.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FairShare {
+    /// High-priority tasks this agent claimed within the window.
+    pub recent_claims: i32,
+    /// High-priority tasks claimed by all connected agents within the window.
+    pub total_claims: i32,
+    /// Connected agents sharing the work.
+    pub agents: i32,
+    /// Lowest priority counted as high-priority work.
+    pub high_priority: Priority,
+    /// Fraction of a high-priority task's priority score this agent loses (0 = fair).
+    pub penalty: f64,
+}
+
+impl FairShare {
+    /// Scale a priority score down for high-priority tasks by the agent's penalty.
+    pub fn adjust_priority_score(&self, priority: Priority, score: f64) -> f64 {
+        if priority >= self.high_priority {
+            score * (1.0 - self.penalty)
+        } else {
+            score
+        }
+    }
+}
+
+/// The single best next task for an agent, from `suggest_next_task`.
+///
+/// Each component is in 0.0..=1.0; `score` is their weighted sum.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSuggestion {
+    pub id: String,
+    pub title: String,
+    pub priority: Priority,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub points: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_estimate_ms: Option<i64>,
+    /// Deadline from the `due_at` custom field (epoch ms).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<i64>,
+    /// Longest chain of unfinished tasks waiting on this one.
+    pub downstream: i32,
+    pub score: f64,
+    /// Task priority normalized to the configured scale.
+    pub priority_score: f64,
+    /// How closely the agent's tags match the task's needed/wanted tags (0.5 = no requirements).
+    pub tag_score: f64,
+    /// Deadline proximity (1.0 = due now or overdue, 0.5 = due in a day, 0 = no deadline).
+    pub deadline_score: f64,
+    /// Downstream chain length relative to the longest among candidates (0.5 = no chains).
+    pub critical_path_score: f64,
+}
+
+/// A reminder fired before (or at) a claimed task's `due_at` deadline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadlineReminder {
+    pub task_id: String,
+    pub title: String,
+    /// Worker holding the task.
+    pub worker_id: String,
+    /// Deadline (epoch ms).
+    pub due_at: i64,
+    /// How long before the deadline the reminder was due (0 = at the deadline).
+    pub offset_ms: i64,
+    /// Whether the deadline has passed.
+    pub overdue: bool,
+}
+
+/// A claimed task's deadline, as listed by `query://deadlines`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskDeadline {
+    pub task_id: String,
+    pub title: String,
+    pub status: String,
+    pub worker_id: String,
+    /// Deadline (epoch ms).
+    pub due_at: i64,
+    pub overdue: bool,
+    /// Offsets (ms before `due_at`) whose reminders have fired for this deadline.
+    pub reminded: Vec<i64>,
+}
+
+/// Workload for one agent group (team).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupStats {
+    pub group: String,
+    /// Connected workers in the group.
+    pub members: Vec<String>,
+    /// Unfinished tasks that need this group.
+    pub open_tasks: i64,
+    /// Open tasks that nobody has claimed yet.
+    pub unclaimed_tasks: i64,
+    /// Sum of points over open tasks.
+    pub open_points: i64,
+    /// Finished tasks that needed this group.
+    pub completed_tasks: i64,
+    /// Tasks of any kind currently owned by group members.
+    pub claimed_by_members: i64,
+}
+
+/// Task counts and connected workers for one project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSummary {
+    pub project: String,
+    pub total_tasks: i64,
+    pub tasks_by_status: BTreeMap<String, i64>,
+    /// Workers connected to the project.
+    pub workers: Vec<String>,
+}
+
+/// Tasks listed to an agent matched by visibility rules: those carrying any of
+/// `tags`, plus every task in the subtrees rooted at `subtrees`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskScope {
+    /// Names of the visibility rules that produced this scope.
+    pub rules: Vec<String>,
+    pub tags: Vec<String>,
+    pub subtrees: Vec<String>,
+}
+
 /// Summary of stale worker cleanup operation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CleanupSummary {
@@ -442,6 +926,38 @@ pub struct CleanupSummary {
     pub final_status: String,
     /// IDs of evicted workers.
     pub evicted_worker_ids: Vec<String>,
+    /// Total number of tasks handed to other workers by a `reassign` policy.
+    #[serde(default)]
+    pub tasks_reassigned: i32,
+    /// Per-worker outcome of the stale-claim policy applied on eviction.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evictions: Vec<StaleEviction>,
+}
+
+/// Outcome of evicting one stale worker under its stale-claim policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleEviction {
+    pub worker_id: String,
+    /// Policy action applied (`requeue`, `fail`, `escalate`, `reassign`).
+    pub action: String,
+    /// Status given to released tasks.
+    pub status: String,
+    /// Tasks released with `status`.
+    pub tasks_released: Vec<String>,
+    /// Tasks handed to another worker in the `assigned` state.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reassigned: Vec<Reassignment>,
+    pub files_released: i32,
+    /// Coordinator task that received an escalation note.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalated_to: Option<String>,
+}
+
+/// A stale worker's task handed to a live worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reassignment {
+    pub task_id: String,
+    pub worker_id: String,
 }
 
 /// A task tag row for export/import.
@@ -465,6 +981,15 @@ pub struct TaskWantedTagRow {
     pub tag: String,
 }
 
+/// A task custom field row for export/import.
+/// The value is kept in its stored JSON-encoded form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskFieldRow {
+    pub task_id: String,
+    pub name: String,
+    pub value: String,
+}
+
 /// Exported tables container for database export.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ExportTables {
@@ -481,10 +1006,34 @@ pub struct ExportTables {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub task_wanted_tags: Option<Vec<TaskWantedTagRow>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_fields: Option<Vec<TaskFieldRow>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub task_sequence: Option<Vec<TaskSequenceEvent>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_changes: Option<Vec<TaskChange>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_thoughts: Option<Vec<TaskThought>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit_log: Option<Vec<AuditEntry>>,
 }
 
 #[cfg(test)]
 mod tests {
-    // Priority tests removed - Priority is now a type alias for i32
+    use super::*;
+
+    #[test]
+    fn test_tag_hierarchy() {
+        assert!(tag_matches("lang", "lang"));
+        assert!(tag_matches("lang", "lang/rust"));
+        assert!(tag_matches("lang/rust", "lang/rust/async"));
+        assert!(!tag_matches("lang", "language"));
+        assert!(!tag_matches("lang/rust", "lang"));
+
+        let ancestors: Vec<&str> = tag_ancestors("lang/rust/async").collect();
+        assert_eq!(ancestors, vec!["lang/rust", "lang"]);
+        assert_eq!(
+            with_tag_ancestors(&["lang/rust".to_string(), "lang/go".to_string()]),
+            vec!["lang/rust", "lang", "lang/go"]
+        );
+    }
 }
@@ -6,10 +6,13 @@
 use serde_json::json;
 use std::path::PathBuf;
 use std::sync::Arc;
-use task_graph_mcp::config::workflows::WorkflowsConfig;
+use task_graph_mcp::config::workflows::{RoleDefinition, WorkflowsConfig};
 use task_graph_mcp::config::{
-    AppConfig, AttachmentsConfig, AutoAdvanceConfig, DependenciesConfig, FeedbackConfig, IdsConfig,
-    PhasesConfig, ServerPaths, StatesConfig, TagsConfig,
+    AppConfig, AttachmentsConfig, AutoAdvanceConfig, ClaimingConfig, CustomFieldsConfig,
+    DependenciesConfig, FeedbackConfig, GroupDefinition, GroupsConfig, IdsConfig, LimitsConfig,
+    PhasesConfig, PrioritiesConfig, ServerPaths, SkillDelivery, SkillsConfig, SourceScanConfig,
+    StaleAction, StaleClaimsConfig, StalePolicy, StatesConfig, TagsConfig, ToolsConfig,
+    VisibilityConfig,
 };
 use task_graph_mcp::db::Database;
 use task_graph_mcp::tools::agents::{self, ConnectOptions};
@@ -30,8 +33,18 @@ fn default_app_config() -> AppConfig {
         Arc::new(AttachmentsConfig::default()),
         Arc::new(TagsConfig::default()),
         Arc::new(IdsConfig::default()),
+        Arc::new(CustomFieldsConfig::default()),
+        Arc::new(PrioritiesConfig::default()),
         workflows,
         Arc::new(FeedbackConfig::default()),
+        Arc::new(GroupsConfig::default()),
+        Arc::new(StaleClaimsConfig::default()),
+        Arc::new(ClaimingConfig::default()),
+        Arc::new(VisibilityConfig::default()),
+        Arc::new(SourceScanConfig::default()),
+        Arc::new(ToolsConfig::default()),
+        Arc::new(SkillsConfig::default()),
+        Arc::new(LimitsConfig::default()),
     )
 }
 
@@ -57,6 +70,7 @@ fn connect_without_workflow_returns_null_workflow() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "test-worker-no-workflow"
@@ -81,6 +95,7 @@ fn connect_with_workflow_returns_workflow_in_response() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "test-worker-with-workflow",
@@ -107,6 +122,7 @@ fn connect_stores_workflow_in_database() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "db-workflow-worker",
@@ -137,6 +153,7 @@ fn connect_stores_null_workflow_when_not_provided() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "no-workflow-worker"
@@ -166,6 +183,7 @@ fn connect_with_force_updates_workflow() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "force-workflow-worker",
@@ -183,6 +201,7 @@ fn connect_with_force_updates_workflow() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "force-workflow-worker",
@@ -216,6 +235,7 @@ fn connect_with_force_can_clear_workflow() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "clear-workflow-worker",
@@ -231,6 +251,7 @@ fn connect_with_force_can_clear_workflow() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "clear-workflow-worker",
@@ -263,6 +284,7 @@ fn connect_without_force_fails_for_existing_worker() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "duplicate-worker",
@@ -278,6 +300,7 @@ fn connect_without_force_fails_for_existing_worker() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "duplicate-worker",
@@ -302,6 +325,7 @@ fn connect_response_includes_all_expected_fields() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "full-response-worker",
@@ -340,6 +364,7 @@ fn connect_with_empty_workflow_string_stores_empty() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "empty-workflow-worker",
@@ -376,6 +401,7 @@ fn connect_with_overlays_stores_overlays_in_database() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "overlay-db-worker",
@@ -408,6 +434,7 @@ fn connect_response_includes_overlays_when_non_empty() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "overlay-response-worker",
@@ -438,6 +465,7 @@ fn connect_without_overlays_returns_no_overlays_field() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "no-overlay-worker"
@@ -471,6 +499,7 @@ fn connect_with_force_updates_overlays() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "force-overlay-worker",
@@ -492,6 +521,7 @@ fn connect_with_force_updates_overlays() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "force-overlay-worker",
@@ -533,6 +563,7 @@ fn connect_with_force_can_clear_overlays() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "clear-overlay-worker",
@@ -548,6 +579,7 @@ fn connect_with_force_can_clear_overlays() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "clear-overlay-worker",
@@ -580,6 +612,7 @@ fn list_workers_includes_overlays() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "overlay-list-a",
@@ -595,6 +628,7 @@ fn list_workers_includes_overlays() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "overlay-list-b"
@@ -631,6 +665,7 @@ fn list_workers_includes_workflow() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "worker-a",
@@ -645,6 +680,7 @@ fn list_workers_includes_workflow() {
             server_paths: &server_paths,
             config: &app_config,
             workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
         },
         json!({
             "worker_id": "worker-b"
@@ -664,3 +700,336 @@ fn list_workers_includes_workflow() {
     assert_eq!(worker_a.unwrap().workflow, Some("swarm".to_string()));
     assert!(worker_b.unwrap().workflow.is_none());
 }
+
+#[test]
+fn connect_joins_configured_groups() {
+    let db = setup_db();
+    let server_paths = test_server_paths();
+    let mut app_config = default_app_config();
+    let mut groups = GroupsConfig::default();
+    groups.definitions.insert(
+        "reviewers".to_string(),
+        GroupDefinition {
+            description: Some("Code reviewers".to_string()),
+            members: vec!["rev-1".to_string()],
+        },
+    );
+    app_config.groups = Arc::new(groups);
+
+    let result = agents::connect(
+        ConnectOptions {
+            db: &db,
+            server_paths: &server_paths,
+            config: &app_config,
+            workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
+        },
+        json!({ "worker_id": "rev-1" }),
+    )
+    .expect("connect should succeed");
+
+    assert_eq!(result["groups"], json!(["reviewers"]));
+    assert_eq!(db.get_group_members("reviewers").unwrap(), vec!["rev-1"]);
+}
+
+#[test]
+fn connect_delivers_configured_skills() {
+    let db = setup_db();
+    let server_paths = test_server_paths();
+    let dir = tempfile::tempdir().unwrap();
+    let skill_dir = dir.path().join("rust-style");
+    std::fs::create_dir_all(&skill_dir).unwrap();
+    std::fs::write(
+        skill_dir.join("SKILL.md"),
+        "---\ndescription: Rust conventions\n---\nWelcome {{agent_id}}.\n",
+    )
+    .unwrap();
+
+    let mut app_config = default_app_config();
+    app_config.skills = Arc::new(SkillsConfig {
+        on_connect: vec![
+            SkillDelivery {
+                role: None,
+                tags: vec!["rust".to_string()],
+                skills: vec!["rust-style".to_string(), "missing".to_string()],
+            },
+            SkillDelivery {
+                role: Some("reviewer".to_string()),
+                tags: vec![],
+                skills: vec!["repair".to_string()],
+            },
+        ],
+        include_content: true,
+    });
+    let connect_as = |worker_id: &str, tags: serde_json::Value| {
+        agents::connect(
+            ConnectOptions {
+                db: &db,
+                server_paths: &server_paths,
+                config: &app_config,
+                workflows: &WorkflowsConfig::default(),
+                skills_dir: Some(dir.path()),
+            },
+            json!({ "worker_id": worker_id, "tags": tags }),
+        )
+        .expect("connect should succeed")
+    };
+
+    let result = connect_as("ferris", json!(["rust"]));
+    let skills = result["skills"].as_array().unwrap();
+    assert_eq!(skills.len(), 1);
+    assert_eq!(skills[0]["name"], "rust-style");
+    assert_eq!(
+        skills[0]["content"],
+        "---\ndescription: Rust conventions\n---\nWelcome ferris.\n"
+    );
+    assert!(
+        result["skill_warnings"][0]
+            .as_str()
+            .unwrap()
+            .contains("'missing'")
+    );
+
+    // No rule matches: nothing delivered
+    let result = connect_as("gopher", json!(["go"]));
+    assert!(result.get("skills").is_none());
+    assert!(result.get("skill_warnings").is_none());
+}
+
+/// Connect with the default test config.
+fn connect(db: &Database, args: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    agents::connect(
+        ConnectOptions {
+            db,
+            server_paths: &test_server_paths(),
+            config: &default_app_config(),
+            workflows: &WorkflowsConfig::default(),
+            skills_dir: None,
+        },
+        args,
+    )
+}
+
+#[test]
+fn connect_resume_restores_claims() {
+    let db = setup_db();
+    let states = StatesConfig::default();
+    connect(&db, json!({ "worker_id": "crashy", "tags": ["rust"] })).unwrap();
+    let task = db
+        .create_task_simple("Half done", &states, &IdsConfig::default())
+        .unwrap();
+    db.claim_task(&task.id, "crashy", &states).unwrap();
+
+    // Without resume the stale registration collides
+    assert!(connect(&db, json!({ "worker_id": "crashy" })).is_err());
+
+    let result = connect(&db, json!({ "worker_id": "crashy", "resume": true })).unwrap();
+    assert_eq!(result["resumed"], json!(true));
+    assert_eq!(result["tags"], json!(["rust"]));
+    assert_eq!(result["session"]["claims"][0]["id"], json!(task.id));
+}
+
+#[test]
+fn connect_resume_after_grace_window_starts_fresh() {
+    let db = setup_db();
+    let states = StatesConfig::default();
+    connect(&db, json!({ "worker_id": "gone-too-long" })).unwrap();
+    let task = db
+        .create_task_simple("Abandoned", &states, &IdsConfig::default())
+        .unwrap();
+    db.claim_task(&task.id, "gone-too-long", &states).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+
+    let result = connect(
+        &db,
+        json!({ "worker_id": "gone-too-long", "resume": true, "grace_seconds": 0 }),
+    )
+    .unwrap();
+    assert_eq!(result["resumed"], json!(false));
+    assert_eq!(result["expired_session"]["tasks_released"], json!(1));
+    assert!(result.get("session").is_none());
+
+    let task = db.get_task(&task.id).unwrap().unwrap();
+    assert!(task.worker_id.is_none());
+    assert_eq!(task.status, states.disconnect_state);
+    assert!(db.get_worker("gone-too-long").unwrap().is_some());
+}
+
+#[test]
+fn connect_resume_requires_worker_id() {
+    let db = setup_db();
+    assert!(connect(&db, json!({ "resume": true })).is_err());
+}
+
+#[test]
+fn connect_sets_project_and_resume_keeps_it() {
+    let db = setup_db();
+    let result = connect(&db, json!({ "worker_id": "ann", "project": "alpha" })).unwrap();
+    assert_eq!(result["project"], json!("alpha"));
+    assert_eq!(db.get_worker("ann").unwrap().unwrap().project, "alpha");
+
+    let result = connect(&db, json!({ "worker_id": "ann", "resume": true })).unwrap();
+    assert_eq!(result["project"], json!("alpha"));
+
+    // A resumed session cannot switch projects
+    assert!(
+        connect(
+            &db,
+            json!({ "worker_id": "ann", "resume": true, "project": "beta" })
+        )
+        .is_err()
+    );
+    assert!(connect(&db, json!({ "worker_id": "bob", "project": "no/slash" })).is_err());
+}
+
+/// AppConfig with the given stale-claim policy as the default.
+fn app_config_with_stale_policy(policy: StalePolicy) -> AppConfig {
+    let mut app_config = default_app_config();
+    app_config.stale_claims = Arc::new(StaleClaimsConfig {
+        default: policy,
+        ..Default::default()
+    });
+    app_config
+}
+
+#[test]
+fn cleanup_stale_fail_policy_marks_tasks_failed() {
+    let db = setup_db();
+    let states = StatesConfig::default();
+    let app_config = app_config_with_stale_policy(StalePolicy {
+        action: StaleAction::Fail,
+        ..Default::default()
+    });
+    connect(&db, json!({ "worker_id": "flaky" })).unwrap();
+    let task = db
+        .create_task_simple("Doomed", &states, &IdsConfig::default())
+        .unwrap();
+    db.claim_task(&task.id, "flaky", &states).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+
+    let result = agents::cleanup_stale(&db, &app_config, json!({ "timeout": 0 })).unwrap();
+    assert_eq!(result["workers_evicted"], json!(1));
+    assert_eq!(result["evictions"][0]["action"], json!("fail"));
+
+    let task = db.get_task(&task.id).unwrap().unwrap();
+    assert_eq!(task.status, "failed");
+    assert!(task.worker_id.is_none());
+}
+
+#[test]
+fn cleanup_stale_final_status_overrides_policy() {
+    let db = setup_db();
+    let states = StatesConfig::default();
+    let app_config = app_config_with_stale_policy(StalePolicy {
+        action: StaleAction::Fail,
+        ..Default::default()
+    });
+    connect(&db, json!({ "worker_id": "flaky" })).unwrap();
+    let task = db
+        .create_task_simple("Retry me", &states, &IdsConfig::default())
+        .unwrap();
+    db.claim_task(&task.id, "flaky", &states).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+
+    agents::cleanup_stale(
+        &db,
+        &app_config,
+        json!({ "timeout": 0, "final_status": "pending" }),
+    )
+    .unwrap();
+    assert_eq!(db.get_task(&task.id).unwrap().unwrap().status, "pending");
+}
+
+#[test]
+fn cleanup_stale_escalate_policy_notes_coordinator() {
+    let db = setup_db();
+    let states = StatesConfig::default();
+    let coordinator = db
+        .create_task_simple("Coordinate", &states, &IdsConfig::default())
+        .unwrap();
+    let app_config = app_config_with_stale_policy(StalePolicy {
+        action: StaleAction::Escalate,
+        coordinator_task: Some(coordinator.id.clone()),
+        ..Default::default()
+    });
+    connect(&db, json!({ "worker_id": "flaky" })).unwrap();
+    let task = db
+        .create_task_simple("Orphaned", &states, &IdsConfig::default())
+        .unwrap();
+    db.claim_task(&task.id, "flaky", &states).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+
+    let result = agents::cleanup_stale(&db, &app_config, json!({ "timeout": 0 })).unwrap();
+    assert_eq!(
+        result["evictions"][0]["escalated_to"],
+        json!(coordinator.id)
+    );
+    assert_eq!(
+        db.get_task(&task.id).unwrap().unwrap().status,
+        states.disconnect_state
+    );
+
+    let notes = db.get_attachments(&coordinator.id).unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].attachment_type, "escalation");
+}
+
+#[test]
+fn cleanup_stale_reassign_policy_hands_tasks_to_role() {
+    let db = setup_db();
+    let states = StatesConfig::default();
+    let mut app_config = app_config_with_stale_policy(StalePolicy {
+        action: StaleAction::Reassign,
+        role: Some("worker".to_string()),
+        ..Default::default()
+    });
+    let mut workflows = WorkflowsConfig::default();
+    workflows.roles.insert(
+        "worker".to_string(),
+        RoleDefinition {
+            tags: vec!["backend".to_string()],
+            ..Default::default()
+        },
+    );
+    app_config.workflows = Arc::new(workflows);
+
+    connect(&db, json!({ "worker_id": "flaky", "tags": ["backend"] })).unwrap();
+    connect(&db, json!({ "worker_id": "steady", "tags": ["backend"] })).unwrap();
+    let task = db
+        .create_task_simple("Keep going", &states, &IdsConfig::default())
+        .unwrap();
+    db.claim_task(&task.id, "flaky", &states).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    db.heartbeat("steady").unwrap();
+
+    let result = agents::cleanup_stale(&db, &app_config, json!({ "timeout": 1 })).unwrap();
+    assert_eq!(result["evicted_worker_ids"], json!(["flaky"]));
+    assert_eq!(result["tasks_reassigned"], json!(1));
+
+    let task = db.get_task(&task.id).unwrap().unwrap();
+    assert_eq!(task.worker_id.as_deref(), Some("steady"));
+    assert_eq!(task.status, "assigned");
+}
+
+#[test]
+fn stale_claims_validate_requires_action_settings() {
+    let states = StatesConfig::default();
+    let config = StaleClaimsConfig {
+        default: StalePolicy {
+            action: StaleAction::Escalate,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert!(config.validate(&states).is_err());
+
+    let config = StaleClaimsConfig {
+        default: StalePolicy {
+            status: Some("working".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert!(config.validate(&states).is_err());
+    assert!(StaleClaimsConfig::default().validate(&states).is_ok());
+}
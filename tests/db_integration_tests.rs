@@ -3,14 +3,22 @@
 //! These tests verify the core database operations using an in-memory SQLite database.
 //! Tests are organized by module and functionality.
 
+use serde_json::Value;
+use std::path::Path;
 use std::sync::Arc;
 use task_graph_mcp::config::workflows::WorkflowsConfig;
 use task_graph_mcp::config::{
-    AppConfig, AttachmentsConfig, AutoAdvanceConfig, DependenciesConfig, FeedbackConfig, IdsConfig,
-    PhasesConfig, StatesConfig, TagsConfig,
+    AppConfig, AttachmentsConfig, AutoAdvanceConfig, ClaimingConfig, CustomFieldsConfig,
+    DependenciesConfig, FeedbackConfig, GroupsConfig, IdsConfig, LimitsConfig, PhasesConfig,
+    PrioritiesConfig, Prompts, ServerPaths, SkillsConfig, SourceScanConfig, StaleClaimsConfig,
+    StatesConfig, TagsConfig, ToolsConfig, VisibilityConfig,
 };
 use task_graph_mcp::db::Database;
 use task_graph_mcp::db::tasks::ListTasksQuery;
+use task_graph_mcp::format::OutputFormat;
+use task_graph_mcp::paths::PathMapper;
+use task_graph_mcp::tools::ToolHandler;
+use task_graph_mcp::tools::tasks::create;
 use task_graph_mcp::types::PRIORITY_DEFAULT;
 
 /// Helper to create a fresh in-memory database for testing.
@@ -48,8 +56,71 @@ fn default_app_config() -> AppConfig {
         Arc::new(AttachmentsConfig::default()),
         Arc::new(TagsConfig::default()),
         Arc::new(IdsConfig::default()),
+        Arc::new(CustomFieldsConfig::default()),
+        Arc::new(PrioritiesConfig::default()),
         Arc::new(WorkflowsConfig::default()),
         Arc::new(FeedbackConfig::default()),
+        Arc::new(GroupsConfig::default()),
+        Arc::new(StaleClaimsConfig::default()),
+        Arc::new(ClaimingConfig::default()),
+        Arc::new(VisibilityConfig::default()),
+        Arc::new(SourceScanConfig::default()),
+        Arc::new(ToolsConfig::default()),
+        Arc::new(SkillsConfig::default()),
+        Arc::new(LimitsConfig::default()),
+    )
+}
+
+/// Helper to create a task from `create` arguments and return its ID.
+fn new_task(db: &Database, config: &AppConfig, args: Value) -> String {
+    create(db, config, args).unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Helper to register a worker with no tags.
+fn register(db: &Database, id: &str) {
+    register_with_tags(db, id, &[]);
+}
+
+/// Helper to register a worker with the given tags.
+fn register_with_tags(db: &Database, id: &str, tags: &[&str]) {
+    db.register_worker(
+        Some(id.to_string()),
+        tags.iter().map(|t| t.to_string()).collect(),
+        false,
+        &default_ids_config(),
+        None,
+        vec![],
+    )
+    .unwrap();
+}
+
+/// Helper to create a ToolHandler over `db` with the default config, keeping
+/// its files under `dir`.
+fn handler(db: Database, dir: &Path) -> ToolHandler {
+    handler_with_config(db, default_app_config(), dir)
+}
+
+/// Helper to create a ToolHandler over `db` with `config`, keeping its files
+/// under `dir`.
+fn handler_with_config(db: Database, config: AppConfig, dir: &Path) -> ToolHandler {
+    ToolHandler::new(
+        Arc::new(db),
+        dir.join("media"),
+        dir.join("skills"),
+        Arc::new(ServerPaths {
+            db_path: dir.join("tasks.db"),
+            media_dir: dir.join("media"),
+            log_dir: dir.join("logs"),
+            config_path: None,
+        }),
+        Arc::new(Prompts::default()),
+        config,
+        OutputFormat::Json,
+        50,
+        Arc::new(PathMapper::default()),
     )
 }
 
@@ -1725,14 +1796,11 @@ mod task_tests {
 
         let db = setup_db();
         let _ids = create_n_tasks(&db, 5);
-        let states_config = default_states_config();
-        let deps_config = default_deps_config();
 
         // Request limit=3 from 5 total tasks => has_more should be true
         let result = list_tasks(
             &db,
-            &states_config,
-            &deps_config,
+            &default_app_config(),
             OutputFormat::Json,
             json!({
                 "limit": 3,
@@ -1759,14 +1827,11 @@ mod task_tests {
 
         let db = setup_db();
         let _ids = create_n_tasks(&db, 5);
-        let states_config = default_states_config();
-        let deps_config = default_deps_config();
 
         // Request offset=3, limit=3: only 2 remaining => has_more should be false
         let result = list_tasks(
             &db,
-            &states_config,
-            &deps_config,
+            &default_app_config(),
             OutputFormat::Json,
             json!({
                 "limit": 3,
@@ -1793,14 +1858,11 @@ mod task_tests {
 
         let db = setup_db();
         let _ids = create_n_tasks(&db, 6);
-        let states_config = default_states_config();
-        let deps_config = default_deps_config();
 
         // Request limit=3, offset=3 with exactly 6 tasks: 3 remaining fits exactly
         let result = list_tasks(
             &db,
-            &states_config,
-            &deps_config,
+            &default_app_config(),
             OutputFormat::Json,
             json!({
                 "limit": 3,
@@ -1826,8 +1888,6 @@ mod task_tests {
 
         let db = setup_db();
         let ids = create_n_tasks(&db, 5);
-        let states_config = default_states_config();
-        let deps_config = default_deps_config();
 
         // Collect all task IDs across pages (limit=2)
         let mut all_ids: Vec<String> = Vec::new();
@@ -1836,8 +1896,7 @@ mod task_tests {
         loop {
             let result = list_tasks(
                 &db,
-                &states_config,
-                &deps_config,
+                &default_app_config(),
                 OutputFormat::Json,
                 json!({
                     "limit": 2,
@@ -1876,14 +1935,11 @@ mod task_tests {
 
         let db = setup_db();
         let _ids = create_n_tasks(&db, 5);
-        let states_config = default_states_config();
-        let deps_config = default_deps_config();
 
         // No limit => all tasks returned, has_more is false
         let result = list_tasks(
             &db,
-            &states_config,
-            &deps_config,
+            &default_app_config(),
             OutputFormat::Json,
             json!({
                 "sort_by": "created_at",
@@ -1907,14 +1963,11 @@ mod task_tests {
 
         let db = setup_db();
         let ids = create_n_tasks(&db, 10);
-        let states_config = default_states_config();
-        let deps_config = default_deps_config();
 
         // offset=3, limit=4 with 10 tasks => 7 remaining, return 4, has_more=true
         let result = list_tasks(
             &db,
-            &states_config,
-            &deps_config,
+            &default_app_config(),
             OutputFormat::Json,
             json!({
                 "limit": 4,
@@ -1942,14 +1995,11 @@ mod task_tests {
 
         let db = setup_db();
         let _ids = create_n_tasks(&db, 5);
-        let states_config = default_states_config();
-        let deps_config = default_deps_config();
 
         // Request limit=2 from 5 total tasks in markdown format
         let result = list_tasks(
             &db,
-            &states_config,
-            &deps_config,
+            &default_app_config(),
             OutputFormat::Markdown,
             json!({
                 "limit": 2,
@@ -3647,7 +3697,7 @@ mod dependency_tests {
             .unwrap();
 
         let ready = db
-            .get_ready_tasks(None, &states_config, &deps_config, None, None)
+            .get_ready_tasks(None, &states_config, &deps_config, None, None, None)
             .unwrap();
 
         // task1 is ready, task2 is blocked
@@ -3722,7 +3772,7 @@ mod dependency_tests {
         .unwrap();
 
         let ready = db
-            .get_ready_tasks(None, &states_config, &deps_config, None, None)
+            .get_ready_tasks(None, &states_config, &deps_config, None, None, None)
             .unwrap();
 
         // Now task2 is ready
@@ -3812,7 +3862,7 @@ mod dependency_tests {
             .unwrap();
 
         let ready = db
-            .get_ready_tasks(None, &states_config, &deps_config, None, None)
+            .get_ready_tasks(None, &states_config, &deps_config, None, None, None)
             .unwrap();
 
         // Container task should NOT appear in ready results (it has children)
@@ -3882,7 +3932,7 @@ mod dependency_tests {
 
         // Parent should NOT be ready (it's a container)
         let ready = db
-            .get_ready_tasks(None, &states_config, &deps_config, None, None)
+            .get_ready_tasks(None, &states_config, &deps_config, None, None, None)
             .unwrap();
         let ready_ids: Vec<&str> = ready.iter().map(|t| t.id.as_str()).collect();
         assert!(
@@ -3900,7 +3950,7 @@ mod dependency_tests {
 
         // Now parent should be ready (no longer a container)
         let ready = db
-            .get_ready_tasks(None, &states_config, &deps_config, None, None)
+            .get_ready_tasks(None, &states_config, &deps_config, None, None, None)
             .unwrap();
         let ready_ids: Vec<&str> = ready.iter().map(|t| t.id.as_str()).collect();
         assert!(
@@ -5818,4 +5868,9928 @@ mod rename_tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("not found"));
     }
+
+    fn create_named(db: &Database, id: &str) {
+        db.create_task(
+            Some(id.to_string()),
+            id.to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &default_states_config(),
+            &default_ids_config(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn rename_task_keeps_old_id_as_alias() {
+        let db = setup_db();
+        create_named(&db, "task-a");
+
+        db.rename_task("task-a", "task-b").unwrap();
+        db.rename_task("task-b", "task-c").unwrap();
+
+        // Both previous IDs resolve to the current one
+        assert_eq!(
+            db.resolve_task_alias("task-a").unwrap().as_deref(),
+            Some("task-c")
+        );
+        assert_eq!(
+            db.resolve_task_alias("task-b").unwrap().as_deref(),
+            Some("task-c")
+        );
+        assert_eq!(db.get_task_aliases("task-c").unwrap().len(), 2);
+
+        // Live and unknown IDs are not aliases
+        assert!(db.resolve_task_alias("task-c").unwrap().is_none());
+        assert!(db.resolve_task_alias("nope").unwrap().is_none());
+    }
+
+    #[test]
+    fn alias_is_shadowed_by_live_task() {
+        let db = setup_db();
+        create_named(&db, "task-a");
+        db.rename_task("task-a", "task-b").unwrap();
+
+        // A new task reusing the old ID takes precedence over the alias
+        create_named(&db, "task-a");
+        assert!(db.resolve_task_alias("task-a").unwrap().is_none());
+
+        // Renaming back onto an alias drops the stale alias
+        db.rename_task("task-b", "task-x").unwrap();
+        db.rename_task("task-x", "task-b").unwrap();
+        assert_eq!(
+            db.get_task_aliases("task-b").unwrap(),
+            vec!["task-a".to_string(), "task-x".to_string()]
+        );
+    }
+
+    #[test]
+    fn aliases_removed_when_task_obliterated() {
+        let db = setup_db();
+        create_named(&db, "task-a");
+        db.rename_task("task-a", "task-b").unwrap();
+
+        db.delete_task("task-b", "w1", false, None, true, false)
+            .unwrap();
+        assert!(db.resolve_task_alias("task-a").unwrap().is_none());
+    }
+}
+
+mod clone_tests {
+    use super::*;
+    use task_graph_mcp::db::template::InstantiateOptions;
+
+    fn create(db: &Database, id: &str, parent: Option<&str>, tags: Vec<&str>) {
+        db.create_task(
+            Some(id.to_string()),
+            id.to_string(),
+            None,
+            parent.map(String::from),
+            Some("implement".to_string()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(tags.into_iter().map(String::from).collect()),
+            &default_states_config(),
+            &default_ids_config(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn clone_subtree_copies_tasks_with_fresh_ids() {
+        let db = setup_db();
+        let dc = default_deps_config();
+        create(&db, "phase", None, vec!["sprint"]);
+        create(&db, "design", Some("phase"), vec![]);
+        create(&db, "build", Some("phase"), vec!["rust"]);
+        create(&db, "outside", None, vec![]);
+        db.add_dependency("design", "build", "blocks", &dc).unwrap();
+        db.add_dependency("outside", "build", "blocks", &dc)
+            .unwrap();
+        db.add_attachment(
+            "build",
+            "note".to_string(),
+            String::new(),
+            "remember the tests".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let result = db
+            .clone_subtree("phase", &default_ids_config(), &InstantiateOptions::new())
+            .unwrap();
+
+        assert_eq!(result.id_map.len(), 3);
+        let new_root = &result.id_map["phase"];
+        let new_design = &result.id_map["design"];
+        let new_build = &result.id_map["build"];
+        assert_ne!(new_root, "phase");
+
+        // Hierarchy and internal dependencies are rewired to the copies
+        let mut children = db.get_children_ids(new_root).unwrap();
+        children.sort();
+        let mut expected = vec![new_design.clone(), new_build.clone()];
+        expected.sort();
+        assert_eq!(children, expected);
+        assert_eq!(
+            db.get_blockers(new_build).unwrap(),
+            vec![new_design.clone()]
+        );
+
+        // Tags, phase, and attachments are copied
+        let build_copy = db.get_task(new_build).unwrap().unwrap();
+        assert_eq!(build_copy.tags, vec!["rust"]);
+        assert_eq!(build_copy.phase.as_deref(), Some("implement"));
+        assert_eq!(db.get_attachments(new_build).unwrap().len(), 1);
+
+        // Originals are untouched
+        assert_eq!(db.get_children_ids("phase").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn clone_subtree_resets_runtime_state_and_attaches_to_parent() {
+        let db = setup_db();
+        let sc = default_states_config();
+        create(&db, "holder", None, vec![]);
+        create(&db, "phase", None, vec![]);
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        db.claim_task("phase", "w1", &sc).unwrap();
+        db.log_metrics("phase", Some(1.5), &[10, 20]).unwrap();
+
+        let mut options = InstantiateOptions::new()
+            .with_parent("holder")
+            .with_extra_tags(vec!["copy".to_string()]);
+        options.initial_status = Some(sc.initial.clone());
+        let result = db
+            .clone_subtree("phase", &default_ids_config(), &options)
+            .unwrap();
+
+        let copy = db.get_task(&result.id_map["phase"]).unwrap().unwrap();
+        assert_eq!(copy.status, sc.initial);
+        assert!(copy.worker_id.is_none());
+        assert!(copy.started_at.is_none());
+        assert_eq!(copy.cost_usd, 0.0);
+        assert_eq!(copy.metrics, [0; 8]);
+        assert_eq!(copy.tags, vec!["copy"]);
+        assert_eq!(db.get_parent(&copy.id).unwrap().as_deref(), Some("holder"));
+    }
+
+    #[test]
+    fn clone_subtree_skips_deleted_descendants() {
+        let db = setup_db();
+        create(&db, "phase", None, vec![]);
+        create(&db, "keep", Some("phase"), vec![]);
+        create(&db, "gone", Some("phase"), vec![]);
+        db.delete_task("gone", "w1", false, None, false, false)
+            .unwrap();
+
+        let result = db
+            .clone_subtree("phase", &default_ids_config(), &InstantiateOptions::new())
+            .unwrap();
+
+        assert_eq!(result.id_map.len(), 2);
+        assert!(!result.id_map.contains_key("gone"));
+    }
+
+    #[test]
+    fn clone_tree_copies_media_files_so_detaching_the_clone_keeps_the_original() {
+        use task_graph_mcp::tools::attachments::{attach, detach};
+        use task_graph_mcp::tools::tasks::clone_tree;
+
+        let db = setup_db();
+        let media = tempfile::TempDir::new().unwrap();
+        create(&db, "phase", None, vec![]);
+        attach(
+            &db,
+            media.path(),
+            &AttachmentsConfig::default(),
+            serde_json::json!({"task": "phase", "type": "notes", "mime": "text/plain",
+                               "content": "keep me", "store_as_file": true}),
+        )
+        .unwrap();
+        let original = db.get_attachments("phase").unwrap()[0]
+            .file_path
+            .clone()
+            .unwrap();
+
+        let result = clone_tree(
+            &db,
+            &default_app_config(),
+            media.path(),
+            serde_json::json!({"task": "phase"}),
+        )
+        .unwrap();
+        let copy_id = result["root"].as_str().unwrap();
+        let copy = db.get_attachments(copy_id).unwrap()[0]
+            .file_path
+            .clone()
+            .unwrap();
+        assert_ne!(copy, original);
+        assert_eq!(std::fs::read_to_string(&copy).unwrap(), "keep me");
+
+        detach(
+            &db,
+            media.path(),
+            serde_json::json!({"task": copy_id, "type": "notes", "delete_files": true}),
+        )
+        .unwrap();
+        assert!(!std::path::Path::new(&copy).exists());
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "keep me");
+    }
+
+    #[test]
+    fn clone_subtree_fails_for_unknown_root() {
+        let db = setup_db();
+        let result = db.clone_subtree("missing", &default_ids_config(), &InstantiateOptions::new());
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
+}
+
+mod split_tests {
+    use super::*;
+    use task_graph_mcp::db::tasks::SplitTaskOptions;
+
+    fn split_opts<'a>(
+        task_id: &'a str,
+        worker_id: &'a str,
+        titles: &[&str],
+        config: &'a AppConfig,
+    ) -> SplitTaskOptions<'a> {
+        SplitTaskOptions {
+            task_id,
+            worker_id,
+            titles: titles.iter().map(|t| t.to_string()).collect(),
+            sibling_type: None,
+            distribute_estimate: false,
+            force: false,
+            states_config: &config.states,
+            phases_config: &config.phases,
+            tags_config: &config.tags,
+            ids_config: &config.ids,
+        }
+    }
+
+    fn setup_claimed(db: &Database, config: &AppConfig) {
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &config.ids,
+            None,
+            vec![],
+        )
+        .unwrap();
+        db.create_task(
+            Some("big".to_string()),
+            "Big task".to_string(),
+            None,
+            None,
+            Some("implement".to_string()),
+            Some(8),
+            Some(5),
+            Some(10_000),
+            None,
+            None,
+            Some(vec!["backend".to_string()]),
+            &config.states,
+            &config.ids,
+        )
+        .unwrap();
+        db.claim_task("big", "w1", &config.states).unwrap();
+    }
+
+    #[test]
+    fn split_creates_children_under_original() {
+        let db = setup_db();
+        let config = default_app_config();
+        setup_claimed(&db, &config);
+
+        let (ids, _) = db
+            .split_task(split_opts("big", "w1", &["Part A", "Part B"], &config))
+            .unwrap();
+
+        assert_eq!(ids.len(), 2);
+        assert_eq!(db.get_children_ids("big").unwrap().len(), 2);
+
+        let child = db.get_task(&ids[0]).unwrap().unwrap();
+        assert_eq!(child.title, "Part A");
+        assert_eq!(child.phase.as_deref(), Some("implement"));
+        assert_eq!(child.priority, 8);
+        assert_eq!(child.tags, vec!["backend"]);
+        assert_eq!(child.status, config.states.initial);
+        assert!(child.time_estimate_ms.is_none());
+
+        // Original keeps its claim
+        let original = db.get_task("big").unwrap().unwrap();
+        assert_eq!(original.worker_id.as_deref(), Some("w1"));
+    }
+
+    #[test]
+    fn split_distributes_remaining_estimate() {
+        let db = setup_db();
+        let config = default_app_config();
+        setup_claimed(&db, &config);
+        db.log_time("big", 4_000).unwrap();
+
+        let mut opts = split_opts("big", "w1", &["A", "B", "C", "D"], &config);
+        opts.distribute_estimate = true;
+        opts.sibling_type = Some("follows".to_string());
+        let (ids, _) = db.split_task(opts).unwrap();
+
+        let tasks: Vec<_> = ids
+            .iter()
+            .map(|id| db.get_task(id).unwrap().unwrap())
+            .collect();
+        let times: Vec<_> = tasks.iter().map(|t| t.time_estimate_ms).collect();
+        assert_eq!(times, vec![Some(1_500); 4]);
+        let points: Vec<_> = tasks.iter().map(|t| t.points).collect();
+        assert_eq!(points, vec![Some(2), Some(1), Some(1), Some(1)]);
+
+        // Sequential children via sibling_type
+        let follows: Vec<_> = db
+            .get_all_dependencies()
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.dep_type == "follows")
+            .collect();
+        assert_eq!(follows.len(), 3);
+    }
+
+    #[test]
+    fn split_rejects_non_owner() {
+        let db = setup_db();
+        let config = default_app_config();
+        setup_claimed(&db, &config);
+
+        let result = db.split_task(split_opts("big", "w2", &["A"], &config));
+        assert!(result.is_err());
+        assert!(db.get_children_ids("big").unwrap().is_empty());
+
+        let mut opts = split_opts("big", "w2", &["A"], &config);
+        opts.force = true;
+        assert!(db.split_task(opts).is_ok());
+    }
+}
+
+mod id_resolution_tests {
+    use super::*;
+    use task_graph_mcp::db::tasks::TaskIdMatch;
+    use task_graph_mcp::error::{ErrorCode, ToolError};
+
+    fn create_named(db: &Database, id: &str) {
+        db.create_task(
+            Some(id.to_string()),
+            id.to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &default_states_config(),
+            &default_ids_config(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn exact_id_is_unchanged() {
+        let db = setup_db();
+        create_named(&db, "gentle");
+        create_named(&db, "gentle-turtle");
+
+        // An exact match wins even though it is also a prefix of another ID
+        assert_eq!(
+            db.resolve_task_id("gentle").unwrap(),
+            TaskIdMatch::Unchanged
+        );
+    }
+
+    #[test]
+    fn unique_prefix_expands_to_full_id() {
+        let db = setup_db();
+        create_named(&db, "gentle-turtle");
+        create_named(&db, "brave-otter");
+
+        assert_eq!(
+            db.resolve_task_id("gentle-t").unwrap(),
+            TaskIdMatch::Prefix("gentle-turtle".to_string())
+        );
+        assert_eq!(
+            db.resolve_task_id("missing").unwrap(),
+            TaskIdMatch::Unchanged
+        );
+    }
+
+    #[test]
+    fn ambiguous_prefix_lists_candidates() {
+        let db = setup_db();
+        create_named(&db, "gentle-turtle");
+        create_named(&db, "gentle-tiger");
+        create_named(&db, "brave-otter");
+
+        let err = db.resolve_task_id("gentle-t").unwrap_err();
+        let tool_err = err.downcast::<ToolError>().unwrap();
+        assert_eq!(tool_err.code, ErrorCode::AmbiguousId);
+        assert_eq!(
+            tool_err.candidates.unwrap(),
+            vec!["gentle-tiger".to_string(), "gentle-turtle".to_string()]
+        );
+    }
+
+    #[test]
+    fn prefix_ignores_deleted_tasks_and_prefers_aliases() {
+        let db = setup_db();
+        create_named(&db, "gentle-turtle");
+        create_named(&db, "gentle-tiger");
+        db.delete_task("gentle-tiger", "w1", false, None, false, false)
+            .unwrap();
+
+        assert_eq!(
+            db.resolve_task_id("gentle").unwrap(),
+            TaskIdMatch::Prefix("gentle-turtle".to_string())
+        );
+
+        create_named(&db, "old");
+        db.rename_task("old", "older-name").unwrap();
+        assert_eq!(
+            db.resolve_task_id("old").unwrap(),
+            TaskIdMatch::Alias("older-name".to_string())
+        );
+    }
+}
+
+mod id_scheme_tests {
+    use super::*;
+    use task_graph_mcp::config::IdScheme;
+    use task_graph_mcp::db::tasks::CreateTreeOptions;
+    use task_graph_mcp::types::TaskTreeInput;
+
+    fn ids(scheme: IdScheme, prefix: Option<&str>) -> IdsConfig {
+        IdsConfig {
+            task_id_scheme: scheme,
+            task_id_prefix: prefix.map(String::from),
+            ..IdsConfig::default()
+        }
+    }
+
+    fn create(db: &Database, id: Option<&str>, ic: &IdsConfig) -> String {
+        db.create_task(
+            id.map(String::from),
+            "Task".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &default_states_config(),
+            ic,
+        )
+        .unwrap()
+        .id
+    }
+
+    #[test]
+    fn prefixed_petname_ids() {
+        let db = setup_db();
+        let id = create(&db, None, &ids(IdScheme::Petname, Some("auth")));
+        assert!(id.starts_with("auth-"));
+        assert_eq!(id.split('-').count(), 3);
+    }
+
+    #[test]
+    fn sequential_ids_are_monotonic() {
+        let db = setup_db();
+        let ic = ids(IdScheme::Sequential, None);
+
+        assert_eq!(create(&db, None, &ic), "T-0001");
+        assert_eq!(create(&db, None, &ic), "T-0002");
+
+        // Obliterated IDs are not reissued
+        db.delete_task("T-0002", "w1", false, None, true, false)
+            .unwrap();
+        assert_eq!(create(&db, None, &ic), "T-0003");
+    }
+
+    #[test]
+    fn sequential_ids_seed_from_existing_and_skip_taken() {
+        let db = setup_db();
+        let ic = IdsConfig {
+            sequence_digits: 3,
+            ..ids(IdScheme::Sequential, Some("AUTH"))
+        };
+
+        create(&db, Some("AUTH-041"), &default_ids_config());
+        create(&db, Some("AUTH-043"), &default_ids_config());
+        create(&db, Some("AUTH-notes"), &default_ids_config());
+
+        assert_eq!(create(&db, None, &ic), "AUTH-044");
+
+        // Explicitly named tasks claiming the next number are skipped over
+        create(&db, Some("AUTH-045"), &default_ids_config());
+        assert_eq!(create(&db, None, &ic), "AUTH-046");
+    }
+
+    #[test]
+    fn sequential_ids_in_task_tree() {
+        let db = setup_db();
+        let config = AppConfig {
+            ids: Arc::new(ids(IdScheme::Sequential, None)),
+            ..default_app_config()
+        };
+        let input: TaskTreeInput = serde_json::from_value(serde_json::json!({
+            "title": "Root",
+            "children": [{ "title": "A" }, { "title": "B" }]
+        }))
+        .unwrap();
+
+        let (root, all_ids, _, _) = db
+            .create_task_tree(CreateTreeOptions {
+                input,
+                parent_id: None,
+                child_type: None,
+                sibling_type: None,
+                states_config: &config.states,
+                phases_config: &config.phases,
+                tags_config: &config.tags,
+                ids_config: &config.ids,
+            })
+            .unwrap();
+
+        assert_eq!(root, "T-0001");
+        assert_eq!(all_ids, vec!["T-0001", "T-0002", "T-0003"]);
+    }
+
+    #[test]
+    fn remapped_imports_draw_sequence_numbers_only_on_commit() {
+        use task_graph_mcp::db::export::ExportOptions;
+        use task_graph_mcp::db::import::{ImportOptions, remap_snapshot_with};
+        use task_graph_mcp::export::Snapshot;
+
+        let source = setup_db();
+        create(&source, Some("a"), &default_ids_config());
+        create(&source, Some("b"), &default_ids_config());
+        let snapshot =
+            Snapshot::from_tables(source.export_tables(&ExportOptions::default()).unwrap())
+                .unwrap();
+
+        let db = setup_db();
+        let ic = ids(IdScheme::Sequential, None);
+        let remap = |s: &Snapshot, next: &mut dyn FnMut() -> anyhow::Result<String>| {
+            remap_snapshot_with(s, next)
+        };
+
+        // Previews show the IDs an import would get without reserving them
+        for _ in 0..2 {
+            let (_, id_map) = db.preview_remap(&snapshot, &ic, remap).unwrap();
+            let mut new_ids: Vec<_> = id_map.into_values().collect();
+            new_ids.sort();
+            assert_eq!(new_ids, vec!["T-0001", "T-0002"]);
+        }
+
+        // A failed import rolls its numbers back
+        let options = ImportOptions::merge().with_remap_ids();
+        let failed = db.import_snapshot_remapped(&snapshot, &options, &ic, |_, next| {
+            next()?;
+            anyhow::bail!("remap failed")
+        });
+        assert!(failed.is_err());
+
+        let result = db
+            .import_snapshot_remapped(&snapshot, &options, &ic, remap)
+            .unwrap();
+        let id_map = result.id_remap.unwrap();
+        assert!(db.task_exists(&id_map["a"]).unwrap());
+        assert!(db.task_exists(&id_map["b"]).unwrap());
+        assert_eq!(create(&db, None, &ic), "T-0003");
+    }
+
+    #[test]
+    fn ulid_ids_sort_by_creation() {
+        let db = setup_db();
+        let ic = ids(IdScheme::Ulid, None);
+
+        let first = create(&db, None, &ic);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = create(&db, None, &ic);
+
+        assert_eq!(first.len(), 26);
+        assert!(first < second);
+
+        let prefixed = create(&db, None, &ids(IdScheme::Ulid, Some("auth")));
+        assert!(prefixed.starts_with("auth-"));
+        assert_eq!(prefixed.len(), 31);
+    }
+
+    #[test]
+    fn ids_config_parses_scheme_from_yaml() {
+        let ic: IdsConfig = serde_yaml::from_str(
+            "task_id_scheme: sequential\ntask_id_prefix: PROJ\nsequence_digits: 5\n",
+        )
+        .unwrap();
+        assert_eq!(ic.task_id_scheme, IdScheme::Sequential);
+        assert_eq!(ic.sequence_prefix(), "PROJ");
+        assert_eq!(ic.sequence_digits, 5);
+
+        let defaults: IdsConfig = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(defaults.task_id_scheme, IdScheme::Petname);
+        assert_eq!(defaults.sequence_prefix(), "T");
+        assert_eq!(defaults.sequence_digits, 4);
+    }
+}
+
+// ============================================================================
+// Custom Field Tests
+// ============================================================================
+
+mod custom_fields_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::config::{CustomFieldDefinition, CustomFieldType, UnknownKeyBehavior};
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::tasks::{UpdateOptions, create, get, list_tasks, update};
+
+    fn fields_config(unknown_field: UnknownKeyBehavior) -> CustomFieldsConfig {
+        let mut config = CustomFieldsConfig {
+            unknown_field,
+            ..Default::default()
+        };
+        config.definitions.insert(
+            "customer".to_string(),
+            CustomFieldDefinition {
+                pattern: Some("^[A-Z]+$".to_string()),
+                ..Default::default()
+            },
+        );
+        config.definitions.insert(
+            "severity".to_string(),
+            CustomFieldDefinition {
+                field_type: CustomFieldType::Enum,
+                values: vec!["low".to_string(), "high".to_string()],
+                ..Default::default()
+            },
+        );
+        config.definitions.insert(
+            "estimate".to_string(),
+            CustomFieldDefinition {
+                field_type: CustomFieldType::Integer,
+                min: Some(0.0),
+                max: Some(100.0),
+                ..Default::default()
+            },
+        );
+        config
+    }
+
+    fn app_config(unknown_field: UnknownKeyBehavior) -> AppConfig {
+        let mut config = default_app_config();
+        config.custom_fields = Arc::new(fields_config(unknown_field));
+        config
+    }
+
+    #[test]
+    fn set_get_and_clear_fields() {
+        let db = setup_db();
+        let task = db
+            .create_task(
+                None,
+                "Task".to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &default_states_config(),
+                &default_ids_config(),
+            )
+            .unwrap();
+
+        let values = json!({"customer": "ACME", "estimate": 3, "urgent": true});
+        db.set_task_fields(&task.id, values.as_object().unwrap())
+            .unwrap();
+        assert_eq!(json!(db.get_task_fields(&task.id).unwrap()), values);
+
+        // Null removes a field, other values are merged
+        let changes = json!({"urgent": null, "estimate": 5});
+        db.set_task_fields(&task.id, changes.as_object().unwrap())
+            .unwrap();
+        assert_eq!(
+            json!(db.get_task_fields(&task.id).unwrap()),
+            json!({"customer": "ACME", "estimate": 5})
+        );
+
+        assert!(
+            db.set_task_fields("missing", values.as_object().unwrap())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn validation_enforces_definitions() {
+        let config = fields_config(UnknownKeyBehavior::Reject);
+
+        assert!(config.validate_field("customer", &json!("ACME")).is_ok());
+        assert!(config.validate_field("customer", &json!("acme")).is_err());
+        assert!(config.validate_field("severity", &json!("high")).is_ok());
+        assert!(config.validate_field("severity", &json!("urgent")).is_err());
+        assert!(config.validate_field("estimate", &json!(42)).is_ok());
+        assert!(config.validate_field("estimate", &json!(4.5)).is_err());
+        assert!(config.validate_field("estimate", &json!(101)).is_err());
+        assert!(config.validate_field("estimate", &json!(null)).is_ok());
+        assert!(config.validate_field("other", &json!("x")).is_err());
+
+        let warn = fields_config(UnknownKeyBehavior::Warn);
+        assert!(warn.validate_field("other", &json!("x")).unwrap().is_some());
+        assert!(warn.validate_field("other", &json!({"a": 1})).is_err());
+
+        let allow = fields_config(UnknownKeyBehavior::Allow);
+        assert!(
+            allow
+                .validate_field("other", &json!("x"))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn create_and_update_tools_store_fields() {
+        let db = setup_db();
+        let config = app_config(UnknownKeyBehavior::Warn);
+
+        let result = create(
+            &db,
+            &config,
+            json!({"title": "Bug", "fields": {"severity": "high", "team": "core"}}),
+        )
+        .unwrap();
+        let task_id = result["id"].as_str().unwrap().to_string();
+        assert_eq!(
+            result["fields"],
+            json!({"severity": "high", "team": "core"})
+        );
+        assert_eq!(result["field_warnings"].as_array().unwrap().len(), 1);
+
+        // Invalid values are rejected before the task is created
+        let before = db.list_tasks(ListTasksQuery::default()).unwrap().len();
+        assert!(
+            create(
+                &db,
+                &config,
+                json!({"title": "X", "fields": {"severity": "meh"}})
+            )
+            .is_err()
+        );
+        assert_eq!(
+            db.list_tasks(ListTasksQuery::default()).unwrap().len(),
+            before
+        );
+
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        let updated = update(
+            UpdateOptions {
+                db: &db,
+                config: &config,
+                workflows: &config.workflows,
+            },
+            json!({"worker_id": "w1", "task": task_id, "fields": {"team": null, "estimate": 8}}),
+        )
+        .unwrap();
+        assert_eq!(
+            updated["fields"],
+            json!({"estimate": 8, "severity": "high"})
+        );
+
+        let fetched = get(
+            &db,
+            &config.states,
+            &config.priorities,
+            &VisibilityConfig::default(),
+            OutputFormat::Json,
+            json!({"task": task_id, "format": "json"}),
+        )
+        .unwrap();
+        assert_eq!(
+            fetched["fields"],
+            json!({"estimate": 8, "severity": "high"})
+        );
+    }
+
+    #[test]
+    fn failed_field_write_rolls_back_task_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("tasks.db");
+        let db = Database::open(&db_path).unwrap();
+        let config = app_config(UnknownKeyBehavior::Allow);
+        let task_id = create(&db, &config, json!({"title": "Before"})).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+
+        // Make every field write fail
+        rusqlite::Connection::open(&db_path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TRIGGER reject_fields BEFORE INSERT ON task_fields
+                 BEGIN SELECT RAISE(ABORT, 'field write failed'); END;",
+            )
+            .unwrap();
+
+        assert!(
+            create(
+                &db,
+                &config,
+                json!({"title": "Orphan", "fields": {"customer": "ACME"}})
+            )
+            .is_err()
+        );
+        let titles: Vec<String> = db
+            .list_tasks(ListTasksQuery::default())
+            .unwrap()
+            .into_iter()
+            .map(|t| t.title)
+            .collect();
+        assert_eq!(titles, vec!["Before"]);
+
+        assert!(
+            update(
+                UpdateOptions {
+                    db: &db,
+                    config: &config,
+                    workflows: &config.workflows,
+                },
+                json!({"worker_id": "w1", "task": task_id, "title": "After",
+                       "fields": {"customer": "ACME"}}),
+            )
+            .is_err()
+        );
+        assert_eq!(db.get_task(&task_id).unwrap().unwrap().title, "Before");
+    }
+
+    #[test]
+    fn list_tasks_filters_by_fields() {
+        let db = setup_db();
+        let config = app_config(UnknownKeyBehavior::Allow);
+
+        let high = create(
+            &db,
+            &config,
+            json!({"title": "High", "fields": {"severity": "high", "estimate": 2}}),
+        )
+        .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        create(
+            &db,
+            &config,
+            json!({"title": "Low", "fields": {"severity": "low", "estimate": 2}}),
+        )
+        .unwrap();
+        create(&db, &config, json!({"title": "None"})).unwrap();
+
+        for extra in [json!({}), json!({"ready": true})] {
+            let mut args = json!({"fields": {"severity": "high", "estimate": 2}, "format": "json"});
+            args.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            let result = list_tasks(&db, &config, OutputFormat::Json, args).unwrap();
+            let tasks = result["tasks"].as_array().unwrap();
+            assert_eq!(tasks.len(), 1);
+            assert_eq!(tasks[0]["id"], json!(high));
+            assert_eq!(tasks[0]["fields"]["severity"], json!("high"));
+        }
+
+        // Values are matched by type, so the string "2" does not match the number 2
+        let result = list_tasks(
+            &db,
+            &default_app_config(),
+            OutputFormat::Json,
+            json!({"fields": {"estimate": "2"}, "format": "json"}),
+        )
+        .unwrap();
+        assert!(result["tasks"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn fields_follow_rename_and_export_import() {
+        use task_graph_mcp::db::export::ExportOptions;
+
+        let db = setup_db();
+        let config = app_config(UnknownKeyBehavior::Allow);
+        create(
+            &db,
+            &config,
+            json!({"id": "old-id", "title": "Task", "fields": {"customer": "ACME"}}),
+        )
+        .unwrap();
+
+        db.rename_task("old-id", "new-id").unwrap();
+        assert!(db.get_task_fields("old-id").unwrap().is_empty());
+        assert_eq!(
+            json!(db.get_task_fields("new-id").unwrap()),
+            json!({"customer": "ACME"})
+        );
+
+        let export = db.export_tables(&ExportOptions::default()).unwrap();
+        let rows = export.task_fields.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].task_id, "new-id");
+        assert_eq!(rows[0].value, "\"ACME\"");
+    }
+}
+
+// ============================================================================
+// Priority Scale Tests
+// ============================================================================
+
+mod priority_scale_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::tasks::{
+        UpdateOptions, create, create_tree, get, list_tasks, update,
+    };
+
+    fn p_scale_config() -> AppConfig {
+        let mut labels = std::collections::BTreeMap::new();
+        for (label, value) in [("P0", 4), ("P1", 3), ("P2", 2), ("P3", 1), ("P4", 0)] {
+            labels.insert(label.to_string(), value);
+        }
+        let mut config = default_app_config();
+        config.priorities = Arc::new(PrioritiesConfig {
+            min: 0,
+            max: 4,
+            default: 2,
+            labels,
+            ..Default::default()
+        });
+        config
+    }
+
+    #[test]
+    fn create_uses_scale_default_and_labels() {
+        let db = setup_db();
+        let config = p_scale_config();
+
+        let plain = create(&db, &config, json!({"title": "Plain"})).unwrap();
+        assert_eq!(plain["priority"], json!(2));
+
+        let urgent = create(&db, &config, json!({"title": "Urgent", "priority": "P0"})).unwrap();
+        assert_eq!(urgent["priority"], json!(4));
+
+        let err = create(&db, &config, json!({"title": "Too high", "priority": 7})).unwrap_err();
+        assert!(err.to_string().contains("out of range"));
+        assert!(create(&db, &config, json!({"title": "Bad", "priority": "P7"})).is_err());
+    }
+
+    #[test]
+    fn create_tree_resolves_labels_and_defaults() {
+        let db = setup_db();
+        let config = p_scale_config();
+
+        let result = create_tree(
+            &db,
+            &config,
+            json!({"tree": {
+                "title": "Root",
+                "priority": "P1",
+                "children": [{"title": "Child"}]
+            }}),
+        )
+        .unwrap();
+        let ids: Vec<String> = serde_json::from_value(result["all_ids"].clone()).unwrap();
+        let priorities: Vec<i32> = ids
+            .iter()
+            .map(|id| db.get_task(id).unwrap().unwrap().priority)
+            .collect();
+        assert_eq!(priorities, vec![3, 2]);
+    }
+
+    #[test]
+    fn update_validates_and_markdown_shows_labels() {
+        let db = setup_db();
+        let config = p_scale_config();
+        let task_id = create(&db, &config, json!({"title": "Task"})).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        let opts = || UpdateOptions {
+            db: &db,
+            config: &config,
+            workflows: &config.workflows,
+        };
+
+        assert!(
+            update(
+                opts(),
+                json!({"worker_id": "w1", "task": task_id, "priority": 11})
+            )
+            .is_err()
+        );
+        let updated = update(
+            opts(),
+            json!({"worker_id": "w1", "task": task_id, "priority": "p0"}),
+        )
+        .unwrap();
+        assert_eq!(updated["priority"], json!(4));
+
+        let md = get(
+            &db,
+            &config.states,
+            &config.priorities,
+            &VisibilityConfig::default(),
+            OutputFormat::Markdown,
+            json!({"task": task_id}),
+        )
+        .unwrap();
+        assert!(md.to_string().contains("**priority**: P0"));
+
+        let list = list_tasks(&db, &config, OutputFormat::Markdown, json!({})).unwrap();
+        assert!(list.to_string().contains("!!! Task"));
+    }
+}
+
+// ============================================================================
+// Dependency Semantics Tests
+// ============================================================================
+
+mod dependency_semantics_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::config::{
+        BlockTarget, DependencyDefinition, DependencyDisplay, DependencyReadiness,
+        DependencySemantics,
+    };
+    use task_graph_mcp::tools::claiming::claim;
+
+    fn semantics_config() -> AppConfig {
+        let mut deps = DependenciesConfig::default();
+        deps.definitions.insert(
+            "prefers".to_string(),
+            DependencyDefinition {
+                display: DependencyDisplay::Horizontal,
+                blocks: BlockTarget::Start,
+                semantics: DependencySemantics::Soft,
+                readiness: DependencyReadiness::All,
+            },
+        );
+        deps.definitions.insert(
+            "conflicts".to_string(),
+            DependencyDefinition {
+                display: DependencyDisplay::Horizontal,
+                blocks: BlockTarget::None,
+                semantics: DependencySemantics::Exclusive,
+                readiness: DependencyReadiness::All,
+            },
+        );
+        let mut config = default_app_config();
+        config.deps = Arc::new(deps);
+        config
+    }
+
+    fn ready_ids(db: &Database, config: &AppConfig) -> Vec<String> {
+        db.get_ready_tasks(None, &config.states, &config.deps, None, None, None)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect()
+    }
+
+    #[test]
+    fn soft_dependency_warns_instead_of_blocking() {
+        let db = setup_db();
+        let config = semantics_config();
+        register(&db, "w1");
+        let first = new_task(&db, &config, json!({"title": "First"}));
+        let second = new_task(&db, &config, json!({"title": "Second"}));
+        db.add_dependency(&first, &second, "prefers", &config.deps)
+            .unwrap();
+
+        assert!(ready_ids(&db, &config).contains(&second));
+
+        let result = claim(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "w1", "task": second}),
+        )
+        .unwrap();
+        let warnings = result["dependency_warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].as_str().unwrap().contains(&first));
+    }
+
+    #[test]
+    fn exclusive_dependency_prevents_concurrent_work() {
+        let db = setup_db();
+        let config = semantics_config();
+        register(&db, "w1");
+        register(&db, "w2");
+        let a = new_task(&db, &config, json!({"title": "Migrate schema"}));
+        let b = new_task(&db, &config, json!({"title": "Rebuild index"}));
+        db.add_dependency(&a, &b, "conflicts", &config.deps)
+            .unwrap();
+
+        claim(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "w1", "task": a}),
+        )
+        .unwrap();
+
+        // The link is symmetric: b is neither ready nor claimable while a is worked
+        assert!(!ready_ids(&db, &config).contains(&b));
+        let err = claim(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "w2", "task": b}),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains(&a));
+        assert_eq!(
+            db.get_exclusive_conflicts(&b, &config.states, &config.deps)
+                .unwrap(),
+            vec![a.clone()]
+        );
+
+        // force bypasses the check like other claim constraints
+        claim(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "w2", "task": b, "force": true}),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn exclusive_partner_is_ready_again_after_release() {
+        let db = setup_db();
+        let config = semantics_config();
+        register(&db, "w1");
+        let a = new_task(&db, &config, json!({"title": "A"}));
+        let b = new_task(&db, &config, json!({"title": "B"}));
+        db.add_dependency(&a, &b, "conflicts", &config.deps)
+            .unwrap();
+
+        claim(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "w1", "task": b}),
+        )
+        .unwrap();
+        assert!(!ready_ids(&db, &config).contains(&a));
+
+        db.update_task_unified(
+            &b,
+            "w1",
+            None,
+            None,
+            None,
+            Some("completed".to_string()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+            &config.states,
+            &config.deps,
+            &config.auto_advance,
+        )
+        .unwrap();
+        assert!(ready_ids(&db, &config).contains(&a));
+    }
+
+    #[test]
+    fn validate_rejects_blocking_exclusive_type() {
+        let mut deps = DependenciesConfig::default();
+        deps.definitions.insert(
+            "conflicts".to_string(),
+            DependencyDefinition {
+                display: DependencyDisplay::Horizontal,
+                blocks: BlockTarget::Start,
+                semantics: DependencySemantics::Exclusive,
+                readiness: DependencyReadiness::All,
+            },
+        );
+        assert!(deps.validate().is_err());
+
+        // Soft start types do not count toward the required hard start type
+        let mut soft_only = DependenciesConfig::default();
+        for def in soft_only.definitions.values_mut() {
+            def.semantics = DependencySemantics::Soft;
+        }
+        assert!(soft_only.validate().is_err());
+        assert!(soft_only.start_blocking_types().is_empty());
+    }
+}
+
+// ============================================================================
+// Dependency Lag Tests
+// ============================================================================
+
+mod dependency_lag_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::deps::link;
+
+    const HOUR_MS: i64 = 60 * 60 * 1000;
+
+    fn transition(db: &Database, config: &AppConfig, task_id: &str, status: &str) -> Vec<String> {
+        let (_, unblocked, _) = db
+            .update_task_unified(
+                task_id,
+                "w1",
+                None,
+                None,
+                None,
+                Some(status.to_string()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                true,
+                &config.states,
+                &config.deps,
+                &config.auto_advance,
+            )
+            .unwrap();
+        unblocked
+    }
+
+    fn is_ready(db: &Database, config: &AppConfig, task_id: &str) -> bool {
+        db.get_ready_tasks(None, &config.states, &config.deps, None, None, None)
+            .unwrap()
+            .iter()
+            .any(|t| t.id == task_id)
+    }
+
+    #[test]
+    fn lag_delays_readiness_after_blocker_completes() {
+        let db = setup_db();
+        let config = default_app_config();
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        let deploy = new_task(&db, &config, json!({"title": "Deploy"}));
+        let verify = new_task(&db, &config, json!({"title": "Verify after bake"}));
+        let result = link(
+            &db,
+            &config.deps,
+            json!({"from": deploy, "to": verify, "lag_ms": HOUR_MS}),
+        )
+        .unwrap();
+        assert_eq!(result["created"][0]["lag_ms"], json!(HOUR_MS));
+
+        // Completing the blocker does not unblock the task while the lag runs
+        transition(&db, &config, &deploy, "working");
+        let unblocked = transition(&db, &config, &deploy, "completed");
+        assert!(!unblocked.contains(&verify));
+        assert!(!is_ready(&db, &config, &verify));
+        assert!(
+            db.get_blocked_tasks(&config.states, &config.deps, None, None, None)
+                .unwrap()
+                .iter()
+                .any(|t| t.id == verify)
+        );
+        assert!(
+            db.has_unmet_start_dependencies(&verify, &config.states, &config.deps)
+                .unwrap()
+        );
+
+        // Once the lag has elapsed (here: shortened to zero) the task is ready
+        let result = link(
+            &db,
+            &config.deps,
+            json!({"from": deploy, "to": verify, "lag_ms": 0}),
+        )
+        .unwrap();
+        assert_eq!(result["updated"][0]["lag_ms"], json!(0));
+        assert!(is_ready(&db, &config, &verify));
+    }
+
+    #[test]
+    fn lag_is_listed_and_validated() {
+        let db = setup_db();
+        let config = default_app_config();
+        let a = new_task(&db, &config, json!({"title": "A"}));
+        let b = new_task(&db, &config, json!({"title": "B"}));
+        link(
+            &db,
+            &config.deps,
+            json!({"from": a, "to": b, "type": "follows", "lag_ms": 500}),
+        )
+        .unwrap();
+
+        let deps = db.get_all_dependencies().unwrap();
+        assert_eq!(deps.len(), 1);
+        assert_eq!(deps[0].lag_ms, Some(500));
+
+        assert!(
+            link(
+                &db,
+                &config.deps,
+                json!({"from": a, "to": b, "type": "relates-to", "lag_ms": 500}),
+            )
+            .is_err()
+        );
+        assert!(link(&db, &config.deps, json!({"from": a, "to": b, "lag_ms": -1})).is_err());
+    }
+}
+
+// ============================================================================
+// Wait-For Tests
+// ============================================================================
+
+mod wait_for_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::claiming::wait_for;
+
+    use task_graph_mcp::tools::tracking::thinking;
+
+    fn transition(db: &Database, config: &AppConfig, task_id: &str, status: &str) -> Vec<String> {
+        let (_, unblocked, _) = db
+            .update_task_unified(
+                task_id,
+                "worker",
+                None,
+                None,
+                None,
+                Some(status.to_string()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &config.states,
+                &config.deps,
+                &config.auto_advance,
+            )
+            .unwrap();
+        unblocked
+    }
+
+    #[test]
+    fn ready_task_is_returned_without_registering() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "waiter");
+        let task = new_task(&db, &config, json!({"title": "Free"}));
+
+        let result = wait_for(&db, &config, json!({"worker_id": "waiter", "task": task})).unwrap();
+        assert_eq!(result["ready"], json!([task]));
+        assert_eq!(result["waiting"], json!([]));
+        assert!(db.get_waiting_tasks("waiter").unwrap().is_empty());
+    }
+
+    #[test]
+    fn unblock_is_delivered_to_registered_waiter() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "waiter");
+        register(&db, "worker");
+        let blocker = new_task(&db, &config, json!({"title": "Blocker"}));
+        let blocked = new_task(&db, &config, json!({"title": "Blocked"}));
+        db.add_dependency(&blocker, &blocked, "blocks", &config.deps)
+            .unwrap();
+
+        let result = wait_for(
+            &db,
+            &config,
+            json!({"worker_id": "waiter", "task": [blocked.clone()]}),
+        )
+        .unwrap();
+        assert_eq!(result["ready"], json!([]));
+        assert_eq!(result["waiting"], json!([blocked]));
+        assert_eq!(
+            db.get_waiting_tasks("waiter").unwrap(),
+            vec![blocked.clone()]
+        );
+
+        transition(&db, &config, &blocker, "working");
+        let unblocked = transition(&db, &config, &blocker, "completed");
+        assert_eq!(unblocked, vec![blocked.clone()]);
+
+        // The next heartbeat carries the targeted notification exactly once
+        let beat = thinking(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"agent": "waiter", "thought": "idle"}),
+        )
+        .unwrap();
+        assert_eq!(beat["unblocked"], json!([blocked]));
+        let beat = thinking(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"agent": "waiter", "thought": "idle"}),
+        )
+        .unwrap();
+        assert!(beat.get("unblocked").is_none());
+        assert!(db.get_waiting_tasks("waiter").unwrap().is_empty());
+    }
+
+    #[test]
+    fn claimed_task_is_reported_closed() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "waiter");
+        register(&db, "worker");
+        let task = new_task(&db, &config, json!({"title": "Taken"}));
+        transition(&db, &config, &task, "working");
+
+        let result = wait_for(&db, &config, json!({"worker_id": "waiter", "task": task})).unwrap();
+        assert_eq!(result["closed"], json!([task]));
+        assert!(db.get_waiting_tasks("waiter").unwrap().is_empty());
+
+        assert!(wait_for(&db, &config, json!({"worker_id": "nobody", "task": task})).is_err());
+    }
+}
+
+// ============================================================================
+// Blocked Reason Tests
+// ============================================================================
+
+mod blocked_reason_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::tasks::{UpdateOptions, list_tasks, update};
+
+    fn do_update(db: &Database, config: &AppConfig, args: Value) -> anyhow::Result<Value> {
+        update(
+            UpdateOptions {
+                db,
+                config,
+                workflows: &config.workflows,
+            },
+            args,
+        )
+    }
+
+    #[test]
+    fn update_sets_and_clears_blocked_reason() {
+        let db = setup_db();
+        let config = default_app_config();
+        let task = new_task(&db, &config, json!({"title": "Needs sign-off"}));
+
+        let result = do_update(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "task": task, "blocked_reason": "waiting-on-human", "blocked_note": "legal review"}),
+        )
+        .unwrap();
+        assert_eq!(result["blocked_reason"], "waiting-on-human");
+        assert_eq!(result["blocked_note"], "legal review");
+
+        // A bare note keeps the reason
+        do_update(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "task": task, "blocked_note": "legal review, round 2"}),
+        )
+        .unwrap();
+        let stored = db.get_task(&task).unwrap().unwrap();
+        assert_eq!(stored.blocked_reason.as_deref(), Some("waiting-on-human"));
+        assert_eq!(
+            stored.blocked_note.as_deref(),
+            Some("legal review, round 2")
+        );
+
+        let result = do_update(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "task": task, "blocked_reason": null}),
+        )
+        .unwrap();
+        assert!(result.get("blocked_reason").is_none());
+        let stored = db.get_task(&task).unwrap().unwrap();
+        assert!(stored.blocked_reason.is_none());
+        assert!(stored.blocked_note.is_none());
+    }
+
+    #[test]
+    fn unknown_blocked_reason_is_rejected() {
+        let db = setup_db();
+        let config = default_app_config();
+        let task = new_task(&db, &config, json!({"title": "Task"}));
+
+        let err = do_update(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "task": task, "blocked_reason": "bored"}),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("blocked_reason must be one of"));
+    }
+
+    #[test]
+    fn completing_a_task_clears_blocked_reason() {
+        let db = setup_db();
+        let config = default_app_config();
+        let task = new_task(&db, &config, json!({"title": "Task"}));
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+
+        do_update(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "task": task, "blocked_reason": "needs-decision"}),
+        )
+        .unwrap();
+        do_update(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "task": task, "status": "working"}),
+        )
+        .unwrap();
+        let result = do_update(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "task": task, "status": "completed"}),
+        )
+        .unwrap();
+        assert!(result.get("blocked_reason").is_none());
+        assert!(
+            db.get_task(&task)
+                .unwrap()
+                .unwrap()
+                .blocked_reason
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn list_tasks_filters_by_blocked_reason() {
+        let db = setup_db();
+        let config = default_app_config();
+        let human = new_task(&db, &config, json!({"title": "Human"}));
+        let external = new_task(&db, &config, json!({"title": "External"}));
+        new_task(&db, &config, json!({"title": "Free"}));
+
+        db.set_blocked_reason(&human, Some("waiting-on-human"), None)
+            .unwrap();
+        db.set_blocked_reason(&external, Some("external-dependency"), Some("vendor API"))
+            .unwrap();
+
+        let result = list_tasks(
+            &db,
+            &config,
+            OutputFormat::Json,
+            json!({"blocked_reason": "external-dependency", "format": "json"}),
+        )
+        .unwrap();
+        let tasks = result["tasks"].as_array().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0]["id"], json!(external));
+        assert_eq!(tasks[0]["blocked_note"], "vendor API");
+    }
+
+    #[test]
+    fn blocked_resource_groups_by_reason() {
+        let db = setup_db();
+        let config = default_app_config();
+        let a = new_task(&db, &config, json!({"title": "A"}));
+        let b = new_task(&db, &config, json!({"title": "B"}));
+        let c = new_task(&db, &config, json!({"title": "C"}));
+
+        db.set_blocked_reason(&a, Some("needs-decision"), None)
+            .unwrap();
+        db.set_blocked_reason(&b, Some("needs-decision"), None)
+            .unwrap();
+        db.set_blocked_reason(&c, Some("needs-info"), None).unwrap();
+
+        let result = task_graph_mcp::resources::tasks::get_blocked_tasks(
+            &db,
+            &config.states,
+            &config.deps,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result["tasks"].as_array().unwrap().len(), 3);
+        assert_eq!(result["by_reason"]["needs-decision"], json!([a, b]));
+        assert_eq!(result["by_reason"]["needs-info"], json!([c]));
+    }
+}
+
+// ============================================================================
+// File Mark Conflict Tests
+// ============================================================================
+
+mod mark_conflict_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::db::locks::mark_paths_overlap;
+    use task_graph_mcp::error::{ErrorCode, ToolError};
+    use task_graph_mcp::tools::files::{mark_file, mark_updates, unmark_file};
+
+    #[test]
+    fn overlap_covers_same_path_and_directories() {
+        assert!(mark_paths_overlap("/p/src/main.rs", "/p/src/main.rs"));
+        assert!(mark_paths_overlap("/p/src", "/p/src/main.rs"));
+        assert!(mark_paths_overlap("/p/src/main.rs", "/p/src/"));
+        assert!(!mark_paths_overlap("/p/src", "/p/src2/main.rs"));
+        assert!(!mark_paths_overlap("/p/src/a.rs", "/p/src/b.rs"));
+    }
+
+    #[test]
+    fn conflicting_mark_returns_structured_error() {
+        let db = setup_db();
+        register(&db, "alice");
+        register(&db, "bob");
+        let task = db
+            .create_task_simple("Refactor", &default_states_config(), &default_ids_config())
+            .unwrap();
+
+        mark_file(
+            &db,
+            json!({"agent": "alice", "file": "/p/src", "task": task.id}),
+        )
+        .unwrap();
+
+        let err = mark_file(&db, json!({"agent": "bob", "file": "/p/src/main.rs"})).unwrap_err();
+        let tool_err = err.downcast::<ToolError>().unwrap();
+        assert_eq!(tool_err.code, ErrorCode::MarkConflict);
+        let conflicts = tool_err.conflicts.unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].file, "/p/src/main.rs");
+        assert_eq!(conflicts[0].marked_path, "/p/src");
+        assert_eq!(conflicts[0].held_by, "alice");
+        assert_eq!(conflicts[0].task_id.as_deref(), Some(task.id.as_str()));
+        assert!(conflicts[0].mark_age_ms >= 0);
+
+        // Nothing was marked for bob
+        assert!(
+            db.get_file_locks(None, Some("bob"), None)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn own_marks_do_not_conflict() {
+        let db = setup_db();
+        register(&db, "alice");
+
+        mark_file(&db, json!({"agent": "alice", "file": "/p/src"})).unwrap();
+        let result = mark_file(&db, json!({"agent": "alice", "file": "/p/src/main.rs"})).unwrap();
+        assert_eq!(result["marked"], json!(["/p/src/main.rs"]));
+    }
+
+    #[test]
+    fn wait_marks_free_files_and_notifies_on_release() {
+        let db = setup_db();
+        register(&db, "alice");
+        register(&db, "bob");
+
+        mark_file(&db, json!({"agent": "alice", "file": "/p/a.rs"})).unwrap();
+
+        let result = mark_file(
+            &db,
+            json!({"agent": "bob", "file": ["/p/a.rs", "/p/b.rs"], "wait": true}),
+        )
+        .unwrap();
+        assert_eq!(result["marked"], json!(["/p/b.rs"]));
+        assert_eq!(result["waiting"], json!(["/p/a.rs"]));
+        assert_eq!(result["conflicts"][0]["held_by"], "alice");
+
+        let updates = mark_updates(&db, json!({"agent": "bob"})).unwrap();
+        assert_eq!(updates["available"], json!([]));
+
+        unmark_file(&db, json!({"agent": "alice", "file": "/p/a.rs"})).unwrap();
+
+        let updates = mark_updates(&db, json!({"agent": "bob"})).unwrap();
+        assert_eq!(updates["available"], json!(["/p/a.rs"]));
+
+        // Reported once
+        let updates = mark_updates(&db, json!({"agent": "bob"})).unwrap();
+        assert_eq!(updates["available"], json!([]));
+    }
+
+    #[test]
+    fn exclusive_locks_are_not_mark_conflicts() {
+        let db = setup_db();
+        register(&db, "alice");
+        register(&db, "bob");
+
+        mark_file(&db, json!({"agent": "alice", "file": "lock:git-commit"})).unwrap();
+        let err = mark_file(&db, json!({"agent": "bob", "file": "lock:git-commit"})).unwrap_err();
+        assert_eq!(
+            err.downcast::<ToolError>().unwrap().code,
+            ErrorCode::LockConflict
+        );
+    }
+}
+
+// ============================================================================
+// Mark Violation Tests
+// ============================================================================
+
+mod mark_violation_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::files::mark_file;
+
+    #[test]
+    fn unmarked_changes_are_recorded() {
+        let db = setup_db();
+        db.register_worker(
+            Some("alice".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        mark_file(&db, json!({"agent": "alice", "file": "/p/src"})).unwrap();
+
+        let violations = db
+            .record_mark_violations(&["/p/src/main.rs".to_string(), "/p/README.md".to_string()])
+            .unwrap();
+        assert_eq!(violations, vec!["/p/README.md".to_string()]);
+
+        let recorded = db.get_mark_violations(10).unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].file_path, "/p/README.md");
+    }
+
+    #[test]
+    fn marks_resource_lists_recent_violations() {
+        let db = setup_db();
+        db.record_mark_violations(&["/p/a.rs".to_string()]).unwrap();
+        db.record_mark_violations(&["/p/b.rs".to_string()]).unwrap();
+
+        let resource = task_graph_mcp::resources::files::get_all_file_locks(&db).unwrap();
+        let violations = resource["violations"].as_array().unwrap();
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0]["file_path"], "/p/b.rs");
+    }
+}
+
+// ============================================================================
+// File Mark Lease Tests
+// ============================================================================
+
+mod mark_lease_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::files::{mark_file, mark_updates};
+
+    #[test]
+    fn ttl_sets_expiry_and_expired_marks_are_reaped() {
+        let db = setup_db();
+        register(&db, "alice");
+
+        let result = mark_file(
+            &db,
+            json!({"agent": "alice", "file": "/p/a.rs", "ttl_ms": 60_000}),
+        )
+        .unwrap();
+        assert!(result["expires_at"].as_i64().is_some());
+        let marks = db.get_file_locks(None, Some("alice"), None).unwrap();
+        assert!(marks["/p/a.rs"].expires_at.is_some());
+
+        // Nothing has expired yet
+        assert!(db.reap_expired_marks().unwrap().is_empty());
+
+        // Backdate the lease so it has expired
+        db.set_mark_expiry("/p/a.rs", "alice", Some(1)).unwrap();
+        let released = db.reap_expired_marks().unwrap();
+        assert_eq!(released, vec![("/p/a.rs".to_string(), "alice".to_string())]);
+        assert!(
+            db.get_file_locks(None, Some("alice"), None)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn expired_mark_does_not_conflict() {
+        let db = setup_db();
+        register(&db, "alice");
+        register(&db, "bob");
+
+        mark_file(
+            &db,
+            json!({"agent": "alice", "file": "/p/a.rs", "ttl_ms": 60_000}),
+        )
+        .unwrap();
+        db.set_mark_expiry("/p/a.rs", "alice", Some(1)).unwrap();
+
+        let result = mark_file(&db, json!({"agent": "bob", "file": "/p/a.rs"})).unwrap();
+        assert_eq!(result["marked"], json!(["/p/a.rs"]));
+    }
+
+    #[test]
+    fn mark_updates_reports_expiring_and_expired_marks() {
+        let db = setup_db();
+        register(&db, "alice");
+        register(&db, "bob");
+
+        mark_file(
+            &db,
+            json!({"agent": "alice", "file": "/p/soon.rs", "ttl_ms": 30_000}),
+        )
+        .unwrap();
+        mark_file(
+            &db,
+            json!({"agent": "alice", "file": "/p/later.rs", "ttl_ms": 3_600_000}),
+        )
+        .unwrap();
+        // Bob sees both claims first
+        mark_updates(&db, json!({"agent": "bob"})).unwrap();
+
+        let updates = mark_updates(&db, json!({"agent": "alice"})).unwrap();
+        let expiring = updates["expiring"].as_array().unwrap();
+        assert_eq!(expiring.len(), 1);
+        assert_eq!(expiring[0]["file"], "/p/soon.rs");
+
+        db.set_mark_expiry("/p/soon.rs", "alice", Some(1)).unwrap();
+        let updates = mark_updates(&db, json!({"agent": "bob"})).unwrap();
+        let removed = updates["removed_marks"].as_array().unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0]["file"], "/p/soon.rs");
+        assert_eq!(removed[0]["reason"], "lease expired");
+    }
+
+    #[test]
+    fn non_positive_ttl_is_rejected() {
+        let db = setup_db();
+        register(&db, "alice");
+        assert!(
+            mark_file(
+                &db,
+                json!({"agent": "alice", "file": "/p/a.rs", "ttl_ms": 0})
+            )
+            .is_err()
+        );
+    }
+}
+
+// ============================================================================
+// Mark History Tests
+// ============================================================================
+
+mod mark_history_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::files::{mark_file, mark_history, unmark_file};
+
+    #[test]
+    fn history_includes_overlapping_paths_newest_first() {
+        let db = setup_db();
+        register(&db, "alice");
+        register(&db, "bob");
+
+        mark_file(&db, json!({"agent": "alice", "file": "/p/src/a.rs"})).unwrap();
+        unmark_file(
+            &db,
+            json!({"agent": "alice", "file": "/p/src/a.rs", "reason": "done"}),
+        )
+        .unwrap();
+        mark_file(&db, json!({"agent": "bob", "file": "/p/src/b.rs"})).unwrap();
+        mark_file(&db, json!({"agent": "bob", "file": "/p/docs/x.md"})).unwrap();
+
+        let result = mark_history(&db, OutputFormat::Json, json!({"file": "/p/src"})).unwrap();
+        let events = result["events"].as_array().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0]["file"], "/p/src/b.rs");
+        assert_eq!(events[1]["event"], "released");
+        assert_eq!(events[1]["reason"], "done");
+        assert_eq!(events[2]["event"], "claimed");
+        assert!(events[2]["held_ms"].as_i64().is_some());
+
+        let agents = result["agents"].as_array().unwrap();
+        assert_eq!(agents.len(), 2);
+        assert_eq!(agents[0]["agent"], "bob");
+        assert_eq!(agents[1]["agent"], "alice");
+        assert_eq!(agents[1]["events"], 2);
+    }
+
+    #[test]
+    fn history_filters_by_agent_and_limit() {
+        let db = setup_db();
+        register(&db, "alice");
+        register(&db, "bob");
+
+        mark_file(&db, json!({"agent": "alice", "file": "/p/a.rs"})).unwrap();
+        unmark_file(&db, json!({"agent": "alice", "file": "/p/a.rs"})).unwrap();
+        mark_file(&db, json!({"agent": "bob", "file": "/p/a.rs"})).unwrap();
+
+        let result = mark_history(
+            &db,
+            OutputFormat::Json,
+            json!({"file": "/p/a.rs", "agent": "alice"}),
+        )
+        .unwrap();
+        assert_eq!(result["events"].as_array().unwrap().len(), 2);
+
+        let result = mark_history(
+            &db,
+            OutputFormat::Json,
+            json!({"file": "/p/a.rs", "limit": 1}),
+        )
+        .unwrap();
+        let events = result["events"].as_array().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["agent"], "bob");
+
+        assert!(
+            mark_history(
+                &db,
+                OutputFormat::Json,
+                json!({"file": "/p/a.rs", "limit": 0})
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn lock_resources_match_exactly() {
+        let db = setup_db();
+        register(&db, "alice");
+
+        mark_file(&db, json!({"agent": "alice", "file": "lock:git"})).unwrap();
+        mark_file(&db, json!({"agent": "alice", "file": "lock:git-push"})).unwrap();
+
+        let events = db.get_claim_history(Some("lock:git"), None, 10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].file_path, "lock:git");
+
+        assert_eq!(db.get_claim_history(None, None, 10).unwrap().len(), 2);
+    }
+}
+
+// ============================================================================
+// Blob Attachment Tests
+// ============================================================================
+
+mod blob_attachment_tests {
+    use super::*;
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde_json::json;
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::attachments::{attach, attachments, detach, gc_blobs};
+    use tempfile::TempDir;
+
+    fn blob_count(media: &TempDir) -> usize {
+        std::fs::read_dir(media.path().join("blobs"))
+            .map(|d| d.count())
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn base64_content_is_stored_once_per_digest() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let config = AttachmentsConfig::default();
+        let t1 = db
+            .create_task_simple("one", &default_states_config(), &default_ids_config())
+            .unwrap();
+        let t2 = db
+            .create_task_simple("two", &default_states_config(), &default_ids_config())
+            .unwrap();
+        let png = STANDARD.encode([0x89u8, b'P', b'N', b'G', 0, 1, 2, 255]);
+
+        let a = attach(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": t1.id, "type": "screenshot", "mime": "image/png",
+                   "content": png, "encoding": "base64"}),
+        )
+        .unwrap();
+        let b = attach(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": t2.id, "type": "screenshot", "mime": "image/png",
+                   "content": png, "encoding": "base64"}),
+        )
+        .unwrap();
+
+        assert_eq!(a["size_bytes"], 8);
+        assert_eq!(a["content_hash"], b["content_hash"]);
+        assert_eq!(a["file_path"], b["file_path"]);
+        assert_eq!(blob_count(&media), 1);
+        let stored = std::fs::read(a["file_path"].as_str().unwrap()).unwrap();
+        assert_eq!(stored, vec![0x89u8, b'P', b'N', b'G', 0, 1, 2, 255]);
+
+        // Only metadata lives in the database
+        let meta = db.get_attachments(&t1.id).unwrap();
+        assert_eq!(meta[0].content_hash.as_deref(), a["content_hash"].as_str());
+        let full = db.get_attachment(&t1.id, "screenshot", 0).unwrap().unwrap();
+        assert!(full.content.is_empty());
+
+        let listed = attachments(
+            &db,
+            media.path(),
+            &config,
+            OutputFormat::Json,
+            json!({"task": t1.id}),
+        )
+        .unwrap();
+        assert_eq!(listed["attachments"][0]["size_bytes"], 8);
+        assert!(listed["attachments"][0].get("missing").is_none());
+    }
+
+    #[test]
+    fn gc_blobs_collects_blob_only_when_unreferenced() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let config = AttachmentsConfig::default();
+        let t1 = db
+            .create_task_simple("one", &default_states_config(), &default_ids_config())
+            .unwrap();
+        let t2 = db
+            .create_task_simple("two", &default_states_config(), &default_ids_config())
+            .unwrap();
+        let data = STANDARD.encode(b"build output");
+
+        attach(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": [t1.id.clone(), t2.id.clone()], "type": "artifact",
+                   "mime": "application/octet-stream", "content": data, "encoding": "base64"}),
+        )
+        .unwrap();
+        assert_eq!(blob_count(&media), 1);
+
+        detach(
+            &db,
+            media.path(),
+            json!({"task": t1.id, "type": "artifact", "delete_files": true}),
+        )
+        .unwrap();
+        let result = gc_blobs(&db, media.path(), &config, json!({})).unwrap();
+        assert_eq!(result["deleted_count"], 0);
+        assert_eq!(blob_count(&media), 1);
+
+        // Detaching the last reference leaves the blob for gc_blobs
+        detach(
+            &db,
+            media.path(),
+            json!({"task": t2.id, "type": "artifact"}),
+        )
+        .unwrap();
+        assert_eq!(blob_count(&media), 1);
+
+        let preview = gc_blobs(&db, media.path(), &config, json!({"dry_run": true})).unwrap();
+        assert_eq!(preview["deleted_count"], 0);
+        assert_eq!(preview["would_delete"].as_array().unwrap().len(), 1);
+        assert_eq!(blob_count(&media), 1);
+
+        let result = gc_blobs(&db, media.path(), &config, json!({})).unwrap();
+        assert_eq!(result["deleted_count"], 1);
+        assert_eq!(blob_count(&media), 0);
+    }
+
+    #[test]
+    fn file_with_store_as_file_is_copied_into_blob_store() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let src = TempDir::new().unwrap();
+        let config = AttachmentsConfig::default();
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+        let file = src.path().join("shot.png");
+        std::fs::write(&file, [1u8, 2, 3]).unwrap();
+
+        let result = attach(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": task.id, "type": "screenshot", "mime": "image/png",
+                   "file": file.to_string_lossy(), "store_as_file": true}),
+        )
+        .unwrap();
+        assert_eq!(result["size_bytes"], 3);
+        assert!(
+            result["file_path"]
+                .as_str()
+                .unwrap()
+                .starts_with(&media.path().to_string_lossy().to_string())
+        );
+
+        // Replacing drops the last reference; the old blob stays until gc_blobs
+        std::fs::write(&file, [4u8, 5]).unwrap();
+        attach(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": task.id, "type": "screenshot", "mime": "image/png", "mode": "replace",
+                   "file": file.to_string_lossy(), "store_as_file": true}),
+        )
+        .unwrap();
+        assert_eq!(blob_count(&media), 2);
+        gc_blobs(&db, media.path(), &config, json!({})).unwrap();
+        assert_eq!(blob_count(&media), 1);
+    }
+
+    #[test]
+    fn s3_backend_without_settings_is_an_error() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+        let config: AttachmentsConfig = serde_yaml::from_str("storage:\n  backend: s3\n").unwrap();
+
+        let err = attach(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": task.id, "type": "artifact", "content": STANDARD.encode(b"x"),
+                   "encoding": "base64"}),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("s3"));
+        assert_eq!(blob_count(&media), 0);
+    }
+
+    #[test]
+    fn invalid_base64_is_rejected() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+
+        assert!(
+            attach(
+                &db,
+                media.path(),
+                &AttachmentsConfig::default(),
+                json!({"task": task.id, "type": "artifact", "content": "not base64!",
+                       "encoding": "base64"}),
+            )
+            .is_err()
+        );
+    }
+}
+
+// ============================================================================
+// Chunked Attachment Upload Tests
+// ============================================================================
+
+mod chunked_upload_tests {
+    use super::*;
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+    use task_graph_mcp::tools::attachments::{attach_begin, attach_chunk, attach_commit};
+    use tempfile::TempDir;
+
+    fn sha256_hex(bytes: &[u8]) -> String {
+        Sha256::digest(bytes)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    fn begin(db: &Database, media: &TempDir, args: serde_json::Value) -> String {
+        attach_begin(db, media.path(), &AttachmentsConfig::default(), args).unwrap()["upload_id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn chunks_are_assembled_and_verified_on_commit() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+        let content: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+
+        let id = begin(
+            &db,
+            &media,
+            json!({"task": task.id, "type": "build", "mime": "application/octet-stream",
+                   "size": 1000, "sha256": sha256_hex(&content)}),
+        );
+        for (i, chunk) in content.chunks(300).enumerate() {
+            let r = attach_chunk(
+                &db,
+                media.path(),
+                json!({"upload_id": id, "index": i, "data": STANDARD.encode(chunk)}),
+            )
+            .unwrap();
+            assert_eq!(r["next_chunk"], i + 1);
+        }
+
+        let result = attach_commit(
+            &db,
+            media.path(),
+            &AttachmentsConfig::default(),
+            json!({"upload_id": id}),
+        )
+        .unwrap();
+        assert_eq!(result["size_bytes"], 1000);
+        assert_eq!(result["content_hash"], sha256_hex(&content));
+        let stored = std::fs::read(result["file_path"].as_str().unwrap()).unwrap();
+        assert_eq!(stored, content);
+
+        // The upload is gone once committed
+        assert!(db.get_attachment_upload(&id).unwrap().is_none());
+        assert!(
+            attach_commit(
+                &db,
+                media.path(),
+                &AttachmentsConfig::default(),
+                json!({"upload_id": id})
+            )
+            .is_err()
+        );
+        assert_eq!(db.get_attachments(&task.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn out_of_order_and_oversized_chunks_are_rejected() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+        let id = begin(
+            &db,
+            &media,
+            json!({"task": task.id, "type": "build", "size": 4}),
+        );
+
+        assert!(
+            attach_chunk(
+                &db,
+                media.path(),
+                json!({"upload_id": id, "index": 1, "data": STANDARD.encode(b"ab")}),
+            )
+            .is_err()
+        );
+        attach_chunk(
+            &db,
+            media.path(),
+            json!({"upload_id": id, "index": 0, "data": STANDARD.encode(b"ab")}),
+        )
+        .unwrap();
+        // Replaying chunk 0 is rejected rather than appended twice
+        assert!(
+            attach_chunk(
+                &db,
+                media.path(),
+                json!({"upload_id": id, "index": 0, "data": STANDARD.encode(b"ab")}),
+            )
+            .is_err()
+        );
+        assert!(
+            attach_chunk(
+                &db,
+                media.path(),
+                json!({"upload_id": id, "index": 1, "data": STANDARD.encode(b"cde")}),
+            )
+            .is_err()
+        );
+        assert_eq!(
+            db.get_attachment_upload(&id)
+                .unwrap()
+                .unwrap()
+                .received_bytes,
+            2
+        );
+    }
+
+    #[test]
+    fn digest_mismatch_discards_upload() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+        let id = begin(&db, &media, json!({"task": task.id, "type": "build"}));
+        attach_chunk(
+            &db,
+            media.path(),
+            json!({"upload_id": id, "index": 0, "data": STANDARD.encode(b"hello")}),
+        )
+        .unwrap();
+
+        let err = attach_commit(
+            &db,
+            media.path(),
+            &AttachmentsConfig::default(),
+            json!({"upload_id": id, "sha256": sha256_hex(b"goodbye")}),
+        );
+        assert!(err.is_err());
+        assert!(db.get_attachment_upload(&id).unwrap().is_none());
+        assert!(db.get_attachments(&task.id).unwrap().is_empty());
+        assert!(
+            !media
+                .path()
+                .join("uploads")
+                .join(format!("{}.part", id))
+                .exists()
+        );
+    }
+
+    #[test]
+    fn begin_rejects_unknown_task() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        assert!(
+            attach_begin(
+                &db,
+                media.path(),
+                &AttachmentsConfig::default(),
+                json!({"task": "nope", "type": "build"}),
+            )
+            .is_err()
+        );
+    }
+}
+
+// ============================================================================
+// Attachment Versioning Tests
+// ============================================================================
+
+mod attachment_version_tests {
+    use super::*;
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde_json::json;
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::attachments::{attach, attachments, get_attachment};
+    use tempfile::TempDir;
+
+    fn version(db: &Database, media: &TempDir, config: &AttachmentsConfig, task: &str, text: &str) {
+        attach(
+            db,
+            media.path(),
+            config,
+            json!({"task": task, "type": "design", "name": "spec.md",
+                   "content": text, "mode": "version"}),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn attach_with_version_mode_adds_versions_of_the_same_name() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let config = AttachmentsConfig::default();
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+
+        version(&db, &media, &config, &task.id, "v1");
+        version(&db, &media, &config, &task.id, "v2");
+
+        let latest = db
+            .get_attachment_version(&task.id, "design", "spec.md", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.version, 2);
+        assert_eq!(latest.content, "v2");
+        let first = db
+            .get_attachment_version(&task.id, "design", "spec.md", Some(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.content, "v1");
+
+        // Listing shows only the latest version unless asked for all of them
+        let listed = attachments(
+            &db,
+            media.path(),
+            &config,
+            OutputFormat::Json,
+            json!({"task": task.id}),
+        )
+        .unwrap();
+        let listed = listed["attachments"].as_array().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0]["version"], 2);
+
+        let all = attachments(
+            &db,
+            media.path(),
+            &config,
+            OutputFormat::Json,
+            json!({"task": task.id, "include_versions": true}),
+        )
+        .unwrap();
+        assert_eq!(all["attachments"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn version_mode_requires_a_name() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+
+        assert!(
+            attach(
+                &db,
+                media.path(),
+                &AttachmentsConfig::default(),
+                json!({"task": task.id, "type": "design", "content": "x", "mode": "version"}),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn old_versions_beyond_the_limit_are_pruned() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let config = AttachmentsConfig {
+            max_versions: Some(2),
+            ..Default::default()
+        };
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+
+        for text in ["v1", "v2", "v3"] {
+            version(&db, &media, &config, &task.id, text);
+        }
+
+        assert!(
+            db.get_attachment_version(&task.id, "design", "spec.md", Some(1))
+                .unwrap()
+                .is_none()
+        );
+        let kept = db.get_attachments(&task.id).unwrap();
+        let mut versions: Vec<i32> = kept.iter().map(|a| a.version).collect();
+        versions.sort();
+        assert_eq!(versions, vec![2, 3]);
+    }
+
+    #[test]
+    fn get_attachment_returns_versioned_blob_content() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let config = AttachmentsConfig::default();
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+
+        for bytes in [&[0xffu8, 0x00, 0x01][..], &[0xfe, 0x02][..]] {
+            attach(
+                &db,
+                media.path(),
+                &config,
+                json!({"task": task.id, "type": "screenshot", "name": "home.png",
+                       "mime": "image/png", "content": STANDARD.encode(bytes),
+                       "encoding": "base64", "mode": "version"}),
+            )
+            .unwrap();
+        }
+
+        let first = get_attachment(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": task.id, "type": "screenshot", "name": "home.png", "version": 1}),
+        )
+        .unwrap();
+        assert_eq!(first["version"], 1);
+        assert_eq!(first["encoding"], "base64");
+        assert_eq!(first["content"], STANDARD.encode([0xffu8, 0x00, 0x01]));
+
+        let by_sequence = get_attachment(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": task.id, "type": "screenshot", "sequence": 1}),
+        )
+        .unwrap();
+        assert_eq!(by_sequence["version"], 2);
+
+        assert!(
+            get_attachment(
+                &db,
+                media.path(),
+                &config,
+                json!({"task": task.id, "type": "screenshot", "name": "home.png", "version": 9}),
+            )
+            .is_err()
+        );
+    }
+}
+
+// ============================================================================
+// Attachment Quota and Retention Tests
+// ============================================================================
+
+mod attachment_retention_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::config::AttachmentKeyDefinition;
+    use task_graph_mcp::error::{ErrorCode, ToolError};
+    use task_graph_mcp::tools::attachments::{attach, prune_attachments};
+    use tempfile::TempDir;
+
+    fn log_note(
+        db: &Database,
+        media: &TempDir,
+        config: &AttachmentsConfig,
+        task: &str,
+        text: &str,
+    ) {
+        attach(
+            db,
+            media.path(),
+            config,
+            json!({"task": task, "type": "log", "content": text}),
+        )
+        .unwrap();
+    }
+
+    fn config_with_log_policy(
+        keep_last: Option<usize>,
+        max_age_seconds: Option<i64>,
+    ) -> AttachmentsConfig {
+        let mut config = AttachmentsConfig::default();
+        config.definitions.insert(
+            "log".to_string(),
+            AttachmentKeyDefinition {
+                mime: "text/plain".to_string(),
+                mode: "append".to_string(),
+                max_versions: None,
+                keep_last,
+                max_age_seconds,
+            },
+        );
+        config
+    }
+
+    #[test]
+    fn attach_keeps_only_the_newest_attachments_of_a_type() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let config = config_with_log_policy(Some(2), None);
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+
+        for text in ["one", "two", "three"] {
+            log_note(&db, &media, &config, &task.id, text);
+        }
+
+        let logs = db.get_attachments(&task.id).unwrap();
+        let sequences: Vec<i32> = logs.iter().map(|a| a.sequence).collect();
+        assert_eq!(sequences, vec![1, 2]);
+    }
+
+    #[test]
+    fn task_quota_rejects_oversized_attachments() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let config = AttachmentsConfig {
+            max_task_bytes: Some(10),
+            ..Default::default()
+        };
+        let task = db
+            .create_task_simple("t", &default_states_config(), &default_ids_config())
+            .unwrap();
+
+        log_note(&db, &media, &config, &task.id, "12345678");
+        let err = attach(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": task.id, "type": "log", "content": "abc"}),
+        )
+        .unwrap_err();
+        let tool_err = err.downcast_ref::<ToolError>().unwrap();
+        assert_eq!(tool_err.code, ErrorCode::QuotaExceeded);
+
+        // Replacing the type frees its existing bytes first
+        attach(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": task.id, "type": "log", "content": "abcdefghij", "mode": "replace"}),
+        )
+        .unwrap();
+        assert_eq!(db.get_attachment_usage(Some(&task.id), None).unwrap(), 10);
+    }
+
+    #[test]
+    fn project_quota_counts_every_task() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let config = AttachmentsConfig {
+            max_project_bytes: Some(12),
+            ..Default::default()
+        };
+        let states = default_states_config();
+        let ids = default_ids_config();
+        let a = db.create_task_simple("a", &states, &ids).unwrap();
+        let b = db.create_task_simple("b", &states, &ids).unwrap();
+
+        log_note(&db, &media, &config, &a.id, "12345678");
+        assert!(
+            attach(
+                &db,
+                media.path(),
+                &config,
+                json!({"task": b.id, "type": "log", "content": "12345"}),
+            )
+            .is_err()
+        );
+        log_note(&db, &media, &config, &b.id, "1234");
+    }
+
+    #[test]
+    fn prune_attachments_applies_explicit_and_configured_policies() {
+        let db = setup_db();
+        let media = TempDir::new().unwrap();
+        let config = AttachmentsConfig::default();
+        let states = default_states_config();
+        let ids = default_ids_config();
+        let a = db.create_task_simple("a", &states, &ids).unwrap();
+        let b = db.create_task_simple("b", &states, &ids).unwrap();
+        for task in [&a, &b] {
+            for text in ["one", "two", "three"] {
+                log_note(&db, &media, &config, &task.id, text);
+            }
+        }
+
+        // Dry run reports without deleting
+        let preview = prune_attachments(
+            &db,
+            media.path(),
+            &config,
+            json!({"type": "log", "keep_last": 1, "dry_run": true}),
+        )
+        .unwrap();
+        assert_eq!(preview["would_delete"].as_array().unwrap().len(), 4);
+        assert_eq!(db.get_attachments(&a.id).unwrap().len(), 3);
+
+        let result = prune_attachments(
+            &db,
+            media.path(),
+            &config,
+            json!({"task": a.id, "type": "log", "keep_last": 1}),
+        )
+        .unwrap();
+        assert_eq!(result["deleted_count"], 2);
+        assert_eq!(result["bytes_freed"], 6);
+        assert_eq!(db.get_attachments(&a.id).unwrap()[0].sequence, 2);
+        assert_eq!(db.get_attachments(&b.id).unwrap().len(), 3);
+
+        // Without an explicit policy, configured max_age_seconds applies everywhere
+        let aged = config_with_log_policy(None, Some(0));
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let result = prune_attachments(&db, media.path(), &aged, json!({})).unwrap();
+        assert_eq!(result["deleted_count"], 4);
+        assert_eq!(result["by_type"]["log"], 4);
+
+        assert!(prune_attachments(&db, media.path(), &config, json!({"keep_last": 1})).is_err());
+    }
+}
+
+// ============================================================================
+// Agent Group Tests
+// ============================================================================
+
+mod agent_group_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::agents::register_group;
+
+    fn worker(db: &Database, id: &str) -> String {
+        db.register_worker(
+            Some(id.to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap()
+        .id
+    }
+
+    fn group_task(db: &Database, title: &str, group: &str) -> String {
+        let task = db
+            .create_task_simple(title, &default_states_config(), &default_ids_config())
+            .unwrap();
+        assert!(db.set_needed_group(&task.id, Some(group)).unwrap());
+        task.id
+    }
+
+    #[test]
+    fn claim_requires_group_membership() {
+        let db = setup_db();
+        let states = default_states_config();
+        let agent = worker(&db, "alice");
+        let task_id = group_task(&db, "Frontend work", "frontend");
+
+        let err = db.claim_task(&task_id, &agent, &states).unwrap_err();
+        assert!(err.to_string().contains("frontend"));
+
+        db.join_groups(&agent, &["frontend".to_string()]).unwrap();
+        let claimed = db.claim_task(&task_id, &agent, &states).unwrap();
+        assert_eq!(claimed.worker_id.as_deref(), Some("alice"));
+        assert_eq!(claimed.needed_group.as_deref(), Some("frontend"));
+    }
+
+    #[test]
+    fn ready_tasks_for_agent_respect_groups() {
+        let db = setup_db();
+        let agent = worker(&db, "bob");
+        let open = db
+            .create_task_simple("Anyone", &default_states_config(), &default_ids_config())
+            .unwrap();
+        let backend = group_task(&db, "Backend only", "backend");
+
+        let ids = |agent: Option<&str>| -> Vec<String> {
+            db.get_ready_tasks(
+                agent,
+                &default_states_config(),
+                &default_deps_config(),
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect()
+        };
+
+        assert_eq!(ids(Some(&agent)), vec![open.id.clone()]);
+        assert_eq!(ids(None).len(), 2);
+
+        db.join_groups(&agent, &["backend".to_string()]).unwrap();
+        let ready = ids(Some(&agent));
+        assert_eq!(ready.len(), 2);
+        assert!(ready.contains(&backend));
+    }
+
+    #[test]
+    fn register_group_joins_and_leaves() {
+        let db = setup_db();
+        worker(&db, "lead");
+        worker(&db, "dev");
+
+        let result = register_group(
+            &db,
+            json!({"worker_id": "lead", "group": "core", "members": ["dev"]}),
+        )
+        .unwrap();
+        assert_eq!(result["joined"], json!(["lead", "dev"]));
+        assert_eq!(result["members"], json!(["dev", "lead"]));
+
+        // Joining again changes nothing
+        let again = register_group(&db, json!({"worker_id": "dev", "group": "core"})).unwrap();
+        assert_eq!(again["joined"], json!([]));
+
+        let left = register_group(
+            &db,
+            json!({"worker_id": "dev", "group": "core", "leave": true}),
+        )
+        .unwrap();
+        assert_eq!(left["left"], json!(["dev"]));
+        assert_eq!(db.get_group_members("core").unwrap(), vec!["lead"]);
+
+        assert!(register_group(&db, json!({"worker_id": "ghost", "group": "core"})).is_err());
+    }
+
+    #[test]
+    fn workers_list_and_filter_by_group() {
+        let db = setup_db();
+        let a = worker(&db, "a");
+        worker(&db, "b");
+        db.join_groups(&a, &["ops".to_string(), "infra".to_string()])
+            .unwrap();
+
+        let in_ops = db
+            .list_workers_filtered(None, None, None, 0, Some("ops"))
+            .unwrap();
+        assert_eq!(in_ops.len(), 1);
+        assert_eq!(in_ops[0].groups, vec!["infra", "ops"]);
+
+        let all = db.list_workers_info().unwrap();
+        let b = all.iter().find(|w| w.id == "b").unwrap();
+        assert!(b.groups.is_empty());
+
+        // Membership goes away with the worker
+        db.unregister_worker(&a, "pending").unwrap();
+        assert!(db.get_group_members("ops").unwrap().is_empty());
+    }
+
+    #[test]
+    fn group_stats_and_task_filter() {
+        let db = setup_db();
+        let states = default_states_config();
+        let agent = worker(&db, "carol");
+        db.join_groups(&agent, &["qa".to_string()]).unwrap();
+
+        let first = group_task(&db, "Test login", "qa");
+        group_task(&db, "Test logout", "qa");
+        db.claim_task(&first, &agent, &states).unwrap();
+
+        let stats = db.get_group_stats(&["docs"], &states).unwrap();
+        let names: Vec<&str> = stats.iter().map(|g| g.group.as_str()).collect();
+        assert_eq!(names, vec!["docs", "qa"]);
+
+        let qa = &stats[1];
+        assert_eq!(qa.members, vec!["carol"]);
+        assert_eq!(qa.open_tasks, 2);
+        assert_eq!(qa.unclaimed_tasks, 1);
+        assert_eq!(qa.claimed_by_members, 1);
+        assert_eq!(stats[0].open_tasks, 0);
+
+        let tasks = db
+            .list_tasks(ListTasksQuery {
+                needed_group: Some("qa"),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(tasks.len(), 2);
+
+        // Clearing the group makes the task claimable by anyone
+        let other = worker(&db, "dave");
+        let second = tasks.iter().find(|t| t.id != first).unwrap();
+        db.set_needed_group(&second.id, None).unwrap();
+        assert!(db.claim_task(&second.id, &other, &states).is_ok());
+    }
+}
+
+// ============================================================================
+// Claim Scoring Tests
+// ============================================================================
+
+mod claim_scoring_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::claiming::claim;
+
+    const HOUR_MS: i64 = 3_600_000;
+
+    fn rank(db: &Database, config: &AppConfig, agent: &str) -> Vec<String> {
+        db.rank_claim_candidates(
+            agent,
+            &config.states,
+            &config.deps,
+            &config.priorities,
+            &config.tags,
+            None,
+            10,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|c| c.id)
+        .collect()
+    }
+
+    #[test]
+    fn matching_tags_outrank_generic_tasks() {
+        let db = setup_db();
+        let config = default_app_config();
+        register_with_tags(&db, "rustacean", &["rust", "code"]);
+
+        let generic = new_task(&db, &config, json!({"title": "Generic"}));
+        let partial = new_task(
+            &db,
+            &config,
+            json!({"title": "Partial", "wanted_tags": ["rust", "python"]}),
+        );
+        let matched = new_task(
+            &db,
+            &config,
+            json!({"title": "Matched", "needed_tags": ["rust"], "wanted_tags": ["code"]}),
+        );
+
+        assert_eq!(
+            rank(&db, &config, "rustacean"),
+            vec![matched, partial, generic]
+        );
+    }
+
+    #[test]
+    fn priority_breaks_ties_between_equal_matches() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "w");
+
+        let low = new_task(&db, &config, json!({"title": "Low", "priority": 1}));
+        let high = new_task(&db, &config, json!({"title": "High", "priority": 9}));
+
+        let candidates = db
+            .rank_claim_candidates(
+                "w",
+                &config.states,
+                &config.deps,
+                &config.priorities,
+                &config.tags,
+                None,
+                10,
+            )
+            .unwrap();
+        assert_eq!(candidates[0].id, high);
+        assert_eq!(candidates[1].id, low);
+        assert_eq!(candidates[0].priority_score, 0.9);
+        assert_eq!(candidates[0].tag_score, 0.5);
+    }
+
+    #[test]
+    fn estimates_near_agent_history_score_higher() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "veteran");
+
+        // One finished hour-long task gives the agent a history
+        let done = new_task(&db, &config, json!({"title": "Done"}));
+        db.claim_task(&done, "veteran", &config.states).unwrap();
+        db.log_time(&done, HOUR_MS).unwrap();
+        db.complete_task(&done, "veteran", &config.states).unwrap();
+        // Claim-to-complete wall time is added on top of the logged hour
+        let typical_ms = db.get_agent_typical_duration("veteran").unwrap().unwrap();
+        assert!((HOUR_MS as f64..(HOUR_MS + 60_000) as f64).contains(&typical_ms));
+
+        let huge = new_task(
+            &db,
+            &config,
+            json!({"title": "Huge", "time_estimate_ms": 100 * HOUR_MS}),
+        );
+        let typical = new_task(
+            &db,
+            &config,
+            json!({"title": "Typical", "time_estimate_ms": 2 * HOUR_MS}),
+        );
+
+        let candidates = db
+            .rank_claim_candidates(
+                "veteran",
+                &config.states,
+                &config.deps,
+                &config.priorities,
+                &config.tags,
+                None,
+                10,
+            )
+            .unwrap();
+        assert_eq!(candidates[0].id, typical);
+        assert_eq!(candidates[1].id, huge);
+        assert_eq!(candidates[1].estimate_score, 0.0);
+
+        // Without history the estimate is neutral
+        register(&db, "rookie");
+        let rookie = db
+            .rank_claim_candidates(
+                "rookie",
+                &config.states,
+                &config.deps,
+                &config.priorities,
+                &config.tags,
+                None,
+                10,
+            )
+            .unwrap();
+        assert!(rookie.iter().all(|c| c.estimate_score == 0.5));
+    }
+
+    #[test]
+    fn claim_without_task_returns_candidates() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "w");
+        for i in 0..3 {
+            new_task(&db, &config, json!({"title": format!("Task {}", i)}));
+        }
+
+        let result = claim(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "w", "limit": 2}),
+        )
+        .unwrap();
+        assert_eq!(result["candidates"].as_array().unwrap().len(), 2);
+        assert!(result["candidates"][0]["score"].as_f64().is_some());
+
+        // Nothing was claimed
+        assert!(db.get_claimed_tasks(None, None).unwrap().is_empty());
+
+        assert!(
+            claim(
+                &db,
+                &config,
+                &config.workflows,
+                json!({"worker_id": "ghost"})
+            )
+            .is_err()
+        );
+    }
+}
+
+// ============================================================================
+// Fair-Share Scheduling Tests
+// ============================================================================
+
+mod fair_share_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::config::FairnessConfig;
+    use task_graph_mcp::tools::claiming::claim;
+
+    fn fair_config() -> AppConfig {
+        let mut config = default_app_config();
+        config.claiming = Arc::new(ClaimingConfig {
+            fairness: FairnessConfig {
+                enabled: true,
+                ..Default::default()
+            },
+        });
+        config
+    }
+
+    fn top_candidate(db: &Database, config: &AppConfig, agent: &str) -> String {
+        claim(db, config, &config.workflows, json!({"worker_id": agent})).unwrap()["candidates"][0]
+            ["id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn hogging_agent_is_steered_away_from_high_priority_work() {
+        let db = setup_db();
+        let config = fair_config();
+        register(&db, "hog");
+        register(&db, "newcomer");
+
+        for i in 0..2 {
+            let id = new_task(
+                &db,
+                &config,
+                json!({"title": format!("Hot {}", i), "priority": 9}),
+            );
+            db.claim_task(&id, "hog", &config.states).unwrap();
+        }
+
+        let high = new_task(&db, &config, json!({"title": "High", "priority": 9}));
+        let mid = new_task(&db, &config, json!({"title": "Mid", "priority": 5}));
+
+        let share = db
+            .get_fair_share(
+                "hog",
+                &config.claiming.fairness,
+                &config.priorities,
+                &config.states,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(share.recent_claims, 2);
+        assert_eq!(share.total_claims, 2);
+        assert_eq!(share.penalty, 0.5);
+
+        assert_eq!(top_candidate(&db, &config, "hog"), mid);
+        assert_eq!(top_candidate(&db, &config, "newcomer"), high);
+    }
+
+    #[test]
+    fn fairness_is_off_by_default() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "hog");
+        register(&db, "newcomer");
+
+        let hot = new_task(&db, &config, json!({"title": "Hot", "priority": 9}));
+        db.claim_task(&hot, "hog", &config.states).unwrap();
+        let high = new_task(&db, &config, json!({"title": "High", "priority": 9}));
+        new_task(&db, &config, json!({"title": "Mid", "priority": 5}));
+
+        assert!(
+            db.get_fair_share(
+                "hog",
+                &config.claiming.fairness,
+                &config.priorities,
+                &config.states,
+            )
+            .unwrap()
+            .is_none()
+        );
+        assert_eq!(top_candidate(&db, &config, "hog"), high);
+    }
+
+    #[test]
+    fn low_priority_claims_do_not_count() {
+        let db = setup_db();
+        let config = fair_config();
+        register(&db, "busy");
+        register(&db, "idle");
+
+        let chore = new_task(&db, &config, json!({"title": "Chore", "priority": 2}));
+        db.claim_task(&chore, "busy", &config.states).unwrap();
+
+        let share = db
+            .get_fair_share(
+                "busy",
+                &config.claiming.fairness,
+                &config.priorities,
+                &config.states,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(share.recent_claims, 0);
+        assert_eq!(share.penalty, 0.0);
+    }
+}
+
+// ============================================================================
+// Next-Task Suggestion Tests
+// ============================================================================
+
+mod suggest_next_task_tests {
+    use super::*;
+    use serde_json::{Map, json};
+    use task_graph_mcp::tools::claiming::suggest_next_task;
+
+    fn suggest(db: &Database, config: &AppConfig, agent: &str) -> Option<String> {
+        db.suggest_next_task(
+            agent,
+            &config.states,
+            &config.deps,
+            &config.priorities,
+            &config.tags,
+            None,
+        )
+        .unwrap()
+        .0
+        .map(|s| s.id)
+    }
+
+    #[test]
+    fn critical_path_outweighs_isolated_task() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "w");
+
+        let isolated = new_task(&db, &config, json!({"title": "Isolated"}));
+        let root = new_task(&db, &config, json!({"title": "Root"}));
+        let mid = new_task(&db, &config, json!({"title": "Mid"}));
+        let leaf = new_task(&db, &config, json!({"title": "Leaf"}));
+        db.add_dependency(&root, &mid, "blocks", &config.deps)
+            .unwrap();
+        db.add_dependency(&mid, &leaf, "blocks", &config.deps)
+            .unwrap();
+
+        let (suggestion, considered) = db
+            .suggest_next_task(
+                "w",
+                &config.states,
+                &config.deps,
+                &config.priorities,
+                &config.tags,
+                None,
+            )
+            .unwrap();
+        let suggestion = suggestion.unwrap();
+        assert_eq!(suggestion.id, root);
+        assert_eq!(suggestion.downstream, 2);
+        assert_eq!(suggestion.critical_path_score, 1.0);
+        // Mid and Leaf are blocked
+        assert_eq!(considered, 2);
+        assert_ne!(suggestion.id, isolated);
+    }
+
+    #[test]
+    fn imminent_deadline_beats_higher_priority() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "w");
+
+        let _important = new_task(&db, &config, json!({"title": "Important", "priority": 7}));
+        let urgent = new_task(&db, &config, json!({"title": "Urgent", "priority": 5}));
+        let mut fields = Map::new();
+        fields.insert(
+            "due_at".to_string(),
+            json!(chrono::Utc::now().timestamp_millis()),
+        );
+        db.set_task_fields(&urgent, &fields).unwrap();
+
+        assert_eq!(suggest(&db, &config, "w"), Some(urgent));
+    }
+
+    #[test]
+    fn date_deadlines_are_parsed() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "w");
+
+        let task = new_task(&db, &config, json!({"title": "Dated"}));
+        let mut fields = Map::new();
+        fields.insert("due_at".to_string(), json!("2020-01-01"));
+        db.set_task_fields(&task, &fields).unwrap();
+
+        let (suggestion, _) = db
+            .suggest_next_task(
+                "w",
+                &config.states,
+                &config.deps,
+                &config.priorities,
+                &config.tags,
+                None,
+            )
+            .unwrap();
+        let suggestion = suggestion.unwrap();
+        assert_eq!(suggestion.due_at, Some(1_577_836_800_000));
+        assert_eq!(suggestion.deadline_score, 1.0);
+    }
+
+    #[test]
+    fn tool_reports_load_and_handles_no_work() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "w");
+
+        let result = suggest_next_task(&db, &config, json!({"worker_id": "w"})).unwrap();
+        assert!(result["task"].is_null());
+        assert_eq!(result["considered"], json!(0));
+
+        let held = new_task(&db, &config, json!({"title": "Held"}));
+        db.claim_task(&held, "w", &config.states).unwrap();
+        let next = new_task(&db, &config, json!({"title": "Next"}));
+
+        let result = suggest_next_task(&db, &config, json!({"worker_id": "w"})).unwrap();
+        assert_eq!(result["task"]["id"], json!(next));
+        assert_eq!(result["load"], json!(1));
+
+        // Nothing was claimed
+        assert_eq!(db.get_claimed_tasks(Some("w"), None).unwrap().len(), 1);
+        assert!(suggest_next_task(&db, &config, json!({"worker_id": "ghost"})).is_err());
+    }
+}
+
+// ============================================================================
+// Deadline Ordering Tests
+// ============================================================================
+
+mod deadline_ordering_tests {
+    use super::*;
+    use serde_json::{Map, Value, json};
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::tasks::list_tasks;
+
+    fn set_due_now(db: &Database, task_id: &str) {
+        let mut fields = Map::new();
+        fields.insert(
+            "due_at".to_string(),
+            json!(chrono::Utc::now().timestamp_millis()),
+        );
+        db.set_task_fields(task_id, &fields).unwrap();
+    }
+
+    fn ready_ids(db: &Database, config: &AppConfig, args: Value) -> Vec<String> {
+        let result = list_tasks(db, config, OutputFormat::Json, args).unwrap();
+        result["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn imminent_deadline_moves_up_ready_list() {
+        let db = setup_db();
+        let config = default_app_config();
+        let important = new_task(&db, &config, json!({"title": "Important", "priority": 7}));
+        let due = new_task(&db, &config, json!({"title": "Due", "priority": 5}));
+        let later = new_task(&db, &config, json!({"title": "Later", "priority": 3}));
+        set_due_now(&db, &due);
+
+        assert_eq!(
+            ready_ids(&db, &config, json!({"ready": true, "format": "json"})),
+            vec![due.clone(), important.clone(), later.clone()]
+        );
+
+        // An explicit sort ignores deadlines
+        assert_eq!(
+            ready_ids(
+                &db,
+                &config,
+                json!({"ready": true, "sort_by": "priority", "sort_order": "desc", "format": "json"})
+            ),
+            vec![important, due, later]
+        );
+    }
+
+    #[test]
+    fn zero_deadline_weight_keeps_priority_order() {
+        let db = setup_db();
+        let mut config = default_app_config();
+        config.priorities = Arc::new(PrioritiesConfig {
+            deadline_weight: 0.0,
+            ..Default::default()
+        });
+        let important = new_task(&db, &config, json!({"title": "Important", "priority": 7}));
+        let due = new_task(&db, &config, json!({"title": "Due", "priority": 5}));
+        set_due_now(&db, &due);
+
+        assert_eq!(
+            ready_ids(&db, &config, json!({"ready": true, "format": "json"})),
+            vec![important, due]
+        );
+    }
+
+    #[test]
+    fn claim_candidates_weigh_deadlines() {
+        let db = setup_db();
+        let config = default_app_config();
+        db.register_worker(
+            Some("w".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        let _important = new_task(&db, &config, json!({"title": "Important", "priority": 7}));
+        let due = new_task(&db, &config, json!({"title": "Due", "priority": 5}));
+        set_due_now(&db, &due);
+
+        let candidates = db
+            .rank_claim_candidates(
+                "w",
+                &config.states,
+                &config.deps,
+                &config.priorities,
+                &config.tags,
+                None,
+                10,
+            )
+            .unwrap();
+        assert_eq!(candidates[0].id, due);
+        assert_eq!(candidates[0].deadline_score, 1.0);
+        assert_eq!(candidates[1].deadline_score, 0.0);
+    }
+}
+
+// ============================================================================
+// Session Resume Tests
+// ============================================================================
+
+mod session_resume_tests {
+    use super::*;
+
+    fn worker(db: &Database, id: &str, tags: Vec<String>) -> String {
+        db.register_worker(
+            Some(id.to_string()),
+            tags,
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap()
+        .id
+    }
+
+    #[test]
+    fn resume_returns_claims_marks_and_waits() {
+        let db = setup_db();
+        let states = default_states_config();
+        let ids = default_ids_config();
+        let agent = worker(&db, "resumer", vec!["rust".to_string()]);
+
+        let claimed = db.create_task_simple("In progress", &states, &ids).unwrap();
+        db.claim_task(&claimed.id, &agent, &states).unwrap();
+        db.set_thought(&agent, Some("halfway there".to_string()), None)
+            .unwrap();
+        let awaited = db.create_task_simple("Later", &states, &ids).unwrap();
+        db.add_waiters(&agent, std::slice::from_ref(&awaited.id))
+            .unwrap();
+        db.lock_file("src/lib.rs".to_string(), &agent, None, None)
+            .unwrap();
+        db.add_mark_waiters(&agent, &["src/main.rs".to_string()])
+            .unwrap();
+
+        let (resumed, session) = db
+            .resume_worker(&agent, None, None, None, 300)
+            .unwrap()
+            .expect("session within the grace window should resume");
+
+        // Omitted tags are kept
+        assert_eq!(resumed.tags, vec!["rust".to_string()]);
+        assert_eq!(session.claims.len(), 1);
+        assert_eq!(session.claims[0].id, claimed.id);
+        assert_eq!(
+            session.claims[0].current_thought.as_deref(),
+            Some("halfway there")
+        );
+        assert_eq!(session.marks, vec!["src/lib.rs".to_string()]);
+        assert_eq!(session.waiting_for, vec![awaited.id]);
+        assert_eq!(session.mark_waits, vec!["src/main.rs".to_string()]);
+
+        // The claim is untouched
+        let task = db.get_task(&claimed.id).unwrap().unwrap();
+        assert_eq!(task.worker_id.as_deref(), Some(agent.as_str()));
+    }
+
+    #[test]
+    fn resume_replaces_only_given_settings() {
+        let db = setup_db();
+        let agent = worker(&db, "retagged", vec!["old".to_string()]);
+
+        let (resumed, _) = db
+            .resume_worker(
+                &agent,
+                Some(vec!["new".to_string()]),
+                Some("swarm".to_string()),
+                None,
+                300,
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(resumed.tags, vec!["new".to_string()]);
+        assert_eq!(resumed.workflow.as_deref(), Some("swarm"));
+        assert!(resumed.overlays.is_empty());
+    }
+
+    #[test]
+    fn resume_fails_for_unknown_or_expired_sessions() {
+        let db = setup_db();
+        assert!(
+            db.resume_worker("nobody", None, None, None, 300)
+                .unwrap()
+                .is_none()
+        );
+
+        let agent = worker(&db, "sleeper", vec![]);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert!(
+            db.resume_worker(&agent, None, None, None, 0)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
+
+// ============================================================================
+// Worker Detail Tests
+// ============================================================================
+
+mod worker_detail_tests {
+    use super::*;
+    use task_graph_mcp::db::tasks::THOUGHT_LOG_LIMIT;
+
+    fn setup_worker(db: &Database, id: &str) {
+        db.register_worker(
+            Some(id.to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn thoughts_are_logged_per_claimed_task() {
+        let db = setup_db();
+        let states = default_states_config();
+        setup_worker(&db, "thinker");
+
+        // A thought without claims is still part of the stream
+        db.set_thought("thinker", Some("looking around".to_string()), None)
+            .unwrap();
+
+        let task = db
+            .create_task_simple("Think", &states, &default_ids_config())
+            .unwrap();
+        db.claim_task(&task.id, "thinker", &states).unwrap();
+        db.set_thought("thinker", Some("on it".to_string()), None)
+            .unwrap();
+        // Clearing the current thought is not logged
+        db.set_thought("thinker", None, None).unwrap();
+
+        let thoughts = db.get_worker_thoughts("thinker", 10).unwrap();
+        assert_eq!(thoughts.len(), 2);
+        assert_eq!(thoughts[0].thought, "on it");
+        assert_eq!(thoughts[0].task_id.as_deref(), Some(task.id.as_str()));
+        assert_eq!(thoughts[1].thought, "looking around");
+        assert!(thoughts[1].task_id.is_none());
+    }
+
+    #[test]
+    fn thought_log_is_pruned_per_worker() {
+        let db = setup_db();
+        setup_worker(&db, "chatty");
+        setup_worker(&db, "quiet");
+        db.set_thought("quiet", Some("hello".to_string()), None)
+            .unwrap();
+
+        for i in 0..THOUGHT_LOG_LIMIT + 5 {
+            db.set_thought("chatty", Some(format!("thought {}", i)), None)
+                .unwrap();
+        }
+
+        let thoughts = db.get_worker_thoughts("chatty", 1000).unwrap();
+        assert_eq!(thoughts.len() as i64, THOUGHT_LOG_LIMIT);
+        assert_eq!(
+            thoughts[0].thought,
+            format!("thought {}", THOUGHT_LOG_LIMIT + 4)
+        );
+        assert_eq!(db.get_worker_thoughts("quiet", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn completions_and_metrics_are_attributed_to_the_worker() {
+        let db = setup_db();
+        let states = default_states_config();
+        let ids = default_ids_config();
+        setup_worker(&db, "finisher");
+        setup_worker(&db, "bystander");
+
+        let done = db.create_task_simple("Done", &states, &ids).unwrap();
+        db.claim_task(&done.id, "finisher", &states).unwrap();
+        db.log_metrics(&done.id, Some(0.25), &[100, 20]).unwrap();
+        db.complete_task(&done.id, "finisher", &states).unwrap();
+
+        let open = db.create_task_simple("Open", &states, &ids).unwrap();
+        db.claim_task(&open.id, "finisher", &states).unwrap();
+        db.log_metrics(&open.id, Some(0.5), &[50]).unwrap();
+
+        let completions = db.get_worker_completions("finisher", 10).unwrap();
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].task_id, done.id);
+        assert_eq!(completions[0].cost_usd, 0.25);
+
+        let metrics = db.get_worker_metrics("finisher").unwrap();
+        assert_eq!(metrics.task_count, 2);
+        assert_eq!(metrics.completed_count, 1);
+        assert_eq!(metrics.total_cost_usd, 0.75);
+        assert_eq!(metrics.metrics[0], 150);
+        assert_eq!(metrics.metrics[1], 20);
+
+        assert!(
+            db.get_worker_completions("bystander", 10)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(db.get_worker_metrics("bystander").unwrap().task_count, 0);
+    }
+}
+
+mod project_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::resources::tasks as task_resources;
+    use task_graph_mcp::tools::tasks::create;
+    use task_graph_mcp::types::DEFAULT_PROJECT;
+
+    fn connect(db: &Database, id: &str, project: &str) {
+        db.register_worker(
+            Some(id.to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        db.set_worker_project(id, project).unwrap();
+    }
+
+    #[test]
+    fn tasks_default_to_the_default_project_and_children_inherit() {
+        let db = setup_db();
+        let config = default_app_config();
+        let plain = new_task(&db, &config, json!({"title": "Plain"}));
+        let root = new_task(&db, &config, json!({"title": "Root", "project": "alpha"}));
+        let child = new_task(&db, &config, json!({"title": "Child", "parent": root}));
+
+        assert_eq!(
+            db.get_task(&plain).unwrap().unwrap().project,
+            DEFAULT_PROJECT
+        );
+        assert_eq!(db.get_task(&root).unwrap().unwrap().project, "alpha");
+        assert_eq!(db.get_task(&child).unwrap().unwrap().project, "alpha");
+
+        // A project that contradicts the parent's is rejected
+        let err = create(
+            &db,
+            &config,
+            json!({"title": "Stray", "parent": root, "project": "beta"}),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("alpha"));
+    }
+
+    #[test]
+    fn agents_only_see_and_claim_their_own_project() {
+        let db = setup_db();
+        let config = default_app_config();
+        let alpha = new_task(&db, &config, json!({"title": "Alpha", "project": "alpha"}));
+        let beta = new_task(&db, &config, json!({"title": "Beta", "project": "beta"}));
+        connect(&db, "ann", "alpha");
+
+        let ready: Vec<String> = db
+            .get_ready_tasks(Some("ann"), &config.states, &config.deps, None, None, None)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(ready, vec![alpha.clone()]);
+
+        assert!(db.claim_task(&alpha, "ann", &config.states).is_ok());
+        let err = db.claim_task(&beta, "ann", &config.states).unwrap_err();
+        assert!(err.to_string().contains("project"));
+    }
+
+    #[test]
+    fn links_cannot_cross_projects() {
+        let db = setup_db();
+        let config = default_app_config();
+        let a = new_task(&db, &config, json!({"title": "A", "project": "alpha"}));
+        let b = new_task(&db, &config, json!({"title": "B", "project": "beta"}));
+        let c = new_task(&db, &config, json!({"title": "C", "project": "alpha"}));
+
+        assert!(db.add_dependency(&a, &b, "blocks", &config.deps).is_err());
+        assert!(db.add_dependency(&a, &c, "blocks", &config.deps).is_ok());
+    }
+
+    #[test]
+    fn marks_record_the_agent_project() {
+        let db = setup_db();
+        connect(&db, "ann", "alpha");
+        connect(&db, "bob", DEFAULT_PROJECT);
+        db.lock_file("src/a.rs".to_string(), "ann", None, None)
+            .unwrap();
+        db.lock_file("src/b.rs".to_string(), "bob", None, None)
+            .unwrap();
+
+        let alpha = db.get_project_file_locks("alpha").unwrap();
+        assert_eq!(alpha.len(), 1);
+        assert_eq!(alpha["src/a.rs"].worker_id, "ann");
+        assert_eq!(db.get_project_file_locks(DEFAULT_PROJECT).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn project_resources_are_scoped() {
+        let db = setup_db();
+        let config = default_app_config();
+        new_task(&db, &config, json!({"title": "A1", "project": "alpha"}));
+        new_task(&db, &config, json!({"title": "A2", "project": "alpha"}));
+        new_task(&db, &config, json!({"title": "Other"}));
+        connect(&db, "ann", "alpha");
+
+        let ready =
+            task_resources::get_ready_tasks(&db, &config.states, &config.deps, Some("alpha"), None)
+                .unwrap();
+        assert_eq!(ready["tasks"].as_array().unwrap().len(), 2);
+
+        let projects = task_resources::get_projects(&db).unwrap();
+        let projects = projects["projects"].as_array().unwrap();
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects[0]["project"], "alpha");
+        assert_eq!(projects[0]["total_tasks"], 2);
+        assert_eq!(projects[0]["workers"], json!(["ann"]));
+        assert_eq!(projects[1]["project"], DEFAULT_PROJECT);
+        assert_eq!(projects[1]["total_tasks"], 1);
+    }
+}
+
+mod visibility_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::config::VisibilityRule;
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::resources::tasks as task_resources;
+    use task_graph_mcp::tools::{search, tasks};
+
+    fn connect(db: &Database, id: &str, tags: &[&str]) {
+        db.register_worker(
+            Some(id.to_string()),
+            tags.iter().map(|t| t.to_string()).collect(),
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+    }
+
+    /// Contractors (tagged `contractor`) see `external` tasks and the `shared` subtree.
+    fn contractor_config(shared: &str) -> AppConfig {
+        let mut config = default_app_config();
+        let mut visibility = VisibilityConfig::default();
+        visibility.rules.insert(
+            "contractors".to_string(),
+            VisibilityRule {
+                agent_tags: vec!["contractor".to_string()],
+                tags: vec!["external".to_string()],
+                subtrees: vec![shared.to_string()],
+                ..Default::default()
+            },
+        );
+        config.visibility = Arc::new(visibility);
+        config
+    }
+
+    fn listed(db: &Database, config: &AppConfig, args: Value) -> Vec<String> {
+        let result = tasks::list_tasks(db, config, OutputFormat::Json, args).unwrap();
+        let mut ids: Vec<String> = result["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn rules_match_agents_by_id_group_or_tag() {
+        let mut visibility = VisibilityConfig::default();
+        visibility.rules.insert(
+            "a".to_string(),
+            VisibilityRule {
+                agents: vec!["ext-1".to_string()],
+                tags: vec!["public".to_string()],
+                ..Default::default()
+            },
+        );
+        visibility.rules.insert(
+            "b".to_string(),
+            VisibilityRule {
+                groups: vec!["vendors".to_string()],
+                subtrees: vec!["root".to_string()],
+                ..Default::default()
+            },
+        );
+
+        let scope = visibility
+            .scope_for("ext-1", &["vendors".to_string()], &[])
+            .unwrap();
+        assert_eq!(scope.rules, vec!["a", "b"]);
+        assert_eq!(scope.tags, vec!["public"]);
+        assert_eq!(scope.subtrees, vec!["root"]);
+
+        assert!(visibility.scope_for("staff", &[], &[]).is_none());
+        assert!(visibility.validate().is_ok());
+
+        visibility
+            .rules
+            .insert("nobody".to_string(), VisibilityRule::default());
+        assert!(visibility.validate().is_err());
+    }
+
+    #[test]
+    fn restricted_agents_list_only_visible_tasks() {
+        let db = setup_db();
+        let shared = new_task(&db, &default_app_config(), json!({"title": "Shared"}));
+        let config = contractor_config(&shared);
+        let child = new_task(&db, &config, json!({"title": "Child", "parent": shared}));
+        let external = new_task(
+            &db,
+            &config,
+            json!({"title": "External", "tags": ["external"]}),
+        );
+        let internal = new_task(&db, &config, json!({"title": "Internal"}));
+        connect(&db, "ext", &["contractor"]);
+        connect(&db, "staff", &[]);
+
+        let mut visible = vec![shared.clone(), child.clone(), external.clone()];
+        visible.sort();
+        assert_eq!(listed(&db, &config, json!({"worker_id": "ext"})), visible);
+        assert_eq!(
+            listed(
+                &db,
+                &config,
+                json!({"worker_id": "ext", "tags_any": ["external"]})
+            ),
+            vec![external.clone()]
+        );
+        let mut ready = vec![child.clone(), external.clone()];
+        ready.sort();
+        assert_eq!(
+            listed(&db, &config, json!({"agent": "ext", "ready": true})),
+            ready
+        );
+        assert_eq!(
+            listed(
+                &db,
+                &config,
+                json!({"worker_id": "ext", "parent": shared, "recursive": true})
+            ),
+            vec![child.clone()]
+        );
+
+        // Unrestricted agents see everything; anonymous calls see nothing
+        assert_eq!(listed(&db, &config, json!({"worker_id": "staff"})).len(), 4);
+        assert!(listed(&db, &config, json!({"worker_id": "staff"})).contains(&internal));
+        assert!(listed(&db, &config, json!({})).is_empty());
+    }
+
+    #[test]
+    fn search_and_resources_respect_visibility() {
+        let db = setup_db();
+        let shared = new_task(
+            &db,
+            &default_app_config(),
+            json!({"title": "Shared widget"}),
+        );
+        let config = contractor_config(&shared);
+        new_task(&db, &config, json!({"title": "Secret widget"}));
+        connect(&db, "ext", &["contractor"]);
+
+        let hits = |worker: Option<&str>| {
+            let mut args = json!({"query": "widget"});
+            if let Some(w) = worker {
+                args["worker_id"] = json!(w);
+            }
+            search::search(&db, &config.visibility, 20, args).unwrap()["result_count"].clone()
+        };
+        assert_eq!(hits(Some("ext")), json!(1));
+        assert_eq!(hits(None), json!(0));
+
+        let scope = db.task_scope("ext", &config.visibility).unwrap();
+        let all = task_resources::get_all_tasks(&db, None, scope.as_ref()).unwrap();
+        assert_eq!(all["tasks"].as_array().unwrap().len(), 1);
+        assert_eq!(all["tasks"][0]["id"], json!(shared));
+        let ready = task_resources::get_ready_tasks(
+            &db,
+            &config.states,
+            &config.deps,
+            None,
+            scope.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(ready["tasks"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn direct_reads_hide_tasks_outside_the_scope() {
+        use task_graph_mcp::error::{ErrorCode, ToolError};
+        use task_graph_mcp::format::ToolResult;
+        use task_graph_mcp::tools::{deps, query};
+
+        let db = setup_db();
+        let shared = new_task(&db, &default_app_config(), json!({"title": "Shared"}));
+        let config = contractor_config(&shared);
+        let child = new_task(&db, &config, json!({"title": "Child", "parent": shared}));
+        let tagged = new_task(
+            &db,
+            &config,
+            json!({"title": "Tagged", "tags": ["external"]}),
+        );
+        new_task(&db, &config, json!({"title": "Untagged", "parent": tagged}));
+        let internal = new_task(&db, &config, json!({"title": "Internal"}));
+        db.add_dependency(&internal, &child, "blocks", &config.deps)
+            .unwrap();
+        connect(&db, "ext", &["contractor"]);
+        connect(&db, "staff", &[]);
+        let code = |err: anyhow::Error| err.downcast::<ToolError>().unwrap().code;
+        let get = |args: Value| {
+            tasks::get(
+                &db,
+                &config.states,
+                &config.priorities,
+                &config.visibility,
+                OutputFormat::Json,
+                args,
+            )
+        };
+
+        // Hidden tasks do not exist for restricted and anonymous callers
+        let err = get(json!({"task": internal, "worker_id": "ext"})).unwrap_err();
+        assert_eq!(code(err), ErrorCode::TaskNotFound);
+        let err = get(json!({"task": shared})).unwrap_err();
+        assert_eq!(code(err), ErrorCode::TaskNotFound);
+        assert!(get(json!({"task": internal, "worker_id": "staff"})).is_ok());
+
+        // Expansions leave out hidden neighbours
+        let task =
+            get(json!({"task": child, "worker_id": "ext", "include": ["blockers"]})).unwrap();
+        assert_eq!(task["blockers"], json!([]));
+        assert_eq!(task["blocked_by"], json!([]));
+
+        let scan = |args: Value| {
+            tasks::scan(
+                &db,
+                &config.priorities,
+                &config.visibility,
+                OutputFormat::Json,
+                args,
+            )
+        };
+        let result =
+            scan(json!({"task": child, "worker_id": "ext", "before": -1, "above": -1})).unwrap();
+        assert!(result.get("before").is_none());
+        assert_eq!(result["above"][0]["id"], json!(shared));
+        let err = scan(json!({"task": internal, "worker_id": "ext"})).unwrap_err();
+        assert_eq!(code(err), ErrorCode::TaskNotFound);
+
+        // Hidden descendants are pruned even though the root is visible
+        let raw = |result: ToolResult| match result {
+            ToolResult::Raw(text) => text,
+            _ => panic!("expected raw output"),
+        };
+        let md = raw(tasks::export_markdown(
+            &db,
+            &config.states,
+            &config.visibility,
+            json!({"task": tagged, "worker_id": "ext"}),
+        )
+        .unwrap());
+        assert!(md.contains("Tagged"));
+        assert!(!md.contains("Untagged"));
+        let csv = raw(deps::export_matrix(
+            &db,
+            &config.deps,
+            &config.visibility,
+            json!({"worker_id": "ext", "layout": "edges"}),
+        )
+        .unwrap());
+        assert!(!csv.contains(&internal));
+
+        // Raw SQL is only for unrestricted callers
+        let sql = |args: Value| query::query(&db, &config.visibility, OutputFormat::Json, args);
+        let err = sql(json!({"sql": "SELECT id FROM tasks", "worker_id": "ext"})).unwrap_err();
+        assert_eq!(code(err), ErrorCode::NotVisible);
+        assert!(sql(json!({"sql": "SELECT id FROM tasks"})).is_err());
+        assert!(sql(json!({"sql": "SELECT id FROM tasks", "worker_id": "staff"})).is_ok());
+    }
+
+    #[tokio::test]
+    async fn unscoped_task_resources_are_refused_under_visibility_rules() {
+        use task_graph_mcp::resources::ResourceHandler;
+
+        let db = Arc::new(setup_db());
+        let shared = new_task(&db, &default_app_config(), json!({"title": "Shared"}));
+        connect(&db, "ext", &["contractor"]);
+        let handler = ResourceHandler::new(db.clone(), contractor_config(&shared));
+
+        for uri in [
+            "query://tasks/all",
+            "query://tasks/tree/x",
+            "query://projects/default/all",
+            "query://deadlines",
+            "changes://since/0",
+            "deps://matrix",
+        ] {
+            assert!(handler.read_resource(uri).await.is_err(), "{}", uri);
+        }
+        let visible = handler
+            .read_resource("query://visible/ext/all")
+            .await
+            .unwrap();
+        assert_eq!(visible["tasks"][0]["id"], json!(shared));
+
+        let open = ResourceHandler::new(db, default_app_config());
+        assert!(open.read_resource("query://tasks/all").await.is_ok());
+    }
+}
+
+mod audit_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::db::audit::AuditQuery;
+    use task_graph_mcp::db::export::ExportOptions;
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> anyhow::Result<Value> {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let result = rt.block_on(handler.call_tool(name, args, &ctx))?;
+        Ok(serde_json::from_str(&result.into_string()).unwrap())
+    }
+
+    #[test]
+    fn mutating_calls_are_recorded_and_reads_are_not() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(setup_db(), dir.path());
+
+        let created = call(
+            &handler,
+            "create",
+            json!({"worker_id": "w1", "title": "Audited"}),
+        )
+        .unwrap();
+        let task_id = created["id"].as_str().unwrap().to_string();
+        call(&handler, "get", json!({"task": task_id})).unwrap();
+        call(
+            &handler,
+            "delete",
+            json!({"worker_id": "w1", "task": task_id}),
+        )
+        .unwrap();
+        assert!(
+            call(
+                &handler,
+                "delete",
+                json!({"worker_id": "w2", "task": "no-such-task"})
+            )
+            .is_err()
+        );
+
+        let entries = handler.db.get_audit_log(AuditQuery::default()).unwrap();
+        let tools: Vec<&str> = entries.iter().map(|e| e.tool.as_str()).collect();
+        assert_eq!(tools, vec!["delete", "delete", "create"]);
+
+        let failed = &entries[0];
+        assert_eq!(failed.worker_id.as_deref(), Some("w2"));
+        assert_ne!(failed.outcome, "ok");
+        assert!(failed.error.is_some());
+
+        let deleted = &entries[1];
+        assert_eq!(deleted.outcome, "ok");
+        assert_eq!(deleted.task_ids, vec![task_id.clone()]);
+        assert_eq!(deleted.args_hash.len(), 64);
+
+        // Created IDs come from the response
+        assert_eq!(entries[2].task_ids, vec![task_id]);
+        assert_ne!(entries[2].args_hash, deleted.args_hash);
+    }
+
+    #[test]
+    fn audit_tool_filters_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(setup_db(), dir.path());
+        let db = &handler.db;
+        db.record_audit(
+            Some("a1"),
+            "link",
+            "h1",
+            &["t1".into(), "t2".into()],
+            "ok",
+            None,
+        )
+        .unwrap();
+        db.record_audit(Some("a2"), "detach", "h2", &["t2".into()], "ok", None)
+            .unwrap();
+        db.record_audit(
+            Some("a1"),
+            "delete",
+            "h3",
+            &["t3".into()],
+            "TASK_NOT_FOUND",
+            Some("Task not found"),
+        )
+        .unwrap();
+
+        let ids = |args: Value| -> Vec<String> {
+            call(&handler, "audit", args).unwrap()["entries"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["args_hash"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(ids(json!({})), vec!["h3", "h2", "h1"]);
+        assert_eq!(ids(json!({"agent": "a1"})), vec!["h3", "h1"]);
+        assert_eq!(ids(json!({"task": "t2"})), vec!["h2", "h1"]);
+        assert_eq!(ids(json!({"tool": "detach"})), vec!["h2"]);
+        assert_eq!(ids(json!({"outcome": "error"})), vec!["h3"]);
+        assert_eq!(ids(json!({"outcome": "ok", "limit": 1})), vec!["h2"]);
+        assert!(ids(json!({"until": "2000-01-01"})).is_empty());
+
+        // Querying the log is not itself audited
+        assert_eq!(db.get_audit_log(AuditQuery::default()).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn export_includes_audit_log() {
+        let db = setup_db();
+        db.record_audit(None, "attach", "h1", &["t1".into()], "ok", None)
+            .unwrap();
+
+        let export = db.export_tables(&ExportOptions::default()).unwrap();
+        let entries = export.audit_log.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].tool, "attach");
+        assert_eq!(entries[0].task_ids, vec!["t1"]);
+    }
+}
+
+mod trash_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::trash;
+
+    fn task(db: &Database, title: &str, parent: Option<&str>, tags: &[&str]) -> String {
+        db.create_task(
+            None,
+            title.to_string(),
+            None,
+            parent.map(String::from),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(tags.iter().map(|t| t.to_string()).collect()),
+            &StatesConfig::default(),
+            &default_ids_config(),
+        )
+        .unwrap()
+        .id
+    }
+
+    /// Soft-delete a task (and its subtree) as if it happened `days` ago.
+    /// Backdates every deletion newer than that, so call oldest first.
+    fn delete_days_ago(db: &Database, id: &str, days: i64) {
+        db.delete_task(id, "w1", true, Some("done".into()), false, false)
+            .unwrap();
+        let deleted_at = task_graph_mcp::db::now_ms() - days * 24 * 60 * 60 * 1000;
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE tasks SET deleted_at = ?1 WHERE deleted_at > ?1",
+                [deleted_at],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn purge_removes_old_deletions_with_their_rows_and_files() {
+        let db = setup_db();
+        let media = tempfile::tempdir().unwrap();
+
+        let old = task(&db, "Old", None, &["stale"]);
+        let old_child = task(&db, "Old child", Some(&old), &[]);
+        let recent = task(&db, "Recent", None, &[]);
+        let live = task(&db, "Live", None, &[]);
+        db.add_dependency(&old, &live, "blocks", &DependenciesConfig::default())
+            .unwrap();
+
+        let file = media.path().join("notes.txt");
+        std::fs::write(&file, "notes").unwrap();
+        db.add_attachment(
+            &old_child,
+            "note".into(),
+            "notes".into(),
+            String::new(),
+            None,
+            Some(file.to_string_lossy().to_string()),
+        )
+        .unwrap();
+
+        delete_days_ago(&db, &old, 40);
+        delete_days_ago(&db, &recent, 5);
+
+        let preview = trash::purge(
+            &db,
+            media.path(),
+            json!({"older_than_days": 30, "dry_run": true}),
+        )
+        .unwrap();
+        assert_eq!(preview["task_count"], json!(2));
+        assert_eq!(preview["rows"]["dependencies"], json!(2));
+        assert_eq!(preview["rows"]["attachments"], json!(1));
+        assert_eq!(preview["rows"]["task_tags"], json!(1));
+        assert!(db.get_task(&old).unwrap().is_some());
+        assert!(file.exists());
+
+        let purged = trash::purge(&db, media.path(), json!({"older_than_days": 30})).unwrap();
+        let mut expected = vec![old.clone(), old_child.clone()];
+        expected.sort();
+        assert_eq!(purged["tasks"], json!(expected));
+        assert_eq!(purged["files_deleted"], json!(1));
+        assert!(db.get_task(&old).unwrap().is_none());
+        assert!(db.get_task(&old_child).unwrap().is_none());
+        assert!(!file.exists());
+
+        // Recent deletions and live tasks stay; edges into the purged set go
+        assert!(db.get_task(&recent).unwrap().is_some());
+        assert!(db.get_blockers(&live).unwrap().is_empty());
+        assert!(db.get_task(&live).unwrap().is_some());
+    }
+
+    #[test]
+    fn purge_rejects_negative_age_and_handles_empty_trash() {
+        let db = setup_db();
+        let media = tempfile::tempdir().unwrap();
+        task(&db, "Live", None, &[]);
+
+        assert!(trash::purge(&db, media.path(), json!({"older_than_days": -1})).is_err());
+        let result = trash::purge(&db, media.path(), json!({"older_than_days": 0})).unwrap();
+        assert_eq!(result["task_count"], json!(0));
+        assert!(result.get("files_deleted").is_none());
+    }
+
+    #[test]
+    fn list_deleted_reports_who_why_and_parent_state() {
+        let db = setup_db();
+        let parent = task(&db, "Parent", None, &[]);
+        let child = task(&db, "Child", Some(&parent), &[]);
+        let other = task(&db, "Other", None, &[]);
+        delete_days_ago(&db, &parent, 3);
+        db.delete_task(&other, "w2", false, None, false, false)
+            .unwrap();
+
+        let listed = trash::list_deleted(&db, json!({})).unwrap();
+        assert_eq!(listed["task_count"], json!(3));
+        let tasks = listed["tasks"].as_array().unwrap();
+        assert_eq!(tasks[0]["id"], json!(other));
+        assert_eq!(tasks[0]["deleted_by"], json!("w2"));
+
+        let entry = tasks.iter().find(|t| t["id"] == json!(child)).unwrap();
+        assert_eq!(entry["parent"], json!(parent));
+        assert_eq!(entry["parent_deleted"], json!(true));
+        assert_eq!(entry["deleted_reason"], json!("done"));
+        assert!(entry["age_ms"].as_i64().unwrap() >= 3 * 24 * 60 * 60 * 1000);
+
+        let by_w1 = trash::list_deleted(&db, json!({"deleted_by": "w1", "limit": 1})).unwrap();
+        assert_eq!(by_w1["task_count"], json!(1));
+        assert_eq!(by_w1["has_more"], json!(true));
+    }
+
+    #[test]
+    fn restore_brings_back_subtree_and_edges() {
+        let db = setup_db();
+        let parent = task(&db, "Parent", None, &[]);
+        let child = task(&db, "Child", Some(&parent), &[]);
+        let live = task(&db, "Live", None, &[]);
+        db.add_dependency(&child, &live, "blocks", &DependenciesConfig::default())
+            .unwrap();
+        delete_days_ago(&db, &parent, 1);
+
+        // The child alone cannot come back under a deleted parent
+        let err = trash::restore(&db, json!({"task": child})).unwrap_err();
+        assert!(err.to_string().contains("is deleted"));
+
+        let restored = trash::restore(&db, json!({"task": parent})).unwrap();
+        let mut expected = vec![parent.clone(), child.clone()];
+        expected.sort();
+        let mut got: Vec<String> = serde_json::from_value(restored["restored"].clone()).unwrap();
+        got.sort();
+        assert_eq!(got, expected);
+        assert_eq!(restored["dependencies"], json!(2));
+        assert!(
+            trash::list_deleted(&db, json!({})).unwrap()["tasks"]
+                .as_array()
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(db.get_parent(&child).unwrap(), Some(parent.clone()));
+        assert_eq!(db.get_blockers(&live).unwrap().len(), 1);
+
+        // Restoring a live task is an error
+        assert!(trash::restore(&db, json!({"task": parent})).is_err());
+    }
+
+    #[test]
+    fn restore_with_detach_drops_deleted_parent() {
+        let db = setup_db();
+        let parent = task(&db, "Parent", None, &[]);
+        let child = task(&db, "Child", Some(&parent), &[]);
+        delete_days_ago(&db, &parent, 1);
+
+        let restored = trash::restore(&db, json!({"task": child, "detach": true})).unwrap();
+        assert_eq!(restored["restored"], json!([child.clone()]));
+        assert_eq!(restored["detached_from"], json!(parent));
+        assert_eq!(db.get_parent(&child).unwrap(), None);
+        assert!(db.get_task(&parent).unwrap().is_some());
+        assert_eq!(
+            trash::list_deleted(&db, json!({})).unwrap()["task_count"],
+            json!(1)
+        );
+    }
+}
+
+mod task_changes_tests {
+    use super::*;
+    use serde_json::{Map, Value, json};
+    use task_graph_mcp::db::changes::diff_task;
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+    use task_graph_mcp::types::Task;
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> anyhow::Result<Value> {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let result = rt.block_on(handler.call_tool(name, args, &ctx))?;
+        Ok(serde_json::from_str(&result.into_string()).unwrap())
+    }
+
+    #[test]
+    fn diff_covers_columns_and_custom_fields() {
+        let db = setup_db();
+        let before = db
+            .create_task(
+                None,
+                "Diffed".to_string(),
+                Some("old".to_string()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &default_states_config(),
+                &default_ids_config(),
+            )
+            .unwrap();
+        let after = Task {
+            description: None,
+            priority: 9,
+            tags: vec!["infra".to_string()],
+            ..before.clone()
+        };
+        let mut before_fields = Map::new();
+        before_fields.insert("due_at".to_string(), json!("2026-01-01"));
+        before_fields.insert("owner".to_string(), json!("ops"));
+        let mut after_fields = before_fields.clone();
+        after_fields.insert("due_at".to_string(), json!("2026-02-01"));
+
+        let changes = diff_task(&before, &before_fields, &after, &after_fields);
+        let fields: Vec<&str> = changes.iter().map(|(f, _, _)| f.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["description", "priority", "tags", "fields.due_at"]
+        );
+        assert_eq!(changes[0].1, json!("old"));
+        assert_eq!(changes[0].2, Value::Null);
+
+        assert_eq!(
+            db.record_task_changes(Some("w1"), &before, &before_fields, &after, &after_fields)
+                .unwrap(),
+            4
+        );
+        assert_eq!(
+            db.record_task_changes(Some("w1"), &after, &after_fields, &after, &after_fields)
+                .unwrap(),
+            0
+        );
+        let stored = db.get_task_changes(&before.id).unwrap();
+        assert_eq!(stored.len(), 4);
+        assert_eq!(stored[0].old_value, json!("old"));
+        assert_eq!(stored[0].new_value, Value::Null);
+        assert_eq!(stored[1].new_value, json!(9));
+        assert_eq!(stored[2].new_value, json!(["infra"]));
+        assert_eq!(stored[3].worker_id.as_deref(), Some("w1"));
+    }
+
+    #[test]
+    fn update_records_changes_and_history_shows_diffs() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(setup_db(), dir.path());
+
+        let created = call(
+            &handler,
+            "create",
+            json!({"worker_id": "w1", "title": "Tracked", "description": "one\ntwo\nthree"}),
+        )
+        .unwrap();
+        let task_id = created["id"].as_str().unwrap().to_string();
+
+        call(
+            &handler,
+            "update",
+            json!({
+                "worker_id": "w2",
+                "task": task_id,
+                "description": "one\n2\nthree",
+                "time_estimate_ms": 60000
+            }),
+        )
+        .unwrap();
+        call(
+            &handler,
+            "update",
+            json!({"worker_id": "w3", "task": task_id, "fields": {"area": "backend"}}),
+        )
+        .unwrap();
+
+        let plain = call(&handler, "task_history", json!({"task": task_id})).unwrap();
+        assert!(plain.get("changes").is_none());
+
+        let history = call(
+            &handler,
+            "task_history",
+            json!({"task": task_id, "show_diffs": true}),
+        )
+        .unwrap();
+        let changes = history["changes"].as_array().unwrap();
+        let fields: Vec<&str> = changes
+            .iter()
+            .map(|c| c["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            vec!["description", "time_estimate_ms", "fields.area"]
+        );
+
+        assert_eq!(changes[0]["worker_id"], "w2");
+        assert_eq!(
+            changes[0]["diff"],
+            json!(["  one", "- two", "+ 2", "  three"])
+        );
+        assert_eq!(changes[1]["old_value"], Value::Null);
+        assert_eq!(changes[1]["new_value"], json!(60000));
+        assert!(changes[1].get("diff").is_none());
+        assert_eq!(changes[2]["worker_id"], "w3");
+        assert_eq!(changes[2]["new_value"], "backend");
+
+        let markdown = call(
+            &handler,
+            "task_history",
+            json!({"task": task_id, "show_diffs": true, "format": "markdown"}),
+        )
+        .unwrap();
+        let text = markdown.to_string();
+        assert!(text.contains("## Field Changes"));
+        assert!(text.contains("- two"));
+    }
+}
+
+mod time_travel_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::db::export::ExportOptions;
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> anyhow::Result<Value> {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let result = rt.block_on(handler.call_tool(name, args, &ctx))?;
+        Ok(serde_json::from_str(&result.into_string()).unwrap())
+    }
+
+    /// A timestamp strictly between the operations before and after it.
+    fn checkpoint() -> i64 {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let now = chrono::Utc::now().timestamp_millis();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        now
+    }
+
+    /// Task A is created, then edited and claimed, then deleted; B appears midway.
+    fn setup(handler: &ToolHandler) -> (String, String, i64, i64, i64) {
+        let before_a = checkpoint();
+        let a = call(
+            handler,
+            "create",
+            json!({"worker_id": "w1", "title": "Plan", "description": "v1", "tags": ["draft"]}),
+        )
+        .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let t1 = checkpoint();
+
+        call(handler, "connect", json!({"worker_id": "w1"})).unwrap();
+        call(
+            handler,
+            "update",
+            json!({
+                "worker_id": "w1",
+                "task": a,
+                "description": "v2",
+                "priority": 8,
+                "tags": ["final"],
+                "status": "working",
+                "fields": {"area": "backend"}
+            }),
+        )
+        .unwrap();
+        let b = call(
+            handler,
+            "create",
+            json!({"worker_id": "w2", "title": "Follow-up"}),
+        )
+        .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let t2 = checkpoint();
+
+        call(
+            handler,
+            "delete",
+            json!({"worker_id": "w1", "task": a, "force": true}),
+        )
+        .unwrap();
+        (a, b, before_a, t1, t2)
+    }
+
+    #[test]
+    fn get_as_of_rebuilds_past_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(setup_db(), dir.path());
+        let (a, _, before_a, t1, t2) = setup(&handler);
+
+        let past = call(&handler, "get", json!({"task": a, "as_of": t1.to_string()})).unwrap();
+        assert_eq!(past["description"], "v1");
+        assert_eq!(past["status"], "pending");
+        assert_eq!(past["tags"], json!(["draft"]));
+        assert!(past.get("priority").is_none());
+        assert!(past.get("worker_id").is_none());
+        assert!(past.get("fields").is_none());
+        assert_eq!(past["as_of"], json!(t1));
+
+        let later = call(&handler, "get", json!({"task": a, "as_of": t2.to_string()})).unwrap();
+        assert_eq!(later["description"], "v2");
+        assert_eq!(later["priority"], 8);
+        assert_eq!(later["status"], "working");
+        assert_eq!(later["worker_id"], "w1");
+        assert_eq!(later["fields"], json!({"area": "backend"}));
+
+        assert!(
+            call(
+                &handler,
+                "get",
+                json!({"task": a, "as_of": before_a.to_string()})
+            )
+            .is_err()
+        );
+        assert!(call(&handler, "get", json!({"task": a, "as_of": "last tuesday"})).is_err());
+    }
+
+    #[test]
+    fn list_tasks_as_of_filters_past_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(setup_db(), dir.path());
+        let (a, b, _, t1, t2) = setup(&handler);
+
+        let ids = |result: Value| -> Vec<String> {
+            let mut ids: Vec<String> = result["tasks"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t["id"].as_str().unwrap().to_string())
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        let at_t1 = call(&handler, "list_tasks", json!({"as_of": t1.to_string()})).unwrap();
+        assert_eq!(ids(at_t1), vec![a.clone()]);
+
+        let mut both = vec![a.clone(), b.clone()];
+        both.sort();
+        let at_t2 = call(&handler, "list_tasks", json!({"as_of": t2.to_string()})).unwrap();
+        assert_eq!(ids(at_t2), both);
+
+        let working = call(
+            &handler,
+            "list_tasks",
+            json!({"as_of": t2.to_string(), "status": "working", "fields": {"area": "backend"}}),
+        )
+        .unwrap();
+        assert_eq!(ids(working), vec![a.clone()]);
+
+        let draft = call(
+            &handler,
+            "list_tasks",
+            json!({"as_of": t1.to_string(), "tags_any": ["draft"]}),
+        )
+        .unwrap();
+        assert_eq!(ids(draft), vec![a]);
+
+        assert!(
+            call(
+                &handler,
+                "list_tasks",
+                json!({"as_of": t1.to_string(), "ready": true})
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn export_snapshot_at_rewinds_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(setup_db(), dir.path());
+        let (a, _, _, t1, _) = setup(&handler);
+
+        let export = handler
+            .db
+            .export_tables(&ExportOptions {
+                snapshot_at: Some(t1),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let tasks = export.tasks.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, a);
+        assert_eq!(tasks[0].description.as_deref(), Some("v1"));
+        assert_eq!(tasks[0].status, "pending");
+
+        let tags: Vec<String> = export
+            .task_tags
+            .unwrap()
+            .into_iter()
+            .map(|r| r.tag)
+            .collect();
+        assert_eq!(tags, vec!["draft"]);
+        assert!(export.task_fields.unwrap().is_empty());
+        assert!(export.task_changes.unwrap().is_empty());
+        let sequence = export.task_sequence.unwrap();
+        assert_eq!(sequence.len(), 1);
+        assert!(sequence[0].end_timestamp.is_none());
+        assert!(export.audit_log.unwrap().iter().all(|e| e.timestamp <= t1));
+    }
+}
+
+mod change_feed_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::resources::changes::get_changes_since;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> anyhow::Result<Value> {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let result = rt.block_on(handler.call_tool(name, args, &ctx))?;
+        Ok(serde_json::from_str(&result.into_string()).unwrap())
+    }
+
+    fn create(handler: &ToolHandler, title: &str) -> String {
+        call(
+            handler,
+            "create",
+            json!({"worker_id": "w1", "title": title}),
+        )
+        .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// (entity, action, task_id, detail) of each entry.
+    fn summarize(page: &Value) -> Vec<(String, String, String, Option<String>)> {
+        page["changes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| {
+                (
+                    c["entity"].as_str().unwrap().to_string(),
+                    c["action"].as_str().unwrap().to_string(),
+                    c["task_id"].as_str().unwrap().to_string(),
+                    c["detail"].as_str().map(String::from),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn feed_records_mutations_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = handler(setup_db(), dir.path());
+        let a = create(&h, "A");
+        let b = create(&h, "B");
+        call(&h, "link", json!({"from": a, "to": b, "type": "blocks"})).unwrap();
+        call(
+            &h,
+            "update",
+            json!({"worker_id": "w1", "task": b, "title": "B2", "fields": {"area": "api"}}),
+        )
+        .unwrap();
+        call(
+            &h,
+            "attach",
+            json!({"task": b, "type": "note", "name": "n1", "content": "hi"}),
+        )
+        .unwrap();
+        call(&h, "delete", json!({"worker_id": "w1", "task": a})).unwrap();
+
+        let page = get_changes_since(&h.db, "0").unwrap();
+        let summary = summarize(&page);
+        let entry = |e: &str, act: &str, t: &str, d: Option<&str>| {
+            (
+                e.to_string(),
+                act.to_string(),
+                t.to_string(),
+                d.map(String::from),
+            )
+        };
+        assert_eq!(summary[0], entry("task", "created", &a, None));
+        assert_eq!(summary[1], entry("task", "created", &b, None));
+        assert!(summary.contains(&entry("dependency", "added", &b, Some("blocks"))));
+        assert!(summary.contains(&entry("task", "updated", &b, None)));
+        assert!(summary.contains(&entry("task", "updated", &b, Some("fields.area"))));
+        assert!(summary.contains(&entry("attachment", "added", &b, Some("n1"))));
+        assert_eq!(summary.last().unwrap(), &entry("task", "deleted", &a, None));
+
+        // Cursors strictly increase and the page ends at the head
+        let ids: Vec<i64> = page["changes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["id"].as_i64().unwrap())
+            .collect();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(page["next_cursor"], json!(ids.last().unwrap()));
+        assert_eq!(page["has_more"], json!(false));
+    }
+
+    #[test]
+    fn cursor_resumes_after_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("feed.db");
+
+        let cursor = {
+            let h = handler(Database::open(&db_path).unwrap(), dir.path());
+            create(&h, "A");
+            get_changes_since(&h.db, "0").unwrap()["next_cursor"]
+                .as_i64()
+                .unwrap()
+        };
+
+        let h = handler(Database::open(&db_path).unwrap(), dir.path());
+        let b = create(&h, "B");
+        let page = get_changes_since(&h.db, &cursor.to_string()).unwrap();
+        let summary = summarize(&page);
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].1, "created");
+        assert_eq!(summary[0].2, b);
+
+        // Caught up: nothing new, cursor stays put
+        let next = page["next_cursor"].as_i64().unwrap();
+        let page = get_changes_since(&h.db, &next.to_string()).unwrap();
+        assert!(page["changes"].as_array().unwrap().is_empty());
+        assert_eq!(page["next_cursor"], json!(next));
+    }
+
+    #[test]
+    fn invalid_and_future_cursors() {
+        let db = setup_db();
+        assert!(get_changes_since(&db, "abc").is_err());
+        assert!(get_changes_since(&db, "-1").is_err());
+
+        let page = get_changes_since(&db, "1000").unwrap();
+        assert_eq!(page["reset"], json!(true));
+        assert_eq!(page["next_cursor"], json!(0));
+    }
+}
+
+mod call_log_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::config::ToolLogConfig;
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::logging::calls::{ToolCallLog, ToolCallRecord, call_log_path};
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn handler(db: Database, dir: &std::path::Path) -> ToolHandler {
+        super::handler(db, dir).with_call_log(Arc::new(ToolCallLog::new(
+            dir.join("logs"),
+            &ToolLogConfig::default(),
+        )))
+    }
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> anyhow::Result<Value> {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let result = rt.block_on(handler.call_tool(name, args, &ctx))?;
+        Ok(serde_json::from_str(&result.into_string()).unwrap())
+    }
+
+    #[test]
+    fn every_call_is_logged_with_outcome() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = handler(setup_db(), dir.path());
+
+        let worker = call(&h, "connect", json!({})).unwrap()["worker_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        call(&h, "list_tasks", json!({})).unwrap();
+        assert!(call(&h, "get", json!({"task": "missing"})).is_err());
+
+        let records: Vec<ToolCallRecord> =
+            std::fs::read_to_string(call_log_path(&dir.path().join("logs"), 0))
+                .unwrap()
+                .lines()
+                .map(|l| serde_json::from_str(l).unwrap())
+                .collect();
+        let tools: Vec<&str> = records.iter().map(|r| r.tool.as_str()).collect();
+        assert_eq!(tools, vec!["connect", "list_tasks", "get"]);
+
+        // connect reports the generated ID only in its response
+        assert_eq!(records[0].worker_id.as_deref(), Some(worker.as_str()));
+        assert_eq!(records[1].outcome, "ok");
+        assert!(records[1].result_bytes > 0);
+        assert_eq!(records[2].outcome, "TASK_NOT_FOUND");
+        assert!(records[2].error.is_some());
+        assert_eq!(records[2].result_bytes, 0);
+    }
+
+    #[test]
+    fn get_logs_filters_calls() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = handler(setup_db(), dir.path());
+        call(&h, "connect", json!({"worker_id": "w1"})).unwrap();
+        assert!(call(&h, "get", json!({"worker_id": "w1", "task": "missing"})).is_err());
+        call(&h, "list_tasks", json!({"worker_id": "w1"})).unwrap();
+
+        let result = call(&h, "get_logs", json!({"agent": "w1", "outcome": "error"})).unwrap();
+        assert_eq!(result["enabled"], json!(true));
+        assert_eq!(result["entry_count"], json!(1));
+        assert_eq!(result["entries"][0]["tool"], json!("get"));
+        assert_eq!(result["entries"][0]["outcome"], json!("TASK_NOT_FOUND"));
+
+        let result = call(&h, "get_logs", json!({"limit": 2})).unwrap();
+        let tools: Vec<&str> = result["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["tool"].as_str().unwrap())
+            .collect();
+        // The earlier get_logs call is logged too
+        assert_eq!(tools, vec!["get_logs", "list_tasks"]);
+        assert_eq!(result["has_more"], json!(true));
+    }
+}
+
+mod field_selection_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> String {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(handler.call_tool(name, args, &ctx))
+            .unwrap()
+            .into_string()
+    }
+
+    fn setup(dir: &std::path::Path) -> (ToolHandler, String) {
+        let h = handler(setup_db(), dir);
+        call(&h, "connect", json!({"worker_id": "w1"}));
+        let created: Value = serde_json::from_str(&call(
+            &h,
+            "create",
+            json!({"worker_id": "w1", "title": "Fix parser", "description": "A long description"}),
+        ))
+        .unwrap();
+        (h, created["id"].as_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn list_tasks_returns_only_selected_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let (h, id) = setup(dir.path());
+
+        let result: Value = serde_json::from_str(&call(
+            &h,
+            "list_tasks",
+            json!({"select": ["title", "status"]}),
+        ))
+        .unwrap();
+        assert_eq!(
+            result["tasks"][0],
+            json!({"id": id, "title": "Fix parser", "status": "pending"})
+        );
+
+        let result: Value =
+            serde_json::from_str(&call(&h, "list_tasks", json!({"select": ["-description"]})))
+                .unwrap();
+        let task = result["tasks"][0].as_object().unwrap();
+        assert!(task.contains_key("title"));
+        assert!(!task.contains_key("description"));
+    }
+
+    #[test]
+    fn compact_columns_follow_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        let (h, id) = setup(dir.path());
+
+        let out = call(
+            &h,
+            "list_tasks",
+            json!({"format": "compact", "select": ["title"]}),
+        );
+        assert_eq!(out, format!("id\ttitle\n{}\tFix parser\n", id));
+
+        let out = call(
+            &h,
+            "get",
+            json!({"task": id, "format": "compact", "select": ["status"]}),
+        );
+        assert_eq!(out, format!("id\tst\n{}\tpending\n", id));
+    }
+
+    #[test]
+    fn get_omits_fields_in_json_and_markdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let (h, id) = setup(dir.path());
+
+        let task: Value = serde_json::from_str(&call(
+            &h,
+            "get",
+            json!({"task": id, "select": ["-description", "-attachments"]}),
+        ))
+        .unwrap();
+        assert_eq!(task["title"], json!("Fix parser"));
+        assert!(task.get("description").is_none());
+        assert!(task.get("attachments").is_none());
+        assert!(task.get("attachment_counts").is_some());
+
+        let md = call(
+            &h,
+            "get",
+            json!({"task": id, "format": "markdown", "select": ["-description"]}),
+        );
+        assert!(md.contains("Fix parser"));
+        assert!(!md.contains("A long description"));
+    }
+}
+
+mod group_by_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> String {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(handler.call_tool(name, args, &ctx))
+            .unwrap()
+            .into_string()
+    }
+
+    fn create(h: &ToolHandler, args: Value) -> String {
+        let created: Value = serde_json::from_str(&call(h, "create", args)).unwrap();
+        created["id"].as_str().unwrap().to_string()
+    }
+
+    /// An epic with two tagged children, one of them claimed by w1.
+    fn setup(dir: &std::path::Path) -> (ToolHandler, String, String, String) {
+        let h = handler(setup_db(), dir);
+        call(&h, "connect", json!({"worker_id": "w1"}));
+        let epic = create(&h, json!({"worker_id": "w1", "title": "Epic"}));
+        let a = create(
+            &h,
+            json!({"worker_id": "w1", "title": "A", "parent": epic, "tags": ["ui"]}),
+        );
+        let b = create(
+            &h,
+            json!({"worker_id": "w1", "title": "B", "parent": epic, "tags": ["api", "ui"]}),
+        );
+        call(&h, "claim", json!({"worker_id": "w1", "task": b}));
+        (h, epic, a, b)
+    }
+
+    fn group_summary(result: &Value) -> Vec<(Value, u64)> {
+        result["groups"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|g| (g["key"].clone(), g["count"].as_u64().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn groups_by_status_and_agent() {
+        let dir = tempfile::tempdir().unwrap();
+        let (h, _, _, b) = setup(dir.path());
+
+        let result: Value =
+            serde_json::from_str(&call(&h, "list_tasks", json!({"group_by": "status"}))).unwrap();
+        assert_eq!(result["group_by"], json!("status"));
+        assert_eq!(result["total"], json!(3));
+        assert_eq!(
+            group_summary(&result),
+            vec![(json!("working"), 1), (json!("pending"), 2)]
+        );
+
+        let result: Value =
+            serde_json::from_str(&call(&h, "list_tasks", json!({"group_by": "agent"}))).unwrap();
+        assert_eq!(
+            group_summary(&result),
+            vec![(json!("w1"), 1), (Value::Null, 2)]
+        );
+        assert_eq!(result["groups"][0]["tasks"][0]["id"], json!(b));
+    }
+
+    #[test]
+    fn groups_by_tag_and_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let (h, epic, _, _) = setup(dir.path());
+
+        let result: Value =
+            serde_json::from_str(&call(&h, "list_tasks", json!({"group_by": "tag"}))).unwrap();
+        assert_eq!(
+            group_summary(&result),
+            vec![(json!("api"), 1), (json!("ui"), 2), (Value::Null, 1)]
+        );
+
+        let result: Value =
+            serde_json::from_str(&call(&h, "list_tasks", json!({"group_by": "parent"}))).unwrap();
+        assert_eq!(
+            group_summary(&result),
+            vec![(json!(epic), 2), (Value::Null, 1)]
+        );
+        assert_eq!(result["groups"][0]["title"], json!("Epic"));
+    }
+
+    #[test]
+    fn grouped_markdown_and_compact() {
+        let dir = tempfile::tempdir().unwrap();
+        let (h, _, _, b) = setup(dir.path());
+
+        let md = call(
+            &h,
+            "list_tasks",
+            json!({"group_by": "agent", "format": "markdown"}),
+        );
+        assert!(md.contains("## @w1 (1)"));
+        assert!(md.contains("## Unassigned (2)"));
+
+        let out = call(
+            &h,
+            "list_tasks",
+            json!({"group_by": "agent", "format": "compact", "select": ["title"]}),
+        );
+        assert!(out.starts_with("group\tid\ttitle\n"));
+        assert!(out.contains(&format!("w1\t{}\tB\n", b)));
+    }
+
+    #[test]
+    fn rejects_unknown_grouping() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = handler(setup_db(), dir.path());
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let result = rt.block_on(h.call_tool("list_tasks", json!({"group_by": "color"}), &ctx));
+        assert!(result.is_err());
+    }
+}
+
+mod where_filter_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> anyhow::Result<Value> {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let result = rt.block_on(handler.call_tool(name, args, &ctx))?;
+        Ok(serde_json::from_str(&result.into_string()).unwrap())
+    }
+
+    /// Titles of the tasks list_tasks returns for these arguments, sorted.
+    fn titles(h: &ToolHandler, args: Value) -> Vec<String> {
+        let result = call(h, "list_tasks", args).unwrap();
+        let mut titles: Vec<String> = result["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["title"].as_str().unwrap().to_string())
+            .collect();
+        titles.sort();
+        titles
+    }
+
+    fn setup(dir: &std::path::Path) -> ToolHandler {
+        let h = handler(setup_db(), dir);
+        call(&h, "connect", json!({"worker_id": "w1"})).unwrap();
+        for (title, priority, tags, fields) in [
+            ("Api", 8, json!(["backend"]), json!({"estimate": 5})),
+            ("Db", 3, json!(["backend"]), json!({"estimate": 1})),
+            ("Ui", 9, json!(["frontend"]), json!({})),
+        ] {
+            call(
+                &h,
+                "create",
+                json!({"worker_id": "w1", "title": title, "priority": priority, "tags": tags, "fields": fields}),
+            )
+            .unwrap();
+        }
+        let api = call(&h, "list_tasks", json!({"where": "title=Api"})).unwrap()["tasks"][0]["id"]
+            .clone();
+        call(&h, "claim", json!({"worker_id": "w1", "task": api})).unwrap();
+        h
+    }
+
+    #[test]
+    fn combines_status_tags_and_priority() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = setup(dir.path());
+
+        assert_eq!(
+            titles(
+                &h,
+                json!({"where": "status=working AND tag:backend AND priority>=7"})
+            ),
+            vec!["Api"]
+        );
+        assert_eq!(
+            titles(
+                &h,
+                json!({"where": "tag:frontend OR (tag:backend AND priority<5)"})
+            ),
+            vec!["Db", "Ui"]
+        );
+        assert_eq!(
+            titles(&h, json!({"where": "NOT owner=w1"})),
+            vec!["Db", "Ui"]
+        );
+        assert_eq!(titles(&h, json!({"where": "owner=null"})), vec!["Db", "Ui"]);
+        assert_eq!(
+            titles(&h, json!({"where": "title~\"a\" AND status!=working"})),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn filters_custom_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = setup(dir.path());
+
+        assert_eq!(
+            titles(&h, json!({"where": "fields.estimate>2"})),
+            vec!["Api"]
+        );
+        assert_eq!(
+            titles(&h, json!({"where": "fields.estimate=null"})),
+            vec!["Ui"]
+        );
+        assert_eq!(
+            titles(&h, json!({"where": "fields.estimate!=1"})),
+            vec!["Api", "Ui"]
+        );
+    }
+
+    #[test]
+    fn applies_on_top_of_other_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = setup(dir.path());
+
+        assert_eq!(
+            titles(&h, json!({"ready": true, "where": "priority>5"})),
+            vec!["Ui"]
+        );
+        assert_eq!(
+            titles(&h, json!({"tags_any": ["backend"], "where": "priority<5"})),
+            vec!["Db"]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = setup(dir.path());
+
+        for expr in ["status=", "color=red", "title='x' OR 1=1; DROP TABLE tasks"] {
+            assert!(
+                call(&h, "list_tasks", json!({"where": expr})).is_err(),
+                "{} should be rejected",
+                expr
+            );
+        }
+        assert!(
+            call(
+                &h,
+                "list_tasks",
+                json!({"where": "status=working", "as_of": "0"})
+            )
+            .is_err()
+        );
+        assert_eq!(titles(&h, json!({})).len(), 3);
+    }
+}
+
+mod get_include_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> anyhow::Result<String> {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        Ok(rt
+            .block_on(handler.call_tool(name, args, &ctx))?
+            .into_string())
+    }
+
+    fn call_json(handler: &ToolHandler, name: &str, args: Value) -> Value {
+        serde_json::from_str(&call(handler, name, args).unwrap()).unwrap()
+    }
+
+    fn create(h: &ToolHandler, args: Value) -> String {
+        call_json(h, "create", args)["id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Epic > Feature > Work, where Design blocks Work and Work blocks Release.
+    struct Graph {
+        handler: ToolHandler,
+        epic: String,
+        feature: String,
+        work: String,
+        design: String,
+        release: String,
+        leaf: String,
+    }
+
+    fn setup(dir: &std::path::Path) -> Graph {
+        let h = handler(setup_db(), dir);
+        call(&h, "connect", json!({"worker_id": "w1"})).unwrap();
+        let epic = create(&h, json!({"worker_id": "w1", "title": "Epic"}));
+        let feature = create(
+            &h,
+            json!({"worker_id": "w1", "title": "Feature", "parent": epic}),
+        );
+        let work = create(
+            &h,
+            json!({"worker_id": "w1", "title": "Work", "parent": feature}),
+        );
+        let leaf = create(
+            &h,
+            json!({"worker_id": "w1", "title": "Leaf", "parent": work}),
+        );
+        let design = create(&h, json!({"worker_id": "w1", "title": "Design"}));
+        let release = create(&h, json!({"worker_id": "w1", "title": "Release"}));
+        call(
+            &h,
+            "link",
+            json!({"from": design, "to": work, "type": "blocks"}),
+        )
+        .unwrap();
+        call(
+            &h,
+            "link",
+            json!({"from": work, "to": release, "type": "blocks"}),
+        )
+        .unwrap();
+        Graph {
+            handler: h,
+            epic,
+            feature,
+            work,
+            design,
+            release,
+            leaf,
+        }
+    }
+
+    fn ids(list: &Value) -> Vec<String> {
+        list.as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn expands_graph_neighborhood() {
+        let dir = tempfile::tempdir().unwrap();
+        let g = setup(dir.path());
+
+        let task = call_json(
+            &g.handler,
+            "get",
+            json!({"task": g.work, "include": ["children", "parents", "blockers", "blocking"]}),
+        );
+        assert_eq!(
+            ids(&task["parents"]),
+            vec![g.feature.clone(), g.epic.clone()]
+        );
+        assert_eq!(ids(&task["children"]), vec![g.leaf.clone()]);
+        assert_eq!(ids(&task["blockers"]), vec![g.design.clone()]);
+        assert_eq!(ids(&task["blocking"]), vec![g.release.clone()]);
+        assert_eq!(task["blockers"][0]["title"], json!("Design"));
+        assert_eq!(task["blockers"][0]["status"], json!("pending"));
+
+        // Nothing is expanded unless asked for
+        let task = call_json(&g.handler, "get", json!({"task": g.work}));
+        assert!(task.get("children").is_none());
+        assert!(task.get("recent_history").is_none());
+    }
+
+    #[test]
+    fn expands_attachments_and_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let g = setup(dir.path());
+        call(
+            &g.handler,
+            "attach",
+            json!({"agent": "w1", "task": g.work, "type": "note", "content": "remember the cache"}),
+        )
+        .unwrap();
+        call(
+            &g.handler,
+            "update",
+            json!({"worker_id": "w1", "task": g.work, "title": "Work harder"}),
+        )
+        .unwrap();
+
+        let task = call_json(
+            &g.handler,
+            "get",
+            json!({"task": g.work, "include": ["attachments", "recent_history"]}),
+        );
+        assert_eq!(
+            task["attachments"][0]["content"],
+            json!("remember the cache")
+        );
+        let changes = task["recent_history"]["changes"].as_array().unwrap();
+        assert_eq!(changes[0]["field"], json!("title"));
+        assert!(task["recent_history"]["events"].is_array());
+
+        let md = call(
+            &g.handler,
+            "get",
+            json!({"task": g.work, "format": "markdown", "include": ["attachments", "recent_history", "blockers"]}),
+        )
+        .unwrap();
+        assert!(md.contains("### Blockers"));
+        assert!(md.contains("Design"));
+        assert!(md.contains("### Recent History"));
+        assert!(md.contains("remember the cache"));
+    }
+
+    #[test]
+    fn rejects_unknown_expansions() {
+        let dir = tempfile::tempdir().unwrap();
+        let g = setup(dir.path());
+        assert!(
+            call(
+                &g.handler,
+                "get",
+                json!({"task": g.work, "include": ["siblings"]})
+            )
+            .is_err()
+        );
+        assert!(
+            call(
+                &g.handler,
+                "get",
+                json!({"task": g.work, "include": ["children"], "as_of": "0"})
+            )
+            .is_err()
+        );
+    }
+}
+
+mod outline_tree_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::tasks::create_tree;
+    use task_graph_mcp::types::Task;
+
+    /// Children of a task, ordered by title.
+    fn children(db: &Database, id: &str) -> Vec<Task> {
+        let mut tasks: Vec<Task> = db
+            .get_children_ids(id)
+            .unwrap()
+            .iter()
+            .map(|id| db.get_task(id).unwrap().unwrap())
+            .collect();
+        tasks.sort_by(|a, b| a.title.cmp(&b.title));
+        tasks
+    }
+
+    fn titles(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn builds_tree_from_markdown_outline() {
+        let db = setup_db();
+        let config = default_app_config();
+
+        let result = create_tree(
+            &db,
+            &config,
+            json!({"outline": "# Launch\n\
+                               - Docs #writing\n\
+                               \x20 - [ ] API guide !9\n\
+                               \x20   Cover the new endpoints.\n\
+                               - Release #ops !3\n"}),
+        )
+        .unwrap();
+        assert_eq!(result["count"], json!(4));
+        let root = result["root"]["id"].as_str().unwrap();
+        assert_eq!(result["root"]["title"], json!("Launch"));
+
+        let top = children(&db, root);
+        assert_eq!(titles(&top), vec!["Docs", "Release"]);
+        assert_eq!(top[0].tags, vec!["writing"]);
+        assert_eq!(top[1].priority, 3);
+
+        let guide = children(&db, &top[0].id).remove(0);
+        assert_eq!(guide.title, "API guide");
+        assert_eq!(guide.priority, 9);
+        assert_eq!(
+            guide.description.as_deref(),
+            Some("Cover the new endpoints.")
+        );
+    }
+
+    #[test]
+    fn builds_tree_from_yaml_outline() {
+        let db = setup_db();
+        let config = default_app_config();
+
+        let result = create_tree(
+            &db,
+            &config,
+            json!({
+                "outline_format": "yaml",
+                "outline": "Migration:\n  - title: Schema\n    points: 5\n  - Backfill\n",
+                "sibling_type": "follows"
+            }),
+        )
+        .unwrap();
+        let root = result["root"]["id"].as_str().unwrap();
+        let top = children(&db, root);
+        assert_eq!(titles(&top), vec!["Backfill", "Schema"]);
+        assert_eq!(top[1].points, Some(5));
+    }
+
+    #[test]
+    fn rejects_outlines_without_a_single_root() {
+        let db = setup_db();
+        let config = default_app_config();
+
+        let err = create_tree(&db, &config, json!({"outline": "- One\n- Two\n"})).unwrap_err();
+        assert!(err.to_string().contains("one root"));
+        assert!(
+            create_tree(
+                &db,
+                &config,
+                json!({"outline": "- One\n", "tree": {"title": "Also"}})
+            )
+            .is_err()
+        );
+        assert!(
+            create_tree(
+                &db,
+                &config,
+                json!({"outline": "- One\n", "outline_format": "toml"})
+            )
+            .is_err()
+        );
+        assert!(create_tree(&db, &config, json!({})).is_err());
+    }
+}
+
+mod source_scan_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use std::fs;
+    use std::path::Path;
+    use task_graph_mcp::tools::source::{FINGERPRINT_FIELD, LOCATION_FIELD, scan_source};
+
+    fn config(root: &Path) -> AppConfig {
+        AppConfig {
+            source_scan: Arc::new(SourceScanConfig {
+                root: Some(root.to_path_buf()),
+                ..Default::default()
+            }),
+            ..default_app_config()
+        }
+    }
+
+    fn ids(list: &Value) -> Vec<&str> {
+        list.as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().or_else(|| v["id"].as_str()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn creates_tagged_tasks_with_location() {
+        let db = setup_db();
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(
+            dir.path().join("src/lib.rs"),
+            "fn a() {}\n// TODO: cache results\nfn b() {} // FIXME(ana): overflow\n",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join("target/gen.rs"), "// TODO: generated\n").unwrap();
+        let config = config(dir.path());
+
+        let result = scan_source(&db, &config, json!({})).unwrap();
+        assert_eq!(result["found"], json!(2));
+        assert_eq!(result["files_scanned"], json!(1));
+        let created = result["created"].as_array().unwrap();
+        assert_eq!(created.len(), 2);
+
+        let id = created[0]["id"].as_str().unwrap();
+        let task = db.get_task(id).unwrap().unwrap();
+        assert_eq!(task.title, "TODO: cache results");
+        assert_eq!(task.tags, vec!["from-code"]);
+        assert!(task.description.unwrap().contains("src/lib.rs:2"));
+        let fields = db.get_task_fields(id).unwrap();
+        assert_eq!(fields[LOCATION_FIELD], json!("src/lib.rs:2"));
+        assert_eq!(fields[FINGERPRINT_FIELD], created[0]["fingerprint"]);
+        assert_eq!(created[1]["title"], json!("FIXME: overflow"));
+    }
+
+    #[test]
+    fn rescan_deduplicates_and_tracks_moves() {
+        let db = setup_db();
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("main.py");
+        fs::write(&file, "# TODO: retry\n").unwrap();
+        let config = config(dir.path());
+
+        let first = scan_source(&db, &config, json!({})).unwrap();
+        let id = ids(&first["created"])[0].to_string();
+
+        let again = scan_source(&db, &config, json!({})).unwrap();
+        assert!(ids(&again["created"]).is_empty());
+        assert_eq!(again["unchanged"], json!(1));
+
+        fs::write(&file, "import os\n\n# TODO: retry\n").unwrap();
+        let moved = scan_source(&db, &config, json!({})).unwrap();
+        assert_eq!(ids(&moved["updated"]), vec![id.as_str()]);
+        assert_eq!(
+            db.get_task_fields(&id).unwrap()[LOCATION_FIELD],
+            json!("main.py:3")
+        );
+        assert_eq!(db.get_all_tasks().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn resolve_completes_tasks_whose_comment_is_gone() {
+        let db = setup_db();
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.rs");
+        fs::write(&file, "// TODO: one\n// TODO: two\n").unwrap();
+        let config = config(dir.path());
+
+        let first = scan_source(&db, &config, json!({})).unwrap();
+        let created: Vec<String> = ids(&first["created"])
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        fs::write(&file, "// TODO: two\n").unwrap();
+        let report = scan_source(&db, &config, json!({})).unwrap();
+        assert_eq!(ids(&report["missing"]), vec![created[0].as_str()]);
+        assert_eq!(db.get_task(&created[0]).unwrap().unwrap().status, "pending");
+
+        let dry = scan_source(&db, &config, json!({"resolve": true, "dry_run": true})).unwrap();
+        assert_eq!(ids(&dry["resolved"]), vec![created[0].as_str()]);
+        assert_eq!(db.get_task(&created[0]).unwrap().unwrap().status, "pending");
+
+        // Pending tasks cannot complete directly, so they are cancelled
+        let resolved = scan_source(&db, &config, json!({"resolve": true})).unwrap();
+        assert_eq!(ids(&resolved["resolved"]), vec![created[0].as_str()]);
+        assert_eq!(resolved["resolved"][0]["status"], json!("cancelled"));
+        assert_eq!(
+            db.get_task(&created[0]).unwrap().unwrap().status,
+            "cancelled"
+        );
+
+        // A finished task is not reopened or duplicated if its comment returns
+        fs::write(&file, "// TODO: one\n// TODO: two\n").unwrap();
+        let back = scan_source(&db, &config, json!({})).unwrap();
+        assert_eq!(ids(&back["closed"]), vec![created[0].as_str()]);
+        assert!(ids(&back["created"]).is_empty());
+    }
+
+    #[test]
+    fn dirs_and_parent_arguments() {
+        let db = setup_db();
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("api")).unwrap();
+        fs::create_dir(dir.path().join("web")).unwrap();
+        fs::write(dir.path().join("api/a.go"), "// HACK: temp\n").unwrap();
+        fs::write(dir.path().join("web/b.js"), "// TODO: later\n").unwrap();
+        let config = config(dir.path());
+        let parent = db
+            .create_task_simple("Code debt", &config.states, &config.ids)
+            .unwrap();
+
+        let result = scan_source(
+            &db,
+            &config,
+            json!({"dirs": "api", "parent": parent.id, "resolve": true}),
+        )
+        .unwrap();
+        assert_eq!(result["found"], json!(1));
+        let id = ids(&result["created"])[0].to_string();
+        assert_eq!(db.get_children_ids(&parent.id).unwrap(), vec![id.clone()]);
+
+        // Scanning another directory leaves tasks from this one alone
+        let web = scan_source(&db, &config, json!({"dirs": ["web"], "resolve": true})).unwrap();
+        assert!(ids(&web["resolved"]).is_empty());
+        assert_eq!(db.get_task(&id).unwrap().unwrap().status, "pending");
+
+        assert!(scan_source(&db, &config, json!({"dirs": "missing"})).is_err());
+        assert!(scan_source(&db, &config, json!({"parent": "nope"})).is_err());
+    }
+}
+
+mod source_watch_tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use task_graph_mcp::source_scan::SourceScanner;
+    use task_graph_mcp::source_watch::rescan_files;
+    use task_graph_mcp::tools::source::LOCATION_FIELD;
+
+    fn config(root: &std::path::Path) -> AppConfig {
+        AppConfig {
+            source_scan: Arc::new(SourceScanConfig {
+                root: Some(root.to_path_buf()),
+                extensions: vec!["rs".to_string()],
+                ..Default::default()
+            }),
+            ..default_app_config()
+        }
+    }
+
+    #[test]
+    fn rescans_only_changed_files() {
+        let db = setup_db();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let a = root.join("a.rs");
+        let b = root.join("b.rs");
+        fs::write(&a, "// TODO: in a\n").unwrap();
+        fs::write(&b, "// TODO: in b\n").unwrap();
+        let config = config(&root);
+        let scanner = SourceScanner::new(&config.source_scan, root.clone()).unwrap();
+        let dirs = vec![root.clone()];
+
+        let sync = rescan_files(&db, &config, &scanner, &dirs, std::slice::from_ref(&a)).unwrap();
+        assert!(sync.changed());
+        assert_eq!(sync.created.len(), 1);
+        assert_eq!(sync.created[0]["title"], "TODO: in a");
+        let id = sync.created[0]["id"].as_str().unwrap().to_string();
+
+        // Moving the TODO updates its location
+        fs::write(&a, "\n// TODO: in a\n").unwrap();
+        let sync = rescan_files(&db, &config, &scanner, &dirs, std::slice::from_ref(&a)).unwrap();
+        assert_eq!(sync.updated.len(), 1);
+        assert_eq!(
+            db.get_task_fields(&id).unwrap()[LOCATION_FIELD],
+            serde_json::json!("a.rs:2")
+        );
+
+        // Unchanged content is a no-op
+        let sync = rescan_files(&db, &config, &scanner, &dirs, std::slice::from_ref(&a)).unwrap();
+        assert!(!sync.changed());
+        assert_eq!(sync.unchanged, 1);
+    }
+
+    #[test]
+    fn removed_todos_and_deleted_files_resolve_tasks() {
+        let db = setup_db();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let a = root.join("a.rs");
+        let b = root.join("b.rs");
+        fs::write(&a, "// TODO: one\n// FIXME: two\n").unwrap();
+        fs::write(&b, "// HACK: three\n").unwrap();
+        let config = config(&root);
+        let scanner = SourceScanner::new(&config.source_scan, root.clone()).unwrap();
+        let dirs = vec![root.clone()];
+
+        let sync = rescan_files(&db, &config, &scanner, &dirs, &[a.clone(), b.clone()]).unwrap();
+        assert_eq!(sync.created.len(), 3);
+        let id_of = |title: &str| {
+            sync.created.iter().find(|c| c["title"] == title).unwrap()["id"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+        let (one, two, three) = (
+            id_of("TODO: one"),
+            id_of("FIXME: two"),
+            id_of("HACK: three"),
+        );
+
+        fs::write(&a, "// FIXME: two\n").unwrap();
+        fs::remove_file(&b).unwrap();
+        let sync = rescan_files(&db, &config, &scanner, &dirs, &[a.clone(), b.clone()]).unwrap();
+        let resolved: Vec<&str> = sync
+            .resolved
+            .iter()
+            .map(|r| r["id"].as_str().unwrap())
+            .collect();
+        let mut expected = vec![one.as_str(), three.as_str()];
+        expected.sort();
+        assert_eq!(resolved, expected);
+        assert_eq!(db.get_task(&two).unwrap().unwrap().status, "pending");
+        assert_ne!(db.get_task(&one).unwrap().unwrap().status, "pending");
+    }
+
+    #[test]
+    fn ignores_files_outside_dirs_or_filters() {
+        let db = setup_db();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        fs::create_dir(root.join("src")).unwrap();
+        let outside = root.join("build.rs");
+        let filtered = root.join("src/notes.md");
+        fs::write(&outside, "// TODO: outside\n").unwrap();
+        fs::write(&filtered, "<!-- TODO: markdown -->\n").unwrap();
+        let config = config(&root);
+        let scanner = SourceScanner::new(&config.source_scan, root.clone()).unwrap();
+        let dirs: Vec<PathBuf> = vec![root.join("src")];
+
+        let sync = rescan_files(&db, &config, &scanner, &dirs, &[outside, filtered]).unwrap();
+        assert!(!sync.changed());
+        assert!(db.get_all_tasks().unwrap().is_empty());
+    }
+}
+
+mod checklist_import_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::config::PrioritiesConfig;
+    use task_graph_mcp::db::import::{ImportOptions, remap_snapshot_with};
+    use task_graph_mcp::export::checklist::{
+        PLACEHOLDER_PREFIX, checklist_snapshot, skip_existing,
+    };
+    use task_graph_mcp::format::ToolResult;
+    use task_graph_mcp::tools::tasks::export_markdown;
+
+    const PLAN: &str = "# Release\n\
+                        - [x] Changelog\n\
+                        - Docs\n\
+                        \x20 - [x] Upgrade guide\n\
+                        \x20 - [ ] Screenshots #docs\n";
+
+    fn import(db: &Database, parent: Option<&str>) {
+        import_markdown(db, PLAN, parent);
+    }
+
+    fn import_markdown(db: &Database, markdown: &str, parent: Option<&str>) {
+        let mut snapshot = checklist_snapshot(
+            markdown,
+            &default_states_config(),
+            &PrioritiesConfig::default(),
+        )
+        .unwrap();
+        skip_existing(&mut snapshot, |id| db.task_exists(id)).unwrap();
+        let mut options = ImportOptions::merge().with_remap_ids();
+        if let Some(parent) = parent {
+            options = options.with_parent(parent.to_string());
+        }
+        db.import_snapshot_remapped(&snapshot, &options, &default_ids_config(), |s, next| {
+            remap_snapshot_with(s, next)
+        })
+        .unwrap();
+    }
+
+    fn child(db: &Database, parent: &str, title: &str) -> task_graph_mcp::types::Task {
+        db.get_children_ids(parent)
+            .unwrap()
+            .iter()
+            .map(|id| db.get_task(id).unwrap().unwrap())
+            .find(|t| t.title == title)
+            .unwrap()
+    }
+
+    #[test]
+    fn imports_checklist_as_task_tree() {
+        let db = setup_db();
+        import(&db, None);
+
+        let tasks = db.get_all_tasks().unwrap();
+        assert_eq!(tasks.len(), 5);
+        assert!(tasks.iter().all(|t| !t.id.starts_with(PLACEHOLDER_PREFIX)));
+
+        let release = tasks.iter().find(|t| t.title == "Release").unwrap();
+        assert_eq!(release.status, "pending");
+        assert_eq!(child(&db, &release.id, "Changelog").status, "completed");
+        let docs = child(&db, &release.id, "Docs");
+        assert_eq!(docs.status, "pending");
+        assert_eq!(child(&db, &docs.id, "Upgrade guide").status, "completed");
+        let screenshots = child(&db, &docs.id, "Screenshots");
+        assert_eq!(screenshots.status, "pending");
+        assert_eq!(screenshots.tags, vec!["docs"]);
+    }
+
+    #[test]
+    fn imports_twice_under_a_parent_without_clashing() {
+        let db = setup_db();
+        let states = default_states_config();
+        let ids = default_ids_config();
+        let parent = db
+            .create_task(
+                None,
+                "Plans".to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &states,
+                &ids,
+            )
+            .unwrap();
+
+        import(&db, Some(&parent.id));
+        import(&db, Some(&parent.id));
+
+        let roots = db.get_children_ids(&parent.id).unwrap();
+        assert_eq!(roots.len(), 2);
+        assert_eq!(db.get_all_tasks().unwrap().len(), 11);
+    }
+
+    #[test]
+    fn exported_checklist_round_trips() {
+        let db = setup_db();
+        import(&db, None);
+        let release = db
+            .get_all_tasks()
+            .unwrap()
+            .into_iter()
+            .find(|t| t.title == "Release")
+            .unwrap();
+
+        let ToolResult::Raw(markdown) = export_markdown(
+            &db,
+            &default_states_config(),
+            &VisibilityConfig::default(),
+            json!({ "task": release.id }),
+        )
+        .unwrap() else {
+            panic!("expected markdown");
+        };
+        let changelog = child(&db, &release.id, "Changelog");
+        assert!(markdown.starts_with(&format!("- [ ] Release <!-- id: {} -->\n", release.id)));
+        assert!(markdown.contains(&format!(
+            "  - [x] Changelog <!-- id: {} -->\n",
+            changelog.id
+        )));
+
+        // Add an item under Docs and import the list back
+        let edited: String = markdown
+            .lines()
+            .flat_map(|line| {
+                let extra = line.contains("Screenshots").then_some("    - [ ] Diagrams");
+                std::iter::once(line).chain(extra)
+            })
+            .map(|line| format!("{}\n", line))
+            .collect();
+        import_markdown(&db, &edited, None);
+        assert_eq!(db.get_all_tasks().unwrap().len(), 6);
+        let docs = child(&db, &release.id, "Docs");
+        assert_eq!(child(&db, &docs.id, "Diagrams").status, "pending");
+        assert_eq!(db.get_children_ids(&release.id).unwrap().len(), 2);
+
+        assert!(
+            export_markdown(
+                &db,
+                &default_states_config(),
+                &VisibilityConfig::default(),
+                json!({ "task": "nope" })
+            )
+            .is_err()
+        );
+    }
+}
+
+mod trends_tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60 * 1000;
+
+    fn task(db: &Database, title: &str, points: i32) -> String {
+        db.create_task(
+            None,
+            title.to_string(),
+            None,
+            None,
+            None,
+            None,
+            Some(points),
+            None,
+            None,
+            None,
+            None,
+            &default_states_config(),
+            &default_ids_config(),
+        )
+        .unwrap()
+        .id
+    }
+
+    /// Move every task's creation (and its initial status event) back in time.
+    fn backdate(db: &Database, days: i64) {
+        let at = task_graph_mcp::db::now_ms() - days * DAY;
+        db.with_conn(|conn| {
+            conn.execute("UPDATE tasks SET created_at = ?1", [at])?;
+            conn.execute("UPDATE task_sequence SET timestamp = ?1", [at])?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    fn transition(db: &Database, task_id: &str, worker: &str, status: &str, days_ago: f64) {
+        let at = task_graph_mcp::db::now_ms() - (days_ago * DAY as f64) as i64;
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO task_sequence (task_id, worker_id, status, timestamp)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![task_id, worker, status, at],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn replays_status_history_per_period() {
+        let db = setup_db();
+        let small = task(&db, "Small", 3);
+        let large = task(&db, "Large", 5);
+        backdate(&db, 3);
+        transition(&db, &small, "w1", "working", 1.8);
+        transition(&db, &small, "w1", "completed", 1.5);
+        transition(&db, &large, "w2", "working", 0.5);
+
+        let trends = db.get_trends("day", 3, &default_states_config()).unwrap();
+
+        assert_eq!(trends.period, "day");
+        let counts: Vec<(i64, i64, i64)> = trends
+            .points
+            .iter()
+            .map(|p| (p.open, p.closed, p.open_points))
+            .collect();
+        assert_eq!(counts, vec![(2, 0, 8), (1, 1, 5), (1, 1, 5)]);
+        assert_eq!(trends.points[2].label, "Today");
+
+        assert_eq!(trends.agents.len(), 1);
+        assert_eq!(trends.agents[0].worker_id, "w1");
+        assert_eq!(trends.agents[0].completed, vec![0, 1, 0]);
+        assert_eq!(trends.agents[0].total, 1);
+    }
+
+    #[test]
+    fn excludes_tasks_before_creation_and_after_deletion() {
+        let db = setup_db();
+        let gone = task(&db, "Gone", 2);
+        backdate(&db, 3);
+        task(&db, "New", 1);
+        db.delete_task(&gone, "w1", false, None, false, false)
+            .unwrap();
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE tasks SET deleted_at = ?1 WHERE id = ?2",
+                rusqlite::params![task_graph_mcp::db::now_ms() - DAY / 2, gone],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        let trends = db.get_trends("day", 3, &default_states_config()).unwrap();
+        let open: Vec<i64> = trends.points.iter().map(|p| p.open).collect();
+
+        // "Gone" is open until deleted; "New" only shows up in today's sample
+        assert_eq!(open, vec![1, 1, 1]);
+        assert_eq!(trends.points[2].open_points, 1);
+        assert!(trends.agents.is_empty());
+    }
+}
+
+mod agent_presence_tests {
+    use super::*;
+
+    #[test]
+    fn lists_workers_with_claims_longest_silent_first() {
+        let db = setup_db();
+        for id in ["fresh", "quiet"] {
+            db.register_worker(
+                Some(id.to_string()),
+                vec![],
+                false,
+                &default_ids_config(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        }
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE workers SET last_heartbeat = last_heartbeat - 600000 WHERE id = 'quiet'",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        let task = db
+            .create_task(
+                None,
+                "Claimed".to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &default_states_config(),
+                &default_ids_config(),
+            )
+            .unwrap();
+        db.claim_task(&task.id, "fresh", &default_states_config())
+            .unwrap();
+        db.set_thought("fresh", Some("Halfway".to_string()), None)
+            .unwrap();
+
+        let presence = db.get_agent_presence().unwrap();
+        let ids: Vec<&str> = presence.iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(ids, vec!["quiet", "fresh"]);
+        assert!(presence[0].claims.is_empty());
+        assert_eq!(presence[1].claims.len(), 1);
+        assert_eq!(presence[1].claims[0].id, task.id);
+        assert_eq!(presence[1].current_thought.as_deref(), Some("Halfway"));
+    }
+}
+
+mod snapshot_round_trip_tests {
+    use super::*;
+    use task_graph_mcp::db::export::ExportOptions;
+    use task_graph_mcp::db::import::ImportOptions;
+    use task_graph_mcp::export::Snapshot;
+
+    #[test]
+    fn exported_snapshot_imports_into_fresh_database() {
+        let source = setup_db();
+        for (title, priority) in [("Default", None), ("Urgent", Some(8))] {
+            source
+                .create_task(
+                    None,
+                    title.to_string(),
+                    None,
+                    None,
+                    None,
+                    priority,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    &default_states_config(),
+                    &default_ids_config(),
+                )
+                .unwrap();
+        }
+
+        let tables = source.export_tables(&ExportOptions::default()).unwrap();
+        let bytes = Snapshot::from_tables(tables)
+            .unwrap()
+            .to_json_gzip()
+            .unwrap();
+        let snapshot = Snapshot::from_bytes(&bytes).unwrap();
+
+        let target = setup_db();
+        target
+            .import_snapshot(&snapshot, &ImportOptions::fresh())
+            .unwrap();
+
+        let mut priorities: Vec<(String, i32)> = target
+            .get_all_tasks()
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.title, t.priority))
+            .collect();
+        priorities.sort();
+        assert_eq!(
+            priorities,
+            vec![
+                ("Default".to_string(), PRIORITY_DEFAULT),
+                ("Urgent".to_string(), 8)
+            ]
+        );
+    }
+}
+
+mod search_filter_tests {
+    use super::*;
+    use task_graph_mcp::db::search::SearchFilters;
+
+    fn task(db: &Database, title: &str, tags: Vec<&str>) -> String {
+        db.create_task(
+            None,
+            title.to_string(),
+            Some("Parser cleanup".to_string()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(tags.into_iter().map(String::from).collect()),
+            &default_states_config(),
+            &default_ids_config(),
+        )
+        .unwrap()
+        .id
+    }
+
+    fn ids(db: &Database, filters: &SearchFilters<'_>) -> Vec<String> {
+        let mut ids: Vec<String> = db
+            .search_tasks_filtered("parser", None, 0, false, filters, None)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.task_id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn narrows_by_status_tag_and_owner() {
+        let db = setup_db();
+        db.register_worker(
+            Some("agent-a".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        let backend = task(&db, "Backend parser", vec!["backend"]);
+        let frontend = task(&db, "Frontend parser", vec!["frontend"]);
+        db.claim_task(&frontend, "agent-a", &default_states_config())
+            .unwrap();
+
+        let mut all = vec![backend.clone(), frontend.clone()];
+        all.sort();
+        assert_eq!(ids(&db, &SearchFilters::default()), all);
+        assert_eq!(
+            ids(
+                &db,
+                &SearchFilters {
+                    tag: Some("backend"),
+                    ..Default::default()
+                }
+            ),
+            vec![backend.clone()]
+        );
+        assert_eq!(
+            ids(
+                &db,
+                &SearchFilters {
+                    owner: Some("agent-a"),
+                    ..Default::default()
+                }
+            ),
+            vec![frontend.clone()]
+        );
+        assert_eq!(
+            ids(
+                &db,
+                &SearchFilters {
+                    status: Some("pending"),
+                    tag: Some("frontend"),
+                    owner: None,
+                }
+            ),
+            Vec::<String>::new()
+        );
+    }
+}
+
+mod reader_pool_tests {
+    use super::*;
+
+    #[test]
+    fn readers_see_committed_writes_and_reject_writes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(dir.path().join("tasks.db")).unwrap();
+
+        let task = db
+            .create_task(
+                None,
+                "Visible to readers".to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &default_states_config(),
+                &default_ids_config(),
+            )
+            .unwrap();
+        assert!(db.task_exists(&task.id).unwrap());
+
+        let write = db.with_read_conn(|conn| {
+            conn.execute("DELETE FROM tasks", [])?;
+            Ok(())
+        });
+        assert!(write.is_err());
+        assert!(db.get_task(&task.id).unwrap().is_some());
+    }
+
+    #[test]
+    fn reads_proceed_while_writer_is_busy() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(dir.path().join("tasks.db")).unwrap();
+
+        // Hold the writer inside an open write transaction and read from a reader
+        db.with_conn_mut(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT INTO workers (id, tags, registered_at, last_heartbeat) VALUES ('w1', '[]', 0, 0)",
+                [],
+            )?;
+            let uncommitted = db
+                .with_read_conn(|reader| {
+                    Ok(reader.query_row("SELECT COUNT(*) FROM workers", [], |row| {
+                        row.get::<_, i64>(0)
+                    })?)
+                })
+                .unwrap();
+            assert_eq!(uncommitted, 0);
+            tx.commit()?;
+            Ok(())
+        })
+        .unwrap();
+
+        let committed = db
+            .with_read_conn(|reader| {
+                Ok(reader.query_row("SELECT COUNT(*) FROM workers", [], |row| {
+                    row.get::<_, i64>(0)
+                })?)
+            })
+            .unwrap();
+        assert_eq!(committed, 1);
+    }
+}
+
+mod bulk_insert_tests {
+    use super::*;
+    use task_graph_mcp::config::IdScheme;
+    use task_graph_mcp::db::export::ExportOptions;
+    use task_graph_mcp::db::import::ImportOptions;
+    use task_graph_mcp::db::tasks::CreateTreeOptions;
+    use task_graph_mcp::export::Snapshot;
+    use task_graph_mcp::types::TaskTreeInput;
+
+    /// A root with 20 groups of 50 tagged leaves, chained by `follows`.
+    fn wide_tree() -> TaskTreeInput {
+        let groups: Vec<_> = (0..20)
+            .map(|g| {
+                let leaves: Vec<_> = (0..50)
+                    .map(|l| {
+                        serde_json::json!({
+                            "title": format!("Leaf {}-{}", g, l),
+                            "tags": ["bulk", "leaf"]
+                        })
+                    })
+                    .collect();
+                serde_json::json!({ "title": format!("Group {}", g), "children": leaves })
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({ "title": "Root", "children": groups })).unwrap()
+    }
+
+    fn create_wide_tree(db: &Database, ids: IdsConfig) -> Vec<String> {
+        let config = AppConfig {
+            ids: Arc::new(ids),
+            ..default_app_config()
+        };
+        let (_, all_ids, _, _) = db
+            .create_task_tree(CreateTreeOptions {
+                input: wide_tree(),
+                parent_id: None,
+                child_type: None,
+                sibling_type: Some("follows".to_string()),
+                states_config: &config.states,
+                phases_config: &config.phases,
+                tags_config: &config.tags,
+                ids_config: &config.ids,
+            })
+            .unwrap();
+        all_ids
+    }
+
+    fn count(db: &Database, table: &str) -> i64 {
+        db.with_read_conn(|conn| {
+            Ok(
+                conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                    row.get(0)
+                })?,
+            )
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn thousand_node_tree_creates_and_imports() {
+        let source = setup_db();
+        let all_ids = create_wide_tree(
+            &source,
+            IdsConfig {
+                task_id_scheme: IdScheme::Sequential,
+                ..IdsConfig::default()
+            },
+        );
+        assert_eq!(all_ids.len(), 1021);
+        assert_eq!(all_ids.last().map(String::as_str), Some("T-1021"));
+        assert_eq!(count(&source, "tasks"), 1021);
+        assert_eq!(count(&source, "task_tags"), 2000);
+        // 1020 `contains` edges plus 19 + 20 * 49 `follows` edges between siblings
+        assert_eq!(count(&source, "dependencies"), 1020 + 19 + 20 * 49);
+
+        let tables = source.export_tables(&ExportOptions::default()).unwrap();
+        let snapshot = Snapshot::from_tables(tables).unwrap();
+        let target = setup_db();
+        target
+            .import_snapshot(&snapshot, &ImportOptions::fresh())
+            .unwrap();
+        let result = target
+            .import_snapshot(&snapshot, &ImportOptions::merge())
+            .unwrap();
+        assert_eq!(result.rows_skipped.get("tasks"), Some(&1021));
+        for table in ["tasks", "task_tags", "dependencies", "task_sequence"] {
+            assert_eq!(count(&target, table), count(&source, table), "{}", table);
+        }
+        assert_eq!(
+            target
+                .search_tasks("Leaf", Some(5), 0, false, None, None)
+                .unwrap()
+                .len(),
+            5
+        );
+    }
+}
+
+mod query_plan_tests {
+    use super::*;
+
+    #[test]
+    fn hot_queries_use_indexes() {
+        let db = setup_db();
+        for report in db.audit_query_plans().unwrap() {
+            assert!(report.is_ok(), "{}: {:?}", report.name, report.plan);
+        }
+    }
+}
+
+mod aggregate_cache_tests {
+    use super::*;
+    use std::cell::Cell;
+    use task_graph_mcp::subscriptions::MutationKind;
+
+    const DEPS: &[MutationKind] = &[MutationKind::TaskChanged];
+
+    fn task_count(db: &Database, computed: &Cell<u32>) -> i64 {
+        db.cached("test://count", DEPS, || {
+            computed.set(computed.get() + 1);
+            Ok(db.get_task_stats()?.0)
+        })
+        .unwrap()
+    }
+
+    fn create(db: &Database, title: &str) {
+        db.create_task(
+            None,
+            title.to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &default_states_config(),
+            &default_ids_config(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn reuses_results_until_a_dependent_mutation() {
+        let db = setup_db();
+        let computed = Cell::new(0);
+
+        assert_eq!(task_count(&db, &computed), 0);
+        assert_eq!(task_count(&db, &computed), 0);
+        assert_eq!(computed.get(), 1);
+
+        // Unrelated mutations keep the entry
+        db.aggregates().invalidate(&[MutationKind::FileMarkChanged]);
+        task_count(&db, &computed);
+        assert_eq!(computed.get(), 1);
+
+        db.aggregates().invalidate(&[MutationKind::TaskChanged]);
+        task_count(&db, &computed);
+        assert_eq!(computed.get(), 2);
+
+        db.aggregates().clear();
+        task_count(&db, &computed);
+        assert_eq!(computed.get(), 3);
+    }
+
+    #[test]
+    fn writes_without_events_invalidate_through_the_change_feed() {
+        let db = setup_db();
+        let computed = Cell::new(0);
+        assert_eq!(task_count(&db, &computed), 0);
+
+        // No event is reported, as for another process writing to the database
+        create(&db, "Written elsewhere");
+        assert_eq!(task_count(&db, &computed), 1);
+        assert_eq!(computed.get(), 2);
+    }
+}
+
+mod task_closure_tests {
+    use super::*;
+
+    fn create(db: &Database, id: &str) {
+        db.create_task(
+            Some(id.to_string()),
+            id.to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &default_states_config(),
+            &default_ids_config(),
+        )
+        .unwrap();
+    }
+
+    fn contain(db: &Database, parent: &str, child: &str) {
+        db.add_dependency(parent, child, "contains", &default_deps_config())
+            .unwrap();
+    }
+
+    fn closure(db: &Database) -> Vec<(String, String, i64)> {
+        db.with_read_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT ancestor_id, descendant_id, depth FROM task_closure
+                 ORDER BY ancestor_id, descendant_id",
+            )?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })
+        .unwrap()
+    }
+
+    /// The trigger-maintained closure matches one recomputed from the edges.
+    fn assert_consistent(db: &Database) {
+        let maintained = closure(db);
+        db.rebuild_task_closure().unwrap();
+        assert_eq!(maintained, closure(db));
+    }
+
+    fn ids(tasks: Vec<task_graph_mcp::types::Task>) -> Vec<String> {
+        tasks.into_iter().map(|t| t.id).collect()
+    }
+
+    /// root > a > b > c, root > d
+    fn tree(db: &Database) {
+        for id in ["root", "a", "b", "c", "d"] {
+            create(db, id);
+        }
+        contain(db, "a", "b");
+        contain(db, "b", "c");
+        contain(db, "root", "a");
+        contain(db, "root", "d");
+    }
+
+    #[test]
+    fn links_maintain_ancestor_and_descendant_pairs() {
+        let db = setup_db();
+        tree(&db);
+        assert_consistent(&db);
+
+        assert_eq!(ids(db.get_ancestors("c", -1).unwrap()), ["b", "a", "root"]);
+        assert_eq!(ids(db.get_ancestors("c", 2).unwrap()), ["b", "a"]);
+        assert_eq!(
+            ids(db.get_descendants("root", -1).unwrap()),
+            ["a", "d", "b", "c"]
+        );
+        assert_eq!(ids(db.get_descendants("root", 1).unwrap()), ["a", "d"]);
+
+        let tree = db.get_task_tree("root").unwrap().unwrap();
+        assert_eq!(tree.children.len(), 2);
+        assert_eq!(tree.children[0].task.id, "a");
+        assert_eq!(tree.children[0].children[0].children[0].task.id, "c");
+    }
+
+    #[test]
+    fn unlink_move_and_rename_keep_the_closure_consistent() {
+        let db = setup_db();
+        tree(&db);
+
+        // Move b (with c) from a to d
+        db.relink(
+            &["a".to_string()],
+            &["b".to_string()],
+            &["d".to_string()],
+            &["b".to_string()],
+            "contains",
+            &default_deps_config(),
+        )
+        .unwrap();
+        assert_consistent(&db);
+        assert_eq!(ids(db.get_ancestors("c", -1).unwrap()), ["b", "d", "root"]);
+
+        db.rename_task("d", "d2").unwrap();
+        assert_consistent(&db);
+        assert_eq!(ids(db.get_ancestors("c", -1).unwrap()), ["b", "d2", "root"]);
+
+        db.remove_dependency("root", "d2", "contains").unwrap();
+        assert_consistent(&db);
+        assert_eq!(ids(db.get_ancestors("c", -1).unwrap()), ["b", "d2"]);
+        assert_eq!(ids(db.get_descendants("root", -1).unwrap()), ["a"]);
+    }
+
+    #[test]
+    fn cascading_delete_uses_and_clears_the_closure() {
+        let db = setup_db();
+        tree(&db);
+
+        db.delete_task("a", "tester", true, None, true, true)
+            .unwrap();
+        assert!(db.get_task("c").unwrap().is_none());
+        assert!(db.get_task("d").unwrap().is_some());
+        assert_consistent(&db);
+        assert_eq!(closure(&db), vec![("root".to_string(), "d".to_string(), 1)]);
+    }
+}
+
+mod tools_config_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn handler(tools: ToolsConfig, dir: &std::path::Path) -> ToolHandler {
+        let config = AppConfig {
+            tools: Arc::new(tools),
+            ..default_app_config()
+        };
+        handler_with_config(setup_db(), config, dir)
+    }
+
+    fn tool_names(handler: &ToolHandler) -> Vec<String> {
+        handler
+            .get_tools()
+            .into_iter()
+            .map(|t| t.name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn disabled_tools_are_hidden_and_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(
+            ToolsConfig {
+                enabled: vec![],
+                disabled: vec!["query".into(), "delete".into()],
+                ..Default::default()
+            },
+            dir.path(),
+        );
+
+        let names = tool_names(&handler);
+        assert!(!names.contains(&"query".to_string()));
+        assert!(!names.contains(&"delete".to_string()));
+        assert!(names.contains(&"create".to_string()));
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let ctx = ToolContext::new(Logger::new());
+        let err = rt
+            .block_on(handler.call_tool("query", json!({"sql": "SELECT 1"}), &ctx))
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown tool: query"), "{}", err);
+    }
+
+    #[test]
+    fn enabled_list_is_an_allowlist() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(
+            ToolsConfig {
+                enabled: vec!["connect".into(), "list_tasks".into(), "claim".into()],
+                disabled: vec!["claim".into()],
+                ..Default::default()
+            },
+            dir.path(),
+        );
+
+        let mut names = tool_names(&handler);
+        names.sort();
+        assert_eq!(names, vec!["connect", "list_tasks"]);
+    }
+
+    #[test]
+    fn unknown_tool_names_fail_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(ToolsConfig::default(), dir.path());
+        let known = handler.tool_names();
+        // Feedback tools are known even while feedback is off
+        assert!(known.contains(&"give_feedback".to_string()));
+
+        let tools = ToolsConfig {
+            enabled: vec![],
+            disabled: vec!["qurey".into()],
+            ..Default::default()
+        };
+        let err = tools.validate(&known).unwrap_err().to_string();
+        assert_eq!(err, "tools.disabled: unknown tool 'qurey'");
+
+        let tools = ToolsConfig {
+            prefix: "tg.".into(),
+            ..Default::default()
+        };
+        let err = tools.validate(&known).unwrap_err().to_string();
+        assert!(err.starts_with("tools.prefix: "), "{}", err);
+    }
+
+    #[test]
+    fn prefix_renames_exposed_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(
+            ToolsConfig {
+                disabled: vec!["query".into()],
+                prefix: "tg_".into(),
+                ..Default::default()
+            },
+            dir.path(),
+        );
+
+        let names = tool_names(&handler);
+        assert!(names.iter().all(|n| n.starts_with("tg_")), "{:?}", names);
+        assert!(names.contains(&"tg_connect".to_string()));
+        assert!(!names.contains(&"tg_query".to_string()));
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let ctx = ToolContext::new(Logger::new());
+        rt.block_on(handler.call_tool("tg_connect", json!({}), &ctx))
+            .unwrap();
+
+        // Unprefixed names are not routed, and the prefix does not bypass the denylist
+        for name in ["connect", "tg_query"] {
+            let err = rt
+                .block_on(handler.call_tool(name, json!({}), &ctx))
+                .unwrap_err();
+            assert!(err.to_string().contains("Unknown tool"), "{}", err);
+        }
+    }
+}
+
+mod skill_tool_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::skills;
+
+    #[test]
+    fn get_skill_expands_templates_for_worker() {
+        let dir = tempfile::tempdir().unwrap();
+        let skill_dir = dir.path().join("onboarding");
+        std::fs::create_dir_all(&skill_dir).unwrap();
+        std::fs::write(
+            skill_dir.join("SKILL.md"),
+            "---\ndescription: Worker onboarding\n---\nHello {{agent_id}} ({{agent_tags}}).\nCurrent task: {{task_title}}\nClaims:\n{{agent_claims}}\n",
+        )
+        .unwrap();
+
+        let db = setup_db();
+        let config = default_app_config();
+        let agent = db
+            .register_worker(
+                Some("onboard-me".into()),
+                vec!["rust".into()],
+                false,
+                &default_ids_config(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        let task = db
+            .create_task(
+                None,
+                "Fix the parser".to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &config.states,
+                &default_ids_config(),
+            )
+            .unwrap();
+        db.claim_task(&task.id, &agent.id, &config.states).unwrap();
+
+        let call = |args| {
+            skills::call_tool(
+                &db,
+                &config,
+                &config.workflows,
+                dir.path(),
+                "get_skill",
+                &args,
+            )
+        };
+
+        // Without a worker the content is returned as written
+        let raw = call(json!({"name": "onboarding"})).unwrap();
+        assert!(raw["content"].as_str().unwrap().contains("{{agent_id}}"));
+
+        let expanded = call(json!({"name": "onboarding", "worker_id": "onboard-me"})).unwrap();
+        let content = expanded["content"].as_str().unwrap();
+        assert!(content.contains("Hello onboard-me (rust)."), "{}", content);
+        assert!(
+            content.contains("Current task: Fix the parser"),
+            "{}",
+            content
+        );
+        assert!(
+            content.contains(&format!("- `{}` Fix the parser (working)", task.id)),
+            "{}",
+            content
+        );
+
+        let err = call(json!({"name": "onboarding", "worker_id": "nobody"})).unwrap_err();
+        assert!(err.to_string().contains("nobody"), "{}", err);
+    }
+}
+
+mod reminder_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::config::workflows::Reminder;
+    use task_graph_mcp::tools::tracking::thinking;
+
+    fn workflows_with(reminders: Vec<Reminder>) -> WorkflowsConfig {
+        WorkflowsConfig {
+            reminders,
+            ..Default::default()
+        }
+    }
+
+    fn reminder(name: &str, every_minutes: u32, states: &[&str]) -> Reminder {
+        Reminder {
+            name: name.to_string(),
+            every_minutes,
+            states: states.iter().map(|s| s.to_string()).collect(),
+            prompt: format!("{} for {{{{task_id}}}}", name),
+        }
+    }
+
+    fn beat(db: &Database, config: &AppConfig, workflows: &WorkflowsConfig) -> Value {
+        thinking(
+            db,
+            config,
+            workflows,
+            json!({"agent": "worker", "thought": "busy"}),
+        )
+        .unwrap()
+    }
+
+    /// Have `worker` claim a task `minutes_ago`.
+    fn claim(db: &Database, minutes_ago: i64) -> String {
+        let states = default_states_config();
+        let ids = default_ids_config();
+        let task = db
+            .create_task(
+                None,
+                "Long task".to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &states,
+                &ids,
+            )
+            .unwrap();
+        db.claim_task(&task.id, "worker", &states).unwrap();
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE tasks SET claimed_at = claimed_at - ?1 WHERE id = ?2",
+                rusqlite::params![minutes_ago * 60_000, task.id],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        task.id
+    }
+
+    #[test]
+    fn reminder_is_delivered_once_per_interval() {
+        let db = setup_db();
+        let config = default_app_config();
+        let workflows = workflows_with(vec![reminder("update", 30, &[])]);
+
+        // Nothing claimed: nothing to remind about
+        register(&db, "worker");
+        assert!(beat(&db, &config, &workflows).get("reminders").is_none());
+
+        let task = claim(&db, 31);
+        let response = beat(&db, &config, &workflows);
+        assert_eq!(
+            response["reminders"],
+            json!([{"name": "update", "prompt": format!("update for {}", task)}])
+        );
+
+        // Not repeated until another interval has passed
+        assert!(beat(&db, &config, &workflows).get("reminders").is_none());
+    }
+
+    #[test]
+    fn reminder_waits_for_interval_and_matching_state() {
+        let db = setup_db();
+        let config = default_app_config();
+        register(&db, "worker");
+        claim(&db, 10);
+
+        let workflows = workflows_with(vec![
+            reminder("early", 30, &[]),
+            reminder("elsewhere", 0, &["pending"]),
+            reminder("now", 0, &["working"]),
+        ]);
+        let response = beat(&db, &config, &workflows);
+        let names: Vec<&str> = response["reminders"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["now"]);
+    }
+}
+
+mod limits_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::tasks::{
+        UpdateOptions, create, create_tree, get, list_tasks, update,
+    };
+
+    fn limited_config() -> AppConfig {
+        let mut config = default_app_config();
+        config.limits = Arc::new(LimitsConfig {
+            max_title_chars: Some(20),
+            max_description_chars: Some(100),
+            description_preview_chars: 10,
+        });
+        config
+    }
+
+    #[test]
+    fn oversized_text_is_rejected_on_write() {
+        let db = setup_db();
+        let config = limited_config();
+
+        let err = create(&db, &config, json!({"title": "x".repeat(21)}))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("title is 21 characters; the limit is 20"),
+            "{}",
+            err
+        );
+        assert!(
+            create(
+                &db,
+                &config,
+                json!({"title": "Ok", "description": "x".repeat(101)})
+            )
+            .is_err()
+        );
+        assert!(
+            create_tree(
+                &db,
+                &config,
+                json!({"tree": {"title": "Root", "children": [{"title": "y".repeat(21)}]}}),
+            )
+            .is_err()
+        );
+        assert!(db.list_tasks(ListTasksQuery::default()).unwrap().is_empty());
+
+        let task_id = create(&db, &config, json!({"title": "Ok"})).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        let opts = || UpdateOptions {
+            db: &db,
+            config: &config,
+            workflows: &config.workflows,
+        };
+        assert!(
+            update(
+                opts(),
+                json!({"worker_id": "w1", "task": task_id, "description": "x".repeat(101)}),
+            )
+            .is_err()
+        );
+        update(
+            opts(),
+            json!({"worker_id": "w1", "task": task_id, "description": "x".repeat(100)}),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn descriptions_are_previewed_unless_full() {
+        let db = setup_db();
+        let config = limited_config();
+        let description = "0123456789abcdefghij";
+        let task_id = create(
+            &db,
+            &config,
+            json!({"title": "Long", "description": description}),
+        )
+        .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let list = |args: Value| {
+            list_tasks(&db, &config, OutputFormat::Json, args).unwrap()["tasks"][0]["description"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+        let preview = list(json!({}));
+        assert!(
+            preview.starts_with("0123456789... [truncated: 20 characters"),
+            "{}",
+            preview
+        );
+        assert_eq!(list(json!({"full": true})), description);
+        assert_eq!(list(json!({"preview_chars": 0})), description);
+
+        // get returns the whole description unless a preview is asked for
+        let get = |args: Value| {
+            get(
+                &db,
+                &config.states,
+                &config.priorities,
+                &VisibilityConfig::default(),
+                OutputFormat::Json,
+                args,
+            )
+            .unwrap()["description"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(get(json!({"task": task_id})), description);
+        assert!(
+            get(json!({"task": task_id, "preview_chars": 4})).starts_with("0123... [truncated")
+        );
+    }
+}
+
+mod tag_hierarchy_tests {
+    use super::*;
+    use task_graph_mcp::db::filter::TaskFilter;
+    use task_graph_mcp::db::search::SearchFilters;
+
+    fn tagged(db: &Database, title: &str, tags: &[&str], needed_tags: &[&str]) -> String {
+        let to_vec = |tags: &[&str]| Some(tags.iter().map(|t| t.to_string()).collect());
+        db.create_task(
+            None,
+            title.to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            to_vec(needed_tags),
+            None,
+            to_vec(tags),
+            &default_states_config(),
+            &default_ids_config(),
+        )
+        .unwrap()
+        .id
+    }
+
+    fn titles(tasks: Vec<task_graph_mcp::types::Task>) -> Vec<String> {
+        let mut titles: Vec<String> = tasks.into_iter().map(|t| t.title).collect();
+        titles.sort();
+        titles
+    }
+
+    #[test]
+    fn parent_tag_filters_match_children() {
+        let db = setup_db();
+        tagged(&db, "rust parser", &["lang/rust"], &[]);
+        tagged(&db, "python parser", &["lang/python", "db"], &[]);
+        tagged(&db, "language docs", &["language"], &[]);
+
+        let filtered = |any: &[&str], all: &[&str]| {
+            let list = |tags: &[&str]| {
+                (!tags.is_empty()).then(|| tags.iter().map(|t| t.to_string()).collect())
+            };
+            titles(
+                db.list_tasks_with_tag_filters(
+                    None,
+                    None,
+                    None,
+                    list(any),
+                    list(all),
+                    None,
+                    None,
+                    0,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap(),
+            )
+        };
+        assert_eq!(
+            filtered(&["lang"], &[]),
+            vec!["python parser", "rust parser"]
+        );
+        assert_eq!(filtered(&["lang/rust"], &[]), vec!["rust parser"]);
+        assert_eq!(filtered(&[], &["lang", "db"]), vec!["python parser"]);
+
+        let filter = TaskFilter::parse("tag:lang", &PrioritiesConfig::default()).unwrap();
+        let tasks = db
+            .list_tasks(ListTasksQuery {
+                filter: Some(&filter),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(titles(tasks), vec!["python parser", "rust parser"]);
+
+        let found = db
+            .search_tasks_filtered(
+                "parser",
+                None,
+                0,
+                false,
+                &SearchFilters {
+                    tag: Some("lang"),
+                    ..Default::default()
+                },
+                None,
+            )
+            .unwrap();
+        assert_eq!(found.len(), 2);
+    }
+
+    #[test]
+    fn child_tag_meets_parent_requirement() {
+        let db = setup_db();
+        let states = default_states_config();
+        let general = tagged(&db, "any language", &[], &["lang"]);
+        let specific = tagged(&db, "rust only", &[], &["lang/rust"]);
+        for (id, tags) in [("rustacean", vec!["lang/rust"]), ("polyglot", vec!["lang"])] {
+            db.register_worker(
+                Some(id.to_string()),
+                tags.into_iter().map(String::from).collect(),
+                false,
+                &default_ids_config(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        }
+
+        let ready = |agent: &str| {
+            titles(
+                db.get_ready_tasks(
+                    Some(agent),
+                    &states,
+                    &DependenciesConfig::default(),
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap(),
+            )
+        };
+        assert_eq!(ready("rustacean"), vec!["any language", "rust only"]);
+        // A parent tag does not stand in for a more specific requirement
+        assert_eq!(ready("polyglot"), vec!["any language"]);
+
+        assert!(db.claim_task(&specific, "polyglot", &states).is_err());
+        db.claim_task(&general, "rustacean", &states).unwrap();
+    }
+}
+
+mod tag_alias_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::tasks::{create, list_tasks};
+
+    fn alias_config() -> AppConfig {
+        let mut tags = TagsConfig {
+            normalize: true,
+            ..Default::default()
+        };
+        tags.aliases
+            .insert("js".to_string(), "javascript".to_string());
+        let mut config = default_app_config();
+        config.tags = Arc::new(tags);
+        config
+    }
+
+    #[test]
+    fn tags_are_canonical_on_write_and_in_filters() {
+        let db = setup_db();
+        let config = alias_config();
+
+        let created = create(
+            &db,
+            &config,
+            json!({"title": "Widget", "tags": ["JS", "Code_Review", "js/React"], "needed_tags": ["JS"]}),
+        )
+        .unwrap();
+        let task = db
+            .get_task(created["id"].as_str().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            task.tags,
+            vec!["javascript", "code-review", "javascript/react"]
+        );
+        assert_eq!(task.needed_tags, vec!["javascript"]);
+
+        let count = |args: Value| {
+            list_tasks(&db, &config, OutputFormat::Json, args).unwrap()["tasks"]
+                .as_array()
+                .unwrap()
+                .len()
+        };
+        assert_eq!(count(json!({"tags_any": ["Js"]})), 1);
+        assert_eq!(count(json!({"tags_all": ["code_review", "JS/react"]})), 1);
+        assert_eq!(count(json!({"where": "tag:JS"})), 1);
+        assert_eq!(count(json!({"tags_any": ["python"]})), 0);
+    }
+}
+
+mod tag_stats_tests {
+    use super::*;
+
+    #[test]
+    fn tag_stats_count_usage_and_flag_uncovered_needs() {
+        let db = setup_db();
+        let states = default_states_config();
+        let ids = default_ids_config();
+        let tags = |t: &[&str]| Some(t.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        let create = |title: &str, needed: Option<Vec<String>>, task_tags: Option<Vec<String>>| {
+            db.create_task(
+                None,
+                title.to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                needed,
+                None,
+                task_tags,
+                &states,
+                &ids,
+            )
+            .unwrap()
+        };
+        create("parse", tags(&["lang"]), tags(&["backend"]));
+        create("deploy", tags(&["ops"]), tags(&["backend"]));
+        let done = create("old", tags(&["legacy"]), tags(&["legacy"]));
+        for status in ["working", "completed"] {
+            db.update_task(
+                &done.id,
+                None,
+                None,
+                Some(status.to_string()),
+                None,
+                None,
+                None,
+                &states,
+            )
+            .unwrap();
+        }
+        db.register_worker(
+            Some("rustacean".to_string()),
+            vec!["lang/rust".to_string()],
+            false,
+            &ids,
+            None,
+            vec![],
+        )
+        .unwrap();
+
+        let stats = db.get_tag_stats(&states).unwrap();
+        let tag = |name: &str| stats.iter().find(|s| s.tag == name).unwrap();
+
+        assert_eq!(tag("backend").tasks_by_status.get("pending"), Some(&2));
+        assert!(tag("backend").last_used.is_some());
+        // The agent's child tag meets the need for its parent
+        assert_eq!(tag("lang").agents, 1);
+        assert!(!tag("lang").uncovered);
+        assert_eq!(tag("ops").needed_by, 1);
+        assert_eq!(tag("ops").agents, 0);
+        assert!(tag("ops").uncovered);
+        assert_eq!(tag("lang/rust").agents, 1);
+        assert_eq!(tag("legacy").needed_by, 0);
+        assert_eq!(tag("legacy").tasks_by_status.get("completed"), Some(&1));
+        assert!(!tag("legacy").uncovered);
+    }
+}
+
+mod required_tag_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::config::workflows::WorkflowsConfig;
+    use task_graph_mcp::tools::tasks::{UpdateOptions, create, create_tree, update};
+
+    fn id(result: Value) -> String {
+        result["id"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn parent_rules_apply_to_the_whole_subtree() {
+        let db = setup_db();
+        let config = default_app_config();
+        let epic = id(create(
+            &db,
+            &config,
+            json!({"title": "Release", "required_child_tags": ["component/*"]}),
+        )
+        .unwrap());
+
+        let err = create(&db, &config, json!({"title": "Untagged", "parent": epic}))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'component/*'"), "{}", err);
+        assert!(err.contains(&format!("task '{}'", epic)), "{}", err);
+        // The parent tag alone is not a child tag
+        assert!(
+            create(
+                &db,
+                &config,
+                json!({"title": "Vague", "parent": epic, "tags": ["component"]})
+            )
+            .is_err()
+        );
+        let story = id(create(
+            &db,
+            &config,
+            json!({"title": "Story", "parent": epic, "tags": ["component/ui"]}),
+        )
+        .unwrap());
+
+        // Grandchildren are covered too, in trees as well as single creates
+        let err = create_tree(
+            &db,
+            &config,
+            json!({"parent": story, "tree": {
+                "title": "Tagged", "tags": ["component/api"],
+                "children": [{"title": "Bare"}]
+            }}),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("Task 'Bare'"), "{}", err);
+        assert!(db.get_children(&story).unwrap().is_empty());
+
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        let opts = || UpdateOptions {
+            db: &db,
+            config: &config,
+            workflows: &config.workflows,
+        };
+        assert!(
+            update(
+                opts(),
+                json!({"worker_id": "w1", "task": story, "tags": ["docs"]})
+            )
+            .is_err()
+        );
+        update(
+            opts(),
+            json!({"worker_id": "w1", "task": epic, "required_child_tags": []}),
+        )
+        .unwrap();
+        update(
+            opts(),
+            json!({"worker_id": "w1", "task": story, "tags": ["docs"]}),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn tree_nodes_and_workflow_declare_rules() {
+        let db = setup_db();
+        let mut config = default_app_config();
+        config.workflows = Arc::new(WorkflowsConfig {
+            required_tags: vec!["team".to_string()],
+            ..Default::default()
+        });
+
+        assert!(create(&db, &config, json!({"title": "Loose"})).is_err());
+        create(
+            &db,
+            &config,
+            json!({"title": "Owned", "tags": ["team/infra"]}),
+        )
+        .unwrap();
+
+        let tree = |leaf_tags: Value| {
+            create_tree(
+                &db,
+                &config,
+                json!({"tree": {
+                    "title": "Epic", "tags": ["team"], "required_child_tags": ["area"],
+                    "children": [{"title": "Leaf", "tags": leaf_tags}]
+                }}),
+            )
+        };
+        let err = tree(json!(["team"])).unwrap_err().to_string();
+        assert!(err.contains("'area' (required by task 'Epic')"), "{}", err);
+        let result = tree(json!(["team", "area/db"])).unwrap();
+        let root = db
+            .get_task(result["root"]["id"].as_str().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(root.required_child_tags, vec!["area"]);
+    }
+}
+
+mod weighted_tag_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::config::TagDefinition;
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::tasks::{create, list_tasks};
+
+    fn weighted_config() -> AppConfig {
+        let mut tags = TagsConfig::default();
+        for (name, weight) in [("database", 3.0), ("docs", 1.0)] {
+            tags.definitions.insert(
+                name.to_string(),
+                TagDefinition {
+                    weight: Some(weight),
+                    ..Default::default()
+                },
+            );
+        }
+        let mut config = default_app_config();
+        config.tags = Arc::new(tags);
+        config
+    }
+
+    fn worker(db: &Database, id: &str, tags: &[&str]) {
+        db.register_worker(
+            Some(id.to_string()),
+            tags.iter().map(|t| t.to_string()).collect(),
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn optional_wanted_tags_score_without_gating() {
+        let db = setup_db();
+        let config = weighted_config();
+        worker(&db, "dba", &["database"]);
+        worker(&db, "writer", &["docs"]);
+        worker(&db, "anyone", &[]);
+
+        let created = create(
+            &db,
+            &config,
+            json!({"title": "Tune query", "wanted_tags": ["database", "docs"], "wanted_optional": true}),
+        )
+        .unwrap();
+        assert_eq!(created["wanted_optional"], json!(true));
+        let gated = create(
+            &db,
+            &config,
+            json!({"title": "Gated", "wanted_tags": ["database"]}),
+        )
+        .unwrap();
+
+        let ready = |agent: &str| {
+            list_tasks(
+                &db,
+                &config,
+                OutputFormat::Json,
+                json!({"ready": true, "agent": agent}),
+            )
+            .unwrap()["tasks"]
+                .as_array()
+                .unwrap()
+                .clone()
+        };
+        let anyone = ready("anyone");
+        assert_eq!(anyone.len(), 1);
+        assert_eq!(anyone[0]["match_score"], json!(0.5));
+        assert!(ready("dba").iter().any(|t| t["id"] == gated["id"]));
+
+        // The heavier tag raises the score more: 0.5 + 0.5 * 3/4 vs 0.5 + 0.5 * 1/4
+        let score = |agent: &str| {
+            ready(agent)
+                .iter()
+                .find(|t| t["id"] == created["id"])
+                .unwrap()["match_score"]
+                .as_f64()
+                .unwrap()
+        };
+        assert_eq!(score("dba"), 0.875);
+        assert_eq!(score("writer"), 0.625);
+
+        let task_id = created["id"].as_str().unwrap();
+        db.claim_task(task_id, "anyone", &config.states).unwrap();
+        // Without the flag, wanted tags gate claiming
+        assert!(
+            db.claim_task(gated["id"].as_str().unwrap(), "writer", &config.states)
+                .is_err()
+        );
+    }
+}
+
+mod claim_many_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+    use task_graph_mcp::tools::claiming::claim_many;
+
+    fn ids(items: &Value) -> Vec<&str> {
+        items
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn count_claims_best_ready_tasks_up_to_max_claims() {
+        let db = setup_db();
+        let config = default_app_config();
+        db.register_worker(
+            Some("batch".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        db.update_worker("batch", None, Some(2)).unwrap();
+        let low = new_task(&db, &config, json!({"title": "Low", "priority": 1}));
+        let high = new_task(&db, &config, json!({"title": "High", "priority": 9}));
+        let mid = new_task(&db, &config, json!({"title": "Mid", "priority": 5}));
+
+        let result = claim_many(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "batch", "count": 3}),
+        )
+        .unwrap();
+        assert_eq!(ids(&result["claimed"]), vec![high.as_str(), mid.as_str()]);
+        assert_eq!(result["requested"], json!(3));
+        assert!(db.get_task(&low).unwrap().unwrap().worker_id.is_none());
+
+        // At the limit nothing more is claimed
+        let result = claim_many(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "batch", "count": 1}),
+        )
+        .unwrap();
+        assert!(ids(&result["claimed"]).is_empty());
+        assert_eq!(result["success"], json!(false));
+    }
+
+    #[test]
+    fn explicit_list_reports_each_skipped_task() {
+        let db = setup_db();
+        let config = default_app_config();
+        for (id, tags) in [("batch", vec![]), ("other", vec!["gpu".to_string()])] {
+            db.register_worker(
+                Some(id.to_string()),
+                tags,
+                false,
+                &default_ids_config(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        }
+        let free = new_task(&db, &config, json!({"title": "Free"}));
+        let taken = new_task(&db, &config, json!({"title": "Taken"}));
+        db.claim_task(&taken, "other", &config.states).unwrap();
+        let gpu = new_task(
+            &db,
+            &config,
+            json!({"title": "GPU", "needed_tags": ["gpu"]}),
+        );
+        let blocked = new_task(&db, &config, json!({"title": "Blocked"}));
+        db.add_dependency(&free, &blocked, "blocks", &config.deps)
+            .unwrap();
+
+        let result = claim_many(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "batch", "tasks": [&taken, &free, &gpu, &blocked, "missing"]}),
+        )
+        .unwrap();
+        assert_eq!(ids(&result["claimed"]), vec![free.as_str()]);
+        let skipped: HashMap<String, String> = result["skipped"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| {
+                (
+                    s["id"].as_str().unwrap().to_string(),
+                    s["reason"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(skipped.len(), 4);
+        assert!(skipped[&taken].contains("already claimed by 'other'"));
+        assert!(skipped[&gpu].contains("missing required tag: gpu"));
+        assert!(skipped[&blocked].contains(&free), "{}", skipped[&blocked]);
+        assert_eq!(skipped["missing"], "Task not found");
+
+        assert!(
+            claim_many(
+                &db,
+                &config,
+                &config.workflows,
+                json!({"worker_id": "batch", "count": 1, "tasks": [&gpu]}),
+            )
+            .is_err()
+        );
+    }
+}
+
+mod claim_filter_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::tools::claiming::claim;
+
+    #[test]
+    fn filter_claims_the_best_matching_ready_task() {
+        let db = setup_db();
+        let config = default_app_config();
+        for id in ["a", "b", "c"] {
+            db.register_worker(
+                Some(id.to_string()),
+                vec![],
+                false,
+                &default_ids_config(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        }
+        let epic = new_task(&db, &config, json!({"title": "Epic", "priority": 1}));
+        let ui = new_task(
+            &db,
+            &config,
+            json!({"title": "UI", "parent": epic, "tags": ["area/ui"], "priority": 3}),
+        );
+        let api = new_task(
+            &db,
+            &config,
+            json!({"title": "API", "parent": epic, "tags": ["area/api"], "priority": 6}),
+        );
+        let urgent = new_task(
+            &db,
+            &config,
+            json!({"title": "Urgent", "tags": ["area/ui"], "priority": 9}),
+        );
+
+        let claimed = |worker: &str, filter: Value| {
+            let mut args = filter;
+            args["worker_id"] = json!(worker);
+            claim(&db, &config, &config.workflows, args).unwrap()
+        };
+
+        // Highest priority task under the parent tag
+        let result = claimed("a", json!({"tags": ["area"]}));
+        assert_eq!(result["task"]["id"], json!(urgent));
+        assert_eq!(result["task"]["worker_id"], json!("a"));
+
+        // A second worker with the same filter gets the next match
+        let result = claimed("b", json!({"tags": ["area"], "parent": epic}));
+        assert_eq!(result["task"]["id"], json!(api));
+
+        let result = claimed("c", json!({"parent": epic, "min_priority": 5}));
+        assert_eq!(result["success"], json!(false));
+        assert!(result["task"].is_null());
+        assert!(db.get_task(&ui).unwrap().unwrap().worker_id.is_none());
+
+        let result = claimed("c", json!({"tags": ["area/ui"]}));
+        assert_eq!(result["task"]["id"], json!(ui));
+    }
+}
+
+mod reservation_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::claiming::{claim, reserve};
+    use task_graph_mcp::tools::tasks::{create, list_tasks};
+
+    fn setup() -> (Database, AppConfig, String) {
+        let db = setup_db();
+        let config = default_app_config();
+        for id in ["a", "b"] {
+            db.register_worker(
+                Some(id.to_string()),
+                vec![],
+                false,
+                &default_ids_config(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        }
+        let task = create(&db, &config, json!({"title": "Look first"})).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        (db, config, task)
+    }
+
+    fn ready(db: &Database, config: &AppConfig, agent: &str) -> Vec<Value> {
+        list_tasks(
+            db,
+            config,
+            OutputFormat::Json,
+            json!({"ready": true, "agent": agent}),
+        )
+        .unwrap()["tasks"]
+            .as_array()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn reservation_holds_task_for_its_worker() {
+        let (db, config, task) = setup();
+        let result = reserve(&db, json!({"worker_id": "a", "task": task})).unwrap();
+        assert_eq!(result["reserved_by"], json!("a"));
+
+        // Hidden from other workers, shown with its holder to the reserver
+        assert!(ready(&db, &config, "b").is_empty());
+        let mine = ready(&db, &config, "a");
+        assert_eq!(mine[0]["reserved_by"], json!("a"));
+
+        let err = claim(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "b", "task": task}),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("reserved by 'a'"), "{}", err);
+        assert!(reserve(&db, json!({"worker_id": "b", "task": task})).is_err());
+
+        // Claiming ends the hold
+        claim(
+            &db,
+            &config,
+            &config.workflows,
+            json!({"worker_id": "a", "task": task}),
+        )
+        .unwrap();
+        assert!(db.get_active_reservations().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reservations_expire_or_are_released() {
+        let (db, config, task) = setup();
+        reserve(&db, json!({"worker_id": "a", "task": task, "ttl_ms": 1})).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(ready(&db, &config, "b").len(), 1);
+
+        reserve(&db, json!({"worker_id": "b", "task": task})).unwrap();
+        let result = reserve(&db, json!({"worker_id": "b", "task": task, "ttl_ms": 0})).unwrap();
+        assert_eq!(result["released"], json!(true));
+        db.claim_task(&task, "a", &config.states).unwrap();
+    }
+}
+
+mod update_ops_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::tasks::{UpdateOptions, create, list_tasks, update};
+
+    fn setup() -> (Database, AppConfig, String) {
+        let db = setup_db();
+        let config = default_app_config();
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        let task = create(
+            &db,
+            &config,
+            json!({"title": "Patch me", "description": "First", "tags": ["a", "b"]}),
+        )
+        .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        (db, config, task)
+    }
+
+    #[test]
+    fn ops_edit_the_current_task() {
+        let (db, config, task) = setup();
+        let opts = || UpdateOptions {
+            db: &db,
+            config: &config,
+            workflows: &config.workflows,
+        };
+
+        // Another agent's tag edit in between is kept, not overwritten
+        db.set_task_fields(
+            &task,
+            &json!({"owner_team": "core"}).as_object().unwrap().clone(),
+        )
+        .unwrap();
+        update(
+            opts(),
+            json!({"worker_id": "w1", "task": task, "ops": [
+                {"op": "add", "path": "/tags/-", "value": "c"}
+            ]}),
+        )
+        .unwrap();
+        update(
+            opts(),
+            json!({"worker_id": "w1", "task": task, "ops": [
+                {"op": "remove", "path": "/tags", "value": "a"},
+                {"op": "add", "path": "/needed_tags", "value": ["gpu"]},
+                {"op": "add", "path": "/description", "value": "Second"},
+                {"op": "replace", "path": "/fields/points_left", "value": 3},
+                {"op": "remove", "path": "/fields/owner_team"}
+            ]}),
+        )
+        .unwrap();
+
+        let patched = db.get_task(&task).unwrap().unwrap();
+        assert_eq!(patched.tags, vec!["b", "c"]);
+        assert_eq!(patched.needed_tags, vec!["gpu"]);
+        assert_eq!(patched.description.as_deref(), Some("First\nSecond"));
+        let fields = db.get_task_fields(&task).unwrap();
+        assert_eq!(fields.get("points_left"), Some(&json!(3)));
+        assert!(!fields.contains_key("owner_team"));
+        // Junction tables follow, so tag filters see the change
+        let tagged = list_tasks(
+            &db,
+            &config,
+            OutputFormat::Json,
+            json!({"tags_all": ["b", "c"]}),
+        )
+        .unwrap();
+        assert_eq!(tagged["tasks"][0]["id"], json!(task));
+    }
+
+    #[test]
+    fn failing_op_rejects_the_whole_batch() {
+        let (db, config, task) = setup();
+        let opts = || UpdateOptions {
+            db: &db,
+            config: &config,
+            workflows: &config.workflows,
+        };
+
+        let err = update(
+            opts(),
+            json!({"worker_id": "w1", "task": task, "ops": [
+                {"op": "add", "path": "/tags", "value": "z"},
+                {"op": "remove", "path": "/tags", "value": "missing"}
+            ]}),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("ops[1]"), "{}", err);
+        assert_eq!(db.get_task(&task).unwrap().unwrap().tags, vec!["a", "b"]);
+
+        // Malformed ops are all reported before anything is applied
+        let err = update(
+            opts(),
+            json!({"worker_id": "w1", "task": task, "tags": ["x"], "ops": [
+                {"op": "move", "path": "/title"},
+                {"op": "add", "path": "/tags", "value": "y"}
+            ]}),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("ops[0]") && err.contains("ops[1]"), "{}", err);
+    }
+}
+
+mod precondition_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::error::{ErrorCode, ToolError};
+    use task_graph_mcp::tools::tasks::{UpdateOptions, create, delete, update};
+
+    fn setup() -> (Database, AppConfig, String) {
+        let db = setup_db();
+        let config = default_app_config();
+        for id in ["w1", "w2"] {
+            db.register_worker(
+                Some(id.to_string()),
+                vec![],
+                false,
+                &default_ids_config(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        }
+        let task = create(&db, &config, json!({"title": "Guarded"})).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        (db, config, task)
+    }
+
+    fn precondition_field(err: anyhow::Error) -> String {
+        let tool_err = err.downcast_ref::<ToolError>().unwrap();
+        assert_eq!(tool_err.code, ErrorCode::PreconditionFailed);
+        tool_err.field.clone().unwrap()
+    }
+
+    #[test]
+    fn update_applies_only_when_guards_match() {
+        let (db, config, task) = setup();
+        let opts = || UpdateOptions {
+            db: &db,
+            config: &config,
+            workflows: &config.workflows,
+        };
+
+        update(
+            opts(),
+            json!({"worker_id": "w1", "task": task, "status": "working",
+                   "if_status": "pending", "if_owner": null}),
+        )
+        .unwrap();
+
+        // A second agent acting on a stale read is refused, and nothing changes
+        let err = update(
+            opts(),
+            json!({"worker_id": "w2", "task": task, "status": "working",
+                   "if_status": "pending", "force": true}),
+        )
+        .unwrap_err();
+        assert_eq!(precondition_field(err), "if_status");
+        let err = update(
+            opts(),
+            json!({"worker_id": "w1", "task": task, "title": "Renamed", "if_owner": "w2"}),
+        )
+        .unwrap_err();
+        assert_eq!(precondition_field(err), "if_owner");
+        let current = db.get_task(&task).unwrap().unwrap();
+        assert_eq!(current.worker_id.as_deref(), Some("w1"));
+        assert_eq!(current.title, "Guarded");
+
+        update(
+            opts(),
+            json!({"worker_id": "w1", "task": task, "title": "Renamed",
+                   "if_status": ["working", "blocked"], "if_owner": "w1"}),
+        )
+        .unwrap();
+        assert_eq!(db.get_task(&task).unwrap().unwrap().title, "Renamed");
+    }
+
+    #[test]
+    fn delete_applies_only_when_guards_match() {
+        let (db, _config, task) = setup();
+
+        let err = delete(
+            &db,
+            json!({"worker_id": "w1", "task": task, "if_status": "completed"}),
+        )
+        .unwrap_err();
+        assert_eq!(precondition_field(err), "if_status");
+        assert!(db.list_deleted_tasks(None, 10, 0).unwrap().is_empty());
+
+        delete(
+            &db,
+            json!({"worker_id": "w1", "task": task, "if_status": "pending", "if_owner": null}),
+        )
+        .unwrap();
+        let deleted = db.list_deleted_tasks(None, 10, 0).unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].id, task);
+    }
+
+    #[test]
+    fn malformed_guards_are_rejected() {
+        let (db, config, task) = setup();
+        let opts = UpdateOptions {
+            db: &db,
+            config: &config,
+            workflows: &config.workflows,
+        };
+        let err = update(
+            opts,
+            json!({"worker_id": "w1", "task": task, "if_owner": 3}),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ToolError>().unwrap().code,
+            ErrorCode::InvalidFieldValue
+        );
+    }
+}
+
+mod delete_cascade_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::tasks::{create, delete};
+
+    /// root > mid > (a > a1, b)
+    fn setup() -> Database {
+        let db = setup_db();
+        let config = default_app_config();
+        for (id, parent) in [
+            ("root", None),
+            ("mid", Some("root")),
+            ("a", Some("mid")),
+            ("a1", Some("a")),
+            ("b", Some("mid")),
+        ] {
+            create(
+                &db,
+                &config,
+                json!({"id": id, "title": id, "parent": parent}),
+            )
+            .unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn refuses_a_parent_without_cascade() {
+        let db = setup();
+        let err = delete(&db, json!({"worker_id": "w1", "task": "mid"})).unwrap_err();
+        assert!(err.to_string().contains("has children"), "{}", err);
+        assert!(db.get_task("mid").unwrap().is_some());
+    }
+
+    #[test]
+    fn dry_run_previews_without_changing_anything() {
+        let db = setup();
+        let preview = delete(
+            &db,
+            json!({"worker_id": "w1", "task": "mid", "cascade": "delete-subtree", "dry_run": true}),
+        )
+        .unwrap();
+        assert_eq!(preview["dry_run"], true);
+        assert_eq!(preview["deleted_count"], 4);
+        assert_eq!(preview["deleted"], json!(["mid", "a", "b", "a1"]));
+
+        let preview = delete(
+            &db,
+            json!({"worker_id": "w1", "task": "mid", "cascade": "reparent-to-grandparent",
+                   "dry_run": true}),
+        )
+        .unwrap();
+        assert_eq!(preview["deleted_count"], 1);
+        assert_eq!(preview["reparented"], json!(["a", "b"]));
+        assert_eq!(preview["new_parent"], "root");
+
+        assert!(db.list_deleted_tasks(None, 10, 0).unwrap().is_empty());
+        assert_eq!(db.get_children_ids("mid").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn delete_subtree_removes_all_descendants() {
+        let db = setup();
+        delete(
+            &db,
+            json!({"worker_id": "w1", "task": "mid", "cascade": "delete-subtree"}),
+        )
+        .unwrap();
+        assert_eq!(db.list_deleted_tasks(None, 10, 0).unwrap().len(), 4);
+        assert!(db.get_task("root").unwrap().is_some());
+
+        // `true` keeps meaning delete-subtree
+        let result = delete(
+            &db,
+            json!({"worker_id": "w1", "task": "root", "cascade": true}),
+        )
+        .unwrap();
+        assert_eq!(result["cascade"], "delete-subtree");
+        assert_eq!(result["deleted"], json!(["root"]));
+    }
+
+    #[test]
+    fn orphan_leaves_children_as_roots() {
+        let db = setup();
+        let result = delete(
+            &db,
+            json!({"worker_id": "w1", "task": "mid", "cascade": "orphan", "obliterate": true}),
+        )
+        .unwrap();
+        assert_eq!(result["orphaned"], json!(["a", "b"]));
+        assert!(db.get_task("mid").unwrap().is_none());
+        assert_eq!(db.get_parent("a").unwrap(), None);
+        assert_eq!(db.get_parent("b").unwrap(), None);
+        // The grandchild keeps its own parent, and the closure no longer links it to root
+        assert_eq!(db.get_parent("a1").unwrap().as_deref(), Some("a"));
+        assert!(db.get_children_ids("root").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reparent_moves_children_to_the_grandparent() {
+        let db = setup();
+        delete(
+            &db,
+            json!({"worker_id": "w1", "task": "mid", "cascade": "reparent-to-grandparent"}),
+        )
+        .unwrap();
+        assert_eq!(db.get_parent("a").unwrap().as_deref(), Some("root"));
+        assert_eq!(db.get_parent("b").unwrap().as_deref(), Some("root"));
+        let deleted = db.list_deleted_tasks(None, 10, 0).unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].id, "mid");
+
+        // With no grandparent, the children become roots
+        let result = delete(
+            &db,
+            json!({"worker_id": "w1", "task": "root", "cascade": "reparent-to-grandparent"}),
+        )
+        .unwrap();
+        assert_eq!(result["orphaned"], json!(["a", "b"]));
+        assert_eq!(db.get_parent("a").unwrap(), None);
+    }
+
+    #[test]
+    fn unknown_cascade_mode_is_rejected() {
+        let db = setup();
+        let err = delete(
+            &db,
+            json!({"worker_id": "w1", "task": "mid", "cascade": "everything"}),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("cascade"), "{}", err);
+    }
+}
+
+mod bulk_cleanup_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::error::{ErrorCode, ToolError};
+    use task_graph_mcp::tools::tasks::create;
+    use task_graph_mcp::tools::trash::{bulk_archive, bulk_delete};
+
+    fn setup() -> (Database, AppConfig) {
+        let db = setup_db();
+        let config = default_app_config();
+        for (id, parent, tags) in [
+            ("scan-1", None, vec!["scanner"]),
+            ("scan-2", None, vec!["scanner"]),
+            ("scan-parent", None, vec!["scanner"]),
+            ("kept-child", Some("scan-parent"), vec![]),
+            ("manual", None, vec![]),
+        ] {
+            create(
+                &db,
+                &config,
+                json!({"id": id, "title": id, "parent": parent, "tags": tags}),
+            )
+            .unwrap();
+        }
+        (db, config)
+    }
+
+    #[test]
+    fn bulk_delete_previews_then_requires_the_previewed_count() {
+        let (db, config) = setup();
+
+        let preview = bulk_delete(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "where": "tag:scanner"}),
+        )
+        .unwrap();
+        assert_eq!(preview["dry_run"], true);
+        assert_eq!(preview["count"], 2);
+        assert_eq!(preview["skipped"][0]["id"], "scan-parent");
+        assert!(db.list_deleted_tasks(None, 10, 0).unwrap().is_empty());
+
+        let err = bulk_delete(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "where": "tag:scanner", "dry_run": false}),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ToolError>().unwrap().code,
+            ErrorCode::MissingRequiredField
+        );
+        let err = bulk_delete(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "where": "tag:scanner", "dry_run": false,
+                   "expected_count": 3}),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ToolError>().unwrap().code,
+            ErrorCode::PreconditionFailed
+        );
+        assert!(db.list_deleted_tasks(None, 10, 0).unwrap().is_empty());
+
+        let result = bulk_delete(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "where": "tag:scanner", "dry_run": false,
+                   "expected_count": 2, "reason": "stale"}),
+        )
+        .unwrap();
+        assert_eq!(result["tasks"], json!(["scan-1", "scan-2"]));
+        let deleted = db.list_deleted_tasks(None, 10, 0).unwrap();
+        assert_eq!(deleted.len(), 2);
+        assert!(db.get_task("scan-parent").unwrap().is_some());
+        assert!(db.get_task("manual").unwrap().is_some());
+    }
+
+    #[test]
+    fn bulk_delete_takes_a_whole_matched_subtree() {
+        let (db, config) = setup();
+        let result = bulk_delete(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "where": "status=pending", "dry_run": false,
+                   "expected_count": 5, "obliterate": true}),
+        )
+        .unwrap();
+        assert_eq!(result["count"], 5);
+        assert!(db.get_task("kept-child").unwrap().is_none());
+    }
+
+    #[test]
+    fn bulk_archive_moves_unfinished_matches_to_a_terminal_state() {
+        let (db, config) = setup();
+        let preview = bulk_archive(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "where": "tag:scanner"}),
+        )
+        .unwrap();
+        assert_eq!(preview["count"], 3);
+
+        let result = bulk_archive(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "where": "tag:scanner", "dry_run": false,
+                   "expected_count": 3}),
+        )
+        .unwrap();
+        assert_eq!(result["status"], "cancelled");
+        // A parent with unfinished children is refused by the transition itself
+        assert_eq!(result["tasks"], json!(["scan-1", "scan-2"]));
+        assert_eq!(result["skipped"][0]["id"], "scan-parent");
+        assert_eq!(db.get_task("scan-1").unwrap().unwrap().status, "cancelled");
+        assert_eq!(db.get_task("manual").unwrap().unwrap().status, "pending");
+
+        // Already-finished tasks no longer count
+        let again = bulk_archive(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "where": "tag:scanner"}),
+        )
+        .unwrap();
+        assert_eq!(again["tasks"], json!(["scan-parent"]));
+
+        let err = bulk_archive(
+            &db,
+            &config,
+            json!({"worker_id": "w1", "where": "tag:scanner", "status": "working"}),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("terminal"), "{}", err);
+    }
+}
+
+mod link_batch_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::tools::deps::link;
+    use task_graph_mcp::tools::tasks::create;
+
+    fn setup(ids: &[&str]) -> (Database, AppConfig) {
+        let db = setup_db();
+        let config = default_app_config();
+        for id in ids {
+            create(&db, &config, json!({"id": id, "title": id})).unwrap();
+        }
+        (db, config)
+    }
+
+    fn edges(db: &Database) -> Vec<(String, String)> {
+        let mut edges: Vec<_> = db
+            .get_all_dependencies()
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.from_task_id, d.to_task_id))
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    #[test]
+    fn chain_links_each_task_to_the_next() {
+        let (db, config) = setup(&["t1", "t2", "t3", "t4"]);
+        let result = link(
+            &db,
+            &config.deps,
+            json!({"chain": ["t1", "t2", "t3", "t4"]}),
+        )
+        .unwrap();
+        assert_eq!(result["success"], true);
+        assert_eq!(result["created"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            edges(&db),
+            vec![
+                ("t1".to_string(), "t2".to_string()),
+                ("t2".to_string(), "t3".to_string()),
+                ("t3".to_string(), "t4".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_failing_edge_rolls_back_the_whole_batch() {
+        let (db, config) = setup(&["a", "b", "c"]);
+        link(&db, &config.deps, json!({"from": "c", "to": "a"})).unwrap();
+
+        // a->b is fine on its own, but b->c closes the loop c->a->b->c
+        let result = link(&db, &config.deps, json!({"chain": ["a", "b", "c"]})).unwrap();
+        assert_eq!(result["success"], false);
+        assert_eq!(result["created"], json!([]));
+        assert_eq!(result["errors"][0]["from"], "b");
+        assert_eq!(edges(&db), vec![("c".to_string(), "a".to_string())]);
+    }
+
+    #[test]
+    fn fan_in_skips_missing_tasks_with_warnings() {
+        let (db, config) = setup(&["a", "b", "d"]);
+        let result = link(
+            &db,
+            &config.deps,
+            json!({"from": ["a", "b", "ghost"], "to": "d"}),
+        )
+        .unwrap();
+        assert_eq!(result["success"], true);
+        assert_eq!(result["created"].as_array().unwrap().len(), 2);
+        assert_eq!(result["warnings"].as_array().unwrap().len(), 1);
+
+        assert!(link(&db, &config.deps, json!({"chain": ["a", "b"], "from": "a"})).is_err());
+        assert!(link(&db, &config.deps, json!({"chain": ["a"]})).is_err());
+    }
+}
+
+mod sequence_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::deps::{link, sequence};
+    use task_graph_mcp::tools::tasks::{create, get};
+
+    fn setup() -> (Database, AppConfig) {
+        let db = setup_db();
+        let config = default_app_config();
+        create(&db, &config, json!({"id": "p", "title": "Parent"})).unwrap();
+        for id in ["a", "b", "c", "d"] {
+            create(&db, &config, json!({"id": id, "title": id, "parent": "p"})).unwrap();
+        }
+        (db, config)
+    }
+
+    fn blocks_edges(db: &Database) -> Vec<(String, String)> {
+        let mut edges: Vec<_> = db
+            .get_all_dependencies()
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.dep_type == "blocks")
+            .map(|d| (d.from_task_id, d.to_task_id))
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    fn pair(from: &str, to: &str) -> (String, String) {
+        (from.to_string(), to.to_string())
+    }
+
+    #[test]
+    fn sequence_inserts_reorders_and_removes() {
+        let (db, config) = setup();
+
+        let result = sequence(&db, &config.deps, json!({"tasks": ["c", "a", "b"]})).unwrap();
+        assert_eq!(result["parent"], "p");
+        assert_eq!(result["sequence"], json!(["c", "a", "b"]));
+        assert_eq!(blocks_edges(&db), vec![pair("a", "b"), pair("c", "a")]);
+
+        // Insert d and move b to the front
+        let result = sequence(&db, &config.deps, json!({"tasks": ["b", "c", "d", "a"]})).unwrap();
+        assert_eq!(result["sequence"], json!(["b", "c", "d", "a"]));
+        assert_eq!(
+            result["unlinked"],
+            json!([{"from": "a", "to": "b"}, {"from": "c", "to": "a"}])
+        );
+        assert_eq!(
+            blocks_edges(&db),
+            vec![pair("b", "c"), pair("c", "d"), pair("d", "a")]
+        );
+
+        // Removing c leaves it unordered
+        sequence(&db, &config.deps, json!({"tasks": ["b", "d", "a"]})).unwrap();
+        assert_eq!(db.get_sequence("p").unwrap(), vec!["b", "d", "a"]);
+        assert_eq!(blocks_edges(&db), vec![pair("b", "d"), pair("d", "a")]);
+
+        // An empty list clears the order
+        sequence(&db, &config.deps, json!({"tasks": [], "parent": "p"})).unwrap();
+        assert!(blocks_edges(&db).is_empty());
+    }
+
+    #[test]
+    fn sequence_is_shown_in_get_and_tree_order() {
+        let (db, config) = setup();
+        sequence(&db, &config.deps, json!({"tasks": ["d", "b", "a"]})).unwrap();
+
+        let task = get(
+            &db,
+            &config.states,
+            &config.priorities,
+            &VisibilityConfig::default(),
+            OutputFormat::Json,
+            json!({"task": "b", "include": ["children"]}),
+        )
+        .unwrap();
+        assert_eq!(
+            task["sequence"],
+            json!({"parent": "p", "position": 2, "length": 3, "previous": "d", "next": "a"})
+        );
+        let unsequenced = get(
+            &db,
+            &config.states,
+            &config.priorities,
+            &VisibilityConfig::default(),
+            OutputFormat::Json,
+            json!({"task": "c"}),
+        )
+        .unwrap();
+        assert!(unsequenced.get("sequence").is_none());
+
+        let parent = get(
+            &db,
+            &config.states,
+            &config.priorities,
+            &VisibilityConfig::default(),
+            OutputFormat::Json,
+            json!({"task": "p", "include": ["children"]}),
+        )
+        .unwrap();
+        let children: Vec<&str> = parent["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(children, vec!["d", "b", "a", "c"]);
+
+        let tree = db.get_task_tree("p").unwrap().unwrap();
+        let tree_ids: Vec<&str> = tree.children.iter().map(|c| c.task.id.as_str()).collect();
+        assert_eq!(tree_ids, vec!["d", "b", "a", "c"]);
+    }
+
+    #[test]
+    fn sequence_rejects_non_siblings_and_cycles() {
+        let (db, config) = setup();
+        create(&db, &config, json!({"id": "other", "title": "Other"})).unwrap();
+
+        assert!(sequence(&db, &config.deps, json!({"tasks": ["a", "other"]})).is_err());
+        assert!(sequence(&db, &config.deps, json!({"tasks": ["a", "b", "a"]})).is_err());
+        assert!(sequence(&db, &config.deps, json!({"tasks": ["other"]})).is_err());
+
+        // a reaches b through a task outside the parent, so b cannot come first
+        create(&db, &config, json!({"id": "x", "title": "X"})).unwrap();
+        link(&db, &config.deps, json!({"chain": ["a", "x", "b"]})).unwrap();
+        assert!(sequence(&db, &config.deps, json!({"tasks": ["b", "a"]})).is_err());
+        assert_eq!(blocks_edges(&db), vec![pair("a", "x"), pair("x", "b")]);
+    }
+}
+
+mod dependency_matrix_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::format::ToolResult;
+    use task_graph_mcp::tools::deps::{export_matrix, link};
+    use task_graph_mcp::tools::tasks::create;
+
+    fn setup() -> (Database, AppConfig) {
+        let db = setup_db();
+        let config = default_app_config();
+        create(&db, &config, json!({"id": "p", "title": "Plan"})).unwrap();
+        for id in ["a", "b", "c"] {
+            create(&db, &config, json!({"id": id, "title": id, "parent": "p"})).unwrap();
+        }
+        create(&db, &config, json!({"id": "x", "title": "Outside"})).unwrap();
+        link(&db, &config.deps, json!({"chain": ["a", "b", "c"]})).unwrap();
+        link(&db, &config.deps, json!({"from": "x", "to": "a"})).unwrap();
+        (db, config)
+    }
+
+    #[test]
+    fn matrix_counts_transitive_blockers_within_subtree() {
+        let (db, config) = setup();
+
+        let matrix = db.dependency_matrix(Some("p"), &config.deps, None).unwrap();
+        let ids: Vec<&str> = matrix.tasks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["p", "a", "b", "c"]);
+        // contains edges and the edge from outside the subtree are left out
+        let edges: Vec<(&str, &str)> = matrix
+            .edges
+            .iter()
+            .map(|(from, to, _)| (from.as_str(), to.as_str()))
+            .collect();
+        assert_eq!(edges, vec![("a", "b"), ("b", "c")]);
+        let counts: Vec<(usize, usize)> = matrix
+            .tasks
+            .iter()
+            .map(|t| (t.blockers, t.transitive_blockers))
+            .collect();
+        assert_eq!(counts, vec![(0, 0), (0, 0), (1, 1), (1, 2)]);
+
+        // Without a root, the outside blocker counts
+        let matrix = db.dependency_matrix(None, &config.deps, None).unwrap();
+        let c = matrix.tasks.iter().find(|t| t.id == "c").unwrap();
+        assert_eq!(c.transitive_blockers, 3);
+
+        assert!(
+            db.dependency_matrix(Some("missing"), &config.deps, None)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn export_matrix_renders_csv_layouts() {
+        let (db, config) = setup();
+
+        let ToolResult::Raw(csv) =
+            export_matrix(&db, &config.deps, &config.visibility, json!({"task": "p"})).unwrap()
+        else {
+            panic!("expected CSV");
+        };
+        assert_eq!(
+            csv,
+            "id,title,status,blockers,transitive_blockers,p,a,b,c\n\
+             p,Plan,pending,0,0,,,,\n\
+             a,a,pending,0,0,,,,\n\
+             b,b,pending,1,1,,blocks,,\n\
+             c,c,pending,1,2,,,blocks,\n"
+        );
+
+        let ToolResult::Raw(csv) = export_matrix(
+            &db,
+            &config.deps,
+            &config.visibility,
+            json!({"task": "p", "layout": "edges"}),
+        )
+        .unwrap() else {
+            panic!("expected CSV");
+        };
+        assert_eq!(csv, "from,to,type\na,b,blocks\nb,c,blocks\n");
+
+        assert!(
+            export_matrix(
+                &db,
+                &config.deps,
+                &config.visibility,
+                json!({"layout": "grid"})
+            )
+            .is_err()
+        );
+        assert!(
+            export_matrix(
+                &db,
+                &config.deps,
+                &config.visibility,
+                json!({"task": "missing"})
+            )
+            .is_err()
+        );
+    }
+}
+
+mod any_of_dependency_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::config::{
+        BlockTarget, DependencyDefinition, DependencyDisplay, DependencyReadiness,
+        DependencySemantics,
+    };
+    use task_graph_mcp::tools::deps::link;
+    use task_graph_mcp::tools::tasks::{UpdateOptions, create, update};
+
+    fn any_of_config() -> AppConfig {
+        let mut deps = default_deps_config();
+        deps.definitions.insert(
+            "alternative".to_string(),
+            DependencyDefinition {
+                display: DependencyDisplay::Horizontal,
+                blocks: BlockTarget::Start,
+                semantics: DependencySemantics::Hard,
+                readiness: DependencyReadiness::Any,
+            },
+        );
+        let mut config = default_app_config();
+        config.deps = Arc::new(deps);
+        config
+    }
+
+    fn setup(config: &AppConfig) -> Database {
+        let db = setup_db();
+        for id in ["a", "b", "c", "d"] {
+            create(&db, config, json!({"id": id, "title": id})).unwrap();
+        }
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        db
+    }
+
+    fn complete(db: &Database, config: &AppConfig, task: &str) -> Value {
+        let update = |args: Value| {
+            update(
+                UpdateOptions {
+                    db,
+                    config,
+                    workflows: &config.workflows,
+                },
+                args,
+            )
+            .unwrap()
+        };
+        update(json!({"worker_id": "w1", "task": task, "status": "working"}));
+        update(json!({"worker_id": "w1", "task": task, "status": "completed"}))
+    }
+
+    fn is_ready(db: &Database, config: &AppConfig, task: &str) -> bool {
+        db.get_ready_tasks(None, &config.states, &config.deps, None, None, None)
+            .unwrap()
+            .iter()
+            .any(|t| t.id == task)
+    }
+
+    fn is_blocked(db: &Database, config: &AppConfig, task: &str) -> bool {
+        db.get_blocked_tasks(&config.states, &config.deps, None, None, None)
+            .unwrap()
+            .iter()
+            .any(|t| t.id == task)
+    }
+
+    #[test]
+    fn any_of_group_is_satisfied_by_one_blocker() {
+        let config = default_app_config();
+        let db = setup(&config);
+        let result = link(
+            &db,
+            &config.deps,
+            json!({"from": ["a", "b"], "to": "c", "any_of": "approach"}),
+        )
+        .unwrap();
+        assert_eq!(result["created"][0]["any_of"], "approach");
+        // d is not an alternative and must complete on its own
+        link(&db, &config.deps, json!({"from": "d", "to": "c"})).unwrap();
+        assert!(is_blocked(&db, &config, "c"));
+
+        let result = complete(&db, &config, "a");
+        assert!(result.get("unblocked").is_none());
+        assert!(!is_ready(&db, &config, "c"));
+
+        let result = complete(&db, &config, "d");
+        assert_eq!(result["unblocked"], json!(["c"]));
+        assert!(is_ready(&db, &config, "c"));
+        assert!(!is_blocked(&db, &config, "c"));
+        // b is still pending, but its group is satisfied
+        assert_eq!(db.get_task("b").unwrap().unwrap().status, "pending");
+    }
+
+    #[test]
+    fn readiness_any_type_groups_new_edges() {
+        let config = any_of_config();
+        let db = setup(&config);
+        link(
+            &db,
+            &config.deps,
+            json!({"from": ["a", "b"], "to": "c", "type": "alternative"}),
+        )
+        .unwrap();
+        let groups: Vec<Option<String>> = db
+            .get_dependencies_by_type("c", "alternative", "incoming")
+            .unwrap()
+            .into_iter()
+            .map(|d| d.any_of)
+            .collect();
+        assert_eq!(groups, vec![Some("alternative".to_string()); 2]);
+        assert!(!is_ready(&db, &config, "c"));
+
+        complete(&db, &config, "b");
+        assert!(is_ready(&db, &config, "c"));
+    }
+
+    #[test]
+    fn any_of_requires_start_blocking_type() {
+        let config = default_app_config();
+        let db = setup(&config);
+        let err = link(
+            &db,
+            &config.deps,
+            json!({"from": "a", "to": "c", "type": "relates-to", "any_of": "x"}),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("blocks: start"));
+        assert!(
+            link(
+                &db,
+                &config.deps,
+                json!({"from": "a", "to": "c", "any_of": " "})
+            )
+            .is_err()
+        );
+
+        let mut deps = default_deps_config();
+        deps.definitions.get_mut("relates-to").unwrap().readiness = DependencyReadiness::Any;
+        assert!(deps.validate().is_err());
+    }
+
+    #[test]
+    fn relinking_moves_edge_into_group() {
+        let config = default_app_config();
+        let db = setup(&config);
+        link(&db, &config.deps, json!({"from": ["a", "b"], "to": "c"})).unwrap();
+        let result = link(
+            &db,
+            &config.deps,
+            json!({"from": ["a", "b"], "to": "c", "any_of": "either"}),
+        )
+        .unwrap();
+        assert_eq!(result["updated"].as_array().unwrap().len(), 2);
+
+        complete(&db, &config, "a");
+        assert!(is_ready(&db, &config, "c"));
+    }
+}
+
+mod predicate_gate_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::config::{GateDefinition, GateEnforcement};
+    use task_graph_mcp::tools::attachments::attach;
+    use task_graph_mcp::tools::gates::check_gates;
+    use task_graph_mcp::tools::tasks::{UpdateOptions, create, update};
+    use tempfile::TempDir;
+
+    fn gate(gate_type: &str, enforcement: GateEnforcement, sql: &str) -> GateDefinition {
+        GateDefinition {
+            gate_type: gate_type.to_string(),
+            enforcement,
+            description: format!("{} must hold", gate_type),
+            sql: Some(sql.to_string()),
+            command: None,
+            timeout_seconds: None,
+        }
+    }
+
+    fn workflows_with(gates: Vec<GateDefinition>) -> WorkflowsConfig {
+        let mut workflows = WorkflowsConfig::default();
+        workflows.gates.insert("status:working".to_string(), gates);
+        workflows
+    }
+
+    fn setup() -> (Database, AppConfig) {
+        let db = setup_db();
+        let config = default_app_config();
+        create(&db, &config, json!({"id": "t", "title": "Task"})).unwrap();
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        (db, config)
+    }
+
+    fn set_status(
+        db: &Database,
+        config: &AppConfig,
+        workflows: &WorkflowsConfig,
+        status: &str,
+    ) -> anyhow::Result<Value> {
+        update(
+            UpdateOptions {
+                db,
+                config,
+                workflows,
+            },
+            json!({"worker_id": "w1", "task": "t", "status": status}),
+        )
+    }
+
+    const TESTED: &str = "SELECT COUNT(*) FROM attachments \
+                          WHERE task_id = :task_id AND attachment_type = 'test-results'";
+
+    #[test]
+    fn failing_predicate_blocks_transition_with_gate_named() {
+        let (db, config) = setup();
+        let workflows = workflows_with(vec![gate("gate/tested", GateEnforcement::Reject, TESTED)]);
+        set_status(&db, &config, &workflows, "working").unwrap();
+
+        let checked = check_gates(&db, &workflows, json!({"task": "t"})).unwrap();
+        assert_eq!(checked["status"], "fail");
+        assert_eq!(checked["gates"][0]["type"], "gate/tested");
+        assert_eq!(checked["gates"][0]["predicate"], true);
+
+        let err = set_status(&db, &config, &workflows, "completed").unwrap_err();
+        assert!(err.to_string().contains("gate/tested"));
+        let suggestion = err
+            .downcast::<task_graph_mcp::error::ToolError>()
+            .unwrap()
+            .suggestion
+            .unwrap();
+        assert!(
+            suggestion.starts_with("Satisfy 'gate/tested'"),
+            "{}",
+            suggestion
+        );
+        assert!(!suggestion.contains("attach"), "{}", suggestion);
+        assert_eq!(db.get_task("t").unwrap().unwrap().status, "working");
+
+        let media = TempDir::new().unwrap();
+        attach(
+            &db,
+            media.path(),
+            &config.attachments,
+            json!({"task": "t", "type": "test-results", "content": "12 passed"}),
+        )
+        .unwrap();
+        let checked = check_gates(&db, &workflows, json!({"task": "t"})).unwrap();
+        assert_eq!(checked["status"], "pass");
+        set_status(&db, &config, &workflows, "completed").unwrap();
+    }
+
+    #[test]
+    fn warn_predicate_can_be_forced() {
+        let (db, config) = setup();
+        let workflows = workflows_with(vec![gate(
+            "gate/high-priority",
+            GateEnforcement::Warn,
+            "SELECT priority >= 8 FROM tasks WHERE id = :task_id",
+        )]);
+        set_status(&db, &config, &workflows, "working").unwrap();
+
+        let err = set_status(&db, &config, &workflows, "completed").unwrap_err();
+        assert!(err.to_string().contains("gate/high-priority"));
+
+        let result = update(
+            UpdateOptions {
+                db: &db,
+                config: &config,
+                workflows: &workflows,
+            },
+            json!({"worker_id": "w1", "task": "t", "status": "completed", "force": true}),
+        )
+        .unwrap();
+        assert!(
+            result["gate_warnings"]
+                .to_string()
+                .contains("gate/high-priority")
+        );
+    }
+
+    #[test]
+    fn predicate_must_be_read_only() {
+        let (db, config) = setup();
+        set_status(&db, &config, &WorkflowsConfig::default(), "working").unwrap();
+        for sql in [
+            "DELETE FROM tasks WHERE id = :task_id",
+            "SELECT COUNT(*) FROM tasks WHERE id = ?1",
+        ] {
+            let workflows = workflows_with(vec![gate("gate/bad", GateEnforcement::Reject, sql)]);
+            let err = check_gates(&db, &workflows, json!({"task": "t"})).unwrap_err();
+            assert!(err.to_string().contains("gate/bad"), "{}", err);
+        }
+        assert!(db.get_task("t").unwrap().is_some());
+    }
+}
+
+#[cfg(unix)]
+mod command_gate_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::config::{GateDefinition, GateEnforcement};
+    use task_graph_mcp::tools::gates::check_gates;
+    use task_graph_mcp::tools::tasks::{UpdateOptions, create, update};
+
+    fn gate(command: &str, timeout_seconds: Option<u64>) -> GateDefinition {
+        GateDefinition {
+            gate_type: "gate/ci".to_string(),
+            enforcement: GateEnforcement::Reject,
+            description: "CI must be green".to_string(),
+            sql: None,
+            command: Some(command.to_string()),
+            timeout_seconds,
+        }
+    }
+
+    fn workflows_with(gate: GateDefinition) -> WorkflowsConfig {
+        let mut workflows = WorkflowsConfig::default();
+        workflows
+            .gates
+            .insert("status:working".to_string(), vec![gate]);
+        workflows
+    }
+
+    fn setup() -> (Database, AppConfig) {
+        let db = setup_db();
+        let config = default_app_config();
+        create(&db, &config, json!({"id": "t", "title": "Ship it"})).unwrap();
+        db.register_worker(
+            Some("w1".to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap();
+        update(
+            UpdateOptions {
+                db: &db,
+                config: &config,
+                workflows: &WorkflowsConfig::default(),
+            },
+            json!({"worker_id": "w1", "task": "t", "status": "working"}),
+        )
+        .unwrap();
+        (db, config)
+    }
+
+    fn complete(
+        db: &Database,
+        config: &AppConfig,
+        workflows: &WorkflowsConfig,
+    ) -> anyhow::Result<serde_json::Value> {
+        update(
+            UpdateOptions {
+                db,
+                config,
+                workflows,
+            },
+            json!({"worker_id": "w1", "task": "t", "status": "completed"}),
+        )
+    }
+
+    fn outputs(db: &Database) -> Vec<String> {
+        db.get_attachments_full("t", true)
+            .unwrap()
+            .into_iter()
+            .filter(|a| a.attachment_type == "gate/ci")
+            .map(|a| a.content)
+            .collect()
+    }
+
+    #[test]
+    fn passing_command_allows_transition_and_attaches_output() {
+        let (db, config) = setup();
+        let workflows = workflows_with(gate(
+            "echo \"$TASK_GRAPH_TASK_ID:$TASK_GRAPH_TASK_TITLE:$TASK_GRAPH_GATE\"",
+            None,
+        ));
+
+        complete(&db, &config, &workflows).unwrap();
+        assert_eq!(db.get_task("t").unwrap().unwrap().status, "completed");
+        let outputs = outputs(&db);
+        assert_eq!(outputs.len(), 1);
+        assert!(outputs[0].contains("exit 0"), "{}", outputs[0]);
+        assert!(outputs[0].contains("t:Ship it:gate/ci"), "{}", outputs[0]);
+    }
+
+    #[test]
+    fn failing_command_blocks_transition() {
+        let (db, config) = setup();
+        let workflows = workflows_with(gate("echo 3 failed >&2; exit 2", None));
+
+        let err = complete(&db, &config, &workflows).unwrap_err();
+        assert!(err.to_string().contains("gate/ci"), "{}", err);
+        assert!(err.to_string().contains("exit 2"), "{}", err);
+        let suggestion = err
+            .downcast::<task_graph_mcp::error::ToolError>()
+            .unwrap()
+            .suggestion
+            .unwrap();
+        assert!(
+            suggestion.starts_with("Make the 'gate/ci' command pass"),
+            "{}",
+            suggestion
+        );
+        assert_eq!(db.get_task("t").unwrap().unwrap().status, "working");
+        assert_eq!(outputs(&db).len(), 1);
+
+        // Each run replaces the previous run's output
+        let workflows = workflows_with(gate("echo 4 failed >&2; exit 3", None));
+        complete(&db, &config, &workflows).unwrap_err();
+        let outputs = outputs(&db);
+        assert_eq!(outputs.len(), 1);
+        assert!(outputs[0].contains("4 failed"), "{}", outputs[0]);
+        assert!(outputs[0].contains("exit 3"), "{}", outputs[0]);
+    }
+
+    #[test]
+    fn output_over_quota_is_not_attached() {
+        let (db, mut config) = setup();
+        config.attachments = Arc::new(AttachmentsConfig {
+            max_task_bytes: Some(16),
+            ..Default::default()
+        });
+        let workflows = workflows_with(gate("echo a long line of build output", None));
+
+        complete(&db, &config, &workflows).unwrap();
+        assert_eq!(db.get_task("t").unwrap().unwrap().status, "completed");
+        assert!(outputs(&db).is_empty());
+    }
+
+    #[test]
+    fn check_gates_reports_last_run_without_running() {
+        let (db, config) = setup();
+        let workflows = workflows_with(gate("exit 2", None));
+
+        let checked = check_gates(&db, &workflows, json!({"task": "t"})).unwrap();
+        assert_eq!(checked["status"], "fail");
+        assert_eq!(checked["gates"][0]["command"], true);
+        assert_eq!(
+            checked["gates"][0]["detail"],
+            "not run yet; runs on the transition"
+        );
+        assert!(outputs(&db).is_empty());
+
+        complete(&db, &config, &workflows).unwrap_err();
+        let checked = check_gates(&db, &workflows, json!({"task": "t"})).unwrap();
+        assert_eq!(checked["gates"][0]["detail"], "last run: exit 2");
+        assert_eq!(outputs(&db).len(), 1);
+
+        // Output of a since-changed command is not taken as its last run
+        let changed = workflows_with(gate("exit 0", None));
+        let checked = check_gates(&db, &changed, json!({"task": "t"})).unwrap();
+        assert_eq!(
+            checked["gates"][0]["detail"],
+            "not run yet; runs on the transition"
+        );
+    }
+
+    #[test]
+    fn command_is_killed_after_timeout() {
+        let (db, config) = setup();
+        let workflows = workflows_with(gate("sleep 30", Some(1)));
+
+        let started = std::time::Instant::now();
+        let err = complete(&db, &config, &workflows).unwrap_err();
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        assert!(err.to_string().contains("timed out after 1s"), "{}", err);
+    }
+}
+
+mod project_history_grouping_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::format::OutputFormat;
+    use task_graph_mcp::tools::tasks::create;
+    use task_graph_mcp::tools::tracking::project_history;
+
+    const DAY1: i64 = 1_767_225_600_000; // 2026-01-01T00:00:00Z
+    const DAY2: i64 = DAY1 + 86_400_000;
+
+    /// Tasks a and b with a hand-written transition log:
+    /// four `working` stints of 1-4s across two days and agents, then an
+    /// open `completed` with no agent.
+    fn setup() -> Database {
+        let db = setup_db();
+        let config = default_app_config();
+        create(&db, &config, json!({"id": "a", "title": "A"})).unwrap();
+        create(&db, &config, json!({"id": "b", "title": "B"})).unwrap();
+        db.with_conn(|conn| {
+            conn.execute("DELETE FROM task_sequence", [])?;
+            for (task, worker, status, at, end) in [
+                ("a", Some("w1"), "working", DAY1, Some(DAY1 + 1000)),
+                ("a", Some("w1"), "working", DAY1 + 5000, Some(DAY1 + 9000)),
+                ("b", Some("w2"), "working", DAY2, Some(DAY2 + 3000)),
+                ("b", Some("w1"), "working", DAY2 + 5000, Some(DAY2 + 7000)),
+                ("b", None, "completed", DAY2 + 7000, None),
+            ] {
+                conn.execute(
+                    "INSERT INTO task_sequence (task_id, worker_id, status, timestamp, end_timestamp)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    rusqlite::params![task, worker, status, at, end],
+                )?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        db
+    }
+
+    fn grouped(db: &Database, args: serde_json::Value) -> serde_json::Value {
+        project_history(db, OutputFormat::Json, args).unwrap()
+    }
+
+    #[test]
+    fn groups_by_state_with_duration_percentiles() {
+        let db = setup();
+        let result = grouped(&db, json!({"group_by": "state"}));
+        assert!(result.get("transitions").is_none());
+
+        let groups = result["groups"].as_array().unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0]["key"], "completed");
+        assert_eq!(groups[0]["transitions"], 1);
+        assert_eq!(groups[0]["ended"], 0);
+        assert!(groups[0]["p50_ms"].is_null());
+
+        let working = &groups[1];
+        assert_eq!(working["key"], "working");
+        assert_eq!(working["transitions"], 4);
+        assert_eq!(working["tasks"], 2);
+        assert_eq!(working["total_ms"], 10_000);
+        assert_eq!(working["avg_ms"], 2500);
+        assert_eq!(working["p50_ms"], 2000);
+        assert_eq!(working["p90_ms"], 4000);
+        assert_eq!(working["max_ms"], 4000);
+    }
+
+    #[test]
+    fn groups_by_day_and_agent() {
+        let db = setup();
+        let by_day = grouped(&db, json!({"group_by": "day"}));
+        let days: Vec<(&str, i64)> = by_day["groups"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|g| {
+                (
+                    g["key"].as_str().unwrap(),
+                    g["transitions"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(days, vec![("2026-01-01", 2), ("2026-01-02", 3)]);
+
+        let by_agent = grouped(&db, json!({"group_by": "agent"}));
+        let agents: Vec<(Option<&str>, i64)> = by_agent["groups"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|g| (g["key"].as_str(), g["total_ms"].as_i64().unwrap()))
+            .collect();
+        assert_eq!(
+            agents,
+            vec![(None, 0), (Some("w1"), 7000), (Some("w2"), 3000)]
+        );
+    }
+
+    #[test]
+    fn grouping_applies_time_range_and_state_filter() {
+        let db = setup();
+        let result = grouped(
+            &db,
+            json!({"group_by": "agent", "from": DAY2.to_string(), "states": ["working"]}),
+        );
+        let groups = result["groups"].as_array().unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0]["key"], "w1");
+        assert_eq!(groups[0]["transitions"], 1);
+        assert_eq!(groups[1]["key"], "w2");
+
+        let err = project_history(&db, OutputFormat::Json, json!({"group_by": "week"}));
+        assert!(err.unwrap_err().to_string().contains("group_by"));
+    }
+}
+
+mod task_history_timeline_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> anyhow::Result<Value> {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let result = rt.block_on(handler.call_tool(name, args, &ctx))?;
+        Ok(serde_json::from_str(&result.into_string()).unwrap())
+    }
+
+    /// Task b with a status change, a tag change, an incoming and an
+    /// outgoing dependency (one later removed) and an attachment.
+    fn setup(h: &ToolHandler) -> String {
+        for (id, title) in [("a", "A"), ("b", "B"), ("c", "C")] {
+            call(
+                h,
+                "create",
+                json!({"worker_id": "w1", "id": id, "title": title}),
+            )
+            .unwrap();
+        }
+        call(h, "link", json!({"from": "a", "to": "b", "type": "blocks"})).unwrap();
+        call(
+            h,
+            "link",
+            json!({"from": "b", "to": "c", "type": "follows"}),
+        )
+        .unwrap();
+        call(
+            h,
+            "update",
+            json!({"worker_id": "w1", "task": "b", "tags": ["api"], "status": "cancelled"}),
+        )
+        .unwrap();
+        call(
+            h,
+            "attach",
+            json!({"task": "b", "type": "note", "name": "why", "content": "dropped"}),
+        )
+        .unwrap();
+        call(
+            h,
+            "unlink",
+            json!({"from": "b", "to": "c", "type": "follows"}),
+        )
+        .unwrap();
+        "b".to_string()
+    }
+
+    fn kinds(timeline: &Value) -> Vec<String> {
+        timeline
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| {
+                format!(
+                    "{}:{}",
+                    e["kind"].as_str().unwrap(),
+                    e["action"]
+                        .as_str()
+                        .or(e["status"].as_str())
+                        .or(e["field"].as_str())
+                        .unwrap()
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn timeline_interleaves_requested_event_classes() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = handler(setup_db(), dir.path());
+        let task = setup(&h);
+
+        let plain = call(&h, "task_history", json!({"task": task})).unwrap();
+        assert!(plain.get("timeline").is_none());
+
+        let history = call(
+            &h,
+            "task_history",
+            json!({"task": task, "include": ["attachments", "dependencies", "tags"]}),
+        )
+        .unwrap();
+        let timeline = &history["timeline"];
+        let mut seen = kinds(timeline);
+        let stamps: Vec<i64> = timeline
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["timestamp"].as_i64().unwrap())
+            .collect();
+        assert!(stamps.windows(2).all(|w| w[0] <= w[1]));
+        seen.sort();
+        assert_eq!(
+            seen,
+            vec![
+                "attachment:added",
+                "dependency:added",
+                "dependency:added",
+                "dependency:removed",
+                "status:cancelled",
+                "status:pending",
+                "tags:tags",
+            ]
+        );
+
+        let outgoing = timeline
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["action"] == "removed")
+            .unwrap();
+        assert_eq!(outgoing["from"], "b");
+        assert_eq!(outgoing["to"], "c");
+        assert_eq!(outgoing["dep_type"], "follows");
+    }
+
+    #[test]
+    fn timeline_includes_only_requested_classes() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = handler(setup_db(), dir.path());
+        let task = setup(&h);
+
+        let history = call(
+            &h,
+            "task_history",
+            json!({"task": task, "include": ["attachments"], "states": ["cancelled"]}),
+        )
+        .unwrap();
+        let mut seen = kinds(&history["timeline"]);
+        seen.sort();
+        assert_eq!(seen, vec!["attachment:added", "status:cancelled"]);
+
+        let markdown = call(
+            &h,
+            "task_history",
+            json!({"task": task, "include": ["tags"], "format": "markdown"}),
+        )
+        .unwrap()
+        .to_string();
+        assert!(markdown.contains("## Timeline"));
+        assert!(markdown.contains("status → cancelled"));
+
+        let err = call(
+            &h,
+            "task_history",
+            json!({"task": task, "include": ["notes"]}),
+        );
+        assert!(err.unwrap_err().to_string().contains("notes"));
+    }
+}
+
+mod thought_journal_tests {
+    use super::*;
+    use serde_json::json;
+    use task_graph_mcp::db::export::ExportOptions;
+    use task_graph_mcp::db::import::ImportOptions;
+    use task_graph_mcp::db::tasks::THOUGHT_LOG_LIMIT;
+    use task_graph_mcp::export::Snapshot;
+    use task_graph_mcp::tools::tracking::get_thoughts;
+
+    fn worker(db: &Database, id: &str) -> String {
+        db.register_worker(
+            Some(id.to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap()
+        .id
+    }
+
+    fn think(db: &Database, agent: &str, thought: &str) {
+        db.set_thought(agent, Some(thought.to_string()), None)
+            .unwrap();
+    }
+
+    #[test]
+    fn thinking_appends_to_the_claimed_tasks_journal() {
+        let db = setup_db();
+        let states = default_states_config();
+        let agent = worker(&db, "thinker");
+        let task = db
+            .create_task_simple("Journaled", &states, &default_ids_config())
+            .unwrap();
+
+        think(&db, &agent, "before claiming");
+        db.claim_task(&task.id, &agent, &states).unwrap();
+        think(&db, &agent, "reading the code");
+        think(&db, &agent, "found the bug");
+
+        let result = get_thoughts(&db, json!({"task": task.id})).unwrap();
+        assert_eq!(result["count"], 2);
+        assert_eq!(result["thoughts"][0]["thought"], "reading the code");
+        assert_eq!(result["thoughts"][1]["thought"], "found the bug");
+        assert_eq!(result["thoughts"][1]["worker_id"], "thinker");
+
+        let latest = get_thoughts(&db, json!({"task": task.id, "limit": 1})).unwrap();
+        assert_eq!(latest["thoughts"][0]["thought"], "found the bug");
+        // The live thought is still overwritten
+        assert_eq!(
+            db.get_task(&task.id)
+                .unwrap()
+                .unwrap()
+                .current_thought
+                .as_deref(),
+            Some("found the bug")
+        );
+
+        assert!(get_thoughts(&db, json!({"task": "missing"})).is_err());
+        assert!(get_thoughts(&db, json!({"task": task.id, "limit": 0})).is_err());
+    }
+
+    #[test]
+    fn journal_outlives_the_worker_thought_stream() {
+        let db = setup_db();
+        let states = default_states_config();
+        let agent = worker(&db, "chatty");
+        let task = db
+            .create_task_simple("Long", &states, &default_ids_config())
+            .unwrap();
+        db.claim_task(&task.id, &agent, &states).unwrap();
+
+        think(&db, &agent, "first step");
+        for i in 0..THOUGHT_LOG_LIMIT {
+            think(&db, &agent, &format!("step {}", i));
+        }
+
+        let stream = db.get_worker_thoughts(&agent, 1000).unwrap();
+        assert!(stream.iter().all(|t| t.thought != "first step"));
+        let journal = db.get_task_thoughts(&task.id, 1000).unwrap();
+        assert_eq!(journal.len() as i64, THOUGHT_LOG_LIMIT + 1);
+        assert_eq!(journal[0].thought, "first step");
+    }
+
+    #[test]
+    fn journal_is_exported_and_imported() {
+        let source = setup_db();
+        let states = default_states_config();
+        let agent = worker(&source, "exporter");
+        let task = source
+            .create_task_simple("Exported", &states, &default_ids_config())
+            .unwrap();
+        source.claim_task(&task.id, &agent, &states).unwrap();
+        think(&source, &agent, "worth keeping");
+
+        let tables = source.export_tables(&ExportOptions::default()).unwrap();
+        let thoughts = tables.task_thoughts.as_ref().unwrap();
+        assert_eq!(thoughts.len(), 1);
+        assert_eq!(thoughts[0].task_id, task.id);
+
+        let snapshot = Snapshot::from_tables(tables).unwrap();
+        let target = setup_db();
+        target
+            .import_snapshot(&snapshot, &ImportOptions::fresh())
+            .unwrap();
+        let imported = target.get_task_thoughts(&task.id, 10).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].thought, "worth keeping");
+        assert_eq!(imported[0].worker_id, "exporter");
+    }
+}
+
+// ============================================================================
+// Task ID Resolution Scope Tests
+// ============================================================================
+
+mod task_id_resolution_scope_tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use task_graph_mcp::logging::Logger;
+    use task_graph_mcp::tools::{ToolContext, ToolHandler};
+
+    fn call(handler: &ToolHandler, name: &str, args: Value) -> anyhow::Result<Value> {
+        let ctx = ToolContext::new(Logger::new());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let result = rt.block_on(handler.call_tool(name, args, &ctx))?;
+        Ok(serde_json::from_str(&result.into_string()).unwrap())
+    }
+
+    fn date_ms(date: &str) -> i64 {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis()
+    }
+
+    #[test]
+    fn timestamp_bounds_are_not_resolved_as_task_prefixes() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = handler(Database::open_in_memory().unwrap(), dir.path());
+        call(
+            &h,
+            "create",
+            json!({"worker_id": "w1", "id": "2026-01-01-kickoff", "title": "Kickoff"}),
+        )
+        .unwrap();
+
+        let history = call(
+            &h,
+            "project_history",
+            json!({"from": "2026-01-01", "format": "json"}),
+        )
+        .unwrap();
+        assert!(history.get("resolved_prefixes").is_none());
+        assert_eq!(history["time_range"]["from_ms"], date_ms("2026-01-01"));
+
+        // The same string is still a prefix where a task ID is expected
+        let task = call(&h, "get", json!({"task": "2026-01-01", "format": "json"})).unwrap();
+        assert_eq!(
+            task["resolved_prefixes"]["2026-01-01"],
+            "2026-01-01-kickoff"
+        );
+    }
+
+    #[test]
+    fn timestamp_bounds_are_not_resolved_as_task_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        let h = handler(Database::open_in_memory().unwrap(), dir.path());
+        call(
+            &h,
+            "create",
+            json!({"worker_id": "w1", "id": "2026-01-02", "title": "Launch"}),
+        )
+        .unwrap();
+        call(
+            &h,
+            "rename",
+            json!({"worker_id": "w1", "task": "2026-01-02", "new_id": "launch"}),
+        )
+        .unwrap();
+
+        let history = call(
+            &h,
+            "project_history",
+            json!({"to": "2026-01-02", "format": "json"}),
+        )
+        .unwrap();
+        assert!(history.get("resolved_aliases").is_none());
+        assert_eq!(history["time_range"]["to_ms"], date_ms("2026-01-02"));
+
+        let task = call(&h, "get", json!({"task": "2026-01-02", "format": "json"})).unwrap();
+        assert_eq!(task["resolved_aliases"]["2026-01-02"], "launch");
+    }
+
+    #[test]
+    fn feedback_task_id_resolves_aliases_and_prefixes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = default_app_config();
+        config.feedback = Arc::new(FeedbackConfig { enabled: true });
+        let h = handler_with_config(Database::open_in_memory().unwrap(), config, dir.path());
+        call(
+            &h,
+            "create",
+            json!({"worker_id": "w1", "id": "login-form", "title": "Login form"}),
+        )
+        .unwrap();
+        call(
+            &h,
+            "rename",
+            json!({"worker_id": "w1", "task": "login-form", "new_id": "signin-form"}),
+        )
+        .unwrap();
+
+        let result = call(
+            &h,
+            "give_feedback",
+            json!({"message": "Unclear spec", "task_id": "login-form"}),
+        )
+        .unwrap();
+        assert_eq!(result["resolved_aliases"]["login-form"], "signin-form");
+        let result = call(
+            &h,
+            "give_feedback",
+            json!({"message": "Still unclear", "task_id": "signin"}),
+        )
+        .unwrap();
+        assert_eq!(result["resolved_prefixes"]["signin"], "signin-form");
+
+        let feedback = std::fs::read_to_string(dir.path().join("feedback.md")).unwrap();
+        assert_eq!(feedback.matches("- **Task:** signin-form").count(), 2);
+    }
+}
+
+mod deadline_reminder_tests {
+    use super::*;
+    use serde_json::{Map, Value, json};
+    use task_graph_mcp::config::DeadlineRemindersConfig;
+    use task_graph_mcp::deadline_reminders::webhook_payload;
+
+    const HOUR: i64 = 3_600_000;
+    const NOW: i64 = 1_800_000_000_000;
+
+    fn worker(db: &Database, id: &str) -> String {
+        db.register_worker(
+            Some(id.to_string()),
+            vec![],
+            false,
+            &default_ids_config(),
+            None,
+            vec![],
+        )
+        .unwrap()
+        .id
+    }
+
+    /// A task due at `due_at`, claimed by `agent` when given.
+    fn due_task(db: &Database, due_at: i64, agent: Option<&str>) -> String {
+        let states = default_states_config();
+        let task = db
+            .create_task_simple("Deadline", &states, &default_ids_config())
+            .unwrap();
+        let mut fields = Map::new();
+        fields.insert("due_at".to_string(), json!(due_at));
+        db.set_task_fields(&task.id, &fields).unwrap();
+        if let Some(agent) = agent {
+            db.claim_task(&task.id, agent, &states).unwrap();
+        }
+        task.id
+    }
+
+    fn tags(db: &Database, task: &str) -> Vec<String> {
+        db.get_task(task).unwrap().unwrap().tags
+    }
+
+    #[test]
+    fn reminders_fire_once_per_offset() {
+        let db = setup_db();
+        let (config, states) = (DeadlineRemindersConfig::default(), default_states_config());
+        let agent = worker(&db, "w");
+        let task = due_task(&db, NOW + 30 * HOUR, Some(&agent));
+
+        // More than a day out: nothing yet
+        let sweep = db.sweep_deadlines(&config, &states, NOW).unwrap();
+        assert!(sweep.reminders.is_empty());
+
+        let day_before = NOW + 6 * HOUR;
+        let sweep = db.sweep_deadlines(&config, &states, day_before).unwrap();
+        assert_eq!(sweep.reminders.len(), 1);
+        let reminder = &sweep.reminders[0];
+        assert_eq!(reminder.task_id, task);
+        assert_eq!(reminder.worker_id, "w");
+        assert_eq!(reminder.offset_ms, 24 * HOUR);
+        assert!(!reminder.overdue);
+        assert_eq!(webhook_payload(reminder)["event"], "deadline_reminder");
+
+        // Already sent, including across sweeps
+        let sweep = db
+            .sweep_deadlines(&config, &states, day_before + 1)
+            .unwrap();
+        assert!(sweep.reminders.is_empty());
+
+        let deadlines = db.get_claimed_deadlines(&states).unwrap();
+        assert_eq!(deadlines.len(), 1);
+        assert_eq!(deadlines[0].reminded, vec![24 * HOUR]);
+    }
+
+    #[test]
+    fn only_the_latest_passed_offset_fires() {
+        let db = setup_db();
+        let (config, states) = (DeadlineRemindersConfig::default(), default_states_config());
+        let agent = worker(&db, "w");
+        due_task(&db, NOW + HOUR / 2, Some(&agent));
+
+        // The day and hour offsets have both passed; only the hour one fires
+        let sweep = db.sweep_deadlines(&config, &states, NOW).unwrap();
+        assert_eq!(sweep.reminders.len(), 1);
+        assert_eq!(sweep.reminders[0].offset_ms, HOUR);
+
+        let deadlines = db.get_claimed_deadlines(&states).unwrap();
+        assert_eq!(deadlines[0].reminded, vec![24 * HOUR, HOUR]);
+    }
+
+    #[test]
+    fn moving_the_deadline_rearms_reminders() {
+        let db = setup_db();
+        let (config, states) = (DeadlineRemindersConfig::default(), default_states_config());
+        let agent = worker(&db, "w");
+        let task = due_task(&db, NOW + HOUR / 2, Some(&agent));
+        assert_eq!(
+            db.sweep_deadlines(&config, &states, NOW)
+                .unwrap()
+                .reminders
+                .len(),
+            1
+        );
+
+        let mut fields = Map::new();
+        fields.insert("due_at".to_string(), json!(NOW + HOUR / 4));
+        db.set_task_fields(&task, &fields).unwrap();
+        let sweep = db.sweep_deadlines(&config, &states, NOW).unwrap();
+        assert_eq!(sweep.reminders.len(), 1);
+        assert_eq!(sweep.reminders[0].due_at, NOW + HOUR / 4);
+    }
+
+    #[test]
+    fn unclaimed_tasks_get_no_reminders() {
+        let db = setup_db();
+        let (config, states) = (DeadlineRemindersConfig::default(), default_states_config());
+        due_task(&db, NOW, None);
+
+        let sweep = db.sweep_deadlines(&config, &states, NOW).unwrap();
+        assert!(sweep.reminders.is_empty());
+        assert!(db.get_claimed_deadlines(&states).unwrap().is_empty());
+    }
+
+    #[test]
+    fn overdue_tag_follows_the_deadline() {
+        let db = setup_db();
+        let (config, states) = (DeadlineRemindersConfig::default(), default_states_config());
+        let agent = worker(&db, "w");
+        let late = due_task(&db, NOW - HOUR, Some(&agent));
+        let unclaimed = due_task(&db, NOW - HOUR, None);
+        let on_time = due_task(&db, NOW + HOUR, None);
+        let cursor = db.change_feed_head().unwrap();
+
+        let sweep = db.sweep_deadlines(&config, &states, NOW).unwrap();
+        assert_eq!(sweep.retagged.len(), 2);
+        assert!(sweep.reminders[0].overdue);
+        assert_eq!(tags(&db, &late), vec!["overdue"]);
+        assert_eq!(tags(&db, &unclaimed), vec!["overdue"]);
+        assert!(tags(&db, &on_time).is_empty());
+
+        // The retag is in the task's history and the change feed
+        let changes = db.get_task_changes(&late).unwrap();
+        let change = changes.last().unwrap();
+        assert_eq!(change.field, "tags");
+        assert_eq!(change.old_value, json!([]));
+        assert_eq!(change.new_value, json!(["overdue"]));
+        assert_eq!(change.timestamp, NOW);
+        let feed = db.get_changes_since(cursor, 10).unwrap();
+        assert!(
+            feed.iter()
+                .any(|e| e.action == "updated" && e.task_id.as_deref() == Some(&late))
+        );
+
+        // Finishing the task or dropping its deadline takes the tag off
+        db.complete_task(&late, &agent, &states).unwrap();
+        db.set_task_fields(
+            &unclaimed,
+            &Map::from_iter([("due_at".to_string(), Value::Null)]),
+        )
+        .unwrap();
+        let sweep = db.sweep_deadlines(&config, &states, NOW).unwrap();
+        assert_eq!(sweep.retagged.len(), 2);
+        assert!(tags(&db, &late).is_empty());
+        assert!(tags(&db, &unclaimed).is_empty());
+        let change = db.get_task_changes(&unclaimed).unwrap().pop().unwrap();
+        assert_eq!(change.field, "tags");
+        assert_eq!(change.new_value, json!([]));
+    }
+
+    #[test]
+    fn overdue_tagging_can_be_disabled() {
+        let db = setup_db();
+        let config = DeadlineRemindersConfig {
+            overdue_tag: None,
+            ..DeadlineRemindersConfig::default()
+        };
+        let task = due_task(&db, NOW - HOUR, None);
+
+        let sweep = db
+            .sweep_deadlines(&config, &default_states_config(), NOW)
+            .unwrap();
+        assert!(sweep.retagged.is_empty());
+        assert!(tags(&db, &task).is_empty());
+    }
+
+    #[test]
+    fn config_rejects_bad_webhooks_and_empty_tags() {
+        let config = DeadlineRemindersConfig {
+            webhooks: vec!["ftp://example.com".to_string()],
+            ..DeadlineRemindersConfig::default()
+        };
+        assert!(config.validate().is_err());
+        let config = DeadlineRemindersConfig {
+            overdue_tag: Some(" ".to_string()),
+            ..DeadlineRemindersConfig::default()
+        };
+        assert!(config.validate().is_err());
+        assert_eq!(
+            DeadlineRemindersConfig::default().offsets_ms(),
+            vec![24 * HOUR, HOUR, 0]
+        );
+    }
 }
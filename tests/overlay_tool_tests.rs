@@ -9,8 +9,10 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use task_graph_mcp::config::workflows::{StateWorkflow, TransitionPrompts, WorkflowsConfig};
 use task_graph_mcp::config::{
-    AppConfig, AttachmentsConfig, AutoAdvanceConfig, DependenciesConfig, FeedbackConfig, IdsConfig,
-    PhasesConfig, StatesConfig, TagsConfig,
+    AppConfig, AttachmentsConfig, AutoAdvanceConfig, ClaimingConfig, CustomFieldsConfig,
+    DependenciesConfig, FeedbackConfig, GroupsConfig, IdsConfig, LimitsConfig, PhasesConfig,
+    PrioritiesConfig, SkillsConfig, SourceScanConfig, StaleClaimsConfig, StatesConfig, TagsConfig,
+    ToolsConfig, VisibilityConfig,
 };
 use task_graph_mcp::db::Database;
 use task_graph_mcp::tools::agents;
@@ -90,8 +92,18 @@ fn app_config_with_overlays() -> AppConfig {
         Arc::new(AttachmentsConfig::default()),
         Arc::new(TagsConfig::default()),
         Arc::new(IdsConfig::default()),
+        Arc::new(CustomFieldsConfig::default()),
+        Arc::new(PrioritiesConfig::default()),
         Arc::new(workflows),
         Arc::new(FeedbackConfig::default()),
+        Arc::new(GroupsConfig::default()),
+        Arc::new(StaleClaimsConfig::default()),
+        Arc::new(ClaimingConfig::default()),
+        Arc::new(VisibilityConfig::default()),
+        Arc::new(SourceScanConfig::default()),
+        Arc::new(ToolsConfig::default()),
+        Arc::new(SkillsConfig::default()),
+        Arc::new(LimitsConfig::default()),
     )
 }
 
@@ -3,7 +3,7 @@
 //! These tests verify the read-only SQL query functionality.
 
 use serde_json::{Value, json};
-use task_graph_mcp::config::{IdsConfig, StatesConfig};
+use task_graph_mcp::config::{IdsConfig, StatesConfig, VisibilityConfig};
 use task_graph_mcp::db::Database;
 use task_graph_mcp::format::{OutputFormat, ToolResult};
 use task_graph_mcp::tools::query;
@@ -78,6 +78,7 @@ fn query_select_all_tasks() {
     let result = unwrap_json(
         query::query(
             &db,
+            &VisibilityConfig::default(),
             OutputFormat::Json,
             json!({
                 "sql": "SELECT id, title FROM tasks ORDER BY created_at"
@@ -141,6 +142,7 @@ fn query_with_parameters() {
     let result = unwrap_json(
         query::query(
             &db,
+            &VisibilityConfig::default(),
             OutputFormat::Json,
             json!({
                 "sql": "SELECT id, title FROM tasks WHERE id = ?",
@@ -184,6 +186,7 @@ fn query_enforces_row_limit() {
     let result = unwrap_json(
         query::query(
             &db,
+            &VisibilityConfig::default(),
             OutputFormat::Json,
             json!({
                 "sql": "SELECT id, title FROM tasks",
@@ -224,6 +227,7 @@ fn query_csv_format() {
     let csv_data = unwrap_raw(
         query::query(
             &db,
+            &VisibilityConfig::default(),
             OutputFormat::Json,
             json!({
                 "sql": "SELECT title, status FROM tasks",
@@ -263,6 +267,7 @@ fn query_markdown_format() {
     let md_data = unwrap_raw(
         query::query(
             &db,
+            &VisibilityConfig::default(),
             OutputFormat::Json,
             json!({
                 "sql": "SELECT title FROM tasks",
@@ -283,6 +288,7 @@ fn query_rejects_insert() {
 
     let result = query::query(
         &db,
+        &VisibilityConfig::default(),
         OutputFormat::Json,
         json!({
             "sql": "INSERT INTO tasks (id, title) VALUES ('x', 'bad')"
@@ -300,6 +306,7 @@ fn query_rejects_update() {
 
     let result = query::query(
         &db,
+        &VisibilityConfig::default(),
         OutputFormat::Json,
         json!({
             "sql": "UPDATE tasks SET title = 'hacked'"
@@ -315,6 +322,7 @@ fn query_rejects_delete() {
 
     let result = query::query(
         &db,
+        &VisibilityConfig::default(),
         OutputFormat::Json,
         json!({
             "sql": "DELETE FROM tasks"
@@ -330,6 +338,7 @@ fn query_rejects_drop() {
 
     let result = query::query(
         &db,
+        &VisibilityConfig::default(),
         OutputFormat::Json,
         json!({
             "sql": "DROP TABLE tasks"
@@ -345,6 +354,7 @@ fn query_rejects_multiple_statements() {
 
     let result = query::query(
         &db,
+        &VisibilityConfig::default(),
         OutputFormat::Json,
         json!({
             "sql": "SELECT 1; DROP TABLE tasks;"
@@ -363,6 +373,7 @@ fn query_allows_column_names_with_keywords() {
     // This should work - "deleted_at" contains "DELETE" but it's a column name
     let result = query::query(
         &db,
+        &VisibilityConfig::default(),
         OutputFormat::Json,
         json!({
             "sql": "SELECT id, status FROM tasks WHERE status = 'pending'"
@@ -397,6 +408,7 @@ fn query_with_cte() {
     let result = unwrap_json(
         query::query(
             &db,
+            &VisibilityConfig::default(),
             OutputFormat::Json,
             json!({
                 "sql": "WITH task_list AS (SELECT id, title FROM tasks) SELECT * FROM task_list"
@@ -416,6 +428,7 @@ fn query_max_limit_enforced() {
     let result = unwrap_json(
         query::query(
             &db,
+            &VisibilityConfig::default(),
             OutputFormat::Json,
             json!({
                 "sql": "SELECT 1",
@@ -437,6 +450,7 @@ fn query_default_limit() {
     let result = unwrap_json(
         query::query(
             &db,
+            &VisibilityConfig::default(),
             OutputFormat::Json,
             json!({
                 "sql": "SELECT 1"
@@ -45,9 +45,8 @@ phases:
 /// Workflow YAML with custom prompts to verify loading.
 fn swarm_like_workflow_yaml() -> &'static str {
     r##"
-topology:
-  name: swarm
-  description: Parallel generalists with fine-grained tasks
+name: swarm
+description: Parallel generalists with fine-grained tasks
 
 settings:
   initial_state: pending